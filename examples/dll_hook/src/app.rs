@@ -1,7 +1,8 @@
 use std::path::Path;
 
 use anyhow::Result;
-use simple_log::info;
+use rayon::prelude::*;
+use tracing::info;
 
 use crate::{globals, ue};
 
@@ -18,7 +19,7 @@ unsafe fn testing() {
         info!("a");
         let objects = globals().guobject_array_unchecked().objects();
         let refs = objects
-            .iter()
+            .par_iter()
             .filter(|obj| {
                 if let Some(obj) = obj {
                     obj.name_private
@@ -43,9 +44,7 @@ unsafe fn testing() {
                     .name_private
                     .to_string();
 
-                if class == "Function" {
-                    // TODO safe casting
-                    let s = &*((*obj as *const _) as *const ue::UStruct);
+                if let Some(s) = obj.cast::<ue::UFunction>().map(|f| &f.ustruct) {
                     if !s.script.is_empty() {
                         info!("{:x?}", s.script);
                         info!("{i:10} {} {}", class, name.to_string());
@@ -7,6 +7,7 @@ use egui_winit::winit::platform::windows::EventLoopBuilderExtWindows;
 #[cfg(unix)]
 use egui_winit::winit::platform::x11::EventLoopBuilderExtX11;
 use indexmap::IndexMap;
+use patternsleuth::resolvers::progress::ResolverStatus;
 
 use super::*;
 
@@ -97,20 +98,14 @@ struct MyApp {
     objects: Arc<RwLock<ObjectNameCache>>,
     filtered: IndexMap<ObjectIndex, ObjectCache>,
     kismet_log: String,
+    console_input: String,
+    console_log: String,
+    event_filter: patternsleuth::event_tap::EventFilter,
+    event_capacity: usize,
+    widgets: Vec<patternsleuth::ue::WidgetNode>,
     ctx: Arc<OnceLock<egui::Context>>,
 }
 
-macro_rules! move_clone {
-    ( ( $($($arg:ident)+$(,)?)* ), $expr:expr) => {
-        {
-            $( $(
-                    let $arg = $arg.clone();
-            )*)*
-            $expr
-        }
-    };
-}
-
 impl MyApp {
     fn new() -> Self {
         let (tx, events) = std::sync::mpsc::channel();
@@ -163,6 +158,11 @@ impl MyApp {
                 },
             )
         );
+        scan_progress().on_resolved(move_clone!((ctx), move |_: &ResolverStatus| {
+            if let Some(ctx) = ctx.get() {
+                ctx.request_repaint();
+            }
+        }));
         let txc = tx.clone();
         let kismet_print = Arc::new(move |message: &str| {
             txc.send(Event::KismetPrintMessage {
@@ -185,6 +185,11 @@ impl MyApp {
             objects: cache,
             filtered: Default::default(),
             kismet_log: "".into(),
+            console_input: "".into(),
+            console_log: "".into(),
+            event_filter: Default::default(),
+            event_capacity: 1000,
+            widgets: vec![],
             ctx,
         }
     }
@@ -317,6 +322,116 @@ impl eframe::App for MyApp {
                     );
                 });
 
+            egui::Window::new("hooks")
+                .default_height(300.)
+                .show(ctx, |ui| {
+                    egui::Grid::new("hooks").striped(true).show(ui, |ui| {
+                        for hook in hooks::registry().hooks() {
+                            let mut enabled = hook.is_enabled();
+                            if ui.checkbox(&mut enabled, hook.name()).changed() {
+                                if let Err(err) = hook.set_enabled(enabled) {
+                                    self.console_log.push_str(&format!("{err:#}\n"));
+                                }
+                            }
+                            ui.label(hook.calls().to_string());
+                            ui.label(hook.last_args().unwrap_or_default());
+                            ui.end_row();
+                        }
+                    });
+
+                    ui.separator();
+                    let res = ui.text_edit_singleline(&mut self.console_input);
+                    if res.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        let command = std::mem::take(&mut self.console_input);
+                        self.console_log.push_str(&format!("> {command}\n"));
+                        match hooks::registry().execute(&command) {
+                            Ok(output) => self.console_log.push_str(&format!("{output}\n")),
+                            Err(err) => self.console_log.push_str(&format!("{err:#}\n")),
+                        }
+                        res.request_focus();
+                    }
+                    egui::ScrollArea::vertical()
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            ui.monospace(&self.console_log);
+                        });
+                });
+
+            egui::Window::new("resolvers")
+                .default_height(300.)
+                .show(ctx, scan_progress_ui);
+
+            egui::Window::new("process events")
+                .default_height(400.)
+                .show(ctx, |ui| {
+                    let tap = hooks::event_tap();
+                    ui.horizontal(|ui| {
+                        ui.label("Class: ");
+                        ui.text_edit_singleline(&mut self.event_filter.class);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Function: ");
+                        ui.text_edit_singleline(&mut self.event_filter.function);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.event_capacity).prefix("capacity: "));
+                        if tap.is_capturing() {
+                            if ui.button("Stop").clicked() {
+                                tap.stop_capture();
+                            }
+                        } else if ui.button("Capture").clicked() {
+                            tap.start_capture(self.event_filter.clone(), self.event_capacity);
+                        }
+                        if ui.button("Dump").clicked() {
+                            let result = std::env::current_exe().and_then(|exe| {
+                                let path = exe.with_file_name("process_events.txt");
+                                tap.dump(std::fs::File::create(&path)?)?;
+                                Ok(path)
+                            });
+                            match result {
+                                Ok(path) => self
+                                    .console_log
+                                    .push_str(&format!("dumped events to {}\n", path.display())),
+                                Err(err) => self
+                                    .console_log
+                                    .push_str(&format!("failed to dump events: {err}\n")),
+                            }
+                        }
+                    });
+
+                    let events = tap.captured();
+                    let text_style = egui::TextStyle::Monospace;
+                    let row_height = ui.text_style_height(&text_style);
+                    egui::ScrollArea::vertical()
+                        .stick_to_bottom(true)
+                        .show_rows(ui, row_height, events.len(), |ui, row_range| {
+                            for event in &events[row_range] {
+                                ui.monospace(format!(
+                                    "{:10} {} {}",
+                                    event.index, event.class, event.function_name
+                                ));
+                            }
+                        });
+                });
+
+            egui::Window::new("widgets")
+                .default_height(400.)
+                .show(ctx, |ui| {
+                    if ui.button("Refresh").clicked() {
+                        match dump_widgets() {
+                            Ok(widgets) => self.widgets = widgets,
+                            Err(err) => self
+                                .console_log
+                                .push_str(&format!("failed to dump widgets: {err}\n")),
+                        }
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for widget in &self.widgets {
+                            widget_tree(ui, widget);
+                        }
+                    });
+                });
+
             let _log_window = |name: &str, mut log: &str| {
                 egui::Window::new(name)
                     .default_height(500.)
@@ -338,3 +453,94 @@ impl eframe::App for MyApp {
         });
     }
 }
+
+/// Resolvers of the startup scan as they finish, failed ones with their error on hover
+fn scan_progress_ui(ui: &mut egui::Ui) {
+    let progress = scan_progress();
+    let state = progress.snapshot();
+    let failed = state.failed().count();
+    if state.finished {
+        ui.label(format!(
+            "{} resolvers finished, {failed} failed",
+            state.resolvers.len()
+        ));
+    } else {
+        ui.label(format!(
+            "scanning stage {} ({} patterns) for {:.1?}",
+            state.stage,
+            state.stage_patterns,
+            progress.elapsed()
+        ));
+    }
+    egui::ScrollArea::vertical()
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            egui::Grid::new("resolvers").striped(true).show(ui, |ui| {
+                for status in &state.resolvers {
+                    match &status.error {
+                        None => ui.colored_label(egui::Color32::GREEN, status.name),
+                        Some(err) => ui
+                            .colored_label(egui::Color32::RED, status.name)
+                            .on_hover_text(err),
+                    };
+                    ui.label(format!("{:.2?}", status.elapsed));
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// Read the trees of every top level user widget through reflection
+fn dump_widgets() -> Result<Vec<patternsleuth::ue::WidgetNode>, patternsleuth::MemoryAccessError> {
+    use patternsleuth::ue::{CurrentProcess, ObjectArray, ObjectLayout, Objects, Widgets};
+
+    let mem = unsafe { CurrentProcess::new() };
+    let array = ObjectArray::new(
+        &mem,
+        crate::member(&globals().resolution.guobject_array).0,
+        0x18,
+    );
+    let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+        let name = ue::FName {
+            comparison_index: ue::FNameEntryId {
+                value: name.comparison_index,
+            },
+            number: name.number,
+        };
+        Some(name.to_string())
+    });
+    let widgets = Widgets::new(&objects);
+    widgets
+        .root_user_widgets()
+        .into_iter()
+        .map(|widget| widgets.tree(widget))
+        .collect()
+}
+
+fn widget_tree(ui: &mut egui::Ui, node: &patternsleuth::ue::WidgetNode) {
+    let mut label = format!(
+        "{} ({})",
+        node.name.as_deref().unwrap_or("?"),
+        node.class.as_deref().unwrap_or("?")
+    );
+    if let Some(visibility) = node.visibility {
+        label.push_str(&format!(" {visibility:?}"));
+    }
+    if let Some(layout) = node.layout {
+        label.push_str(&format!(" at {:?} size {:?}", layout.position, layout.size));
+    }
+    let path = node.path.as_deref().unwrap_or("?");
+    if node.children.is_empty() {
+        ui.label(label).on_hover_text(path);
+    } else {
+        egui::CollapsingHeader::new(label)
+            .id_source(node.widget)
+            .show(ui, |ui| {
+                for child in &node.children {
+                    widget_tree(ui, child);
+                }
+            })
+            .header_response
+            .on_hover_text(path);
+    }
+}
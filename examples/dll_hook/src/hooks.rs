@@ -1,20 +1,32 @@
 use std::{
-    ffi::c_void,
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr},
     sync::{Arc, LazyLock, Mutex, Weak},
 };
 
 use anyhow::Result;
+use patternsleuth::{
+    event_tap::EventTap,
+    hooks::{HookControl, HookRegistry},
+};
 
-use crate::{assert_main_thread, globals, object_cache, ue};
+use crate::{
+    assert_main_thread, globals, move_clone,
+    native::{self, Arg, Param},
+    object_cache, ue,
+};
 
 retour::static_detour! {
     static HookUGameEngineTick: unsafe extern "system" fn(*mut c_void, f32, u8);
     static HookFEngineLoopInit: unsafe extern "system" fn(*mut c_void);
     static HookAllocateUObject: unsafe extern "system" fn(*mut c_void, *const ue::UObjectBase, bool);
     static HookFreeUObject: unsafe extern "system" fn(*mut ue::UObjectBase, *const c_void); // inlined into UObject dtor so args are messed up
-    static HookKismetPrintString: unsafe extern "system" fn(*mut ue::UObjectBase, *mut ue::kismet::FFrame, *mut c_void);
     static HookKismetExecutionMessage: unsafe extern "system" fn(*const u16, u8, ue::FName);
     static HookUFunctionBind: unsafe extern "system" fn(*mut ue::UFunction);
+    static HookProcessEvent: unsafe extern "system" fn(*mut ue::UObject, *mut ue::UFunction, *mut c_void);
+    // (Expr, File, Line, ...) followed by version dependent arguments which are passed through
+    static HookAssertFailed: unsafe extern "system" fn(*const c_char, *const c_char, i32, usize, usize, usize);
+    static HookEnsureFailed: unsafe extern "system" fn(*const c_char, *const c_char, i32, usize, usize, usize);
 }
 
 macro_rules! event {
@@ -50,118 +62,159 @@ event!(delete_uobject(/*uobject_array: &UObjectLock,*/ object: &ue::UObjectBase)
 event!(kismet_execution_message(message: &widestring::U16CStr, verbosity: u8, warning_id: ue::FName));
 event!(kismet_print_message(message: &str));
 
+/// Registry of all hooks installed by [`initialize`]
+pub fn registry() -> &'static HookRegistry {
+    static REGISTRY: LazyLock<HookRegistry> = LazyLock::new(HookRegistry::new);
+    &REGISTRY
+}
+
+/// ProcessEvent calls, fed by the ProcessEvent hook when it could be resolved
+pub fn event_tap() -> &'static EventTap {
+    static TAP: LazyLock<EventTap> = LazyLock::new(EventTap::new);
+    &TAP
+}
+
+/// Names of classes and functions passed through ProcessEvent keyed by address. Entries are
+/// never invalidated so a name may be stale if its object is freed and the memory reused.
+fn cached_name(address: usize, name: impl FnOnce() -> String) -> Arc<str> {
+    static NAMES: LazyLock<Mutex<HashMap<usize, Arc<str>>>> = LazyLock::new(Default::default);
+    NAMES
+        .lock()
+        .unwrap()
+        .entry(address)
+        .or_insert_with(|| name().into())
+        .clone()
+}
+
+struct Detour<T: retour::Function>(&'static retour::StaticDetour<T>);
+impl<T: retour::Function> HookControl for Detour<T> {
+    fn enable(&self) -> Result<()> {
+        unsafe { self.0.enable()? };
+        Ok(())
+    }
+    fn disable(&self) -> Result<()> {
+        unsafe { self.0.disable()? };
+        Ok(())
+    }
+}
+
 pub type UObjectLock = parking_lot::FairMutexGuard<'static, &'static ue::FUObjectArray>;
 static mut GUOBJECT_LOCK: Option<UObjectLock> = None;
 
 pub unsafe fn initialize() -> Result<()> {
     assert_main_thread!();
 
-    GUOBJECT_LOCK = Some(globals().guobject_array());
-
-    HookFEngineLoopInit.initialize(
-        std::mem::transmute(globals().resolution.engine_loop_init.0),
-        move |engine_loop| {
-            assert_main_thread!();
-
-            HookFEngineLoopInit.call(engine_loop);
-            simple_log::info!("ENGINE LOOP INIT");
-        },
-    )?;
-    HookFEngineLoopInit.enable()?;
-
-    HookUGameEngineTick.initialize(
-        std::mem::transmute(globals().resolution.game_tick.0),
-        move |game_engine, delta_seconds, idle_mode| {
-            assert_main_thread!();
-
-            //info!("tick time={:0.5}", delta_seconds);
-
-            GUOBJECT_LOCK.take();
-            HookUGameEngineTick.call(game_engine, delta_seconds, idle_mode);
-            GUOBJECT_LOCK = Some(globals().guobject_array());
-        },
-    )?;
-    HookUGameEngineTick.enable()?;
-
-    HookAllocateUObject.initialize(
-        std::mem::transmute(globals().resolution.allocate_uobject.0),
-        |this, object, merging_threads| {
-            //assert_main_thread!();
-
-            //info!("allocate uobject {:?}", object);
-
-            HookAllocateUObject.call(this, object, merging_threads);
-
-            object_cache::object_created(&*object);
-            create_uobject::call(/*GUOBJECT_LOCK.as_ref().unwrap(),*/ &*object);
-        },
-    )?;
-    HookAllocateUObject.enable()?;
-
-    HookFreeUObject.initialize(
-        std::mem::transmute(globals().resolution.free_uobject.0),
-        |this, object| {
-            //assert_main_thread!();
-
-            //info!("delete uobject {:?}", object);
-
-            object_cache::object_deleted(&*this);
-            delete_uobject::call(/*GUOBJECT_LOCK.as_ref().unwrap(),*/ &*this);
-
-            HookFreeUObject.call(this, object);
-        },
-    )?;
-    HookFreeUObject.enable()?;
-
-    HookKismetPrintString.initialize(
-        std::mem::transmute(
-            *globals()
-                .resolution
-                .kismet_system_library
-                .0
-                .get("PrintString")
-                .unwrap(),
-        ),
-        |_context, stack, _result| {
-            let stack = &mut *stack;
-
-            let mut ctx: Option<&ue::UObject> = None;
-            let mut string = ue::FString::default();
-            let mut print_to_screen = false;
-            let mut print_to_log = false;
-            let mut color = ue::FLinearColor::default();
-            let mut duration = 0f32;
-
-            ue::kismet::arg(stack, &mut ctx);
-            ue::kismet::arg(stack, &mut string);
-            ue::kismet::arg(stack, &mut print_to_screen);
-            ue::kismet::arg(stack, &mut print_to_log);
-            ue::kismet::arg(stack, &mut color);
-            ue::kismet::arg(stack, &mut duration);
-
-            //let s = string.to_string();
-            //info!("PrintString({s:?})");
-            kismet_print_message::call(&string.to_string());
-
-            if !stack.code.is_null() {
-                stack.code = stack.code.add(1);
-            }
-        },
-    )?;
-    HookKismetPrintString.enable()?;
-
-    HookKismetExecutionMessage.initialize(
-        std::mem::transmute(globals().resolution.fframe_kismet_execution_message.0),
-        |message, verbosity, warning_id| {
-            kismet_execution_message::call(
-                widestring::U16CStr::from_ptr_str(message),
-                verbosity,
-                warning_id,
-            );
-            HookKismetExecutionMessage.call(message, verbosity, warning_id);
-        },
-    )?;
-    HookKismetExecutionMessage.enable()?;
+    if let Ok(engine_loop_init) = &globals().resolution.engine_loop_init {
+        let hook = registry().register("FEngineLoopInit", Detour(&HookFEngineLoopInit))?;
+        HookFEngineLoopInit.initialize(
+            std::mem::transmute(engine_loop_init.0),
+            move_clone!((hook), move |engine_loop| {
+                assert_main_thread!();
+                hook.record(engine_loop);
+
+                HookFEngineLoopInit.call(engine_loop);
+                tracing::info!("ENGINE LOOP INIT");
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if let Ok(game_tick) = &globals().resolution.game_tick {
+        // released while ticking, so only taken if ticks are hooked
+        GUOBJECT_LOCK = Some(globals().guobject_array());
+
+        let hook = registry().register("UGameEngineTick", Detour(&HookUGameEngineTick))?;
+        HookUGameEngineTick.initialize(
+            std::mem::transmute(game_tick.0),
+            move_clone!((hook), move |game_engine, delta_seconds, idle_mode| {
+                assert_main_thread!();
+                hook.record((game_engine, delta_seconds, idle_mode));
+
+                //info!("tick time={:0.5}", delta_seconds);
+
+                GUOBJECT_LOCK.take();
+                HookUGameEngineTick.call(game_engine, delta_seconds, idle_mode);
+                GUOBJECT_LOCK = Some(globals().guobject_array());
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if let Ok(allocate_uobject) = &globals().resolution.allocate_uobject {
+        let hook = registry().register("AllocateUObject", Detour(&HookAllocateUObject))?;
+        HookAllocateUObject.initialize(
+            std::mem::transmute(allocate_uobject.0),
+            move_clone!((hook), move |this, object, merging_threads| {
+                //assert_main_thread!();
+                hook.record((this, object, merging_threads));
+
+                //info!("allocate uobject {:?}", object);
+
+                HookAllocateUObject.call(this, object, merging_threads);
+
+                object_cache::object_created(&*object);
+                create_uobject::call(/*GUOBJECT_LOCK.as_ref().unwrap(),*/ &*object);
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if let Ok(free_uobject) = &globals().resolution.free_uobject {
+        let hook = registry().register("FreeUObject", Detour(&HookFreeUObject))?;
+        HookFreeUObject.initialize(
+            std::mem::transmute(free_uobject.0),
+            move_clone!((hook), move |this, object| {
+                //assert_main_thread!();
+                hook.record(this);
+
+                //info!("delete uobject {:?}", object);
+
+                object_cache::object_deleted(&*this);
+                delete_uobject::call(/*GUOBJECT_LOCK.as_ref().unwrap(),*/ &*this);
+
+                HookFreeUObject.call(this, object);
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if globals().natives_available() {
+        native::hook_kismet_native(
+            "PrintString",
+            &[
+                Param::Object,
+                Param::String,
+                Param::Bool,
+                Param::Bool,
+                Param::Struct(std::mem::size_of::<ue::FLinearColor>()),
+                Param::Float,
+            ],
+            |call| {
+                if let Some(Arg::String(string)) = call.args.get(1) {
+                    kismet_print_message::call(string);
+                }
+            },
+        )?;
+    }
+
+    if let Ok(fframe_kismet_execution_message) =
+        &globals().resolution.fframe_kismet_execution_message
+    {
+        let hook = registry().register(
+            "KismetExecutionMessage",
+            Detour(&HookKismetExecutionMessage),
+        )?;
+        HookKismetExecutionMessage.initialize(
+            std::mem::transmute(fframe_kismet_execution_message.0),
+            move_clone!((hook), move |message, verbosity, warning_id| {
+                let message = widestring::U16CStr::from_ptr_str(message);
+                hook.record((message.as_ptr(), verbosity));
+                kismet_execution_message::call(message, verbosity, warning_id);
+                HookKismetExecutionMessage.call(message.as_ptr(), verbosity, warning_id);
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
 
     type ExecFn = unsafe extern "system" fn(*mut ue::UObject, *mut ue::kismet::FFrame, *mut c_void);
 
@@ -178,37 +231,114 @@ pub unsafe fn initialize() -> Result<()> {
     .into_iter()
     .collect::<std::collections::HashMap<_, ExecFn>>();
 
-    HookUFunctionBind.initialize(
-        std::mem::transmute(globals().resolution.ufunction_bind.0),
-        move |function| {
-            HookUFunctionBind.call(function);
-            if let Some(function) = function.as_mut() {
-                let path = function
-                    .ustruct
-                    .ufield
-                    .uobject
-                    .uobject_base_utility
-                    .uobject_base
-                    .get_path_name(None);
-                if let Some(hook) = hooks.get(path.as_str()) {
-                    simple_log::info!(
-                        "UFunction::Bind({path}) func = {:?} flags = {:?}",
-                        function.func,
-                        function.function_flags
-                    );
-                    function
-                        .function_flags
-                        .insert(ue::EFunctionFlags::FUNC_Native | ue::EFunctionFlags::FUNC_Final);
-                    function.func = *hook;
+    if let Ok(ufunction_bind) = &globals().resolution.ufunction_bind {
+        let hook = registry().register("UFunctionBind", Detour(&HookUFunctionBind))?;
+        HookUFunctionBind.initialize(
+            std::mem::transmute(ufunction_bind.0),
+            move_clone!((hook), move |function| {
+                hook.record(function);
+                HookUFunctionBind.call(function);
+                if let Some(function) = function.as_mut() {
+                    let path = function
+                        .ustruct
+                        .ufield
+                        .uobject
+                        .uobject_base_utility
+                        .uobject_base
+                        .get_path_name(None);
+                    if let Some(native) = hooks.get(path.as_str()) {
+                        tracing::info!(
+                            "UFunction::Bind({path}) func = {:?} flags = {:?}",
+                            function.func,
+                            function.function_flags
+                        );
+                        function.function_flags.insert(
+                            ue::EFunctionFlags::FUNC_Native | ue::EFunctionFlags::FUNC_Final,
+                        );
+                        function.func = *native;
+                    }
                 }
-            }
-        },
-    )?;
-    HookUFunctionBind.enable()?;
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if let Some(process_event) = &globals().process_event {
+        let hook = registry().register("ProcessEvent", Detour(&HookProcessEvent))?;
+        HookProcessEvent.initialize(
+            std::mem::transmute(process_event.0),
+            move_clone!((hook), move |object, function, params| {
+                hook.record((object, function, params));
+                event_tap().process(object as usize, function as usize, params as usize, || {
+                    let class = (*object).uobject_base_utility.uobject_base.class_private;
+                    (
+                        cached_name(class as usize, || {
+                            (*class)
+                                .ustruct
+                                .ufield
+                                .uobject
+                                .uobject_base_utility
+                                .uobject_base
+                                .name_private
+                                .to_string()
+                        }),
+                        cached_name(function as usize, || {
+                            (*function)
+                                .ustruct
+                                .ufield
+                                .uobject
+                                .uobject_base_utility
+                                .uobject_base
+                                .get_path_name(None)
+                        }),
+                    )
+                });
+                HookProcessEvent.call(object, function, params);
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    if let Some(assert_failed) = &globals().assert_failed {
+        let hook = registry().register("AssertFailed", Detour(&HookAssertFailed))?;
+        HookAssertFailed.initialize(
+            std::mem::transmute(assert_failed.0),
+            move_clone!((hook), move |expr, file, line, a, b, c| {
+                hook.record((expr, file, line));
+                tracing::error!("assertion failed: {}", failure_message(expr, file, line));
+                HookAssertFailed.call(expr, file, line, a, b, c);
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
+
+    // ensures are logged instead of being reported, which would collect a crash report
+    if let Some(ensure_failed) = &globals().ensure_failed {
+        let hook = registry().register("EnsureFailed", Detour(&HookEnsureFailed))?;
+        HookEnsureFailed.initialize(
+            std::mem::transmute(ensure_failed.0),
+            move_clone!((hook), move |expr, file, line, _, _, _| {
+                hook.record((expr, file, line));
+                tracing::warn!("ensure failed: {}", failure_message(expr, file, line));
+            }),
+        )?;
+        hook.set_enabled(true)?;
+    }
 
     Ok(())
 }
 
+unsafe fn failure_message(expr: *const c_char, file: *const c_char, line: i32) -> String {
+    let string = |s: *const c_char| {
+        if s.is_null() {
+            Default::default()
+        } else {
+            CStr::from_ptr(s).to_string_lossy()
+        }
+    };
+    format!("{} [File:{}] [Line: {line}]", string(expr), string(file))
+}
+
 unsafe extern "system" fn do_stuff(
     _context: *mut ue::UObject,
     stack: *mut ue::kismet::FFrame,
@@ -218,9 +348,9 @@ unsafe extern "system" fn do_stuff(
     let mut ctx: Option<&ue::UObject> = None;
     ue::kismet::arg(stack, &mut ctx);
 
-    simple_log::info!("doing stuff!!");
+    tracing::info!("doing stuff!!");
 
-    stack.code = stack.code.add(1);
+    ue::kismet::finish(stack);
 }
 
 unsafe extern "system" fn exec_regex(
@@ -231,15 +361,16 @@ unsafe extern "system" fn exec_regex(
     let stack = stack.as_mut().unwrap();
 
     let mut ctx: Option<&ue::UObject> = None;
-    let mut regex = ue::FString::default();
-    let mut input = ue::FString::default();
-    let mut matches: ue::TArray<ue::FString> = Default::default();
+    let mut regex = ue::FStringOwned::default();
+    let mut input = ue::FStringOwned::default();
+    let mut matches: ue::TArrayOwned<ue::FStringOwned> = Default::default();
 
     ue::kismet::arg(stack, &mut regex);
     ue::kismet::arg(stack, &mut input);
     ue::kismet::arg(stack, &mut ctx);
     ue::kismet::arg(stack, &mut matches);
-    let matches_address = (stack.most_recent_property_address as *mut ue::TArray<ue::FString>)
+    let matches_address = (stack.most_recent_property_address
+        as *mut ue::TArrayOwned<ue::FStringOwned>)
         .as_mut()
         .unwrap();
 
@@ -247,19 +378,14 @@ unsafe extern "system" fn exec_regex(
     if let Ok(re) = regex::Regex::new(&regex.to_string()) {
         for cap in re.captures(&input.to_string()).iter() {
             for cap in cap.iter() {
-                let new_str = ue::FString::from(
-                    widestring::U16CString::from_str(
-                        cap.as_ref().map(|m| m.as_str()).unwrap_or_default(),
-                    )
-                    .unwrap()
-                    .as_slice_with_nul(),
-                );
-                matches_address.push(new_str);
+                matches_address.push(ue::FStringOwned::from(
+                    cap.as_ref().map(|m| m.as_str()).unwrap_or_default(),
+                ));
             }
         }
     }
 
     std::mem::forget(matches);
 
-    stack.code = stack.code.add(1);
+    ue::kismet::finish(stack);
 }
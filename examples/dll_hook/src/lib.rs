@@ -3,16 +3,22 @@
 mod app;
 mod gui;
 mod hooks;
+mod native;
 mod object_cache;
 mod ue;
 
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
 
-use anyhow::{anyhow, Context, Result};
-use patternsleuth::resolvers::impl_try_collector;
+use anyhow::{anyhow, bail, Context, Result};
+use patternsleuth::resolvers::memo::ResolutionMemo;
+use patternsleuth::resolvers::progress::ResolverProgress;
 use patternsleuth::resolvers::unreal::blueprint_library::UFunctionBind;
 use patternsleuth::resolvers::unreal::UObjectBaseUtilityGetPathName;
 use patternsleuth::resolvers::unreal::{
+    crash::{FDebugAssertFailed, FDebugEnsureFailed},
     fname::FNameToString,
     game_loop::{FEngineLoopInit, UGameEngineTick},
     gmalloc::GMalloc,
@@ -20,9 +26,13 @@ use patternsleuth::resolvers::unreal::{
         FUObjectArrayAllocateUObjectIndex, FUObjectArrayFreeUObjectIndex, GUObjectArray,
     },
     kismet::{FFrameStep, FFrameStepExplicitProperty, FFrameStepViaExec},
+    layout::{FNameSize, FUObjectItemSize, UObjectBaseLayout},
+    process_event::UObjectProcessEvent,
     KismetSystemLibrary,
 };
-use simple_log::{error, info, LogConfigBuilder};
+use patternsleuth::resolvers::{impl_try_collector, Multi};
+use patternsleuth::ue::ResolvedGlobal;
+use tracing::{error, info, warn};
 use windows::Win32::{
     Foundation::HMODULE,
     System::{
@@ -89,17 +99,33 @@ unsafe extern "system" fn init(_: usize) {
 fn setup() -> Result<PathBuf> {
     let exe_path = std::env::current_exe()?;
     let bin_dir = exe_path.parent().context("could not find exe parent dir")?;
-    let config = LogConfigBuilder::builder()
-        .path(bin_dir.join("dll_hook.txt").to_str().unwrap()) // TODO why does this not take a path??
-        .time_format("%Y-%m-%d %H:%M:%S.%f")
-        .level("debug")
-        .output_file()
-        .size(u64::MAX)
-        .build();
-    simple_log::new(config).map_err(|e| anyhow!("{e}"))?;
+    init_logging(bin_dir)?;
     Ok(bin_dir.to_path_buf())
 }
 
+/// Log to `dll_hook.txt` next to the game executable. Filtered by `RUST_LOG` if set, e.g.
+/// `RUST_LOG=info,patternsleuth[resolver{name=GMalloc}]=trace` to trace the scans of a single
+/// resolver. Resolver timings are logged when their spans close.
+fn init_logging(bin_dir: &std::path::Path) -> Result<()> {
+    use tracing_subscriber::{filter::LevelFilter, fmt, fmt::format::FmtSpan, EnvFilter};
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(bin_dir.join("dll_hook.txt"))?;
+    fmt()
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::DEBUG.into())
+                .from_env_lossy(),
+        )
+        .try_init()
+        .map_err(|e| anyhow!("{e}"))
+}
+
 #[derive(Debug, PartialEq)]
 pub struct FFrameKismetExecutionMessage(usize);
 
@@ -138,31 +164,110 @@ impl_try_collector! {
         ufunction_bind: UFunctionBind,
         uobject_base_utility_get_path_name: UObjectBaseUtilityGetPathName,
     }
+    partial DllHookResolutionPartial;
+}
+
+impl_try_collector! {
+    #[derive(Debug, PartialEq, Clone)]
+    struct EngineLayout {
+        fname_size: FNameSize,
+        fuobject_item_size: FUObjectItemSize,
+        uobject_base_layout: UObjectBaseLayout,
+    }
+    partial EngineLayoutPartial;
+}
+
+/// Warn if the game's struct layouts differ from [`ue`], which matches one particular engine
+/// build
+fn check_layout(layout: &EngineLayoutPartial) {
+    for (name, value) in layout.values().0 {
+        info!("layout {name} = {value}");
+    }
+    for (name, err) in layout.errors() {
+        info!("failed to resolve layout {name}: {err}");
+    }
+    let check = |what: &str, game: usize, hook: usize| {
+        if game != hook {
+            warn!(
+                "{what} is {game:#x} in the game but {hook:#x} in the hook, expect broken objects"
+            );
+        }
+    };
+    if let Ok(size) = &layout.fname_size {
+        check("sizeof(FName)", size.0, std::mem::size_of::<ue::FName>());
+    }
+    if let Ok(size) = &layout.fuobject_item_size {
+        check(
+            "sizeof(FUObjectItem)",
+            size.0,
+            std::mem::size_of::<ue::FUObjectItem>(),
+        );
+    }
+    if let Ok(base) = &layout.uobject_base_layout {
+        check(
+            "offset of UObjectBase::InternalIndex",
+            base.internal_index,
+            std::mem::offset_of!(ue::UObjectBase, internal_index),
+        );
+    }
 }
 
-static mut GLOBALS: Option<Globals> = None;
+static GLOBALS: ResolvedGlobal<Globals> = ResolvedGlobal::new("Globals");
+
+/// Members of [`DllHookResolution`] nothing works without. Any other member failing only
+/// disables the hooks using it.
+const REQUIRED: [&str; 4] = [
+    "gmalloc",
+    "guobject_array",
+    "fnametostring",
+    "uobject_base_utility_get_path_name",
+];
+
+/// A member of the resolution, which [`patch`] checked if it's [`REQUIRED`]
+fn member<T>(member: &patternsleuth::resolvers::Result<Arc<T>>) -> &T {
+    member
+        .as_deref()
+        .expect("member is required or checked by the caller")
+}
 
 pub struct Globals {
-    resolution: DllHookResolution,
+    resolution: DllHookResolutionPartial,
+    /// Optional as the ProcessEvent patterns don't cover every engine version
+    process_event: Option<UObjectProcessEvent>,
+    /// Optional as ensures may be compiled out and the failure messages differ between builds
+    assert_failed: Option<FDebugAssertFailed>,
+    ensure_failed: Option<FDebugEnsureFailed>,
     guobject_array: parking_lot::FairMutex<&'static ue::FUObjectArray>,
     main_thread_id: std::thread::ThreadId,
 }
+// the object array is only accessed through the mutex or by callers of the unchecked accessor
+unsafe impl Send for Globals {}
+unsafe impl Sync for Globals {}
 
 impl Globals {
-    pub fn gmalloc(&self) -> &ue::FMalloc {
-        unsafe { &**(self.resolution.gmalloc.0 as *const *const ue::FMalloc) }
-    }
+    /// Only available if [`Globals::natives_available`]
     pub fn fframe_step(&self) -> ue::FnFFrameStep {
-        unsafe { std::mem::transmute(self.resolution.fframe_step.0) }
+        unsafe { std::mem::transmute(member(&self.resolution.fframe_step).0) }
     }
+    /// Only available if [`Globals::natives_available`]
     pub fn fframe_step_explicit_property(&self) -> ue::FnFFrameStepExplicitProperty {
-        unsafe { std::mem::transmute(self.resolution.fframe_step_explicit_property.0) }
+        unsafe { std::mem::transmute(member(&self.resolution.fframe_step_explicit_property).0) }
     }
     pub fn fname_to_string(&self) -> ue::FnFNameToString {
-        unsafe { std::mem::transmute(self.resolution.fnametostring.0) }
+        unsafe { std::mem::transmute(member(&self.resolution.fnametostring).0) }
     }
     pub fn uobject_base_utility_get_path_name(&self) -> ue::FnUObjectBaseUtilityGetPathName {
-        unsafe { std::mem::transmute(self.resolution.uobject_base_utility_get_path_name.0) }
+        unsafe {
+            std::mem::transmute(member(&self.resolution.uobject_base_utility_get_path_name).0)
+        }
+    }
+    /// Whether everything needed to hook natives and read their arguments resolved
+    pub fn natives_available(&self) -> bool {
+        let r = &self.resolution;
+        r.kismet_system_library.is_ok()
+            && r.ufunction_bind.is_ok()
+            && r.fframe_step.is_ok()
+            && r.fframe_step_explicit_property.is_ok()
     }
     pub fn guobject_array(&self) -> parking_lot::FairMutexGuard<'static, &ue::FUObjectArray> {
         self.guobject_array.lock()
@@ -173,7 +278,13 @@ impl Globals {
 }
 
 pub fn globals() -> &'static Globals {
-    unsafe { GLOBALS.as_ref().unwrap() }
+    GLOBALS.get()
+}
+
+/// Progress of the startup scan. Unlike [`globals`] it's available while the scan is running.
+pub fn scan_progress() -> &'static Arc<ResolverProgress> {
+    static SCAN_PROGRESS: OnceLock<Arc<ResolverProgress>> = OnceLock::new();
+    SCAN_PROGRESS.get_or_init(Default::default)
 }
 
 #[macro_export]
@@ -183,6 +294,19 @@ macro_rules! assert_main_thread {
     };
 }
 
+/// Clone each listed variable before evaluating `$expr`, useful for `move` closures
+#[macro_export]
+macro_rules! move_clone {
+    ( ( $($($arg:ident)+$(,)?)* ), $expr:expr) => {
+        {
+            $( $(
+                    let $arg = $arg.clone();
+            )*)*
+            $expr
+        }
+    };
+}
+
 fn dump_backtrace() {
     info!(
         "Dumping backtrace on thread {:?}:",
@@ -197,20 +321,89 @@ fn dump_backtrace() {
 unsafe fn patch(bin_dir: PathBuf) -> Result<()> {
     let exe = patternsleuth::process::internal::read_image()?;
 
+    // remember resolved addresses so launching the same build again skips scanning
+    let memo_path = bin_dir.join("dll_hook_memo.txt");
+    let memo = ResolutionMemo::load(&memo_path).unwrap_or_else(|err| {
+        error!("failed to load resolution memo: {err:#}");
+        Default::default()
+    });
+    if let Some(build_id) = exe.build_id() {
+        info!("build id {build_id}");
+        memo.retain_build(&build_id);
+    }
+
+    let progress = scan_progress();
+    progress.on_resolved(|status| match &status.error {
+        None if status.relaxation > 0 => info!(
+            "resolved {} after {:?} with relaxation level {}",
+            status.name, status.elapsed, status.relaxation
+        ),
+        None => info!("resolved {} after {:?}", status.name, status.elapsed),
+        Some(err) => info!("failed {} after {:?}: {err}", status.name, status.elapsed),
+    });
+    let events = progress.handler();
+
     info!("starting scan");
-    let resolution = exe.resolve(DllHookResolution::resolver())?;
+    let resolution = exe.resolve_memoized_with_events(
+        DllHookResolutionPartial::resolver(),
+        &memo,
+        events.clone(),
+    );
     info!("finished scan");
 
+    let process_event = exe
+        .resolve_memoized_with_events(UObjectProcessEvent::resolver(), &memo, events.clone())
+        .map_err(|err| error!("failed to resolve UObjectProcessEvent: {err}"))
+        .ok();
+    let assert_failed = exe
+        .resolve_memoized_with_events(FDebugAssertFailed::resolver(), &memo, events.clone())
+        .map_err(|err| error!("failed to resolve FDebugAssertFailed: {err}"))
+        .ok();
+    // layout probes are memoized as values, so checking them is free on later launches
+    match exe.resolve_memoized_with_events(EngineLayoutPartial::resolver(), &memo, events.clone()) {
+        Ok(layout) => check_layout(&layout),
+        Err(err) => error!("failed to resolve engine layout: {err}"),
+    }
+    let ensure_failed = exe
+        .resolve_memoized_with_events(FDebugEnsureFailed::resolver(), &memo, events)
+        .map_err(|err| error!("failed to resolve FDebugEnsureFailed: {err}"))
+        .ok();
+
+    // before bailing so the overlay doesn't stay on scanning
+    progress.finish();
+    let resolution = resolution?;
+
+    for (name, err) in resolution.errors() {
+        if REQUIRED.contains(&name) {
+            bail!("failed to resolve {name}: {err}");
+        }
+        error!("failed to resolve {name}, disabling hooks using it: {err}");
+    }
+
+    if let Err(err) = memo.save(&memo_path) {
+        error!("failed to save resolution memo: {err}");
+    }
+
     info!("results: {:?}", resolution);
 
+    patternsleuth::ue::set_gmalloc(member(&resolution.gmalloc));
+
     let guobject_array: &'static ue::FUObjectArray =
-        &*(resolution.guobject_array.0 as *const ue::FUObjectArray);
+        &*(member(&resolution.guobject_array).0 as *const ue::FUObjectArray);
 
-    GLOBALS = Some(Globals {
-        guobject_array: guobject_array.into(),
-        resolution,
-        main_thread_id: std::thread::current().id(),
-    });
+    if GLOBALS
+        .set(Globals {
+            guobject_array: guobject_array.into(),
+            resolution,
+            process_event,
+            assert_failed,
+            ensure_failed,
+            main_thread_id: std::thread::current().id(),
+        })
+        .is_err()
+    {
+        bail!("already initialized");
+    }
 
     hooks::initialize()?;
 
@@ -0,0 +1,244 @@
+//! Replacing Kismet natives (`exec` functions) with Rust closures.
+//!
+//! Natives are located by name through the [`KismetSystemLibrary`] resolution and detoured to
+//! [`dispatch`], so natives can be hooked whether or not their `UFunction` is already bound.
+//! `dispatch` finds the hook through the `Func` of `FFrame::CurrentNativeFunction` (set by
+//! `UFunction::Invoke` for both bytecode and compiled-in calls), reads the arguments described by
+//! the hook's parameters and calls the closure.
+//!
+//! [`KismetSystemLibrary`]: patternsleuth::resolvers::unreal::KismetSystemLibrary
+
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock, RwLock,
+    },
+};
+
+use retour::GenericDetour;
+
+use anyhow::{Context, Result};
+use patternsleuth::hooks::{Hook, HookControl};
+
+use crate::{globals, hooks, ue};
+
+type ExecFn = unsafe extern "system" fn(*mut ue::UObject, *mut ue::kismet::FFrame, *mut c_void);
+
+/// Type of a native function parameter, in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Param {
+    Bool,
+    Byte,
+    Int,
+    Int64,
+    Float,
+    Double,
+    Name,
+    Object,
+    String,
+    /// Any other value as raw bytes of the given size. Heap allocations owned by the value
+    /// (e.g. of `TArray` members) are leaked.
+    Struct(usize),
+}
+
+#[derive(Debug)]
+pub enum Arg {
+    Bool(bool),
+    Byte(u8),
+    Int(i32),
+    Int64(i64),
+    Float(f32),
+    Double(f64),
+    Name(ue::FName),
+    Object(*mut ue::UObject),
+    String(String),
+    Struct(Vec<u8>),
+}
+
+/// A call of a hooked native
+pub struct NativeCall {
+    pub context: *mut ue::UObject,
+    pub args: Vec<Arg>,
+    /// `FFrame::MostRecentPropertyAddress` after reading each argument, the location to write to
+    /// for out parameters
+    pub addresses: Vec<*mut c_void>,
+    /// Where to write the return value, if any
+    pub result: *mut c_void,
+}
+impl NativeCall {
+    unsafe fn read(
+        context: *mut ue::UObject,
+        stack: &mut ue::kismet::FFrame,
+        params: &[Param],
+        result: *mut c_void,
+    ) -> Self {
+        fn step<T>(stack: &mut ue::kismet::FFrame, mut value: T) -> T {
+            ue::kismet::arg(stack, &mut value);
+            value
+        }
+
+        let mut args = vec![];
+        let mut addresses = vec![];
+        for param in params {
+            args.push(match param {
+                // bool properties are read into a uint32 (see P_GET_UBOOL)
+                Param::Bool => Arg::Bool(step(stack, 0u32) != 0),
+                Param::Byte => Arg::Byte(step(stack, 0)),
+                Param::Int => Arg::Int(step(stack, 0)),
+                Param::Int64 => Arg::Int64(step(stack, 0)),
+                Param::Float => Arg::Float(step(stack, 0.)),
+                Param::Double => Arg::Double(step(stack, 0.)),
+                Param::Name => Arg::Name(step(stack, std::mem::zeroed())),
+                Param::Object => Arg::Object(step(stack, std::ptr::null_mut())),
+                Param::String => Arg::String(step(stack, ue::FStringOwned::default()).to_string()),
+                Param::Struct(size) => {
+                    // 8 byte aligned buffer
+                    let mut buffer = vec![0u64; size.div_ceil(8)];
+                    ue::kismet::arg_raw(stack, buffer.as_mut_ptr() as *mut c_void);
+                    let mut bytes = buffer
+                        .iter()
+                        .flat_map(|w| w.to_le_bytes())
+                        .collect::<Vec<_>>();
+                    bytes.truncate(*size);
+                    Arg::Struct(bytes)
+                }
+            });
+            addresses.push(stack.most_recent_property_address as *mut c_void);
+        }
+        ue::kismet::finish(stack);
+
+        Self {
+            context,
+            args,
+            addresses,
+            result,
+        }
+    }
+}
+
+type Handler = Box<dyn Fn(&mut NativeCall) + Send + Sync>;
+
+struct NativeHook {
+    params: Vec<Param>,
+    handler: Handler,
+    hook: Arc<Hook>,
+    detour: Arc<GenericDetour<ExecFn>>,
+    /// Whether a mismatch of the parameters has been reported
+    mismatch_reported: AtomicBool,
+}
+
+/// Lets the hook registry switch between the closure and the original native
+struct NativeDetour(Arc<GenericDetour<ExecFn>>);
+impl HookControl for NativeDetour {
+    fn enable(&self) -> Result<()> {
+        unsafe { self.0.enable()? };
+        Ok(())
+    }
+    fn disable(&self) -> Result<()> {
+        unsafe { self.0.disable()? };
+        Ok(())
+    }
+}
+
+type Natives = HashMap<usize, Arc<NativeHook>>;
+
+/// exec function address -> hook
+static NATIVES: LazyLock<RwLock<Natives>> = LazyLock::new(Default::default);
+
+/// Replace the KismetSystemLibrary native `name` (e.g. "PrintString") with `handler`. `params`
+/// describes the parameters of the function so they can be read from the stack before the
+/// handler is called. The hook is registered as `KismetSystemLibrary::<name>` in the
+/// [`hooks::registry`] where disabling it restores the original native.
+pub fn hook_kismet_native(
+    name: &str,
+    params: &[Param],
+    handler: impl Fn(&mut NativeCall) + Send + Sync + 'static,
+) -> Result<()> {
+    let address = *globals()
+        .resolution
+        .kismet_system_library
+        .as_ref()
+        .ok()
+        .context("KismetSystemLibrary failed to resolve")?
+        .0
+        .get(name)
+        .with_context(|| format!("KismetSystemLibrary has no native named {name:?}"))?;
+
+    let detour = Arc::new(unsafe {
+        GenericDetour::<ExecFn>::new(std::mem::transmute::<usize, ExecFn>(address), dispatch)?
+    });
+    let hook = hooks::registry().register(
+        format!("KismetSystemLibrary::{name}"),
+        NativeDetour(detour.clone()),
+    )?;
+
+    // registered before enabling so dispatch always finds it
+    NATIVES.write().unwrap().insert(
+        address,
+        Arc::new(NativeHook {
+            params: params.to_vec(),
+            handler: Box::new(handler),
+            hook: hook.clone(),
+            detour,
+            mismatch_reported: AtomicBool::new(false),
+        }),
+    );
+    hook.set_enabled(true)?;
+    Ok(())
+}
+
+unsafe extern "system" fn dispatch(
+    context: *mut ue::UObject,
+    stack: *mut ue::kismet::FFrame,
+    result: *mut c_void,
+) {
+    let function = &*((*stack).current_native_function as *const ue::UFunction);
+    // looked up per call so the current registration is used
+    let Some(hook) = NATIVES
+        .read()
+        .unwrap()
+        .get(&(function.func as usize))
+        .cloned()
+    else {
+        tracing::error!("no native hook for {:?}", function.func);
+        // the caller's bytecode continues after the parameters
+        return skip_params(&mut *stack);
+    };
+
+    if hook.params.len() != param_count(function) {
+        // reading the wrong number of parameters would leave the caller's bytecode misaligned
+        if !hook.mismatch_reported.swap(true, Ordering::Relaxed) {
+            tracing::error!(
+                "{}: expected {} params, UFunction has {}",
+                hook.hook.name(),
+                hook.params.len(),
+                param_count(function)
+            );
+        }
+        return hook.detour.call(context, stack, result);
+    }
+
+    hook.hook.record((context, stack, result));
+    let mut call = NativeCall::read(context, &mut *stack, &hook.params, result);
+    (hook.handler)(&mut call);
+}
+
+/// Step over the parameters of the native being called without knowing their types. Values are
+/// read into scratch memory so heap allocations they own are leaked.
+unsafe fn skip_params(stack: &mut ue::kismet::FFrame) {
+    let function = &*(stack.current_native_function as *const ue::UFunction);
+    // 8 byte aligned buffer large enough for any parameter
+    let mut scratch = vec![0u64; (function.parms_size as usize).div_ceil(8).max(1)];
+    for _ in 0..param_count(function) {
+        ue::kismet::arg_raw(stack, scratch.as_mut_ptr() as *mut c_void);
+    }
+    ue::kismet::finish(stack);
+}
+
+/// Number of parameters read from the stack, i.e. excluding the return value
+fn param_count(function: &ue::UFunction) -> usize {
+    let has_return = function.return_value_offset != u16::MAX;
+    function.num_parms as usize - usize::from(has_return)
+}
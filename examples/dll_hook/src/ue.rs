@@ -5,6 +5,8 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+pub use patternsleuth::ue::{FStringOwned, TArray, TArrayOwned};
+
 use windows::Win32::System::Threading::{
     EnterCriticalSection, LeaveCriticalSection, CRITICAL_SECTION,
 };
@@ -19,10 +21,10 @@ pub type FnFFrameStepExplicitProperty = unsafe extern "system" fn(
     property: *const FProperty,
 );
 
-pub type FnFNameToString = unsafe extern "system" fn(&FName, &mut FString);
+pub type FnFNameToString = unsafe extern "system" fn(&FName, &mut FStringOwned);
 impl Display for FName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut string = FString::new();
+        let mut string = FStringOwned::new();
         unsafe {
             (globals().fname_to_string())(self, &mut string);
         };
@@ -31,10 +33,10 @@ impl Display for FName {
 }
 
 pub type FnUObjectBaseUtilityGetPathName =
-    unsafe extern "system" fn(&UObjectBase, Option<&UObject>, &mut FString);
+    unsafe extern "system" fn(&UObjectBase, Option<&UObject>, &mut FStringOwned);
 impl UObjectBase {
     pub fn get_path_name(&self, stop_outer: Option<&UObject>) -> String {
-        let mut string = FString::new();
+        let mut string = FStringOwned::new();
         unsafe {
             (globals().uobject_base_utility_get_path_name())(self, stop_outer, &mut string);
         }
@@ -42,61 +44,6 @@ impl UObjectBase {
     }
 }
 
-#[derive(Debug)]
-#[repr(C)]
-pub struct FMalloc {
-    vtable: *const FMallocVTable,
-}
-unsafe impl Sync for FMalloc {}
-unsafe impl Send for FMalloc {}
-impl FMalloc {
-    pub fn malloc(&self, count: usize, alignment: u32) -> *mut c_void {
-        unsafe { ((*self.vtable).malloc)(self, count, alignment) }
-    }
-    pub fn realloc(&self, original: *mut c_void, count: usize, alignment: u32) -> *mut c_void {
-        unsafe { ((*self.vtable).realloc)(self, original, count, alignment) }
-    }
-    pub fn free(&self, original: *mut c_void) {
-        unsafe { ((*self.vtable).free)(self, original) }
-    }
-}
-
-#[derive(Debug)]
-#[repr(C)]
-pub struct FMallocVTable {
-    pub __vec_del_dtor: *const (),
-    pub exec: *const (),
-    pub malloc:
-        unsafe extern "system" fn(this: &FMalloc, count: usize, alignment: u32) -> *mut c_void,
-    pub try_malloc:
-        unsafe extern "system" fn(this: &FMalloc, count: usize, alignment: u32) -> *mut c_void,
-    pub realloc: unsafe extern "system" fn(
-        this: &FMalloc,
-        original: *mut c_void,
-        count: usize,
-        alignment: u32,
-    ) -> *mut c_void,
-    pub try_realloc: unsafe extern "system" fn(
-        this: &FMalloc,
-        original: *mut c_void,
-        count: usize,
-        alignment: u32,
-    ) -> *mut c_void,
-    pub free: unsafe extern "system" fn(this: &FMalloc, original: *mut c_void),
-    pub quantize_size: *const (),
-    pub get_allocation_size: *const (),
-    pub trim: *const (),
-    pub setup_tls_caches_on_current_thread: *const (),
-    pub clear_and_disable_tlscaches_on_current_thread: *const (),
-    pub initialize_stats_metadata: *const (),
-    pub update_stats: *const (),
-    pub get_allocator_stats: *const (),
-    pub dump_allocator_stats: *const (),
-    pub is_internally_thread_safe: *const (),
-    pub validate_heap: *const (),
-    pub get_descriptive_name: *const (),
-}
-
 #[derive(Debug)]
 #[repr(C)]
 pub struct FWindowsCriticalSection(UnsafeCell<CRITICAL_SECTION>);
@@ -105,11 +52,11 @@ impl FWindowsCriticalSection {
         &self.0 as *const _ as *mut _
     }
     unsafe fn lock(&self) {
-        simple_log::info!("LOCKING objects");
+        tracing::info!("LOCKING objects");
         EnterCriticalSection(self.crit_ptr_mut());
     }
     unsafe fn unlock(&self) {
-        simple_log::info!("UNLOCKING objects");
+        tracing::info!("UNLOCKING objects");
         LeaveCriticalSection(self.crit_ptr_mut());
     }
 }
@@ -146,6 +93,24 @@ impl<T: ?Sized> DerefMut for CriticalSectionGuard<'_, '_, T> {
         unsafe { &mut *self.data.get() }
     }
 }
+impl CriticalSectionGuard<'_, '_, FChunkedFixedUObjectArray> {
+    /// Iterate the objects on the rayon thread pool, one chunk (64K objects) per task. Takes the
+    /// guard so the lock is held until every task finished.
+    pub fn par_iter(&self) -> impl rayon::iter::ParallelIterator<Item = Option<&UObjectBase>> {
+        use rayon::prelude::*;
+
+        let array: &FChunkedFixedUObjectArray = self;
+        let num_elements = array.num_elements;
+        (0..array.used_chunks(num_elements))
+            .into_par_iter()
+            .flat_map_iter(move |chunk| {
+                array
+                    .chunk(chunk, num_elements)
+                    .iter()
+                    .map(ObjectIterator::object)
+            })
+    }
+}
 
 #[derive(Debug)]
 #[repr(C)]
@@ -200,34 +165,102 @@ impl FUObjectArray {
     }
 }
 
+/// Iterator over the object slots of a [`FChunkedFixedUObjectArray`]. The items of the chunk
+/// being iterated are held as a slice so the chunk table is only read once per chunk.
 pub struct ObjectIterator<'a> {
     array: &'a FChunkedFixedUObjectArray,
-    index: i32,
+    /// Remaining items of the chunk last loaded from the front
+    front: std::slice::Iter<'a, FUObjectItem>,
+    /// Remaining items of the chunk last loaded from the back
+    back: std::slice::Iter<'a, FUObjectItem>,
+    /// Chunks not loaded from either end yet
+    chunks: std::ops::Range<i32>,
+    /// `num_elements` when iteration started, objects created since are not visited
+    num_elements: i32,
+    remaining: usize,
+}
+impl<'a> ObjectIterator<'a> {
+    fn new(array: &'a FChunkedFixedUObjectArray) -> Self {
+        let num_elements = array.num_elements;
+        Self {
+            array,
+            front: [].iter(),
+            back: [].iter(),
+            chunks: 0..array.used_chunks(num_elements),
+            num_elements,
+            remaining: num_elements.max(0) as usize,
+        }
+    }
+    fn chunk_len(&self, chunk: i32) -> usize {
+        self.array.chunk_len(chunk, self.num_elements)
+    }
+    fn chunk(&self, chunk: i32) -> std::slice::Iter<'a, FUObjectItem> {
+        self.array.chunk(chunk, self.num_elements).iter()
+    }
+    fn object(item: &'a FUObjectItem) -> Option<&'a UObjectBase> {
+        unsafe { item.object.as_ref() }
+    }
 }
 impl<'a> Iterator for ObjectIterator<'a> {
     type Item = Option<&'a UObjectBase>;
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = self.array.num_elements as usize;
-        (size, Some(size))
-    }
-    fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        let n = n as i32;
-        if self.index < n {
-            self.index = n;
+        (self.remaining, Some(self.remaining))
+    }
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        // skip whole chunks without reading them
+        if n >= self.front.len() && !self.chunks.is_empty() {
+            n -= self.front.len();
+            self.remaining -= self.front.len();
+            self.front = [].iter();
+            while !self.chunks.is_empty() && n >= self.chunk_len(self.chunks.start) {
+                let len = self.chunk_len(self.chunks.start);
+                n -= len;
+                self.remaining -= len;
+                self.chunks.start += 1;
+            }
+        }
+        for _ in 0..n {
+            self.next()?;
         }
         self.next()
     }
-    fn next(&mut self) -> Option<Option<&'a UObjectBase>> {
-        if self.index >= self.array.num_elements {
-            None
-        } else {
-            let obj = unsafe { self.array.item(self.index).object.as_ref() };
-
-            self.index += 1;
-            Some(obj)
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.front.next() {
+                self.remaining -= 1;
+                return Some(Self::object(item));
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = self.chunk(chunk),
+                None => {
+                    let item = self.back.next()?;
+                    self.remaining -= 1;
+                    return Some(Self::object(item));
+                }
+            }
         }
     }
 }
+impl DoubleEndedIterator for ObjectIterator<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.back.next_back() {
+                self.remaining -= 1;
+                return Some(Self::object(item));
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = self.chunk(chunk),
+                None => {
+                    let item = self.front.next_back()?;
+                    self.remaining -= 1;
+                    return Some(Self::object(item));
+                }
+            }
+        }
+    }
+}
+impl ExactSizeIterator for ObjectIterator<'_> {}
+impl std::iter::FusedIterator for ObjectIterator<'_> {}
 
 #[derive(Debug)]
 #[repr(C)]
@@ -239,15 +272,39 @@ pub struct FChunkedFixedUObjectArray {
     pub max_chunks: i32,
     pub num_chunks: i32,
 }
+// SAFETY: the array and the objects it points to are only read, and only reachable through the
+// guard of the `FUObjectArray` lock (see `FUObjectArray::objects`), which `par_iter` borrows for
+// as long as other threads read through it
+unsafe impl Sync for FChunkedFixedUObjectArray {}
 impl FChunkedFixedUObjectArray {
     pub fn iter(&self) -> ObjectIterator<'_> {
-        ObjectIterator {
-            array: self,
-            index: 0,
+        ObjectIterator::new(self)
+    }
+    fn per_chunk(&self) -> i32 {
+        self.max_elements / self.max_chunks
+    }
+    /// Number of chunks holding the first `num_elements` items
+    fn used_chunks(&self, num_elements: i32) -> i32 {
+        if num_elements <= 0 {
+            0
+        } else {
+            (num_elements - 1) / self.per_chunk() + 1
+        }
+    }
+    fn chunk_len(&self, chunk: i32, num_elements: i32) -> usize {
+        (num_elements - chunk * self.per_chunk()).clamp(0, self.per_chunk()) as usize
+    }
+    /// Items of `chunk`, up to `num_elements`
+    fn chunk(&self, chunk: i32, num_elements: i32) -> &[FUObjectItem] {
+        unsafe {
+            std::slice::from_raw_parts(
+                *self.objects.add(chunk as usize),
+                self.chunk_len(chunk, num_elements),
+            )
         }
     }
     fn item_ptr(&self, index: ObjectIndex) -> *const FUObjectItem {
-        let per_chunk = self.max_elements / self.max_chunks;
+        let per_chunk = self.per_chunk();
 
         unsafe {
             (*self.objects.add((index / per_chunk) as usize)).add((index % per_chunk) as usize)
@@ -269,6 +326,8 @@ pub struct FUObjectItem {
     pub cluster_root_index: i32,
     pub serial_number: std::sync::atomic::AtomicI32,
 }
+// SAFETY: see `FChunkedFixedUObjectArray`
+unsafe impl Sync for FUObjectItem {}
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -383,6 +442,8 @@ pub struct UObjectBase {
     pub name_private: FName,
     pub outer_private: *const UObject,
 }
+// SAFETY: see `FChunkedFixedUObjectArray`
+unsafe impl Sync for UObjectBase {}
 
 #[derive(Debug)]
 #[repr(C)]
@@ -488,6 +549,56 @@ pub struct UClass {
     pub ustruct: UStruct,
 }
 
+/// Reflection types laid out as the UObject subclass named `CLASS`, so a `UObject` can be cast to
+/// them once its class is known to derive from it
+///
+/// # Safety
+/// The type must be `#[repr(C)]` and start with the fields of `CLASS`'s super class.
+pub unsafe trait UObjectType {
+    const CLASS: &'static str;
+}
+unsafe impl UObjectType for UObject {
+    const CLASS: &'static str = "Object";
+}
+unsafe impl UObjectType for UField {
+    const CLASS: &'static str = "Field";
+}
+unsafe impl UObjectType for UStruct {
+    const CLASS: &'static str = "Struct";
+}
+unsafe impl UObjectType for UFunction {
+    const CLASS: &'static str = "Function";
+}
+unsafe impl UObjectType for UClass {
+    const CLASS: &'static str = "Class";
+}
+
+impl UObjectBase {
+    /// Whether the class of the object is `T`'s class or derives from it, walking the
+    /// SuperStruct chain of the class
+    pub fn is_a<T: UObjectType>(&self) -> bool {
+        let mut next = self.class_private as *const UStruct;
+        while let Some(ustruct) = unsafe { next.as_ref() } {
+            let name = &ustruct
+                .ufield
+                .uobject
+                .uobject_base_utility
+                .uobject_base
+                .name_private;
+            if name.to_string() == T::CLASS {
+                return true;
+            }
+            next = ustruct.super_struct;
+        }
+        false
+    }
+    /// The object as a `T` if it [`is_a`](Self::is_a) `T`
+    pub fn cast<T: UObjectType>(&self) -> Option<&T> {
+        self.is_a::<T>()
+            .then(|| unsafe { &*(self as *const Self as *const T) })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct FName {
@@ -515,136 +626,6 @@ pub struct FReferenceControllerBase {
     pub weak_reference_count: i32,
 }
 
-pub type FString = TArray<u16>;
-
-#[derive(Debug)]
-#[repr(C)]
-pub struct TArray<T> {
-    data: *const T,
-    num: i32,
-    max: i32,
-}
-impl<T> TArray<T> {
-    fn new() -> Self {
-        Self {
-            data: std::ptr::null(),
-            num: 0,
-            max: 0,
-        }
-    }
-}
-impl<T> Drop for TArray<T> {
-    fn drop(&mut self) {
-        unsafe {
-            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(
-                self.data.cast_mut(),
-                self.num as usize,
-            ))
-        }
-        globals().gmalloc().free(self.data as *mut c_void);
-    }
-}
-impl<T> Default for TArray<T> {
-    fn default() -> Self {
-        Self {
-            data: std::ptr::null(),
-            num: 0,
-            max: 0,
-        }
-    }
-}
-impl<T> TArray<T> {
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            data: globals().gmalloc().malloc(
-                capacity * std::mem::size_of::<T>(),
-                std::mem::align_of::<T>() as u32,
-            ) as *const T,
-            num: 0,
-            max: capacity as i32,
-        }
-    }
-    pub fn len(&self) -> usize {
-        self.num as usize
-    }
-    pub fn capacity(&self) -> usize {
-        self.max as usize
-    }
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
-    pub fn as_slice(&self) -> &[T] {
-        if self.num == 0 {
-            &[]
-        } else {
-            unsafe { std::slice::from_raw_parts(self.data, self.num as usize) }
-        }
-    }
-    pub fn as_mut_slice(&mut self) -> &mut [T] {
-        if self.num == 0 {
-            &mut []
-        } else {
-            unsafe { std::slice::from_raw_parts_mut(self.data as *mut _, self.num as usize) }
-        }
-    }
-    pub fn clear(&mut self) {
-        let elems: *mut [T] = self.as_mut_slice();
-
-        unsafe {
-            self.num = 0;
-            std::ptr::drop_in_place(elems);
-        }
-    }
-    pub fn push(&mut self, new_value: T) {
-        if self.num >= self.max {
-            self.max = u32::next_power_of_two((self.max + 1) as u32) as i32;
-            let new = globals().gmalloc().realloc(
-                self.data as *mut c_void,
-                self.max as usize * std::mem::size_of::<T>(),
-                std::mem::align_of::<T>() as u32,
-            ) as *const T;
-            self.data = new;
-        }
-        unsafe {
-            std::ptr::write(self.data.add(self.num as usize).cast_mut(), new_value);
-        }
-        self.num += 1;
-    }
-}
-
-impl<T> From<&[T]> for TArray<T>
-where
-    T: Copy,
-{
-    fn from(value: &[T]) -> Self {
-        let mut new = Self::with_capacity(value.len());
-        // TODO this is probably unsound
-        new.num = value.len() as i32;
-        new.as_mut_slice().copy_from_slice(value);
-        new
-    }
-}
-
-impl Display for FString {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let slice = self.as_slice();
-        let last = slice.len()
-            - slice
-                .iter()
-                .cloned()
-                .rev()
-                .position(|c| c != 0)
-                .unwrap_or_default();
-        write!(
-            f,
-            "{}",
-            widestring::U16Str::from_slice(&slice[..last])
-                .to_string()
-                .unwrap()
-        )
-    }
-}
-
 #[derive(Debug, Default)]
 #[repr(C)]
 pub struct FVector {
@@ -684,15 +665,25 @@ pub mod kismet {
     }
 
     pub fn arg<T: Sized>(stack: &mut FFrame, output: &mut T) {
-        let output = output as *const _ as *mut _;
-        unsafe {
-            if stack.code.is_null() {
-                let cur = stack.property_chain_for_compiled_in;
-                stack.property_chain_for_compiled_in = (*cur).next;
-                (globals().fframe_step_explicit_property())(stack, output, cur as *const FProperty);
-            } else {
-                (globals().fframe_step())(stack, stack.object, output);
-            }
+        unsafe { arg_raw(stack, output as *mut T as *mut c_void) }
+    }
+
+    /// Step the next argument into `output` which must be large enough to hold it
+    pub unsafe fn arg_raw(stack: &mut FFrame, output: *mut c_void) {
+        if stack.code.is_null() {
+            let cur = stack.property_chain_for_compiled_in;
+            stack.property_chain_for_compiled_in = (*cur).next;
+            (globals().fframe_step_explicit_property())(stack, output, cur as *const FProperty);
+        } else {
+            (globals().fframe_step())(stack, stack.object, output);
+        }
+    }
+
+    /// Equivalent of P_FINISH: skip EX_EndFunctionParms when called from bytecode. Natives
+    /// called from compiled-in code (e.g. ProcessEvent) have no code to advance.
+    pub fn finish(stack: &mut FFrame) {
+        if !stack.code.is_null() {
+            stack.code = unsafe { stack.code.add(1) };
         }
     }
 }
@@ -0,0 +1,552 @@
+//! A small x86-64 interpreter for resolvers needing values which are only computed at runtime,
+//! e.g. globals stored encrypted or pointers initialized lazily. Execution starts at a matched
+//! address and runs until a [`StopCondition`] is reached, after which registers and stores can
+//! be inspected.
+//!
+//! Only common integer instructions are supported and memory not written by the emulated code
+//! is read from the image. Anything else stops emulation with an error.
+
+use std::{collections::HashMap, ops::Range};
+
+use iced_x86::{ConditionCode, FlowControl, Instruction, Mnemonic, OpKind, Register};
+
+use crate::{
+    disassemble::disassemble_single,
+    resolvers::{bail_out, Result},
+    Image, MemoryTrait,
+};
+
+/// Initial stack pointer, far away from any image
+const STACK_BASE: usize = 0x7ffe_0000_0000;
+const STACK_SIZE: usize = 0x10_0000;
+const DEFAULT_MAX_STEPS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopCondition {
+    /// Before executing the instruction at this address
+    Address(usize),
+    /// Before a `ret` leaving the function emulation started in
+    Return,
+    /// Before any call
+    Call,
+    /// After the first store to memory outside the stack
+    Store,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Flags {
+    zf: bool,
+    sf: bool,
+    cf: bool,
+    of: bool,
+}
+
+pub struct Emulator<'img, 'mem> {
+    exe: &'img Image<'mem>,
+    registers: [u64; 16],
+    rip: usize,
+    flags: Flags,
+    /// Bytes written during emulation, shadowing the image
+    memory: HashMap<usize, u8>,
+    /// `(address, size, value)` of stores outside the stack in order
+    stores: Vec<(usize, usize, u64)>,
+    /// Number of calls entered and not yet returned from
+    depth: usize,
+    max_steps: usize,
+    stopped: Option<StopCondition>,
+}
+
+impl<'img, 'mem> Emulator<'img, 'mem> {
+    pub fn new(exe: &'img Image<'mem>, start: usize) -> Self {
+        let mut registers = [0; 16];
+        registers[Register::RSP.number()] = STACK_BASE as u64;
+        Self {
+            exe,
+            registers,
+            rip: start,
+            flags: Default::default(),
+            memory: Default::default(),
+            stores: vec![],
+            depth: 0,
+            max_steps: DEFAULT_MAX_STEPS,
+            stopped: None,
+        }
+    }
+    /// Fail [`Self::run`] after this many instructions (default 10000)
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+    pub fn rip(&self) -> usize {
+        self.rip
+    }
+    /// The condition which stopped the last [`Self::run`]
+    pub fn stopped(&self) -> Option<StopCondition> {
+        self.stopped
+    }
+    /// `(address, size, value)` of stores outside the stack in execution order
+    pub fn stores(&self) -> &[(usize, usize, u64)] {
+        &self.stores
+    }
+    /// Read a general purpose register, e.g. `RAX` or `ECX`
+    pub fn register(&self, register: Register) -> Result<u64> {
+        let value = self.registers[gpr_index(register)?];
+        Ok(if is_high_byte(register) {
+            (value >> 8) & 0xff
+        } else {
+            value & mask(register.size())
+        })
+    }
+    /// Write a general purpose register, e.g. to pass arguments before running. Writes to 32 bit
+    /// registers zero the upper half like the hardware does.
+    pub fn set_register(&mut self, register: Register, value: u64) -> Result<()> {
+        let index = gpr_index(register)?;
+        let old = self.registers[index];
+        self.registers[index] = match register.size() {
+            8 => value,
+            4 => value & 0xffff_ffff,
+            2 => (old & !0xffff) | (value & 0xffff),
+            _ if is_high_byte(register) => (old & !0xff00) | ((value & 0xff) << 8),
+            _ => (old & !0xff) | (value & 0xff),
+        };
+        Ok(())
+    }
+    /// Read `size` bytes (at most 8) as a little endian integer, including emulated writes
+    pub fn read(&self, address: usize, size: usize) -> Result<u64> {
+        let mut value = 0;
+        for i in (0..size).rev() {
+            let byte = match self.memory.get(&(address + i)) {
+                Some(byte) => *byte,
+                None => self.exe.memory.index(address + i)?,
+            };
+            value = (value << 8) | byte as u64;
+        }
+        Ok(value)
+    }
+    fn write(&mut self, address: usize, size: usize, value: u64) {
+        for i in 0..size {
+            self.memory.insert(address + i, (value >> (i * 8)) as u8);
+        }
+        if !stack_range().contains(&address) {
+            self.stores.push((address, size, value & mask(size)));
+        }
+    }
+
+    /// Execute until one of `stop` is reached, returning it
+    pub fn run(&mut self, stop: &[StopCondition]) -> Result<StopCondition> {
+        self.stopped = None;
+        for _ in 0..self.max_steps {
+            let inst = self.decode()?;
+            let reached = stop.iter().find(|condition| match condition {
+                StopCondition::Address(address) => *address == self.rip,
+                StopCondition::Return => self.depth == 0 && inst.mnemonic() == Mnemonic::Ret,
+                StopCondition::Call => inst.mnemonic() == Mnemonic::Call,
+                StopCondition::Store => false,
+            });
+            if let Some(condition) = reached {
+                self.stopped = Some(*condition);
+                return Ok(*condition);
+            }
+
+            let stores = self.stores.len();
+            self.execute(&inst)?;
+            if self.stores.len() > stores && stop.contains(&StopCondition::Store) {
+                self.stopped = Some(StopCondition::Store);
+                return Ok(StopCondition::Store);
+            }
+        }
+        bail_out!(format!(
+            "no stop condition reached after {} instructions (at {:#x})",
+            self.max_steps, self.rip
+        ));
+    }
+    /// Execute a single instruction
+    pub fn step(&mut self) -> Result<()> {
+        let inst = self.decode()?;
+        self.execute(&inst)
+    }
+
+    fn decode(&self) -> Result<Instruction> {
+        match disassemble_single(self.exe, self.rip)? {
+            Some(inst) if !inst.is_invalid() => Ok(inst),
+            _ => bail_out!(format!("invalid instruction at {:#x}", self.rip)),
+        }
+    }
+    fn execute(&mut self, inst: &Instruction) -> Result<()> {
+        let mut next = inst.next_ip() as usize;
+        match inst.mnemonic() {
+            Mnemonic::Nop => {}
+            Mnemonic::Mov | Mnemonic::Movzx => {
+                let value = self.read_operand(inst, 1)?;
+                self.write_operand(inst, 0, value)?;
+            }
+            Mnemonic::Movsx | Mnemonic::Movsxd => {
+                let value = sign_extend(self.read_operand(inst, 1)?, operand_size(inst, 1));
+                self.write_operand(inst, 0, value)?;
+            }
+            Mnemonic::Lea => {
+                let address = self.memory_address(inst)?;
+                self.write_operand(inst, 0, address as u64)?;
+            }
+            Mnemonic::Xchg => {
+                let a = self.read_operand(inst, 0)?;
+                let b = self.read_operand(inst, 1)?;
+                self.write_operand(inst, 0, b)?;
+                self.write_operand(inst, 1, a)?;
+            }
+            Mnemonic::Bswap => {
+                let value = self.read_operand(inst, 0)?;
+                let value = match operand_size(inst, 0) {
+                    8 => value.swap_bytes(),
+                    _ => (value as u32).swap_bytes() as u64,
+                };
+                self.write_operand(inst, 0, value)?;
+            }
+            m @ (Mnemonic::Add
+            | Mnemonic::Sub
+            | Mnemonic::Cmp
+            | Mnemonic::And
+            | Mnemonic::Or
+            | Mnemonic::Xor
+            | Mnemonic::Test) => {
+                let size = operand_size(inst, 0);
+                let mask = mask(size);
+                let a = self.read_operand(inst, 0)?;
+                let b = self.read_operand(inst, 1)? & mask;
+                let (result, cf, of) = match m {
+                    Mnemonic::Add => {
+                        let r = a.wrapping_add(b) & mask;
+                        (r, r < a, sign(!(a ^ b) & (a ^ r), size))
+                    }
+                    Mnemonic::Sub | Mnemonic::Cmp => {
+                        let r = a.wrapping_sub(b) & mask;
+                        (r, a < b, sign((a ^ b) & (a ^ r), size))
+                    }
+                    Mnemonic::Or => (a | b, false, false),
+                    Mnemonic::Xor => (a ^ b, false, false),
+                    _ => (a & b, false, false),
+                };
+                self.set_flags(result, size, cf, of);
+                if !matches!(m, Mnemonic::Cmp | Mnemonic::Test) {
+                    self.write_operand(inst, 0, result)?;
+                }
+            }
+            m @ (Mnemonic::Inc | Mnemonic::Dec) => {
+                let size = operand_size(inst, 0);
+                let a = self.read_operand(inst, 0)?;
+                let result = match m {
+                    Mnemonic::Inc => a.wrapping_add(1),
+                    _ => a.wrapping_sub(1),
+                } & mask(size);
+                // overflow from the largest positive to the smallest negative value or back
+                let sign_bit = 1 << (size * 8 - 1);
+                let of = match m {
+                    Mnemonic::Inc => result == sign_bit,
+                    _ => a == sign_bit,
+                };
+                self.set_flags(result, size, self.flags.cf, of);
+                self.write_operand(inst, 0, result)?;
+            }
+            Mnemonic::Neg => {
+                let size = operand_size(inst, 0);
+                let a = self.read_operand(inst, 0)?;
+                let result = a.wrapping_neg() & mask(size);
+                self.set_flags(result, size, a != 0, a != 0 && a == result);
+                self.write_operand(inst, 0, result)?;
+            }
+            Mnemonic::Not => {
+                let size = operand_size(inst, 0);
+                let value = !self.read_operand(inst, 0)? & mask(size);
+                self.write_operand(inst, 0, value)?;
+            }
+            m @ (Mnemonic::Shl | Mnemonic::Shr | Mnemonic::Sar | Mnemonic::Rol | Mnemonic::Ror) => {
+                let size = operand_size(inst, 0);
+                let bits = size as u32 * 8;
+                let a = self.read_operand(inst, 0)?;
+                let count = (self.read_operand(inst, 1)? & if size == 8 { 63 } else { 31 }) as u32;
+                if count != 0 {
+                    let rotate = count % bits;
+                    let result = match m {
+                        Mnemonic::Shl => a.checked_shl(count).unwrap_or(0),
+                        Mnemonic::Shr => a >> count,
+                        Mnemonic::Sar => (sign_extend(a, size) as i64 >> count) as u64,
+                        Mnemonic::Rol => a << rotate | a.checked_shr(bits - rotate).unwrap_or(0),
+                        _ => a >> rotate | a.checked_shl(bits - rotate).unwrap_or(0),
+                    } & mask(size);
+                    if matches!(m, Mnemonic::Shl | Mnemonic::Shr | Mnemonic::Sar) {
+                        self.set_flags(result, size, self.flags.cf, self.flags.of);
+                    }
+                    self.write_operand(inst, 0, result)?;
+                }
+            }
+            Mnemonic::Imul => {
+                let size = operand_size(inst, 0);
+                let (a, b) = match inst.op_count() {
+                    2 => (self.read_operand(inst, 0)?, self.read_operand(inst, 1)?),
+                    3 => (self.read_operand(inst, 1)?, self.read_operand(inst, 2)?),
+                    _ => bail_out!(format!("unsupported one operand imul at {:#x}", self.rip)),
+                };
+                let result =
+                    (sign_extend(a, size) as i64).wrapping_mul(sign_extend(b, size) as i64);
+                self.write_operand(inst, 0, result as u64 & mask(size))?;
+            }
+            Mnemonic::Push => {
+                let value = self.read_operand(inst, 0)?;
+                self.push(value);
+            }
+            Mnemonic::Pop => {
+                let value = self.pop()?;
+                self.write_operand(inst, 0, value)?;
+            }
+            Mnemonic::Call => {
+                let target = self.branch_target(inst)?;
+                self.push(next as u64);
+                self.depth += 1;
+                next = target;
+            }
+            Mnemonic::Ret => {
+                next = self.pop()? as usize;
+                if inst.op_count() == 1 {
+                    self.registers[Register::RSP.number()] += inst.immediate(0);
+                }
+                self.depth = self.depth.saturating_sub(1);
+            }
+            Mnemonic::Jmp => {
+                next = self.branch_target(inst)?;
+            }
+            _ if inst.flow_control() == FlowControl::ConditionalBranch => {
+                if self.condition(inst.condition_code())? {
+                    next = inst.near_branch_target() as usize;
+                }
+            }
+            m => bail_out!(format!("unsupported instruction {m:?} at {:#x}", self.rip)),
+        }
+        self.rip = next;
+        Ok(())
+    }
+
+    fn set_flags(&mut self, result: u64, size: usize, cf: bool, of: bool) {
+        self.flags = Flags {
+            zf: result & mask(size) == 0,
+            sf: sign(result, size),
+            cf,
+            of,
+        };
+    }
+    fn condition(&self, condition: ConditionCode) -> Result<bool> {
+        let Flags { zf, sf, cf, of } = self.flags;
+        Ok(match condition {
+            ConditionCode::o => of,
+            ConditionCode::no => !of,
+            ConditionCode::b => cf,
+            ConditionCode::ae => !cf,
+            ConditionCode::e => zf,
+            ConditionCode::ne => !zf,
+            ConditionCode::be => cf || zf,
+            ConditionCode::a => !cf && !zf,
+            ConditionCode::s => sf,
+            ConditionCode::ns => !sf,
+            ConditionCode::l => sf != of,
+            ConditionCode::ge => sf == of,
+            ConditionCode::le => zf || sf != of,
+            ConditionCode::g => !zf && sf == of,
+            c => bail_out!(format!("unsupported condition {c:?} at {:#x}", self.rip)),
+        })
+    }
+    fn push(&mut self, value: u64) {
+        let rsp = Register::RSP.number();
+        self.registers[rsp] = self.registers[rsp].wrapping_sub(8);
+        self.write(self.registers[rsp] as usize, 8, value);
+    }
+    fn pop(&mut self) -> Result<u64> {
+        let rsp = Register::RSP.number();
+        let value = self.read(self.registers[rsp] as usize, 8)?;
+        self.registers[rsp] = self.registers[rsp].wrapping_add(8);
+        Ok(value)
+    }
+    fn branch_target(&self, inst: &Instruction) -> Result<usize> {
+        Ok(match inst.op0_kind() {
+            OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64 => {
+                inst.near_branch_target() as usize
+            }
+            _ => self.read_operand(inst, 0)? as usize,
+        })
+    }
+    fn memory_address(&self, inst: &Instruction) -> Result<usize> {
+        if matches!(inst.memory_segment(), Register::FS | Register::GS) {
+            bail_out!(format!("unsupported segment access at {:#x}", self.rip));
+        }
+        if inst.is_ip_rel_memory_operand() {
+            return Ok(inst.ip_rel_memory_address() as usize);
+        }
+        let mut address = inst.memory_displacement64();
+        if inst.memory_base() != Register::None {
+            address = address.wrapping_add(self.register(inst.memory_base())?);
+        }
+        if inst.memory_index() != Register::None {
+            let index = self.register(inst.memory_index())?;
+            address = address.wrapping_add(index * inst.memory_index_scale() as u64);
+        }
+        Ok(address as usize)
+    }
+    fn read_operand(&self, inst: &Instruction, operand: u32) -> Result<u64> {
+        Ok(match inst.op_kind(operand) {
+            OpKind::Register => self.register(inst.op_register(operand))?,
+            OpKind::Memory => self.read(self.memory_address(inst)?, inst.memory_size().size())?,
+            OpKind::Immediate8
+            | OpKind::Immediate16
+            | OpKind::Immediate32
+            | OpKind::Immediate64
+            | OpKind::Immediate8to16
+            | OpKind::Immediate8to32
+            | OpKind::Immediate8to64
+            | OpKind::Immediate32to64 => inst.immediate(operand),
+            kind => bail_out!(format!("unsupported operand {kind:?} at {:#x}", self.rip)),
+        })
+    }
+    fn write_operand(&mut self, inst: &Instruction, operand: u32, value: u64) -> Result<()> {
+        match inst.op_kind(operand) {
+            OpKind::Register => self.set_register(inst.op_register(operand), value)?,
+            OpKind::Memory => {
+                let address = self.memory_address(inst)?;
+                self.write(address, inst.memory_size().size(), value);
+            }
+            kind => bail_out!(format!("unsupported operand {kind:?} at {:#x}", self.rip)),
+        }
+        Ok(())
+    }
+}
+
+fn stack_range() -> Range<usize> {
+    STACK_BASE - STACK_SIZE..STACK_BASE
+}
+fn gpr_index(register: Register) -> Result<usize> {
+    let full = register.full_register();
+    if !full.is_gpr64() {
+        bail_out!(format!("unsupported register {register:?}"));
+    }
+    Ok(full.number())
+}
+fn is_high_byte(register: Register) -> bool {
+    matches!(
+        register,
+        Register::AH | Register::CH | Register::DH | Register::BH
+    )
+}
+fn operand_size(inst: &Instruction, operand: u32) -> usize {
+    match inst.op_kind(operand) {
+        OpKind::Register => inst.op_register(operand).size(),
+        OpKind::Memory => inst.memory_size().size(),
+        _ => 8,
+    }
+}
+fn mask(size: usize) -> u64 {
+    if size >= 8 {
+        u64::MAX
+    } else {
+        (1 << (size * 8)) - 1
+    }
+}
+fn sign(value: u64, size: usize) -> bool {
+    (value >> (size * 8 - 1)) & 1 == 1
+}
+fn sign_extend(value: u64, size: usize) -> u64 {
+    let shift = 64 - size as u32 * 8;
+    (((value << shift) as i64) >> shift) as u64
+}
+
+#[cfg(all(test, feature = "image-pe"))]
+mod test {
+    use object::SectionKind;
+
+    use super::*;
+    use crate::testing::TestImageBuilder;
+
+    /// rip-relative displacement from the end of an instruction at `next` to `target`
+    fn rel(next: usize, target: usize) -> [u8; 4] {
+        (target.wrapping_sub(next) as u32).to_le_bytes()
+    }
+
+    #[test]
+    fn test_decrypt_global() {
+        let base = 0x140000000;
+        let f = base + 0x1000;
+        let key = base + 0x2000;
+        let out = key + 8;
+        let encrypted: u64 = 0x1234_5678_9abc_def0;
+
+        let mut code = vec![0x48, 0x8b, 0x05]; // mov rax, [key]
+        code.extend(rel(f + 7, key));
+        code.extend([0x48, 0x35, 0x5a, 0x5a, 0x5a, 0x5a]); // xor rax, 0x5a5a5a5a
+        code.extend([0x48, 0xc1, 0xc0, 0x0d]); // rol rax, 13
+        code.extend([0x48, 0x89, 0x05]); // mov [out], rax
+        code.extend(rel(f + 0x18, out));
+        code.push(0xc3); // ret
+
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, f, 0x1000)
+            .section(".data", SectionKind::Data, key, 0x1000)
+            .write(f, &code)
+            .write(key, &encrypted.to_le_bytes())
+            .function(f..f + code.len())
+            .build()
+            .unwrap();
+        let expected = (encrypted ^ 0x5a5a5a5a).rotate_left(13);
+
+        let mut emulator = Emulator::new(&image, f);
+        assert_eq!(
+            emulator.run(&[StopCondition::Store]).unwrap(),
+            StopCondition::Store
+        );
+        assert_eq!(emulator.stores(), [(out, 8, expected)]);
+
+        let mut emulator = Emulator::new(&image, f);
+        emulator.run(&[StopCondition::Return]).unwrap();
+        assert_eq!(emulator.rip(), f + 0x18);
+        assert_eq!(emulator.register(Register::RAX).unwrap(), expected);
+        assert_eq!(emulator.read(out, 8).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_loop_and_call() {
+        let base = 0x140000000;
+        let g = base + 0x1000;
+        let h = base + 0x1100;
+
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, g, 0x1000)
+            .write(
+                g,
+                &[
+                    0x31, 0xc9, // xor ecx, ecx
+                    0xff, 0xc1, // inc ecx
+                    0x83, 0xf9, 0x05, // cmp ecx, 5
+                    0x75, 0xf9, // jne g+2
+                    0xe8, 0xf2, 0x00, 0x00, 0x00, // call h
+                    0xc3, // ret
+                ],
+            )
+            .write(h, &[0x8d, 0x04, 0x09, 0xc3]) // lea eax, [rcx+rcx]; ret
+            .function(g..g + 0xf)
+            .function(h..h + 4)
+            .build()
+            .unwrap();
+
+        let mut emulator = Emulator::new(&image, g);
+        emulator.set_register(Register::RAX, u64::MAX).unwrap();
+        assert_eq!(
+            emulator.run(&[StopCondition::Call]).unwrap(),
+            StopCondition::Call
+        );
+        assert_eq!(emulator.rip(), g + 9);
+        assert_eq!(emulator.register(Register::ECX).unwrap(), 5);
+
+        emulator.run(&[StopCondition::Return]).unwrap();
+        assert_eq!(emulator.rip(), g + 0xe);
+        assert_eq!(emulator.register(Register::RAX).unwrap(), 10);
+        assert!(emulator.stores().is_empty());
+
+        let mut emulator = Emulator::new(&image, g).with_max_steps(5);
+        assert!(emulator.run(&[StopCondition::Return]).is_err());
+    }
+}
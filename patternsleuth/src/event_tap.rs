@@ -0,0 +1,285 @@
+//! Subscriptions to and capture of `UObject::ProcessEvent` calls (see
+//! [`UObjectProcessEvent`]). The hook itself is installed by the injected tool which forwards
+//! every call to [`EventTap::process`].
+//!
+//! Class and function names are only requested from the hook when something is listening, so an
+//! idle tap costs one atomic load per call.
+//!
+//! [`UObjectProcessEvent`]: crate::resolvers::unreal::process_event::UObjectProcessEvent
+
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+};
+
+/// A single ProcessEvent call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Sequence number of the call, counting all calls seen while the tap was active
+    pub index: u64,
+    pub object: usize,
+    pub function: usize,
+    pub params: usize,
+    /// Name of the class of `object`
+    pub class: Arc<str>,
+    /// Path name of `function`, e.g. `/Script/Engine.Actor:ReceiveTick`
+    pub function_name: Arc<str>,
+}
+
+/// Case insensitive `*` and `?` wildcard match
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_ascii_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` and the text position it currently matches up to
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Selects events by wildcard patterns (`*` and `?`) on the class and function names. Empty
+/// patterns match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    pub class: String,
+    pub function: String,
+}
+impl EventFilter {
+    pub fn new(class: impl Into<String>, function: impl Into<String>) -> Self {
+        Self {
+            class: class.into(),
+            function: function.into(),
+        }
+    }
+    pub fn matches(&self, class: &str, function: &str) -> bool {
+        (self.class.is_empty() || wildcard_match(&self.class, class))
+            && (self.function.is_empty() || wildcard_match(&self.function, function))
+    }
+}
+
+/// Handle returned by [`EventTap::subscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Callback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+struct Capture {
+    filter: EventFilter,
+    capacity: usize,
+    events: VecDeque<Event>,
+}
+
+#[derive(Default)]
+pub struct EventTap {
+    subscribers: RwLock<Vec<(SubscriptionId, EventFilter, Callback)>>,
+    capture: Mutex<Option<Capture>>,
+    /// Whether there are any subscribers or a capture is running
+    active: AtomicBool,
+    next_id: AtomicU64,
+    calls: AtomicU64,
+}
+impl EventTap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update_active(&self) {
+        let active =
+            !self.subscribers.read().unwrap().is_empty() || self.capture.lock().unwrap().is_some();
+        self.active.store(active, Ordering::Relaxed);
+    }
+    /// Whether any call would be delivered or captured. Hooks can check this to skip work.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Call `callback` for every event matching `filter` until unsubscribed. Callbacks run on
+    /// the thread calling ProcessEvent, usually the game thread.
+    pub fn subscribe(
+        &self,
+        filter: EventFilter,
+        callback: impl Fn(&Event) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subscribers
+            .write()
+            .unwrap()
+            .push((id, filter, Arc::new(callback)));
+        self.update_active();
+        id
+    }
+    /// Returns whether the subscription existed
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self.subscribers.write().unwrap();
+        let len = subscribers.len();
+        subscribers.retain(|(i, _, _)| *i != id);
+        let removed = subscribers.len() != len;
+        drop(subscribers);
+        self.update_active();
+        removed
+    }
+
+    /// Start recording events matching `filter` into a ring buffer keeping the most recent
+    /// `capacity` events. Replaces any running capture.
+    pub fn start_capture(&self, filter: EventFilter, capacity: usize) {
+        *self.capture.lock().unwrap() = Some(Capture {
+            filter,
+            capacity,
+            events: VecDeque::with_capacity(capacity.min(0x10000)),
+        });
+        self.update_active();
+    }
+    /// Stop capturing, returning the captured events
+    pub fn stop_capture(&self) -> Vec<Event> {
+        let capture = self.capture.lock().unwrap().take();
+        self.update_active();
+        capture.map(|c| c.events.into()).unwrap_or_default()
+    }
+    pub fn is_capturing(&self) -> bool {
+        self.capture.lock().unwrap().is_some()
+    }
+    /// Events captured so far, oldest first
+    pub fn captured(&self) -> Vec<Event> {
+        self.capture
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|c| c.events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    /// Write captured events to `writer`, one per line
+    pub fn dump(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for event in self.captured() {
+            writeln!(
+                writer,
+                "{:>10} {:<40} {} object={:#x} params={:#x}",
+                event.index, event.class, event.function_name, event.object, event.params
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Forward a ProcessEvent call. `names` returns the class name of `object` and the path
+    /// name of `function` and is only called while the tap is active.
+    pub fn process(
+        &self,
+        object: usize,
+        function: usize,
+        params: usize,
+        names: impl FnOnce() -> (Arc<str>, Arc<str>),
+    ) {
+        if !self.is_active() {
+            return;
+        }
+        let (class, function_name) = names();
+        let event = Event {
+            index: self.calls.fetch_add(1, Ordering::Relaxed),
+            object,
+            function,
+            params,
+            class,
+            function_name,
+        };
+
+        // clone callbacks so they can (un)subscribe without deadlocking
+        let callbacks = self
+            .subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, filter, _)| filter.matches(&event.class, &event.function_name))
+            .map(|(_, _, callback)| callback.clone())
+            .collect::<Vec<_>>();
+        for callback in callbacks {
+            callback(&event);
+        }
+
+        if let Some(capture) = self.capture.lock().unwrap().as_mut() {
+            if capture.capacity > 0 && capture.filter.matches(&event.class, &event.function_name) {
+                if capture.events.len() == capture.capacity {
+                    capture.events.pop_front();
+                }
+                capture.events.push_back(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("BP_*_C", "bp_enemy_c"));
+        assert!(wildcard_match(
+            "*:Receive*",
+            "/Script/Engine.Actor:ReceiveTick"
+        ));
+        assert!(wildcard_match("Tick?", "Tick2"));
+        assert!(!wildcard_match("Tick?", "Tick"));
+        assert!(!wildcard_match("*Tick", "TickComponent"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+    }
+
+    #[test]
+    fn test_event_tap() {
+        let tap = EventTap::new();
+        let mut named = false;
+        tap.process(1, 2, 3, || {
+            named = true;
+            ("Actor".into(), "/Script/Engine.Actor:ReceiveTick".into())
+        });
+        assert!(!named, "names requested while inactive");
+
+        let seen = Arc::new(AtomicU64::new(0));
+        let id = tap.subscribe(EventFilter::new("", "*Tick"), {
+            let seen = seen.clone();
+            move |_| {
+                seen.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        tap.start_capture(EventFilter::new("BP_*", ""), 2);
+
+        for (class, function) in [
+            ("BP_Enemy_C", "/Game/BP_Enemy.BP_Enemy_C:ReceiveTick"),
+            ("Actor", "/Script/Engine.Actor:ReceiveTick"),
+            ("BP_Door_C", "/Game/BP_Door.BP_Door_C:Open"),
+            ("BP_Door_C", "/Game/BP_Door.BP_Door_C:Close"),
+        ] {
+            tap.process(0, 0, 0, || (class.into(), function.into()));
+        }
+        assert_eq!(seen.load(Ordering::Relaxed), 2);
+
+        let captured = tap.stop_capture();
+        assert_eq!(
+            captured.iter().map(|e| e.index).collect::<Vec<_>>(),
+            [2, 3],
+            "ring buffer keeps the most recent matching events"
+        );
+
+        assert!(tap.is_active());
+        assert!(tap.unsubscribe(id));
+        assert!(!tap.is_active());
+    }
+}
@@ -0,0 +1,519 @@
+//! Registry of named hooks for injected tools. Hooks are registered once with a [`HookControl`]
+//! implementation (usually wrapping a detour) and can then be enabled, disabled and inspected at
+//! runtime by name, e.g. from a GUI or a console.
+//!
+//! [`detect_patch`] checks whether a function was already hooked by someone else before
+//! installing a hook on it.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc, RwLock,
+};
+
+use anyhow::{bail, Context, Result};
+use iced_x86::{Code, Decoder, DecoderOptions, Instruction, Mnemonic, OpKind, Register};
+
+use crate::{ue::ReadMemory, MemoryAccessError};
+
+/// Installs and removes a single hook
+pub trait HookControl: Send + Sync {
+    fn enable(&self) -> Result<()>;
+    fn disable(&self) -> Result<()>;
+}
+
+/// A hook argument recorded as a register sized word
+pub trait RawArg: Copy {
+    fn raw(self) -> usize;
+}
+impl<T> RawArg for *const T {
+    fn raw(self) -> usize {
+        self as usize
+    }
+}
+impl<T> RawArg for *mut T {
+    fn raw(self) -> usize {
+        self as usize
+    }
+}
+impl RawArg for bool {
+    fn raw(self) -> usize {
+        self as usize
+    }
+}
+impl RawArg for f32 {
+    fn raw(self) -> usize {
+        self.to_bits() as usize
+    }
+}
+impl RawArg for f64 {
+    fn raw(self) -> usize {
+        self.to_bits() as usize
+    }
+}
+macro_rules! impl_raw_arg_int {
+    ($($ty:ty),*) => {
+        $(impl RawArg for $ty {
+            fn raw(self) -> usize {
+                self as usize
+            }
+        })*
+    };
+}
+impl_raw_arg_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Maximum number of arguments kept by [`Hook::record`]
+pub const MAX_RECORDED_ARGS: usize = 6;
+
+/// Arguments of a hooked call, a single [`RawArg`] or a tuple of up to [`MAX_RECORDED_ARGS`]
+pub trait RawArgs {
+    /// Write the arguments to `words`, returning their number
+    fn raw_args(self, words: &mut [usize; MAX_RECORDED_ARGS]) -> usize;
+}
+impl<A: RawArg> RawArgs for A {
+    fn raw_args(self, words: &mut [usize; MAX_RECORDED_ARGS]) -> usize {
+        words[0] = self.raw();
+        1
+    }
+}
+macro_rules! impl_raw_args_tuple {
+    ($($arg:ident: $index:tt),*) => {
+        impl<$($arg: RawArg),*> RawArgs for ($($arg,)*) {
+            fn raw_args(self, words: &mut [usize; MAX_RECORDED_ARGS]) -> usize {
+                $(words[$index] = self.$index.raw();)*
+                [$($index),*].len()
+            }
+        }
+    };
+}
+impl_raw_args_tuple!(A: 0, B: 1);
+impl_raw_args_tuple!(A: 0, B: 1, C: 2);
+impl_raw_args_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_raw_args_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_raw_args_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+/// A registered hook and its call statistics
+pub struct Hook {
+    name: String,
+    enabled: AtomicBool,
+    calls: AtomicU64,
+    last_args: [AtomicUsize; MAX_RECORDED_ARGS],
+    last_arg_count: AtomicUsize,
+    control: Box<dyn HookControl>,
+}
+impl Hook {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+    /// Number of calls recorded with [`Hook::record`]
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+    /// Arguments of the most recent call as hex words. Arguments of calls recorded concurrently
+    /// on other threads may be mixed in.
+    pub fn last_args(&self) -> Option<String> {
+        let words = self
+            .last_args
+            .iter()
+            .take(self.last_arg_count.load(Ordering::Acquire))
+            .map(|word| format!("{:#x}", word.load(Ordering::Relaxed)))
+            .collect::<Vec<_>>();
+        match words.as_slice() {
+            [] => None,
+            [word] => Some(word.clone()),
+            words => Some(format!("({})", words.join(", "))),
+        }
+    }
+    /// Record a call, meant to be called from the hook body. Only stores the raw arguments,
+    /// they are formatted by [`Hook::last_args`].
+    pub fn record(&self, args: impl RawArgs) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let mut words = [0; MAX_RECORDED_ARGS];
+        let count = args.raw_args(&mut words);
+        for (slot, word) in self.last_args.iter().zip(&words[..count]) {
+            slot.store(*word, Ordering::Relaxed);
+        }
+        self.last_arg_count.store(count, Ordering::Release);
+    }
+    pub fn set_enabled(&self, enabled: bool) -> Result<()> {
+        // claim the transition so concurrent callers don't toggle the control twice
+        if self
+            .enabled
+            .compare_exchange(!enabled, enabled, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Ok(());
+        }
+        let result = if enabled {
+            self.control.enable()
+        } else {
+            self.control.disable()
+        };
+        if let Err(err) = result {
+            self.enabled.store(!enabled, Ordering::Release);
+            return Err(err.context(format!("failed to toggle hook {:?}", self.name)));
+        }
+        Ok(())
+    }
+}
+
+/// Named hooks in registration order
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: RwLock<Vec<Arc<Hook>>>,
+}
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Register a disabled hook. Names must be unique.
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        control: impl HookControl + 'static,
+    ) -> Result<Arc<Hook>> {
+        let name = name.into();
+        let mut hooks = self.hooks.write().unwrap();
+        if hooks.iter().any(|h| h.name == name) {
+            bail!("hook {name:?} is already registered");
+        }
+        let hook = Arc::new(Hook {
+            name,
+            enabled: AtomicBool::new(false),
+            calls: AtomicU64::new(0),
+            last_args: Default::default(),
+            last_arg_count: AtomicUsize::new(0),
+            control: Box::new(control),
+        });
+        hooks.push(hook.clone());
+        Ok(hook)
+    }
+    pub fn get(&self, name: &str) -> Option<Arc<Hook>> {
+        self.hooks
+            .read()
+            .unwrap()
+            .iter()
+            .find(|h| h.name == name)
+            .cloned()
+    }
+    pub fn hooks(&self) -> Vec<Arc<Hook>> {
+        self.hooks.read().unwrap().clone()
+    }
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        self.get(name)
+            .with_context(|| format!("no hook named {name:?}"))?
+            .set_enabled(enabled)
+    }
+
+    /// Run a console command, returning the text to display:
+    ///
+    /// - `list`: all hooks with their state and call counts
+    /// - `enable <name>` / `disable <name>`
+    /// - `show <name>`: state, call count and arguments of the last call
+    pub fn execute(&self, command: &str) -> Result<String> {
+        let mut args = command.split_whitespace();
+        let summary = |hook: &Arc<Hook>| {
+            format!(
+                "{} [{}] calls={}",
+                hook.name,
+                if hook.is_enabled() { "on" } else { "off" },
+                hook.calls()
+            )
+        };
+        match (args.next(), args.next(), args.next()) {
+            (Some("list"), None, None) => Ok(self
+                .hooks()
+                .iter()
+                .map(summary)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            (Some(cmd @ ("enable" | "disable")), Some(name), None) => {
+                self.set_enabled(name, cmd == "enable")?;
+                Ok(format!("{cmd}d {name}"))
+            }
+            (Some("show"), Some(name), None) => {
+                let hook = self
+                    .get(name)
+                    .with_context(|| format!("no hook named {name:?}"))?;
+                Ok(format!(
+                    "{}\nlast args: {}",
+                    summary(&hook),
+                    hook.last_args().as_deref().unwrap_or("<none>")
+                ))
+            }
+            _ => bail!(
+                "unknown command {command:?}, expected one of: list, enable <name>, \
+                 disable <name>, show <name>"
+            ),
+        }
+    }
+}
+
+/// How a function entry was patched, see [`detect_patch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchKind {
+    /// `jmp rel`, the trampoline written by most detour libraries
+    Jmp,
+    /// `jmp [rip+disp]` through an absolute address
+    JmpIndirect,
+    /// `mov reg, imm64; jmp reg`
+    JmpRegister,
+    /// `push imm32; ret`, optionally writing the high half with `mov dword [rsp+4], imm32`
+    PushRet,
+    /// `int3` software breakpoint from a debugger or an exception based hook
+    Breakpoint,
+}
+impl std::fmt::Display for PatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Jmp => "jmp",
+            Self::JmpIndirect => "jmp [rip]",
+            Self::JmpRegister => "mov+jmp reg",
+            Self::PushRet => "push+ret",
+            Self::Breakpoint => "int3",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A patched function entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    pub address: usize,
+    pub kind: PatchKind,
+    /// Where the patch finally transfers control to after following relay stubs, if known.
+    /// Usually inside the module that installed the hook.
+    pub destination: Option<usize>,
+}
+
+/// Maximum number of relay stubs followed to find the destination of a patch
+const MAX_RELAYS: usize = 4;
+
+/// Inspect the entry of the function at `address` for known hook stubs. Thunks that legitimately
+/// start with a jump (e.g. incremental linking) are reported as well, so this is only meaningful
+/// for addresses known to be the start of a real function body.
+pub fn detect_patch(
+    mem: &impl ReadMemory,
+    address: usize,
+) -> Result<Option<Patch>, MemoryAccessError> {
+    let Some((kind, mut destination)) = decode_stub(mem, address)? else {
+        return Ok(None);
+    };
+    // detours commonly jump to a relay stub placed near the target which then jumps to the hook
+    for _ in 0..MAX_RELAYS {
+        let Some(next) = destination else {
+            break;
+        };
+        match decode_stub(mem, next) {
+            Ok(Some((kind, relay))) if kind != PatchKind::Breakpoint && relay.is_some() => {
+                destination = relay;
+            }
+            _ => break,
+        }
+    }
+    Ok(Some(Patch {
+        address,
+        kind,
+        destination,
+    }))
+}
+
+fn decode_stub(
+    mem: &impl ReadMemory,
+    address: usize,
+) -> Result<Option<(PatchKind, Option<usize>)>, MemoryAccessError> {
+    // a shorter read succeeds if the function is close to the end of readable memory
+    let bytes = mem
+        .read_vec(address, 32)
+        .or_else(|_| mem.read_vec(address, 16))?;
+    let mut decoder = Decoder::with_ip(64, &bytes, address as u64, DecoderOptions::NONE);
+    let mut next = || {
+        let mut instruction = Instruction::default();
+        decoder
+            .can_decode()
+            .then(|| {
+                decoder.decode_out(&mut instruction);
+                instruction
+            })
+            .filter(|i| !i.is_invalid())
+    };
+    let Some(first) = next() else {
+        return Ok(None);
+    };
+
+    Ok(match first.mnemonic() {
+        Mnemonic::Int3 => Some((PatchKind::Breakpoint, None)),
+        Mnemonic::Jmp => match first.op0_kind() {
+            OpKind::NearBranch64 => {
+                Some((PatchKind::Jmp, Some(first.near_branch_target() as usize)))
+            }
+            OpKind::Memory if first.is_ip_rel_memory_operand() => Some((
+                PatchKind::JmpIndirect,
+                mem.read_ptr(first.ip_rel_memory_address() as usize).ok(),
+            )),
+            _ => None,
+        },
+        Mnemonic::Mov
+            if first.op0_kind() == OpKind::Register && first.op1_kind() == OpKind::Immediate64 =>
+        {
+            let register = first.op0_register();
+            next()
+                .filter(|jmp| {
+                    jmp.mnemonic() == Mnemonic::Jmp
+                        && jmp.op0_kind() == OpKind::Register
+                        && jmp.op0_register() == register
+                })
+                .map(|_| (PatchKind::JmpRegister, Some(first.immediate64() as usize)))
+        }
+        Mnemonic::Push if first.code() == Code::Pushq_imm32 => {
+            let low = first.immediate32() as usize;
+            let mut high = None;
+            let mut instruction = next();
+            if let Some(mov) = instruction.filter(|i| {
+                i.mnemonic() == Mnemonic::Mov
+                    && i.op0_kind() == OpKind::Memory
+                    && i.memory_base() == Register::RSP
+                    && i.memory_displacement64() == 4
+                    && i.op1_kind() == OpKind::Immediate32
+            }) {
+                high = Some(mov.immediate32() as usize);
+                instruction = next();
+            }
+            instruction
+                .filter(|ret| ret.mnemonic() == Mnemonic::Ret)
+                .map(|_| {
+                    let destination = match high {
+                        Some(high) => high << 32 | low,
+                        // the immediate is sign extended when pushed
+                        None => first.immediate32to64() as usize,
+                    };
+                    (PatchKind::PushRet, Some(destination))
+                })
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default, Clone)]
+    struct Flag(Arc<AtomicBool>);
+    impl HookControl for Flag {
+        fn enable(&self) -> Result<()> {
+            self.0.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        fn disable(&self) -> Result<()> {
+            self.0.store(false, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registry() -> Result<()> {
+        let registry = HookRegistry::new();
+        let flag = Flag::default();
+        let hook = registry.register("Tick", flag.clone())?;
+        assert!(registry.register("Tick", Flag::default()).is_err());
+
+        registry.execute("enable Tick")?;
+        assert!(hook.is_enabled() && flag.0.load(Ordering::Relaxed));
+
+        assert_eq!(hook.last_args(), None);
+        hook.record((1u32, 2.0f32, true));
+        hook.record((2usize, 0x10 as *const u8));
+        assert_eq!(hook.calls(), 2);
+        assert_eq!(hook.last_args().as_deref(), Some("(0x2, 0x10)"));
+        hook.record(0x20 as *const u8);
+        assert_eq!(hook.last_args().as_deref(), Some("0x20"));
+        assert_eq!(registry.execute("list")?, "Tick [on] calls=3");
+
+        registry.execute("disable Tick")?;
+        assert!(!flag.0.load(Ordering::Relaxed));
+        assert!(registry.execute("disable Missing").is_err());
+        assert!(registry.execute("frobnicate").is_err());
+        Ok(())
+    }
+
+    /// Memory made of byte snippets at fixed addresses
+    struct Snippets(Vec<(usize, Vec<u8>)>);
+    impl ReadMemory for Snippets {
+        fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+            for (start, data) in &self.0 {
+                if let Some(data) = address
+                    .checked_sub(*start)
+                    .and_then(|offset| data.get(offset..offset + buffer.len()))
+                {
+                    buffer.copy_from_slice(data);
+                    return Ok(());
+                }
+            }
+            Err(MemoryAccessError::MemoryOutOfBoundsError)
+        }
+    }
+
+    fn padded(bytes: &[u8]) -> Vec<u8> {
+        let mut data = bytes.to_vec();
+        data.resize(32, 0xcc);
+        data
+    }
+
+    #[test]
+    fn test_detect_patch() {
+        let f = 0x140001000;
+        let relay = 0x140002000;
+        let hook = 0x7ff612340000usize;
+        let pointer = relay + 0x20;
+
+        let mut jmp = vec![0xe9]; // jmp relay
+        jmp.extend(((relay - (f + 5)) as u32).to_le_bytes());
+        let mut relay_stub = vec![0xff, 0x25, 0x1a, 0x00, 0x00, 0x00]; // jmp [rip+0x1a]
+        relay_stub.resize(0x20, 0xcc);
+        relay_stub.extend(hook.to_le_bytes());
+        relay_stub.resize(0x40, 0xcc);
+
+        let mem = Snippets(vec![(f, padded(&jmp)), (relay, relay_stub)]);
+        assert_eq!(
+            detect_patch(&mem, f).unwrap(),
+            Some(Patch {
+                address: f,
+                kind: PatchKind::Jmp,
+                destination: Some(hook),
+            })
+        );
+        let patch = detect_patch(&mem, relay).unwrap().unwrap();
+        assert_eq!(patch.kind, PatchKind::JmpIndirect);
+        assert_eq!(mem.read_ptr(pointer).unwrap(), hook);
+
+        let mut mov_jmp = vec![0x48, 0xb8]; // mov rax, hook
+        mov_jmp.extend((hook as u64).to_le_bytes());
+        mov_jmp.extend([0xff, 0xe0]); // jmp rax
+        let mem = Snippets(vec![(f, padded(&mov_jmp))]);
+        let patch = detect_patch(&mem, f).unwrap().unwrap();
+        assert_eq!(patch.kind, PatchKind::JmpRegister);
+        assert_eq!(patch.destination, Some(hook));
+
+        let mut push_ret = vec![0x68]; // push low
+        push_ret.extend((hook as u32).to_le_bytes());
+        push_ret.extend([0xc7, 0x44, 0x24, 0x04]); // mov dword [rsp+4], high
+        push_ret.extend(((hook >> 32) as u32).to_le_bytes());
+        push_ret.push(0xc3); // ret
+        let mem = Snippets(vec![(f, padded(&push_ret))]);
+        let patch = detect_patch(&mem, f).unwrap().unwrap();
+        assert_eq!(patch.kind, PatchKind::PushRet);
+        assert_eq!(patch.destination, Some(hook));
+
+        // regular prologue: mov [rsp+8], rbx; push rdi
+        let mem = Snippets(vec![(f, padded(&[0x48, 0x89, 0x5c, 0x24, 0x08, 0x57]))]);
+        assert_eq!(detect_patch(&mem, f).unwrap(), None);
+        let mem = Snippets(vec![(f, padded(&[]))]);
+        assert_eq!(
+            detect_patch(&mem, f).unwrap().map(|p| p.kind),
+            Some(PatchKind::Breakpoint)
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use std::{collections::HashMap, mem, ops::Range};
 
-use crate::{Memory, MemoryAccessError, MemoryTrait, NamedMemorySection, RuntimeFunction};
+use crate::{
+    Memory, MemoryAccessError, MemoryTrait, NamedMemorySection, RuntimeFunction, SectionPermissions,
+};
 
-use super::{Image, ImageType};
+use super::{Image, ImageType, ImageWarning, Overlay};
 use gimli::{BaseAddresses, CieOrFde, EhFrame, EhFrameHdr, NativeEndian, UnwindSection};
 
 #[cfg(feature = "symbols")]
@@ -15,6 +17,8 @@ use object::{
 
 pub struct ElfImage {
     pub functions: Option<Vec<Range<usize>>>,
+    /// See [`Image::build_id`]
+    pub build_id: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -49,6 +53,13 @@ impl ElfImage {
             })
         })
     }
+    pub fn get_parent_function(
+        &self,
+        _: &Image<'_>,
+        _: usize,
+    ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        Ok(None)
+    }
     pub fn get_root_function_range(
         &self,
         _image: &Image<'_>,
@@ -74,6 +85,30 @@ impl ElfImage {
     ) -> Result<Vec<Range<usize>>, MemoryAccessError> {
         Ok(self.functions.as_ref().unwrap().to_vec())
     }
+    pub fn get_root_function_starts(&self, _: &Image<'_>) -> Result<Vec<usize>, MemoryAccessError> {
+        Ok(self
+            .functions
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|f| f.start)
+            .collect())
+    }
+    pub fn build_id(&self, _: &Image<'_>) -> Option<String> {
+        self.build_id.clone()
+    }
+    pub fn pdb_path(&self, _: &Image<'_>) -> Option<String> {
+        None
+    }
+    pub fn overlay(&self, _: &Image<'_>) -> Option<Overlay> {
+        None
+    }
+    pub fn entry_point(&self, _: &Image<'_>) -> Option<usize> {
+        None
+    }
+    pub fn tls_callbacks(&self, _: &Image<'_>) -> Vec<usize> {
+        vec![]
+    }
 }
 
 // read_inner
@@ -86,6 +121,12 @@ impl ElfImage {
         memory: Memory<'data>,
         object: ElfFile64<'data>,
     ) -> Result<Image<'data>, anyhow::Error> {
+        let build_id = object
+            .build_id()
+            .ok()
+            .flatten()
+            .map(|id| id.iter().map(|b| format!("{b:02x}")).collect::<String>());
+
         // start to parse eh_frame
 
         let endian = object.endian();
@@ -239,7 +280,9 @@ impl ElfImage {
             imports: HashMap::default(),
             image_type: ImageType::ElfImage(ElfImage {
                 functions: Some(functions),
+                build_id,
             }),
+            warnings: vec![],
         })
     }
 
@@ -248,6 +291,7 @@ impl ElfImage {
         base_addr: Option<usize>,
         exe_path: Option<P>,
         _cache_functions: bool,
+        tolerant: bool,
         object: object::File<'_>,
     ) -> Result<Image<'_>, anyhow::Error> {
         let base_address = base_addr.unwrap_or(object.relative_address_base() as usize);
@@ -303,6 +347,8 @@ impl ElfImage {
             };
 
             let entrypoint = object.entry();
+            let data = object.data();
+            let mut warnings = vec![];
             let sections = phdrs
                 .iter()
                 .enumerate()
@@ -314,18 +360,40 @@ impl ElfImage {
                     } else {
                         ".text".to_owned()
                     };
-                    NamedMemorySection::new(
+                    let segment_data = match data.get(offset_range.clone()) {
+                        Some(segment_data) => segment_data,
+                        None if tolerant => {
+                            // keep whatever part of the segment is in the file
+                            let end = offset_range.end.min(data.len());
+                            warnings.push(ImageWarning::new(
+                                Some(section_name.clone()),
+                                format!(
+                                    "segment {offset_range:x?} truncated at end of file {:#x}",
+                                    data.len()
+                                ),
+                            ));
+                            &data[offset_range.start.min(end)..end]
+                        }
+                        None => bail!(
+                            "{section_name}: segment {offset_range:x?} is outside of the file"
+                        ),
+                    };
+                    Ok(NamedMemorySection::new(
                         section_name,
                         base_address + segment.p_vaddr as usize,
                         calc_kind(segment.p_flags),
-                        &object.data()[offset_range],
-                    )
+                        SectionPermissions::from_elf_segment_flags(segment.p_flags),
+                        segment_data,
+                    ))
                 })
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>>>()?;
 
             let memory = Memory { sections };
 
-            Self::read_inner_memory(base_address, exe_path, linked, memory, object)
+            let mut image =
+                Self::read_inner_memory(base_address, exe_path, linked, memory, object)?;
+            image.warnings = warnings;
+            Ok(image)
         } else {
             bail!("Not a elf file")
         }
@@ -0,0 +1,120 @@
+//! Queries over the functions of an image: the unwind table of PE images, the symbol ranges of
+//! ELF images.
+//!
+//! A PE function isn't always one contiguous range. The optimizer moves cold blocks out of the
+//! function and gives each of them its own unwind entry, chained to the entry of the code it was
+//! split from. Those chunks are collected into one [`Function`] whose chunks can be separated by
+//! unrelated code.
+
+use std::ops::Range;
+
+use super::Image;
+use crate::{MemoryAccessError, RuntimeFunction};
+
+/// A function with all of its chunks
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// Chunk holding the entry point of the function
+    pub root: RuntimeFunction,
+    /// Every chunk, including `root`, sorted by address
+    pub chunks: Vec<RuntimeFunction>,
+}
+impl Function {
+    pub fn start(&self) -> usize {
+        self.root.range.start
+    }
+    /// From the start of the first chunk to the end of the last one, which includes the code
+    /// between chunks of a split function. Use [`Function::ranges`] for the code of the function
+    /// only.
+    pub fn span(&self) -> Range<usize> {
+        let start = self.chunks.first().map_or(0, |c| c.range.start);
+        let end = self.chunks.last().map_or(0, |c| c.range.end);
+        start..end
+    }
+    /// Address ranges of the chunks with adjacent ones merged
+    pub fn ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for chunk in &self.chunks {
+            match ranges.last_mut() {
+                Some(last) if last.end >= chunk.range.start => {
+                    last.end = last.end.max(chunk.range.end)
+                }
+                _ => ranges.push(chunk.range.clone()),
+            }
+        }
+        ranges
+    }
+    /// Number of bytes of code of the function, excluding gaps between chunks
+    pub fn size(&self) -> usize {
+        self.ranges().iter().map(Range::len).sum()
+    }
+    /// Whether `address` is in one of the chunks, unlike `span().contains(address)`
+    pub fn contains(&self, address: usize) -> bool {
+        self.chunks.iter().any(|c| c.range.contains(&address))
+    }
+    pub fn is_split(&self) -> bool {
+        self.ranges().len() > 1
+    }
+}
+
+/// Function queries of an image, see [`Image::functions`]
+#[derive(Clone, Copy)]
+pub struct FunctionTable<'img, 'data> {
+    image: &'img Image<'data>,
+}
+impl<'img, 'data> FunctionTable<'img, 'data> {
+    pub(crate) fn new(image: &'img Image<'data>) -> Self {
+        Self { image }
+    }
+    /// Chunk containing `address`
+    pub fn chunk_at(&self, address: usize) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        self.image.get_function(address)
+    }
+    /// Function with a chunk containing `address`
+    pub fn function_at(&self, address: usize) -> Result<Option<Function>, MemoryAccessError> {
+        let Some(root) = self.image.get_root_function(address)? else {
+            return Ok(None);
+        };
+        self.function(root).map(Some)
+    }
+    fn function(&self, root: RuntimeFunction) -> Result<Function, MemoryAccessError> {
+        let mut chunks = self.image.get_child_functions(root.range.start)?;
+        chunks.sort_by_key(|c| c.range.start);
+        chunks.dedup();
+        Ok(Function { root, chunks })
+    }
+    /// Chunks from the one containing `address` up to the root chunk of its function, following
+    /// chained unwind info. Empty if `address` isn't in a function.
+    pub fn parent_chain(&self, address: usize) -> Result<Vec<RuntimeFunction>, MemoryAccessError> {
+        let mut chain = vec![];
+        let mut next = self.image.get_function(address)?;
+        while let Some(chunk) = next {
+            next = self.image.get_parent_function(chunk.range.start)?;
+            chain.push(chunk);
+        }
+        Ok(chain)
+    }
+    /// Chunks chained directly or indirectly to the chunk starting at `address`, excluding it
+    pub fn children(&self, address: usize) -> Result<Vec<RuntimeFunction>, MemoryAccessError> {
+        let mut children = self.image.get_child_functions(address)?;
+        children.retain(|c| c.range.start != address);
+        Ok(children)
+    }
+    /// Every function, sorted by start address
+    pub fn all(&self) -> Result<Vec<Function>, MemoryAccessError> {
+        let mut starts = self.image.get_root_function_starts()?;
+        starts.sort();
+        starts
+            .into_iter()
+            .filter_map(|start| self.image.get_function(start).transpose())
+            .map(|root| self.function(root?))
+            .collect()
+    }
+}
+
+impl<'data> Image<'data> {
+    /// Queries over the functions of the image
+    pub fn functions(&self) -> FunctionTable<'_, 'data> {
+        FunctionTable::new(self)
+    }
+}
@@ -1,5 +1,6 @@
 #[cfg(feature = "image-elf")]
 pub mod elf;
+mod functions;
 mod macros;
 #[cfg(feature = "image-pe")]
 pub mod pe;
@@ -13,6 +14,8 @@ use pe::PEImage;
 
 use macros::*;
 
+pub use functions::{Function, FunctionTable};
+
 #[cfg(not(any(feature = "image-pe", feature = "image-elf")))]
 compile_error!("requires at least one of image-pe or image-elf features");
 
@@ -24,15 +27,163 @@ image_type_dispatch! {
 
     @fns {
         fn get_function(address: usize) -> Result<Option<RuntimeFunction>, MemoryAccessError>;
+        fn get_parent_function(address: usize) -> Result<Option<RuntimeFunction>, MemoryAccessError>;
         fn get_root_function(address: usize) -> Result<Option<RuntimeFunction>, MemoryAccessError>;
         fn get_root_function_range(address: usize) -> Result<Option<Range<usize>>, MemoryAccessError>;
         fn get_child_functions(address: usize) -> Result<Vec<RuntimeFunction>, MemoryAccessError>;
         fn get_root_functions() -> Result<Vec<Range<usize>>, MemoryAccessError>;
+        fn get_root_function_starts() -> Result<Vec<usize>, MemoryAccessError>;
+        // identifier of the build the image was read from, the same for every process of one
+        // build, e.g. to key `resolvers::memo::ResolutionMemo`
+        fn build_id() -> Option<String>;
+        // path of the PDB recorded in the CodeView debug record, `None` for ELF images and PE
+        // images without debug info
+        fn pdb_path() -> Option<String>;
+        // data appended to the image file after its sections, see `Overlay`
+        fn overlay() -> Option<Overlay>;
+        // address execution starts at, `None` for ELF images and PE images without one (e.g.
+        // resource only DLLs)
+        fn entry_point() -> Option<usize>;
+        // TLS callbacks in the order the loader calls them, before the entry point
+        fn tls_callbacks() -> Vec<usize>;
     }
 }
 
 pub use _image_type_reflection as image_type_reflection;
 
+/// Data appended to an image file after its last section. The loader doesn't map it so it isn't
+/// part of [`Image::memory`] unless requested with [`ImageBuilder::overlay`]. Shipping
+/// executables sometimes carry their paks this way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlay {
+    /// Offset of the overlay in the image file
+    pub offset: usize,
+    pub size: usize,
+    pub kind: OverlayKind,
+}
+impl Overlay {
+    /// The overlay within the data of the image file it was read from
+    pub fn data<'a>(&self, file: &'a [u8]) -> Option<&'a [u8]> {
+        file.get(self.offset..self.offset + self.size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayKind {
+    /// Unreal Engine pak file
+    Pak,
+    Zip,
+    SevenZip,
+    Unknown,
+}
+impl OverlayKind {
+    /// Identify overlay data by its magic
+    pub fn identify(data: &[u8]) -> Self {
+        const PAK_MAGIC: [u8; 4] = 0x5a6f12e1u32.to_le_bytes();
+        // the size of the pak footer depends on the pak version, its magic is near the end
+        let tail = &data[data.len().saturating_sub(256)..];
+        if data.starts_with(b"PK\x03\x04") {
+            Self::Zip
+        } else if data.starts_with(b"7z\xbc\xaf\x27\x1c") {
+            Self::SevenZip
+        } else if tail.windows(4).any(|w| w == PAK_MAGIC) {
+            Self::Pak
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// Human readable location of an address within an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub address: usize,
+    /// Name of the section containing the address
+    pub section: Option<String>,
+    /// Range of the root function containing the address
+    pub function: Option<Range<usize>>,
+    /// Nearest symbol at or before the address
+    pub symbol: Option<String>,
+    /// Offset of the address from `symbol`
+    pub symbol_offset: usize,
+}
+/// Reason an image is suspected to be packed, encrypted, or virtualized
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObfuscationReport {
+    /// Section name of a known protector
+    KnownProtector {
+        section: String,
+        protector: &'static str,
+    },
+    /// Executable section containing close to random data
+    HighEntropy { section: String, entropy: f64 },
+}
+impl std::fmt::Display for ObfuscationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KnownProtector { section, protector } => {
+                write!(f, "section {section:?} belongs to {protector}")
+            }
+            Self::HighEntropy { section, entropy } => {
+                write!(
+                    f,
+                    "executable section {section:?} has entropy {entropy:.2} bits/byte"
+                )
+            }
+        }
+    }
+}
+
+/// Shannon entropy in bits per byte
+fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for b in data {
+        counts[*b as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|c| **c != 0)
+        .map(|c| {
+            let p = *c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Function found by searching symbols
+#[cfg(feature = "symbols")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolFunction {
+    pub address: usize,
+    pub symbol: symbols::Symbol,
+    /// Full range of the function if it is known from exception data
+    pub range: Option<Range<usize>>,
+}
+
+impl std::fmt::Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}", self.address)?;
+        if let Some(section) = &self.section {
+            write!(f, " {section}")?;
+        }
+        if let Some(symbol) = &self.symbol {
+            write!(f, " {symbol}")?;
+            if self.symbol_offset != 0 {
+                write!(f, "+{:#x}", self.symbol_offset)?;
+            }
+        } else if let Some(function) = &self.function {
+            write!(
+                f,
+                " sub_{:x}+{:#x}",
+                function.start,
+                self.address - function.start
+            )?;
+        }
+        Ok(())
+    }
+}
+
 pub struct Image<'data> {
     pub base_address: usize,
     pub memory: Memory<'data>,
@@ -40,6 +191,33 @@ pub struct Image<'data> {
     pub symbols: Option<HashMap<usize, symbols::Symbol>>,
     pub imports: HashMap<String, HashMap<String, usize>>,
     pub image_type: ImageType,
+    /// Parts of the image skipped because they couldn't be read, only collected by
+    /// [`ImageBuilder::tolerant`] builds which would otherwise fail
+    pub warnings: Vec<ImageWarning>,
+}
+
+/// Part of an image which was skipped while reading it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageWarning {
+    /// Name of the section the problem is confined to
+    pub section: Option<String>,
+    pub message: String,
+}
+impl ImageWarning {
+    pub fn new(section: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            section,
+            message: message.into(),
+        }
+    }
+}
+impl std::fmt::Display for ImageWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.section {
+            Some(section) => write!(f, "{section}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
 }
 
 // Type-independent
@@ -49,16 +227,26 @@ impl<'data> Image<'data> {
         data: &'data [u8],
         exe_path: Option<P>,
         cache_functions: bool,
+    ) -> Result<Image<'data>> {
+        Self::read_with(base_addr, data, exe_path, cache_functions, false)
+    }
+    /// See [`ImageBuilder::tolerant`]
+    fn read_with<P: AsRef<Path>>(
+        base_addr: Option<usize>,
+        data: &'data [u8],
+        exe_path: Option<P>,
+        cache_functions: bool,
+        tolerant: bool,
     ) -> Result<Image<'data>> {
         let object = object::File::parse(data)?;
         match object {
             #[cfg(feature = "image-elf")]
             object::File::Elf64(_) => {
-                ElfImage::read_inner(base_addr, exe_path, cache_functions, object)
+                ElfImage::read_inner(base_addr, exe_path, cache_functions, tolerant, object)
             }
             #[cfg(feature = "image-pe")]
             object::File::Pe64(_) => {
-                PEImage::read_inner(base_addr, exe_path, cache_functions, object)
+                PEImage::read_inner(base_addr, exe_path, cache_functions, tolerant, object)
             }
             _ => Err(Error::msg("Unsupported file format")),
         }
@@ -66,6 +254,205 @@ impl<'data> Image<'data> {
     pub fn builder() -> ImageBuilder {
         Default::default()
     }
+    /// Drop sections not matching `filter`, keeping the one holding the exception directory
+    pub fn retain_sections(&mut self, filter: &SectionFilter) {
+        let exception_directory = match &self.image_type {
+            #[cfg(feature = "image-pe")]
+            ImageType::PEImage(pe) if !pe.exception_directory_range.is_empty() => {
+                Some(pe.exception_directory_range.start)
+            }
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+        self.memory.retain_sections(|section| {
+            filter.matches(section)
+                || exception_directory.is_some_and(|address| {
+                    (section.address()..section.address() + section.len()).contains(&address)
+                })
+        });
+    }
+    /// Map the overlay after the last section as a read-only section named `overlay`, so it is
+    /// scanned along with the rest of the image
+    fn map_overlay(&mut self, file: &'data [u8]) {
+        let Some(overlay) = self.overlay() else {
+            return;
+        };
+        let Some(data) = overlay.data(file) else {
+            return;
+        };
+        let end = self
+            .memory
+            .sections()
+            .iter()
+            .map(|s| s.address() + s.len())
+            .max()
+            .unwrap_or(self.base_address);
+        let mut section = NamedMemorySection::new(
+            "overlay".to_string(),
+            end.next_multiple_of(0x10000),
+            object::SectionKind::Other,
+            SectionPermissions::R,
+            data,
+        );
+        section.file_range = Some(overlay.offset..overlay.offset + overlay.size);
+        self.memory.sections.push(section);
+    }
+    /// Find functions whose mangled or demangled symbol name matches `re`
+    #[cfg(feature = "symbols")]
+    pub fn find_function_by_symbol(&self, re: &regex::Regex) -> Vec<SymbolFunction> {
+        self.find_functions_by_symbols(std::slice::from_ref(re))
+            .pop()
+            .unwrap_or_default()
+    }
+    /// Batch version of [`Image::find_function_by_symbol`] which only demangles each symbol once.
+    /// Returns matches for each regex in the same order, sorted by address.
+    #[cfg(feature = "symbols")]
+    pub fn find_functions_by_symbols(&self, res: &[regex::Regex]) -> Vec<Vec<SymbolFunction>> {
+        let mut results = vec![vec![]; res.len()];
+        let Some(symbols) = &self.symbols else {
+            return results;
+        };
+        for (address, symbol) in symbols {
+            let demangled = symbol.demangle();
+            for (re, results) in res.iter().zip(results.iter_mut()) {
+                if re.is_match(&symbol.name) || re.is_match(&demangled) {
+                    results.push(SymbolFunction {
+                        address: *address,
+                        symbol: symbol.clone(),
+                        range: self.get_root_function_range(*address).ok().flatten(),
+                    });
+                }
+            }
+        }
+        for results in &mut results {
+            results.sort_by_key(|f| f.address);
+        }
+        results
+    }
+    /// Detect packed or encrypted code which makes offline pattern scanning pointless. Regular
+    /// x86-64 code sits around 6 bits/byte of entropy while compressed or encrypted data is
+    /// close to 8.
+    pub fn detect_obfuscation(&self) -> Option<ObfuscationReport> {
+        const PROTECTORS: &[(&str, &str)] = &[
+            (".vmp", "VMProtect"),
+            (".themida", "Themida"),
+            (".winlice", "Themida"),
+            (".enigma", "Enigma Protector"),
+            (".arxan", "Arxan"),
+            ("UPX", "UPX"),
+        ];
+        const MAX_ENTROPY: f64 = 7.2;
+
+        for section in self.memory.sections() {
+            if let Some((_, protector)) = PROTECTORS
+                .iter()
+                .find(|(prefix, _)| section.name().starts_with(prefix))
+            {
+                return Some(ObfuscationReport::KnownProtector {
+                    section: section.name().to_string(),
+                    protector,
+                });
+            }
+        }
+        self.memory
+            .sections()
+            .iter()
+            .filter(|s| s.permissions().execute && s.len() >= 0x1000)
+            .find_map(|section| {
+                let entropy = entropy(section.data());
+                (entropy > MAX_ENTROPY).then(|| ObfuscationReport::HighEntropy {
+                    section: section.name().to_string(),
+                    entropy,
+                })
+            })
+    }
+    /// Relative virtual address of `address`, `None` if it lies below the image base
+    pub fn va_to_rva(&self, address: usize) -> Option<usize> {
+        address.checked_sub(self.base_address)
+    }
+    pub fn rva_to_va(&self, rva: usize) -> usize {
+        self.base_address + rva
+    }
+    /// Offset of `address` in the image file. `None` if it isn't backed by file data, e.g. in
+    /// `.bss` or past the raw size of a section.
+    pub fn va_to_file_offset(&self, address: usize) -> Option<usize> {
+        let section = self.memory.get_section_containing(address).ok()?;
+        let file_range = section.file_range()?;
+        let offset = file_range.start + (address - section.address());
+        file_range.contains(&offset).then_some(offset)
+    }
+    /// Address the file `offset` is mapped to, `None` if it isn't part of a section
+    pub fn file_offset_to_va(&self, offset: usize) -> Option<usize> {
+        self.memory.sections().iter().find_map(|section| {
+            let file_range = section.file_range()?;
+            let va = section.address() + offset.checked_sub(file_range.start)?;
+            (file_range.contains(&offset) && va < section.address() + section.len()).then_some(va)
+        })
+    }
+    /// Whether `address` looks like code: inside an executable section and either inside a
+    /// function known from exception data or not on `int3` padding. Leaf functions have no
+    /// exception entry so being outside every known function alone doesn't rule an address out.
+    pub fn is_code(&self, address: usize) -> bool {
+        let Ok(section) = self.memory.get_section_containing(address) else {
+            return false;
+        };
+        if !section.permissions().allows(SectionPermissions::X) {
+            return false;
+        }
+        if matches!(self.get_function(address), Ok(Some(_))) {
+            return true;
+        }
+        section.data()[address - section.address()] != 0xcc
+    }
+    /// Discard addresses which don't look like code, see [`Image::is_code`]
+    pub fn filter_code(&self, addresses: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        addresses
+            .into_iter()
+            .filter(|address| self.is_code(*address))
+            .collect()
+    }
+    /// Describe `address` with its containing section, function, and nearest symbol
+    pub fn annotate(&self, address: usize) -> Annotation {
+        let section = self
+            .memory
+            .get_section_containing(address)
+            .ok()
+            .map(|s| s.name().to_string());
+        let function = self
+            .get_root_function(address)
+            .ok()
+            .flatten()
+            .map(|f| f.range);
+
+        #[allow(unused_mut)]
+        let mut symbol: Option<(usize, String)> = None;
+        #[cfg(feature = "symbols")]
+        if let Some(symbols) = &self.symbols {
+            // prefer the symbol of the containing function, otherwise the closest preceding one
+            symbol = function
+                .as_ref()
+                .and_then(|f| symbols.get(&f.start).map(|s| (f.start, s)))
+                .or_else(|| {
+                    symbols
+                        .iter()
+                        .filter(|(a, _)| **a <= address)
+                        .max_by_key(|(a, _)| **a)
+                        .map(|(a, s)| (*a, s))
+                })
+                .map(|(a, s)| (a, s.demangle()));
+        }
+
+        Annotation {
+            address,
+            section,
+            function,
+            symbol_offset: symbol
+                .as_ref()
+                .map(|(a, _)| address - a)
+                .unwrap_or_default(),
+            symbol: symbol.map(|(_, s)| s),
+        }
+    }
     pub fn resolve<T: Send + Sync>(
         &self,
         resolver: &'static resolvers::ResolverFactory<T>,
@@ -80,6 +467,59 @@ impl<'data> Image<'data> {
         resolvers::resolve_many(self, resolvers)
     }
 
+    /// Addresses and values of every registered resolver keyed by resolver name. See
+    /// [`resolvers::resolve_map`] to run only some.
+    pub fn resolve_map(&self) -> resolvers::ResolvedMap {
+        resolvers::resolve_map(self, &resolvers::resolvers().collect::<Vec<_>>())
+    }
+
+    /// Addresses and values of the selected `resolvers` keyed by name, see
+    /// [`resolvers::resolve_map`]
+    pub fn resolve_map_of(
+        &self,
+        resolvers: &[&'static resolvers::NamedResolver],
+    ) -> resolvers::ResolvedMap {
+        resolvers::resolve_map(self, resolvers)
+    }
+
+    /// Same as [`Image::resolve`] but answers resolvers from `memo` when possible, see
+    /// [`resolvers::memo`]
+    pub fn resolve_memoized<T: Send + Sync>(
+        &self,
+        resolver: &'static resolvers::ResolverFactory<T>,
+        memo: &resolvers::memo::ResolutionMemo,
+    ) -> resolvers::Result<T> {
+        resolvers::resolve_memoized(self, resolver, memo)
+    }
+
+    /// Same as [`Image::resolve_memoized`] but reports progress to `events`
+    pub fn resolve_memoized_with_events<T: Send + Sync>(
+        &self,
+        resolver: &'static resolvers::ResolverFactory<T>,
+        memo: &resolvers::memo::ResolutionMemo,
+        events: resolvers::EventHandler,
+    ) -> resolvers::Result<T> {
+        resolvers::resolve_memoized_with_events(self, resolver, memo, Some(events))
+    }
+
+    /// Same as [`Image::resolve`] but reports progress to `events`
+    pub fn resolve_with_events<T: Send + Sync>(
+        &self,
+        resolver: &'static resolvers::ResolverFactory<T>,
+        events: resolvers::EventHandler,
+    ) -> resolvers::Result<T> {
+        resolvers::resolve_with_events(self, resolver, Some(events))
+    }
+
+    /// Same as [`Image::resolve_many`] but reports progress to `events`
+    pub fn resolve_many_with_events(
+        &self,
+        resolvers: &[fn() -> &'static resolvers::DynResolverFactory],
+        events: resolvers::EventHandler,
+    ) -> Vec<resolvers::Result<std::sync::Arc<dyn resolvers::Resolution>>> {
+        resolvers::resolve_many_with_events(self, resolvers, Some(events))
+    }
+
     pub fn scan<'patterns, S>(
         &self,
         pattern_configs: &'patterns [PatternConfig<S>],
@@ -101,49 +541,60 @@ impl<'data> Image<'data> {
             .collect::<Vec<_>>();
 
         for section in self.memory.sections() {
-            let base_address = section.address();
-            let data = section.data();
-
-            let (pattern_scans, patterns): (Vec<_>, Vec<_>) = scan_queue
+            // group scans by the part of the section they are restricted to so each window is
+            // scanned only once
+            let mut windows: std::collections::BTreeMap<(usize, usize), Vec<&PendingScan>> =
+                Default::default();
+            for scan in scan_queue
                 .iter()
-                .filter_map(|scan| {
-                    scan.scan
-                        .section
-                        .map(|s| s == section.kind())
-                        .unwrap_or(true)
-                        .then(|| {
-                            scan.scan
-                                .scan_type
-                                .get_pattern()
-                                .map(|pattern| (scan, pattern))
-                        })
-                        .flatten()
-                })
-                .unzip();
+                .filter(|scan| scan.scan.matches_section(section))
+            {
+                for window in scan.scan.windows(section) {
+                    windows
+                        .entry((window.start, window.end))
+                        .or_default()
+                        .push(scan);
+                }
+            }
 
-            let (xref_scans, xrefs): (Vec<_>, Vec<_>) = scan_queue
-                .iter()
-                .filter_map(|scan| {
-                    scan.scan
-                        .section
-                        .map(|s| s == section.kind())
-                        .unwrap_or(true)
-                        .then(|| scan.scan.scan_type.get_xref().map(|xref| (scan, xref)))
-                        .flatten()
-                })
-                .unzip();
-
-            let scan_results = scanner::scan_pattern(&patterns, base_address, data)
-                .into_iter()
-                .chain(scanner::scan_xref(&xrefs, base_address, data))
-                .zip(pattern_scans.iter().chain(xref_scans.iter()));
-
-            for (addresses, scan) in scan_results {
-                for address in addresses {
-                    results.push((
-                        &pattern_configs[scan.original_config_index],
-                        Resolution { address },
-                    ));
+            for ((start, end), scans) in windows {
+                let data = &section.data()[start - section.address()..end - section.address()];
+
+                let (pattern_scans, patterns): (Vec<&PendingScan>, Vec<_>) = scans
+                    .iter()
+                    .copied()
+                    .filter_map(|scan| {
+                        scan.scan
+                            .scan_type
+                            .get_pattern()
+                            .map(|pattern| (scan, pattern))
+                    })
+                    .unzip();
+
+                let (xref_scans, xrefs): (Vec<&PendingScan>, Vec<_>) = scans
+                    .iter()
+                    .copied()
+                    .filter_map(|scan| scan.scan.scan_type.get_xref().map(|xref| (scan, xref)))
+                    .unzip();
+
+                let scan_results = scanner::scan_pattern(&patterns, start, data)
+                    .into_iter()
+                    .chain(scanner::scan_xref(&xrefs, start, data))
+                    .zip(pattern_scans.iter().chain(xref_scans.iter()));
+
+                // matches in padding or data between functions are false positives of
+                // patterns meant for code
+                let filter_code = section.permissions().allows(SectionPermissions::X);
+                for (addresses, scan) in scan_results {
+                    for address in addresses {
+                        if filter_code && scan.scan.filter_code && !self.is_code(address) {
+                            continue;
+                        }
+                        results.push((
+                            &pattern_configs[scan.original_config_index],
+                            Resolution { address },
+                        ));
+                    }
                 }
             }
         }
@@ -152,28 +603,146 @@ impl<'data> Image<'data> {
     }
 }
 
+/// Read-only memory map of an image file.
+///
+/// Pages are loaded lazily and shared with the OS page cache, so scanning many large images
+/// doesn't require holding each one in memory. Use with [`ImageBuilder::build_mapped`].
+#[cfg(feature = "mmap")]
+pub struct MappedFile(memmap2::Mmap);
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is read-only but may still observe modifications made to the file by
+        // other processes while mapped
+        Ok(Self(unsafe { memmap2::Mmap::map(&file)? }))
+    }
+}
+#[cfg(feature = "mmap")]
+impl std::ops::Deref for MappedFile {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Sections of an image to load, see [`ImageBuilder::sections`]. A section is loaded if it
+/// matches any of the names or grants the permissions, or if neither is given, as long as it
+/// isn't larger than the maximum size.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SectionFilter {
+    names: Vec<String>,
+    permissions: Option<SectionPermissions>,
+    max_size: Option<usize>,
+}
+impl SectionFilter {
+    /// Load sections named `name`, e.g. ".text" (can be called multiple times)
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+    /// Load sections granting at least `permissions`
+    pub fn permissions(mut self, permissions: SectionPermissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+    /// Skip sections larger than `max_size` bytes, e.g. embedded resources
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+    pub fn matches(&self, section: &NamedMemorySection<'_>) -> bool {
+        let selected = if self.names.is_empty() && self.permissions.is_none() {
+            true
+        } else {
+            self.names.iter().any(|name| name == section.name())
+                || self
+                    .permissions
+                    .is_some_and(|permissions| section.permissions().allows(permissions))
+        };
+        selected && self.max_size.is_none_or(|max| section.len() <= max)
+    }
+}
+
 #[derive(Default)]
 pub struct ImageBuilder {
     functions: bool,
+    base_address: Option<usize>,
+    sections: Option<SectionFilter>,
+    overlay: bool,
+    tolerant: bool,
 }
 pub struct ImageBuilderWithSymbols<P: AsRef<Path>> {
     symbols: Option<P>,
+    #[cfg(feature = "symbols")]
+    symbol_providers: Option<symbols::SymbolProviders>,
     functions: bool,
+    base_address: Option<usize>,
+    sections: Option<SectionFilter>,
+    overlay: bool,
+    tolerant: bool,
 }
 impl ImageBuilder {
     pub fn functions(mut self, functions: bool) -> Self {
         self.functions = functions;
         self
     }
+    /// Load the image at `base_address` instead of its preferred base, applying base
+    /// relocations so absolute addresses (vtables, GNatives, etc.) match a running instance
+    /// loaded there with ASLR. Only PE images are relocated.
+    pub fn base_address(mut self, base_address: usize) -> Self {
+        self.base_address = Some(base_address);
+        self
+    }
+    /// Only load sections matching `filter`, e.g. to skip hundreds of MB of embedded assets
+    /// when only code and read-only data are scanned. The section holding the exception
+    /// directory is always kept so function lookups keep working, unwind info of MSVC builds is
+    /// stored in `.rdata` so that is needed as well for chained functions.
+    pub fn sections(mut self, filter: SectionFilter) -> Self {
+        self.sections = Some(filter);
+        self
+    }
+    /// Also load the [`Overlay`] of the image as a section named `overlay`. It is left out by
+    /// default since it is usually an embedded pak, which is large and holds no code.
+    pub fn overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+    /// Skip sections and exception entries which can't be read instead of failing the whole
+    /// build, recording them in [`Image::warnings`]. Some protected executables ship with
+    /// deliberately corrupted ranges that are only repaired at runtime.
+    pub fn tolerant(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
     #[cfg(feature = "symbols")]
     pub fn symbols<P: AsRef<Path>>(self, exe_path: P) -> ImageBuilderWithSymbols<P> {
         ImageBuilderWithSymbols {
             symbols: Some(exe_path),
+            #[cfg(feature = "symbols")]
+            symbol_providers: None,
             functions: self.functions,
+            base_address: self.base_address,
+            sections: self.sections,
+            overlay: self.overlay,
+            tolerant: self.tolerant,
         }
     }
     pub fn build(self, data: &[u8]) -> Result<Image<'_>> {
-        Image::read::<&str>(None, data, None, self.functions)
+        let mut image =
+            Image::read_with::<&str>(self.base_address, data, None, self.functions, self.tolerant)?;
+        if self.overlay {
+            image.map_overlay(data);
+        }
+        if let Some(filter) = &self.sections {
+            image.retain_sections(filter);
+        }
+        Ok(image)
+    }
+    /// Build from a memory mapped file. Sections borrow directly from the map.
+    #[cfg(feature = "mmap")]
+    pub fn build_mapped(self, file: &MappedFile) -> Result<Image<'_>> {
+        self.build(file)
     }
 }
 impl<P: AsRef<Path>> ImageBuilderWithSymbols<P> {
@@ -181,12 +750,76 @@ impl<P: AsRef<Path>> ImageBuilderWithSymbols<P> {
         self.functions = functions;
         self
     }
+    /// See [`ImageBuilder::base_address`]
+    pub fn base_address(mut self, base_address: usize) -> Self {
+        self.base_address = Some(base_address);
+        self
+    }
+    /// See [`ImageBuilder::sections`]
+    pub fn sections(mut self, filter: SectionFilter) -> Self {
+        self.sections = Some(filter);
+        self
+    }
+    /// See [`ImageBuilder::overlay`]
+    pub fn overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+    /// See [`ImageBuilder::tolerant`]
+    pub fn tolerant(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
     #[cfg(feature = "symbols")]
     pub fn symbols(mut self, exe_path: P) -> Self {
         self.symbols = Some(exe_path);
         self
     }
+    /// Find the PDB of PE images with `providers` instead of
+    /// [`SymbolProviders::from_env`](symbols::SymbolProviders::from_env)
+    #[cfg(feature = "symbols")]
+    pub fn symbol_providers(mut self, providers: symbols::SymbolProviders) -> Self {
+        self.symbol_providers = Some(providers);
+        self
+    }
     pub fn build(self, data: &[u8]) -> Result<Image<'_>> {
-        Image::read(None, data, self.symbols, self.functions)
+        #[allow(unused_mut)]
+        let mut exe_path = self.symbols;
+        // overridden providers only apply to PDBs, read separately once the image is loaded
+        #[cfg(all(feature = "symbols", feature = "image-pe"))]
+        let pdb = match self.symbol_providers {
+            Some(providers) if matches!(object::File::parse(data)?, object::File::Pe64(_)) => {
+                exe_path.take().map(|exe_path| (providers, exe_path))
+            }
+            _ => None,
+        };
+        let mut image = Image::read_with(
+            self.base_address,
+            data,
+            exe_path,
+            self.functions,
+            self.tolerant,
+        )?;
+        #[cfg(all(feature = "symbols", feature = "image-pe"))]
+        if let Some((providers, exe_path)) = pdb {
+            image.symbols = PEImage::read_symbols(
+                &providers,
+                exe_path.as_ref(),
+                &object::File::parse(data)?,
+                image.base_address,
+            )?;
+        }
+        if self.overlay {
+            image.map_overlay(data);
+        }
+        if let Some(filter) = &self.sections {
+            image.retain_sections(filter);
+        }
+        Ok(image)
+    }
+    /// Build from a memory mapped file. Sections borrow directly from the map.
+    #[cfg(feature = "mmap")]
+    pub fn build_mapped(self, file: &MappedFile) -> Result<Image<'_>> {
+        self.build(file)
     }
 }
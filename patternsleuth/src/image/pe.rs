@@ -4,7 +4,7 @@ use std::ops::Range;
 use anyhow::{bail, Context, Result};
 use itertools::Itertools;
 
-use super::{Image, ImageType};
+use super::{Image, ImageType, ImageWarning, Overlay, OverlayKind};
 #[cfg(feature = "symbols")]
 use crate::symbols;
 use crate::{Memory, MemoryAccessError, MemoryTrait, RuntimeFunction};
@@ -12,19 +12,132 @@ use object::Object;
 
 pub struct PEImage {
     pub exception_directory_range: Range<usize>,
-    pub exception_children_cache: HashMap<usize, Vec<RuntimeFunction>>,
+    /// Chained functions of every function, `None` unless the image was built with
+    /// [`functions(true)`](crate::image::ImageBuilder::functions)
+    pub exception_children_cache: Option<HashMap<usize, Vec<RuntimeFunction>>>,
+    /// See [`Image::build_id`]
+    pub build_id: Option<String>,
+    /// See [`Image::pdb_path`]
+    pub pdb_path: Option<String>,
+    /// See [`Image::overlay`], `None` for images read from process memory
+    pub overlay: Option<Overlay>,
+    /// See [`Image::entry_point`]
+    pub entry_point: Option<usize>,
+    /// See [`Image::tls_callbacks`]
+    pub tls_callbacks: Vec<usize>,
 }
 
 impl PEImage {
+    pub fn build_id(&self, _: &Image<'_>) -> Option<String> {
+        self.build_id.clone()
+    }
+    pub fn pdb_path(&self, _: &Image<'_>) -> Option<String> {
+        self.pdb_path.clone()
+    }
+    pub fn overlay(&self, _: &Image<'_>) -> Option<Overlay> {
+        self.overlay
+    }
+    pub fn entry_point(&self, _: &Image<'_>) -> Option<usize> {
+        self.entry_point
+    }
+    pub fn tls_callbacks(&self, _: &Image<'_>) -> Vec<usize> {
+        self.tls_callbacks.clone()
+    }
+    /// Read the null terminated callback array of the `IMAGE_TLS_DIRECTORY64` at
+    /// `tls_directory`. The array holds absolute addresses, so `memory` must already be
+    /// relocated to the image base.
+    pub fn read_tls_callbacks(
+        memory: &Memory<'_>,
+        tls_directory: usize,
+    ) -> Result<Vec<usize>, MemoryAccessError> {
+        // AddressOfCallBacks
+        let mut address = memory.ptr(tls_directory + 0x18)?;
+        let mut callbacks = vec![];
+        if address == 0 {
+            return Ok(callbacks);
+        }
+        loop {
+            let callback = memory.ptr(address)?;
+            if callback == 0 {
+                return Ok(callbacks);
+            }
+            callbacks.push(callback);
+            address += 8;
+        }
+    }
+    /// Find data appended after the last section, excluding the Authenticode signature which
+    /// is stored there as well
+    pub fn read_overlay(object: &object::File<'_>) -> Option<Overlay> {
+        use object::LittleEndian as LE;
+
+        let object::File::Pe64(inner) = object else {
+            return None;
+        };
+        let data = inner.data();
+        let headers_end = inner.nt_headers().optional_header.size_of_headers.get(LE) as usize;
+        let start = inner
+            .section_table()
+            .iter()
+            .map(|section| {
+                let (offset, size) = section.pe_file_range();
+                (offset + size) as usize
+            })
+            .fold(headers_end, usize::max);
+        let mut end = data.len();
+        if let Some(security) = inner.data_directory(object::pe::IMAGE_DIRECTORY_ENTRY_SECURITY) {
+            // the address of the security directory is a file offset rather than an RVA
+            let offset = security.virtual_address.get(LE) as usize;
+            if (start..end).contains(&offset) {
+                end = offset;
+            }
+        }
+        (start < end).then(|| Overlay {
+            offset: start,
+            size: end - start,
+            kind: OverlayKind::identify(&data[start..end]),
+        })
+    }
+    /// Build identifier made of the COFF timestamp and image size, followed by the CodeView
+    /// GUID and age when the image has debug info
+    pub fn read_build_id(object: &object::File<'_>) -> Option<String> {
+        use object::LittleEndian as LE;
+
+        let object::File::Pe64(inner) = object else {
+            return None;
+        };
+        let headers = inner.nt_headers();
+        let mut id = format!(
+            "{:08X}{:X}",
+            headers.file_header.time_date_stamp.get(LE),
+            headers.optional_header.size_of_image.get(LE)
+        );
+        if let Ok(Some(pdb)) = object.pdb_info() {
+            for b in pdb.guid() {
+                id.push_str(&format!("{b:02X}"));
+            }
+            id.push_str(&format!("{:X}", pdb.age()));
+        }
+        Some(id)
+    }
+    fn children_cache(&self) -> Result<&HashMap<usize, Vec<RuntimeFunction>>, MemoryAccessError> {
+        self.exception_children_cache
+            .as_ref()
+            .ok_or(MemoryAccessError::ExceptionDataUnavailable)
+    }
     pub fn get_function(
         &self,
         image: &Image<'_>,
         address: usize,
     ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        if self.exception_directory_range.is_empty() {
+            return Ok(None);
+        }
         // place holder only
         let size = 12;
         let mut min = 0;
-        let mut max = self.exception_directory_range.len() / size - 1;
+        let Some(mut max) = (self.exception_directory_range.len() / size).checked_sub(1) else {
+            return Ok(None);
+        };
 
         while min <= max {
             let i = (max + min) / 2;
@@ -44,49 +157,63 @@ impl PEImage {
                     min = i + 1;
                 }
             } else {
-                max = i - 1;
+                // `address` is before the first function
+                let Some(prev) = i.checked_sub(1) else {
+                    break;
+                };
+                max = prev;
             }
         }
         Ok(None)
     }
-    pub fn get_root_function(
+    /// The function `address` is chained to by its unwind info, i.e. the code it was split from
+    pub fn get_parent_function(
         &self,
         image: &Image<'_>,
         address: usize,
     ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
-        if let Some(f) = self.get_function(image, address)? {
-            let mut f = RuntimeFunction {
-                range: f.range,
-                unwind: f.unwind,
-            };
-
-            loop {
-                let mut unwind_addr = f.unwind;
+        let Some(f) = self.get_function(image, address)? else {
+            return Ok(None);
+        };
+        let mut unwind_addr = f.unwind;
 
-                let section = image.memory.get_section_containing(unwind_addr)?;
+        let section = image.memory.get_section_containing(unwind_addr)?;
 
-                let has_chain_info = section.section.index(unwind_addr)? >> 3 == 0x4;
-                if has_chain_info {
-                    let unwind_code_count = section.section.index(unwind_addr + 2)?;
+        let has_chain_info = section.section.index(unwind_addr)? >> 3 == 0x4;
+        if !has_chain_info {
+            return Ok(None);
+        }
+        let unwind_code_count = section.section.index(unwind_addr + 2)?;
 
-                    unwind_addr += 4 + 2 * unwind_code_count as usize;
-                    if unwind_addr % 4 != 0 {
-                        // align
-                        unwind_addr += 2;
-                    }
+        unwind_addr += 4 + 2 * unwind_code_count as usize;
+        if unwind_addr % 4 != 0 {
+            // align
+            unwind_addr += 2;
+        }
 
-                    if section.address() + section.data().len() > unwind_addr + 12 {
-                        f = RuntimeFunction::read(section, image.base_address, unwind_addr)?;
-                    } else {
-                        todo!("not adding chain info {unwind_addr}");
-                    }
-                } else {
-                    return Ok(Some(f));
-                }
-            }
+        if section.address() + section.data().len() > unwind_addr + 12 {
+            Ok(Some(RuntimeFunction::read(
+                section,
+                image.base_address,
+                unwind_addr,
+            )?))
         } else {
-            Ok(None)
+            // chained function entry would be read past the end of the section
+            Err(MemoryAccessError::MemoryOutOfBoundsError)
+        }
+    }
+    pub fn get_root_function(
+        &self,
+        image: &Image<'_>,
+        address: usize,
+    ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        let Some(mut f) = self.get_function(image, address)? else {
+            return Ok(None);
+        };
+        while let Some(parent) = self.get_parent_function(image, f.range.start)? {
+            f = parent;
         }
+        Ok(Some(f))
     }
 
     pub fn get_root_function_range(
@@ -96,9 +223,7 @@ impl PEImage {
     ) -> Result<Option<Range<usize>>, MemoryAccessError> {
         let exception = self.get_root_function(image, address)?;
         if let Some(exception) = exception {
-            let fns = self
-                .get_child_functions(image, exception.range.start)
-                .unwrap();
+            let fns = self.get_child_functions(image, exception.range.start)?;
             let min = fns.iter().map(|f| f.range.start).min().unwrap();
             let max = fns.iter().map(|f| f.range.end).max().unwrap();
             if exception.range.start != address {
@@ -108,7 +233,8 @@ impl PEImage {
                     address,
                 ))
             } else {
-                Ok(Some(min..max)) // TODO does not handle sparse ranges
+                // spans the gaps between chunks of split functions, see `FunctionTable`
+                Ok(Some(min..max))
             }
         } else {
             Ok(None)
@@ -120,10 +246,11 @@ impl PEImage {
         image: &Image<'_>,
         address: usize,
     ) -> Result<Vec<RuntimeFunction>, MemoryAccessError> {
+        let cache = self.children_cache()?;
         let mut queue = vec![address];
         let mut all_children = vec![self.get_function(image, address)?.unwrap()];
         while let Some(next) = queue.pop() {
-            if let Some(children) = self.exception_children_cache.get(&next) {
+            if let Some(children) = cache.get(&next) {
                 for child in children {
                     queue.push(child.range().start);
                     all_children.push(child.clone());
@@ -133,23 +260,29 @@ impl PEImage {
         Ok(all_children)
     }
 
-    pub fn get_root_functions(
-        &self,
-        image: &Image<'_>,
-    ) -> Result<Vec<Range<usize>>, MemoryAccessError> {
-        let mut functions = self.exception_children_cache.keys().collect::<HashSet<_>>();
-        for e in self.exception_children_cache.values() {
+    /// Start addresses of the functions which aren't chained to another one
+    pub fn get_root_function_starts(&self, _: &Image<'_>) -> Result<Vec<usize>, MemoryAccessError> {
+        let cache = self.children_cache()?;
+        let mut functions = cache.keys().copied().collect::<HashSet<_>>();
+        for e in cache.values() {
             for c in e {
                 functions.remove(&c.range.start);
             }
         }
-        functions
+        Ok(functions.into_iter().collect())
+    }
+
+    pub fn get_root_functions(
+        &self,
+        image: &Image<'_>,
+    ) -> Result<Vec<Range<usize>>, MemoryAccessError> {
+        self.get_root_function_starts(image)?
             .iter()
             .map(|function| -> Result<Range<usize>, MemoryAccessError> {
                 let fns = self
                     .get_child_functions(
                         image,
-                        self.get_function(image, **function)?
+                        self.get_function(image, *function)?
                             .ok_or(MemoryAccessError::MemoryOutOfBoundsError)?
                             .range
                             .start,
@@ -164,76 +297,117 @@ impl PEImage {
 }
 
 impl Image<'_> {
-    // this function is privately used by pe image
-    fn populate_exception_cache(&mut self) -> Result<(), MemoryAccessError> {
+    // this function is used by pe image and the synthetic test images
+    pub(crate) fn populate_exception_cache(&mut self) -> Result<(), MemoryAccessError> {
+        self.read_exception_cache(false).map(|_| ())
+    }
+    /// Same as [`Image::populate_exception_cache`] but skipping entries whose function or unwind
+    /// info can't be read instead of failing, returns the number of skipped entries
+    pub(crate) fn populate_exception_cache_lossy(&mut self) -> Result<usize, MemoryAccessError> {
+        self.read_exception_cache(true)
+    }
+    fn read_exception_cache(&mut self, skip_invalid: bool) -> Result<usize, MemoryAccessError> {
         #[allow(irrefutable_let_patterns)]
         if let ImageType::PEImage(ref mut pe) = self.image_type {
+            let mut cache: HashMap<usize, Vec<RuntimeFunction>> = HashMap::new();
+            let mut skipped = 0;
             for i in pe.exception_directory_range.clone().step_by(12) {
-                let f = RuntimeFunction::read(&self.memory, self.base_address, i)?;
-                pe.exception_children_cache.insert(f.range.start, vec![]);
-
-                let Ok(section) = self.memory.get_section_containing(f.unwind) else {
-                    // TODO disabled cause spammy
-                    //println!("invalid unwind info addr {:x}", f.unwind);
-                    continue;
-                };
-
-                let mut unwind = f.unwind;
-                let has_chain_info = section.section.index(unwind)? >> 3 == 0x4;
-                if has_chain_info {
-                    let unwind_code_count = section.section.index(unwind + 2)?;
+                match read_exception_entry(&self.memory, self.base_address, i, &mut cache) {
+                    Err(_) if skip_invalid => skipped += 1,
+                    res => res?,
+                }
+            }
 
-                    unwind += 4 + 2 * unwind_code_count as usize;
-                    if unwind % 4 != 0 {
-                        // align
-                        unwind += 2;
-                    }
+            //println!("{:#x?}", self.exception_children_cache);
+            pe.exception_children_cache = Some(cache);
+            Ok(skipped)
+        } else {
+            unreachable!("not a PE image")
+        }
+    }
+}
 
-                    if section.address() + section.data().len() > unwind + 12 {
-                        let chained = RuntimeFunction::read(section, self.base_address, unwind)?;
+/// Add the `RUNTIME_FUNCTION` at `entry` to `cache`, under its parent if the unwind info is
+/// chained
+fn read_exception_entry(
+    memory: &Memory<'_>,
+    base_address: usize,
+    entry: usize,
+    cache: &mut HashMap<usize, Vec<RuntimeFunction>>,
+) -> Result<(), MemoryAccessError> {
+    let f = RuntimeFunction::read(memory, base_address, entry)?;
+    cache.insert(f.range.start, vec![]);
+
+    let Ok(section) = memory.get_section_containing(f.unwind) else {
+        // TODO disabled cause spammy
+        //println!("invalid unwind info addr {:x}", f.unwind);
+        return Ok(());
+    };
+
+    let mut unwind = f.unwind;
+    let has_chain_info = section.section.index(unwind)? >> 3 == 0x4;
+    if has_chain_info {
+        let unwind_code_count = section.section.index(unwind + 2)?;
+
+        unwind += 4 + 2 * unwind_code_count as usize;
+        if unwind % 4 != 0 {
+            // align
+            unwind += 2;
+        }
 
-                        // TODO disabled because it spams the log too much
-                        //let referenced = self.get_function(chained.range.start);
+        if section.address() + section.data().len() > unwind + 12 {
+            let chained = RuntimeFunction::read(section, base_address, unwind)?;
 
-                        //assert_eq!(Some(&chained), referenced.as_ref());
-                        //if Some(&chained) != referenced.as_ref() {
-                        //println!("mismatch {:x?} {referenced:x?}", Some(&chained));
-                        //}
+            // TODO disabled because it spams the log too much
+            //let referenced = self.get_function(chained.range.start);
 
-                        pe.exception_children_cache
-                            .entry(chained.range.start)
-                            .or_default()
-                            .push(f);
-                    } else {
-                        println!("invalid unwind addr {:x}", unwind);
-                    }
-                }
-            }
+            //assert_eq!(Some(&chained), referenced.as_ref());
+            //if Some(&chained) != referenced.as_ref() {
+            //println!("mismatch {:x?} {referenced:x?}", Some(&chained));
+            //}
 
-            //println!("{:#x?}", self.exception_children_cache);
-            Ok(())
+            cache.entry(chained.range.start).or_default().push(f);
         } else {
-            unreachable!("not a PE image")
+            println!("invalid unwind addr {:x}", unwind);
         }
     }
+    Ok(())
 }
 
 impl PEImage {
+    /// Symbols of the image at `exe_path` from the PDB found by `provider`, if any
+    #[cfg(feature = "symbols")]
+    pub(crate) fn read_symbols(
+        provider: &dyn symbols::SymbolProvider,
+        exe_path: &std::path::Path,
+        object: &object::File<'_>,
+        base_address: usize,
+    ) -> Result<Option<HashMap<usize, symbols::Symbol>>> {
+        let info = symbols::PdbInfo::from_object(object);
+        provider
+            .find_pdb(exe_path, info.as_ref())?
+            .map(|pdb_path| {
+                symbols::load_pdb_symbols(exe_path, &pdb_path, info.as_ref(), base_address)
+            })
+            .transpose()
+    }
     /// Read and parse ELF object, using data from memory
     pub fn read_inner_memory<'data, P: AsRef<std::path::Path>>(
         base_address: usize,
         #[allow(unused_variables)] exe_path: Option<P>,
         cache_functions: bool,
+        tolerant: bool,
         memory: Memory<'data>,
         object: object::File<'_>,
     ) -> Result<Image<'data>, anyhow::Error> {
         #[cfg(feature = "symbols")]
         let symbols = if let Some(exe_path) = exe_path {
-            let pdb_path = exe_path.as_ref().with_extension("pdb");
-            pdb_path
-                .exists()
-                .then(|| symbols::dump_pdb_symbols(pdb_path, base_address))
-                .transpose()?
+            Self::read_symbols(
+                &symbols::SymbolProviders::from_env(),
+                exe_path.as_ref(),
+                &object,
+                base_address,
+            )?
         } else {
             None
         };
@@ -261,7 +435,9 @@ impl PEImage {
 
                     let mut imports: HashMap<String, HashMap<String, usize>> = Default::default();
 
-                    let import_table = inner.import_table()?.unwrap();
+                    let Some(import_table) = inner.import_table()? else {
+                        return Ok(Default::default());
+                    };
                     let mut import_descs = import_table.descriptors()?;
 
                     while let Some(import_desc) = import_descs.next()? {
@@ -288,6 +464,30 @@ impl PEImage {
             })
         };
 
+        let (entry_point, tls_callbacks) = match object {
+            object::File::Pe64(ref inner) => {
+                use object::LittleEndian as LE;
+
+                let entry = inner
+                    .nt_headers()
+                    .optional_header
+                    .address_of_entry_point
+                    .get(LE);
+                let tls_callbacks = inner
+                    .data_directory(object::pe::IMAGE_DIRECTORY_ENTRY_TLS)
+                    .and_then(|tls| {
+                        let address = base_address + tls.virtual_address.get(LE) as usize;
+                        Self::read_tls_callbacks(&memory, address).ok()
+                    })
+                    .unwrap_or_default();
+                (
+                    (entry != 0).then(|| base_address + entry as usize),
+                    tls_callbacks,
+                )
+            }
+            _ => (None, vec![]),
+        };
+
         let mut new = Image {
             base_address,
             memory,
@@ -297,10 +497,28 @@ impl PEImage {
             image_type: ImageType::PEImage(PEImage {
                 exception_directory_range: get_ex_dir().unwrap_or_default(),
                 exception_children_cache: Default::default(),
+                build_id: PEImage::read_build_id(&object),
+                pdb_path: object
+                    .pdb_info()
+                    .ok()
+                    .flatten()
+                    .map(|pdb| String::from_utf8_lossy(pdb.path()).into_owned()),
+                overlay: None,
+                entry_point,
+                tls_callbacks,
             }),
+            warnings: vec![],
         };
 
-        if cache_functions {
+        if cache_functions && tolerant {
+            let skipped = new.populate_exception_cache_lossy()?;
+            if skipped != 0 {
+                new.warnings.push(ImageWarning::new(
+                    None,
+                    format!("skipped {skipped} unreadable exception directory entries"),
+                ));
+            }
+        } else if cache_functions {
             new.populate_exception_cache()?;
         }
         Ok(new)
@@ -310,10 +528,88 @@ impl PEImage {
         base_addr: Option<usize>,
         exe_path: Option<P>,
         cache_functions: bool,
+        tolerant: bool,
         object: object::File<'_>,
     ) -> Result<Image<'_>, anyhow::Error> {
-        let base_address = base_addr.unwrap_or(object.relative_address_base() as usize);
-        let memory = Memory::new(&object)?;
-        Self::read_inner_memory(base_address, exe_path, cache_functions, memory, object)
+        let preferred = object.relative_address_base() as usize;
+        let base_address = base_addr.unwrap_or(preferred);
+        let (mut memory, warnings) = if tolerant {
+            Memory::new_tolerant(&object)
+        } else {
+            (Memory::new(&object)?, vec![])
+        };
+        if base_address != preferred {
+            Self::rebase(&mut memory, &object, preferred, base_address)?;
+        }
+        let overlay = Self::read_overlay(&object);
+        let mut image = Self::read_inner_memory(
+            base_address,
+            exe_path,
+            cache_functions,
+            tolerant,
+            memory,
+            object,
+        )?;
+        image.warnings.splice(0..0, warnings);
+        #[allow(irrefutable_let_patterns)]
+        if let ImageType::PEImage(pe) = &mut image.image_type {
+            pe.overlay = overlay;
+        }
+        Ok(image)
+    }
+
+    /// Move sections loaded at `preferred` to `base_address` and apply base relocations the way
+    /// the loader would. Relocated sections are copied.
+    fn rebase(
+        memory: &mut Memory<'_>,
+        object: &object::File<'_>,
+        preferred: usize,
+        base_address: usize,
+    ) -> Result<()> {
+        use object::pe::{
+            IMAGE_REL_BASED_ABSOLUTE, IMAGE_REL_BASED_DIR64, IMAGE_REL_BASED_HIGHLOW,
+        };
+
+        let object::File::Pe64(inner) = object else {
+            bail!("not a PE file");
+        };
+        let delta = base_address.wrapping_sub(preferred);
+        for section in &mut memory.sections {
+            section.section.address = section.section.address.wrapping_add(delta);
+        }
+
+        let Some(mut blocks) = inner
+            .data_directories()
+            .relocation_blocks(inner.data(), &inner.section_table())?
+        else {
+            return Ok(());
+        };
+        while let Some(block) = blocks.next()? {
+            for relocation in block {
+                let size = match relocation.typ {
+                    IMAGE_REL_BASED_ABSOLUTE => continue, // block padding
+                    IMAGE_REL_BASED_DIR64 => 8,
+                    IMAGE_REL_BASED_HIGHLOW => 4,
+                    typ => bail!("unsupported base relocation type {typ}"),
+                };
+                let address = base_address + relocation.virtual_address as usize;
+                let Some(section) = memory.sections.iter_mut().find(|s| {
+                    address >= s.section.address && address + size <= s.section.address + s.len()
+                }) else {
+                    // relocations in zero filled data have nothing to patch
+                    continue;
+                };
+                let offset = address - section.section.address;
+                let data = &mut section.section.data.to_mut()[offset..offset + size];
+                if size == 8 {
+                    let value = u64::from_le_bytes(data.try_into().unwrap());
+                    data.copy_from_slice(&value.wrapping_add(delta as u64).to_le_bytes());
+                } else {
+                    let value = u32::from_le_bytes(data.try_into().unwrap());
+                    data.copy_from_slice(&value.wrapping_add(delta as u32).to_le_bytes());
+                }
+            }
+        }
+        Ok(())
     }
 }
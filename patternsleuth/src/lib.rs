@@ -1,8 +1,19 @@
+pub mod emulate;
+pub mod event_tap;
+pub mod hooks;
 pub mod image;
+#[cfg(feature = "pattern-sets")]
+pub mod pattern_set;
+pub mod pointer_scan;
 pub mod process;
+#[cfg(feature = "pattern-sets")]
+pub mod profile;
 pub mod resolvers;
 #[cfg(feature = "symbols")]
 pub mod symbols;
+#[cfg(feature = "image-pe")]
+pub mod testing;
+pub mod ue;
 #[cfg(feature = "symbols")]
 pub mod uesym;
 
@@ -39,8 +50,37 @@ pub struct Resolution {
 #[derive(Debug, Clone)]
 pub struct Scan {
     pub section: Option<object::SectionKind>,
+    /// Only scan sections which grant at least these permissions
+    pub permissions: Option<SectionPermissions>,
+    /// Only scan these address ranges, scanning everything if empty
+    pub ranges: Vec<Range<usize>>,
+    /// Discard matches in executable sections which don't look like code, see
+    /// [`Image::is_code`]. Matches in other sections are kept.
+    pub filter_code: bool,
     pub scan_type: ScanType,
 }
+impl Scan {
+    /// Whether `section` should be scanned
+    pub fn matches_section(&self, section: &NamedMemorySection<'_>) -> bool {
+        self.section.map(|s| s == section.kind()).unwrap_or(true)
+            && self
+                .permissions
+                .map(|p| section.permissions().allows(p))
+                .unwrap_or(true)
+    }
+    /// Parts of `section` to scan, the entire section unless restricted to [`Scan::ranges`]
+    pub fn windows(&self, section: &NamedMemorySection<'_>) -> Vec<Range<usize>> {
+        let section_range = section.address()..section.address() + section.len();
+        if self.ranges.is_empty() {
+            return vec![section_range];
+        }
+        self.ranges
+            .iter()
+            .map(|r| r.start.max(section_range.start)..r.end.min(section_range.end))
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+}
 #[derive(Debug, Clone)]
 pub enum ScanType {
     Pattern(Pattern),
@@ -71,11 +111,45 @@ impl From<Xref> for ScanType {
     }
 }
 
+/// Number of matches a pattern is expected to produce, see [`PatternConfig::expect`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedMatches {
+    Exactly(usize),
+    AtLeast(usize),
+}
+impl ExpectedMatches {
+    pub fn matches(&self, count: usize) -> bool {
+        match *self {
+            Self::Exactly(n) => count == n,
+            Self::AtLeast(n) => count >= n,
+        }
+    }
+}
+impl std::fmt::Display for ExpectedMatches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exactly(n) => write!(f, "{n}"),
+            Self::AtLeast(n) => write!(f, "at least {n}"),
+        }
+    }
+}
+/// Parses `N` as exactly N matches and `N+` as at least N
+impl std::str::FromStr for ExpectedMatches {
+    type Err = std::num::ParseIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.strip_suffix('+') {
+            Some(n) => Self::AtLeast(n.parse()?),
+            None => Self::Exactly(s.parse()?),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct PatternConfig<S> {
     pub sig: S,
     pub name: String,
     pub scan: Scan,
+    pub expected: Option<ExpectedMatches>,
 }
 impl<S> PatternConfig<S> {
     pub fn new(
@@ -89,8 +163,12 @@ impl<S> PatternConfig<S> {
             name,
             scan: Scan {
                 section,
+                permissions: None,
+                ranges: vec![],
+                filter_code: false,
                 scan_type: pattern.into(),
             },
+            expected: None,
         }
     }
     pub fn xref(sig: S, name: String, section: Option<object::SectionKind>, xref: Xref) -> Self {
@@ -99,10 +177,34 @@ impl<S> PatternConfig<S> {
             name,
             scan: Scan {
                 section,
+                permissions: None,
+                ranges: vec![],
+                filter_code: false,
                 scan_type: xref.into(),
             },
+            expected: None,
         }
     }
+    /// Restrict scan to sections granting at least `permissions`
+    pub fn permissions(mut self, permissions: SectionPermissions) -> Self {
+        self.scan.permissions = Some(permissions);
+        self
+    }
+    /// Restrict scan to the virtual address `range` (can be called multiple times)
+    pub fn range(mut self, range: Range<usize>) -> Self {
+        self.scan.ranges.push(range);
+        self
+    }
+    /// Drop matches in padding or data between the functions of executable sections
+    pub fn filter_code(mut self) -> Self {
+        self.scan.filter_code = true;
+        self
+    }
+    /// Declare how many matches the pattern should produce so scans can flag deviations
+    pub fn expect(mut self, expected: ExpectedMatches) -> Self {
+        self.expected = Some(expected);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -164,6 +266,8 @@ pub enum MemoryAccessError {
     Utf8Error,
     Utf16Error,
     MisalginedAddress(usize, usize),
+    /// Function ranges were requested from an image read without caching its exception data
+    ExceptionDataUnavailable,
 }
 impl std::error::Error for MemoryAccessError {}
 impl std::fmt::Display for MemoryAccessError {
@@ -175,6 +279,10 @@ impl std::fmt::Display for MemoryAccessError {
             Self::MisalginedAddress(addr, align) => {
                 write!(f, "MisalginedAddress: address {:#x} != {:#x}", addr, align)
             }
+            Self::ExceptionDataUnavailable => write!(
+                f,
+                "exception data unavailable, rebuild Image with functions(true)"
+            ),
         }
     }
 }
@@ -332,9 +440,204 @@ impl<'data> MemoryBlockTrait<'data> for MemorySection<'data> {
     }
 }
 
+/// Options for [`Memory::find_utf16`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utf16Scan {
+    pub string: String,
+    /// Only return matches whose address is a multiple of `alignment`
+    pub alignment: usize,
+    /// Compare ASCII characters case-insensitively
+    pub case_insensitive: bool,
+    /// Require a null terminator following the string
+    pub null_terminated: bool,
+}
+impl Utf16Scan {
+    pub fn new<S: Into<String>>(string: S) -> Self {
+        Self {
+            string: string.into(),
+            alignment: 2,
+            case_insensitive: false,
+            null_terminated: false,
+        }
+    }
+    pub fn alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment;
+        self
+    }
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+    pub fn null_terminated(mut self, null_terminated: bool) -> Self {
+        self.null_terminated = null_terminated;
+        self
+    }
+}
+
+/// Options for [`Memory::find_literal`]. Floats default to their natural alignment since they
+/// are usually loaded from constant data, integers to no alignment since they are often
+/// immediates embedded in code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiteralScan {
+    /// Little endian encoding of the value
+    pub bytes: Vec<u8>,
+    /// Only return matches whose address is a multiple of `alignment`
+    pub alignment: usize,
+    /// Match the big endian encoding instead
+    pub big_endian: bool,
+}
+impl LiteralScan {
+    pub fn new<B: Into<Vec<u8>>>(bytes: B) -> Self {
+        Self {
+            bytes: bytes.into(),
+            alignment: 1,
+            big_endian: false,
+        }
+    }
+    pub fn f32(value: f32) -> Self {
+        Self::new(value.to_le_bytes()).alignment(4)
+    }
+    pub fn f64(value: f64) -> Self {
+        Self::new(value.to_le_bytes()).alignment(8)
+    }
+    pub fn u16(value: u16) -> Self {
+        Self::new(value.to_le_bytes())
+    }
+    pub fn u32(value: u32) -> Self {
+        Self::new(value.to_le_bytes())
+    }
+    pub fn u64(value: u64) -> Self {
+        Self::new(value.to_le_bytes())
+    }
+    pub fn i32(value: i32) -> Self {
+        Self::new(value.to_le_bytes())
+    }
+    pub fn i64(value: i64) -> Self {
+        Self::new(value.to_le_bytes())
+    }
+    pub fn alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment;
+        self
+    }
+    pub fn big_endian(mut self, big_endian: bool) -> Self {
+        self.big_endian = big_endian;
+        self
+    }
+    /// Bytes to search for with the configured endianness
+    pub fn needle(&self) -> Vec<u8> {
+        let mut needle = self.bytes.clone();
+        if self.big_endian {
+            needle.reverse();
+        }
+        needle
+    }
+}
+
+/// A string found by [`Memory::find_utf16`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringMatch {
+    pub address: usize,
+    /// Name of the section containing the string
+    pub section: String,
+}
+
+/// Memory protection of a section
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SectionPermissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+impl SectionPermissions {
+    pub const R: Self = Self::new(true, false, false);
+    pub const RW: Self = Self::new(true, true, false);
+    pub const RX: Self = Self::new(true, false, true);
+    pub const X: Self = Self::new(false, false, true);
+    pub const W: Self = Self::new(false, true, false);
+
+    pub const fn new(read: bool, write: bool, execute: bool) -> Self {
+        Self {
+            read,
+            write,
+            execute,
+        }
+    }
+    /// Whether every permission in `required` is granted
+    pub fn allows(self, required: Self) -> bool {
+        (self.read || !required.read)
+            && (self.write || !required.write)
+            && (self.execute || !required.execute)
+    }
+    /// Read permissions from object section flags, falling back to guessing from section kind
+    pub fn from_section(section: &object::Section<'_, '_>) -> Self {
+        use object::{pe, SectionFlags};
+        match section.flags() {
+            SectionFlags::Coff { characteristics } => Self::new(
+                characteristics & pe::IMAGE_SCN_MEM_READ != 0,
+                characteristics & pe::IMAGE_SCN_MEM_WRITE != 0,
+                characteristics & pe::IMAGE_SCN_MEM_EXECUTE != 0,
+            ),
+            SectionFlags::Elf { sh_flags } => Self::new(
+                true,
+                sh_flags & object::elf::SHF_WRITE as u64 != 0,
+                sh_flags & object::elf::SHF_EXECINSTR as u64 != 0,
+            ),
+            _ => Self::from_kind(section.kind()),
+        }
+    }
+    /// Read permissions from ELF program header flags
+    pub fn from_elf_segment_flags(flags: u32) -> Self {
+        Self::new(
+            flags & object::elf::PF_R != 0,
+            flags & object::elf::PF_W != 0,
+            flags & object::elf::PF_X != 0,
+        )
+    }
+    /// Guess permissions from section kind
+    pub fn from_kind(kind: object::SectionKind) -> Self {
+        use object::SectionKind;
+        match kind {
+            SectionKind::Text => Self::RX,
+            SectionKind::Data | SectionKind::UninitializedData | SectionKind::Tls => Self::RW,
+            _ => Self::R,
+        }
+    }
+}
+impl std::str::FromStr for SectionPermissions {
+    type Err = anyhow::Error;
+    /// Parse permissions from a string such as "rx" or "r-x"
+    fn from_str(s: &str) -> Result<Self> {
+        let mut permissions = Self::default();
+        for c in s.chars() {
+            match c {
+                'r' | 'R' => permissions.read = true,
+                'w' | 'W' => permissions.write = true,
+                'x' | 'X' => permissions.execute = true,
+                '-' => {}
+                _ => bail!("invalid permission {c:?}, expected any of \"rwx\""),
+            }
+        }
+        Ok(permissions)
+    }
+}
+impl std::fmt::Display for SectionPermissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' }
+        )
+    }
+}
+
 pub struct NamedMemorySection<'data> {
     name: String,
     kind: object::SectionKind,
+    permissions: SectionPermissions,
+    /// Range of the section data in the image file, if known and backed by file data
+    file_range: Option<Range<usize>>,
     section: MemorySection<'data>,
 }
 
@@ -343,17 +646,36 @@ impl<'data> NamedMemorySection<'data> {
         name: String,
         address: usize,
         kind: object::SectionKind,
+        permissions: SectionPermissions,
         data: T,
     ) -> Self {
         Self {
             name,
             kind,
+            permissions,
+            file_range: None,
             section: MemorySection {
                 address,
                 data: data.into(),
             },
         }
     }
+    fn from_section<T: Into<Cow<'data, [u8]>>>(
+        section: &object::Section<'_, '_>,
+        data: T,
+    ) -> Result<Self> {
+        let mut new = Self::new(
+            section.name()?.to_string(),
+            section.address() as usize,
+            section.kind(),
+            SectionPermissions::from_section(section),
+            data,
+        );
+        new.file_range = section
+            .file_range()
+            .map(|(offset, size)| offset as usize..(offset + size) as usize);
+        Ok(new)
+    }
 }
 impl NamedMemorySection<'_> {
     pub fn name(&self) -> &str {
@@ -362,9 +684,17 @@ impl NamedMemorySection<'_> {
     pub fn kind(&self) -> object::SectionKind {
         self.kind
     }
+    pub fn permissions(&self) -> SectionPermissions {
+        self.permissions
+    }
     pub fn address(&self) -> usize {
         self.section.address()
     }
+    /// Range of the section data in the image file, `None` for sections without file data (e.g.
+    /// `.bss`) and synthetic images
+    pub fn file_range(&self) -> Option<Range<usize>> {
+        self.file_range.clone()
+    }
     pub fn data(&self) -> &[u8] {
         self.section.data()
     }
@@ -393,29 +723,41 @@ impl<'data> Memory<'data> {
         Ok(Self {
             sections: object
                 .sections()
-                .map(|s| {
-                    Ok(NamedMemorySection::new(
-                        s.name()?.to_string(),
-                        s.address() as usize,
-                        s.kind(),
-                        s.data()?,
-                    ))
-                })
+                .map(|s| NamedMemorySection::from_section(&s, s.data()?))
                 .collect::<Result<Vec<_>>>()?,
         })
     }
+    /// Same as [`Memory::new`] but skipping sections whose data can't be read (e.g. a raw data
+    /// range past the end of a truncated file) instead of failing
+    pub fn new_tolerant(object: &File<'data>) -> (Self, Vec<image::ImageWarning>) {
+        let mut warnings = vec![];
+        let sections = object
+            .sections()
+            .filter_map(|s| {
+                match s
+                    .data()
+                    .map_err(Into::into)
+                    .and_then(|data| NamedMemorySection::from_section(&s, data))
+                {
+                    Ok(section) => Some(section),
+                    Err(err) => {
+                        let name = s.name().ok().map(str::to_string);
+                        warnings.push(image::ImageWarning::new(
+                            name,
+                            format!("skipped section at {:#x}: {err}", s.address()),
+                        ));
+                        None
+                    }
+                }
+            })
+            .collect();
+        (Self { sections }, warnings)
+    }
     pub fn new_external_data(sections: Vec<(object::Section<'_, '_>, Vec<u8>)>) -> Result<Self> {
         Ok(Self {
             sections: sections
                 .into_iter()
-                .map(|(s, d)| {
-                    Ok(NamedMemorySection::new(
-                        s.name()?.to_string(),
-                        s.address() as usize,
-                        s.kind(),
-                        d,
-                    ))
-                })
+                .map(|(s, d)| NamedMemorySection::from_section(&s, d))
                 .collect::<Result<Vec<_>>>()?,
         })
     }
@@ -425,20 +767,100 @@ impl<'data> Memory<'data> {
         Ok(Self {
             sections: sections
                 .into_iter()
-                .map(|(s, d)| {
-                    Ok(NamedMemorySection::new(
-                        s.name()?.to_string(),
-                        s.address() as usize,
-                        s.kind(),
-                        d,
-                    ))
-                })
+                .map(|(s, d)| NamedMemorySection::from_section(&s, d))
                 .collect::<Result<Vec<_>>>()?,
         })
     }
     pub fn sections(&self) -> &[NamedMemorySection] {
         &self.sections
     }
+    pub(crate) fn retain_sections(&mut self, f: impl FnMut(&NamedMemorySection<'data>) -> bool) {
+        self.sections.retain(f)
+    }
+    /// Sections granting at least `permissions`
+    pub fn sections_with(
+        &self,
+        permissions: SectionPermissions,
+    ) -> impl Iterator<Item = &NamedMemorySection<'data>> {
+        self.sections
+            .iter()
+            .filter(move |s| s.permissions.allows(permissions))
+    }
+    /// Find all occurrences of a UTF-16 string. Much faster than scanning for the equivalent
+    /// byte pattern since a single needle can be searched with SIMD accelerated substring search.
+    pub fn find_utf16(&self, scan: &Utf16Scan) -> Vec<StringMatch> {
+        let mut needle = scan.string.encode_utf16().collect::<Vec<_>>();
+        if scan.null_terminated {
+            needle.push(0);
+        }
+        if needle.is_empty() {
+            return vec![];
+        }
+        let fold = |c: u16| -> u16 {
+            if scan.case_insensitive {
+                u8::try_from(c)
+                    .map(|c| c.to_ascii_lowercase() as u16)
+                    .unwrap_or(c)
+            } else {
+                c
+            }
+        };
+        let bytes = needle
+            .iter()
+            .flat_map(|c| c.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let alignment = scan.alignment.max(1);
+
+        let mut matches = vec![];
+        for section in &self.sections {
+            let base = section.address();
+            let data = section.data();
+            let mut push = |i: usize| {
+                if (base + i).is_multiple_of(alignment) {
+                    matches.push(StringMatch {
+                        address: base + i,
+                        section: section.name().to_string(),
+                    });
+                }
+            };
+            if scan.case_insensitive {
+                let first = fold(needle[0]).to_le_bytes();
+                let upper = (first[0] as char).to_ascii_uppercase() as u8;
+                for i in memchr::memchr2_iter(first[0], upper, data) {
+                    let Some(candidate) = data.get(i..i + bytes.len()) else {
+                        break;
+                    };
+                    let is_match = candidate
+                        .chunks_exact(2)
+                        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                        .zip(&needle)
+                        .all(|(a, b)| fold(a) == fold(*b));
+                    if is_match {
+                        push(i);
+                    }
+                }
+            } else {
+                memchr::memmem::find_iter(data, &bytes).for_each(&mut push);
+            }
+        }
+        matches
+    }
+    /// Find every occurrence of an encoded literal value
+    pub fn find_literal(&self, scan: &LiteralScan) -> Vec<usize> {
+        let needle = scan.needle();
+        if needle.is_empty() {
+            return vec![];
+        }
+        let alignment = scan.alignment.max(1);
+        self.sections
+            .iter()
+            .flat_map(|section| {
+                memchr::memmem::find_iter(section.data(), &needle)
+                    .map(move |i| section.address() + i)
+                    .filter(move |address| address.is_multiple_of(alignment))
+            })
+            .collect()
+    }
     pub fn get_section_containing(
         &self,
         address: usize,
@@ -541,11 +963,20 @@ impl<'data> Matchable<'data> for Memory<'data> {
 }
 
 pub mod disassemble {
-    use std::{collections::HashSet, ops::Range};
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashSet},
+        ops::Range,
+    };
 
-    use iced_x86::{Decoder, DecoderOptions, FlowControl, Formatter, Instruction, NasmFormatter};
+    use iced_x86::{
+        Decoder, DecoderOptions, FlowControl, Formatter, Instruction, InstructionInfoFactory,
+        NasmFormatter, OpAccess, OpKind,
+    };
 
-    use crate::{Image, MemoryAccessError, MemoryTrait};
+    use crate::{
+        scanner::{scan_xref, Xref},
+        Image, MemoryAccessError, MemoryTrait,
+    };
 
     pub fn function_range(
         exe: &Image<'_>,
@@ -564,6 +995,178 @@ pub mod disassemble {
         Ok(min..max)
     }
 
+    /// How an instruction uses a rip-relative memory operand
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum DataAccess {
+        Read,
+        Write,
+        ReadWrite,
+        /// Only the address is taken (`lea`)
+        Address,
+    }
+
+    /// A rip-relative data reference inside a function
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct DataReference {
+        /// Address of the instruction
+        pub ip: usize,
+        /// Referenced address
+        pub address: usize,
+        pub access: DataAccess,
+        /// Size of the access in bytes, 0 for [`DataAccess::Address`]
+        pub size: usize,
+    }
+
+    /// All rip-relative data references reachable from `function` without leaving its root
+    /// function, ordered by instruction address. Lets resolvers use rules like "the global written
+    /// by the first store" rather than capturing data offsets in patterns.
+    pub fn data_references(
+        exe: &Image<'_>,
+        function: usize,
+    ) -> Result<Vec<DataReference>, MemoryAccessError> {
+        let mut info_factory = InstructionInfoFactory::new();
+        let mut references = vec![];
+        disassemble(exe, function, |inst| {
+            let cur = inst.ip() as usize;
+            if Some(function) != exe.get_root_function(cur)?.map(|f| f.range.start) {
+                return Ok(Control::Break);
+            }
+            if !inst.is_ip_rel_memory_operand() {
+                return Ok(Control::Continue);
+            }
+            let Some(operand) = (0..inst.op_count()).find(|i| inst.op_kind(*i) == OpKind::Memory)
+            else {
+                return Ok(Control::Continue);
+            };
+            let access = match info_factory.info(inst).op_access(operand) {
+                OpAccess::Read | OpAccess::CondRead => DataAccess::Read,
+                OpAccess::Write | OpAccess::CondWrite => DataAccess::Write,
+                OpAccess::ReadWrite | OpAccess::ReadCondWrite => DataAccess::ReadWrite,
+                OpAccess::NoMemAccess => DataAccess::Address,
+                OpAccess::None => return Ok(Control::Continue),
+            };
+            references.push(DataReference {
+                ip: cur,
+                address: inst.ip_rel_memory_address() as usize,
+                access,
+                size: match access {
+                    DataAccess::Address => 0,
+                    _ => inst.memory_size().size(),
+                },
+            });
+            Ok(Control::Continue)
+        })?;
+        references.sort_by_key(|r| r.ip);
+        Ok(references)
+    }
+
+    /// How an instruction changes a global, see [`global_writes`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum WriteKind {
+        /// Stores a new value, e.g. the pointer to a freshly created singleton
+        Store,
+        /// Stores an immediate zero, e.g. when tearing the singleton down
+        Clear,
+        /// Reads and writes the global in one instruction (`inc`, `lock cmpxchg`, ...)
+        Update,
+    }
+
+    /// An instruction writing a global
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct GlobalWrite {
+        /// Start of the root function containing the instruction
+        pub function: usize,
+        /// Address of the instruction
+        pub ip: usize,
+        pub kind: WriteKind,
+        /// Size of the write in bytes
+        pub size: usize,
+    }
+
+    /// Every instruction writing `global` through a rip-relative operand, ordered by function and
+    /// instruction address. Functions are found through the exception table, so references from
+    /// code outside of any function are missed.
+    pub fn global_writes(
+        exe: &Image<'_>,
+        global: usize,
+    ) -> Result<Vec<GlobalWrite>, MemoryAccessError> {
+        // the displacement is relative to the end of the instruction, which is followed by an
+        // immediate when storing a constant
+        let xrefs = [0, 1, 2, 4].map(|imm| Xref(global.wrapping_sub(imm)));
+        let xrefs = xrefs.iter().collect::<Vec<_>>();
+
+        let mut functions = BTreeSet::new();
+        for section in exe.memory.sections() {
+            if !section.permissions().execute {
+                continue;
+            }
+            for address in scan_xref(&xrefs, section.address(), section.data())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(function) = exe.get_root_function(address)? {
+                    functions.insert(function.range.start);
+                }
+            }
+        }
+
+        let mut writes = vec![];
+        for function in functions {
+            for reference in data_references(exe, function)? {
+                if reference.address != global {
+                    continue;
+                }
+                let kind = match reference.access {
+                    DataAccess::Write => {
+                        let clears = disassemble_single(exe, reference.ip)?.is_some_and(|inst| {
+                            (0..inst.op_count()).any(|i| {
+                                matches!(
+                                    inst.op_kind(i),
+                                    OpKind::Immediate8
+                                        | OpKind::Immediate16
+                                        | OpKind::Immediate32
+                                        | OpKind::Immediate8to32
+                                        | OpKind::Immediate8to64
+                                        | OpKind::Immediate32to64
+                                ) && inst.immediate(i) == 0
+                            })
+                        });
+                        if clears {
+                            WriteKind::Clear
+                        } else {
+                            WriteKind::Store
+                        }
+                    }
+                    DataAccess::ReadWrite => WriteKind::Update,
+                    DataAccess::Read | DataAccess::Address => continue,
+                };
+                writes.push(GlobalWrite {
+                    function,
+                    ip: reference.ip,
+                    kind,
+                    size: reference.size,
+                });
+            }
+        }
+        Ok(writes)
+    }
+
+    /// Functions storing a value other than zero to `global`, i.e. the candidates for creating
+    /// the object a singleton global points to. Hooking one of them catches the moment the
+    /// global is (re)assigned, which resolving the global alone can't tell.
+    pub fn global_initializers(
+        exe: &Image<'_>,
+        global: usize,
+    ) -> Result<Vec<usize>, MemoryAccessError> {
+        let mut functions = global_writes(exe, global)?
+            .into_iter()
+            .filter(|w| w.kind == WriteKind::Store)
+            .map(|w| w.function)
+            .collect::<Vec<_>>();
+        functions.dedup();
+        Ok(functions)
+    }
+
     pub fn disassemble_single<'mem, 'img: 'mem>(
         exe: &'img Image<'mem>,
         address: usize,
@@ -720,4 +1323,300 @@ pub mod disassemble {
         }
         Ok(())
     }
+
+    /// A straight line run of instructions ending at a branch, return or the start of another
+    /// block
+    #[derive(Debug, Clone)]
+    pub struct BasicBlock {
+        pub range: Range<usize>,
+        pub instructions: Vec<Instruction>,
+        /// Start addresses of the blocks control can flow to. For conditional branches the branch
+        /// target comes first, then the fall through block.
+        pub successors: Vec<usize>,
+        /// Target of a jump out of the function
+        pub tail_call: Option<usize>,
+    }
+    impl BasicBlock {
+        pub fn start(&self) -> usize {
+            self.range.start
+        }
+        pub fn last(&self) -> &Instruction {
+            self.instructions.last().unwrap()
+        }
+        /// Targets of direct calls made by the block in order
+        pub fn calls(&self) -> impl Iterator<Item = usize> + '_ {
+            self.instructions
+                .iter()
+                .filter(|i| i.flow_control() == FlowControl::Call)
+                .map(|i| i.near_branch_target() as usize)
+        }
+    }
+
+    /// Control flow graph of a function, built by recursive descent from its entry point.
+    /// Branches leaving the root function are treated as tail calls and indirect branches (e.g.
+    /// jump tables) are not followed.
+    #[derive(Debug, Clone)]
+    pub struct ControlFlowGraph {
+        pub entry: usize,
+        /// Blocks by start address
+        pub blocks: BTreeMap<usize, BasicBlock>,
+    }
+    impl ControlFlowGraph {
+        pub fn build(exe: &Image<'_>, function: usize) -> Result<Self, MemoryAccessError> {
+            let root = exe.get_root_function(function)?.map(|f| f.range.start);
+            let in_function =
+                |address: usize| -> Result<bool, MemoryAccessError> {
+                    Ok(root.is_some()
+                        && exe.get_root_function(address)?.map(|f| f.range.start) == root)
+                };
+
+            // decode every reachable instruction and collect block leaders
+            let mut instructions = BTreeMap::new();
+            let mut leaders = BTreeSet::from([function]);
+            let mut queue = vec![function];
+            while let Some(address) = queue.pop() {
+                let mut address = address;
+                while !instructions.contains_key(&address) && in_function(address)? {
+                    let Some(inst) = disassemble_single(exe, address)? else {
+                        break;
+                    };
+                    instructions.insert(address, inst);
+                    let target = inst.near_branch_target() as usize;
+                    let next = inst.next_ip() as usize;
+                    match inst.flow_control() {
+                        FlowControl::Next | FlowControl::Call | FlowControl::IndirectCall => {
+                            address = next;
+                        }
+                        FlowControl::ConditionalBranch => {
+                            if in_function(target)? {
+                                leaders.insert(target);
+                                queue.push(target);
+                            }
+                            leaders.insert(next);
+                            address = next;
+                        }
+                        FlowControl::UnconditionalBranch => {
+                            if in_function(target)? {
+                                leaders.insert(target);
+                                queue.push(target);
+                            }
+                            break;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+
+            // split the instructions into blocks
+            let mut blocks: BTreeMap<usize, BasicBlock> = BTreeMap::new();
+            let mut current: Option<BasicBlock> = None;
+            for (address, inst) in instructions {
+                let continues = current.as_ref().is_some_and(|block| {
+                    block.range.end == address
+                        && !leaders.contains(&address)
+                        && matches!(
+                            block.last().flow_control(),
+                            FlowControl::Next | FlowControl::Call | FlowControl::IndirectCall
+                        )
+                });
+                if !continues {
+                    if let Some(block) = current.take() {
+                        blocks.insert(block.start(), block);
+                    }
+                    current = Some(BasicBlock {
+                        range: address..address,
+                        instructions: vec![],
+                        successors: vec![],
+                        tail_call: None,
+                    });
+                }
+                let block = current.as_mut().unwrap();
+                block.range.end = inst.next_ip() as usize;
+                block.instructions.push(inst);
+            }
+            if let Some(block) = current {
+                blocks.insert(block.start(), block);
+            }
+
+            let starts = blocks.keys().copied().collect::<HashSet<_>>();
+            for block in blocks.values_mut() {
+                let last = block.last();
+                let target = last.near_branch_target() as usize;
+                let next = block.range.end;
+                match last.flow_control() {
+                    FlowControl::Next | FlowControl::Call | FlowControl::IndirectCall => {
+                        block.successors.push(next);
+                    }
+                    FlowControl::ConditionalBranch => {
+                        block.successors.push(target);
+                        block.successors.push(next);
+                    }
+                    FlowControl::UnconditionalBranch => {
+                        if starts.contains(&target) {
+                            block.successors.push(target);
+                        } else {
+                            block.tail_call = Some(target);
+                        }
+                    }
+                    _ => {}
+                }
+                block.successors.retain(|s| starts.contains(s));
+            }
+
+            Ok(Self {
+                entry: function,
+                blocks,
+            })
+        }
+        /// The block containing `address`
+        pub fn block(&self, address: usize) -> Option<&BasicBlock> {
+            self.blocks
+                .range(..=address)
+                .next_back()
+                .map(|(_, block)| block)
+                .filter(|block| block.range.contains(&address))
+        }
+        /// Start addresses of the blocks with an edge to the block starting at `start`
+        pub fn predecessors(&self, start: usize) -> Vec<usize> {
+            self.blocks
+                .values()
+                .filter(|block| block.successors.contains(&start))
+                .map(BasicBlock::start)
+                .collect()
+        }
+        /// Edges `(from, to)` jumping backwards to the start of a block, i.e. loop back edges
+        /// when the compiler lays out loop bodies in order
+        pub fn back_edges(&self) -> Vec<(usize, usize)> {
+            self.blocks
+                .values()
+                .flat_map(|block| {
+                    block
+                        .successors
+                        .iter()
+                        .filter(|s| **s <= block.start())
+                        .map(|s| (block.start(), *s))
+                })
+                .collect()
+        }
+        /// `(ip, target)` of all direct calls in address order
+        pub fn calls(&self) -> Vec<(usize, usize)> {
+            self.blocks
+                .values()
+                .flat_map(|block| &block.instructions)
+                .filter(|i| i.flow_control() == FlowControl::Call)
+                .map(|i| (i.ip() as usize, i.near_branch_target() as usize))
+                .collect()
+        }
+    }
+
+    #[cfg(all(test, feature = "image-pe"))]
+    mod test {
+        use object::SectionKind;
+
+        use super::*;
+        use crate::testing::TestImageBuilder;
+
+        #[test]
+        fn test_data_references() {
+            let base = 0x140000000;
+            let f = base + 0x1000;
+            let data = base + 0x2000;
+
+            // (opcode, target) for rip-relative instructions with a 32 bit displacement last
+            let instructions: [(&[u8], usize); 4] = [
+                (&[0x48, 0x8b, 0x05], data),        // mov rax, [data]
+                (&[0x48, 0x89, 0x05], data + 8),    // mov [data + 8], rax
+                (&[0xff, 0x05], data + 0x10),       // inc dword [data + 0x10]
+                (&[0x48, 0x8d, 0x0d], data + 0x18), // lea rcx, [data + 0x18]
+            ];
+            let mut code = vec![];
+            for (opcode, target) in instructions {
+                code.extend(opcode);
+                let next = f + code.len() + 4;
+                code.extend((target.wrapping_sub(next) as u32).to_le_bytes());
+            }
+            code.push(0xc3);
+
+            let image = TestImageBuilder::new(base)
+                .section(".text", SectionKind::Text, f, 0x1000)
+                .section(".data", SectionKind::Data, data, 0x1000)
+                .write(f, &code)
+                .function(f..f + code.len())
+                .build()
+                .unwrap();
+
+            let references = data_references(&image, f).unwrap();
+            let summary = references
+                .iter()
+                .map(|r| (r.address, r.access, r.size))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                summary,
+                [
+                    (data, DataAccess::Read, 8),
+                    (data + 8, DataAccess::Write, 8),
+                    (data + 0x10, DataAccess::ReadWrite, 4),
+                    (data + 0x18, DataAccess::Address, 0),
+                ]
+            );
+            assert_eq!(references[0].ip, f);
+        }
+
+        #[test]
+        fn test_control_flow_graph() {
+            let base = 0x140000000;
+            let f = base + 0x1000;
+            let l = base + 0x1100;
+            let g = base + 0x1200;
+            let h = base + 0x1300;
+
+            let image = TestImageBuilder::new(base)
+                .section(".text", SectionKind::Text, f, 0x1000)
+                .write(
+                    f,
+                    &[
+                        0x85, 0xc9, // test ecx, ecx
+                        0x74, 0x06, // je f+0xa
+                        0xe8, 0xf7, 0x01, 0x00, 0x00, // call g
+                        0xc3, // ret
+                        0xe9, 0xf1, 0x02, 0x00, 0x00, // jmp h
+                    ],
+                )
+                .write(
+                    l,
+                    &[
+                        0x31, 0xc0, // xor eax, eax
+                        0xff, 0xc0, // inc eax
+                        0x83, 0xf8, 0x0a, // cmp eax, 10
+                        0x75, 0xf9, // jne l+2
+                        0xc3, // ret
+                    ],
+                )
+                .write(g, &[0xc3])
+                .write(h, &[0xc3])
+                .function(f..f + 0xf)
+                .function(l..l + 0xa)
+                .function(g..g + 1)
+                .function(h..h + 1)
+                .build()
+                .unwrap();
+
+            let cfg = ControlFlowGraph::build(&image, f).unwrap();
+            let starts = cfg.blocks.keys().copied().collect::<Vec<_>>();
+            assert_eq!(starts, [f, f + 4, f + 0xa]);
+            assert_eq!(cfg.blocks[&f].successors, [f + 0xa, f + 4]);
+            assert_eq!(cfg.blocks[&(f + 4)].calls().collect::<Vec<_>>(), [g]);
+            assert!(cfg.blocks[&(f + 4)].successors.is_empty());
+            assert_eq!(cfg.blocks[&(f + 0xa)].tail_call, Some(h));
+            assert_eq!(cfg.block(f + 6).map(BasicBlock::start), Some(f + 4));
+            assert_eq!(cfg.predecessors(f + 0xa), [f]);
+            assert_eq!(cfg.calls(), [(f + 4, g)]);
+
+            let cfg = ControlFlowGraph::build(&image, l).unwrap();
+            let starts = cfg.blocks.keys().copied().collect::<Vec<_>>();
+            assert_eq!(starts, [l, l + 2, l + 9]);
+            assert_eq!(cfg.back_edges(), [(l + 2, l + 2)]);
+            assert_eq!(cfg.predecessors(l + 2), [l, l + 2]);
+        }
+    }
 }
@@ -0,0 +1,306 @@
+//! Named pattern sets loaded from TOML so pattern packs can be maintained outside of the crate.
+//!
+//! ```toml
+//! name = "community"
+//! author = "someone"
+//!
+//! [[patterns]]
+//! name = "FNameToString"
+//! pattern = "48 89 5c 24 ?? 57 48 83 ec 20 83 79 04 00 48 8b da"
+//! engine_versions = ["4.25", "4.26", "5"]
+//! notes = "only matches non case preserving builds"
+//! expected_count = 1
+//! section = "text"
+//! permissions = "rx"
+//!
+//! [[patterns]]
+//! name = "GMalloc xref"
+//! xref = "0x14a1b2c30"
+//! min_count = 1
+//! expected = { "Game-Win64-Shipping" = ["0x1401a2b3c"] }
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    scanner::{Pattern, Xref},
+    ExpectedMatches, PatternConfig, SectionPermissions,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PatternSet {
+    /// Name of the pattern set
+    #[serde(default)]
+    pub name: String,
+    /// Default author for patterns which don't specify one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub patterns: Vec<PatternEntry>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PatternEntry {
+    pub name: String,
+    /// Byte pattern, mutually exclusive with `xref`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Address to find references to, mutually exclusive with `pattern`. Accepts hex with a 0x
+    /// prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Engine versions the pattern is known to work on, either "major" or "major.minor"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engine_versions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Number of matches the pattern is expected to produce
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_count: Option<usize>,
+    /// Minimum number of matches, for patterns matching a varying number of times. Mutually
+    /// exclusive with `expected_count`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_count: Option<usize>,
+    /// Section kind to restrict scanning to: "text", "data" or "rodata"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Required section permissions, e.g. "rx"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+    /// Addresses the pattern matched at by game name, as recorded by `scan
+    /// --update-expectations`. Accepts hex with a 0x prefix.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub expected: BTreeMap<String, Vec<String>>,
+}
+
+/// Parse an address, hex with a 0x prefix or decimal
+fn parse_address(s: &str) -> Result<usize> {
+    Ok(match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    })
+}
+
+impl PatternSet {
+    pub fn parse(s: &str) -> Result<Self> {
+        let set: Self = toml::from_str(s)?;
+        for entry in &set.patterns {
+            entry
+                .scan_type()
+                .and(entry.expected_matches())
+                .with_context(|| format!("invalid pattern {:?}", entry.name))?;
+            for game in entry.expected.keys() {
+                entry
+                    .expected_addresses(game)
+                    .with_context(|| format!("invalid expected addresses of {:?}", entry.name))?;
+            }
+        }
+        Ok(set)
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("failed to load pattern set {}", path.display()))
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write pattern set {}", path.display()))
+    }
+    /// Merge `other` into this set. Patterns with the same name are replaced by those from
+    /// `other`, so later sets take precedence over earlier ones (e.g. a local pack overriding
+    /// built-in patterns).
+    pub fn merge(&mut self, other: PatternSet) {
+        let author = other.author;
+        for mut entry in other.patterns {
+            if entry.author.is_none() {
+                entry.author = author.clone();
+            }
+            if let Some(existing) = self.patterns.iter_mut().find(|e| e.name == entry.name) {
+                *existing = entry;
+            } else {
+                self.patterns.push(entry);
+            }
+        }
+    }
+    /// Patterns grouped by name
+    pub fn by_name(&self) -> BTreeMap<&str, &PatternEntry> {
+        self.patterns.iter().map(|e| (e.name.as_str(), e)).collect()
+    }
+    /// Build scan configs for every pattern, using `sig` to produce the config tag
+    pub fn pattern_configs<S, F: Fn(&PatternEntry) -> S>(
+        &self,
+        sig: F,
+    ) -> Result<Vec<PatternConfig<S>>> {
+        self.patterns
+            .iter()
+            .map(|entry| entry.pattern_config(sig(entry)))
+            .collect()
+    }
+}
+
+impl PatternEntry {
+    fn scan_type(&self) -> Result<crate::ScanType> {
+        Ok(match (&self.pattern, &self.xref) {
+            (Some(pattern), None) => Pattern::new(pattern)?.into(),
+            (None, Some(xref)) => Xref(parse_address(xref)?).into(),
+            _ => bail!("exactly one of `pattern` or `xref` must be specified"),
+        })
+    }
+    fn section_kind(&self) -> Result<Option<object::SectionKind>> {
+        use object::SectionKind;
+        Ok(match self.section.as_deref() {
+            None => None,
+            Some("text") => Some(SectionKind::Text),
+            Some("data") => Some(SectionKind::Data),
+            Some("rodata") => Some(SectionKind::ReadOnlyData),
+            Some(other) => bail!("unknown section kind {other:?}"),
+        })
+    }
+    pub fn pattern_config<S>(&self, sig: S) -> Result<PatternConfig<S>> {
+        let section = self.section_kind()?;
+        let mut config = match self.scan_type()? {
+            crate::ScanType::Pattern(pattern) => {
+                PatternConfig::new(sig, self.name.clone(), section, pattern)
+            }
+            crate::ScanType::Xref(xref) => {
+                PatternConfig::xref(sig, self.name.clone(), section, xref)
+            }
+        };
+        if let Some(permissions) = &self.permissions {
+            config = config.permissions(permissions.parse::<SectionPermissions>()?);
+        }
+        if let Some(expected) = self.expected_matches()? {
+            config = config.expect(expected);
+        }
+        Ok(config)
+    }
+    pub fn expected_matches(&self) -> Result<Option<ExpectedMatches>> {
+        Ok(match (self.expected_count, self.min_count) {
+            (Some(count), None) => Some(ExpectedMatches::Exactly(count)),
+            (None, Some(min)) => Some(ExpectedMatches::AtLeast(min)),
+            (None, None) => None,
+            (Some(_), Some(_)) => bail!("only one of `expected_count` or `min_count` may be set"),
+        })
+    }
+    /// Sorted addresses the pattern is expected to match in `game`, `None` if none are recorded
+    pub fn expected_addresses(&self, game: &str) -> Result<Option<Vec<usize>>> {
+        let Some(addresses) = self.expected.get(game) else {
+            return Ok(None);
+        };
+        let mut addresses = addresses
+            .iter()
+            .map(|a| parse_address(a))
+            .collect::<Result<Vec<_>>>()?;
+        addresses.sort();
+        Ok(Some(addresses))
+    }
+    /// Record the addresses matched in `game` as expected. Unless the pattern has a `min_count`,
+    /// `expected_count` is updated as well: set while every recorded game has the same number of
+    /// matches and cleared once they disagree.
+    pub fn record_expected(&mut self, game: &str, addresses: &[usize]) {
+        let mut addresses = addresses.to_vec();
+        addresses.sort();
+        addresses.dedup();
+        self.expected.insert(
+            game.to_string(),
+            addresses.iter().map(|a| format!("{a:#x}")).collect(),
+        );
+        if self.min_count.is_some() {
+            return;
+        }
+        let counts = self.expected.values().map(Vec::len).collect::<Vec<_>>();
+        self.expected_count = counts.iter().all(|c| *c == counts[0]).then_some(counts[0]);
+    }
+    /// Whether the pattern is expected to work for the given engine version. Patterns without
+    /// any listed versions are assumed to work everywhere.
+    pub fn supports_version(&self, major: u16, minor: u16) -> bool {
+        self.engine_versions.is_empty()
+            || self
+                .engine_versions
+                .iter()
+                .any(|v| match v.split_once('.') {
+                    Some((ma, mi)) => ma.parse() == Ok(major) && mi.parse() == Ok(minor),
+                    None => v.parse() == Ok(major),
+                })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let mut set = PatternSet::parse(
+            r#"
+            name = "base"
+            [[patterns]]
+            name = "a"
+            pattern = "48 8b ?? c3"
+            engine_versions = ["4.27", "5"]
+            expected_count = 1
+            section = "text"
+            permissions = "rx"
+            [[patterns]]
+            name = "b"
+            xref = "0x1234"
+            "#,
+        )
+        .unwrap();
+        assert!(set.patterns[0].supports_version(4, 27));
+        assert!(set.patterns[0].supports_version(5, 3));
+        assert!(!set.patterns[0].supports_version(4, 26));
+
+        set.merge(
+            PatternSet::parse(
+                r#"
+                author = "other"
+                [[patterns]]
+                name = "b"
+                pattern = "c3"
+                "#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(set.patterns.len(), 2);
+        assert_eq!(set.patterns[1].pattern.as_deref(), Some("c3"));
+        assert_eq!(set.patterns[1].author.as_deref(), Some("other"));
+
+        set.patterns[0].record_expected("A", &[0x2000, 0x1000, 0x2000]);
+        assert_eq!(set.patterns[0].expected_count, Some(2));
+        set.patterns[0].record_expected("B", &[0x3000]);
+        assert_eq!(set.patterns[0].expected_count, None);
+        set.patterns[0].record_expected("B", &[0x3000, 0x4000]);
+        assert_eq!(set.patterns[0].expected_count, Some(2));
+        set.patterns[1].min_count = Some(1);
+        set.patterns[1].record_expected("A", &[0x1000]);
+        assert_eq!(set.patterns[1].expected_count, None);
+        assert_eq!(
+            set.patterns[0].expected_addresses("A").unwrap(),
+            Some(vec![0x1000, 0x2000])
+        );
+        assert_eq!(set.patterns[0].expected_addresses("C").unwrap(), None);
+        let saved = toml::to_string_pretty(&set).unwrap();
+        assert_eq!(PatternSet::parse(&saved).unwrap(), set);
+
+        let configs = set.pattern_configs(|_| ()).unwrap();
+        assert_eq!(configs[0].scan.section, Some(object::SectionKind::Text));
+        assert_eq!(configs[0].expected, Some(ExpectedMatches::Exactly(2)));
+        assert_eq!(configs[1].expected, Some(ExpectedMatches::AtLeast(1)));
+        assert!(PatternSet::parse("[[patterns]]\nname = \"c\"").is_err());
+        assert!(PatternSet::parse(
+            "[[patterns]]\nname = \"c\"\npattern = \"c3\"\nexpected_count = 1\nmin_count = 1"
+        )
+        .is_err());
+
+        assert_eq!("1".parse(), Ok(ExpectedMatches::Exactly(1)));
+        assert_eq!("2+".parse(), Ok(ExpectedMatches::AtLeast(2)));
+        assert!(ExpectedMatches::AtLeast(2).matches(3));
+        assert!(!ExpectedMatches::Exactly(2).matches(3));
+    }
+}
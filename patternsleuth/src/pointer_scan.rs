@@ -0,0 +1,213 @@
+//! Pointer scanning: finding chains of pointers from static module data to an address on the heap
+//! of a running process, so an object found at runtime can be located again after a restart
+//! (and turned into a resolver for the static base).
+//!
+//! The scan works backwards from the target. A [`PointerMap`] of every pointer value in the
+//! process's writable memory is built once, then each level looks up which addresses hold a
+//! pointer to at most `max_offset` bytes before the addresses found in the previous level.
+
+use std::{collections::HashSet, fmt::Display, ops::Range};
+
+use crate::{ue::ReadMemory, MemoryAccessError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointerScanOptions {
+    /// Maximum number of pointers to follow
+    pub max_depth: usize,
+    /// Maximum offset added to each pointer, i.e. how far into a struct a field may be
+    pub max_offset: usize,
+    /// Stop after finding this many paths
+    pub max_results: usize,
+}
+impl Default for PointerScanOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_offset: 0x1000,
+            max_results: 1000,
+        }
+    }
+}
+
+/// A static address and the offsets to add after each dereference to arrive at the target
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PointerPath {
+    pub base: usize,
+    pub offsets: Vec<usize>,
+}
+impl PointerPath {
+    /// Follow the path in `mem`, returning the address it currently leads to
+    pub fn resolve(&self, mem: &impl ReadMemory) -> Result<usize, MemoryAccessError> {
+        let mut address = self.base;
+        for offset in &self.offsets {
+            address = mem.read_ptr(address)? + offset;
+        }
+        Ok(address)
+    }
+    /// Format with the base relative to a module, e.g. `[[Game.exe+0x4c1e8]+0x30]+0x8`
+    pub fn relative_to(&self, module: &str, module_base: usize) -> String {
+        self.format(format!(
+            "{module}+{:#x}",
+            self.base.wrapping_sub(module_base)
+        ))
+    }
+    fn format(&self, base: String) -> String {
+        self.offsets
+            .iter()
+            .fold(base, |path, offset| format!("[{path}]+{offset:#x}"))
+    }
+}
+impl Display for PointerPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(format!("{:#x}", self.base)))
+    }
+}
+
+/// Every 8 byte aligned value in a set of memory regions which points into one of the regions
+pub struct PointerMap {
+    /// (value, address holding it) sorted by value
+    pointers: Vec<(usize, usize)>,
+}
+impl PointerMap {
+    /// Regions are read in blocks, unreadable blocks are skipped
+    pub fn build(mem: &impl ReadMemory, regions: &[Range<usize>]) -> Self {
+        const BLOCK: usize = 0x10_0000;
+
+        let mut sorted = regions.to_vec();
+        sorted.sort_by_key(|r| r.start);
+        let is_pointer = |value: usize| {
+            let i = sorted.partition_point(|r| r.start <= value);
+            i > 0 && sorted[i - 1].contains(&value)
+        };
+
+        let mut pointers = vec![];
+        for region in &sorted {
+            let start = region.start.next_multiple_of(8);
+            for block in (start..region.end).step_by(BLOCK) {
+                let len = BLOCK.min(region.end - block) & !7;
+                let Ok(data) = mem.read_vec(block, len) else {
+                    continue;
+                };
+                for (i, chunk) in data.chunks_exact(8).enumerate() {
+                    let value = u64::from_le_bytes(chunk.try_into().unwrap()) as usize;
+                    if is_pointer(value) {
+                        pointers.push((value, block + i * 8));
+                    }
+                }
+            }
+        }
+        pointers.sort_unstable();
+        Self { pointers }
+    }
+    pub fn len(&self) -> usize {
+        self.pointers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pointers.is_empty()
+    }
+    /// `(value, address)` of pointers with a value in `range`
+    pub fn pointers_to(&self, range: Range<usize>) -> &[(usize, usize)] {
+        let start = self.pointers.partition_point(|(v, _)| *v < range.start);
+        let end = self.pointers.partition_point(|(v, _)| *v < range.end);
+        &self.pointers[start..end]
+    }
+}
+
+/// Find paths from addresses in `statics` (usually the writable sections of the main module)
+/// to `target`, shortest first. Each intermediate address is only expanded once so of several
+/// paths through the same address only the first found is extended further.
+pub fn pointer_scan(
+    map: &PointerMap,
+    statics: &[Range<usize>],
+    target: usize,
+    options: &PointerScanOptions,
+) -> Vec<PointerPath> {
+    let is_static = |address: usize| statics.iter().any(|s| s.contains(&address));
+
+    let mut results = vec![];
+    let mut visited = HashSet::from([target]);
+    let mut level = vec![(target, vec![])];
+    for _ in 0..options.max_depth {
+        let mut next = vec![];
+        for (address, offsets) in &level {
+            let range = address.saturating_sub(options.max_offset)..address + 1;
+            for (value, pointer) in map.pointers_to(range) {
+                let offsets = [&[address - value][..], offsets.as_slice()].concat();
+                if is_static(*pointer) {
+                    results.push(PointerPath {
+                        base: *pointer,
+                        offsets,
+                    });
+                    if results.len() >= options.max_results {
+                        return results;
+                    }
+                } else if visited.insert(*pointer) {
+                    next.push((*pointer, offsets));
+                }
+            }
+        }
+        level = next;
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Memory made of `(address, data)` regions
+    struct Regions(Vec<(usize, Vec<u8>)>);
+    impl ReadMemory for Regions {
+        fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+            for (start, data) in &self.0 {
+                if address >= *start && address + buffer.len() <= start + data.len() {
+                    let offset = address - start;
+                    buffer.copy_from_slice(&data[offset..offset + buffer.len()]);
+                    return Ok(());
+                }
+            }
+            Err(MemoryAccessError::MemoryOutOfBoundsError)
+        }
+    }
+    fn write(data: &mut [u8], offset: usize, value: usize) {
+        data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_pointer_scan() {
+        // static 0x1008 -> heap object 0x2000, its field at 0x2010 -> object 0x3000 with the
+        // target at 0x3040
+        let mut statics = vec![0; 0x100];
+        let mut heap_a = vec![0; 0x100];
+        let mut heap_b = vec![0; 0x100];
+        write(&mut statics, 0x8, 0x2000);
+        write(&mut heap_a, 0x10, 0x3000);
+        // a pointer loop which must not be followed forever
+        write(&mut heap_b, 0x8, 0x3000);
+        let mem = Regions(vec![(0x1000, statics), (0x2000, heap_a), (0x3000, heap_b)]);
+
+        let regions = [0x1000..0x1100, 0x2000..0x2100, 0x3000..0x3100];
+        let statics = &regions[..1];
+        let map = PointerMap::build(&mem, &regions);
+        assert_eq!(map.len(), 3);
+
+        let paths = pointer_scan(&map, statics, 0x3040, &PointerScanOptions::default());
+        let expected = PointerPath {
+            base: 0x1008,
+            offsets: vec![0x10, 0x40],
+        };
+        assert_eq!(paths, std::slice::from_ref(&expected));
+        assert_eq!(expected.resolve(&mem).unwrap(), 0x3040);
+        assert_eq!(expected.to_string(), "[[0x1008]+0x10]+0x40");
+        assert_eq!(
+            expected.relative_to("Game.exe", 0x1000),
+            "[[Game.exe+0x8]+0x10]+0x40"
+        );
+
+        let shallow = PointerScanOptions {
+            max_depth: 1,
+            ..Default::default()
+        };
+        assert!(pointer_scan(&map, statics, 0x3040, &shallow).is_empty());
+    }
+}
@@ -1,3 +1,106 @@
+//! Reading the image and memory of another process.
+//!
+//! Nothing here writes to the target, allocates in it or starts threads in it. The only system
+//! calls made are:
+//!
+//! Windows
+//! - `OpenProcess` with `PROCESS_VM_READ`, plus `PROCESS_QUERY_INFORMATION` to enumerate modules
+//!   and regions
+//! - `EnumProcessModules`, `GetModuleInformation` to locate the main module
+//! - `VirtualQueryEx`, `GetMappedFileNameW` to list memory regions
+//! - `QueryFullProcessImageNameW` to locate the executable on disk
+//! - `CreateToolhelp32Snapshot`, `Process32FirstW`, `Process32NextW` to find processes by name
+//! - `ReadProcessMemory`
+//! - `CloseHandle`
+//!
+//! Linux (including games running under WINE)
+//! - reading `/proc/<PID>/maps`
+//! - reading `/proc/<PID>/exe` and `/proc/<PID>/cmdline` to find processes by name
+//! - `process_vm_readv`
+//!
+//! [`ReadOptions::suspend`] additionally uses `CreateToolhelp32Snapshot`, `Thread32First`,
+//! `Thread32Next`, `OpenThread` with `THREAD_SUSPEND_RESUME`, `SuspendThread` and `ResumeThread`
+//! on Windows, and `kill` with `SIGSTOP`/`SIGCONT` on Linux. These change the state of the target
+//! so they are refused in [`ReadOptions::read_only`] mode.
+//!
+//! `launch_suspended` (Windows only) starts the game itself with `CreateProcessW`, later calling
+//! `ResumeThread` on its main thread, or `TerminateProcess` if it is dropped before.
+
+/// Options for reading an image from another process
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReadOptions {
+    /// Suspend the target process while its memory is read so the image is taken from a
+    /// consistent snapshot (code being patched or JIT stubs being written mid-read). The process
+    /// is resumed once reading finishes, even on error.
+    pub suspend: bool,
+    /// Strictly only read from the target: no access right beyond `PROCESS_VM_READ` and
+    /// `PROCESS_QUERY_INFORMATION` is requested and anything affecting the process, such as
+    /// [`suspend`](Self::suspend), is an error. For diagnostics tools which must not disturb the
+    /// game.
+    pub read_only: bool,
+}
+impl ReadOptions {
+    fn check(&self) -> anyhow::Result<()> {
+        if self.read_only && self.suspend {
+            anyhow::bail!("suspending the process is not allowed in read-only mode");
+        }
+        Ok(())
+    }
+}
+
+/// A mapped memory region of another process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub range: std::ops::Range<usize>,
+    pub permissions: crate::SectionPermissions,
+    /// Module or file backing the region, if any
+    pub path: Option<String>,
+}
+impl MemoryRegion {
+    /// Whether any section of `image` (e.g. one from [`read_image_from_pid`]) lies in this
+    /// region, i.e. whether it was included in scans of the image
+    pub fn included_in(&self, image: &crate::Image<'_>) -> bool {
+        image.memory.sections().iter().any(|section| {
+            section.address() < self.range.end
+                && self.range.start < section.address() + section.len()
+        })
+    }
+}
+
+/// Join `args` into a Windows command line which `CommandLineToArgvW` and the MSVC CRT split back
+/// into the same arguments. Backslashes are only special before a `"` so they are doubled there,
+/// and arguments which are empty or contain whitespace are quoted.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn command_line<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = String::new();
+    for arg in args {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        let quote = arg.is_empty() || arg.contains([' ', '\t']);
+        if quote {
+            line.push('"');
+        }
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    line.extend(std::iter::repeat_n('\\', backslashes + 1));
+                    backslashes = 0;
+                }
+                _ => backslashes = 0,
+            }
+            line.push(c);
+        }
+        if quote {
+            line.extend(std::iter::repeat_n('\\', backslashes));
+            line.push('"');
+        }
+    }
+    line
+}
+
 #[cfg(target_os = "linux")]
 pub use linux::*;
 
@@ -8,7 +111,8 @@ mod linux {
     use anyhow::{bail, Context, Result};
     use object::{Object, ObjectSection};
 
-    use crate::{image, Image, Memory};
+    use super::MemoryRegion;
+    use crate::{image, Image, Memory, SectionPermissions};
 
     fn read_process_mem(pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize> {
         unsafe {
@@ -38,34 +142,161 @@ mod linux {
     /// Read `/proc/<PID>/maps` and find region ending with ".exe" which is the main module for
     /// processes running under WINE
     fn find_main_module(pid: i32) -> Result<Range<usize>> {
+        memory_regions(pid)?
+            .into_iter()
+            .find(|region| region.path.as_deref().is_some_and(|p| p.ends_with(".exe")))
+            .map(|region| region.range)
+            .context("no main module found")
+    }
+
+    /// Path of the executable of the process on disk
+    pub fn exe_path(pid: i32) -> Result<std::path::PathBuf> {
+        memory_regions(pid)?
+            .into_iter()
+            .find_map(|region| region.path.filter(|p| p.ends_with(".exe")))
+            .map(Into::into)
+            .context("no main module found")
+    }
+
+    /// Memory regions of the process as listed in `/proc/<PID>/maps`
+    pub fn memory_regions(pid: i32) -> Result<Vec<MemoryRegion>> {
         let maps = std::fs::read_to_string(format!("/proc/{pid}/maps"))
             .with_context(|| format!("could not read process maps (PID={pid})"))?;
+        let mut regions = vec![];
         for line in maps.lines() {
             let mut split = line.splitn(6, |c: char| c.is_whitespace());
-            if let [Some(range), Some(_permissions), Some(_offset), Some(_device), Some(_inode), Some(path)] = [
+            let [Some(range), Some(permissions), Some(_offset), Some(_device), Some(_inode)] = [
                 split.next(),
                 split.next(),
                 split.next(),
                 split.next(),
                 split.next(),
-                split.next(),
-            ] {
-                if path.ends_with(".exe") {
-                    let (start, end) = range
-                        .split_once('-')
-                        .context("failed to parse map range: {range?}")?;
-                    let range = usize::from_str_radix(start, 16)?..usize::from_str_radix(end, 16)?;
-                    //println!("{range:x?} {permissions:?} {offset:?} {device:?} {inode:?} {:?}", path.trim_start());
-                    return Ok(range);
-                }
-            } else {
+            ] else {
                 bail!("failed to parse line of maps: {line:?}");
+            };
+            let (start, end) = range
+                .split_once('-')
+                .with_context(|| format!("failed to parse map range: {range:?}"))?;
+            let flags = permissions.as_bytes();
+            let path = split.next().map(str::trim_start).filter(|p| !p.is_empty());
+            regions.push(MemoryRegion {
+                range: usize::from_str_radix(start, 16)?..usize::from_str_radix(end, 16)?,
+                permissions: SectionPermissions::new(
+                    flags.first() == Some(&b'r'),
+                    flags.get(1) == Some(&b'w'),
+                    flags.get(2) == Some(&b'x'),
+                ),
+                path: path.map(str::to_string),
+            });
+        }
+        Ok(regions)
+    }
+
+    /// PIDs of the running processes of the executable named `exe_name`. Matches the file name
+    /// of the process executable and of its first command line argument so games running under
+    /// Wine/Proton are found too.
+    pub fn find_processes(exe_name: &str) -> Result<Vec<i32>> {
+        let file_name = |path: &str| -> String {
+            path.rsplit(['/', '\\'])
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+        };
+        let target = file_name(exe_name);
+
+        let mut pids = vec![];
+        for entry in std::fs::read_dir("/proc")?.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|p| p.parse::<i32>().ok())
+            else {
+                continue;
+            };
+            let exe = std::fs::read_link(entry.path().join("exe"))
+                .map(|p| file_name(&p.to_string_lossy()))
+                .unwrap_or_default();
+            let arg0 = std::fs::read(entry.path().join("cmdline"))
+                .ok()
+                .and_then(|c| {
+                    c.split(|b| *b == 0)
+                        .next()
+                        .map(|a| file_name(&String::from_utf8_lossy(a)))
+                })
+                .unwrap_or_default();
+            if exe == target || arg0 == target {
+                pids.push(pid);
+            }
+        }
+        Ok(pids)
+    }
+
+    /// Writable memory regions of the process (from `/proc/<PID>/maps`), e.g. for
+    /// [`PointerMap::build`](crate::pointer_scan::PointerMap::build)
+    pub fn writable_regions(pid: i32) -> Result<Vec<Range<usize>>> {
+        Ok(memory_regions(pid)?
+            .into_iter()
+            .filter(|region| region.permissions.allows(SectionPermissions::RW))
+            .map(|region| region.range)
+            .collect())
+    }
+
+    /// Stops the process with SIGSTOP and continues it on drop
+    struct SuspendGuard(i32);
+    impl SuspendGuard {
+        fn new(pid: i32) -> Result<Self> {
+            if unsafe { libc::kill(pid, libc::SIGSTOP) } != 0 {
+                bail!(
+                    "failed to suspend PID={pid}: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            Ok(Self(pid))
+        }
+    }
+    impl Drop for SuspendGuard {
+        fn drop(&mut self) {
+            unsafe { libc::kill(self.0, libc::SIGCONT) };
+        }
+    }
+
+    /// Live memory of another process, for reading engine objects outside of the image
+    #[derive(Debug, Clone, Copy)]
+    pub struct ProcessMemory {
+        pid: i32,
+    }
+    impl ProcessMemory {
+        pub fn new(pid: i32) -> Result<Self> {
+            Ok(Self { pid })
+        }
+    }
+    impl crate::ue::ReadMemory for ProcessMemory {
+        fn read_bytes(
+            &self,
+            address: usize,
+            buffer: &mut [u8],
+        ) -> std::result::Result<(), crate::MemoryAccessError> {
+            match read_process_mem(self.pid, address, buffer) {
+                Ok(read) if read == buffer.len() => Ok(()),
+                _ => Err(crate::MemoryAccessError::MemoryOutOfBoundsError),
             }
         }
-        bail!("no main module found")
     }
 
     pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
+        read_image_from_pid_with(pid, Default::default())
+    }
+
+    pub fn read_image_from_pid_with<'data>(
+        pid: i32,
+        options: super::ReadOptions,
+    ) -> Result<Image<'data>> {
+        options.check()?;
+        let _guard = options
+            .suspend
+            .then(|| SuspendGuard::new(pid))
+            .transpose()?;
+
         let main_module = find_main_module(pid)?;
 
         let mut image_header = vec![0; main_module.len()];
@@ -82,10 +313,12 @@ mod linux {
         }
 
         let memory = Memory::new_external_data(sections)?;
+        drop(_guard);
 
         image::pe::PEImage::read_inner_memory::<String>(
             object.relative_address_base() as usize,
             None,
+            true,
             false,
             memory,
             object,
@@ -100,11 +333,31 @@ pub use macos::*;
 mod macos {
     use anyhow::Result;
 
+    use super::MemoryRegion;
     use crate::Image;
 
+    pub fn memory_regions(_pid: i32) -> Result<Vec<MemoryRegion>> {
+        anyhow::bail!("listing memory regions is not supported on macOS")
+    }
+
+    pub fn exe_path(_pid: i32) -> Result<std::path::PathBuf> {
+        anyhow::bail!("locating process executables is not supported on macOS")
+    }
+
+    pub fn find_processes(_exe_name: &str) -> Result<Vec<i32>> {
+        anyhow::bail!("finding processes is not supported on macOS")
+    }
+
     pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
         todo!()
     }
+
+    pub fn read_image_from_pid_with<'data>(
+        _pid: i32,
+        _options: super::ReadOptions,
+    ) -> Result<Image<'data>> {
+        anyhow::bail!("reading process images is not supported on macOS")
+    }
 }
 
 #[cfg(windows)]
@@ -112,62 +365,283 @@ pub use windows::*;
 
 #[cfg(windows)]
 mod windows {
-    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    use anyhow::{bail, Context, Result};
     use object::{Object, ObjectSection};
 
+    use super::MemoryRegion;
     use crate::image::pe::PEImage;
-    use crate::{Image, Memory};
+    use crate::{Image, Memory, SectionPermissions};
 
-    use windows::Win32::Foundation::HMODULE;
+    use windows::core::{HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HMODULE};
     use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, Thread32First, Thread32Next,
+        PROCESSENTRY32W, TH32CS_SNAPPROCESS, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows::Win32::System::Memory::{
+        VirtualQueryEx, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE, PAGE_EXECUTE_READ,
+        PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS,
+        PAGE_PROTECTION_FLAGS, PAGE_READWRITE, PAGE_WRITECOPY,
+    };
     use windows::Win32::System::ProcessStatus::{
-        EnumProcessModules, GetModuleInformation, MODULEINFO,
+        EnumProcessModules, GetMappedFileNameW, GetModuleInformation, MODULEINFO,
     };
     use windows::Win32::System::Threading::{
-        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
+        CreateProcessW, OpenProcess, OpenThread, QueryFullProcessImageNameW, ResumeThread,
+        SuspendThread, TerminateProcess, CREATE_SUSPENDED, PROCESS_INFORMATION, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_INFORMATION, PROCESS_VM_READ, STARTUPINFOW, THREAD_SUSPEND_RESUME,
     };
 
-    pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
-        let (memory, base) = unsafe {
+    /// Suspends all threads of a process and resumes them on drop
+    struct SuspendGuard(Vec<HANDLE>);
+    impl SuspendGuard {
+        fn new(pid: i32) -> Result<Self> {
+            let mut threads = vec![];
+            unsafe {
+                let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)?;
+                let mut entry = THREADENTRY32 {
+                    dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+                    ..Default::default()
+                };
+                let mut next = Thread32First(snapshot, &mut entry).is_ok();
+                while next {
+                    if entry.th32OwnerProcessID == pid as u32 {
+                        if let Ok(thread) =
+                            OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID)
+                        {
+                            if SuspendThread(thread) != u32::MAX {
+                                threads.push(thread);
+                            } else {
+                                let _ = CloseHandle(thread);
+                            }
+                        }
+                    }
+                    next = Thread32Next(snapshot, &mut entry).is_ok();
+                }
+                let _ = CloseHandle(snapshot);
+            }
+            Ok(Self(threads))
+        }
+    }
+    impl Drop for SuspendGuard {
+        fn drop(&mut self) {
+            for thread in self.0.drain(..) {
+                unsafe {
+                    ResumeThread(thread);
+                    let _ = CloseHandle(thread);
+                }
+            }
+        }
+    }
+
+    /// Live memory of another process, for reading engine objects outside of the image
+    #[derive(Debug)]
+    pub struct ProcessMemory {
+        process: HANDLE,
+    }
+    impl ProcessMemory {
+        pub fn new(pid: i32) -> Result<Self> {
+            let process = unsafe { OpenProcess(PROCESS_VM_READ, false, pid as u32)? };
+            Ok(Self { process })
+        }
+    }
+    impl Drop for ProcessMemory {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = CloseHandle(self.process);
+            }
+        }
+    }
+    impl crate::ue::ReadMemory for ProcessMemory {
+        fn read_bytes(
+            &self,
+            address: usize,
+            buffer: &mut [u8],
+        ) -> std::result::Result<(), crate::MemoryAccessError> {
+            unsafe {
+                ReadProcessMemory(
+                    self.process,
+                    address as *const std::ffi::c_void,
+                    buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                    buffer.len(),
+                    None,
+                )
+            }
+            .map_err(|_| crate::MemoryAccessError::MemoryOutOfBoundsError)
+        }
+    }
+
+    /// PIDs of the running processes of the executable named `exe_name`
+    pub fn find_processes(exe_name: &str) -> Result<Vec<i32>> {
+        let target = exe_name.to_ascii_lowercase();
+        let mut pids = vec![];
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)?;
+            let mut entry = PROCESSENTRY32W {
+                dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+                ..Default::default()
+            };
+            let mut next = Process32FirstW(snapshot, &mut entry).is_ok();
+            while next {
+                let name = &entry.szExeFile;
+                let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+                if String::from_utf16_lossy(&name[..len]).to_ascii_lowercase() == target {
+                    pids.push(entry.th32ProcessID as i32);
+                }
+                next = Process32NextW(snapshot, &mut entry).is_ok();
+            }
+            let _ = CloseHandle(snapshot);
+        }
+        Ok(pids)
+    }
+
+    /// Committed memory regions of the process, excluding guard pages
+    pub fn memory_regions(pid: i32) -> Result<Vec<MemoryRegion>> {
+        let mut regions = vec![];
+        unsafe {
             let process = OpenProcess(
                 PROCESS_VM_READ | PROCESS_QUERY_INFORMATION,
                 false,
                 pid as u32,
             )?;
-
-            let mut modules = [Default::default(); 1];
-            let mut out_len = 0;
-            EnumProcessModules(
+            let mut address = 0usize;
+            let mut info = MEMORY_BASIC_INFORMATION::default();
+            while VirtualQueryEx(
                 process,
-                modules.as_mut_ptr(),
-                (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
-                &mut out_len,
-            )?;
-
-            if out_len < 1 {
-                bail!("expected at least one module");
+                Some(address as *const std::ffi::c_void),
+                &mut info,
+                std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            ) != 0
+            {
+                if info.State == MEM_COMMIT && (info.Protect & PAGE_GUARD).0 == 0 {
+                    let has = |flags: PAGE_PROTECTION_FLAGS| (info.Protect & flags).0 != 0;
+                    let permissions = SectionPermissions::new(
+                        !has(PAGE_NOACCESS | PAGE_EXECUTE),
+                        has(PAGE_READWRITE
+                            | PAGE_WRITECOPY
+                            | PAGE_EXECUTE_READWRITE
+                            | PAGE_EXECUTE_WRITECOPY),
+                        has(PAGE_EXECUTE
+                            | PAGE_EXECUTE_READ
+                            | PAGE_EXECUTE_READWRITE
+                            | PAGE_EXECUTE_WRITECOPY),
+                    );
+
+                    let mut name = [0u16; 1024];
+                    let len = GetMappedFileNameW(process, info.BaseAddress, &mut name) as usize;
+                    let path = (len != 0).then(|| String::from_utf16_lossy(&name[..len]));
+
+                    let start = info.BaseAddress as usize;
+                    regions.push(MemoryRegion {
+                        range: start..start + info.RegionSize,
+                        permissions,
+                        path,
+                    });
+                }
+                address = info.BaseAddress as usize + info.RegionSize;
             }
+            let _ = CloseHandle(process);
+        }
+        Ok(regions)
+    }
 
-            let mut info = MODULEINFO::default();
-            GetModuleInformation(
+    /// Path of the executable of the process on disk
+    pub fn exe_path(pid: i32) -> Result<std::path::PathBuf> {
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_INFORMATION, false, pid as u32)?;
+            let mut name = [0u16; 1024];
+            let mut len = name.len() as u32;
+            let res = QueryFullProcessImageNameW(
                 process,
-                modules[0],
-                &mut info,
-                std::mem::size_of::<MODULEINFO>() as u32,
-            )?;
+                PROCESS_NAME_WIN32,
+                PWSTR(name.as_mut_ptr()),
+                &mut len,
+            );
+            let _ = CloseHandle(process);
+            res?;
+            Ok(String::from_utf16_lossy(&name[..len as usize]).into())
+        }
+    }
 
-            let mut mem = vec![0u8; info.SizeOfImage as usize];
-            ReadProcessMemory(
-                process,
-                info.lpBaseOfDll,
-                mem.as_mut_ptr() as *mut std::ffi::c_void,
-                mem.len(),
-                None,
+    /// Committed writable memory regions of the process, e.g. for
+    /// [`PointerMap::build`](crate::pointer_scan::PointerMap::build)
+    pub fn writable_regions(pid: i32) -> Result<Vec<std::ops::Range<usize>>> {
+        Ok(memory_regions(pid)?
+            .into_iter()
+            .filter(|region| region.permissions.write)
+            .map(|region| region.range)
+            .collect())
+    }
+
+    pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
+        read_image_from_pid_with(pid, Default::default())
+    }
+
+    pub fn read_image_from_pid_with<'data>(
+        pid: i32,
+        options: super::ReadOptions,
+    ) -> Result<Image<'data>> {
+        options.check()?;
+        let guard = options
+            .suspend
+            .then(|| SuspendGuard::new(pid))
+            .transpose()?;
+
+        let (memory, base) = unsafe {
+            let process = OpenProcess(
+                PROCESS_VM_READ | PROCESS_QUERY_INFORMATION,
+                false,
+                pid as u32,
             )?;
 
-            (mem, info.lpBaseOfDll as usize)
+            let read = || -> Result<_> {
+                let mut modules = [Default::default(); 1];
+                let mut out_len = 0;
+                EnumProcessModules(
+                    process,
+                    modules.as_mut_ptr(),
+                    (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
+                    &mut out_len,
+                )?;
+
+                if out_len < 1 {
+                    bail!("expected at least one module");
+                }
+
+                let mut info = MODULEINFO::default();
+                GetModuleInformation(
+                    process,
+                    modules[0],
+                    &mut info,
+                    std::mem::size_of::<MODULEINFO>() as u32,
+                )?;
+
+                let mut mem = vec![0u8; info.SizeOfImage as usize];
+                ReadProcessMemory(
+                    process,
+                    info.lpBaseOfDll,
+                    mem.as_mut_ptr() as *mut std::ffi::c_void,
+                    mem.len(),
+                    None,
+                )?;
+
+                Ok((mem, info.lpBaseOfDll as usize))
+            };
+            let read = read();
+            let _ = CloseHandle(process);
+            read?
         };
 
+        drop(guard);
+
+        image_from_memory(memory, base)
+    }
+
+    /// Parse the image read in one piece from `base`
+    fn image_from_memory<'data>(memory: Vec<u8>, base: usize) -> Result<Image<'data>> {
         let object = object::File::parse(memory.as_slice())?;
 
         let mut sections = vec![];
@@ -182,6 +656,146 @@ mod windows {
 
         let memory = Memory::new_external_data(sections)?;
 
-        PEImage::read_inner_memory::<String>(base, None, false, memory, object)
+        PEImage::read_inner_memory::<String>(base, None, true, false, memory, object)
+    }
+
+    /// Read the image of a process whose module list isn't set up yet, such as one created
+    /// suspended, from the mapping of its executable named `exe_name`
+    pub fn read_image_from_suspended<'data>(pid: i32, exe_name: &str) -> Result<Image<'data>> {
+        let suffix = format!("\\{}", exe_name.to_ascii_lowercase());
+        let regions = memory_regions(pid)?
+            .into_iter()
+            .filter(|region| {
+                region
+                    .path
+                    .as_ref()
+                    .is_some_and(|path| path.to_ascii_lowercase().ends_with(&suffix))
+            })
+            .collect::<Vec<_>>();
+        let (Some(first), Some(last)) = (regions.first(), regions.last()) else {
+            bail!("{exe_name} is not mapped in PID={pid}");
+        };
+        let base = first.range.start;
+
+        let mut memory = vec![0u8; last.range.end - base];
+        unsafe {
+            let process = OpenProcess(PROCESS_VM_READ, false, pid as u32)?;
+            let read = ReadProcessMemory(
+                process,
+                base as *const std::ffi::c_void,
+                memory.as_mut_ptr() as *mut std::ffi::c_void,
+                memory.len(),
+                None,
+            );
+            let _ = CloseHandle(process);
+            read?;
+        }
+        image_from_memory(memory, base)
+    }
+
+    /// A process created suspended by [`launch_suspended`]. Its executable is mapped but none of
+    /// its code has run yet, not even DLL initializers. The process is terminated on drop unless
+    /// it was [resumed](Self::resume).
+    pub struct LaunchedProcess {
+        info: PROCESS_INFORMATION,
+        exe_name: String,
+        resumed: bool,
+    }
+    impl LaunchedProcess {
+        pub fn pid(&self) -> i32 {
+            self.info.dwProcessId as i32
+        }
+        /// Read the image of the executable, see [`read_image_from_suspended`]
+        pub fn read_image<'data>(&self) -> Result<Image<'data>> {
+            read_image_from_suspended(self.pid(), &self.exe_name)
+        }
+        /// Let the process start running
+        pub fn resume(mut self) -> Result<()> {
+            if unsafe { ResumeThread(self.info.hThread) } == u32::MAX {
+                bail!(
+                    "failed to resume PID={}: {}",
+                    self.pid(),
+                    std::io::Error::last_os_error()
+                );
+            }
+            self.resumed = true;
+            Ok(())
+        }
+    }
+    impl Drop for LaunchedProcess {
+        fn drop(&mut self) {
+            unsafe {
+                if !self.resumed {
+                    let _ = TerminateProcess(self.info.hProcess, 1);
+                }
+                let _ = CloseHandle(self.info.hThread);
+                let _ = CloseHandle(self.info.hProcess);
+            }
+        }
+    }
+
+    /// Start `exe` with `args` in its own directory, with the main thread suspended
+    pub fn launch_suspended(exe: &Path, args: &[String]) -> Result<LaunchedProcess> {
+        let exe_name = exe
+            .file_name()
+            .context("exe path has no file name")?
+            .to_string_lossy()
+            .to_string();
+        let exe_path = exe.display().to_string();
+        let mut command_line = super::command_line(
+            std::iter::once(exe_path.as_str()).chain(args.iter().map(String::as_str)),
+        )
+        .encode_utf16()
+        .chain([0])
+        .collect::<Vec<_>>();
+        let dir = exe
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| HSTRING::from(dir.as_os_str()));
+
+        let startup = STARTUPINFOW {
+            cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+            ..Default::default()
+        };
+        let mut info = PROCESS_INFORMATION::default();
+        unsafe {
+            CreateProcessW(
+                &HSTRING::from(exe.as_os_str()),
+                PWSTR(command_line.as_mut_ptr()),
+                None,
+                None,
+                false,
+                CREATE_SUSPENDED,
+                None,
+                dir.as_ref()
+                    .map_or(PCWSTR::null(), |dir| PCWSTR(dir.as_ptr())),
+                &startup,
+                &mut info,
+            )
+            .with_context(|| format!("failed to launch {}", exe.display()))?;
+        }
+        Ok(LaunchedProcess {
+            info,
+            exe_name,
+            resumed: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        assert_eq!(
+            command_line([r"C:\Games\My Game\Game.exe", "-log", ""]),
+            r#""C:\Games\My Game\Game.exe" -log """#
+        );
+        assert_eq!(command_line([r#"-name="a b""#]), r#""-name=\"a b\"""#);
+        // backslashes are literal unless followed by a quote
+        assert_eq!(command_line([r"a\b", r#"a\"b"#]), r#"a\b a\\\"b"#);
+        // a trailing backslash would escape the closing quote
+        assert_eq!(command_line([r"C:\My Dir\"]), r#""C:\My Dir\\""#);
     }
 }
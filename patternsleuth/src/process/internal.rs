@@ -104,7 +104,7 @@ mod linux {
             #[cfg(not(feature = "symbols"))]
             let exe_path: Option<std::path::PathBuf> = None;
             //eprintln!("Reading image internal");
-            Image::read(Some(base_addr), data, exe_path, false)
+            Image::read(Some(base_addr), data, exe_path, true)
         }
     }
 }
@@ -160,6 +160,6 @@ mod windows {
 
         let memory = Memory::new_internal_data(sections)?;
 
-        PEImage::read_inner_memory::<String>(image_base_address, None, false, memory, object)
+        PEImage::read_inner_memory::<String>(image_base_address, None, true, false, memory, object)
     }
 }
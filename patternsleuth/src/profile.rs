@@ -0,0 +1,169 @@
+//! Per-game profiles holding the knowledge needed to scan a specific title which can't be
+//! detected from its binaries.
+//!
+//! ```toml
+//! engine_version = "4.27"
+//! modules = ["Game-Win64-Shipping.dll"]
+//! base_address = "0x140000000"
+//! skip_resolvers = ["FNameToString"]
+//!
+//! [[patterns]]
+//! name = "GameInstance"
+//! pattern = "48 8b 0d ?? ?? ?? ?? 48 85 c9 74 ?? e8"
+//! expected_count = 1
+//! ```
+//!
+//! Profiles are looked up as `profile.toml` in the directory of the game, then as
+//! `<game name>.toml` in a profiles directory, see [`GameProfile::find`].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::pattern_set::{PatternEntry, PatternSet};
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GameProfile {
+    /// Engine version of the game, "major.minor". Only selects which of the profile's
+    /// [`patterns`](Self::patterns) apply, it does not override the `EngineVersion` resolver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_version: Option<String>,
+    /// File names of the binaries next to the executable to scan in addition to it, replacing
+    /// the automatically detected ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<String>,
+    /// Address to load the executable at instead of its preferred base. Accepts hex with a 0x
+    /// prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_address: Option<String>,
+    /// Names of resolvers known to produce wrong results for this game
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_resolvers: Vec<String>,
+    /// Game specific patterns, in the same format as a [`PatternSet`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<PatternEntry>,
+}
+
+impl GameProfile {
+    pub fn parse(s: &str) -> Result<Self> {
+        let profile: Self = toml::from_str(s)?;
+        profile.engine_version()?;
+        profile.base_address()?;
+        for entry in &profile.patterns {
+            entry
+                .pattern_config(())
+                .with_context(|| format!("invalid pattern {:?}", entry.name))?;
+        }
+        Ok(profile)
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("failed to load game profile {}", path.display()))
+    }
+    /// Find and load the profile of the game `name` whose executable is in `game_dir`. The game
+    /// directory is searched first, then each of `profiles_dirs` in order.
+    pub fn find<P: AsRef<Path>>(
+        name: &str,
+        game_dir: &Path,
+        profiles_dirs: &[P],
+    ) -> Result<Option<Self>> {
+        Self::candidates(name, game_dir, profiles_dirs)
+            .find(|path| path.is_file())
+            .map(Self::load)
+            .transpose()
+    }
+    fn candidates<'a, P: AsRef<Path>>(
+        name: &'a str,
+        game_dir: &Path,
+        profiles_dirs: &'a [P],
+    ) -> impl Iterator<Item = PathBuf> + 'a {
+        std::iter::once(game_dir.join("profile.toml")).chain(
+            profiles_dirs
+                .iter()
+                .map(move |dir| dir.as_ref().join(format!("{name}.toml"))),
+        )
+    }
+    /// The engine version as (major, minor)
+    pub fn engine_version(&self) -> Result<Option<(u16, u16)>> {
+        let Some(version) = &self.engine_version else {
+            return Ok(None);
+        };
+        let Some((major, minor)) = version.split_once('.') else {
+            bail!("engine version {version:?} is not \"major.minor\"");
+        };
+        Ok(Some((major.parse()?, minor.parse()?)))
+    }
+    pub fn base_address(&self) -> Result<Option<usize>> {
+        let Some(address) = &self.base_address else {
+            return Ok(None);
+        };
+        Ok(Some(match address.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16)?,
+            None => address.parse()?,
+        }))
+    }
+    /// Whether the resolver `name` should be skipped for this game
+    pub fn skips(&self, name: &str) -> bool {
+        self.skip_resolvers.iter().any(|s| s == name)
+    }
+    /// The game specific patterns as a pattern set, excluding those known not to work on the
+    /// profile's engine version
+    pub fn pattern_set(&self) -> PatternSet {
+        let version = self.engine_version().ok().flatten();
+        PatternSet {
+            name: "profile".into(),
+            author: None,
+            patterns: self
+                .patterns
+                .iter()
+                .filter(|p| version.is_none_or(|(ma, mi)| p.supports_version(ma, mi)))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = GameProfile::parse(
+            r#"
+            engine_version = "4.27"
+            modules = ["Game-Win64-Shipping.dll"]
+            base_address = "0x140000000"
+            skip_resolvers = ["FNameToString"]
+            [[patterns]]
+            name = "a"
+            pattern = "48 8b ?? c3"
+            [[patterns]]
+            name = "b"
+            pattern = "c3"
+            engine_versions = ["5"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(profile.engine_version().unwrap(), Some((4, 27)));
+        assert_eq!(profile.base_address().unwrap(), Some(0x140000000));
+        assert!(profile.skips("FNameToString"));
+        assert!(!profile.skips("GMalloc"));
+        assert_eq!(profile.pattern_set().patterns.len(), 1);
+
+        assert!(GameProfile::parse("engine_version = \"5\"").is_err());
+        assert!(GameProfile::parse("[[patterns]]\nname = \"c\"").is_err());
+        assert_eq!(GameProfile::parse("").unwrap(), GameProfile::default());
+
+        let candidates = GameProfile::candidates("Game", Path::new("games/Game"), &["profiles"])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            candidates,
+            [
+                PathBuf::from("games/Game/profile.toml"),
+                PathBuf::from("profiles/Game.toml")
+            ]
+        );
+    }
+}
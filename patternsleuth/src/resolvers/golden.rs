@@ -0,0 +1,129 @@
+//! Golden resolution results: resolutions recorded per game and resolver which later runs are
+//! checked against, so changes to shared helpers can't silently break games that used to
+//! resolve. Only resolutions which succeeded when recorded are checked; failures turning into
+//! successes are reported as improvements rather than regressions.
+
+use std::collections::BTreeMap;
+
+use super::ResolveError;
+
+/// Resolutions by game, then resolver name
+pub type Resolutions<T> = BTreeMap<String, BTreeMap<String, Result<T, ResolveError>>>;
+
+#[derive(Debug, PartialEq)]
+pub enum Regression<'a, T> {
+    /// The game or resolver was not part of the current run
+    Missing,
+    /// The resolver now fails
+    Failed(&'a ResolveError),
+    /// The resolver succeeds with a different value
+    Changed(&'a T),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GoldenCheck<'a, T> {
+    /// `(game, resolver, expected, regression)`
+    pub regressions: Vec<(&'a str, &'a str, &'a T, Regression<'a, T>)>,
+    /// `(game, resolver, new value)` of resolvers which failed when recorded but succeed now
+    pub improvements: Vec<(&'a str, &'a str, &'a T)>,
+}
+impl<T> GoldenCheck<'_, T> {
+    pub fn passed(&self) -> bool {
+        self.regressions.is_empty()
+    }
+}
+
+/// Compare `current` resolutions against `golden` ones. Games and resolvers only present in
+/// `current` are ignored.
+pub fn check<'a, T: PartialEq>(
+    golden: &'a Resolutions<T>,
+    current: &'a Resolutions<T>,
+) -> GoldenCheck<'a, T> {
+    let mut result = GoldenCheck {
+        regressions: vec![],
+        improvements: vec![],
+    };
+    for (game, resolutions) in golden {
+        for (resolver, expected) in resolutions {
+            let (game, resolver) = (game.as_str(), resolver.as_str());
+            let actual = current.get(game).and_then(|game| game.get(resolver));
+            match (expected, actual) {
+                (Ok(expected), None) => {
+                    result
+                        .regressions
+                        .push((game, resolver, expected, Regression::Missing));
+                }
+                (Ok(expected), Some(Err(err))) => {
+                    result
+                        .regressions
+                        .push((game, resolver, expected, Regression::Failed(err)));
+                }
+                (Ok(expected), Some(Ok(actual))) if expected != actual => {
+                    result.regressions.push((
+                        game,
+                        resolver,
+                        expected,
+                        Regression::Changed(actual),
+                    ));
+                }
+                (Err(_), Some(Ok(actual))) => result.improvements.push((game, resolver, actual)),
+                _ => {}
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolutions(entries: &[(&str, &str, Result<usize, &str>)]) -> Resolutions<usize> {
+        let mut resolutions = Resolutions::new();
+        for (game, resolver, res) in entries {
+            resolutions.entry(game.to_string()).or_default().insert(
+                resolver.to_string(),
+                res.map_err(|err| ResolveError::Msg(err.to_string().into())),
+            );
+        }
+        resolutions
+    }
+
+    #[test]
+    fn test_check() {
+        let golden = resolutions(&[
+            ("a", "GMalloc", Ok(0x10)),
+            ("a", "GUObjectArray", Ok(0x20)),
+            ("a", "FNamePool", Err("not found")),
+            ("b", "GMalloc", Ok(0x30)),
+            ("c", "GMalloc", Ok(0x40)),
+        ]);
+        let current = resolutions(&[
+            ("a", "GMalloc", Ok(0x10)),
+            ("a", "GUObjectArray", Ok(0x28)),
+            ("a", "FNamePool", Ok(0x50)),
+            ("a", "GEngine", Ok(0x60)),
+            ("b", "GMalloc", Err("broken")),
+        ]);
+
+        let result = check(&golden, &current);
+        assert!(!result.passed());
+        let regressions = result
+            .regressions
+            .iter()
+            .map(|(game, resolver, _, regression)| (*game, *resolver, regression))
+            .collect::<Vec<_>>();
+        let broken = ResolveError::Msg("broken".into());
+        assert_eq!(
+            regressions,
+            [
+                ("a", "GUObjectArray", &Regression::Changed(&0x28)),
+                ("b", "GMalloc", &Regression::Failed(&broken)),
+                ("c", "GMalloc", &Regression::Missing),
+            ]
+        );
+        assert_eq!(result.improvements, [("a", "FNamePool", &0x50)]);
+
+        assert!(check(&golden, &golden).passed());
+    }
+}
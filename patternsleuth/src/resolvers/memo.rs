@@ -0,0 +1,215 @@
+//! Remembering singleton resolutions across processes of the same game build, so attaching to
+//! a game repeatedly (e.g. while iterating on a mod) skips scanning until the build changes
+
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+use anyhow::{Context, Result};
+
+use super::Value;
+
+/// Addresses resolved by singleton resolvers keyed by
+/// [`Image::build_id`](crate::Image::build_id) and resolver name. Addresses are stored relative
+/// to the image base so they remain valid when a later process loads the image at a different
+/// address.
+///
+/// Pass to [`eval_memoized`](super::eval_memoized) or
+/// [`resolve_memoized`](super::resolve_memoized) to answer resolvers from the memo and record
+/// newly resolved ones. Resolvers returning several addresses (collectors,
+/// [`KismetSystemLibrary`](super::unreal::KismetSystemLibrary)) are always evaluated, but their
+/// singleton members are memoized. Resolvers opting in through
+/// [`AsyncContext::memoized_values`](super::AsyncContext::memoized_values) have their
+/// [`Values`](super::Values) memoized, e.g. layout probes.
+///
+/// The file format is one `<build id>\t<resolver>\t<rva>` line per address and one
+/// `<build id>\t<resolver>\t=<value>` line per value.
+#[derive(Debug, Default)]
+pub struct ResolutionMemo {
+    entries: Mutex<BTreeMap<(String, String), usize>>,
+    values: Mutex<BTreeMap<(String, String), Value>>,
+}
+impl ResolutionMemo {
+    /// Image relative address of `resolver` for build `build_id`
+    pub fn get(&self, build_id: &str, resolver: &str) -> Option<usize> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&(build_id.to_string(), resolver.to_string()))
+            .copied()
+    }
+    pub fn insert(&self, build_id: &str, resolver: &str, rva: usize) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert((build_id.to_string(), resolver.to_string()), rva);
+    }
+    /// Value `name` of a resolver for build `build_id`, named like
+    /// [`Values::prefixed`](super::Values::prefixed)
+    pub fn get_value(&self, build_id: &str, name: &str) -> Option<Value> {
+        self.values
+            .lock()
+            .unwrap()
+            .get(&(build_id.to_string(), name.to_string()))
+            .cloned()
+    }
+    pub fn insert_value(&self, build_id: &str, name: &str, value: Value) {
+        self.values
+            .lock()
+            .unwrap()
+            .insert((build_id.to_string(), name.to_string()), value);
+    }
+    /// Values of resolver `resolver` for build `build_id` keyed relative to the resolver name
+    pub fn get_values(&self, build_id: &str, resolver: &str) -> BTreeMap<String, Value> {
+        self.values
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((build, _), _)| build == build_id)
+            .filter_map(|((_, name), value)| {
+                let name = match name.strip_prefix(resolver)? {
+                    "" => "",
+                    name => name.strip_prefix('.')?,
+                };
+                Some((name.to_string(), value.clone()))
+            })
+            .collect()
+    }
+    /// Drop entries of every build other than `build_id`
+    pub fn retain_build(&self, build_id: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(build, _), _| build == build_id);
+        self.values
+            .lock()
+            .unwrap()
+            .retain(|(build, _), _| build == build_id);
+    }
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len() + self.values.lock().unwrap().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn parse(memo: &str) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        let mut values = BTreeMap::new();
+        for (i, line) in memo
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+        {
+            let mut split = line.split('\t');
+            let (Some(build_id), Some(resolver), Some(rva), None) =
+                (split.next(), split.next(), split.next(), split.next())
+            else {
+                anyhow::bail!("line {}: expected 3 tab separated fields", i + 1);
+            };
+            let key = (build_id.to_string(), resolver.to_string());
+            if let Some(value) = rva.strip_prefix('=') {
+                let value = value
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("line {}: {err}", i + 1))?;
+                values.insert(key, value);
+            } else {
+                let rva = usize::from_str_radix(rva.trim_start_matches("0x"), 16)
+                    .with_context(|| format!("line {}: bad address", i + 1))?;
+                entries.insert(key, rva);
+            }
+        }
+        Ok(Self {
+            entries: entries.into(),
+            values: values.into(),
+        })
+    }
+    /// Load memo from `path`, starting empty if it doesn't exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(memo) => Self::parse(&memo),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+impl std::fmt::Display for ResolutionMemo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ((build_id, resolver), rva) in self.entries.lock().unwrap().iter() {
+            writeln!(f, "{build_id}\t{resolver}\t{rva:#x}")?;
+        }
+        for ((build_id, name), value) in self.values.lock().unwrap().iter() {
+            writeln!(f, "{build_id}\t{name}\t={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let memo = ResolutionMemo::default();
+        memo.insert("5F3A2B1C4E00000", "GMalloc", 0x4c2d180);
+        memo.insert("5F3A2B1C4E00000", "GUObjectArray", 0x4c81a50);
+        memo.insert("60000000", "GMalloc", 0x4b00000);
+
+        let parsed = ResolutionMemo::parse(&memo.to_string()).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed.get("5F3A2B1C4E00000", "GMalloc"), Some(0x4c2d180));
+        assert_eq!(parsed.get("60000000", "GUObjectArray"), None);
+
+        parsed.retain_build("60000000");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.get("60000000", "GMalloc"), Some(0x4b00000));
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        let memo = ResolutionMemo::default();
+        memo.insert_value("60000000", "FNameSize", Value::Int(12));
+        memo.insert_value(
+            "60000000",
+            "UObjectBaseLayout.object_flags",
+            Value::Offset(8),
+        );
+        memo.insert_value(
+            "60000000",
+            "UObjectBaseLayout.internal_index",
+            Value::Offset(0xc),
+        );
+        memo.insert_value("60000000", "Name", Value::String("a\t\"b\"\u{1b}".into()));
+
+        let parsed = ResolutionMemo::parse(&memo.to_string()).unwrap();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(
+            parsed.get_value("60000000", "FNameSize"),
+            Some(Value::Int(12))
+        );
+        assert_eq!(
+            parsed.get_value("60000000", "Name"),
+            Some(Value::String("a\t\"b\"\u{1b}".into()))
+        );
+        assert_eq!(
+            parsed.get_values("60000000", "UObjectBaseLayout"),
+            [
+                ("internal_index".to_string(), Value::Offset(0xc)),
+                ("object_flags".to_string(), Value::Offset(8)),
+            ]
+            .into()
+        );
+        // only exact names or members separated by a dot
+        assert!(parsed.get_values("60000000", "FName").is_empty());
+        assert_eq!(parsed.get_values("60000000", "FNameSize").len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ResolutionMemo::parse("build\tGMalloc").is_err());
+        assert!(ResolutionMemo::parse("build\tGMalloc\tzz").is_err());
+        assert!(ResolutionMemo::parse("build\tFNameSize\t=+zz").is_err());
+        assert!(ResolutionMemo::parse("\n\n").unwrap().is_empty());
+    }
+}
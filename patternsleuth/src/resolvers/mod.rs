@@ -1,6 +1,13 @@
+pub mod golden;
+pub mod memo;
+pub mod progress;
+pub mod relax;
 pub mod unreal;
 
-use crate::{Image, MemoryAccessError};
+use crate::{
+    emulate::{Emulator, StopCondition},
+    Image, LiteralScan, MemoryAccessError, SectionPermissions, StringMatch, Utf16Scan,
+};
 use futures::{
     channel::oneshot,
     executor::LocalPool,
@@ -14,10 +21,16 @@ use patternsleuth_scanner::Pattern;
 use std::{
     any::{Any, TypeId},
     borrow::Cow,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     error::Error,
-    sync::{Arc, Mutex},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
+use tracing::Instrument;
 
 /// Given an iterator of values, returns Ok(value) if all values are equal or Err
 pub fn ensure_one<T: std::fmt::Debug + PartialEq>(data: impl IntoIterator<Item = T>) -> Result<T> {
@@ -64,12 +77,94 @@ pub type Result<T> = std::result::Result<T, ResolveError>;
 pub enum ResolveError {
     Msg(Cow<'static, str>),
     MemoryAccessOutOfBounds(MemoryAccessError),
+    /// A resolver needed function ranges of an image read without its exception data, see
+    /// [`ImageBuilder::functions`](crate::image::ImageBuilder::functions)
+    ExceptionDataUnavailable,
+    /// Resolver failed on an image whose code appears to be packed or encrypted
+    ImageObfuscated {
+        reason: String,
+        error: Box<ResolveError>,
+    },
+    /// A resolver failed, carrying the patterns it scanned (and their match counts) before
+    /// failing. Nested resolver failures form a chain from the outermost collector down to the
+    /// resolver which actually failed.
+    Resolver {
+        resolver: String,
+        scans: Vec<ScanSummary>,
+        error: Box<ResolveError>,
+    },
+}
+
+/// A pattern scanned by a resolver and the number of candidate addresses it matched
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ScanSummary {
+    pub pattern: String,
+    pub candidates: usize,
+}
+
+impl ResolveError {
+    /// Names of the resolvers the error passed through, outermost first
+    pub fn resolver_stack(&self) -> Vec<&str> {
+        let mut stack = vec![];
+        let mut err = self;
+        loop {
+            match err {
+                ResolveError::Resolver {
+                    resolver, error, ..
+                } => {
+                    stack.push(resolver.as_str());
+                    err = error;
+                }
+                ResolveError::ImageObfuscated { error, .. } => err = error,
+                _ => return stack,
+            }
+        }
+    }
+    /// The innermost error without any resolver attribution
+    pub fn root_cause(&self) -> &ResolveError {
+        match self {
+            ResolveError::Resolver { error, .. } | ResolveError::ImageObfuscated { error, .. } => {
+                error.root_cause()
+            }
+            _ => self,
+        }
+    }
 }
 impl std::fmt::Display for ResolveError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             ResolveError::Msg(msg) => write!(f, "{msg}"),
             ResolveError::MemoryAccessOutOfBounds(err) => err.fmt(f),
+            ResolveError::ExceptionDataUnavailable => {
+                MemoryAccessError::ExceptionDataUnavailable.fmt(f)
+            }
+            ResolveError::ImageObfuscated { reason, error } => write!(
+                f,
+                "{error} (image appears obfuscated: {reason}; try scanning the running process instead)"
+            ),
+            ResolveError::Resolver {
+                resolver,
+                scans,
+                error,
+            } => {
+                write!(f, "{resolver}: {error}")?;
+                // only the innermost resolver's scans are relevant to the failure
+                if !matches!(**error, ResolveError::Resolver { .. }) && !scans.is_empty() {
+                    write!(f, " [")?;
+                    for (i, scan) in scans.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{:?} matched {}", scan.pattern, scan.candidates)?;
+                    }
+                    write!(f, "]")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -77,15 +172,18 @@ impl Error for ResolveError {}
 
 impl From<MemoryAccessError> for ResolveError {
     fn from(value: MemoryAccessError) -> Self {
-        Self::MemoryAccessOutOfBounds(value)
+        match value {
+            MemoryAccessError::ExceptionDataUnavailable => Self::ExceptionDataUnavailable,
+            value => Self::MemoryAccessOutOfBounds(value),
+        }
     }
 }
 
 #[macro_export]
 macro_rules! _bail_out {
-    ($msg:expr) => {
+    ($msg:expr) => {{
         return Err($crate::resolvers::ResolveError::Msg($msg.into()));
-    };
+    }};
 }
 pub use _bail_out as bail_out;
 
@@ -114,11 +212,59 @@ pub fn resolvers() -> impl Iterator<Item = &'static NamedResolver> {
     inventory::iter::<NamedResolver>()
 }
 
+/// Resolvers a resolver evaluates through [`AsyncContext::resolve`]. Declared next to the
+/// resolver with [`resolver_dependencies!`], collectors declare their members automatically.
+pub struct ResolverDependencies {
+    pub name: &'static str,
+    /// Paths of the dependencies as written in the declaration
+    pub dependencies: &'static [&'static str],
+}
+
+inventory::collect!(ResolverDependencies);
+
+/// Declared direct dependencies of the resolver called `name`
+pub fn dependencies(name: &str) -> impl Iterator<Item = &'static str> + '_ {
+    inventory::iter::<ResolverDependencies>()
+        .filter(move |d| d.name == name)
+        .flat_map(|d| d.dependencies.iter().copied())
+        .map(|path| path.rsplit("::").next().unwrap_or(path).trim())
+}
+
+/// Every resolver evaluated when resolving `selected`, dependencies before their dependents.
+/// Lets callers see (and prune) what a subset of resolvers pulls in without running anything.
+pub fn resolution_plan(selected: &[&'static NamedResolver]) -> Vec<&'static NamedResolver> {
+    fn visit(
+        resolver: &'static NamedResolver,
+        visited: &mut HashSet<&'static str>,
+        plan: &mut Vec<&'static NamedResolver>,
+    ) {
+        if !visited.insert(resolver.name) {
+            return;
+        }
+        for dependency in dependencies(resolver.name) {
+            if let Some(dependency) = resolvers().find(|r| r.name == dependency) {
+                visit(dependency, visited, plan);
+            }
+        }
+        plan.push(resolver);
+    }
+
+    let mut visited = HashSet::new();
+    let mut plan = vec![];
+    for resolver in selected {
+        visit(resolver, &mut visited, &mut plan);
+    }
+    plan
+}
+
 type DynResolver<'ctx> = BoxFuture<'ctx, Result<Arc<dyn Resolution>>>;
 type Resolver<'ctx, T> = BoxFuture<'ctx, Result<T>>;
 
 #[cfg_attr(feature = "serde-resolvers", typetag::serde(tag = "type"))]
-pub trait Resolution: std::fmt::Debug + std::any::Any + Send + Sync + Singleton + DynEq {}
+pub trait Resolution:
+    std::fmt::Debug + std::any::Any + Send + Sync + Singleton + Multi + DynEq
+{
+}
 
 /// Allow comparison of dyn Resolution
 /// <https://users.rust-lang.org/t/how-to-compare-two-trait-objects-for-equality/88063/3>
@@ -219,7 +365,9 @@ macro_rules! _cfg_image_elf {
 
 #[macro_export]
 macro_rules! _impl_resolver {
-    (all, $name:ident, |$ctx:ident| async $x:block ) => {
+    // `multi` variants leave implementing `Multi` to the caller for resolutions containing
+    // several addresses
+    (all, multi, $name:ident, |$ctx:ident| async $x:block ) => {
         $crate::_impl_resolver_inner!($name, |$ctx| async $x);
 
         impl $crate::resolvers::Singleton for $name {
@@ -229,16 +377,7 @@ macro_rules! _impl_resolver {
         }
     };
 
-    ($arch:ident, $name:ident, |$ctx:ident| async $x:block ) => {
-        $crate::resolvers::cfg_image::$arch! {
-            impl $name where $name: $crate::resolvers::PleaseAddCollectForMe {
-                #[allow(non_snake_case)]
-                pub async fn $arch($ctx: &$crate::resolvers::AsyncContext<'_>) -> $crate::resolvers::Result<$name> $x
-            }
-        }
-    };
-
-    (collect, $name:ident) => {
+    (collect, multi, $name:ident) => {
         $crate::_impl_resolver_inner!($name, |ctx| async {
             $crate::image::image_type_reflection!(all, impl_resolver; generate; {ctx, $name})
         });
@@ -252,6 +391,27 @@ macro_rules! _impl_resolver {
         impl $crate::resolvers::PleaseAddCollectForMe for $name {}
     };
 
+    (all, $name:ident, |$ctx:ident| async $x:block ) => {
+        $crate::_impl_resolver!(all, multi, $name, |$ctx| async $x);
+
+        impl $crate::resolvers::Multi for $name {}
+    };
+
+    ($arch:ident, $name:ident, |$ctx:ident| async $x:block ) => {
+        $crate::resolvers::cfg_image::$arch! {
+            impl $name where $name: $crate::resolvers::PleaseAddCollectForMe {
+                #[allow(non_snake_case)]
+                pub async fn $arch($ctx: &$crate::resolvers::AsyncContext<'_>) -> $crate::resolvers::Result<$name> $x
+            }
+        }
+    };
+
+    (collect, $name:ident) => {
+        $crate::_impl_resolver!(collect, multi, $name);
+
+        impl $crate::resolvers::Multi for $name {}
+    };
+
     (generate, $enum_name_it:ident { $( $img_ident:ident( $img_ty:ty, $img_feature:literal )),* $(,)? }, {$ctx:ident, $name:ident}) => {
         $crate::resolvers::matcharm_generator!(
             $enum_name_it { $( $img_ident( $img_ty, $img_feature )),* },
@@ -267,7 +427,14 @@ macro_rules! _impl_resolver_singleton {
             if let Some(a) = std::env::var(concat!("PATTERNSLEUTH_RES_", stringify!($name))).ok().and_then(|s| (s.strip_prefix("0x").map(|s| usize::from_str_radix(s, 16).ok()).unwrap_or_else(|| s.parse().ok()))) {
                 return Ok($name(a));
             }
-            $x
+            if let Some(a) = $ctx.memoized(stringify!($name)) {
+                return Ok($name(a));
+            }
+            let res: $crate::resolvers::Result<$name> = async $x.await;
+            if let Ok(res) = &res {
+                $ctx.memoize(stringify!($name), res.0);
+            }
+            res
         });
 
         impl $crate::resolvers::Singleton for $name {
@@ -275,6 +442,12 @@ macro_rules! _impl_resolver_singleton {
                 Some(self.0)
             }
         }
+
+        impl $crate::resolvers::Multi for $name {
+            fn addresses(&self) -> $crate::resolvers::Addresses {
+                $crate::resolvers::Addresses::Indexed(vec![self.0])
+            }
+        }
     };
 
     ($arch:ident, $name:ident, |$ctx:ident| async $x:block ) => {
@@ -291,7 +464,14 @@ macro_rules! _impl_resolver_singleton {
             if let Some(a) = std::env::var(concat!("PATTERNSLEUTH_RES_", stringify!($name))).ok().and_then(|s| (s.strip_prefix("0x").map(|s| usize::from_str_radix(s, 16).ok()).unwrap_or_else(|| s.parse().ok()))) {
                 return Ok($name(a));
             }
-            $crate::image::image_type_reflection!(all, impl_resolver_singleton; generate; {ctx, $name})
+            if let Some(a) = ctx.memoized(stringify!($name)) {
+                return Ok($name(a));
+            }
+            let res: $crate::resolvers::Result<$name> = $crate::image::image_type_reflection!(all, impl_resolver_singleton; generate; {ctx, $name});
+            if let Ok(res) = &res {
+                ctx.memoize(stringify!($name), res.0);
+            }
+            res
         });
 
         impl $crate::resolvers::Singleton for $name {
@@ -300,6 +480,12 @@ macro_rules! _impl_resolver_singleton {
             }
         }
 
+        impl $crate::resolvers::Multi for $name {
+            fn addresses(&self) -> $crate::resolvers::Addresses {
+                $crate::resolvers::Addresses::Indexed(vec![self.0])
+            }
+        }
+
         impl $crate::resolvers::PleaseAddCollectForMe for $name {}
     };
 
@@ -364,7 +550,8 @@ macro_rules! _impl_try_collector {
                 $member_vis $member_name: ::std::sync::Arc<$resolver>,
             )*
         }
-        $crate::_impl_resolver!(all, $struct_name, |ctx| async {
+        $crate::resolvers::resolver_dependencies!($struct_name => [$( $resolver ),*]);
+        $crate::_impl_resolver!(all, multi, $struct_name, |ctx| async {
             #[allow(non_snake_case)]
             let (
                 $( $member_name, )*
@@ -375,6 +562,62 @@ macro_rules! _impl_try_collector {
                 $( $member_name, )*
             })
         });
+        impl $crate::resolvers::Multi for $struct_name {
+            fn addresses(&self) -> $crate::resolvers::Addresses {
+                let mut addresses = ::std::collections::BTreeMap::new();
+                $(
+                    addresses.extend($crate::resolvers::Multi::addresses(&*self.$member_name).prefixed(stringify!($member_name)));
+                )*
+                $crate::resolvers::Addresses::Named(addresses)
+            }
+            fn values(&self) -> $crate::resolvers::Values {
+                let mut values = ::std::collections::BTreeMap::new();
+                $(
+                    values.extend($crate::resolvers::Multi::values(&*self.$member_name).prefixed(stringify!($member_name)));
+                )*
+                $crate::resolvers::Values(values)
+            }
+        }
+    };
+
+    // additionally generate a partial collector which resolves every member independently so
+    // callers can inspect which members failed and degrade gracefully
+    (
+        $(#[$outer:meta])*
+        $struct_vis:vis struct $struct_name:ident {
+            $(
+                $(#[$inner:ident $($args:tt)*])*
+                $member_vis:vis $member_name:ident: $resolver:path,
+            )*
+        }
+        partial $partial_name:ident;
+    ) => {
+        $crate::_impl_try_collector! {
+            $(#[$outer])*
+            $struct_vis struct $struct_name {
+                $(
+                    $(#[$inner $($args)*])*
+                    $member_vis $member_name: $resolver,
+                )*
+            }
+        }
+        $crate::_impl_collector! {
+            $(#[$outer])*
+            $struct_vis struct $partial_name {
+                $(
+                    $(#[$inner $($args)*])*
+                    $member_vis $member_name: $resolver,
+                )*
+            }
+        }
+        impl $partial_name {
+            /// Convert into the complete collection, failing with the first member error
+            pub fn complete(self) -> $crate::resolvers::Result<$struct_name> {
+                Ok($struct_name {
+                    $( $member_name: self.$member_name?, )*
+                })
+            }
+        }
     };
 }
 
@@ -397,7 +640,8 @@ macro_rules! _impl_collector {
                 $member_vis $member_name: $crate::resolvers::Result<::std::sync::Arc<$resolver>>,
             )*
         }
-        $crate::_impl_resolver!(all, $struct_name, |ctx| async {
+        $crate::resolvers::resolver_dependencies!($struct_name => [$( $resolver ),*]);
+        $crate::_impl_resolver!(all, multi, $struct_name, |ctx| async {
             #[allow(non_snake_case)]
             let (
                 $( $member_name, )*
@@ -408,6 +652,56 @@ macro_rules! _impl_collector {
                 $( $member_name, )*
             })
         });
+        impl $crate::resolvers::Multi for $struct_name {
+            fn addresses(&self) -> $crate::resolvers::Addresses {
+                let mut addresses = ::std::collections::BTreeMap::new();
+                $(
+                    if let Ok(member) = &self.$member_name {
+                        addresses.extend($crate::resolvers::Multi::addresses(&**member).prefixed(stringify!($member_name)));
+                    }
+                )*
+                $crate::resolvers::Addresses::Named(addresses)
+            }
+            fn values(&self) -> $crate::resolvers::Values {
+                let mut values = ::std::collections::BTreeMap::new();
+                $(
+                    if let Ok(member) = &self.$member_name {
+                        values.extend($crate::resolvers::Multi::values(&**member).prefixed(stringify!($member_name)));
+                    }
+                )*
+                $crate::resolvers::Values(values)
+            }
+        }
+        impl $struct_name {
+            /// Names and errors of all members that failed to resolve
+            pub fn errors(&self) -> Vec<(&'static str, &$crate::resolvers::ResolveError)> {
+                let mut errors = vec![];
+                $(
+                    if let Err(err) = &self.$member_name {
+                        errors.push((stringify!($member_name), err));
+                    }
+                )*
+                errors
+            }
+        }
+    };
+}
+
+/// Declare the resolvers a resolver evaluates, e.g.
+/// `resolver_dependencies!(GMalloc => [GMallocPatterns, GMallocString]);`
+#[macro_export]
+macro_rules! _resolver_dependencies {
+    ($name:ident => [ $( $dependency:path ),* $(,)? ]) => {
+        $crate::resolvers::inventory::submit! {
+            $crate::resolvers::ResolverDependencies {
+                name: stringify!($name),
+                dependencies: &[ $( stringify!($dependency) ),* ],
+            }
+        }
+        // fail to compile if a dependency isn't a resolver
+        const _: () = {
+            $( let _ = <$dependency>::resolver; )*
+        };
     };
 }
 
@@ -416,6 +710,7 @@ pub use _impl_resolver as impl_resolver;
 pub use _impl_resolver_singleton as impl_resolver_singleton;
 pub use _impl_try_collector as impl_try_collector;
 pub use _matcharm_generator as matcharm_generator;
+pub use _resolver_dependencies as resolver_dependencies;
 pub mod cfg_image {
     pub use _cfg_image_elf as ElfImage;
     pub use _cfg_image_pe as PEImage;
@@ -425,6 +720,174 @@ pub trait Singleton {
     fn get(&self) -> Option<usize>;
 }
 
+/// Every address contained in a resolution so generic consumers can enumerate them without
+/// downcasting to the concrete type
+pub trait Multi {
+    fn addresses(&self) -> Addresses {
+        Addresses::Indexed(vec![])
+    }
+    /// Results which aren't addresses, e.g. vtable indexes or field offsets
+    fn values(&self) -> Values {
+        Values::default()
+    }
+}
+
+/// Result of a resolver which isn't an address
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Value {
+    /// Offset in bytes, e.g. of a field within a struct
+    Offset(usize),
+    /// Index into a table, e.g. a vtable slot
+    Index(usize),
+    Int(i64),
+    Bool(bool),
+    String(String),
+}
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Offset(offset) => write!(f, "+{offset:#x}"),
+            Self::Index(index) => write!(f, "[{index:#x}]"),
+            Self::Int(int) => write!(f, "{int}"),
+            Self::Bool(bool) => write!(f, "{bool}"),
+            Self::String(string) => write!(f, "{string:?}"),
+        }
+    }
+}
+/// Parses the [`Display`](std::fmt::Display) representation, e.g. for [`memo`] files
+impl std::str::FromStr for Value {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let hex = |s: &str| {
+            s.strip_prefix("0x")
+                .and_then(|s| usize::from_str_radix(s, 16).ok())
+                .ok_or_else(|| format!("bad hex number {s:?}"))
+        };
+        if let Some(offset) = s.strip_prefix('+') {
+            Ok(Self::Offset(hex(offset)?))
+        } else if let Some(index) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Ok(Self::Index(hex(index)?))
+        } else if let Some(string) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            unescape(string).map(Self::String)
+        } else if let Ok(bool) = s.parse() {
+            Ok(Self::Bool(bool))
+        } else {
+            s.parse()
+                .map(Self::Int)
+                .map_err(|_| format!("bad value {s:?}"))
+        }
+    }
+}
+/// Reverse of the escaping done by the `Debug` impl of `str`
+fn unescape(s: &str) -> std::result::Result<String, String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some('u') => {
+                let code = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|c| *c != '}')
+                    .collect::<String>();
+                u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("bad unicode escape {code:?}"))?
+            }
+            c => return Err(format!("bad escape {c:?}")),
+        });
+    }
+    Ok(unescaped)
+}
+
+/// Non address results of a resolution keyed by name, see [`Multi::values`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Values(pub std::collections::BTreeMap<String, Value>);
+impl Values {
+    /// A single value named after the resolution itself when prefixed
+    pub fn single(value: Value) -> Self {
+        Self([(String::new(), value)].into())
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Value `name`, `""` for a [`Values::single`] value
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+    /// Entries named relative to `prefix` like [`Addresses::prefixed`]
+    pub fn prefixed(&self, prefix: &str) -> Vec<(String, Value)> {
+        self.0
+            .iter()
+            .map(|(k, v)| match k.as_str() {
+                "" => (prefix.to_string(), v.clone()),
+                k => (format!("{prefix}.{k}"), v.clone()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Addresses {
+    Indexed(Vec<usize>),
+    Named(std::collections::BTreeMap<String, usize>),
+}
+impl Addresses {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Indexed(a) => a.len(),
+            Self::Named(a) => a.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// All addresses with their name (or index for indexed addresses)
+    pub fn entries(&self) -> Vec<(String, usize)> {
+        match self {
+            Self::Indexed(a) => a
+                .iter()
+                .enumerate()
+                .map(|(i, a)| (i.to_string(), *a))
+                .collect(),
+            Self::Named(a) => a.iter().map(|(k, a)| (k.clone(), *a)).collect(),
+        }
+    }
+    /// Entries named relative to `prefix`, used to flatten nested resolutions. A single indexed
+    /// address is named `prefix` itself.
+    pub fn prefixed(&self, prefix: &str) -> Vec<(String, usize)> {
+        match self {
+            Self::Indexed(a) if a.len() == 1 => vec![(prefix.to_string(), a[0])],
+            Self::Indexed(a) => a
+                .iter()
+                .enumerate()
+                .map(|(i, a)| (format!("{prefix}[{i}]"), *a))
+                .collect(),
+            Self::Named(a) => a
+                .iter()
+                .map(|(k, a)| (format!("{prefix}.{k}"), *a))
+                .collect(),
+        }
+    }
+}
+
 type AnyValue = Result<Arc<dyn Any + Send + Sync>>;
 
 #[derive(Debug)]
@@ -437,31 +900,158 @@ struct PatternMatches {
 struct AsyncContextInnerWrite {
     resolvers: HashMap<TypeId, AnyValue>,
     pending_resolvers: HashMap<TypeId, Vec<oneshot::Sender<AnyValue>>>,
-    queue: Vec<(Pattern, oneshot::Sender<PatternMatches>)>,
+    queue: Vec<(
+        Pattern,
+        Option<SectionPermissions>,
+        oneshot::Sender<PatternMatches>,
+    )>,
 }
 
 struct AsyncContextInnerRead<'data> {
     write: Mutex<AsyncContextInnerWrite>,
     image: &'data Image<'data>,
+    events: Option<EventHandler>,
+    /// Memo consulted by singleton resolvers along with the build id of `image`
+    memo: Option<(&'data memo::ResolutionMemo, String)>,
+}
+
+/// Progress events emitted while evaluating resolvers
+#[derive(Debug)]
+pub enum EvalEvent<'a> {
+    /// A resolver queued a pattern which will be scanned in the next stage
+    PatternQueued(&'a Pattern),
+    /// Started scanning all patterns queued since the previous stage
+    StageStarted { stage: usize, patterns: usize },
+    /// Finished scanning a section for the current stage
+    SectionScanned {
+        stage: usize,
+        section: &'a str,
+        matches: usize,
+    },
+    /// [`AsyncContext::scan_relaxed`] only matched after relaxing the pattern to `level`
+    PatternRelaxed { pattern: &'a Pattern, level: usize },
+    /// A resolver finished (resolvers are only evaluated once per eval)
+    ResolverFinished {
+        name: &'static str,
+        result: std::result::Result<(), &'a ResolveError>,
+        /// Highest relaxation level the resolver's [`AsyncContext::scan_relaxed`] calls needed,
+        /// 0 if it only used strict patterns
+        relaxation: usize,
+    },
+}
+
+/// Callback receiving [`EvalEvent`]s. Called from the thread running the eval.
+pub type EventHandler = Arc<dyn Fn(&EvalEvent) + Send + Sync>;
+
+/// Result of [`AsyncContext::scan_relaxed`]
+#[derive(Debug, Clone)]
+pub struct RelaxedMatches {
+    /// 0 if the strict pattern matched, otherwise the relaxation level which did
+    pub level: usize,
+    /// The pattern which produced `matches`
+    pub pattern: Pattern,
+    pub matches: Vec<usize>,
 }
 
 #[derive(Clone)]
 pub struct AsyncContext<'data> {
     read: Arc<AsyncContextInnerRead<'data>>,
+    /// Scans performed by the resolver this context was handed to, used for error attribution
+    scans: Arc<Mutex<Vec<ScanSummary>>>,
+    /// Name of the resolver this context was handed to, `None` for the root of an eval
+    resolver: Option<&'static str>,
+    /// Highest relaxation level [`AsyncContext::scan_relaxed`] needed for the resolver this
+    /// context was handed to
+    relaxation: Arc<AtomicUsize>,
 }
 
+/// Every `(resolver, dependency)` pair resolved through [`AsyncContext::resolve`] by the tests,
+/// checked against the declared [`dependencies`]
+#[cfg(test)]
+static RESOLVED_DEPENDENCIES: Mutex<std::collections::BTreeSet<(&'static str, &'static str)>> =
+    Mutex::new(std::collections::BTreeSet::new());
+
 impl<'data> AsyncContext<'data> {
-    fn new(image: &'data Image<'data>) -> Self {
+    fn new(
+        image: &'data Image<'data>,
+        events: Option<EventHandler>,
+        memo: Option<&'data memo::ResolutionMemo>,
+    ) -> Self {
+        let memo = memo.and_then(|memo| match image.build_id() {
+            Some(build_id) => Some((memo, build_id)),
+            None => {
+                tracing::warn!("image has no build id, not using resolution memo");
+                None
+            }
+        });
         Self {
             read: Arc::new(AsyncContextInnerRead {
                 write: Default::default(),
                 image,
+                events,
+                memo,
             }),
+            scans: Default::default(),
+            resolver: None,
+            relaxation: Default::default(),
+        }
+    }
+    /// Context for evaluating the nested resolver `resolver` with its own scan history
+    fn child(&self, resolver: &'static str) -> Self {
+        Self {
+            read: self.read.clone(),
+            scans: Default::default(),
+            resolver: Some(resolver),
+            relaxation: Default::default(),
+        }
+    }
+    fn emit(&self, event: EvalEvent) {
+        if let Some(events) = &self.read.events {
+            events(&event);
         }
     }
     pub fn image(&self) -> &Image<'_> {
         self.read.image
     }
+    /// Address of resolver `name` remembered from a previous evaluation on the same build, see
+    /// [`memo`]
+    pub fn memoized(&self, name: &str) -> Option<usize> {
+        let (memo, build_id) = self.read.memo.as_ref()?;
+        memo.get(build_id, name)
+            .map(|rva| self.image().rva_to_va(rva))
+    }
+    /// Remember `address` resolved by resolver `name` for later evaluations on the same build
+    pub fn memoize(&self, name: &str, address: usize) {
+        if let Some((memo, build_id)) = &self.read.memo {
+            if let Some(rva) = self.image().va_to_rva(address) {
+                memo.insert(build_id, name, rva);
+            }
+        }
+    }
+    /// Resolution of resolver `name` rebuilt by `from_values` from the [`Multi::values`]
+    /// remembered from a previous evaluation on the same build, otherwise evaluates `resolve`
+    /// and remembers its values. See [`memo`]
+    pub async fn memoized_values<T: Multi>(
+        &self,
+        name: &str,
+        from_values: impl FnOnce(&Values) -> Option<T>,
+        resolve: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some((memo, build_id)) = &self.read.memo else {
+            return resolve.await;
+        };
+        let memoized = Values(memo.get_values(build_id, name));
+        if !memoized.is_empty() {
+            if let Some(res) = from_values(&memoized) {
+                return Ok(res);
+            }
+        }
+        let res = resolve.await?;
+        for (name, value) in res.values().prefixed(name) {
+            memo.insert_value(build_id, &name, value);
+        }
+        Ok(res)
+    }
     pub async fn scan(&self, pattern: Pattern) -> Vec<usize> {
         self.scan_tagged((), pattern).await.2
     }
@@ -474,18 +1064,160 @@ impl<'data> AsyncContext<'data> {
             .collect()
     }
     pub async fn scan_tagged<T>(&self, tag: T, pattern: Pattern) -> (T, Pattern, Vec<usize>) {
+        self.scan_inner(tag, pattern, None).await
+    }
+    /// Scan for `pattern`, falling back to progressively [relaxed](relax::relaxations) versions
+    /// of it only if it matches nothing. Each fallback costs an extra scan stage so this is opt-in
+    /// for resolvers whose patterns are known to drift between versions.
+    pub async fn scan_relaxed(&self, pattern: Pattern) -> RelaxedMatches {
+        let relaxed = relax::relaxations(&pattern);
+        let matches = self.scan(pattern.clone()).await;
+        if !matches.is_empty() {
+            return RelaxedMatches {
+                level: 0,
+                pattern,
+                matches,
+            };
+        }
+        for (level, relaxed) in relaxed {
+            let matches = self.scan(relaxed.clone()).await;
+            if !matches.is_empty() {
+                self.relaxation.fetch_max(level, Ordering::Relaxed);
+                self.emit(EvalEvent::PatternRelaxed {
+                    pattern: &relaxed,
+                    level,
+                });
+                tracing::debug!("{pattern} only matched when relaxed to {relaxed}");
+                return RelaxedMatches {
+                    level,
+                    pattern: relaxed,
+                    matches,
+                };
+            }
+        }
+        RelaxedMatches {
+            level: 0,
+            pattern,
+            matches: vec![],
+        }
+    }
+    /// Scan only sections granting at least `permissions`, e.g. [`SectionPermissions::RW`] when
+    /// looking for globals
+    pub async fn scan_with_permissions(
+        &self,
+        pattern: Pattern,
+        permissions: SectionPermissions,
+    ) -> Vec<usize> {
+        self.scan_inner((), pattern, Some(permissions)).await.2
+    }
+    /// Scan only the virtual address `range`, e.g. a function found earlier, to disambiguate
+    /// code sequences repeated elsewhere in the image. The range is scanned directly rather than
+    /// queued with the patterns of the current stage, so keep it small.
+    pub async fn scan_in(&self, range: Range<usize>, pattern: Pattern) -> Vec<usize> {
+        let mut matches = vec![];
+        for section in self.image().memory.sections() {
+            let start = range.start.max(section.address());
+            let end = range.end.min(section.address() + section.len());
+            if start >= end {
+                continue;
+            }
+            let data = &section.data()[start - section.address()..end - section.address()];
+            matches.extend(
+                patternsleuth_scanner::scan_pattern(&[&pattern], start, data)
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+        self.record_scan(format!("{pattern} in {range:x?}"), matches.len());
+        matches
+    }
+    /// Find a null terminated UTF-16 string aligned to 2 bytes
+    pub async fn scan_utf16(&self, string: &str) -> Vec<StringMatch> {
+        self.scan_string(Utf16Scan::new(string).null_terminated(true))
+            .await
+    }
+    /// Find UTF-16 strings. Strings are searched directly rather than queued with the byte
+    /// patterns of the current stage.
+    pub async fn scan_string(&self, scan: Utf16Scan) -> Vec<StringMatch> {
+        let matches = self.image().memory.find_utf16(&scan);
+        self.record_scan(format!("{:?}", scan.string), matches.len());
+        matches
+    }
+    /// Find an encoded literal value such as a tick rate or magic number. Like strings,
+    /// literals are searched directly rather than queued with the byte patterns of the current
+    /// stage.
+    pub async fn scan_literal(&self, scan: LiteralScan) -> Vec<usize> {
+        let matches = self.image().memory.find_literal(&scan);
+        self.record_scan(format!("{:02x?}", scan.needle()), matches.len());
+        matches
+    }
+    /// Find a 4 byte aligned `f32` constant
+    pub async fn scan_f32(&self, value: f32) -> Vec<usize> {
+        self.scan_literal(LiteralScan::f32(value)).await
+    }
+    /// Find an 8 byte aligned `f64` constant
+    pub async fn scan_f64(&self, value: f64) -> Vec<usize> {
+        self.scan_literal(LiteralScan::f64(value)).await
+    }
+    /// Find a little endian `u32` at any alignment
+    pub async fn scan_u32(&self, value: u32) -> Vec<usize> {
+        self.scan_literal(LiteralScan::u32(value)).await
+    }
+    /// Find a little endian `u64` at any alignment
+    pub async fn scan_u64(&self, value: u64) -> Vec<usize> {
+        self.scan_literal(LiteralScan::u64(value)).await
+    }
+    async fn scan_inner<T>(
+        &self,
+        tag: T,
+        pattern: Pattern,
+        permissions: Option<SectionPermissions>,
+    ) -> (T, Pattern, Vec<usize>) {
         let (tx, rx) = oneshot::channel::<PatternMatches>();
+        self.emit(EvalEvent::PatternQueued(&pattern));
         {
             let mut lock = self.read.write.lock().unwrap();
-            lock.queue.push((pattern, tx));
+            lock.queue.push((pattern, permissions, tx));
         }
         let PatternMatches { pattern, matches } = rx.await.unwrap();
+        self.record_scan(pattern.to_string(), matches.len());
         (tag, pattern, matches)
     }
+    /// Discard matches which don't look like code, e.g. a pattern starting with wildcards
+    /// matching `int3` padding before a function. See [`Image::is_code`].
+    pub fn filter_code(&self, matches: Vec<usize>) -> Vec<usize> {
+        self.image().filter_code(matches)
+    }
+    /// Keep a scan for the error report of the current resolver, tracing it within the
+    /// resolver's span
+    fn record_scan(&self, pattern: String, candidates: usize) {
+        tracing::trace!(%pattern, candidates, "scanned");
+        self.scans.lock().unwrap().push(ScanSummary {
+            pattern,
+            candidates,
+        });
+    }
+    /// Emulate from `start` until one of `stop` is reached. Use [`Emulator`] directly to set up
+    /// registers before running.
+    pub fn emulate(&self, start: usize, stop: &[StopCondition]) -> Result<Emulator<'_, '_>> {
+        let mut emulator = Emulator::new(self.image(), start);
+        emulator.run(stop)?;
+        Ok(emulator)
+    }
     pub async fn resolve<T: Send + Sync + 'static>(
         &self,
         resolver: &ResolverFactory<T>,
     ) -> Result<Arc<T>> {
+        let name = std::any::type_name::<T>();
+        let short_name = name.rsplit("::").next().unwrap_or(name);
+        #[cfg(test)]
+        if let Some(resolver) = self.resolver {
+            RESOLVED_DEPENDENCIES
+                .lock()
+                .unwrap()
+                .insert((resolver, short_name));
+        }
+
         let t = TypeId::of::<T>();
         let rx = {
             // first check to see if we've already computed the resolver
@@ -516,8 +1248,26 @@ impl<'data> AsyncContext<'data> {
         }
 
         // compute the resolver value
-        let resolver = (resolver.factory)(self);
-        let res = resolver.await.map(Arc::new);
+        let span = tracing::debug_span!("resolver", name = short_name, parent = ?self.resolver);
+        let child = self.child(short_name);
+        let res = (resolver.factory)(&child)
+            .instrument(span.clone())
+            .await
+            .map(Arc::new);
+        let res = res.map_err(|error| {
+            span.in_scope(|| tracing::debug!("failed: {error}"));
+            ResolveError::Resolver {
+                resolver: short_name.to_string(),
+                scans: std::mem::take(&mut *child.scans.lock().unwrap()),
+                error: Box::new(error),
+            }
+        });
+
+        self.emit(EvalEvent::ResolverFinished {
+            name,
+            result: res.as_ref().map(|_| ()),
+            relaxation: child.relaxation.load(Ordering::Relaxed),
+        });
 
         let cache: Result<Arc<dyn Any + Send + Sync>> = match res.as_ref() {
             Ok(ok) => Ok(ok.clone()),
@@ -537,15 +1287,230 @@ impl<'data> AsyncContext<'data> {
     }
 }
 
-#[tracing::instrument(level = "debug", skip_all, fields(stages))]
+/// Log of every pattern scanned by resolvers and the addresses it matched, keyed by
+/// [`Image::build_id`] and the section permissions the scan was restricted to, so one log can
+/// hold the scans of several games.
+///
+/// Pass to [`eval_with_scan_log`] to record the scans of an eval or to answer them from the log
+/// instead of scanning the image, which allows reproducing resolver failures from a log recorded
+/// elsewhere. Memory reads performed by resolvers are not recorded and still go to the image.
+///
+/// Other evals use the log configured by the environment: when `PATTERNSLEUTH_SCAN_RECORD` is
+/// set to a path, the scans of every eval are appended to that file. When
+/// `PATTERNSLEUTH_SCAN_REPLAY` is set, every eval is answered from it.
+///
+/// The file format is one `<build id>\t<permissions>\t<pattern>\t<matches>` line per scan, with
+/// `-` for images without a build id, `*` for scans of all sections and comma separated hex
+/// addresses.
+#[derive(Debug, Default)]
+pub struct ScanLog {
+    entries: Mutex<BTreeMap<(String, String, String), Vec<usize>>>,
+}
+impl ScanLog {
+    fn key(
+        build_id: &str,
+        pattern: &Pattern,
+        permissions: Option<SectionPermissions>,
+    ) -> (String, String, String) {
+        (
+            build_id.to_string(),
+            permissions.map_or("*".to_string(), |p| p.to_string()),
+            pattern.to_string(),
+        )
+    }
+    pub fn get(
+        &self,
+        build_id: &str,
+        pattern: &Pattern,
+        permissions: Option<SectionPermissions>,
+    ) -> Option<Vec<usize>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&Self::key(build_id, pattern, permissions))
+            .cloned()
+    }
+    pub fn insert(
+        &self,
+        build_id: &str,
+        pattern: &Pattern,
+        permissions: Option<SectionPermissions>,
+        matches: Vec<usize>,
+    ) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(Self::key(build_id, pattern, permissions), matches);
+    }
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn parse(log: &str) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let mut entries = BTreeMap::new();
+        for (i, line) in log
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+        {
+            let mut split = line.split('\t');
+            let (Some(build_id), Some(permissions), Some(pattern), Some(matches), None) = (
+                split.next(),
+                split.next(),
+                split.next(),
+                split.next(),
+                split.next(),
+            ) else {
+                anyhow::bail!("line {}: expected 4 tab separated fields", i + 1);
+            };
+            let permissions = match permissions {
+                "*" => "*".to_string(),
+                p => p
+                    .parse::<SectionPermissions>()
+                    .with_context(|| format!("line {}: bad permissions", i + 1))?
+                    .to_string(),
+            };
+            let matches = matches
+                .split(',')
+                .filter(|m| !m.is_empty())
+                .map(|m| usize::from_str_radix(m.trim_start_matches("0x"), 16))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .with_context(|| format!("line {}: bad address", i + 1))?;
+            entries.insert(
+                (build_id.to_string(), permissions, pattern.to_string()),
+                matches,
+            );
+        }
+        Ok(Self {
+            entries: entries.into(),
+        })
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+    /// Append to the log at `path`, entries already in the file are superseded when loaded
+    pub fn append<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        use std::io::Write;
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(self.to_string().as_bytes())
+    }
+}
+impl std::fmt::Display for ScanLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ((build_id, permissions, pattern), matches) in self.entries.lock().unwrap().iter() {
+            write!(f, "{build_id}\t{permissions}\t{pattern}\t")?;
+            for (i, m) in matches.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{m:#x}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// What [`eval_with_scan_log`] does with its [`ScanLog`]
+#[derive(Debug, Clone, Copy)]
+pub enum ScanLogMode<'a> {
+    /// Scan the image and record every scan in the log
+    Record(&'a ScanLog),
+    /// Answer every scan from the log without scanning the image. Patterns missing from the log
+    /// match nothing.
+    Replay(&'a ScanLog),
+}
+
+/// Scan log configured by `PATTERNSLEUTH_SCAN_REPLAY` or `PATTERNSLEUTH_SCAN_RECORD`
+enum EnvScanLog {
+    Record(PathBuf, ScanLog),
+    Replay(ScanLog),
+}
+impl EnvScanLog {
+    fn from_env() -> Option<Self> {
+        if let Some(path) = std::env::var_os("PATTERNSLEUTH_SCAN_REPLAY") {
+            match ScanLog::load(&path) {
+                Ok(log) => return Some(Self::Replay(log)),
+                Err(err) => tracing::warn!("failed to load scan log {path:?}: {err:#}"),
+            }
+        }
+        std::env::var_os("PATTERNSLEUTH_SCAN_RECORD")
+            .map(|path| Self::Record(path.into(), Default::default()))
+    }
+    fn mode(&self) -> ScanLogMode<'_> {
+        match self {
+            Self::Record(_, log) => ScanLogMode::Record(log),
+            Self::Replay(log) => ScanLogMode::Replay(log),
+        }
+    }
+}
+
 pub fn eval<F, T: Send + Sync>(image: &Image<'_>, f: F) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_with_events(image, None, f)
+}
+
+pub fn eval_with_events<F, T: Send + Sync>(
+    image: &Image<'_>,
+    events: Option<EventHandler>,
+    f: F,
+) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_inner(image, events, None, None, f)
+}
+
+/// Same as [`eval`] but recording scans to `scan_log` or answering them from it, see
+/// [`ScanLog`]
+pub fn eval_with_scan_log<F, T: Send + Sync>(
+    image: &Image<'_>,
+    scan_log: ScanLogMode<'_>,
+    f: F,
+) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_inner(image, None, None, Some(scan_log), f)
+}
+
+/// Same as [`eval`] but answers singleton resolvers from `memo` if it holds their address for
+/// the build of `image` and records the ones resolved by scanning
+pub fn eval_memoized<F, T: Send + Sync>(image: &Image<'_>, memo: &memo::ResolutionMemo, f: F) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_inner(image, None, Some(memo), None, f)
+}
+
+#[tracing::instrument(level = "debug", skip_all, fields(stages))]
+fn eval_inner<F, T: Send + Sync>(
+    image: &Image<'_>,
+    events: Option<EventHandler>,
+    memo: Option<&memo::ResolutionMemo>,
+    scan_log: Option<ScanLogMode<'_>>,
+    f: F,
+) -> T
 where
     F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
 {
     {
         tracing::debug!("starting eval");
 
-        let ctx = AsyncContext::new(image);
+        let ctx = AsyncContext::new(image, events, memo);
         let (rx, tx) = std::sync::mpsc::channel();
 
         let scope = new_relay_scope!();
@@ -562,9 +1527,13 @@ where
             })
             .unwrap();
 
+        let env_scan_log = scan_log.is_none().then(EnvScanLog::from_env).flatten();
+        let scan_log = scan_log.or(env_scan_log.as_ref().map(EnvScanLog::mode));
+        let build_id = image.build_id().unwrap_or_else(|| "-".to_string());
+
         let mut i = 0;
 
-        loop {
+        let res = loop {
             i += 1;
 
             tracing::debug_span!("resolvers", stage = i).in_scope(|| {
@@ -575,16 +1544,57 @@ where
                 tracing::Span::current().record("stages", i);
                 break res;
             } else {
-                let queue: Vec<_> = std::mem::take(&mut ctx.read.write.lock().unwrap().queue);
-                let (patterns, rx): (Vec<_>, Vec<_>) = queue.into_iter().unzip();
+                let mut queue: Vec<_> = std::mem::take(&mut ctx.read.write.lock().unwrap().queue);
+
+                // answer all patterns from the replay log without touching the image
+                if let Some(ScanLogMode::Replay(log)) = scan_log {
+                    for (pattern, permissions, tx) in std::mem::take(&mut queue) {
+                        let matches =
+                            log.get(&build_id, &pattern, permissions)
+                                .unwrap_or_else(|| {
+                                    tracing::warn!("pattern not in scan log: {pattern}");
+                                    vec![]
+                                });
+                        tx.send(PatternMatches { pattern, matches }).unwrap();
+                    }
+                    continue;
+                }
+
+                // scan identical patterns queued by different resolvers only once and fan out
+                // the results to every waiter
+                let mut unique: Vec<(Pattern, Option<SectionPermissions>, Vec<_>)> = vec![];
+                let mut unique_index: HashMap<(String, Option<SectionPermissions>), usize> =
+                    Default::default();
+                for (pattern, permissions, tx) in queue {
+                    let index = *unique_index
+                        .entry((pattern.to_string(), permissions))
+                        .or_insert_with(|| {
+                            unique.push((pattern.clone(), permissions, vec![]));
+                            unique.len() - 1
+                        });
+                    unique[index].2.push((pattern, tx));
+                }
+
+                let (patterns, (permissions, waiters)): (Vec<_>, (Vec<_>, Vec<_>)) = unique
+                    .into_iter()
+                    .map(|(pattern, permissions, waiters)| (pattern, (permissions, waiters)))
+                    .unzip();
                 let setup = patterns.iter().collect::<Vec<_>>();
 
+                ctx.emit(EvalEvent::StageStarted {
+                    stage: i,
+                    patterns: setup.len(),
+                });
+
                 let span = tracing::debug_span!("patterns", patterns = setup.len()).entered();
                 for p in &setup {
                     tracing::debug!("pattern = {p:?}");
                 }
 
-                let mut all_results = rx.into_iter().map(|rx| (rx, vec![])).collect::<Vec<_>>();
+                let mut all_results = waiters
+                    .into_iter()
+                    .map(|waiters| (waiters, vec![]))
+                    .collect::<Vec<_>>();
 
                 for section in image.memory.sections() {
                     let span = tracing::debug_span!(
@@ -598,26 +1608,61 @@ where
                     let base_address = section.address();
                     let data = section.data();
 
+                    // skip patterns restricted to sections with other permissions
+                    let indexes = permissions
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, p)| p.map(|p| section.permissions().allows(p)).unwrap_or(true))
+                        .map(|(i, _)| i)
+                        .collect::<Vec<_>>();
+                    let section_setup = indexes.iter().map(|i| setup[*i]).collect::<Vec<_>>();
+
                     let scan_results =
-                        patternsleuth_scanner::scan_pattern(&setup, base_address, data);
+                        patternsleuth_scanner::scan_pattern(&section_setup, base_address, data);
 
                     let mut total = 0;
 
-                    for (i, res) in scan_results.iter().enumerate() {
+                    for (i, res) in indexes.into_iter().zip(scan_results) {
                         total += res.len();
                         all_results[i].1.extend(res)
                     }
 
                     span.record("results", total);
+
+                    ctx.emit(EvalEvent::SectionScanned {
+                        stage: i,
+                        section: section.name(),
+                        matches: total,
+                    });
                 }
 
                 drop(span);
 
-                for ((rx, matches), pattern) in all_results.into_iter().zip(patterns) {
-                    rx.send(PatternMatches { pattern, matches }).unwrap();
+                for (((waiters, matches), pattern), permissions) in
+                    all_results.into_iter().zip(patterns).zip(permissions)
+                {
+                    if let Some(ScanLogMode::Record(log)) = scan_log {
+                        log.insert(&build_id, &pattern, permissions, matches.clone());
+                    }
+                    for (pattern, tx) in waiters {
+                        tx.send(PatternMatches {
+                            pattern,
+                            matches: matches.clone(),
+                        })
+                        .unwrap();
+                    }
                 }
             }
+        };
+
+        // appended so the scans of other evals recorded to the same file are kept
+        if let Some(EnvScanLog::Record(path, log)) = &env_scan_log {
+            if let Err(err) = log.append(path) {
+                tracing::warn!("failed to write scan log {path:?}: {err}");
+            }
         }
+
+        res
     }
 }
 
@@ -625,16 +1670,487 @@ pub fn resolve<T: Send + Sync>(
     image: &Image<'_>,
     resolver: &'static ResolverFactory<T>,
 ) -> Result<T> {
-    eval(image, |ctx| Box::pin(async { ctx.resolve(resolver).await }))
-        .map(|ok| Arc::<T>::into_inner(ok).unwrap())
+    resolve_with_events(image, resolver, None)
+}
+
+/// Same as [`resolve`] but using `memo`, see [`eval_memoized`]
+pub fn resolve_memoized<T: Send + Sync>(
+    image: &Image<'_>,
+    resolver: &'static ResolverFactory<T>,
+    memo: &memo::ResolutionMemo,
+) -> Result<T> {
+    resolve_memoized_with_events(image, resolver, memo, None)
+}
+
+/// Same as [`resolve_memoized`] but reporting progress to `events`. Resolvers answered from the
+/// memo are reported as finished without any scans.
+pub fn resolve_memoized_with_events<T: Send + Sync>(
+    image: &Image<'_>,
+    resolver: &'static ResolverFactory<T>,
+    memo: &memo::ResolutionMemo,
+    events: Option<EventHandler>,
+) -> Result<T> {
+    eval_inner(image, events, Some(memo), None, |ctx| {
+        Box::pin(async { ctx.resolve(resolver).await })
+    })
+    .map(|ok| Arc::<T>::into_inner(ok).unwrap())
+}
+
+pub fn resolve_with_events<T: Send + Sync>(
+    image: &Image<'_>,
+    resolver: &'static ResolverFactory<T>,
+    events: Option<EventHandler>,
+) -> Result<T> {
+    eval_with_events(image, events, |ctx| {
+        Box::pin(async { ctx.resolve(resolver).await })
+    })
+    .map(|ok| Arc::<T>::into_inner(ok).unwrap())
+    .map_err(|err| explain_error(image, err, &mut None))
+}
+
+/// Wrap resolver errors in [`ResolveError::ImageObfuscated`] if the image looks packed since
+/// resolver specific errors are meaningless in that case. `report` caches the analysis.
+fn explain_error(
+    image: &Image<'_>,
+    error: ResolveError,
+    report: &mut Option<Option<crate::image::ObfuscationReport>>,
+) -> ResolveError {
+    if matches!(error, ResolveError::ImageObfuscated { .. }) {
+        return error;
+    }
+    match report.get_or_insert_with(|| image.detect_obfuscation()) {
+        Some(report) => ResolveError::ImageObfuscated {
+            reason: report.to_string(),
+            error: error.into(),
+        },
+        None => error,
+    }
 }
 
 pub fn resolve_many(
     image: &Image<'_>,
     resolvers: &[fn() -> &'static DynResolverFactory],
+) -> Vec<Result<Arc<dyn Resolution>>> {
+    resolve_many_with_events(image, resolvers, None)
+}
+
+pub fn resolve_many_with_events(
+    image: &Image<'_>,
+    resolvers: &[fn() -> &'static DynResolverFactory],
+    events: Option<EventHandler>,
 ) -> Vec<Result<Arc<dyn Resolution>>> {
     let fns = resolvers.iter().map(|r| r().factory).collect::<Vec<_>>();
-    eval(image, |ctx| {
+    let mut report = None;
+    eval_with_events(image, events, |ctx| {
         Box::pin(async { join_all(fns.into_iter().map(|f| f(ctx))).await })
     })
+    .into_iter()
+    .map(|res| res.map_err(|err| explain_error(image, err, &mut report)))
+    .collect()
+}
+
+/// Results of [`resolve_map`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResolvedMap {
+    /// Addresses of resolvers resolving to a single address keyed by resolver name
+    pub addresses: HashMap<&'static str, usize>,
+    /// Results which aren't addresses (see [`Multi::values`]) keyed by resolver name followed by
+    /// the value name if the resolver returns several
+    pub values: HashMap<String, Value>,
+}
+
+/// Addresses and values of `resolvers` keyed by name, e.g. for dumping, caching or handing
+/// across FFI without declaring a collector. Resolvers which fail, or neither resolve to a single
+/// address nor to values, are left out.
+pub fn resolve_map(image: &Image<'_>, resolvers: &[&'static NamedResolver]) -> ResolvedMap {
+    let getters = resolvers.iter().map(|r| r.getter).collect::<Vec<_>>();
+    let mut map = ResolvedMap::default();
+    for (resolver, res) in resolvers.iter().zip(resolve_many(image, &getters)) {
+        let Ok(res) = res else { continue };
+        if let Some(address) = res.get() {
+            map.addresses.insert(resolver.name, address);
+        }
+        map.values.extend(res.values().prefixed(resolver.name));
+    }
+    map
+}
+
+/// A resolution along with the index of the module it came from, see [`resolve_many_modules`]
+pub type ModuleResolution = (usize, Arc<dyn Resolution>);
+
+/// Resolve against every module of a game which ships its engine separately from the executable
+/// (e.g. a small `Game.exe` launching `Game-Win64-Shipping.dll`). Modules are tried in order of
+/// code size since the engine module dwarfs launchers and plugins, and each resolver keeps the
+/// first success along with the index of the module it came from. Resolvers failing everywhere
+/// report the error from the most likely module.
+pub fn resolve_many_modules(
+    modules: &[&Image<'_>],
+    resolvers: &[fn() -> &'static DynResolverFactory],
+) -> Vec<Result<ModuleResolution>> {
+    let code_size = |image: &Image<'_>| -> usize {
+        image
+            .memory
+            .sections()
+            .iter()
+            .filter(|s| s.permissions().execute)
+            .map(|s| s.len())
+            .sum()
+    };
+    let mut order = (0..modules.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| std::cmp::Reverse(code_size(modules[*i])));
+
+    let mut results: Vec<Option<Result<ModuleResolution>>> =
+        resolvers.iter().map(|_| None).collect();
+    for module in order {
+        let pending = (0..resolvers.len())
+            .filter(|i| !matches!(results[*i], Some(Ok(_))))
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            break;
+        }
+        let subset = pending.iter().map(|i| resolvers[*i]).collect::<Vec<_>>();
+        for (i, res) in pending
+            .into_iter()
+            .zip(resolve_many(modules[module], &subset))
+        {
+            match res {
+                Ok(res) => results[i] = Some(Ok((module, res))),
+                Err(err) => {
+                    results[i].get_or_insert(Err(err));
+                }
+            }
+        }
+    }
+    results
+        .into_iter()
+        .map(|res| res.unwrap_or_else(|| Err(ResolveError::Msg("no modules to resolve".into()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_declared_dependencies_exist() {
+        for declaration in inventory::iter::<ResolverDependencies>() {
+            for dependency in dependencies(declaration.name) {
+                assert!(
+                    resolvers().any(|r| r.name == dependency),
+                    "{} depends on unknown resolver {dependency}",
+                    declaration.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolved_dependencies_declared() {
+        let base = 0x140000000;
+        let image = crate::testing::TestImageBuilder::new(base)
+            .section(".text", object::SectionKind::Text, base + 0x1000, 0x1000)
+            .section(
+                ".rdata",
+                object::SectionKind::ReadOnlyData,
+                base + 0x2000,
+                0x1000,
+            )
+            .build()
+            .unwrap();
+        let getters = resolvers().map(|r| r.getter).collect::<Vec<_>>();
+        resolve_many(&image, &getters);
+
+        let undeclared = RESOLVED_DEPENDENCIES
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(resolver, dependency)| !dependencies(resolver).any(|d| d == *dependency))
+            .copied()
+            .collect::<Vec<_>>();
+        assert!(
+            undeclared.is_empty(),
+            "resolved without declaring the dependency: {undeclared:?}"
+        );
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_relaxed() {
+        use crate::resolvers::unreal::kismet::FFrameStep;
+
+        let base = 0x140000000;
+        let f = base + 0x1000;
+        // FFrame::Step with FFrame::Code at 0x28 instead of 0x20
+        let step = Pattern::new("48 8B 41 28 4C 8B D2 48 8B D1 44 0F B6 08 48 FF C0 48 89 41 28 41 8B C1 4C 8D 0D 00 00 00 00 49 8B CA 49 FF 24 C1").unwrap();
+        let image = crate::testing::TestImageBuilder::new(base)
+            .section(".text", object::SectionKind::Text, f, 0x1000)
+            .write(f, &step.simple.sig)
+            .build()
+            .unwrap();
+
+        let (strict, relaxed) = eval(&image, |ctx| {
+            Box::pin(async move {
+                futures::join!(
+                    ctx.scan_relaxed(Pattern::new("48 8B 41 28").unwrap()),
+                    ctx.scan_relaxed(Pattern::new("48 8B 41 20").unwrap()),
+                )
+            })
+        });
+        assert_eq!((strict.level, strict.matches), (0, vec![f]));
+        assert_eq!((relaxed.level, relaxed.matches), (1, vec![f]));
+        assert_eq!(relaxed.pattern, Pattern::new("48 8B 41 ??").unwrap());
+
+        let levels = Arc::new(Mutex::new(vec![]));
+        let l = levels.clone();
+        let events: EventHandler = Arc::new(move |event: &EvalEvent| {
+            if let EvalEvent::ResolverFinished {
+                name, relaxation, ..
+            } = event
+            {
+                l.lock().unwrap().push((*name, *relaxation));
+            }
+        });
+        let step = resolve_with_events(&image, FFrameStep::resolver(), Some(events)).unwrap();
+        assert_eq!(step, FFrameStep(f));
+        assert_eq!(
+            *levels.lock().unwrap(),
+            [(std::any::type_name::<FFrameStep>(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_values_prefixed() {
+        assert_eq!(
+            Values::single(Value::Index(0x4c)).prefixed("ProcessEvent"),
+            [("ProcessEvent".to_string(), Value::Index(0x4c))]
+        );
+        let values = Values([("offset".to_string(), Value::Offset(8))].into());
+        assert_eq!(
+            values.prefixed("Object"),
+            [("Object.offset".to_string(), Value::Offset(8))]
+        );
+        assert_eq!(Value::Index(0x4c).to_string(), "[0x4c]");
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        for value in [
+            Value::Offset(0xc),
+            Value::Index(0x4c),
+            Value::Int(-12),
+            Value::Bool(true),
+            Value::String("\"quoted\"\n\\ \u{1b}".into()),
+        ] {
+            assert_eq!(value.to_string().parse(), Ok(value));
+        }
+        assert!("+12".parse::<Value>().is_err());
+        assert!("\"\\x\"".parse::<Value>().is_err());
+    }
+
+    #[test]
+    fn test_resolution_plan() {
+        let name_reader = resolvers().find(|r| r.name == "NameReader").unwrap();
+        let plan = resolution_plan(&[name_reader])
+            .into_iter()
+            .map(|r| r.name)
+            .collect::<Vec<_>>();
+        let position = |name| plan.iter().position(|r| *r == name).unwrap();
+        assert!(position("FNameToStringVoid") < position("FNameToString"));
+        assert!(position("FNameToString") < position("NameReader"));
+        assert!(position("FNamePool") < position("NameReader"));
+        assert_eq!(plan.last(), Some(&"NameReader"));
+    }
+
+    #[test]
+    fn test_scan_log() {
+        use crate::testing::TestImageBuilder;
+        use object::SectionKind;
+
+        let base = 0x140000000;
+        let f = base + 0x1000;
+        let builder = || TestImageBuilder::new(base).section(".text", SectionKind::Text, f, 0x1000);
+        let image = builder()
+            .write(f, &[0x48, 0x8b, 0xc1, 0xc3])
+            .build()
+            .unwrap();
+        let pattern = Pattern::new("48 8b c1 c3").unwrap();
+        fn scan<'ctx>(
+            ctx: &'ctx AsyncContext<'_>,
+            pattern: Pattern,
+        ) -> BoxFuture<'ctx, (Vec<usize>, Vec<usize>)> {
+            Box::pin(async move {
+                (
+                    ctx.scan(pattern.clone()).await,
+                    ctx.scan_with_permissions(pattern, SectionPermissions::RW)
+                        .await,
+                )
+            })
+        }
+
+        let log = ScanLog::default();
+        let recorded = eval_with_scan_log(&image, ScanLogMode::Record(&log), |ctx| {
+            scan(ctx, pattern.clone())
+        });
+        assert_eq!(recorded, (vec![f], vec![]));
+        assert_eq!(log.len(), 2);
+
+        let log = ScanLog::parse(&log.to_string()).unwrap();
+        assert_eq!(log.len(), 2);
+        // answered from the log, the image doesn't contain the function
+        let image = builder().build().unwrap();
+        let replayed = eval_with_scan_log(&image, ScanLogMode::Replay(&log), |ctx| {
+            scan(ctx, pattern.clone())
+        });
+        assert_eq!(replayed, recorded);
+
+        assert!(ScanLog::parse("-\t*\t48").is_err());
+        assert!(ScanLog::parse("-\tq\t48\t0x1").is_err());
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_in() {
+        use crate::{testing::TestImageBuilder, PatternConfig};
+        use object::SectionKind;
+
+        let base = 0x140000000;
+        let f = base + 0x1000;
+        let g = base + 0x1100;
+        let code = [0x48, 0x8b, 0xc1, 0xc3]; // mov rax, rcx; ret
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, f, 0x1000)
+            .write(f, &code)
+            .write(g, &code)
+            .build()
+            .unwrap();
+        let pattern = Pattern::new("48 8b c1 c3").unwrap();
+
+        let matches = eval(&image, |ctx| {
+            let pattern = pattern.clone();
+            Box::pin(async move {
+                (
+                    ctx.scan(pattern.clone()).await,
+                    ctx.scan_in(g..g + 0x100, pattern.clone()).await,
+                    // match must fit entirely inside the range
+                    ctx.scan_in(g + 1..g + 0x100, pattern.clone()).await,
+                    ctx.scan_in(f..f + 3, pattern).await,
+                )
+            })
+        });
+        assert_eq!(matches, (vec![f, g], vec![g], vec![], vec![]));
+
+        let configs = [
+            PatternConfig::new((), "all".into(), None, pattern.clone()),
+            PatternConfig::new((), "g".into(), None, pattern).range(g..g + 0x100),
+        ];
+        let results = image.scan(&configs).unwrap().results;
+        let found = |name: &str| {
+            results
+                .iter()
+                .filter(|(config, _)| config.name == name)
+                .map(|(_, res)| res.address)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("all"), [f, g]);
+        assert_eq!(found("g"), [g]);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_filter_code() {
+        use crate::{testing::TestImageBuilder, PatternConfig};
+        use object::SectionKind;
+
+        let base = 0x140000000;
+        let f = base + 0x1010;
+        let data = base + 0x2000;
+        let code = [0x48, 0x8b, 0xc1, 0xc3]; // mov rax, rcx; ret
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, data, 0x1000)
+            .function(f..f + 4)
+            .write(f, &code)
+            .write(data + 2, &code)
+            .build()
+            .unwrap();
+        // leading wildcards also match the int3 padding before the function
+        let pattern = Pattern::new("?? ?? 48 8b c1 c3").unwrap();
+
+        let configs = [
+            PatternConfig::new((), "filtered".into(), None, pattern.clone()).filter_code(),
+            PatternConfig::new((), "unfiltered".into(), None, pattern.clone()),
+        ];
+        let results = image.scan(&configs).unwrap().results;
+        let found = |name: &str| {
+            results
+                .iter()
+                .filter(|(config, _)| config.name == name)
+                .map(|(_, res)| res.address)
+                .collect::<Vec<_>>()
+        };
+        // matches outside executable sections are left alone
+        assert_eq!(found("filtered"), [data]);
+        assert_eq!(found("unfiltered"), [f - 2, data]);
+
+        assert!(image.is_code(f));
+        assert!(!image.is_code(f - 2));
+        assert!(!image.is_code(data + 2));
+
+        let matches = eval(&image, |ctx| {
+            Box::pin(async move {
+                let matches = ctx.scan(pattern).await;
+                ctx.filter_code(matches)
+            })
+        });
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_many_modules() {
+        use object::SectionKind;
+
+        use crate::testing::TestImageBuilder;
+
+        let getter = |name: &str| resolvers().find(|r| r.name == name).unwrap().getter;
+        let base = 0x140000000;
+        let text = base + 0x1000;
+        let module = |text_size: usize, code: &[u8]| {
+            TestImageBuilder::new(base)
+                .section(".text", SectionKind::Text, text, text_size)
+                .write(text, code)
+                .build()
+                .unwrap()
+        };
+        // mov rbx, [rip+0x1000]; test rbx, rbx; jz +3; mov r8b, 1
+        let gworld = [
+            0x48, 0x8b, 0x1d, 0x00, 0x10, 0x00, 0x00, 0x48, 0x85, 0xdb, 0x74, 0x03, 0x41, 0xb0,
+            0x01,
+        ];
+        // UObject::ProcessEvent prologue, only found in the launcher
+        let process_event = "40 55 56 57 41 54 41 55 41 56 41 57 48 81 EC 00 00 00 00 48 8D 6C 24 00 48 89 9D 00 00 00 00 48 8B 05 00 00 00 00 48 33 C5 48 89 85 00 00 00 00 4D 8B F8 48 8B F2 4C 8B E1"
+            .split(' ')
+            .map(|b| u8::from_str_radix(b, 16).unwrap())
+            .collect::<Vec<_>>();
+        let launcher = module(0x1000, &[&gworld[..], &process_event].concat());
+        let engine = module(0x4000, &gworld);
+
+        let results = resolve_many_modules(
+            &[&launcher, &engine],
+            &[
+                getter("GWorld"),
+                getter("UObjectProcessEvent"),
+                getter("GMalloc"),
+            ],
+        );
+        let found = |i: usize| {
+            let (module, res) = results[i].as_ref().unwrap();
+            (*module, res.get())
+        };
+        // both modules match but the larger one is tried first
+        assert_eq!(found(0), (1, Some(text + 7 + 0x1000)));
+        assert_eq!(found(1), (0, Some(text + gworld.len())));
+        assert!(results[2].is_err());
+
+        assert!(resolve_many_modules(&[], &[getter("GWorld")])[0].is_err());
+    }
 }
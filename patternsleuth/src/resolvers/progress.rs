@@ -0,0 +1,172 @@
+//! Recording [`EvalEvent`]s so a consumer can show resolver progress while an eval is still
+//! running, e.g. an overlay injected into the game which would otherwise only learn about the
+//! results once the whole scan finished
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{EvalEvent, EventHandler};
+
+/// A resolver which finished during the recorded evals
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolverStatus {
+    /// Resolver name without its module path
+    pub name: &'static str,
+    /// Error message if the resolver failed
+    pub error: Option<String>,
+    /// Relaxation level the resolver needed, 0 if its patterns matched as written
+    pub relaxation: usize,
+    /// Time from the creation of the [`ResolverProgress`] until the resolver finished
+    pub elapsed: Duration,
+}
+
+/// Snapshot of a [`ResolverProgress`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressState {
+    /// Last stage started, counted per eval
+    pub stage: usize,
+    /// Patterns scanned by the last stage
+    pub stage_patterns: usize,
+    /// Total patterns queued by resolvers
+    pub patterns: usize,
+    /// Patterns which only matched after relaxing them
+    pub relaxed: usize,
+    /// Resolvers in the order they finished
+    pub resolvers: Vec<ResolverStatus>,
+    /// Set by [`ResolverProgress::finish`]
+    pub finished: bool,
+}
+impl ProgressState {
+    pub fn failed(&self) -> impl Iterator<Item = &ResolverStatus> {
+        self.resolvers.iter().filter(|r| r.error.is_some())
+    }
+}
+
+type UpdateFn = Arc<dyn Fn(&ResolverStatus) + Send + Sync>;
+
+/// Thread safe sink for [`EvalEvent`]s. Pass [`ResolverProgress::handler`] to any of the
+/// `*_with_events` functions (several evals may share one) and read
+/// [`ResolverProgress::snapshot`] from the consumer.
+pub struct ResolverProgress {
+    started: Instant,
+    state: Mutex<ProgressState>,
+    on_resolved: Mutex<Vec<UpdateFn>>,
+}
+impl Default for ResolverProgress {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            state: Default::default(),
+            on_resolved: Default::default(),
+        }
+    }
+}
+impl std::fmt::Debug for ResolverProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResolverProgress")
+            .field("started", &self.started)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+impl ResolverProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Call `f` (on the thread running the eval) whenever a resolver finishes, e.g. to log it or
+    /// to request a repaint. Can be added while an eval is running, so a consumer created after
+    /// the eval started still gets notified.
+    pub fn on_resolved(&self, f: impl Fn(&ResolverStatus) + Send + Sync + 'static) {
+        self.on_resolved.lock().unwrap().push(Arc::new(f));
+    }
+    /// Event handler recording into `self`
+    pub fn handler(self: &Arc<Self>) -> EventHandler {
+        let progress = self.clone();
+        Arc::new(move |event: &EvalEvent| progress.record(event))
+    }
+    pub fn record(&self, event: &EvalEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event {
+            EvalEvent::PatternQueued(_) => state.patterns += 1,
+            EvalEvent::StageStarted { stage, patterns } => {
+                state.stage = *stage;
+                state.stage_patterns = *patterns;
+            }
+            EvalEvent::SectionScanned { .. } => {}
+            EvalEvent::PatternRelaxed { .. } => state.relaxed += 1,
+            EvalEvent::ResolverFinished {
+                name,
+                result,
+                relaxation,
+            } => {
+                let status = ResolverStatus {
+                    name: name.rsplit("::").next().unwrap_or(name),
+                    error: result.err().map(|e| e.to_string()),
+                    relaxation: *relaxation,
+                    elapsed: self.started.elapsed(),
+                };
+                state.resolvers.push(status.clone());
+                // don't hold the state lock in case the callback takes a snapshot
+                drop(state);
+                let on_resolved = self.on_resolved.lock().unwrap().clone();
+                for f in on_resolved {
+                    f(&status);
+                }
+            }
+        }
+    }
+    /// Mark the scan as done once the consumer ran all of its evals
+    pub fn finish(&self) {
+        self.state.lock().unwrap().finished = true;
+    }
+    pub fn snapshot(&self) -> ProgressState {
+        self.state.lock().unwrap().clone()
+    }
+    /// Time since the creation of `self`
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resolvers::ResolveError;
+
+    #[test]
+    fn test_record() {
+        let progress = Arc::new(ResolverProgress::new());
+        let resolved = Arc::new(Mutex::new(vec![]));
+        let r = resolved.clone();
+        progress.on_resolved(move |status| r.lock().unwrap().push(status.name));
+
+        let handler = progress.handler();
+        handler(&EvalEvent::StageStarted {
+            stage: 1,
+            patterns: 2,
+        });
+        handler(&EvalEvent::ResolverFinished {
+            name: "patternsleuth::resolvers::unreal::gmalloc::GMalloc",
+            result: Ok(()),
+            relaxation: 1,
+        });
+        let error = ResolveError::Msg("no matches".into());
+        handler(&EvalEvent::ResolverFinished {
+            name: "patternsleuth::resolvers::unreal::fname::FNameToString",
+            result: Err(&error),
+            relaxation: 0,
+        });
+        progress.finish();
+
+        let state = progress.snapshot();
+        assert_eq!((state.stage, state.stage_patterns), (1, 2));
+        assert!(state.finished);
+        let names = state.resolvers.iter().map(|r| r.name).collect::<Vec<_>>();
+        assert_eq!(names, ["GMalloc", "FNameToString"]);
+        assert_eq!(state.resolvers[0].relaxation, 1);
+        assert_eq!(state.failed().count(), 1);
+        assert_eq!(*resolved.lock().unwrap(), ["GMalloc", "FNameToString"]);
+    }
+}
@@ -0,0 +1,93 @@
+//! Progressively relaxed variants of a pattern for resolvers opting in to fall back when their
+//! strict pattern stops matching, e.g. after a struct grew and field offsets shifted.
+//!
+//! The pattern is decoded as x86-64 (with wildcards read as zero) to find which bytes are
+//! operands rather than opcodes:
+//! 1. memory displacements (field offsets and rip-relative addresses) are wildcarded
+//! 2. immediates and branch offsets are wildcarded as well
+//!
+//! Decoding stops at the first byte which doesn't decode, so patterns starting in the middle of
+//! an instruction produce no relaxations.
+
+use iced_x86::{Decoder, DecoderOptions};
+use patternsleuth_scanner::Pattern;
+
+/// Relaxed variants of `pattern` with their level (numbered as in the list above), least relaxed
+/// first. Levels which wouldn't change anything are omitted but keep their number so a level
+/// always means the same relaxation.
+pub fn relaxations(pattern: &Pattern) -> Vec<(usize, Pattern)> {
+    let sig = &pattern.simple.sig;
+    let mut displacements = vec![];
+    let mut immediates = vec![];
+
+    let mut decoder = Decoder::with_ip(64, sig, 0, DecoderOptions::NONE);
+    while decoder.can_decode() {
+        let inst = decoder.decode();
+        if inst.is_invalid() {
+            break;
+        }
+        let start = inst.ip() as usize;
+        let offsets = decoder.get_constant_offsets(&inst);
+        if offsets.has_displacement() {
+            let offset = start + offsets.displacement_offset();
+            displacements.push(offset..offset + offsets.displacement_size());
+        }
+        if offsets.has_immediate() {
+            let offset = start + offsets.immediate_offset();
+            immediates.push(offset..offset + offsets.immediate_size());
+        }
+        if offsets.has_immediate2() {
+            let offset = start + offsets.immediate_offset2();
+            immediates.push(offset..offset + offsets.immediate_size2());
+        }
+    }
+
+    let mut relaxed = vec![];
+    let mut previous = pattern.clone();
+    for (level, ranges) in [(1, displacements), (2, immediates)] {
+        let mut next = previous.clone();
+        for i in ranges.into_iter().flatten() {
+            next.simple.sig[i] = 0;
+            next.simple.mask[i] = 0;
+        }
+        if next != previous {
+            relaxed.push((level, next.clone()));
+        }
+        previous = next;
+    }
+    relaxed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_relaxations() {
+        // mov rax, [rip+0x44332211]; call 0xddccbbaa; add rsp, 0x28
+        let pattern = Pattern::new("48 8b 05 11 22 33 44 e8 aa bb cc dd 48 83 c4 28").unwrap();
+        let relaxed = relaxations(&pattern);
+        assert_eq!(
+            relaxed,
+            [
+                (
+                    1,
+                    Pattern::new("48 8b 05 ?? ?? ?? ?? e8 aa bb cc dd 48 83 c4 28").unwrap()
+                ),
+                (
+                    2,
+                    Pattern::new("48 8b 05 ?? ?? ?? ?? e8 ?? ?? ?? ?? 48 83 c4 ??").unwrap()
+                ),
+            ]
+        );
+
+        // no displacements, the immediate relaxation is still level 2
+        assert_eq!(
+            relaxations(&Pattern::new("48 83 c4 28 c3").unwrap()),
+            [(2, Pattern::new("48 83 c4 ?? c3").unwrap())]
+        );
+
+        // nothing to relax
+        assert!(relaxations(&Pattern::new("48 89 c8 c3").unwrap()).is_empty());
+    }
+}
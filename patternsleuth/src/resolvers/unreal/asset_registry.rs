@@ -0,0 +1,22 @@
+//! Asset registry initialization.
+//!
+//! `IAssetRegistry::Get` and `FAssetRegistryModule::Get` go through the module manager or a
+//! lazily set singleton without an anchor of their own. The registry is the live
+//! `UAssetRegistryImpl` object, found at runtime with
+//! [`AssetRegistry::find`](crate::ue::AssetRegistry::find) which also enumerates its assets.
+
+use crate::resolvers::{ensure_one, impl_resolver_singleton, unreal::util};
+
+/// The function loading the premade `AssetRegistry.bin` of cooked games: the `UAssetRegistryImpl`
+/// constructor, or the premade registry loader it calls (UE 5)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UAssetRegistryImplLoadPremade(pub usize);
+impl_resolver_singleton!(all, UAssetRegistryImplLoadPremade, |ctx| async {
+    let strings = ctx.scan(util::utf16_pattern("AssetRegistry.bin\0")).await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    Ok(Self(ensure_one(util::root_functions(ctx, &refs)?)?))
+});
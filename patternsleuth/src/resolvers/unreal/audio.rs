@@ -0,0 +1,53 @@
+//! Entry points of sound playback, for hooking sound dispatch to replace or mute sounds.
+//!
+//! Sounds started from gameplay code and blueprints go through the `UGameplayStatics` sound
+//! functions whose exec thunks can be found by name in the native function tables.
+//!
+//! `FAudioDeviceManager::GetAudioDevice` is not resolved: it only looks up the device map and
+//! has no string anchor, and in shipping builds it is only reached through
+//! `UWorld::GetAudioDevice` which has none either. Hook the play functions instead, they receive
+//! the sound before any device sees it.
+
+use crate::resolvers::{impl_collector, unreal::util::native_function_resolver};
+
+native_function_resolver!(
+    UGameplayStaticsPlaySound2D,
+    "UGameplayStatics",
+    "PlaySound2D"
+);
+native_function_resolver!(
+    UGameplayStaticsPlaySoundAtLocation,
+    "UGameplayStatics",
+    "PlaySoundAtLocation"
+);
+native_function_resolver!(
+    UGameplayStaticsSpawnSound2D,
+    "UGameplayStatics",
+    "SpawnSound2D"
+);
+native_function_resolver!(
+    UGameplayStaticsSpawnSoundAtLocation,
+    "UGameplayStatics",
+    "SpawnSoundAtLocation"
+);
+native_function_resolver!(
+    UGameplayStaticsSpawnSoundAttached,
+    "UGameplayStatics",
+    "SpawnSoundAttached"
+);
+
+impl_collector! {
+    /// Sound play entry points. Members resolve independently as not every game links every one.
+    #[derive(Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "serde-resolvers",
+        derive(serde::Serialize, serde::Deserialize)
+    )]
+    pub struct Audio {
+        pub play_sound_2d: UGameplayStaticsPlaySound2D,
+        pub play_sound_at_location: UGameplayStaticsPlaySoundAtLocation,
+        pub spawn_sound_2d: UGameplayStaticsSpawnSound2D,
+        pub spawn_sound_at_location: UGameplayStaticsSpawnSoundAtLocation,
+        pub spawn_sound_attached: UGameplayStaticsSpawnSoundAttached,
+    }
+}
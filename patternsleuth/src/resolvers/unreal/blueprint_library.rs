@@ -5,7 +5,7 @@ use futures::{future::join_all, join};
 use patternsleuth_scanner::Pattern;
 
 use crate::{
-    resolvers::{ensure_one, impl_resolver, impl_resolver_singleton, Context},
+    resolvers::{ensure_one, impl_resolver, impl_resolver_singleton, Addresses, Context, Multi},
     Addressable, Matchable,
 };
 
@@ -23,7 +23,7 @@ pub struct BlueprintLibraryInit {
     pub ublueprint_function_library_static_class: usize,
 }
 
-impl_resolver!(all, BlueprintLibraryInit, |ctx| async {
+impl_resolver!(all, multi, BlueprintLibraryInit, |ctx| async {
     let mem = &ctx.image().memory;
 
     let class_str = Pattern::from_bytes(
@@ -171,6 +171,28 @@ impl_resolver!(all, BlueprintLibraryInit, |ctx| async {
         ublueprint_function_library_static_class,
     })
 });
+impl Multi for BlueprintLibraryInit {
+    fn addresses(&self) -> Addresses {
+        Addresses::Named(
+            [
+                ("uclass_compiled_in_defer", self.uclass_compiled_in_defer),
+                ("uobject_compiled_in_defer", self.uobject_compiled_in_defer),
+                ("construct_uclass", self.construct_uclass),
+                (
+                    "get_private_static_class_body",
+                    self.get_private_static_class_body,
+                ),
+                ("uobject_static_class", self.uobject_static_class),
+                (
+                    "ublueprint_function_library_static_class",
+                    self.ublueprint_function_library_static_class,
+                ),
+            ]
+            .map(|(k, v)| (k.to_string(), v))
+            .into(),
+        )
+    }
+}
 
 /// UFunction::Bind
 #[derive(Debug, PartialEq)]
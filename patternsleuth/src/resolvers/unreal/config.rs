@@ -0,0 +1,128 @@
+//! The config system: `GConfig` and the `FConfigCacheIni` getters, to read engine settings out of
+//! a live process or hook reads to override them without touching the ini files.
+//!
+//! Both getters are found through engine startup reads with stable keys:
+//! `GConfig->GetString(TEXT("/Script/Engine.Engine"), TEXT("GameEngine"), ..., GEngineIni)` in
+//! `FEngineLoop::Init` and `GConfig->GetInt(TEXT("/Script/Engine.GarbageCollectionSettings"),
+//! TEXT("gc.MaxObjectsNotConsideredByGC"), ..., GEngineIni)` in `InitUObject` (UE 4.18+).
+
+use iced_x86::{FlowControl, Mnemonic, Register};
+use itertools::Itertools as _;
+
+use crate::{
+    disassemble::{disassemble, Control},
+    image::Image,
+    resolvers::{ensure_one, impl_resolver_singleton, unreal::util, AsyncContext, Result},
+};
+
+const GET_STRING_KEY: &str = "GameEngine";
+const GET_INT_KEY: &str = "gc.MaxObjectsNotConsideredByGC";
+
+/// `GConfig` and the callee of the `GConfig->Get*(Section, Key, ...)` calls passed the wide
+/// string `key`
+async fn config_reads(ctx: &AsyncContext<'_>, key: &str) -> Result<Vec<(usize, usize)>> {
+    let strings = ctx.scan(util::utf16_pattern(&format!("{key}\0"))).await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    refs.into_iter()
+        .map(|addr| config_read(ctx.image(), addr))
+        .flatten_ok()
+        .collect()
+}
+
+/// Follow a reference to a config key to the first direct call after it, tracking the last
+/// global loaded into `rcx` (`this`) since the previous call
+fn config_read(img: &Image<'_>, addr: usize) -> Result<Option<(usize, usize)>> {
+    let Some(root) = img.get_root_function(addr)? else {
+        return Ok(None);
+    };
+    let range = root.range();
+
+    let mut rcx = None;
+    let mut read = None;
+
+    disassemble(img, range.start, |inst| {
+        let cur = inst.ip() as usize;
+        if !range.contains(&cur) {
+            return Ok(Control::Break);
+        }
+        if inst.flow_control() == FlowControl::Call {
+            let callee = inst.near_branch_target() as usize;
+            if cur > addr && callee != 0 {
+                read = rcx.map(|rcx| (rcx, callee));
+                return Ok(Control::Break);
+            }
+            rcx = None;
+        } else if inst.mnemonic() == Mnemonic::Mov
+            && inst.op0_register() == Register::RCX
+            && inst.is_ip_rel_memory_operand()
+        {
+            rcx = Some(inst.ip_rel_memory_address() as usize);
+        }
+        Ok(Control::Continue)
+    })?;
+
+    Ok(read)
+}
+
+/// `FConfigCacheIni* GConfig`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GConfig(pub usize);
+impl_resolver_singleton!(collect, GConfig);
+impl_resolver_singleton!(PEImage, GConfig, |ctx| async {
+    let (strings, ints) = futures::try_join!(
+        config_reads(ctx, GET_STRING_KEY),
+        config_reads(ctx, GET_INT_KEY)
+    )?;
+    Ok(Self(ensure_one(
+        strings.into_iter().chain(ints).map(|(gconfig, _)| gconfig),
+    )?))
+});
+impl_resolver_singleton!(ElfImage, GConfig, |_ctx| async {
+    super::bail_out!("ElfImage unimplemented");
+});
+
+/// `bool FConfigCacheIni::GetString(const TCHAR* Section, const TCHAR* Key, FString& Value,
+/// const FString& Filename)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FConfigCacheIniGetString(pub usize);
+impl_resolver_singleton!(collect, FConfigCacheIniGetString);
+impl_resolver_singleton!(PEImage, FConfigCacheIniGetString, |ctx| async {
+    Ok(Self(ensure_one(
+        config_reads(ctx, GET_STRING_KEY)
+            .await?
+            .into_iter()
+            .map(|(_, f)| f),
+    )?))
+});
+impl_resolver_singleton!(ElfImage, FConfigCacheIniGetString, |_ctx| async {
+    super::bail_out!("ElfImage unimplemented");
+});
+
+/// `bool FConfigCacheIni::GetInt(const TCHAR* Section, const TCHAR* Key, int32& Value,
+/// const FString& Filename)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FConfigCacheIniGetInt(pub usize);
+impl_resolver_singleton!(collect, FConfigCacheIniGetInt);
+impl_resolver_singleton!(PEImage, FConfigCacheIniGetInt, |ctx| async {
+    Ok(Self(ensure_one(
+        config_reads(ctx, GET_INT_KEY)
+            .await?
+            .into_iter()
+            .map(|(_, f)| f),
+    )?))
+});
+impl_resolver_singleton!(ElfImage, FConfigCacheIniGetInt, |_ctx| async {
+    super::bail_out!("ElfImage unimplemented");
+});
@@ -0,0 +1,50 @@
+//! Assertion and ensure failure reporting, to route engine failures to a host's logger or keep
+//! ensures from invoking the crash reporter.
+//!
+//! Both functions start with `(const ANSICHAR* Expr, const ANSICHAR* File, int32 Line, ...)` in
+//! every engine version, the remaining parameters differ. `ReportCrash` and
+//! `FGenericCrashContext` only reference strings assembled at runtime so the crash reporter
+//! itself is not resolved, it is reached through these instead.
+
+use crate::resolvers::{
+    bail_out, ensure_one, impl_resolver_singleton, resolver_dependencies,
+    unreal::{layout::DoCheck, util},
+};
+
+/// The function reporting failed `check`s: `FDebug::LogAssertFailedMessageImplV` (UE 4) or
+/// `FDebug::AssertFailedImplV` (UE 5), found through its `Assertion failed: ` message prefix
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FDebugAssertFailed(pub usize);
+impl_resolver_singleton!(all, FDebugAssertFailed, |ctx| async {
+    let strings = ctx.scan(util::utf16_pattern("Assertion failed: ")).await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    Ok(Self(ensure_one(util::root_functions(ctx, &refs)?)?))
+});
+
+/// `FDebug::EnsureFailed`, reporting failed `ensure`s and submitting an ensure crash report.
+/// Only present when the game was built with ensures enabled.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FDebugEnsureFailed(pub usize);
+resolver_dependencies!(FDebugEnsureFailed => [DoCheck]);
+impl_resolver_singleton!(all, FDebugEnsureFailed, |ctx| async {
+    let strings = ctx
+        .scan(util::utf16_pattern("Ensure condition failed: "))
+        .await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    match ensure_one(util::root_functions(ctx, &refs)?) {
+        Ok(address) => Ok(Self(address)),
+        // tell a missing function apart from a broken pattern
+        Err(_) if !ctx.resolve(DoCheck::resolver()).await?.0 => {
+            bail_out!("checks are compiled out, the build likely has ensures disabled too")
+        }
+        Err(err) => Err(err),
+    }
+});
@@ -0,0 +1,241 @@
+//! `FCoreDelegates` globals, to receive engine lifecycle callbacks through
+//! [`MulticastDelegateBinding`](crate::ue::MulticastDelegateBinding) instead of hooking the
+//! functions broadcasting them.
+//!
+//! The delegates are plain statics without names in shipping builds. They are found through
+//! `Broadcast` calls in functions already resolved, which lock the invocation list while
+//! iterating it:
+//!
+//! ```text
+//! TMulticastDelegate (0x18)
+//!   0x00 TArray<FDelegateBase> InvocationList
+//!   0x10 int32 CompactionThreshold
+//!   0x14 int32 InvocationListLockCount
+//! ```
+//!
+//! `OnPostEngineInit` and `OnEnginePreExit` are broadcast from `FEngineLoop::Init` and
+//! `FEngineLoop::Exit` among several other delegates and are told apart by their position
+//! relative to other code of those functions.
+
+use std::collections::{HashMap, HashSet};
+
+use iced_x86::{Mnemonic, OpKind, Register};
+
+use crate::{
+    resolvers::{
+        bail_out, impl_resolver_singleton, resolver_dependencies,
+        unreal::{
+            game_loop::{FEngineLoopExit, FEngineLoopInit, FEngineLoopTick},
+            layout::visit_function,
+            util,
+        },
+        AsyncContext, Result,
+    },
+    SectionPermissions,
+};
+
+/// Offset of `TMulticastDelegate::InvocationListLockCount`
+const LOCK_COUNT: u64 = 0x14;
+
+/// Multicast delegate globals broadcast by the function at `address` in instruction order along
+/// with the address of their first broadcast. `Broadcast` is either inlined, incrementing and
+/// decrementing the lock count of the global, or called with the global in `rcx`.
+fn broadcast_delegates(ctx: &AsyncContext<'_>, address: usize) -> Result<Vec<(usize, usize)>> {
+    let image = ctx.image();
+    let writable = |address: usize| {
+        image
+            .memory
+            .get_section_containing(address)
+            .is_ok_and(|s| s.permissions().allows(SectionPermissions::RW))
+    };
+    let is_increment = |inst: &iced_x86::Instruction| match inst.mnemonic() {
+        Mnemonic::Inc => Some(true),
+        Mnemonic::Dec => Some(false),
+        Mnemonic::Add | Mnemonic::Sub if inst.op1_kind() == OpKind::Immediate8to32 => {
+            match (inst.mnemonic(), inst.immediate(1) as i32) {
+                (Mnemonic::Add, 1) | (Mnemonic::Sub, -1) => Some(true),
+                (Mnemonic::Sub, 1) | (Mnemonic::Add, -1) => Some(false),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let mut locked = vec![];
+    let mut unlocked = HashSet::new();
+    let mut calls = vec![];
+    let mut pending = None;
+    visit_function(ctx, address, |inst| {
+        if inst.op0_kind() == OpKind::Memory
+            && inst.is_ip_rel_memory_operand()
+            && inst.memory_size().size() == 4
+        {
+            let lock = inst.ip_rel_memory_address() as usize;
+            match is_increment(inst) {
+                Some(true) => locked.push((inst.ip(), lock)),
+                Some(false) => {
+                    unlocked.insert(lock);
+                }
+                None => {}
+            }
+        }
+
+        if inst.is_call_near() {
+            if let Some((next_ip, delegate)) = pending {
+                if next_ip == inst.ip() {
+                    calls.push((inst.ip(), delegate, inst.near_branch_target() as usize));
+                }
+            }
+        }
+        pending = (inst.mnemonic() == Mnemonic::Lea
+            && inst.op0_register() == Register::RCX
+            && inst.is_ip_rel_memory_operand())
+        .then(|| (inst.next_ip(), inst.ip_rel_memory_address() as usize));
+    })?;
+
+    let mut delegates = locked
+        .into_iter()
+        .filter(|(_, lock)| unlocked.contains(lock))
+        .map(|(ip, lock)| (ip as usize, lock - LOCK_COUNT as usize))
+        .collect::<Vec<_>>();
+
+    let mut broadcasts: HashMap<usize, bool> = HashMap::new();
+    for (ip, delegate, target) in calls {
+        // targets which can't be disassembled are not Broadcast
+        let is_broadcast = *broadcasts
+            .entry(target)
+            .or_insert_with(|| locks_invocation_list(ctx, target).unwrap_or_default());
+        if is_broadcast {
+            delegates.push((ip as usize, delegate));
+        }
+    }
+
+    delegates.sort();
+    let mut seen = HashSet::new();
+    Ok(delegates
+        .into_iter()
+        .filter(|(_, delegate)| writable(*delegate) && seen.insert(*delegate))
+        .collect())
+}
+
+/// Whether the function at `address` increments `InvocationListLockCount` of `this`
+fn locks_invocation_list(ctx: &AsyncContext<'_>, address: usize) -> Result<bool> {
+    let mut this = HashSet::from([Register::RCX]);
+    let mut locks = false;
+    visit_function(ctx, address, |inst| {
+        if inst.mnemonic() == Mnemonic::Mov
+            && inst.op0_kind() == OpKind::Register
+            && inst.op1_kind() == OpKind::Register
+            && this.contains(&inst.op1_register())
+        {
+            this.insert(inst.op0_register());
+        }
+        if inst.op0_kind() == OpKind::Memory
+            && this.contains(&inst.memory_base())
+            && inst.memory_index() == Register::None
+            && inst.memory_displacement64() == LOCK_COUNT
+            && inst.memory_size().size() == 4
+            && (inst.mnemonic() == Mnemonic::Inc
+                || (inst.mnemonic() == Mnemonic::Add
+                    && inst.op1_kind() == OpKind::Immediate8to32
+                    && inst.immediate(1) == 1))
+        {
+            locks = true;
+        }
+    })?;
+    Ok(locks)
+}
+
+/// `FSimpleMulticastDelegate FCoreDelegates::OnBeginFrame`, the first delegate broadcast by
+/// `FEngineLoop::Tick`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FCoreDelegatesOnBeginFrame(pub usize);
+resolver_dependencies!(FCoreDelegatesOnBeginFrame => [FEngineLoopTick]);
+impl_resolver_singleton!(all, FCoreDelegatesOnBeginFrame, |ctx| async {
+    let tick = ctx.resolve(FEngineLoopTick::resolver()).await?;
+    match broadcast_delegates(ctx, tick.0)?.first() {
+        Some((_, delegate)) => Ok(Self(*delegate)),
+        None => bail_out!("no delegate broadcast"),
+    }
+});
+
+/// `FSimpleMulticastDelegate FCoreDelegates::OnEndFrame`, the last delegate broadcast by
+/// `FEngineLoop::Tick`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FCoreDelegatesOnEndFrame(pub usize);
+resolver_dependencies!(FCoreDelegatesOnEndFrame => [FEngineLoopTick]);
+impl_resolver_singleton!(all, FCoreDelegatesOnEndFrame, |ctx| async {
+    let tick = ctx.resolve(FEngineLoopTick::resolver()).await?;
+    let delegates = broadcast_delegates(ctx, tick.0)?;
+    if delegates.len() < 2 {
+        bail_out!("expected at least two delegates broadcast");
+    }
+    Ok(Self(delegates.last().unwrap().1))
+});
+
+/// `FSimpleMulticastDelegate FCoreDelegates::OnPostEngineInit`, the last delegate broadcast by
+/// `FEngineLoop::Init` before it loads the `PostEngineInit` modules. Engines which still have the
+/// deprecated `UEngine::OnPostEngineInit` broadcast it right before, so either is called at the
+/// same point of initialization.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FCoreDelegatesOnPostEngineInit(pub usize);
+resolver_dependencies!(FCoreDelegatesOnPostEngineInit => [FEngineLoopInit]);
+impl_resolver_singleton!(all, FCoreDelegatesOnPostEngineInit, |ctx| async {
+    let init = ctx.resolve(FEngineLoopInit::resolver()).await?;
+    let strings = ctx
+        .scan(util::utf16_pattern(
+            "One or more modules failed PostEngineInit",
+        ))
+        .await;
+    let image = ctx.image();
+    let Some(load_modules) = util::scan_xrefs(ctx, &strings)
+        .await
+        .into_iter()
+        .filter(|r| {
+            image
+                .get_root_function(*r)
+                .ok()
+                .flatten()
+                .is_some_and(|f| f.range.start == init.0)
+        })
+        .min()
+    else {
+        bail_out!("FEngineLoop::Init does not load PostEngineInit modules");
+    };
+    match broadcast_delegates(ctx, init.0)?
+        .into_iter()
+        .rfind(|(ip, _)| *ip < load_modules)
+    {
+        Some((_, delegate)) => Ok(Self(delegate)),
+        None => bail_out!("no delegate broadcast before loading PostEngineInit modules"),
+    }
+});
+
+/// `FSimpleMulticastDelegate FCoreDelegates::OnEnginePreExit`, the first delegate broadcast by
+/// `FEngineLoop::Exit`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FCoreDelegatesOnEnginePreExit(pub usize);
+resolver_dependencies!(FCoreDelegatesOnEnginePreExit => [FEngineLoopExit]);
+impl_resolver_singleton!(all, FCoreDelegatesOnEnginePreExit, |ctx| async {
+    let exit = ctx.resolve(FEngineLoopExit::resolver()).await?;
+    match broadcast_delegates(ctx, exit.0)?.first() {
+        Some((_, delegate)) => Ok(Self(*delegate)),
+        None => bail_out!("no delegate broadcast"),
+    }
+});
@@ -1,11 +1,15 @@
-use std::fmt::Debug;
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
 
 use futures::future::join_all;
 
 use patternsleuth_scanner::Pattern;
 
 use crate::{
-    resolvers::{ensure_one, impl_resolver_singleton, try_ensure_one, unreal::util, Result},
+    resolvers::{
+        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, resolver_dependencies,
+        try_ensure_one, unreal::util, Addresses, Context, Multi, Result,
+    },
+    ue::{FName, FString, ReadMemory},
     MemoryTrait,
 };
 
@@ -203,6 +207,7 @@ impl_resolver_singleton!(PEImage, FNameCtorWchar, |ctx| async {
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct FNameToString(pub usize);
+resolver_dependencies!(FNameToString => [FNameToStringFString, FNameToStringVoid]);
 impl_resolver_singleton!(collect, FNameToString);
 
 impl_resolver_singleton!(ElfImage, FNameToString, |ctx| async {
@@ -365,3 +370,298 @@ impl_resolver_singleton!(all, FNamePool, |ctx| async {
         |a| -> Result<usize> { Ok(ctx.image().memory.rip4(*a)?) },
     ))?))
 });
+
+/// Way of converting an FName to a string. Resolves `FName::ToString` when possible and falls back
+/// to `FNamePool` for builds where it has been inlined, so dependents don't need to care which
+/// strategy succeeded.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum NameReader {
+    /// Address of `FName::ToString` (see [`FNameToString`] for the possible signatures)
+    ToString(usize),
+    /// Address of `FNamePool`, names must be read with [`NameReader::read_pool_name`]
+    NamePool(usize),
+}
+resolver_dependencies!(NameReader => [FNameToString, FNamePool]);
+impl_resolver!(all, multi, NameReader, |ctx| async {
+    let (to_string, pool) = futures::join!(
+        ctx.resolve(FNameToString::resolver()),
+        ctx.resolve(FNamePool::resolver()),
+    );
+    match (to_string, pool) {
+        (Ok(to_string), _) => Ok(Self::ToString(to_string.0)),
+        (Err(_), Ok(pool)) => Ok(Self::NamePool(pool.0)),
+        (Err(err), Err(_)) => Err(err),
+    }
+});
+impl Multi for NameReader {
+    fn addresses(&self) -> Addresses {
+        match self {
+            Self::ToString(a) | Self::NamePool(a) => Addresses::Indexed(vec![*a]),
+        }
+    }
+}
+
+impl NameReader {
+    /// Offset of `FNamePool::Entries.Blocks` (after `FRWLock Lock`, `uint32 CurrentBlock` and
+    /// `uint32 CurrentByteCursor`)
+    pub const POOL_BLOCKS_OFFSET: usize = 0x10;
+    /// `FNameEntryAllocator::Stride`
+    pub const POOL_STRIDE: usize = 2;
+
+    /// Read the plain name (without number suffix) of the FName with `comparison_index` directly
+    /// from the `FNamePool` at `pool`. `memory` must be able to read the heap allocated name blocks
+    /// so this is only useful for live process memory (e.g. [`CurrentProcess`] or `ProcessMemory`).
+    ///
+    /// Assumes the default entry header layout (`bIsWide:1, LowercaseProbeHash:5, Len:10`).
+    ///
+    /// [`CurrentProcess`]: crate::ue::CurrentProcess
+    pub fn read_pool_name(
+        memory: &impl ReadMemory,
+        pool: usize,
+        comparison_index: u32,
+    ) -> Result<String> {
+        let block = (comparison_index >> 16) as usize;
+        let offset = (comparison_index & 0xffff) as usize * Self::POOL_STRIDE;
+
+        let block = memory.read_ptr(pool + Self::POOL_BLOCKS_OFFSET + block * 8)?;
+        let entry = block + offset;
+
+        let mut header = [0; 2];
+        memory.read_bytes(entry, &mut header)?;
+        let (wide, len) = Self::pool_entry_header(header);
+        Ok(Self::decode_pool_entry(
+            wide,
+            &memory.read_vec(entry + 2, len)?,
+        ))
+    }
+
+    /// Split an entry header into whether the name is wide and the length of its data in bytes
+    fn pool_entry_header(header: [u8; 2]) -> (bool, usize) {
+        let header = u16::from_le_bytes(header);
+        let wide = header & 1 != 0;
+        let len = (header >> 6) as usize;
+        (wide, if wide { len * 2 } else { len })
+    }
+
+    /// Decode the name data following an entry header
+    fn decode_pool_entry(wide: bool, data: &[u8]) -> String {
+        if wide {
+            String::from_utf16_lossy(
+                &data
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            // ANSI names are Latin-1
+            data.iter().map(|b| *b as char).collect()
+        }
+    }
+
+    /// Read the full name of `name` from the `FNamePool` at `pool` including the `_N` number
+    /// suffix (see [`NameReader::read_pool_name`])
+    pub fn read_pool_fname(memory: &impl ReadMemory, pool: usize, name: FName) -> Result<String> {
+        let mut string = Self::read_pool_name(memory, pool, name.comparison_index)?;
+        if name.number != 0 {
+            string.push_str(&format!("_{}", name.number - 1));
+        }
+        Ok(string)
+    }
+}
+
+/// `FNamePool` reader for resolving many names of another process, e.g. when walking every
+/// object. Name blocks are read whole on first use and decoded names are memoized so a dump
+/// costs one read per block instead of two reads per name lookup.
+///
+/// Names added to the pool after a block was read are picked up by re-reading that block once
+/// when an unwritten entry is encountered.
+///
+/// `FString`s such as object paths are memoized on their data pointer and length with
+/// [`CachedNamePool::fstring`], so a lookup only costs reading the `FString` header.
+pub struct CachedNamePool<'mem, R> {
+    memory: &'mem R,
+    pool: usize,
+    /// block index -> block contents
+    blocks: RefCell<HashMap<usize, Vec<u8>>>,
+    /// comparison index -> plain name
+    names: RefCell<HashMap<u32, Rc<str>>>,
+    /// (data, num) -> string
+    strings: RefCell<HashMap<(usize, i32), Rc<str>>>,
+}
+impl<'mem, R: ReadMemory> CachedNamePool<'mem, R> {
+    /// Size of a name block (`FNameEntryAllocator::BlockSizeBytes`)
+    pub const BLOCK_SIZE: usize = NameReader::POOL_STRIDE << 16;
+
+    /// `pool` is the address of `FNamePool`, see [`NameReader::read_pool_name`]
+    pub fn new(memory: &'mem R, pool: usize) -> Self {
+        Self {
+            memory,
+            pool,
+            blocks: Default::default(),
+            names: Default::default(),
+            strings: Default::default(),
+        }
+    }
+
+    fn read_block(&self, block: usize) -> Result<()> {
+        let address = self
+            .memory
+            .read_ptr(self.pool + NameReader::POOL_BLOCKS_OFFSET + block * 8)?;
+        let data = self.memory.read_vec(address, Self::BLOCK_SIZE)?;
+        self.blocks.borrow_mut().insert(block, data);
+        Ok(())
+    }
+
+    /// Decode the entry at `offset` of a cached block, `None` if it has not been written yet
+    fn decode(&self, block: usize, offset: usize) -> Option<String> {
+        let blocks = self.blocks.borrow();
+        let data = blocks.get(&block)?;
+        let (wide, len) =
+            NameReader::pool_entry_header(data.get(offset..offset + 2)?.try_into().unwrap());
+        if len == 0 {
+            return None;
+        }
+        let start = offset + 2;
+        Some(NameReader::decode_pool_entry(
+            wide,
+            data.get(start..start + len)?,
+        ))
+    }
+
+    /// Plain name (without number suffix) of `comparison_index`
+    pub fn name(&self, comparison_index: u32) -> Result<Rc<str>> {
+        if let Some(name) = self.names.borrow().get(&comparison_index) {
+            return Ok(name.clone());
+        }
+
+        let block = (comparison_index >> 16) as usize;
+        let offset = (comparison_index & 0xffff) as usize * NameReader::POOL_STRIDE;
+
+        let cached = self.blocks.borrow().contains_key(&block);
+        if !cached {
+            self.read_block(block)?;
+        }
+        let name = match self.decode(block, offset) {
+            Some(name) => name,
+            None if cached => {
+                self.read_block(block)?;
+                self.decode(block, offset)
+                    .context("name entry is empty or out of bounds")?
+            }
+            None => bail_out!("name entry is empty or out of bounds"),
+        };
+
+        let name: Rc<str> = name.into();
+        self.names
+            .borrow_mut()
+            .insert(comparison_index, name.clone());
+        Ok(name)
+    }
+
+    /// Full name of `name` including the `_N` number suffix
+    pub fn fname(&self, name: FName) -> Result<String> {
+        let mut string = self.name(name.comparison_index)?.to_string();
+        if name.number != 0 {
+            string.push_str(&format!("_{}", name.number - 1));
+        }
+        Ok(string)
+    }
+
+    /// Contents of the `FString` at `address`. Strings are assumed not to be modified in place,
+    /// a reallocated string has a new data pointer and is read again.
+    pub fn fstring(&self, address: usize) -> Result<Rc<str>> {
+        let key = (
+            self.memory.read_ptr(address)?,
+            self.memory.read_i32(address + 8)?,
+        );
+        if let Some(string) = self.strings.borrow().get(&key) {
+            return Ok(string.clone());
+        }
+        let string: Rc<str> = FString::read(self.memory, address)?.into();
+        self.strings.borrow_mut().insert(key, string.clone());
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::MemoryAccessError;
+
+    /// A pool at 0 with a single block at 0x1000 and an `FString` at 0x2000, counting reads
+    struct FakePool {
+        block: RefCell<Vec<u8>>,
+        reads: Cell<usize>,
+    }
+    impl ReadMemory for FakePool {
+        fn read_bytes(
+            &self,
+            address: usize,
+            buffer: &mut [u8],
+        ) -> std::result::Result<(), MemoryAccessError> {
+            self.reads.set(self.reads.get() + 1);
+            match address {
+                0x10 => buffer.copy_from_slice(&0x1000usize.to_le_bytes()),
+                0x1000 => buffer.copy_from_slice(&self.block.borrow()[..buffer.len()]),
+                0x2000 => buffer.copy_from_slice(&0x3000usize.to_le_bytes()),
+                0x2008 => buffer.copy_from_slice(&3i32.to_le_bytes()),
+                0x3000 => buffer.copy_from_slice(&[b'H', 0, b'i', 0, 0, 0]),
+                _ => return Err(MemoryAccessError::MemoryOutOfBoundsError),
+            }
+            Ok(())
+        }
+    }
+    fn entry(name: &str) -> Vec<u8> {
+        let mut entry = ((name.len() as u16) << 6).to_le_bytes().to_vec();
+        entry.extend(name.as_bytes());
+        entry.resize(entry.len().next_multiple_of(NameReader::POOL_STRIDE), 0);
+        entry
+    }
+
+    #[test]
+    fn test_cached_name_pool() -> Result<()> {
+        let mut block = [entry("None"), entry("Actor")].concat();
+        let next = block.len() / NameReader::POOL_STRIDE;
+        block.resize(CachedNamePool::<FakePool>::BLOCK_SIZE, 0);
+        let memory = FakePool {
+            block: RefCell::new(block),
+            reads: Cell::new(0),
+        };
+
+        let cache = CachedNamePool::new(&memory, 0);
+        assert_eq!(&*cache.name(0)?, "None");
+        assert_eq!(&*cache.name(3)?, "Actor");
+        assert_eq!(
+            cache.fname(FName {
+                comparison_index: 3,
+                number: 2
+            })?,
+            "Actor_1"
+        );
+        assert_eq!(memory.reads.get(), 2, "block read once");
+
+        // a name added after the block was cached
+        let added = entry("Pawn");
+        let start = next * NameReader::POOL_STRIDE;
+        memory.block.borrow_mut()[start..start + added.len()].copy_from_slice(&added);
+        assert_eq!(&*cache.name(next as u32)?, "Pawn");
+        assert_eq!(memory.reads.get(), 4, "block re-read once");
+        assert!(cache.name(0x100).is_err());
+
+        assert_eq!(&*cache.fstring(0x2000)?, "Hi");
+        let reads = memory.reads.get();
+        assert_eq!(&*cache.fstring(0x2000)?, "Hi");
+        assert_eq!(
+            memory.reads.get(),
+            reads + 2,
+            "only the header is read again"
+        );
+        Ok(())
+    }
+}
@@ -141,3 +141,20 @@ impl_resolver_singleton!(ElfImage, FEngineLoopInit, |ctx| async {
     let fns = util::root_functions(ctx, &refs)?;
     Ok(Self(ensure_one(fns)?))
 });
+
+/// void FEngineLoop::Exit(class FEngineLoop* this)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FEngineLoopExit(pub usize);
+impl_resolver_singleton!(all, FEngineLoopExit, |ctx| async {
+    // read to decide whether to flush async loading before shutting down
+    let strings = ctx
+        .scan(util::utf16_pattern("s.FlushStreamingOnExit\0"))
+        .await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    let fns = util::root_functions(ctx, &refs)?;
+    Ok(Self(ensure_one(fns)?))
+});
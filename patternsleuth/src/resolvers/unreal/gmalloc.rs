@@ -7,7 +7,9 @@ use patternsleuth_scanner::Pattern;
 
 use crate::{
     disassemble::{disassemble, Control},
-    resolvers::{impl_resolver_singleton, try_ensure_one, unreal::util, Result},
+    resolvers::{
+        impl_resolver_singleton, resolver_dependencies, try_ensure_one, unreal::util, Result,
+    },
     MemoryTrait,
 };
 
@@ -17,6 +19,7 @@ use crate::{
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct GMalloc(pub usize);
+resolver_dependencies!(GMalloc => [GMallocPatterns, GMallocString]);
 impl_resolver_singleton!(all, GMalloc, |ctx| async {
     //eprintln!("GMalloc Scan Start!");
     let (patterns, strings) = join!(
@@ -5,7 +5,10 @@ use futures::{future::join_all, try_join};
 use patternsleuth_scanner::Pattern;
 
 use crate::{
-    resolvers::{ensure_one, impl_resolver_singleton, try_ensure_one, unreal::util, Result},
+    resolvers::{
+        ensure_one, impl_resolver_singleton, resolver_dependencies, try_ensure_one, unreal::util,
+        Result,
+    },
     MemoryTrait,
 };
 
@@ -94,6 +97,7 @@ impl_resolver_singleton!(all, FUObjectArrayAllocateUObjectIndex, |ctx| async {
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct FUObjectArrayFreeUObjectIndex(pub usize);
+resolver_dependencies!(FUObjectArrayFreeUObjectIndex => [FUObjectArrayAllocateUObjectIndex]);
 impl_resolver_singleton!(all, FUObjectArrayFreeUObjectIndex, |ctx| async {
     let refs_future = async {
         let search_strings = [
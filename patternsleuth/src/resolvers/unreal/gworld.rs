@@ -0,0 +1,59 @@
+use std::fmt::Debug;
+
+use futures::future::join_all;
+
+use patternsleuth_scanner::Pattern;
+
+use crate::{
+    resolvers::{impl_resolver_singleton, try_ensure_one, Result},
+    MemoryTrait,
+};
+
+/// UWorld* GWorld
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GWorld(pub usize);
+impl_resolver_singleton!(all, GWorld, |ctx| async {
+    let patterns = [
+        "0F 2E ?? 74 ?? 48 8B 1D | ?? ?? ?? ?? 48 85 DB 74", // 4.2x
+        "48 8B 1D | ?? ?? ?? ?? 48 85 DB 74 ?? 41 B0 01",    // 4.2x, 5.x
+        "48 8B 05 | ?? ?? ?? ?? 48 3B C3 48 0F 44 C6",       // 5.x
+        "48 89 05 | ?? ?? ?? ?? 48 8B 8E ?? ?? ?? ?? 48 85 C9 74 ?? 48 8B 01 FF 90", // UWorld::InitWorld
+    ];
+
+    let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
+
+    Ok(Self(try_ensure_one(res.iter().flatten().map(
+        |a| -> Result<usize> { Ok(ctx.image().memory.rip4(*a)?) },
+    ))?))
+});
+
+#[cfg(test)]
+mod test {
+    use object::SectionKind;
+
+    use super::*;
+    use crate::{resolvers::resolve, testing::TestImageBuilder};
+
+    #[test]
+    fn test_gworld() {
+        let base = 0x140000000;
+        let text = base + 0x1000;
+        let gworld = base + 0x2000;
+        // mov rbx, [GWorld]; test rbx, rbx; jz +3; mov r8b, 1
+        let mut code = vec![0x48, 0x8b, 0x1d];
+        code.extend(((gworld - (text + 7)) as i32).to_le_bytes());
+        code.extend([0x48, 0x85, 0xdb, 0x74, 0x03, 0x41, 0xb0, 0x01]);
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, text, 0x1000)
+            .section(".data", SectionKind::Data, gworld, 0x1000)
+            .write(text, &code)
+            .build()
+            .unwrap();
+
+        assert_eq!(resolve(&image, GWorld::resolver()).unwrap(), GWorld(gworld));
+    }
+}
@@ -0,0 +1,37 @@
+//! Entry points for injecting input at the engine level.
+//!
+//! EnhancedInput exposes injection to blueprints so its exec thunks can be found by name in the
+//! native function tables. The legacy `UPlayerInput::InputKey`/`ProcessInputStack` path has no
+//! such anchor in shipping builds, its `UPlayerInput` is found at runtime instead with
+//! [`World::local_player_input`](crate::ue::World::local_player_input).
+
+use crate::resolvers::{ensure_one, impl_resolver_singleton, unreal::util};
+
+/// `IEnhancedInputSubsystemInterface::execInjectInputForAction`, the blueprint thunk of
+/// `InjectInputForAction(const UInputAction*, FInputActionValue, const TArray<UInputModifier*>&,
+/// const TArray<UInputTrigger*>&)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EnhancedInputInjectInputForAction(pub usize);
+impl_resolver_singleton!(all, EnhancedInputInjectInputForAction, |ctx| async {
+    Ok(Self(ensure_one(
+        util::scan_native_functions(ctx, "InjectInputForAction").await,
+    )?))
+});
+
+/// `IEnhancedInputSubsystemInterface::execInjectInputVectorForAction`, the blueprint thunk of
+/// `InjectInputVectorForAction(const UInputAction*, FVector, ...)` (UE 5.0+)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EnhancedInputInjectInputVectorForAction(pub usize);
+impl_resolver_singleton!(all, EnhancedInputInjectInputVectorForAction, |ctx| async {
+    Ok(Self(ensure_one(
+        util::scan_native_functions(ctx, "InjectInputVectorForAction").await,
+    )?))
+});
@@ -6,7 +6,8 @@ use patternsleuth_scanner::Pattern;
 
 use crate::{
     resolvers::{
-        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, try_ensure_one, Result,
+        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, resolver_dependencies,
+        try_ensure_one, Addresses, Multi, Result,
     },
     Addressable, Matchable, MemoryTrait,
 };
@@ -38,6 +39,7 @@ impl_resolver_singleton!(all, UObjectSkipFunction, |ctx| async {
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct GNatives(pub usize);
+resolver_dependencies!(GNatives => [UObjectSkipFunction]);
 impl_resolver_singleton!(collect, GNatives);
 impl_resolver_singleton!(PEImage, GNatives, |ctx| async {
     use iced_x86::{Code, Register};
@@ -95,15 +97,15 @@ impl_resolver_singleton!(ElfImage, GNatives, |ctx| async {
 )]
 pub struct FFrameStep(pub usize);
 impl_resolver_singleton!(all, FFrameStep, |ctx| async {
-    let patterns = [
-        "48 8B 41 20 4C 8B D2 48 8B D1 44 0F B6 08 48 FF C0 48 89 41 20 41 8B C1 4C 8D 0D ?? ?? ?? ?? 49 8B CA 49 FF 24 C1",
-        // linux
-        "01001??? 89 f8 01001??? 8b 4f 20 01001??? 8d 79 01 01001??? 89 78 20 0f b6 09 01001??? 8b 0c ?????101 ?? ?? ?? ?? 01001??? 89 f7 01001??? 89 c6 ff e1",
-    ];
-
-    let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
+    let (windows, linux) = futures::join!(
+        // relaxed in case FFrame::Code moves away from 0x20
+        ctx.scan_relaxed(Pattern::new("48 8B 41 20 4C 8B D2 48 8B D1 44 0F B6 08 48 FF C0 48 89 41 20 41 8B C1 4C 8D 0D ?? ?? ?? ?? 49 8B CA 49 FF 24 C1").unwrap()),
+        ctx.scan(Pattern::new("01001??? 89 f8 01001??? 8b 4f 20 01001??? 8d 79 01 01001??? 89 78 20 0f b6 09 01001??? 8b 0c ?????101 ?? ?? ?? ?? 01001??? 89 f7 01001??? 89 c6 ff e1").unwrap()),
+    );
 
-    Ok(FFrameStep(ensure_one(res.into_iter().flatten())?))
+    Ok(FFrameStep(ensure_one(
+        windows.matches.into_iter().chain(linux),
+    )?))
 });
 
 /// public: void __cdecl FFrame::StepExplicitProperty(void *const, class FProperty *)
@@ -140,7 +142,7 @@ pub struct FFrameStepViaExec {
     pub step: usize,
     pub step_explicit_property: usize,
 }
-impl_resolver!(all, FFrameStepViaExec, |ctx| async {
+impl_resolver!(all, multi, FFrameStepViaExec, |ctx| async {
     let patterns = [
         "48 89 5C 24 08 48 89 74 24 10 57 48 83 EC ?? 33 FF 33 C0 49 8B F0 48 8B DA 48 8B CA 48 89 7C 24 20 48 89 7C 24 28 48 39 42 20 74 10 48 8B 52 18 4C 8D 44 24 20 E8 [ ?? ?? ?? ?? ] EB 1C 4C 8B 82 80 00 00 00 49 8B 40 ?? 48 89 82 80 00 00 00 48 8D 54 24 20 E8 [ ?? ?? ?? ?? ] 48 8B 43 20 48 8D 4C 24 20 48 85 C0",
         "48 89 5C 24 08 48 89 74 24 10 57 48 83 EC ?? 33 FF 49 8B F0 48 8B DA 48 89 7C 24 20 48 ?? ?? ?? ?? ?? ?? ?? 48 39 7A 20 74 10 48 8B 52 18 4C 8D 44 24 20 E8 [ ?? ?? ?? ?? ] EB 1C 4C 8B 82 80 00 00 00 49 8B 40 ?? 48 89 82 80 00 00 00 48 8D 54 24 20 E8 [ ?? ?? ?? ?? ] 48 8B 43 20 48 8D 4C 24 20 48 85 C0 40 0F",
@@ -168,3 +170,17 @@ impl_resolver!(all, FFrameStepViaExec, |ctx| async {
             }),
     )
 });
+impl Multi for FFrameStepViaExec {
+    fn addresses(&self) -> Addresses {
+        Addresses::Named(
+            [
+                ("step".to_string(), self.step),
+                (
+                    "step_explicit_property".to_string(),
+                    self.step_explicit_property,
+                ),
+            ]
+            .into(),
+        )
+    }
+}
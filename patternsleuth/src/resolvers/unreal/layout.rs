@@ -0,0 +1,509 @@
+//! Layout probes: struct sizes and field offsets derived from resolved code instead of being
+//! hardcoded per engine version.
+
+use std::collections::{HashMap, HashSet};
+
+use futures::join;
+use iced_x86::{Instruction, Mnemonic, OpKind, Register};
+
+use crate::{
+    disassemble::{disassemble, Control},
+    resolvers::{
+        bail_out, ensure_one, impl_resolver, resolver_dependencies,
+        unreal::fname::FNameToString,
+        unreal::guobject_array::{
+            FUObjectArrayAllocateUObjectIndex, FUObjectArrayFreeUObjectIndex,
+        },
+        unreal::util,
+        AsyncContext, Multi, Result, Value, Values,
+    },
+    ue::{BuildConfig, Configuration},
+    MemoryTrait,
+};
+
+/// Maximum number of bytes inspected when the image has no exception data to bound functions
+const FALLBACK_FUNCTION_SIZE: usize = 0x400;
+
+/// Visit every instruction of the function at `address` (following branches but staying inside
+/// the function)
+pub(crate) fn visit_function(
+    ctx: &AsyncContext<'_>,
+    address: usize,
+    mut visitor: impl FnMut(&Instruction),
+) -> Result<()> {
+    let image = ctx.image();
+    let range = image
+        .get_root_function_range(address)?
+        .unwrap_or(address..address + FALLBACK_FUNCTION_SIZE);
+    disassemble(image, address, |inst| {
+        if !range.contains(&(inst.ip() as usize)) {
+            return Ok(Control::Break);
+        }
+        visitor(inst);
+        Ok(Control::Continue)
+    })?;
+    Ok(())
+}
+
+fn is_simple_memory_operand(inst: &Instruction, operand: u32, base: &HashSet<Register>) -> bool {
+    inst.op_kind(operand) == OpKind::Memory
+        && base.contains(&inst.memory_base())
+        && inst.memory_index() == Register::None
+}
+
+/// sizeof(FName): 8 bytes, or 12 with WITH_CASE_PRESERVING_NAME where a DisplayIndex follows
+/// the ComparisonIndex
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FNameSize(pub usize);
+
+resolver_dependencies!(FNameSize => [FNameToString]);
+impl_resolver!(all, multi, FNameSize, |ctx| async {
+    ctx.memoized_values("FNameSize", size_from_values(Self), fname_size(ctx))
+        .await
+});
+impl Multi for FNameSize {
+    fn values(&self) -> Values {
+        Values::single(Value::Int(self.0 as i64))
+    }
+}
+
+/// Rebuild a resolution holding a single size from its [`Multi::values`]
+fn size_from_values<T>(resolution: fn(usize) -> T) -> impl FnOnce(&Values) -> Option<T> {
+    move |values| match values.get("")? {
+        Value::Int(size) => Some(resolution((*size).try_into().ok()?)),
+        _ => None,
+    }
+}
+
+async fn fname_size(ctx: &AsyncContext<'_>) -> Result<FNameSize> {
+    let to_string = ctx.resolve(FNameToString::resolver()).await?;
+
+    // FName::ToString starts by checking `Number != NAME_NO_NUMBER_INTERNAL` on `this`:
+    // cmp dword ptr [rcx+<offset of Number>], 0
+    let this = HashSet::from([Register::RCX]);
+    let mut offsets = vec![];
+    visit_function(ctx, to_string.0, |inst| {
+        if inst.mnemonic() == Mnemonic::Cmp
+            && is_simple_memory_operand(inst, 0, &this)
+            && inst.memory_size().size() == 4
+            && matches!(
+                inst.op1_kind(),
+                OpKind::Immediate8to32 | OpKind::Immediate32
+            )
+            && inst.immediate(1) == 0
+        {
+            offsets.push(inst.memory_displacement64());
+        }
+    })?;
+
+    match ensure_one(offsets.into_iter().filter(|o| *o == 4 || *o == 8))? {
+        4 => Ok(FNameSize(8)),
+        _ => Ok(FNameSize(12)),
+    }
+}
+
+/// sizeof(FUObjectItem), the stride of the chunked GUObjectArray
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FUObjectItemSize(pub usize);
+
+resolver_dependencies!(FUObjectItemSize => [FUObjectArrayFreeUObjectIndex]);
+impl_resolver!(all, multi, FUObjectItemSize, |ctx| async {
+    ctx.memoized_values(
+        "FUObjectItemSize",
+        size_from_values(Self),
+        fuobject_item_size(ctx),
+    )
+    .await
+});
+impl Multi for FUObjectItemSize {
+    fn values(&self) -> Values {
+        Values::single(Value::Int(self.0 as i64))
+    }
+}
+
+async fn fuobject_item_size(ctx: &AsyncContext<'_>) -> Result<FUObjectItemSize> {
+    let free = ctx
+        .resolve(FUObjectArrayFreeUObjectIndex::resolver())
+        .await?;
+
+    // indexing Objects[Index / NumElementsPerChunk][Index % NumElementsPerChunk] multiplies the
+    // index by the item size using one of:
+    //   imul r, r, size
+    //   lea r, [r+r*2] followed by [base+r*8] (0x18)
+    //   shl r, 4 (0x10)
+    let mut candidates: HashMap<usize, usize> = HashMap::new();
+    let mut times_three = HashSet::new();
+    visit_function(ctx, free.0, |inst| {
+        let size = match inst.mnemonic() {
+            Mnemonic::Imul if inst.op_count() == 3 => Some(inst.immediate(2) as usize),
+            Mnemonic::Shl if inst.op1_kind() == OpKind::Immediate8 => {
+                Some(1usize << inst.immediate8())
+            }
+            Mnemonic::Lea
+                if inst.memory_index_scale() == 2 && inst.memory_base() == inst.memory_index() =>
+            {
+                times_three.insert(inst.op0_register().full_register());
+                None
+            }
+            _ if (0..inst.op_count()).any(|i| inst.op_kind(i) == OpKind::Memory)
+                && inst.memory_index_scale() == 8
+                && times_three.contains(&inst.memory_index().full_register()) =>
+            {
+                Some(0x18)
+            }
+            _ => None,
+        };
+        if let Some(size) = size.filter(|s| (0x10..=0x40).contains(s) && s % 8 == 0) {
+            *candidates.entry(size).or_default() += 1;
+        }
+    })?;
+
+    match candidates
+        .into_iter()
+        .max_by_key(|(size, count)| (*count, *size))
+    {
+        Some((size, _)) => Ok(FUObjectItemSize(size)),
+        None => bail_out!("no item stride found"),
+    }
+}
+
+/// Offsets of UObjectBase::ObjectFlags and UObjectBase::InternalIndex
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UObjectBaseLayout {
+    pub object_flags: usize,
+    pub internal_index: usize,
+}
+
+resolver_dependencies!(UObjectBaseLayout => [FUObjectArrayAllocateUObjectIndex]);
+impl_resolver!(all, multi, UObjectBaseLayout, |ctx| async {
+    let from_values =
+        |values: &Values| match (values.get("object_flags")?, values.get("internal_index")?) {
+            (Value::Offset(object_flags), Value::Offset(internal_index)) => Some(Self {
+                object_flags: *object_flags,
+                internal_index: *internal_index,
+            }),
+            _ => None,
+        };
+    ctx.memoized_values("UObjectBaseLayout", from_values, uobject_base_layout(ctx))
+        .await
+});
+impl Multi for UObjectBaseLayout {
+    fn values(&self) -> Values {
+        Values(
+            [
+                ("object_flags".to_string(), Value::Offset(self.object_flags)),
+                (
+                    "internal_index".to_string(),
+                    Value::Offset(self.internal_index),
+                ),
+            ]
+            .into(),
+        )
+    }
+}
+
+async fn uobject_base_layout(ctx: &AsyncContext<'_>) -> Result<UObjectBaseLayout> {
+    let allocate = ctx
+        .resolve(FUObjectArrayAllocateUObjectIndex::resolver())
+        .await?;
+
+    // AllocateUObjectIndex(UObjectBase* Object, ...) ends with `Object->InternalIndex = Index`:
+    // mov dword ptr [rdx+<offset>], r32 (rdx is commonly copied to a callee saved register
+    // first)
+    let mut object = HashSet::from([Register::RDX]);
+    let mut offsets = vec![];
+    visit_function(ctx, allocate.0, |inst| {
+        if inst.mnemonic() != Mnemonic::Mov {
+            return;
+        }
+        if inst.op0_kind() == OpKind::Register
+            && inst.op1_kind() == OpKind::Register
+            && object.contains(&inst.op1_register())
+        {
+            object.insert(inst.op0_register());
+        } else if is_simple_memory_operand(inst, 0, &object)
+            && inst.op1_kind() == OpKind::Register
+            && inst.op1_register().size() == 4
+        {
+            offsets.push(inst.memory_displacement64() as usize);
+        }
+    })?;
+
+    // ObjectFlags directly precedes InternalIndex and follows the vtable pointer
+    let internal_index = ensure_one(offsets.into_iter().filter(|o| (0xc..=0x10).contains(o)))?;
+    Ok(UObjectBaseLayout {
+        object_flags: internal_index - 4,
+        internal_index,
+    })
+}
+
+/// Whether the game was built WITH_EDITOR, detected by the launch module loading `UnrealEd`
+/// which only happens in editor builds
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct WithEditor(pub bool);
+
+impl_resolver!(all, multi, WithEditor, |ctx| async {
+    let mem = &ctx.image().memory;
+    // the whole string rather than the end of e.g. "/Script/UnrealEd"
+    let found = ctx
+        .scan_utf16("UnrealEd")
+        .await
+        .iter()
+        .any(|s| matches!(mem.u16_le(s.address.wrapping_sub(2)), Ok(0) | Err(_)));
+    Ok(Self(found))
+});
+impl Multi for WithEditor {
+    fn values(&self) -> Values {
+        Values::single(Value::Bool(self.0))
+    }
+}
+
+/// Build configuration of the game, read from the PDB path in the CodeView debug record which
+/// keeps the `-<Configuration>` suffix of the binary name (`FSD-Win64-Shipping.pdb`) even when
+/// the executable has been renamed. Fails for images without debug record.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct BuildConfiguration(pub Configuration);
+
+impl_resolver!(all, BuildConfiguration, |ctx| async {
+    let Some(pdb_path) = ctx.image().pdb_path() else {
+        bail_out!("image has no CodeView debug record");
+    };
+    let name = pdb_path.rsplit(['/', '\\']).next().unwrap_or_default();
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let configuration = [
+        ("-Shipping", Configuration::Shipping),
+        ("-Test", Configuration::Test),
+        ("-DebugGame", Configuration::DebugGame),
+        ("-Debug", Configuration::Debug),
+    ]
+    .into_iter()
+    .find(|(suffix, _)| stem.ends_with(suffix))
+    .map(|(_, configuration)| configuration)
+    // the only configuration without suffix
+    .unwrap_or(Configuration::Development);
+    Ok(Self(configuration))
+});
+
+/// Whether `check`s are compiled in (DO_CHECK), detected from the message of the bounds check in
+/// `TArray::RangeCheck`, the most widely inlined `checkf` of the engine
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct DoCheck(pub bool);
+
+impl_resolver!(all, DoCheck, |ctx| async {
+    let checks = ctx
+        .scan(util::utf16_pattern("Array index out of bounds: "))
+        .await;
+    Ok(Self(!checks.is_empty()))
+});
+
+/// Build options of the game, see [`BuildConfig`]. The configuration is left unknown rather
+/// than failing for images without [`BuildConfiguration`].
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EngineBuildConfig(pub BuildConfig);
+
+resolver_dependencies!(EngineBuildConfig => [FNameSize, WithEditor, BuildConfiguration, DoCheck]);
+impl_resolver!(all, EngineBuildConfig, |ctx| async {
+    let (fname_size, with_editor, configuration, do_check) = join!(
+        ctx.resolve(FNameSize::resolver()),
+        ctx.resolve(WithEditor::resolver()),
+        ctx.resolve(BuildConfiguration::resolver()),
+        ctx.resolve(DoCheck::resolver()),
+    );
+    Ok(Self(
+        BuildConfig::new()
+            .case_preserving_names(fname_size?.0 == 12)
+            .with_editor(with_editor?.0)
+            .configuration(configuration.ok().map(|c| c.0))
+            .do_check(do_check?.0),
+    ))
+});
+
+#[cfg(test)]
+mod test {
+    use object::SectionKind;
+
+    use super::*;
+    use crate::{resolvers::resolve, testing::TestImageBuilder};
+
+    fn image(pdb_path: Option<&str>, checks: bool) -> crate::image::Image<'static> {
+        let base = 0x140000000;
+        let rdata = base + 0x2000;
+        let mut builder = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, rdata, 0x1000);
+        if let Some(pdb_path) = pdb_path {
+            builder = builder.pdb_path(pdb_path);
+        }
+        if checks {
+            builder = builder.write_utf16(
+                rdata,
+                "Array index out of bounds: %lld from an array of size %lld",
+            );
+        }
+        builder.build().unwrap()
+    }
+
+    /// Image with FName::ToString comparing the number at `name_number` and
+    /// FUObjectArray::FreeUObjectIndex indexing the chunk with `stride`
+    fn probe_image(name_number: u8, stride: &[u8]) -> crate::image::Image<'static> {
+        let base = 0x140000000;
+        let text = base + 0x1000;
+        let rdata = base + 0x2000;
+        let (to_string, allocate, free) = (text, text + 0x100, text + 0x200);
+        let (allocate_string, free_string) = (rdata, rdata + 0x100);
+        let lea_rcx = |address: usize, target: usize| {
+            let mut code = vec![0x48, 0x8d, 0x0d];
+            code.extend(((target as i64 - (address + 7) as i64) as i32).to_le_bytes());
+            code
+        };
+
+        // push rsi; push rdi; sub rsp, 28h; mov rsi, rdx; mov rdi, rcx;
+        // cmp dword ptr [rcx+name_number], 0; je +1; nop; add rsp, 28h; pop rdi; pop rsi; ret
+        let to_string_code = [
+            0x56,
+            0x57,
+            0x48,
+            0x83,
+            0xec,
+            0x28,
+            0x48,
+            0x89,
+            0xd6,
+            0x48,
+            0x89,
+            0xcf,
+            0x83,
+            0x79,
+            name_number,
+            0x00,
+            0x74,
+            0x01,
+            0x90,
+            0x48,
+            0x83,
+            0xc4,
+            0x28,
+            0x5f,
+            0x5e,
+            0xc3,
+        ];
+        // mov rbx, rdx; lea rcx, [string]; mov [rbx+0Ch], eax; mov [rbx+20h], ecx; ret
+        let mut allocate_code = vec![0x48, 0x89, 0xd3];
+        allocate_code.extend(lea_rcx(allocate + 3, allocate_string));
+        allocate_code.extend([0x89, 0x43, 0x0c, 0x89, 0x4b, 0x20, 0xc3]);
+        // lea rcx, [string]; <stride>; ret
+        let mut free_code = lea_rcx(free, free_string);
+        free_code.extend(stride);
+        free_code.push(0xc3);
+
+        TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, text, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, rdata, 0x1000)
+            .write(to_string, &to_string_code)
+            .write(allocate, &allocate_code)
+            .write(free, &free_code)
+            .function(to_string..to_string + to_string_code.len())
+            .function(allocate..allocate + allocate_code.len())
+            .function(free..free + free_code.len())
+            .write_utf16(
+                allocate_string,
+                "Unable to add more objects to disregard for GC pool (Max: %d)",
+            )
+            .write_utf16(free_string, "Unexpected concurency while adding new object")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_layout_probes() {
+        // lea rax, [rax+rax*2]; mov rax, [rdx+rax*8]
+        let image = probe_image(8, &[0x48, 0x8d, 0x04, 0x40, 0x48, 0x8b, 0x04, 0xc2]);
+        assert_eq!(
+            resolve(&image, FNameSize::resolver()).unwrap(),
+            FNameSize(12)
+        );
+        assert_eq!(
+            resolve(&image, FUObjectItemSize::resolver()).unwrap(),
+            FUObjectItemSize(0x18)
+        );
+        assert_eq!(
+            resolve(&image, UObjectBaseLayout::resolver()).unwrap(),
+            UObjectBaseLayout {
+                object_flags: 8,
+                internal_index: 0xc,
+            }
+        );
+
+        // shl rax, 4
+        let image = probe_image(4, &[0x48, 0xc1, 0xe0, 0x04]);
+        assert_eq!(
+            resolve(&image, FNameSize::resolver()).unwrap(),
+            FNameSize(8)
+        );
+        assert_eq!(
+            resolve(&image, FUObjectItemSize::resolver()).unwrap(),
+            FUObjectItemSize(0x10)
+        );
+    }
+
+    #[test]
+    fn test_build_configuration() {
+        for (pdb_path, configuration) in [
+            (
+                "D:\\build\\FSD\\Binaries\\Win64\\FSD-Win64-Shipping.pdb",
+                Configuration::Shipping,
+            ),
+            (
+                "C:/Game/Binaries/Win64/Game-Win64-DebugGame.pdb",
+                Configuration::DebugGame,
+            ),
+            ("Game-Win64-Debug.pdb", Configuration::Debug),
+            ("Game.pdb", Configuration::Development),
+        ] {
+            let image = image(Some(pdb_path), false);
+            assert_eq!(
+                resolve(&image, BuildConfiguration::resolver()).unwrap(),
+                BuildConfiguration(configuration),
+                "{pdb_path}"
+            );
+        }
+        assert!(resolve(&image(None, false), BuildConfiguration::resolver()).is_err());
+
+        assert_eq!(
+            resolve(&image(None, true), DoCheck::resolver()).unwrap(),
+            DoCheck(true)
+        );
+        assert_eq!(
+            resolve(&image(None, false), DoCheck::resolver()).unwrap(),
+            DoCheck(false)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Lookups into the localized text of `FTextLocalizationManager`.
+//!
+//! `FTextLocalizationManager::Get` is a lazy singleton accessor without a string anchor in
+//! shipping builds so it isn't resolved. The blueprint lookups into the live table are found by
+//! name in the native function tables instead, and the loaded strings can be read from a live
+//! process with [`read_localization_table`](crate::ue::read_localization_table).
+
+use crate::resolvers::{ensure_one, impl_resolver_singleton, unreal::util};
+
+/// `UKismetTextLibrary::execFindTextInLocalizationTable`, the blueprint thunk of
+/// `FindTextInLocalizationTable(const FString& Namespace, const FString& Key, FText& OutText,
+/// const FString& SourceString)` which looks up the live table (UE 4.20+)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UKismetTextLibraryFindTextInLocalizationTable(pub usize);
+impl_resolver_singleton!(
+    all,
+    UKismetTextLibraryFindTextInLocalizationTable,
+    |ctx| async {
+        Ok(Self(ensure_one(
+            util::scan_native_functions(ctx, "FindTextInLocalizationTable").await,
+        )?))
+    }
+);
+
+/// `UKismetTextLibrary::execTextFromStringTable`, the blueprint thunk of
+/// `TextFromStringTable(FName TableId, const FString& Key)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UKismetTextLibraryTextFromStringTable(pub usize);
+impl_resolver_singleton!(all, UKismetTextLibraryTextFromStringTable, |ctx| async {
+    Ok(Self(ensure_one(
+        util::scan_native_functions(ctx, "TextFromStringTable").await,
+    )?))
+});
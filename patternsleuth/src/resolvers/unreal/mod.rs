@@ -1,5 +1,10 @@
 pub mod aes;
+pub mod asset_registry;
+pub mod audio;
 pub mod blueprint_library;
+pub mod config;
+pub mod crash;
+pub mod delegates;
 pub mod engine_version;
 pub mod fname;
 pub mod ftext;
@@ -8,11 +13,20 @@ pub mod game_loop;
 pub mod gengine;
 pub mod gmalloc;
 pub mod guobject_array;
+pub mod gworld;
+pub mod input;
 pub mod kismet;
+pub mod layout;
+pub mod localization;
+pub mod module_manager;
 pub mod pak;
+pub mod process_event;
+pub mod replay;
 pub mod save_game;
+pub mod slate;
 pub mod static_construct_object;
 pub mod static_find_object;
+pub mod trace;
 
 use std::{
     collections::{HashMap, HashSet},
@@ -27,7 +41,8 @@ use patternsleuth_scanner::Pattern;
 use crate::{
     disassemble::{disassemble, Control},
     resolvers::{
-        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, try_ensure_one, Result,
+        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, try_ensure_one, Addresses,
+        Multi, Result,
     },
     Addressable, Image, Matchable, MemoryTrait,
 };
@@ -95,6 +110,46 @@ pub mod util {
         refs.into_iter().flatten().collect()
     }
 
+    /// Exec thunks (`execFoo`) of UFunctions named `name`, found through the
+    /// `FNameNativePtrPair` tables classes pass to `FNativeFunctionRegistrar::RegisterFunctions`
+    pub async fn scan_native_functions(ctx: &AsyncContext<'_>, name: &str) -> Vec<usize> {
+        let strings = ctx.scan(utf8_pattern(&format!("{name}\0"))).await;
+        let pairs = join_all(
+            strings
+                .iter()
+                .map(|s| ctx.scan(Pattern::from_bytes(usize::to_le_bytes(*s).into()).unwrap())),
+        )
+        .await;
+
+        let image = ctx.image();
+        pairs
+            .into_iter()
+            .flatten()
+            .filter_map(|pair| image.memory.ptr(pair + 8).ok())
+            .filter(|f| image.is_code(*f))
+            .collect()
+    }
+
+    /// Declare a singleton resolver for the exec thunk of a native UFunction, found by name
+    /// with [`scan_native_functions`]
+    macro_rules! native_function_resolver {
+        ($name:ident, $class:literal, $function:literal) => {
+            #[doc = concat!("`", $class, "::exec", $function, "`")]
+            #[derive(Debug, PartialEq)]
+            #[cfg_attr(
+                feature = "serde-resolvers",
+                derive(serde::Serialize, serde::Deserialize)
+            )]
+            pub struct $name(pub usize);
+            $crate::resolvers::impl_resolver_singleton!(all, $name, |ctx| async {
+                Ok(Self($crate::resolvers::ensure_one(
+                    $crate::resolvers::unreal::util::scan_native_functions(ctx, $function).await,
+                )?))
+            });
+        };
+    }
+    pub(crate) use native_function_resolver;
+
     pub async fn scan_xcalls(
         ctx: &AsyncContext<'_>,
         addresses: impl IntoIterator<Item = &usize> + Copy,
@@ -172,6 +227,33 @@ pub mod util {
         Ok(calls)
     }
 
+    /// Functions run before `main`: TLS callbacks, which the loader runs first, then the entry
+    /// point. Globals initialized early (encryption keys, `GConfig`) are often only reachable
+    /// from these.
+    pub fn pre_main_functions(img: &Image<'_>) -> Vec<usize> {
+        img.tls_callbacks()
+            .into_iter()
+            .chain(img.entry_point())
+            .collect()
+    }
+
+    /// Calls and jumps out of the entry point in order, e.g. for MSVC's `mainCRTStartup` the
+    /// first is `__security_init_cookie` and the last `__scrt_common_main_seh`
+    pub fn entry_calls(img: &Image<'_>) -> Result<Vec<Call>> {
+        let Some(entry) = img.entry_point() else {
+            bail_out!("image has no entry point");
+        };
+        find_calls(img, entry)
+    }
+
+    /// The `n`th call or jump out of the entry point, see [`entry_calls`]
+    pub fn nth_entry_call(img: &Image<'_>, n: usize) -> Result<usize> {
+        let Some(call) = entry_calls(img)?.get(n).copied() else {
+            bail_out!(format!("entry point makes fewer than {} calls", n + 1));
+        };
+        Ok(call.callee)
+    }
+
     pub fn find_path(
         img: &Image<'_>,
         f: usize,
@@ -246,24 +328,17 @@ pub mod util {
 )]
 pub struct KismetSystemLibrary(pub HashMap<String, usize>);
 
-impl_resolver!(all, KismetSystemLibrary, |ctx| async {
+impl_resolver!(all, multi, KismetSystemLibrary, |ctx| async {
     let mem = &ctx.image().memory;
 
-    let s = Pattern::from_bytes(
-        "KismetSystemLibrary\x00"
-            .encode_utf16()
-            .flat_map(u16::to_le_bytes)
-            .collect(),
-    )
-    .unwrap();
-    let strings = ctx.scan(s).await;
+    let strings = ctx.scan_utf16("KismetSystemLibrary").await;
 
     let refs = join_all(strings.iter().map(|s| {
         ctx.scan(
             Pattern::new(format!(
         // fragile (only 4.25-4.27 most likely)
         "4c 8d 0d [ ?? ?? ?? ?? ] 88 4c 24 70 4c 8d 05 ?? ?? ?? ?? 49 89 43 e0 48 8d 15 X0x{:x}",
-        s
+        s.address
     ))
             .unwrap(),
         )
@@ -297,6 +372,11 @@ impl_resolver!(all, KismetSystemLibrary, |ctx| async {
         bail_out!("did not match");
     }
 });
+impl Multi for KismetSystemLibrary {
+    fn addresses(&self) -> Addresses {
+        Addresses::Named(self.0.iter().map(|(k, v)| (k.clone(), *v)).collect())
+    }
+}
 
 #[derive(Debug, PartialEq)]
 #[cfg_attr(
@@ -397,7 +477,7 @@ impl_resolver!(all, UtilStringExtractor, |ctx| async {
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct A(pub HashSet<usize>);
-impl_resolver!(all, A, |ctx| async {
+impl_resolver!(all, multi, A, |ctx| async {
     let strings = ctx
         .scan(
             Pattern::new(
@@ -414,3 +494,8 @@ impl_resolver!(all, A, |ctx| async {
         .map(|a| Ok(mem.rip4(a)?))
         .collect::<Result<HashSet<_>>>()?))
 });
+impl Multi for A {
+    fn addresses(&self) -> Addresses {
+        Addresses::Indexed(self.0.iter().copied().sorted().collect())
+    }
+}
@@ -0,0 +1,148 @@
+//! `FModuleManager`, whose module registry can be read with
+//! [`read_modules`](crate::ue::read_modules).
+//!
+//! `FModuleManager::Get` has no anchor of its own. Every statically linked module registers
+//! itself from a static initializer through `FModuleManager::Get().RegisterStaticallyLinkedModule`
+//! with its name as a string, so `Get` is the function called by the initializers of all modules
+//! which lazily creates the singleton:
+//!
+//! ```text
+//! mov rax, [rip+ModuleManager]
+//! test rax, rax
+//! ```
+//!
+//! Only monolithic builds link modules statically, which covers shipping games.
+
+use std::collections::HashSet;
+
+use futures::future::join_all;
+use iced_x86::{Mnemonic, OpKind};
+
+use crate::{
+    resolvers::{
+        bail_out, ensure_one, impl_resolver_singleton, resolver_dependencies,
+        unreal::{layout::visit_function, util},
+        AsyncContext, Result,
+    },
+    MemoryTrait, SectionPermissions,
+};
+
+/// Modules every game links
+const MODULES: [&str; 5] = [
+    "CoreUObject",
+    "Engine",
+    "InputCore",
+    "SlateCore",
+    "RenderCore",
+];
+
+/// Call targets of the functions referencing the whole string `module`
+async fn initializer_calls(ctx: &AsyncContext<'_>, module: &str) -> Result<HashSet<usize>> {
+    let mem = &ctx.image().memory;
+    let strings = ctx
+        .scan_utf16(module)
+        .await
+        .into_iter()
+        .filter(|s| matches!(mem.u16_le(s.address.wrapping_sub(2)), Ok(0) | Err(_)))
+        .map(|s| s.address)
+        .collect::<Vec<_>>();
+    let refs = util::scan_xrefs(ctx, &strings).await;
+
+    let mut calls = HashSet::new();
+    for function in util::root_functions(ctx, &refs)? {
+        visit_function(ctx, function, |inst| {
+            if inst.is_call_near() {
+                calls.insert(inst.near_branch_target() as usize);
+            }
+        })?;
+    }
+    Ok(calls)
+}
+
+/// The lazily created singleton `Get` returns, if the function at `address` looks like `Get`
+fn lazy_singleton(ctx: &AsyncContext<'_>, address: usize) -> Result<Option<usize>> {
+    let image = ctx.image();
+    let mut loaded = None;
+    let mut singleton = None;
+    visit_function(ctx, address, |inst| {
+        if singleton.is_some() {
+            return;
+        }
+        match inst.mnemonic() {
+            Mnemonic::Mov
+                if inst.op0_kind() == OpKind::Register
+                    && inst.op0_register().size() == 8
+                    && inst.is_ip_rel_memory_operand() =>
+            {
+                loaded = Some((inst.op0_register(), inst.ip_rel_memory_address() as usize));
+            }
+            Mnemonic::Test
+                if inst.op0_kind() == OpKind::Register
+                    && inst.op1_kind() == OpKind::Register
+                    && inst.op0_register() == inst.op1_register() =>
+            {
+                if let Some((register, global)) = loaded {
+                    if register == inst.op0_register() {
+                        singleton = Some(global);
+                    }
+                }
+                loaded = None;
+            }
+            _ => loaded = None,
+        }
+    })?;
+    Ok(singleton.filter(|global| {
+        image
+            .memory
+            .get_section_containing(*global)
+            .is_ok_and(|s| s.permissions().allows(SectionPermissions::RW))
+    }))
+}
+
+/// `FModuleManager& FModuleManager::Get()`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FModuleManagerGet(pub usize);
+impl_resolver_singleton!(all, FModuleManagerGet, |ctx| async {
+    let calls = join_all(MODULES.map(|module| initializer_calls(ctx, module))).await;
+
+    // every module registers itself so Get is called by all initializers found
+    let mut common: Option<HashSet<usize>> = None;
+    for calls in calls {
+        let calls = calls?;
+        if calls.is_empty() {
+            continue;
+        }
+        common = Some(match common {
+            Some(common) => common.intersection(&calls).copied().collect(),
+            None => calls,
+        });
+    }
+    let Some(common) = common else {
+        bail_out!("no module initializers found");
+    };
+
+    // targets which can't be disassembled are not Get
+    Ok(Self(ensure_one(common.into_iter().filter(|function| {
+        matches!(lazy_singleton(ctx, *function), Ok(Some(_)))
+    }))?))
+});
+
+/// `static FModuleManager* ModuleManager`, the singleton created by [`FModuleManagerGet`]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GModuleManager(pub usize);
+resolver_dependencies!(GModuleManager => [FModuleManagerGet]);
+impl_resolver_singleton!(all, GModuleManager, |ctx| async {
+    let get = ctx.resolve(FModuleManagerGet::resolver()).await?;
+    match lazy_singleton(ctx, get.0)? {
+        Some(global) => Ok(Self(global)),
+        None => bail_out!("singleton not found"),
+    }
+});
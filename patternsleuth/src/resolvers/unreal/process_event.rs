@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use futures::future::join_all;
+use iced_x86::{Code, Decoder, DecoderOptions, FlowControl, OpKind, Register};
+use patternsleuth_scanner::Pattern;
+
+use crate::{
+    resolvers::{
+        bail_out, ensure_one, impl_resolver, impl_resolver_singleton, resolver_dependencies,
+        AsyncContext, Multi, Result, Value, Values,
+    },
+    Memory, MemoryTrait, SectionPermissions,
+};
+
+/// public: virtual void __cdecl UObject::ProcessEvent(class UFunction *, void *)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UObjectProcessEvent(pub usize);
+impl_resolver_singleton!(all, UObjectProcessEvent, |ctx| async {
+    let patterns = [
+        // 4.25-4.27, 5.x: compares InternalIndex (mov eax, [rcx+0Ch]) with a GUObjectArray field
+        "40 55 56 57 41 54 41 55 41 56 41 57 48 81 EC ?? ?? ?? ?? 48 8D 6C 24 ?? 48 89 9D ?? ?? ?? ?? 48 8B 05 ?? ?? ?? ?? 48 33 C5 48 89 85 ?? ?? ?? ?? 8B 41 0C 45 33 F6 3B 05 ?? ?? ?? ?? 4D 8B F8 48 8B F2 4C 8B E1",
+        // 4.2x
+        "40 55 56 57 41 54 41 55 41 56 41 57 48 81 EC ?? ?? ?? ?? 48 8D 6C 24 ?? 48 89 9D ?? ?? ?? ?? 48 8B 05 ?? ?? ?? ?? 48 33 C5 48 89 85 ?? ?? ?? ?? 4D 8B F8 48 8B F2 4C 8B E1",
+    ];
+
+    let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
+
+    Ok(Self(ensure_one(res.into_iter().flatten())?))
+});
+
+/// Slot of `UObject::ProcessEvent` in the `UObject` vtable, for calling it through an object
+/// instead of the resolved address so overrides are respected
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UObjectProcessEventVTableIndex(pub usize);
+resolver_dependencies!(UObjectProcessEventVTableIndex => [UObjectProcessEvent]);
+impl_resolver!(all, multi, UObjectProcessEventVTableIndex, |ctx| async {
+    let from_values = |values: &Values| match values.get("")? {
+        Value::Index(index) => Some(Self(*index)),
+        _ => None,
+    };
+    ctx.memoized_values(
+        "UObjectProcessEventVTableIndex",
+        from_values,
+        process_event_vtable_index(ctx),
+    )
+    .await
+});
+impl Multi for UObjectProcessEventVTableIndex {
+    fn values(&self) -> Values {
+        Values::single(Value::Index(self.0))
+    }
+}
+
+/// Minimum number of event thunks agreeing on the ProcessEvent vtable displacement
+const MIN_EVENT_THUNKS: usize = 3;
+
+async fn process_event_vtable_index(
+    ctx: &AsyncContext<'_>,
+) -> Result<UObjectProcessEventVTableIndex> {
+    let process_event = ctx.resolve(UObjectProcessEvent::resolver()).await?.0;
+    let mem = &ctx.image().memory;
+
+    // UHT generated event thunks call `ProcessEvent(FindFunctionChecked(NAME_Event), &Parms)`
+    // through the vtable, so the most common callee/displacement pair across those call sites is
+    // FindFunctionChecked and the ProcessEvent slot
+    let sites = join_all(
+        ["FF 90 ?? ?? 00 00", "FF A0 ?? ?? 00 00"]
+            .map(|p| ctx.scan_with_permissions(Pattern::new(p).unwrap(), SectionPermissions::X)),
+    )
+    .await;
+    let mut votes = HashMap::<(usize, usize), usize>::new();
+    for site in sites.into_iter().flatten() {
+        if let Some(call) = event_thunk_call(mem, site) {
+            *votes.entry(call).or_default() += 1;
+        }
+    }
+    let mut votes = votes.into_iter().collect::<Vec<_>>();
+    votes.sort_by_key(|(call, count)| (std::cmp::Reverse(*count), *call));
+    let displacement = match votes.as_slice() {
+        [] => bail_out!("no ProcessEvent call sites found"),
+        [(_, count), ..] if *count < MIN_EVENT_THUNKS => {
+            bail_out!("too few ProcessEvent call sites found")
+        }
+        [(_, a), (_, b), ..] if a == b => bail_out!("ambiguous ProcessEvent call sites"),
+        [((_, displacement), _), ..] => *displacement,
+    };
+    if !displacement.is_multiple_of(8) {
+        bail_out!("ProcessEvent call site displacement is not a vtable slot");
+    }
+
+    // confirm with a vtable holding ProcessEvent at that displacement after only code pointers
+    let is_code = |address: usize| {
+        mem.get_section_containing(address)
+            .is_ok_and(|s| s.permissions().allows(SectionPermissions::X))
+    };
+    let slots = ctx
+        .scan(Pattern::from_bytes(usize::to_le_bytes(process_event).into()).unwrap())
+        .await;
+    let confirmed = slots.into_iter().any(|slot| {
+        slot.is_multiple_of(8)
+            && slot >= displacement
+            && (slot - displacement..slot)
+                .step_by(8)
+                .all(|entry| mem.ptr(entry).is_ok_and(is_code))
+    });
+    if !confirmed {
+        bail_out!("UObject::ProcessEvent not found in any vtable at the called slot");
+    }
+    Ok(UObjectProcessEventVTableIndex(displacement / 8))
+}
+
+/// If `site` is a call or tail call through a vtable loaded into `rax` whose first argument
+/// (`rdx`, after `this`) is the result of a direct call made just before, e.g.
+///
+/// ```text
+/// call    UObject::FindFunctionChecked
+/// mov     rcx, rbx
+/// mov     rdx, rax
+/// mov     rax, [rbx]
+/// jmp     qword ptr [rax+220h]
+/// ```
+///
+/// returns the target of the direct call and the vtable displacement
+fn event_thunk_call(mem: &Memory<'_>, site: usize) -> Option<(usize, usize)> {
+    /// How far back to look for the direct call
+    const WINDOW: usize = 0x20;
+
+    let bytes = mem.range(site.checked_sub(WINDOW)?..site + 6).ok()?;
+    // instruction boundaries before `site` are unknown so try every start decoding onto it
+    (1..=WINDOW).find_map(|back| {
+        let start = WINDOW - back;
+        let mut decoder = Decoder::with_ip(
+            64,
+            &bytes[start..],
+            (site - back) as u64,
+            DecoderOptions::NONE,
+        );
+        let mut call = None;
+        let mut rdx_from_rax = false;
+        let mut vtable_loaded = false;
+        for inst in &mut decoder {
+            if inst.is_invalid() {
+                return None;
+            }
+            if inst.ip() as usize == site {
+                let through_vtable = matches!(
+                    inst.flow_control(),
+                    FlowControl::IndirectBranch | FlowControl::IndirectCall
+                ) && inst.op0_kind() == OpKind::Memory
+                    && inst.memory_base() == Register::RAX
+                    && inst.memory_index() == Register::None;
+                if !(through_vtable && rdx_from_rax && vtable_loaded) {
+                    return None;
+                }
+                return call.map(|call| (call, inst.memory_displacement64() as usize));
+            }
+            if inst.ip() as usize > site {
+                return None;
+            }
+            match inst.code() {
+                Code::Call_rel32_64 => {
+                    call = Some(inst.near_branch_target() as usize);
+                    rdx_from_rax = false;
+                    vtable_loaded = false;
+                }
+                Code::Mov_r64_rm64 | Code::Mov_rm64_r64
+                    if inst.op0_register() == Register::RDX
+                        && inst.op1_register() == Register::RAX =>
+                {
+                    rdx_from_rax = call.is_some() && !vtable_loaded;
+                }
+                Code::Mov_r64_rm64
+                    if inst.op0_register() == Register::RAX
+                        && inst.op1_kind() == OpKind::Memory
+                        && inst.memory_index() == Register::None
+                        && inst.memory_displacement64() == 0 =>
+                {
+                    vtable_loaded = true;
+                }
+                _ if inst.flow_control() != FlowControl::Next => call = None,
+                _ if inst.op0_register().full_register() == Register::RAX => vtable_loaded = false,
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use object::SectionKind;
+
+    use super::*;
+    use crate::{resolvers::resolve, testing::TestImageBuilder};
+
+    /// `ProcessEvent(FindFunctionChecked(NAME_Event), nullptr)` at `address` tail calling
+    /// through the vtable slot at `displacement`
+    fn event_thunk(address: usize, find_function: usize, displacement: u32) -> Vec<u8> {
+        // mov rbx, rcx; call FindFunctionChecked
+        let mut code = vec![0x48, 0x89, 0xcb, 0xe8];
+        code.extend(((find_function as i64 - (address + 8) as i64) as i32).to_le_bytes());
+        // mov rcx, rbx; xor r8d, r8d; mov rdx, rax; mov rax, [rbx]; jmp [rax+displacement]
+        code.extend([
+            0x48, 0x89, 0xd9, 0x45, 0x31, 0xc0, 0x48, 0x8b, 0xd0, 0x48, 0x8b, 0x03, 0xff, 0xa0,
+        ]);
+        code.extend(displacement.to_le_bytes());
+        code
+    }
+
+    #[test]
+    fn test_process_event_vtable_index() {
+        let base = 0x140000000;
+        let text = base + 0x1000;
+        let rdata = base + 0x2000;
+        let find_function = text + 0x800;
+        let process_event = text + 0x900;
+        let vtable = rdata + 0x100;
+
+        let mut builder = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, text, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, rdata, 0x1000)
+            .write(find_function, &[0xc3])
+            // some other virtual called with the result of another function
+            .write(
+                text + 0x300,
+                &event_thunk(text + 0x300, text + 0x810, 0x180),
+            )
+            // mov rax, [rbx]; call [rax+0x180] (not taking a function result)
+            .write(
+                text + 0x340,
+                &[0x48, 0x8b, 0x03, 0xff, 0x90, 0x80, 0x01, 0x00, 0x00],
+            )
+            .write(vtable - 8, &rdata.to_le_bytes());
+        for i in 0..4 {
+            let thunk = text + i * 0x40;
+            builder = builder.write(thunk, &event_thunk(thunk, find_function, 0x220));
+        }
+        for i in 0..0x44 {
+            builder = builder.write(vtable + i * 8, &find_function.to_le_bytes());
+        }
+        let prologue = "40 55 56 57 41 54 41 55 41 56 41 57 48 81 EC ?? ?? ?? ?? 48 8D 6C 24 ?? 48 89 9D ?? ?? ?? ?? 48 8B 05 ?? ?? ?? ?? 48 33 C5 48 89 85 ?? ?? ?? ?? 4D 8B F8 48 8B F2 4C 8B E1"
+            .split(' ')
+            .map(|b| u8::from_str_radix(b, 16).unwrap_or(0))
+            .collect::<Vec<_>>();
+        let image = builder
+            .write(process_event, &prologue)
+            .write(vtable + 0x220, &process_event.to_le_bytes())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            event_thunk_call(&image.memory, text + 0x40 + 0x14),
+            Some((find_function, 0x220))
+        );
+        assert_eq!(event_thunk_call(&image.memory, text + 0x343), None);
+        assert_eq!(
+            resolve(&image, UObjectProcessEventVTableIndex::resolver()).unwrap(),
+            UObjectProcessEventVTableIndex(0x44)
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Recording and playing back replays through the `UGameInstance` replay API.
+//!
+//! [`UGameInstanceReplay`] resolves the game instance replay virtuals and [`Replays`] wraps them
+//! for injected tools, constructing the `FString` and `TArray<FString>` arguments through GMalloc
+//! so the engine can take ownership of or copy them as usual. Since 4.26 the game instance
+//! forwards to `UReplaySubsystem` which makes the game instance the stable entry point across
+//! versions.
+
+use std::{ffi::c_void, io, path::Path};
+
+use itertools::Itertools;
+use patternsleuth_scanner::Pattern;
+
+use crate::{
+    resolvers::{bail_out, ensure_one, impl_resolver, Addresses, Multi, Result},
+    ue::{object_ptr::FName, read::ReadMemory, FStringOwned, Objects, TArrayOwned},
+    MemoryTrait,
+};
+
+use super::util;
+
+/// UGameInstance replay virtuals, declared consecutively so they occupy adjacent vtable slots:
+///
+/// - `virtual void StartRecordingReplay(const FString& InName, const FString& FriendlyName,
+///   const TArray<FString>& AdditionalOptions, TSharedPtr<IAnalyticsProvider> AnalyticsProvider)`
+/// - `virtual void StopRecordingReplay()`
+/// - `virtual bool PlayReplay(const FString& InName, UWorld* WorldOverride,
+///   const TArray<FString>& AdditionalOptions)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UGameInstanceReplay {
+    pub start_recording: usize,
+    pub stop_recording: usize,
+    pub play: usize,
+}
+impl_resolver!(all, multi, UGameInstanceReplay, |ctx| async {
+    // the recording logic formats the friendly name into the demo URL options. It lives in
+    // UGameInstance::StartRecordingReplay before 4.26 and UReplaySubsystem::RecordReplay after.
+    let strings = ctx.scan(util::utf16_pattern("DemoFriendlyName=%s\0")).await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    let record = ensure_one(util::root_functions(ctx, &refs)?)?;
+
+    // 4.26+: StartRecordingReplay is a thin wrapper calling (or tail calling) RecordReplay
+    let callers = util::scan_xcalls(ctx, &[record]).await;
+    let callers = util::root_functions(ctx, &callers)?;
+    let start_recording = match callers.iter().unique().collect_vec().as_slice() {
+        [] => record,
+        [wrapper] => **wrapper,
+        _ => bail_out!("found multiple callers of UReplaySubsystem::RecordReplay"),
+    };
+
+    // the remaining virtuals follow StartRecordingReplay in every vtable it appears in
+    let slots = ctx
+        .scan(Pattern::from_bytes(usize::to_le_bytes(start_recording).into()).unwrap())
+        .await;
+    let mem = &ctx.image().memory;
+    let is_function = |address: usize| -> Result<bool> {
+        Ok(ctx
+            .image()
+            .get_root_function(address)?
+            .is_some_and(|f| f.range.start == address))
+    };
+    let mut candidates = vec![];
+    for slot in slots.into_iter().filter(|slot| slot % 8 == 0) {
+        let stop_recording = mem.ptr(slot + 8)?;
+        let play = mem.ptr(slot + 16)?;
+        if is_function(stop_recording)? && is_function(play)? {
+            candidates.push(Self {
+                start_recording,
+                stop_recording,
+                play,
+            });
+        }
+    }
+
+    ensure_one(candidates)
+});
+impl Multi for UGameInstanceReplay {
+    fn addresses(&self) -> Addresses {
+        Addresses::Named(
+            [
+                ("start_recording", self.start_recording),
+                ("stop_recording", self.stop_recording),
+                ("play", self.play),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+        )
+    }
+}
+
+/// Network replay streamer to record to or play from, passed to the demo net driver as the
+/// `ReplayStreamerOverride` URL option
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayStreamer {
+    /// The streamer configured by the game (`[NetworkReplayStreaming] DefaultFactoryName`)
+    #[default]
+    Default,
+    /// `.replay` files in the demo directory, see [`local_replays`]
+    LocalFile,
+    /// Kept in memory for the lifetime of the process, e.g. for killcams
+    InMemory,
+}
+impl ReplayStreamer {
+    /// Name of the streaming module
+    pub fn module(self) -> Option<&'static str> {
+        match self {
+            Self::Default => None,
+            Self::LocalFile => Some("LocalFileNetworkReplayStreaming"),
+            Self::InMemory => Some("InMemoryNetworkReplayStreaming"),
+        }
+    }
+    fn option(self) -> Option<String> {
+        self.module()
+            .map(|module| format!("ReplayStreamerOverride={module}"))
+    }
+}
+
+type StartRecordingReplay = unsafe extern "system" fn(
+    this: *mut c_void,
+    name: &FStringOwned,
+    friendly_name: &FStringOwned,
+    additional_options: &TArrayOwned<FStringOwned>,
+    // TSharedPtr<IAnalyticsProvider> is passed by reference to a temporary
+    analytics_provider: *mut [usize; 2],
+);
+type StopRecordingReplay = unsafe extern "system" fn(this: *mut c_void);
+type PlayReplay = unsafe extern "system" fn(
+    this: *mut c_void,
+    name: &FStringOwned,
+    world_override: *mut c_void,
+    additional_options: &TArrayOwned<FStringOwned>,
+) -> bool;
+
+/// Replay API of a game instance in the current process
+pub struct Replays {
+    game_instance: *mut c_void,
+    start_recording: StartRecordingReplay,
+    stop_recording: StopRecordingReplay,
+    play: PlayReplay,
+}
+impl Replays {
+    /// # Safety
+    /// `functions` must be resolved from the current process and `game_instance` must be a live
+    /// `UGameInstance` (see [`find_game_instance`]). GMalloc must be set (see
+    /// [`set_gmalloc`](crate::ue::set_gmalloc)).
+    pub unsafe fn new(functions: &UGameInstanceReplay, game_instance: usize) -> Self {
+        Self {
+            game_instance: game_instance as *mut c_void,
+            start_recording: std::mem::transmute::<usize, StartRecordingReplay>(
+                functions.start_recording,
+            ),
+            stop_recording: std::mem::transmute::<usize, StopRecordingReplay>(
+                functions.stop_recording,
+            ),
+            play: std::mem::transmute::<usize, PlayReplay>(functions.play),
+        }
+    }
+
+    fn options(streamer: ReplayStreamer, options: &[&str]) -> TArrayOwned<FStringOwned> {
+        streamer
+            .option()
+            .iter()
+            .map(String::as_str)
+            .chain(options.iter().copied())
+            .map(FStringOwned::from)
+            .collect()
+    }
+
+    /// Start recording the current world as `name`. `options` are additional demo URL options
+    /// in `Key=Value` form.
+    ///
+    /// # Safety
+    /// Must be called from the game thread.
+    pub unsafe fn start_recording(
+        &self,
+        name: &str,
+        friendly_name: &str,
+        streamer: ReplayStreamer,
+        options: &[&str],
+    ) {
+        let mut analytics_provider = [0usize; 2];
+        (self.start_recording)(
+            self.game_instance,
+            &name.into(),
+            &friendly_name.into(),
+            &Self::options(streamer, options),
+            &mut analytics_provider,
+        );
+    }
+
+    /// # Safety
+    /// Must be called from the game thread.
+    pub unsafe fn stop_recording(&self) {
+        (self.stop_recording)(self.game_instance);
+    }
+
+    /// Start playing back the replay `name`, returning whether playback started. The replay
+    /// must be available from `streamer`.
+    ///
+    /// # Safety
+    /// Must be called from the game thread.
+    pub unsafe fn play(&self, name: &str, streamer: ReplayStreamer, options: &[&str]) -> bool {
+        (self.play)(
+            self.game_instance,
+            &name.into(),
+            std::ptr::null_mut(),
+            &Self::options(streamer, options),
+        )
+    }
+}
+
+/// The first live `UGameInstance`. Games only create one and it lives as long as the engine.
+pub fn find_game_instance<R, N>(objects: &Objects<'_, '_, R, N>) -> Option<usize>
+where
+    R: ReadMemory,
+    N: Fn(FName) -> Option<String>,
+{
+    objects
+        .find(|object| objects.is_a(object, "GameInstance"))
+        .first()
+        .copied()
+}
+
+/// Names of the replays recorded by [`ReplayStreamer::LocalFile`] to `demo_dir`, usually
+/// `<Project>/Saved/Demos`, sorted by name. The in-memory streamer has no persistent listing.
+pub fn local_replays(demo_dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = vec![];
+    for entry in std::fs::read_dir(demo_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("replay"))
+        {
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_local_replays() -> io::Result<()> {
+        let dir =
+            std::env::temp_dir().join(format!("patternsleuth-replays-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        for file in ["b.replay", "a.REPLAY", "notes.txt"] {
+            std::fs::write(dir.join(file), [])?;
+        }
+        let names = local_replays(&dir);
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(names?, ["a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamer_option() {
+        assert_eq!(ReplayStreamer::Default.option(), None);
+        assert_eq!(
+            ReplayStreamer::InMemory.option().as_deref(),
+            Some("ReplayStreamerOverride=InMemoryNetworkReplayStreaming")
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+
+use iced_x86::{Mnemonic, OpKind};
+
+use crate::{
+    resolvers::{
+        bail_out, ensure_one, impl_resolver_singleton, resolver_dependencies,
+        unreal::{layout::visit_function, util},
+    },
+    SectionPermissions,
+};
+
+/// FSlateApplication::Create, or the FSlateApplication constructor where it isn't inlined into
+/// it. Found through the constructor reading `[CursorControl] bAllowSoftwareCursor`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FSlateApplicationCreate(pub usize);
+impl_resolver_singleton!(all, FSlateApplicationCreate, |ctx| async {
+    let strings = ctx
+        .scan(util::utf16_pattern("bAllowSoftwareCursor\0"))
+        .await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    Ok(Self(ensure_one(util::root_functions(ctx, &refs)?)?))
+});
+
+/// `TSharedPtr<FSlateApplication> FSlateApplication::CurrentApplication`, what
+/// `FSlateApplication::Get()` dereferences
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct FSlateApplicationCurrentApplication(pub usize);
+resolver_dependencies!(FSlateApplicationCurrentApplication => [FSlateApplicationCreate]);
+impl_resolver_singleton!(all, FSlateApplicationCurrentApplication, |ctx| async {
+    let create = ctx.resolve(FSlateApplicationCreate::resolver()).await?;
+    let image = ctx.image();
+
+    // assigning a TSharedPtr global stores both the object and its reference controller:
+    // mov [rip+CurrentApplication], r64
+    // mov [rip+CurrentApplication+8], r64
+    let mut stores = vec![];
+    visit_function(ctx, create.0, |inst| {
+        if inst.mnemonic() == Mnemonic::Mov
+            && inst.op0_kind() == OpKind::Memory
+            && inst.is_ip_rel_memory_operand()
+            && inst.op1_kind() == OpKind::Register
+            && inst.op1_register().size() == 8
+        {
+            stores.push(inst.ip_rel_memory_address() as usize);
+        }
+    })?;
+    let stored = stores.iter().copied().collect::<HashSet<_>>();
+
+    // `CurrentBaseApplication = CurrentApplication` follows, pointing to the same object
+    let writable = |address: usize| {
+        image
+            .memory
+            .get_section_containing(address)
+            .is_ok_and(|s| s.permissions().allows(SectionPermissions::RW))
+    };
+    match stores
+        .into_iter()
+        .find(|s| stored.contains(&(s + 8)) && writable(*s))
+    {
+        Some(address) => Ok(Self(address)),
+        None => bail_out!("no TSharedPtr global assigned"),
+    }
+});
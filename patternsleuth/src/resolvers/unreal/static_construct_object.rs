@@ -5,7 +5,10 @@ use patternsleuth_scanner::Pattern;
 
 use crate::{
     disassemble::{disassemble, Control},
-    resolvers::{ensure_one, impl_resolver_singleton, try_ensure_one, unreal::util, Result},
+    resolvers::{
+        ensure_one, impl_resolver_singleton, resolver_dependencies, try_ensure_one, unreal::util,
+        Result,
+    },
     MemoryTrait,
 };
 
@@ -16,6 +19,10 @@ use crate::{
     derive(serde::Serialize, serde::Deserialize)
 )]
 pub struct StaticConstructObjectInternal(pub usize);
+resolver_dependencies!(StaticConstructObjectInternal => [
+    StaticConstructObjectInternalPatterns,
+    StaticConstructObjectInternalString,
+]);
 impl_resolver_singleton!(all, StaticConstructObjectInternal, |ctx| async {
     let any = join!(
         ctx.resolve(StaticConstructObjectInternalPatterns::resolver()),
@@ -0,0 +1,112 @@
+//! Collision traces against the world, for debug visualization or invoking traces directly.
+//!
+//! The `UKismetSystemLibrary` blueprint wrappers of the trace family can be found by name in the
+//! native function tables. `UWorld::LineTraceSingleByChannel` itself is plain C++ without a
+//! string anchor in shipping builds, so it is found through its call in
+//! `UKismetSystemLibrary::LineTraceSingle`, right after the world is looked up.
+
+use crate::resolvers::{
+    ensure_one, impl_collector, impl_resolver_singleton, resolver_dependencies,
+    unreal::util::{self, native_function_resolver},
+};
+
+native_function_resolver!(
+    KismetSystemLibraryLineTraceSingle,
+    "UKismetSystemLibrary",
+    "LineTraceSingle"
+);
+native_function_resolver!(
+    KismetSystemLibraryLineTraceMulti,
+    "UKismetSystemLibrary",
+    "LineTraceMulti"
+);
+native_function_resolver!(
+    KismetSystemLibraryLineTraceSingleForObjects,
+    "UKismetSystemLibrary",
+    "LineTraceSingleForObjects"
+);
+native_function_resolver!(
+    KismetSystemLibrarySphereTraceSingle,
+    "UKismetSystemLibrary",
+    "SphereTraceSingle"
+);
+native_function_resolver!(
+    KismetSystemLibraryBoxTraceSingle,
+    "UKismetSystemLibrary",
+    "BoxTraceSingle"
+);
+native_function_resolver!(
+    KismetSystemLibraryCapsuleTraceSingle,
+    "UKismetSystemLibrary",
+    "CapsuleTraceSingle"
+);
+
+/// `UWorld* UEngine::GetWorldFromContextObject(const UObject*, EGetWorldErrorMode)`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UEngineGetWorldFromContextObject(pub usize);
+impl_resolver_singleton!(all, UEngineGetWorldFromContextObject, |ctx| async {
+    // reported through FFrame::KismetExecutionMessage which is kept in shipping builds
+    let strings = ctx
+        .scan(util::utf16_pattern(
+            "No world was found for object (%s) passed in to UEngine::GetWorldFromContextObject().\0",
+        ))
+        .await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    let fns = util::root_functions(ctx, &refs)?;
+    Ok(Self(ensure_one(fns)?))
+});
+
+/// `bool UWorld::LineTraceSingleByChannel(FHitResult&, const FVector&, const FVector&,
+/// ECollisionChannel, const FCollisionQueryParams&, const FCollisionResponseParams&) const`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UWorldLineTraceSingleByChannel(pub usize);
+resolver_dependencies!(UWorldLineTraceSingleByChannel => [UEngineGetWorldFromContextObject]);
+impl_resolver_singleton!(all, UWorldLineTraceSingleByChannel, |ctx| async {
+    let get_world = ctx
+        .resolve(UEngineGetWorldFromContextObject::resolver())
+        .await?;
+    // UKismetSystemLibrary::LineTraceSingle names its query with a function local static FName
+    let strings = ctx.scan(util::utf16_pattern("LineTraceSingle\0")).await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    let fns = util::root_functions(ctx, &refs)?;
+
+    // World ? World->LineTraceSingleByChannel(...) : false
+    let mut candidates = vec![];
+    for f in fns {
+        let calls = util::find_calls(ctx.image(), f)?;
+        candidates.extend(
+            calls
+                .iter()
+                .skip_while(|c| c.callee != get_world.0)
+                .nth(1)
+                .map(|c| c.callee),
+        );
+    }
+    Ok(Self(ensure_one(candidates)?))
+});
+
+impl_collector! {
+    /// World trace entry points. Members resolve independently.
+    #[derive(Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "serde-resolvers",
+        derive(serde::Serialize, serde::Deserialize)
+    )]
+    pub struct Trace {
+        pub line_trace_single_by_channel: UWorldLineTraceSingleByChannel,
+        pub line_trace_single: KismetSystemLibraryLineTraceSingle,
+        pub line_trace_multi: KismetSystemLibraryLineTraceMulti,
+        pub line_trace_single_for_objects: KismetSystemLibraryLineTraceSingleForObjects,
+        pub sphere_trace_single: KismetSystemLibrarySphereTraceSingle,
+        pub box_trace_single: KismetSystemLibraryBoxTraceSingle,
+        pub capsule_trace_single: KismetSystemLibraryCapsuleTraceSingle,
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use pdb::FallibleIterator;
 
@@ -94,3 +97,362 @@ pub fn dump_pdb_symbols<P: AsRef<Path>>(
     }
     Ok(symbols)
 }
+
+/// Magic and version of symbol cache files
+const CACHE_MAGIC: &[u8; 8] = b"PSSYMC01";
+
+/// Load the symbols of `pdb_path` for the executable at `exe_path`, through a compact cache
+/// stored next to the executable (`<exe>.symcache`). Parsing a large PDB takes minutes while the
+/// cache loads in a fraction of that, so it is built on first load and reused for as long as it
+/// matches the PDB. Function ranges come from the exception directory of the image and are
+/// cheap to read, so only names are cached.
+pub fn load_pdb_symbols(
+    exe_path: &Path,
+    pdb_path: &Path,
+    info: Option<&PdbInfo>,
+    base_address: usize,
+) -> Result<HashMap<usize, Symbol>> {
+    let cache_path = exe_path.with_extension("symcache");
+    let key = cache_key(pdb_path, info)?;
+    match read_symbol_cache(&cache_path, &key, base_address) {
+        Ok(Some(symbols)) => return Ok(symbols),
+        Ok(None) => {}
+        Err(err) => tracing::warn!("ignoring symbol cache {}: {err:#}", cache_path.display()),
+    }
+
+    let symbols = dump_pdb_symbols(pdb_path, base_address)?;
+    if let Err(err) = write_symbol_cache(&cache_path, &key, base_address, &symbols) {
+        tracing::warn!(
+            "failed to write symbol cache {}: {err:#}",
+            cache_path.display()
+        );
+    }
+    Ok(symbols)
+}
+
+/// Identifies the PDB a cache was built from: its GUID and age if known, and its size and
+/// modification time in case it was rebuilt without changing them
+fn cache_key(pdb_path: &Path, info: Option<&PdbInfo>) -> Result<String> {
+    let meta = std::fs::metadata(pdb_path)?;
+    let modified = meta
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(format!(
+        "{}:{}:{modified}",
+        info.map(PdbInfo::key).unwrap_or_default(),
+        meta.len()
+    ))
+}
+
+/// Symbol cache layout, all integers little endian:
+/// ```text
+/// [u8; 8] magic
+/// u32     key length, followed by key
+/// u32     symbol count
+/// (u32 RVA, u32 name length) per symbol
+/// names, concatenated
+/// ```
+fn write_symbol_cache(
+    path: &Path,
+    key: &str,
+    base_address: usize,
+    symbols: &HashMap<usize, Symbol>,
+) -> Result<()> {
+    let mut sorted = symbols.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(address, _)| **address);
+
+    let mut data = CACHE_MAGIC.to_vec();
+    data.extend((key.len() as u32).to_le_bytes());
+    data.extend(key.as_bytes());
+    data.extend((sorted.len() as u32).to_le_bytes());
+    for (address, symbol) in &sorted {
+        let rva = u32::try_from(**address - base_address)?;
+        data.extend(rva.to_le_bytes());
+        data.extend((symbol.name.len() as u32).to_le_bytes());
+    }
+    for (_, symbol) in &sorted {
+        data.extend(symbol.name.as_bytes());
+    }
+
+    // write to temporary file first so an interrupted write isn't mistaken for a cache
+    let tmp = path.with_extension("symcache.tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Read the symbol cache at `path`, `None` if it doesn't exist or was built from another PDB
+fn read_symbol_cache(
+    path: &Path,
+    key: &str,
+    base_address: usize,
+) -> Result<Option<HashMap<usize, Symbol>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    #[cfg(feature = "mmap")]
+    let data = crate::image::MappedFile::open(path)?;
+    #[cfg(not(feature = "mmap"))]
+    let data = std::fs::read(path)?;
+
+    let mut reader = CacheReader(&data[..]);
+    if reader.take(CACHE_MAGIC.len())? != CACHE_MAGIC {
+        anyhow::bail!("unknown format");
+    }
+    let key_len = reader.u32()? as usize;
+    if reader.take(key_len)? != key.as_bytes() {
+        return Ok(None);
+    }
+    let count = reader.u32()? as usize;
+    let entries = (0..count)
+        .map(|_| Ok((reader.u32()?, reader.u32()?)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut symbols = HashMap::with_capacity(count);
+    for (rva, name_len) in entries {
+        let name = std::str::from_utf8(reader.take(name_len as usize)?)?;
+        symbols.insert(
+            base_address + rva as usize,
+            Symbol {
+                name: name.to_string(),
+            },
+        );
+    }
+    Ok(Some(symbols))
+}
+
+struct CacheReader<'a>(&'a [u8]);
+impl<'a> CacheReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            anyhow::bail!("truncated");
+        }
+        let (data, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(data)
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// CodeView debug info from the PE debug directory identifying the matching PDB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdbInfo {
+    pub guid: [u8; 16],
+    pub age: u32,
+    /// PDB file name without directory
+    pub name: String,
+}
+impl PdbInfo {
+    pub fn from_object(object: &object::File<'_>) -> Option<Self> {
+        use object::Object;
+
+        let info = object.pdb_info().ok()??;
+        let path = std::str::from_utf8(info.path()).ok()?;
+        let name = path.rsplit(['\\', '/']).next().unwrap_or(path).to_string();
+        Some(Self {
+            guid: info.guid(),
+            age: info.age(),
+            name,
+        })
+    }
+    /// Symbol server key (GUID followed by age) as used in `<name>/<key>/<name>`
+    pub fn key(&self) -> String {
+        let g = &self.guid;
+        let mut key = format!(
+            "{:08X}{:04X}{:04X}",
+            u32::from_le_bytes(g[0..4].try_into().unwrap()),
+            u16::from_le_bytes(g[4..6].try_into().unwrap()),
+            u16::from_le_bytes(g[6..8].try_into().unwrap()),
+        );
+        for b in &g[8..] {
+            key.push_str(&format!("{b:02X}"));
+        }
+        key.push_str(&format!("{:X}", self.age));
+        key
+    }
+    fn cache_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.name).join(self.key()).join(&self.name)
+    }
+}
+
+/// Source of PDB files for an image
+pub trait SymbolProvider: Send + Sync {
+    /// Return path to a PDB matching `info` for the executable at `exe_path` if available
+    fn find_pdb(&self, exe_path: &Path, info: Option<&PdbInfo>) -> Result<Option<PathBuf>>;
+}
+
+/// PDB sitting next to the executable
+pub struct AdjacentPdb;
+impl SymbolProvider for AdjacentPdb {
+    fn find_pdb(&self, exe_path: &Path, info: Option<&PdbInfo>) -> Result<Option<PathBuf>> {
+        let candidates = [Some(exe_path.with_extension("pdb")), {
+            info.and_then(|info| exe_path.parent().map(|dir| dir.join(&info.name)))
+        }];
+        Ok(candidates.into_iter().flatten().find(|p| p.exists()))
+    }
+}
+
+/// Local symbol store laid out like a symbol server (`<root>/<name>/<key>/<name>`)
+pub struct LocalSymbolCache {
+    pub root: PathBuf,
+}
+impl SymbolProvider for LocalSymbolCache {
+    fn find_pdb(&self, _exe_path: &Path, info: Option<&PdbInfo>) -> Result<Option<PathBuf>> {
+        Ok(info
+            .map(|info| info.cache_path(&self.root))
+            .filter(|p| p.exists()))
+    }
+}
+
+/// Remote symbol server (e.g. <https://msdl.microsoft.com/download/symbols>). Downloaded PDBs
+/// are stored in `cache`. Downloading requires the `symbol-server` feature, without it only the
+/// cache is checked.
+pub struct SymbolServer {
+    pub url: String,
+    pub cache: PathBuf,
+}
+impl SymbolProvider for SymbolServer {
+    fn find_pdb(&self, _exe_path: &Path, info: Option<&PdbInfo>) -> Result<Option<PathBuf>> {
+        let Some(info) = info else {
+            return Ok(None);
+        };
+        let path = info.cache_path(&self.cache);
+        if path.exists() {
+            return Ok(Some(path));
+        }
+        self.download(info, &path)
+    }
+}
+impl SymbolServer {
+    #[cfg(feature = "symbol-server")]
+    fn download(&self, info: &PdbInfo, path: &Path) -> Result<Option<PathBuf>> {
+        let url = format!(
+            "{}/{}/{}/{}",
+            self.url.trim_end_matches('/'),
+            info.name,
+            info.key(),
+            info.name
+        );
+        tracing::info!("downloading {url}");
+        let response = match ureq::get(&url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        // download to temporary file first so an interrupted download isn't mistaken for a PDB
+        let tmp = path.with_extension("pdb.tmp");
+        std::io::copy(
+            &mut response.into_reader(),
+            &mut std::fs::File::create(&tmp)?,
+        )?;
+        std::fs::rename(&tmp, path)?;
+        Ok(Some(path.to_path_buf()))
+    }
+    #[cfg(not(feature = "symbol-server"))]
+    fn download(&self, _info: &PdbInfo, _path: &Path) -> Result<Option<PathBuf>> {
+        tracing::warn!(
+            "not downloading symbols from {}: symbol-server feature disabled",
+            self.url
+        );
+        Ok(None)
+    }
+}
+
+/// Ordered list of providers, the first to find a PDB wins
+pub struct SymbolProviders(pub Vec<Box<dyn SymbolProvider>>);
+impl SymbolProviders {
+    /// Adjacent PDB followed by any entries from `_NT_SYMBOL_PATH`
+    pub fn from_env() -> Self {
+        Self::from_symbol_path(&std::env::var("_NT_SYMBOL_PATH").unwrap_or_default())
+    }
+    /// Adjacent PDB followed by the entries of `path`, see [`SymbolProviders::parse_symbol_path`]
+    pub fn from_symbol_path(path: &str) -> Self {
+        let mut providers: Vec<Box<dyn SymbolProvider>> = vec![Box::new(AdjacentPdb)];
+        providers.extend(Self::parse_symbol_path(path).0);
+        Self(providers)
+    }
+    /// Parse a symbol path in the `_NT_SYMBOL_PATH` format, e.g.
+    /// `srv*C:\symbols*https://msdl.microsoft.com/download/symbols;D:\other_symbols`
+    pub fn parse_symbol_path(path: &str) -> Self {
+        let mut providers: Vec<Box<dyn SymbolProvider>> = vec![];
+        for entry in path.split(';').filter(|e| !e.is_empty()) {
+            let parts = entry.split('*').collect::<Vec<_>>();
+            match parts.as_slice() {
+                [srv, rest @ ..] if srv.eq_ignore_ascii_case("srv") => {
+                    let (caches, urls): (Vec<&str>, Vec<&str>) =
+                        rest.iter().copied().partition(|p| !p.contains("://"));
+                    let cache = caches
+                        .first()
+                        .map(PathBuf::from)
+                        .unwrap_or_else(|| std::env::temp_dir().join("symbols"));
+                    providers.push(Box::new(LocalSymbolCache {
+                        root: cache.clone(),
+                    }));
+                    for url in urls {
+                        providers.push(Box::new(SymbolServer {
+                            url: url.to_string(),
+                            cache: cache.clone(),
+                        }));
+                    }
+                }
+                [cache, dir] if cache.eq_ignore_ascii_case("cache") => {
+                    providers.push(Box::new(LocalSymbolCache { root: dir.into() }));
+                }
+                [dir] => providers.push(Box::new(LocalSymbolCache { root: dir.into() })),
+                _ => tracing::warn!("unsupported symbol path entry {entry:?}"),
+            }
+        }
+        Self(providers)
+    }
+}
+impl SymbolProvider for SymbolProviders {
+    fn find_pdb(&self, exe_path: &Path, info: Option<&PdbInfo>) -> Result<Option<PathBuf>> {
+        for provider in &self.0 {
+            match provider.find_pdb(exe_path, info) {
+                Ok(Some(path)) => return Ok(Some(path)),
+                Ok(None) => {}
+                Err(err) => tracing::warn!("symbol provider failed: {err:#}"),
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_symbol_cache() {
+        let path = std::env::temp_dir().join(format!("symbols-{}.symcache", std::process::id()));
+        let symbols = HashMap::from([
+            (
+                0x140001000,
+                Symbol {
+                    name: "?Get@FModuleManager@@SAAEAV1@XZ".into(),
+                },
+            ),
+            (
+                0x140002000,
+                Symbol {
+                    name: "main".into(),
+                },
+            ),
+        ]);
+        write_symbol_cache(&path, "key", 0x140000000, &symbols).unwrap();
+
+        let same = read_symbol_cache(&path, "key", 0x140000000);
+        let rebased = read_symbol_cache(&path, "key", 0x7ff000000000);
+        let stale = read_symbol_cache(&path, "other", 0x140000000);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(same.unwrap(), Some(symbols));
+        let rebased = rebased.unwrap().unwrap();
+        assert_eq!(rebased[&0x7ff000002000].name, "main");
+        assert_eq!(stale.unwrap(), None);
+    }
+}
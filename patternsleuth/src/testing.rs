@@ -0,0 +1,824 @@
+//! Helpers for building small synthetic PE images in memory so resolvers can be unit tested
+//! against hand written byte snippets instead of real game binaries.
+//!
+//! ```ignore
+//! let image = TestImageBuilder::new(0x140000000)
+//!     .section(".text", SectionKind::Text, 0x140001000, 0x1000)
+//!     .write(0x140001000, &[0x48, 0x8d, 0x0d, 0xf9, 0x0f, 0x00, 0x00, 0xc3])
+//!     .function(0x140001000..0x140001008)
+//!     .build()?;
+//! ```
+
+use std::{borrow::Cow, collections::HashMap, ops::Range};
+
+use anyhow::{bail, Result};
+use object::SectionKind;
+
+use crate::{
+    image::{pe::PEImage, Image, ImageType},
+    ue::ObjectLayout,
+    Memory, NamedMemorySection, SectionPermissions,
+};
+
+/// Size of a RUNTIME_FUNCTION entry
+const RUNTIME_FUNCTION_SIZE: usize = 12;
+/// Size reserved for each generated UNWIND_INFO (header + chained RUNTIME_FUNCTION)
+const UNWIND_INFO_SIZE: usize = 16;
+/// UNW_FLAG_CHAININFO
+const UNWIND_FLAG_CHAININFO: u8 = 0x4;
+
+struct TestSection {
+    name: String,
+    kind: SectionKind,
+    address: usize,
+    data: Vec<u8>,
+}
+
+struct TestFunction {
+    range: Range<usize>,
+    /// start address of the parent function if this is a chained (child) function
+    parent: Option<usize>,
+}
+
+/// Builder for a synthetic [`Image`] with fake sections and exception directory entries
+pub struct TestImageBuilder {
+    base_address: usize,
+    sections: Vec<TestSection>,
+    functions: Vec<TestFunction>,
+    writes: Vec<(usize, Vec<u8>)>,
+    /// section address -> range of its data in the image file
+    file_ranges: HashMap<usize, Range<usize>>,
+    imports: HashMap<String, HashMap<String, usize>>,
+    pdb_path: Option<String>,
+}
+
+impl TestImageBuilder {
+    pub fn new(base_address: usize) -> Self {
+        Self {
+            base_address,
+            sections: vec![],
+            functions: vec![],
+            writes: vec![],
+            file_ranges: Default::default(),
+            imports: Default::default(),
+            pdb_path: None,
+        }
+    }
+    /// Add a zero filled section of `size` bytes at `address`. Code sections are filled with
+    /// `int3` instead.
+    pub fn section<N: Into<String>>(
+        mut self,
+        name: N,
+        kind: SectionKind,
+        address: usize,
+        size: usize,
+    ) -> Self {
+        let fill = if kind == SectionKind::Text { 0xcc } else { 0 };
+        self.sections.push(TestSection {
+            name: name.into(),
+            kind,
+            address,
+            data: vec![fill; size],
+        });
+        self
+    }
+    /// Write `bytes` at `address`. The address must be inside a previously added section.
+    pub fn write(mut self, address: usize, bytes: &[u8]) -> Self {
+        self.writes.push((address, bytes.to_vec()));
+        self
+    }
+    /// Back the section at `address` by the file data at `range`, see
+    /// [`NamedMemorySection::file_range`]. The range may be shorter than the section, like the
+    /// raw data of a section with uninitialized data at its end.
+    pub fn file_range(mut self, address: usize, range: Range<usize>) -> Self {
+        self.file_ranges.insert(address, range);
+        self
+    }
+    /// Write a null terminated UTF-16 string at `address`
+    pub fn write_utf16(self, address: usize, string: &str) -> Self {
+        let bytes = string
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        self.write(address, &bytes)
+    }
+    /// Register a root function in the exception directory
+    pub fn function(mut self, range: Range<usize>) -> Self {
+        self.functions.push(TestFunction {
+            range,
+            parent: None,
+        });
+        self
+    }
+    /// Register a function chained to the function starting at `parent` (e.g. a cold block split
+    /// from its root function)
+    pub fn child_function(mut self, range: Range<usize>, parent: usize) -> Self {
+        self.functions.push(TestFunction {
+            range,
+            parent: Some(parent),
+        });
+        self
+    }
+    /// Set the PDB path of the CodeView debug record, see [`Image::pdb_path`]
+    pub fn pdb_path<P: Into<String>>(mut self, pdb_path: P) -> Self {
+        self.pdb_path = Some(pdb_path.into());
+        self
+    }
+    /// Add an import resolved at `address` (address of the IAT entry)
+    pub fn import<L: Into<String>, N: Into<String>>(
+        mut self,
+        library: L,
+        name: N,
+        address: usize,
+    ) -> Self {
+        self.imports
+            .entry(library.into().to_ascii_lowercase())
+            .or_default()
+            .insert(name.into(), address);
+        self
+    }
+
+    pub fn build(self) -> Result<Image<'static>> {
+        let Self {
+            base_address,
+            mut sections,
+            mut functions,
+            writes,
+            file_ranges,
+            imports,
+            pdb_path,
+        } = self;
+
+        sections.sort_by_key(|s| s.address);
+        for pair in sections.windows(2) {
+            if pair[0].address + pair[0].data.len() > pair[1].address {
+                bail!("sections {} and {} overlap", pair[0].name, pair[1].name);
+            }
+        }
+        if sections.iter().any(|s| s.address < base_address) {
+            bail!("section below base address {base_address:#x}");
+        }
+
+        for (address, bytes) in writes {
+            let Some(section) = sections.iter_mut().find(|s| {
+                address >= s.address && address + bytes.len() <= s.address + s.data.len()
+            }) else {
+                bail!(
+                    "write of {} bytes at {address:#x} is outside of any section",
+                    bytes.len()
+                );
+            };
+            let offset = address - section.address;
+            section.data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        }
+
+        // place generated exception data after all user sections
+        let end = sections
+            .iter()
+            .map(|s| s.address + s.data.len())
+            .max()
+            .unwrap_or(base_address);
+        let align = |a: usize| (a + 0xfff) & !0xfff;
+        let pdata_address = align(end);
+        let xdata_address = align(pdata_address + functions.len() * RUNTIME_FUNCTION_SIZE + 1);
+
+        let rva = |address: usize| -> Result<u32> { Ok(u32::try_from(address - base_address)?) };
+
+        functions.sort_by_key(|f| f.range.start);
+        let mut pdata = vec![];
+        // trailing padding so the bounds check on chained entries succeeds for the last entry
+        let mut xdata = vec![0; functions.len() * UNWIND_INFO_SIZE + RUNTIME_FUNCTION_SIZE];
+        for (i, function) in functions.iter().enumerate() {
+            let unwind = xdata_address + i * UNWIND_INFO_SIZE;
+            pdata.extend(rva(function.range.start)?.to_le_bytes());
+            pdata.extend(rva(function.range.end)?.to_le_bytes());
+            pdata.extend(rva(unwind)?.to_le_bytes());
+
+            let info = &mut xdata[i * UNWIND_INFO_SIZE..(i + 1) * UNWIND_INFO_SIZE];
+            if let Some(parent) = function.parent {
+                let Some((parent_index, parent)) = functions
+                    .iter()
+                    .enumerate()
+                    .find(|(_, f)| f.range.start == parent)
+                else {
+                    bail!("parent function {parent:#x} not registered");
+                };
+                // version 1, chained, no unwind codes followed by the parent RUNTIME_FUNCTION
+                info[0] = 1 | (UNWIND_FLAG_CHAININFO << 3);
+                info[4..8].copy_from_slice(&rva(parent.range.start)?.to_le_bytes());
+                info[8..12].copy_from_slice(&rva(parent.range.end)?.to_le_bytes());
+                info[12..16].copy_from_slice(
+                    &rva(xdata_address + parent_index * UNWIND_INFO_SIZE)?.to_le_bytes(),
+                );
+            } else {
+                // version 1, no flags, no unwind codes
+                info[0] = 1;
+            }
+        }
+
+        let exception_directory_range = pdata_address..pdata_address + pdata.len();
+
+        let mut named_sections = sections
+            .into_iter()
+            .map(|s| {
+                let permissions = SectionPermissions::from_kind(s.kind);
+                let mut section =
+                    NamedMemorySection::new(s.name, s.address, s.kind, permissions, s.data);
+                section.file_range = file_ranges.get(&s.address).cloned();
+                section
+            })
+            .collect::<Vec<_>>();
+        named_sections.push(NamedMemorySection::new(
+            ".pdata".to_string(),
+            pdata_address,
+            SectionKind::ReadOnlyData,
+            SectionPermissions::R,
+            Cow::Owned(pdata),
+        ));
+        named_sections.push(NamedMemorySection::new(
+            ".xdata".to_string(),
+            xdata_address,
+            SectionKind::ReadOnlyData,
+            SectionPermissions::R,
+            Cow::Owned(xdata),
+        ));
+
+        let mut image = Image {
+            base_address,
+            memory: Memory {
+                sections: named_sections,
+            },
+            #[cfg(feature = "symbols")]
+            symbols: None,
+            imports,
+            image_type: ImageType::PEImage(PEImage {
+                exception_directory_range,
+                exception_children_cache: Default::default(),
+                build_id: None,
+                pdb_path,
+                overlay: None,
+                entry_point: None,
+                tls_callbacks: vec![],
+            }),
+            warnings: vec![],
+        };
+        image.populate_exception_cache()?;
+        Ok(image)
+    }
+}
+
+/// Fake reflection data in the default [`ObjectLayout`]: classes deriving from each other,
+/// objects and their properties, listed in an object array. Lay it out with
+/// [`TestObjects::add_to`] and read it back with [`Objects`](crate::ue::Objects):
+///
+/// ```ignore
+/// let mut objects = TestObjects::new(0x140010000);
+/// let actor = objects.class("Actor", 0);
+/// objects.object(actor, "Actor_1", 0);
+/// let names = objects.names();
+/// let (builder, array) = objects.add_to(TestImageBuilder::new(0x140000000));
+/// let image = builder.build()?;
+/// let array = ObjectArray::new(&image.memory, array, TestObjects::ITEM_SIZE);
+/// let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+///     names.get(name.comparison_index as usize).cloned()
+/// });
+/// ```
+pub struct TestObjects {
+    address: usize,
+    data: Vec<u8>,
+    layout: ObjectLayout,
+    names: Vec<String>,
+    objects: Vec<usize>,
+    class_class: usize,
+    /// class -> its last declared property
+    last_property: HashMap<usize, usize>,
+}
+
+impl TestObjects {
+    /// Size of the objects, properties must be declared at lower offsets
+    pub const OBJECT_SIZE: usize = 0x200;
+    /// sizeof(FUObjectItem) of the object array
+    pub const ITEM_SIZE: usize = 0x18;
+
+    /// Objects allocated from `address` on
+    pub fn new(address: usize) -> Self {
+        let mut objects = Self {
+            address,
+            data: vec![],
+            layout: ObjectLayout::default(),
+            names: vec!["None".to_string()],
+            objects: vec![],
+            class_class: 0,
+            last_property: Default::default(),
+        };
+        objects.class_class = objects.object(0, "Class", 0);
+        objects.write_ptr(
+            objects.class_class + objects.layout.class,
+            objects.class_class,
+        );
+        objects
+    }
+    /// Allocate `size` zeroed bytes, 8 byte aligned
+    pub fn alloc(&mut self, size: usize) -> usize {
+        let address = self.address + self.data.len();
+        self.data.resize(self.data.len() + ((size + 7) & !7), 0);
+        address
+    }
+    pub fn write(&mut self, address: usize, bytes: &[u8]) {
+        let offset = address - self.address;
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    pub fn write_ptr(&mut self, address: usize, value: usize) {
+        self.write(address, &value.to_le_bytes());
+    }
+    /// Write a TArray of pointers at `address`, allocating its data
+    pub fn write_ptr_array(&mut self, address: usize, values: &[usize]) {
+        let data = self.alloc(values.len() * 8);
+        for (i, value) in values.iter().enumerate() {
+            self.write_ptr(data + i * 8, *value);
+        }
+        self.write_ptr(address, data);
+        let num = values.len() as i32;
+        self.write(
+            address + 8,
+            &[num.to_le_bytes(), num.to_le_bytes()].concat(),
+        );
+    }
+    /// Write the FName `name` at `address`
+    pub fn write_name(&mut self, address: usize, name: &str) {
+        let index = match self.names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        };
+        self.write(address, &(index as u64).to_le_bytes());
+    }
+    /// Add an object named `name` of `class`, outered to `outer` (0 for none)
+    pub fn object(&mut self, class: usize, name: &str, outer: usize) -> usize {
+        let object = self.alloc(Self::OBJECT_SIZE);
+        self.write_ptr(object + self.layout.class, class);
+        self.write_name(object + self.layout.name, name);
+        self.write_ptr(object + self.layout.outer, outer);
+        self.objects.push(object);
+        object
+    }
+    /// Add a class named `name` deriving from `super_class` (0 for none)
+    pub fn class(&mut self, name: &str, super_class: usize) -> usize {
+        let class = self.object(self.class_class, name, 0);
+        self.write_ptr(class + self.layout.super_struct, super_class);
+        class
+    }
+    /// Declare the property `name` of the FFieldClass `kind` (e.g. `ObjectProperty`) at
+    /// `offset` in instances of `class`
+    pub fn property(&mut self, class: usize, name: &str, kind: &str, offset: usize) -> usize {
+        let layout = self.layout;
+        let field_class = self.alloc(8);
+        self.write_name(field_class, kind);
+        let property = self.alloc(layout.property_offset + 4);
+        self.write_ptr(property + layout.field_class, field_class);
+        self.write_name(property + layout.field_name, name);
+        self.write(property + layout.property_array_dim, &1i32.to_le_bytes());
+        self.write(
+            property + layout.property_offset,
+            &(offset as i32).to_le_bytes(),
+        );
+        let previous = match self.last_property.insert(class, property) {
+            Some(last) => last + layout.field_next,
+            None => class + layout.child_properties,
+        };
+        self.write_ptr(previous, property);
+        property
+    }
+    /// Names indexed by the comparison index of the written FNames
+    pub fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+    /// Add the objects to `builder` as a `.data` section, returning the address of the object
+    /// array (with items of [`ITEM_SIZE`](Self::ITEM_SIZE))
+    pub fn add_to(mut self, builder: TestImageBuilder) -> (TestImageBuilder, usize) {
+        let objects = std::mem::take(&mut self.objects);
+        let items = self.alloc(objects.len() * Self::ITEM_SIZE);
+        for (i, object) in objects.iter().enumerate() {
+            self.write_ptr(items + i * Self::ITEM_SIZE, *object);
+        }
+        let chunks = self.alloc(8);
+        self.write_ptr(chunks, items);
+        // FUObjectArray: ObjObjects at 0x10 with Objects, MaxElements, NumElements and MaxChunks
+        let array = self.alloc(0x40);
+        self.write_ptr(array + 0x10, chunks);
+        self.write(array + 0x20, &(objects.len() as i32).to_le_bytes());
+        self.write(array + 0x24, &(objects.len() as i32).to_le_bytes());
+        self.write(array + 0x28, &1i32.to_le_bytes());
+
+        let builder = builder
+            .section(".data", SectionKind::Data, self.address, self.data.len())
+            .write(self.address, &self.data);
+        (builder, array)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scanner::Pattern, LiteralScan, MemoryTrait, PatternConfig};
+
+    #[test]
+    fn test_synthetic_image() {
+        let base = 0x140000000;
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, base + 0x2000, 0x1000)
+            // lea rcx, [rip + 0xff9] -> 0x140002000; ret
+            .write(
+                base + 0x1000,
+                &[0x48, 0x8d, 0x0d, 0xf9, 0x0f, 0x00, 0x00, 0xc3],
+            )
+            .write(base + 0x1100, &[0xc3])
+            .write_utf16(base + 0x2000, "hello")
+            .function(base + 0x1000..base + 0x1008)
+            .child_function(base + 0x1100..base + 0x1101, base + 0x1000)
+            .build()
+            .unwrap();
+
+        let pattern = Pattern::new("48 8d 0d | ?? ?? ?? ?? c3").unwrap();
+        let configs = [PatternConfig::new((), "lea".to_string(), None, pattern)];
+        let scan = image.scan(&configs).unwrap();
+        let addresses = scan.results.iter().map(|r| r.1.address).collect::<Vec<_>>();
+        assert_eq!(addresses, vec![base + 0x1003]);
+
+        let target = image.memory.rip4(addresses[0]).unwrap();
+        assert_eq!(target, base + 0x2000);
+        assert_eq!(image.memory.read_wstring(target).unwrap(), "hello");
+
+        let strings = image
+            .memory
+            .find_utf16(&crate::Utf16Scan::new("HELLO").case_insensitive(true));
+        assert_eq!(strings.len(), 1);
+        assert_eq!(strings[0].address, base + 0x2000);
+        assert_eq!(strings[0].section, ".rdata");
+        assert!(image
+            .memory
+            .find_utf16(&crate::Utf16Scan::new("hello").alignment(0x10000))
+            .is_empty());
+
+        let root = image.get_root_function(base + 0x1100).unwrap().unwrap();
+        assert_eq!(root.range, base + 0x1000..base + 0x1008);
+        assert_eq!(
+            image.get_root_function_range(base + 0x1000).unwrap(),
+            Some(base + 0x1000..base + 0x1101)
+        );
+    }
+
+    #[test]
+    fn test_file_offsets() {
+        let base = 0x140000000;
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".data", SectionKind::Data, base + 0x2000, 0x2000)
+            .section(
+                ".bss",
+                SectionKind::UninitializedData,
+                base + 0x4000,
+                0x1000,
+            )
+            .file_range(base + 0x1000, 0x400..0x1400)
+            // only the first 0x200 bytes are initialized from the file
+            .file_range(base + 0x2000, 0x1400..0x1600)
+            .build()
+            .unwrap();
+
+        assert_eq!(image.va_to_rva(base + 0x1234), Some(0x1234));
+        assert_eq!(image.va_to_rva(base - 1), None);
+        assert_eq!(image.rva_to_va(0x1234), base + 0x1234);
+
+        assert_eq!(image.va_to_file_offset(base + 0x1010), Some(0x410));
+        assert_eq!(image.va_to_file_offset(base + 0x2010), Some(0x1410));
+        assert_eq!(image.va_to_file_offset(base + 0x2200), None);
+        assert_eq!(image.va_to_file_offset(base + 0x4000), None);
+        assert_eq!(image.va_to_file_offset(base), None);
+
+        assert_eq!(image.file_offset_to_va(0x410), Some(base + 0x1010));
+        assert_eq!(image.file_offset_to_va(0x15ff), Some(base + 0x21ff));
+        assert_eq!(image.file_offset_to_va(0x1600), None);
+        assert_eq!(image.file_offset_to_va(0x10), None);
+    }
+
+    /// A PE file preferring `0x140000000` with a `.data` section holding a pointer to itself at
+    /// `+0x10` and a `.reloc` section with a DIR64 relocation for it
+    fn relocated_pe() -> Vec<u8> {
+        let mut file = vec![0; 0x600];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            file[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(0, b"MZ");
+        put(0x3c, &0x40u32.to_le_bytes());
+        put(0x40, b"PE\0\0");
+        // file header: AMD64, 2 sections, optional header size, executable | large address aware
+        put(0x44, &0x8664u16.to_le_bytes());
+        put(0x46, &2u16.to_le_bytes());
+        put(0x54, &0xf0u16.to_le_bytes());
+        put(0x56, &0x22u16.to_le_bytes());
+        // optional header
+        let optional = 0x58;
+        put(optional, &0x20bu16.to_le_bytes());
+        put(optional + 0x18, &0x140000000u64.to_le_bytes());
+        put(optional + 0x20, &0x1000u32.to_le_bytes());
+        put(optional + 0x24, &0x200u32.to_le_bytes());
+        put(optional + 0x38, &0x3000u32.to_le_bytes());
+        put(optional + 0x3c, &0x200u32.to_le_bytes());
+        put(optional + 0x44, &3u16.to_le_bytes());
+        put(optional + 0x6c, &16u32.to_le_bytes());
+        // base relocation directory
+        put(optional + 0x70 + 5 * 8, &0x2000u32.to_le_bytes());
+        put(optional + 0x70 + 5 * 8 + 4, &12u32.to_le_bytes());
+        // section headers: name, virtual size and address, raw size and offset, characteristics
+        for (i, (name, address, offset, characteristics)) in [
+            (b".data\0\0\0", 0x1000u32, 0x200u32, 0xc0000040u32),
+            (b".reloc\0\0", 0x2000, 0x400, 0x42000040),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let header = optional + 0xf0 + i * 40;
+            put(header, name);
+            put(header + 8, &0x200u32.to_le_bytes());
+            put(header + 12, &address.to_le_bytes());
+            put(header + 16, &0x200u32.to_le_bytes());
+            put(header + 20, &offset.to_le_bytes());
+            put(header + 36, &characteristics.to_le_bytes());
+        }
+        put(0x210, &0x140001000u64.to_le_bytes());
+        // one block for page 0x1000: DIR64 at +0x10 followed by ABSOLUTE padding
+        put(0x400, &0x1000u32.to_le_bytes());
+        put(0x404, &12u32.to_le_bytes());
+        put(0x408, &0xa010u16.to_le_bytes());
+        file
+    }
+
+    #[test]
+    fn test_rebase() {
+        let file = relocated_pe();
+
+        let image = Image::builder().build(&file).unwrap();
+        assert_eq!(image.base_address, 0x140000000);
+        assert_eq!(image.memory.ptr(0x140001010).unwrap(), 0x140001000);
+
+        let image = Image::builder()
+            .base_address(0x7ff600000000)
+            .build(&file)
+            .unwrap();
+        assert_eq!(image.base_address, 0x7ff600000000);
+        let addresses = image
+            .memory
+            .sections()
+            .iter()
+            .map(|s| (s.name().to_string(), s.address()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            [
+                (".data".to_string(), 0x7ff600001000),
+                (".reloc".to_string(), 0x7ff600002000)
+            ]
+        );
+        assert_eq!(image.memory.ptr(0x7ff600001010).unwrap(), 0x7ff600001000);
+        // other data is left alone
+        assert_eq!(image.memory.ptr(0x7ff600001008).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_function_table() {
+        let base = 0x140000000;
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .function(base + 0x1000..base + 0x1010)
+            .function(base + 0x1010..base + 0x1020)
+            .child_function(base + 0x1800..base + 0x1808, base + 0x1000)
+            .child_function(base + 0x1808..base + 0x1810, base + 0x1800)
+            .build()
+            .unwrap();
+        let functions = image.functions();
+
+        let split = functions.function_at(base + 0x180c).unwrap().unwrap();
+        assert_eq!(split.start(), base + 0x1000);
+        assert_eq!(split.span(), base + 0x1000..base + 0x1810);
+        assert_eq!(
+            split.ranges(),
+            [base + 0x1000..base + 0x1010, base + 0x1800..base + 0x1810]
+        );
+        assert_eq!(split.size(), 0x20);
+        assert!(split.is_split());
+        // in the span but part of another function
+        assert!(!split.contains(base + 0x1010));
+
+        let chain = functions.parent_chain(base + 0x180c).unwrap();
+        let starts = chain.iter().map(|f| f.range.start).collect::<Vec<_>>();
+        assert_eq!(starts, [base + 0x1808, base + 0x1800, base + 0x1000]);
+        assert_eq!(functions.children(base + 0x1800).unwrap().len(), 1);
+
+        let all = functions.all().unwrap();
+        let starts = all.iter().map(|f| f.start()).collect::<Vec<_>>();
+        assert_eq!(starts, [base + 0x1000, base + 0x1010]);
+        assert_eq!(
+            all[1].ranges(),
+            std::slice::from_ref(&(base + 0x1010..base + 0x1020))
+        );
+        assert_eq!(functions.function_at(base + 0x1900).unwrap(), None);
+    }
+
+    #[test]
+    fn test_retain_sections() {
+        let base = 0x140000000;
+        let mut image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".rdata", SectionKind::ReadOnlyData, base + 0x2000, 0x1000)
+            .section(".rsrc", SectionKind::ReadOnlyData, base + 0x3000, 0x4000)
+            .write(base + 0x1000, &[0xc3])
+            .function(base + 0x1000..base + 0x1001)
+            .build()
+            .unwrap();
+
+        let filter = crate::image::SectionFilter::default()
+            .name(".text")
+            .name(".rsrc")
+            .max_size(0x1000);
+        assert!(!filter.matches(&image.memory.sections()[2]));
+        image.retain_sections(&filter);
+        let names = image
+            .memory
+            .sections()
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<_>>();
+        // the exception directory is kept
+        assert!(names.contains(&".text"));
+        assert!(!names.contains(&".rdata"));
+        assert!(!names.contains(&".rsrc"));
+        assert!(names.contains(&".pdata"));
+        assert!(image.get_function(base + 0x1000).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_exception_data_unavailable() {
+        let base = 0x140000000;
+        let mut image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .write(base + 0x1000, &[0xc3])
+            .function(base + 0x1000..base + 0x1001)
+            .build()
+            .unwrap();
+        #[allow(irrefutable_let_patterns)]
+        if let ImageType::PEImage(ref mut pe) = image.image_type {
+            pe.exception_children_cache = None;
+        }
+
+        // the exception directory itself is still read
+        assert!(image.get_root_function(base + 0x1000).unwrap().is_some());
+        let err = image.get_root_function_range(base + 0x1000).unwrap_err();
+        assert_eq!(err, crate::MemoryAccessError::ExceptionDataUnavailable);
+        assert_eq!(
+            crate::resolvers::ResolveError::from(err),
+            crate::resolvers::ResolveError::ExceptionDataUnavailable
+        );
+    }
+
+    #[test]
+    fn test_lossy_exception_cache() {
+        let base = 0x140000000;
+        let mut image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .function(base + 0x1000..base + 0x1010)
+            .child_function(base + 0x1800..base + 0x1808, base + 0x1000)
+            .build()
+            .unwrap();
+        // pretend the directory has a third entry running past the end of .pdata
+        #[allow(irrefutable_let_patterns)]
+        if let ImageType::PEImage(ref mut pe) = image.image_type {
+            pe.exception_directory_range.end += 12;
+        }
+
+        assert!(image.populate_exception_cache().is_err());
+        assert_eq!(image.populate_exception_cache_lossy(), Ok(1));
+        // the child chunk is still found through the cache built from the valid entries
+        assert_eq!(
+            image.get_root_function_range(base + 0x1000).unwrap(),
+            Some(base + 0x1000..base + 0x1808)
+        );
+    }
+
+    #[test]
+    fn test_global_writes() {
+        use crate::disassemble::{global_initializers, global_writes, GlobalWrite, WriteKind};
+
+        let base = 0x140000000;
+        let global: usize = base + 0x2000;
+        // displacement of `global` from the end of an instruction at `ip` of `len` bytes
+        let operand = |ip: usize, len: usize| (global.wrapping_sub(ip + len) as u32).to_le_bytes();
+        let image = TestImageBuilder::new(base)
+            .section(".text", SectionKind::Text, base + 0x1000, 0x1000)
+            .section(".data", SectionKind::Data, global, 0x1000)
+            // mov [global], rax
+            .write(base + 0x1000, &[0x48, 0x89, 0x05])
+            .write(base + 0x1003, &operand(base + 0x1000, 7))
+            .write(base + 0x1007, &[0xc3])
+            // mov qword ptr [global], 0
+            .write(base + 0x1010, &[0x48, 0xc7, 0x05])
+            .write(base + 0x1013, &operand(base + 0x1010, 11))
+            .write(base + 0x1017, &[0, 0, 0, 0, 0xc3])
+            // mov rax, [global]
+            .write(base + 0x1020, &[0x48, 0x8b, 0x05])
+            .write(base + 0x1023, &operand(base + 0x1020, 7))
+            .write(base + 0x1027, &[0xc3])
+            .function(base + 0x1000..base + 0x1008)
+            .function(base + 0x1010..base + 0x101c)
+            .function(base + 0x1020..base + 0x1028)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            global_writes(&image, global).unwrap(),
+            [
+                GlobalWrite {
+                    function: base + 0x1000,
+                    ip: base + 0x1000,
+                    kind: WriteKind::Store,
+                    size: 8,
+                },
+                GlobalWrite {
+                    function: base + 0x1010,
+                    ip: base + 0x1010,
+                    kind: WriteKind::Clear,
+                    size: 8,
+                },
+            ]
+        );
+        assert_eq!(
+            global_initializers(&image, global).unwrap(),
+            [base + 0x1000]
+        );
+    }
+
+    #[test]
+    fn test_find_literal() {
+        let base = 0x140000000;
+        let rdata = base + 0x2000;
+        let tick = 0.016666668f32.to_le_bytes();
+        let image = TestImageBuilder::new(base)
+            .section(".rdata", SectionKind::ReadOnlyData, rdata, 0x1000)
+            .write(rdata + 0x10, &tick)
+            .write(rdata + 0x21, &tick)
+            .write(rdata + 0x30, &0x9e3779b9u32.to_be_bytes())
+            .build()
+            .unwrap();
+
+        let find = |scan| image.memory.find_literal(&scan);
+        assert_eq!(find(LiteralScan::f32(0.016666668)), [rdata + 0x10]);
+        assert_eq!(
+            find(LiteralScan::f32(0.016666668).alignment(1)),
+            [rdata + 0x10, rdata + 0x21]
+        );
+        assert!(find(LiteralScan::u32(0x9e3779b9)).is_empty());
+        assert_eq!(
+            find(LiteralScan::u32(0x9e3779b9).big_endian(true)),
+            [rdata + 0x30]
+        );
+    }
+
+    #[test]
+    fn test_overlay_kind() {
+        use crate::image::OverlayKind;
+
+        let mut pak = vec![0; 0x1000];
+        // magic of the pak footer at the end of the file
+        pak[0x1000 - 0xbc..][..4].copy_from_slice(&0x5a6f12e1u32.to_le_bytes());
+        assert_eq!(OverlayKind::identify(&pak), OverlayKind::Pak);
+        assert_eq!(OverlayKind::identify(b"PK\x03\x04...."), OverlayKind::Zip);
+        assert_eq!(OverlayKind::identify(&[0; 0x100]), OverlayKind::Unknown);
+        assert_eq!(OverlayKind::identify(&[]), OverlayKind::Unknown);
+    }
+
+    #[test]
+    fn test_read_tls_callbacks() {
+        let base = 0x140000000;
+        let rdata = base + 0x2000;
+        let callbacks = [base + 0x1100, base + 0x1200, 0];
+        let image = TestImageBuilder::new(base)
+            .section(".rdata", SectionKind::ReadOnlyData, rdata, 0x1000)
+            .write(rdata + 0x18, &(rdata + 0x100).to_le_bytes())
+            .write(rdata + 0x100, &callbacks.map(usize::to_le_bytes).concat())
+            .write(rdata + 0x218, &0usize.to_le_bytes())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            PEImage::read_tls_callbacks(&image.memory, rdata).unwrap(),
+            [base + 0x1100, base + 0x1200]
+        );
+        assert!(PEImage::read_tls_callbacks(&image.memory, rdata + 0x200)
+            .unwrap()
+            .is_empty());
+    }
+}
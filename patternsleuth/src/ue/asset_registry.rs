@@ -0,0 +1,277 @@
+//! Enumeration of the assets known to the asset registry of a running game.
+//!
+//! The registry is the live `UAssetRegistryImpl` object, what `IAssetRegistry::Get` returns. Its
+//! `FAssetRegistryState` isn't reflected so the `CachedAssetsByObjectPath` map is located by
+//! shape: the first `TMap<FName, FAssetData*>` whose values start with their own key.
+//!
+//! ```text
+//! FAssetData (UE 4.x to 5.0)
+//!   0x00 FName ObjectPath
+//!   0x08 FName PackageName
+//!   0x10 FName PackagePath
+//!   0x18 FName AssetName
+//!   0x20 FName AssetClass
+//! ```
+//!
+//! UE 5.1 replaced `ObjectPath` and `AssetClass` with `FTopLevelAssetPath` and is not supported.
+
+use super::{
+    object::Objects,
+    object_ptr::FName,
+    read::ReadMemory,
+    set::{read_map, read_set_len, ScriptMapLayout},
+};
+use crate::MemoryAccessError;
+
+/// Range of `UAssetRegistryImpl` searched for `CachedAssetsByObjectPath`
+const SEARCH_END: usize = 0x800;
+/// Maps larger than this are assumed to be garbage
+const MAX_ASSETS: usize = 0x100_0000;
+/// Pairs checked to accept a candidate map
+const SAMPLE_PAIRS: usize = 8;
+
+/// Names of one `FAssetData`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetData {
+    /// e.g. `/Game/Maps/Menu.Menu`
+    pub object_path: String,
+    /// e.g. `/Game/Maps/Menu`
+    pub package_name: String,
+    /// e.g. `/Game/Maps`
+    pub package_path: String,
+    pub asset_name: String,
+    /// Name of the asset's class, e.g. `World`
+    pub asset_class: String,
+}
+
+pub struct AssetRegistry<'a, 'objects, 'mem, R, N> {
+    objects: &'a Objects<'objects, 'mem, R, N>,
+    registry: usize,
+    assets_by_object_path: usize,
+}
+impl<'a, 'objects, 'mem, R, N> AssetRegistry<'a, 'objects, 'mem, R, N>
+where
+    R: ReadMemory,
+    N: Fn(FName) -> Option<String>,
+{
+    /// Find the live `UAssetRegistryImpl` and its cached assets, `None` if there is no registry
+    /// or its state could not be located
+    pub fn find(objects: &'a Objects<'objects, 'mem, R, N>) -> Option<Self> {
+        objects
+            .find(|object| objects.is_a(object, "AssetRegistryImpl"))
+            .into_iter()
+            .find_map(|registry| Self::new(objects, registry).ok().flatten())
+    }
+    /// Registry at `registry`, `None` if its cached assets could not be located
+    pub fn new(
+        objects: &'a Objects<'objects, 'mem, R, N>,
+        registry: usize,
+    ) -> Result<Option<Self>, MemoryAccessError> {
+        let build = objects.layout().build;
+        let layout = ScriptMapLayout::new(build.fname_size(), 4, 8, 8);
+        let mem = objects.mem();
+
+        let looks_like_assets = |map: usize| -> Result<bool, MemoryAccessError> {
+            let len = read_set_len(mem, map)?;
+            if len == 0 || len > MAX_ASSETS {
+                return Ok(false);
+            }
+            let pairs = read_map(mem, map, layout)?;
+            // a length with no allocated elements is another field read as a map
+            if pairs.is_empty() {
+                return Ok(false);
+            }
+            for (key, value) in pairs.into_iter().take(SAMPLE_PAIRS) {
+                let asset = mem.read_ptr(value)?;
+                if asset == 0
+                    || FName::read_for(mem, asset, build)? != FName::read_for(mem, key, build)?
+                {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        };
+
+        let start = objects.layout().outer + 8;
+        Ok((start..SEARCH_END)
+            .step_by(8)
+            .map(|offset| registry + offset)
+            .find(|map| looks_like_assets(*map).unwrap_or_default())
+            .map(|assets_by_object_path| Self {
+                objects,
+                registry,
+                assets_by_object_path,
+            }))
+    }
+    /// Address of the `UAssetRegistryImpl`
+    pub fn registry(&self) -> usize {
+        self.registry
+    }
+
+    /// Addresses of all cached `FAssetData`
+    pub fn asset_addresses(&self) -> Result<Vec<usize>, MemoryAccessError> {
+        let build = self.objects.layout().build;
+        let layout = ScriptMapLayout::new(build.fname_size(), 4, 8, 8);
+        let mem = self.objects.mem();
+        read_map(mem, self.assets_by_object_path, layout)?
+            .into_iter()
+            .map(|(_, value)| mem.read_ptr(value))
+            .collect()
+    }
+    /// Read the `FAssetData` at `address`
+    pub fn asset(&self, address: usize) -> Result<AssetData, MemoryAccessError> {
+        let size = self.objects.layout().build.fname_size();
+        let name = |index: usize| -> Result<String, MemoryAccessError> {
+            Ok(self
+                .objects
+                .fname(address + index * size)?
+                .unwrap_or_default())
+        };
+        Ok(AssetData {
+            object_path: name(0)?,
+            package_name: name(1)?,
+            package_path: name(2)?,
+            asset_name: name(3)?,
+            asset_class: name(4)?,
+        })
+    }
+    /// All cached assets. Assets which can't be read are skipped.
+    pub fn assets(&self) -> Result<Vec<AssetData>, MemoryAccessError> {
+        Ok(self
+            .asset_addresses()?
+            .into_iter()
+            .filter_map(|address| self.asset(address).ok())
+            .collect())
+    }
+    /// Assets whose class is named `class`
+    pub fn assets_of_class(&self, class: &str) -> Result<Vec<AssetData>, MemoryAccessError> {
+        Ok(self
+            .assets()?
+            .into_iter()
+            .filter(|asset| asset.asset_class == class)
+            .collect())
+    }
+    /// Assets in `path` or one of its sub paths, e.g. `/Game/Maps`
+    pub fn assets_in_path(&self, path: &str) -> Result<Vec<AssetData>, MemoryAccessError> {
+        let path = path.trim_end_matches('/');
+        Ok(self
+            .assets()?
+            .into_iter()
+            .filter(|asset| {
+                asset
+                    .package_path
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{TestImageBuilder, TestObjects},
+        ue::{ObjectArray, ObjectLayout},
+    };
+
+    /// Write a `TMap<FName, void*>` with all elements allocated at `address`
+    fn write_map(objects: &mut TestObjects, address: usize, pairs: &[(&str, usize)]) {
+        let stride = ScriptMapLayout::new(8, 4, 8, 8).set.size;
+        let data = objects.alloc(pairs.len() * stride);
+        for (i, (key, value)) in pairs.iter().enumerate() {
+            objects.write_name(data + i * stride, key);
+            objects.write_ptr(data + i * stride + 8, *value);
+        }
+        let num = (pairs.len() as i32).to_le_bytes();
+        objects.write_ptr(address, data);
+        objects.write(address + 8, &[num, num].concat());
+        // inline allocation flags and their NumBits
+        objects.write(address + 0x10, &[(1u8 << pairs.len()) - 1]);
+        objects.write(address + 0x28, &[num, num].concat());
+    }
+
+    #[test]
+    fn test_asset_registry() {
+        let base = 0x140000000;
+        let mut objects = TestObjects::new(base + 0x10000);
+        let object = objects.class("Object", 0);
+        let registry_class = objects.class("AssetRegistryImpl", object);
+        let registry = objects.object(registry_class, "AssetRegistryImpl", 0);
+
+        let mut pairs = vec![];
+        for names in [
+            [
+                "/Game/Maps/Menu.Menu",
+                "/Game/Maps/Menu",
+                "/Game/Maps",
+                "Menu",
+                "World",
+            ],
+            [
+                "/Game/Maps/Sub/Arena.Arena",
+                "/Game/Maps/Sub/Arena",
+                "/Game/Maps/Sub",
+                "Arena",
+                "World",
+            ],
+            [
+                "/Game/MapsOld/X.X",
+                "/Game/MapsOld/X",
+                "/Game/MapsOld",
+                "X",
+                "Texture2D",
+            ],
+        ] {
+            let asset = objects.alloc(5 * 8);
+            for (i, name) in names.iter().enumerate() {
+                objects.write_name(asset + i * 8, name);
+            }
+            pairs.push((names[0], asset));
+        }
+        // a map of objects located before the assets, its values don't start with their key
+        write_map(&mut objects, registry + 0x60, &[("Menu", registry)]);
+        write_map(&mut objects, registry + 0x100, &pairs);
+
+        let names = objects.names();
+        let (builder, array) = objects.add_to(TestImageBuilder::new(base));
+        let image = builder.build().unwrap();
+        let array = ObjectArray::new(&image.memory, array, TestObjects::ITEM_SIZE);
+        let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+            names.get(name.comparison_index as usize).cloned()
+        });
+
+        let assets = AssetRegistry::find(&objects).unwrap();
+        assert_eq!(assets.registry(), registry);
+        assert_eq!(assets.assets().unwrap().len(), 3);
+        assert_eq!(
+            assets.assets().unwrap()[0],
+            AssetData {
+                object_path: "/Game/Maps/Menu.Menu".to_string(),
+                package_name: "/Game/Maps/Menu".to_string(),
+                package_path: "/Game/Maps".to_string(),
+                asset_name: "Menu".to_string(),
+                asset_class: "World".to_string(),
+            }
+        );
+        let asset_names = |assets: Vec<AssetData>| {
+            assets
+                .into_iter()
+                .map(|asset| asset.asset_name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            asset_names(assets.assets_of_class("World").unwrap()),
+            ["Menu", "Arena"]
+        );
+        // sub paths but not paths sharing a prefix
+        assert_eq!(
+            asset_names(assets.assets_in_path("/Game/Maps/").unwrap()),
+            ["Menu", "Arena"]
+        );
+        assert_eq!(
+            asset_names(assets.assets_in_path("/Game/MapsOld").unwrap()),
+            ["X"]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Engine build options which change the layout of types read by [`ue`](super) or where
+//! singletons end up, detected for a game by
+//! [`EngineBuildConfig`](crate::resolvers::unreal::layout::EngineBuildConfig)
+
+/// `EBuildConfiguration`, minus `Unknown`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Configuration {
+    Debug,
+    DebugGame,
+    Development,
+    Test,
+    Shipping,
+}
+
+/// Build options of a game. The default matches a shipping game build with an unknown
+/// [`Configuration`].
+///
+/// ```
+/// # use patternsleuth::ue::{BuildConfig, ObjectLayout};
+/// let build = BuildConfig::new().case_preserving_names(true);
+/// assert_eq!(build.fname_size(), 12);
+/// // UObjectBase::OuterPrivate follows the larger NamePrivate
+/// assert_eq!(ObjectLayout::for_build(build).outer, 0x28);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct BuildConfig {
+    /// WITH_CASE_PRESERVING_NAME: FName holds a DisplayIndex between ComparisonIndex and Number
+    pub case_preserving_names: bool,
+    /// WITH_EDITOR and WITH_EDITORONLY_DATA
+    pub with_editor: bool,
+    /// `None` if the game has no PDB path to read it from
+    pub configuration: Option<Configuration>,
+    /// DO_CHECK: `check`s are compiled in
+    pub do_check: bool,
+}
+impl BuildConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn case_preserving_names(mut self, case_preserving_names: bool) -> Self {
+        self.case_preserving_names = case_preserving_names;
+        self
+    }
+    pub fn with_editor(mut self, with_editor: bool) -> Self {
+        self.with_editor = with_editor;
+        self
+    }
+    pub fn configuration(mut self, configuration: Option<Configuration>) -> Self {
+        self.configuration = configuration;
+        self
+    }
+    pub fn do_check(mut self, do_check: bool) -> Self {
+        self.do_check = do_check;
+        self
+    }
+    /// Debug and DebugGame builds, which compile the game module (and for Debug the engine)
+    /// without optimizations
+    pub fn is_debug(&self) -> bool {
+        matches!(
+            self.configuration,
+            Some(Configuration::Debug | Configuration::DebugGame)
+        )
+    }
+    /// sizeof(FName)
+    pub fn fname_size(&self) -> usize {
+        if self.case_preserving_names {
+            12
+        } else {
+            8
+        }
+    }
+    /// Offset of FName::Number
+    pub fn fname_number(&self) -> usize {
+        self.fname_size() - 4
+    }
+}
@@ -0,0 +1,339 @@
+//! `TArray` and `FString` in two flavours:
+//!
+//! - [`TArray`] is a plain view of an array owned by someone else (usually the engine). It never
+//!   allocates or frees.
+//! - [`TArrayOwned`] owns its allocation, which is made through [`gmalloc`] so it can be handed to
+//!   the engine or taken over from it. Dropping it drops its elements and frees the allocation.
+//!
+//! Both have the same layout so a `*mut TArray<T>` pointing at engine memory can be treated as a
+//! `&mut TArrayOwned<T>` to modify it in place.
+
+use std::{
+    ffi::c_void,
+    fmt::{Debug, Display},
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
+
+use super::{malloc::gmalloc, read::ReadMemory};
+use crate::MemoryAccessError;
+
+#[repr(C)]
+pub struct TArray<T> {
+    data: *mut T,
+    num: i32,
+    max: i32,
+}
+impl<T> Clone for TArray<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for TArray<T> {}
+impl<T> Default for TArray<T> {
+    fn default() -> Self {
+        Self {
+            data: std::ptr::null_mut(),
+            num: 0,
+            max: 0,
+        }
+    }
+}
+impl<T> TArray<T> {
+    /// # Safety
+    /// `data` must point to `max` slots of `T` of which the first `num` are initialized, or be
+    /// null if `max` is 0.
+    pub unsafe fn from_raw_parts(data: *mut T, num: usize, max: usize) -> Self {
+        Self {
+            data,
+            num: num as i32,
+            max: max as i32,
+        }
+    }
+    pub fn as_ptr(&self) -> *const T {
+        self.data
+    }
+    pub fn len(&self) -> usize {
+        self.num as usize
+    }
+    pub fn capacity(&self) -> usize {
+        self.max as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn as_slice(&self) -> &[T] {
+        if self.num <= 0 || self.data.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.data, self.num as usize) }
+        }
+    }
+}
+impl<T: Debug> Debug for TArray<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+/// A [`TArray`] owning its GMalloc allocation
+#[repr(transparent)]
+pub struct TArrayOwned<T>(TArray<T>);
+impl<T> Default for TArrayOwned<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+impl<T> Drop for TArrayOwned<T> {
+    fn drop(&mut self) {
+        self.clear();
+        if !self.0.data.is_null() {
+            // the array owns its allocation
+            unsafe { gmalloc().free(self.0.data as *mut c_void) };
+        }
+    }
+}
+impl<T> TArrayOwned<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut new = Self::new();
+        new.reserve(capacity);
+        new
+    }
+    /// Take ownership of an array allocated through GMalloc, e.g. one returned by the engine.
+    ///
+    /// # Safety
+    /// The array must not be owned by anything else and its data must have been allocated by
+    /// GMalloc.
+    pub unsafe fn from_raw(array: TArray<T>) -> Self {
+        Self(array)
+    }
+    /// Release ownership, e.g. after handing the array over to the engine
+    pub fn into_raw(self) -> TArray<T> {
+        let array = self.0;
+        std::mem::forget(self);
+        array
+    }
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+    pub fn as_raw(&self) -> &TArray<T> {
+        &self.0
+    }
+    pub fn as_raw_mut(&mut self) -> &mut TArray<T> {
+        &mut self.0
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.0.num <= 0 || self.0.data.is_null() {
+            &mut []
+        } else {
+            unsafe { std::slice::from_raw_parts_mut(self.0.data, self.0.num as usize) }
+        }
+    }
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len() + additional;
+        if required <= self.capacity() {
+            return;
+        }
+        let max = required.next_power_of_two().max(4);
+        // the array owns its allocation and replaces it with the returned one
+        self.0.data = unsafe {
+            gmalloc().realloc(
+                self.0.data as *mut c_void,
+                max * std::mem::size_of::<T>(),
+                std::mem::align_of::<T>() as u32,
+            )
+        } as *mut T;
+        self.0.max = max as i32;
+    }
+    pub fn push(&mut self, value: T) {
+        self.reserve(1);
+        unsafe {
+            std::ptr::write(self.0.data.add(self.len()), value);
+        }
+        self.0.num += 1;
+    }
+    /// Remove the element at `index`, replacing it with the last element like the engine's
+    /// `RemoveAtSwap`
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "index {index} out of bounds for length {len}");
+        unsafe {
+            let value = std::ptr::read(self.0.data.add(index));
+            std::ptr::copy(self.0.data.add(len - 1), self.0.data.add(index), 1);
+            self.0.num -= 1;
+            value
+        }
+    }
+    pub fn clear(&mut self) {
+        let elems: *mut [T] = self.as_mut_slice();
+        unsafe {
+            self.0.num = 0;
+            std::ptr::drop_in_place(elems);
+        }
+    }
+}
+impl<T> Deref for TArrayOwned<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+impl<T> DerefMut for TArrayOwned<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+impl<T: Debug> Debug for TArrayOwned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+impl<T: Clone> Clone for TArrayOwned<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+impl<T> Extend<T> for TArrayOwned<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+impl<T> FromIterator<T> for TArrayOwned<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut new = Self::new();
+        new.extend(iter);
+        new
+    }
+}
+impl<T: Clone> From<&[T]> for TArrayOwned<T> {
+    fn from(value: &[T]) -> Self {
+        value.iter().cloned().collect()
+    }
+}
+impl<T> From<Vec<T>> for TArrayOwned<T> {
+    fn from(value: Vec<T>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+pub type FString = TArray<u16>;
+pub type FStringOwned = TArrayOwned<u16>;
+
+/// Strip the null terminator (and any padding nulls after it) of engine string data
+fn trim_nul(chars: &[u16]) -> &[u16] {
+    let end = chars.iter().rposition(|c| *c != 0).map_or(0, |i| i + 1);
+    &chars[..end]
+}
+
+/// Decode engine string data, failing on unpaired surrogates
+pub fn decode_utf16(chars: &[u16]) -> Result<String, std::string::FromUtf16Error> {
+    String::from_utf16(trim_nul(chars))
+}
+
+/// Decode engine string data, replacing unpaired surrogates with U+FFFD. Strings built by game
+/// code (e.g. truncated at a fixed length) are not always valid UTF-16.
+pub fn decode_utf16_lossy(chars: &[u16]) -> String {
+    String::from_utf16_lossy(trim_nul(chars))
+}
+
+impl FString {
+    /// Read the string at `address` of an in-process or external `FString`
+    pub fn read<R: ReadMemory>(mem: &R, address: usize) -> Result<String, MemoryAccessError> {
+        Ok(decode_utf16(&Self::read_chars(mem, address)?)?)
+    }
+    /// Like [`FString::read`] but replacing invalid UTF-16 instead of failing
+    pub fn read_lossy<R: ReadMemory>(mem: &R, address: usize) -> Result<String, MemoryAccessError> {
+        Ok(decode_utf16_lossy(&Self::read_chars(mem, address)?))
+    }
+    fn read_chars<R: ReadMemory>(mem: &R, address: usize) -> Result<Vec<u16>, MemoryAccessError> {
+        let data = mem.read_ptr(address)?;
+        let num = mem.read_i32(address + 8)?;
+        if data == 0 || num <= 0 {
+            return Ok(vec![]);
+        }
+        mem.read_utf16(data, num as usize)
+    }
+    pub fn try_to_string(&self) -> Result<String, std::string::FromUtf16Error> {
+        decode_utf16(self.as_slice())
+    }
+    pub fn to_string_lossy(&self) -> String {
+        decode_utf16_lossy(self.as_slice())
+    }
+}
+impl FStringOwned {
+    pub fn try_to_string(&self) -> Result<String, std::string::FromUtf16Error> {
+        self.0.try_to_string()
+    }
+    pub fn to_string_lossy(&self) -> String {
+        self.0.to_string_lossy()
+    }
+}
+
+impl Display for FString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
+impl Display for FStringOwned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+impl From<&str> for FStringOwned {
+    /// Encode as a null terminated UTF-16 string as expected by the engine
+    fn from(value: &str) -> Self {
+        value.encode_utf16().chain([0]).collect()
+    }
+}
+impl FromStr for FStringOwned {
+    type Err = std::convert::Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_owned_containers() {
+        crate::ue::malloc::test::set_test_gmalloc();
+
+        let string = FStringOwned::from("hello");
+        assert_eq!(string.len(), 6);
+        assert_eq!(string.to_string(), "hello");
+
+        let mut strings: TArrayOwned<FStringOwned> =
+            (0..10).map(|i| i.to_string().as_str().into()).collect();
+        assert_eq!(strings.len(), 10);
+        assert_eq!(strings[9].to_string(), "9");
+        strings.push("last".parse().unwrap());
+
+        // ownership can be released and the array read through the borrowed layout
+        let raw = strings.into_raw();
+        let borrowed: &TArray<FString> = unsafe { &*(&raw as *const TArray<FStringOwned>).cast() };
+        assert_eq!(borrowed.as_slice()[10].to_string(), "last");
+        drop(unsafe { TArrayOwned::from_raw(raw) });
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let chars = "héllo 🦀".encode_utf16().chain([0, 0]).collect::<Vec<_>>();
+        assert_eq!(decode_utf16(&chars).unwrap(), "héllo 🦀");
+        assert_eq!(decode_utf16_lossy(&chars), "héllo 🦀");
+
+        // string truncated in the middle of a surrogate pair
+        let truncated = &chars[..chars.len() - 3];
+        assert!(decode_utf16(truncated).is_err());
+        assert_eq!(decode_utf16_lossy(truncated), "héllo \u{fffd}");
+        assert_eq!(decode_utf16_lossy(&[0]), "");
+    }
+}
@@ -0,0 +1,385 @@
+//! Binding Rust closures to engine multicast delegates such as the `FCoreDelegates` globals (see
+//! [`delegates`](crate::resolvers::unreal::delegates)).
+//!
+//! A `TMulticastDelegate` keeps its bindings in an invocation list of `FDelegateBase`s, each
+//! owning a polymorphic delegate instance allocated with `FMemory`. Binding means building a C++
+//! compatible instance with a vtable and appending it to the list in place. The engine frees the
+//! instance itself once it has been unbound, so it is allocated through [`gmalloc`]. Only valid in
+//! the game process.
+//!
+//! ```text
+//! TMulticastDelegate (0x18)
+//!   0x00 TArray<FDelegateBase> InvocationList
+//!   0x10 int32 CompactionThreshold
+//!   0x14 int32 InvocationListLockCount
+//! FDelegateBase (0x10)
+//!   0x00 void* DelegateAllocator (FHeapAllocator)
+//!   0x08 int32 DelegateSize (in 16 byte units)
+//! ```
+//!
+//! Since 5.1 both are generic over a thread safety mode. The default, not thread safe mode keeps
+//! this layout in shipping builds, thread safe delegates (`FTSSimpleMulticastDelegate`) add a
+//! lock and are not supported. The vtable of delegate instances depends on the version, see
+//! [`DelegateLayout`].
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use super::{
+    containers::{TArray, TArrayOwned},
+    malloc::gmalloc,
+};
+
+/// Alignment and size unit of delegate instance allocations, `FAlignedInlineDelegateType`
+const DELEGATE_ALIGNMENT: usize = 16;
+
+/// Handles are normally generated by the engine counting up from 1, so ours count down from the
+/// top to never collide
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(u64::MAX);
+
+#[repr(C)]
+struct FDelegateBase {
+    allocation: *mut Instance,
+    size: i32,
+}
+
+/// Version dependent vtable of delegate instances
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DelegateLayout {
+    /// A single `CreateCopy(FDelegateBase&)`
+    #[default]
+    SingleCopy,
+    /// `CreateCopy` overloaded for `TDelegateBase` of each thread safety mode
+    /// (`FThreadSafeDelegateMode`, `FNotThreadSafeDelegateMode` and
+    /// `FNotThreadSafeNotCheckedDelegateMode`)
+    ThreadSafetyModes,
+}
+impl DelegateLayout {
+    pub fn for_version(major: u16, minor: u16) -> Self {
+        match (major, minor) {
+            (..=4, _) | (5, 0) => Self::SingleCopy,
+            _ => Self::ThreadSafetyModes,
+        }
+    }
+    fn vtable(self) -> *const InstanceVTable {
+        match self {
+            Self::SingleCopy => &INSTANCE_VTABLE,
+            Self::ThreadSafetyModes => {
+                &INSTANCE_VTABLE_THREAD_SAFETY_MODES as *const InstanceVTable<3> as *const _
+            }
+        }
+    }
+}
+
+type CreateCopy = unsafe extern "system" fn(this: &Instance, base: *mut FDelegateBase);
+
+/// `IBaseDelegateInstance<void()>` of shipping builds (no `TryGetBoundFunctionName`):
+/// ```cpp
+/// virtual UObject* GetUObject() const = 0;
+/// virtual const void* GetObjectForTimerManager() const = 0;
+/// virtual uint64 GetBoundProgramCounterForTimerManager() const = 0;
+/// virtual bool HasSameObject(const void* InUserObject) const = 0;
+/// virtual bool IsCompactable() const;
+/// virtual bool IsSafeToExecute() const = 0;
+/// virtual FDelegateHandle GetHandle() const = 0;
+/// virtual ~IDelegateInstance();
+/// virtual void CreateCopy(FDelegateBase& Base) = 0; // one per thread safety mode since 5.1
+/// virtual void Execute() const = 0;
+/// virtual bool ExecuteIfSafe() const = 0;
+/// ```
+///
+/// The slots from `CreateCopy` on are `COPIES` long.
+#[repr(C)]
+struct InstanceVTable<const COPIES: usize = 1> {
+    get_uobject: unsafe extern "system" fn(this: &Instance) -> usize,
+    get_object_for_timer_manager: unsafe extern "system" fn(this: &Instance) -> usize,
+    get_bound_program_counter_for_timer_manager: unsafe extern "system" fn(this: &Instance) -> u64,
+    has_same_object: unsafe extern "system" fn(this: &Instance, object: usize) -> bool,
+    is_compactable: unsafe extern "system" fn(this: &Instance) -> bool,
+    is_safe_to_execute: unsafe extern "system" fn(this: &Instance) -> bool,
+    // FDelegateHandle is returned through a hidden pointer as member functions always do so
+    get_handle: unsafe extern "system" fn(this: &Instance, handle: *mut u64) -> *mut u64,
+    scalar_del_dtor: unsafe extern "system" fn(this: *mut Instance, flags: u32) -> *mut Instance,
+    create_copy: [CreateCopy; COPIES],
+    execute: unsafe extern "system" fn(this: &Instance),
+    execute_if_safe: unsafe extern "system" fn(this: &Instance) -> bool,
+}
+static INSTANCE_VTABLE: InstanceVTable = InstanceVTable {
+    get_uobject: instance_null,
+    get_object_for_timer_manager: instance_null,
+    get_bound_program_counter_for_timer_manager: instance_program_counter,
+    has_same_object: instance_has_same_object,
+    is_compactable: instance_is_compactable,
+    is_safe_to_execute: instance_is_safe_to_execute,
+    get_handle: instance_get_handle,
+    scalar_del_dtor: instance_scalar_del_dtor,
+    create_copy: [instance_create_copy],
+    execute: instance_execute,
+    execute_if_safe: instance_execute_if_safe,
+};
+
+// Copies are only made into delegates of the type the instance was bound to, so the thread safe
+// overload is never called for a not thread safe delegate and all overloads can share a slot
+// implementation regardless of their order.
+static INSTANCE_VTABLE_THREAD_SAFETY_MODES: InstanceVTable<3> = InstanceVTable {
+    get_uobject: instance_null,
+    get_object_for_timer_manager: instance_null,
+    get_bound_program_counter_for_timer_manager: instance_program_counter,
+    has_same_object: instance_has_same_object,
+    is_compactable: instance_is_compactable,
+    is_safe_to_execute: instance_is_safe_to_execute,
+    get_handle: instance_get_handle,
+    scalar_del_dtor: instance_scalar_del_dtor,
+    create_copy: [instance_create_copy; 3],
+    execute: instance_execute,
+    execute_if_safe: instance_execute_if_safe,
+};
+
+/// Shared between the binding and every copy of its instance the engine makes
+struct State {
+    callback: Box<dyn Fn() + Send + Sync>,
+    bound: AtomicBool,
+    handle: u64,
+    layout: DelegateLayout,
+}
+
+/// The native delegate instance handed to the engine
+#[repr(C)]
+struct Instance {
+    vtable: *const InstanceVTable,
+    state: *const State,
+}
+impl Instance {
+    fn state(&self) -> &State {
+        unsafe { &*self.state }
+    }
+    /// Allocate an instance into `base` the way `FDelegateBase::Allocate` does
+    unsafe fn allocate(base: *mut FDelegateBase, state: Arc<State>) {
+        let base = &mut *base;
+        let size = std::mem::size_of::<Instance>().div_ceil(DELEGATE_ALIGNMENT);
+        if base.size as usize != size {
+            base.allocation = gmalloc().realloc(
+                base.allocation.cast(),
+                size * DELEGATE_ALIGNMENT,
+                DELEGATE_ALIGNMENT as u32,
+            ) as *mut Instance;
+            base.size = size as i32;
+        }
+        base.allocation.write(Instance {
+            vtable: state.layout.vtable(),
+            state: Arc::into_raw(state),
+        });
+    }
+}
+
+unsafe extern "system" fn instance_null(_this: &Instance) -> usize {
+    0
+}
+unsafe extern "system" fn instance_program_counter(_this: &Instance) -> u64 {
+    0
+}
+unsafe extern "system" fn instance_has_same_object(_this: &Instance, _object: usize) -> bool {
+    false
+}
+// unbound instances are removed by the engine the next time it compacts the invocation list
+unsafe extern "system" fn instance_is_compactable(this: &Instance) -> bool {
+    !this.state().bound.load(Ordering::Acquire)
+}
+unsafe extern "system" fn instance_is_safe_to_execute(this: &Instance) -> bool {
+    this.state().bound.load(Ordering::Acquire)
+}
+unsafe extern "system" fn instance_get_handle(this: &Instance, handle: *mut u64) -> *mut u64 {
+    handle.write(this.state().handle);
+    handle
+}
+// the engine destroys instances in place and frees their allocation itself
+unsafe extern "system" fn instance_scalar_del_dtor(
+    this: *mut Instance,
+    flags: u32,
+) -> *mut Instance {
+    drop(Arc::from_raw((*this).state));
+    if flags & 1 != 0 {
+        gmalloc().free(this.cast());
+    }
+    this
+}
+unsafe extern "system" fn instance_create_copy(this: &Instance, base: *mut FDelegateBase) {
+    Arc::increment_strong_count(this.state);
+    Instance::allocate(base, Arc::from_raw(this.state));
+}
+unsafe extern "system" fn instance_execute(this: &Instance) {
+    (this.state().callback)();
+}
+unsafe extern "system" fn instance_execute_if_safe(this: &Instance) -> bool {
+    let state = this.state();
+    let bound = state.bound.load(Ordering::Acquire);
+    if bound {
+        (state.callback)();
+    }
+    bound
+}
+
+/// A closure bound to a multicast delegate without parameters (`FSimpleMulticastDelegate`).
+/// Dropping it unbinds it.
+pub struct MulticastDelegateBinding {
+    state: Arc<State>,
+}
+impl MulticastDelegateBinding {
+    /// Append `callback` to the invocation list of the `TMulticastDelegate<void()>` at
+    /// `delegate` of an engine with the given layout.
+    ///
+    /// # Safety
+    /// `delegate` must be the address of a live, not thread safe multicast delegate without
+    /// parameters in the current process, e.g. a resolved `FCoreDelegates` global, and must only
+    /// be bound from the thread broadcasting it. The invocation list may grow and the instance is
+    /// allocated through [`gmalloc`], so [`set_gmalloc`](super::set_gmalloc) must have been
+    /// called.
+    pub unsafe fn bind(
+        delegate: usize,
+        layout: DelegateLayout,
+        callback: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        let state = Arc::new(State {
+            callback: Box::new(callback),
+            bound: AtomicBool::new(true),
+            handle: NEXT_HANDLE.fetch_sub(1, Ordering::Relaxed),
+            layout,
+        });
+        let mut base = FDelegateBase {
+            allocation: std::ptr::null_mut(),
+            size: 0,
+        };
+        Instance::allocate(&mut base, state.clone());
+        // the list is the engine's, only borrow it as owned to modify it in place
+        let list =
+            &mut *(delegate as *mut TArray<FDelegateBase> as *mut TArrayOwned<FDelegateBase>);
+        list.push(base);
+        Self { state }
+    }
+    /// `FDelegateHandle` of the binding, as passed to `TMulticastDelegate::Remove`
+    pub fn handle(&self) -> u64 {
+        self.state.handle
+    }
+    /// Whether the closure is still called when the delegate is broadcast
+    pub fn is_bound(&self) -> bool {
+        self.state.bound.load(Ordering::Acquire)
+    }
+    /// Stop calling the closure. The engine drops it when it next compacts the invocation list.
+    pub fn unbind(&self) {
+        self.state.bound.store(false, Ordering::Release);
+    }
+}
+impl Drop for MulticastDelegateBinding {
+    fn drop(&mut self) {
+        self.unbind()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    impl InstanceVTable {
+        /// `Execute` and `ExecuteIfSafe` of a vtable of `layout`
+        unsafe fn execute(
+            &self,
+            layout: DelegateLayout,
+        ) -> (
+            unsafe extern "system" fn(this: &Instance),
+            unsafe extern "system" fn(this: &Instance) -> bool,
+        ) {
+            match layout {
+                DelegateLayout::SingleCopy => (self.execute, self.execute_if_safe),
+                DelegateLayout::ThreadSafetyModes => {
+                    let vtable = &*(self as *const Self as *const InstanceVTable<3>);
+                    (vtable.execute, vtable.execute_if_safe)
+                }
+            }
+        }
+    }
+
+    /// Broadcast the way the engine does, in reverse and compacting unbound instances
+    unsafe fn broadcast(delegate: usize, layout: DelegateLayout) {
+        let list = &mut *(delegate as *mut TArrayOwned<FDelegateBase>);
+        let mut compact = false;
+        for base in list.iter().rev() {
+            let instance = &*base.allocation;
+            let (_, execute_if_safe) = (*instance.vtable).execute(layout);
+            compact |= !execute_if_safe(instance);
+        }
+        if compact {
+            while let Some(index) = list.iter().position(|base| {
+                let instance = &*base.allocation;
+                ((*instance.vtable).is_compactable)(instance)
+            }) {
+                let base = list.swap_remove(index);
+                ((*(*base.allocation).vtable).scalar_del_dtor)(base.allocation, 0);
+                gmalloc().free(base.allocation.cast());
+            }
+        }
+    }
+
+    #[test]
+    fn test_multicast_delegate_binding() {
+        crate::ue::malloc::test::set_test_gmalloc();
+
+        for (layout, version) in [
+            (DelegateLayout::SingleCopy, (4, 27)),
+            (DelegateLayout::ThreadSafetyModes, (5, 1)),
+        ] {
+            assert_eq!(DelegateLayout::for_version(version.0, version.1), layout);
+
+            // TMulticastDelegate with an empty invocation list
+            let mut delegate = [0usize; 3];
+            let address = delegate.as_mut_ptr() as usize;
+
+            let calls = Arc::new(AtomicUsize::new(0));
+            let binding = unsafe {
+                let calls = calls.clone();
+                MulticastDelegateBinding::bind(address, layout, move || {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                })
+            };
+            let other = unsafe { MulticastDelegateBinding::bind(address, layout, || {}) };
+            assert_ne!(binding.handle(), other.handle());
+
+            unsafe { broadcast(address, layout) };
+            assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+            // the engine's copy shares the binding
+            let list = unsafe { &*(address as *const TArray<FDelegateBase>) };
+            let mut copy = FDelegateBase {
+                allocation: std::ptr::null_mut(),
+                size: 0,
+            };
+            unsafe {
+                let instance = &*list.as_slice()[0].allocation;
+                ((*instance.vtable).create_copy[0])(instance, &mut copy);
+                let copy = &*copy.allocation;
+                let mut handle = 0;
+                ((*copy.vtable).get_handle)(copy, &mut handle);
+                assert_eq!(handle, binding.handle());
+                let (execute, _) = (*copy.vtable).execute(layout);
+                execute(copy);
+            }
+            assert_eq!(calls.load(Ordering::Relaxed), 2);
+            unsafe {
+                ((*(*copy.allocation).vtable).scalar_del_dtor)(copy.allocation, 1);
+            }
+
+            drop(binding);
+            unsafe { broadcast(address, layout) };
+            assert_eq!(calls.load(Ordering::Relaxed), 2);
+            assert_eq!(list.len(), 1);
+
+            drop(other);
+            unsafe { broadcast(address, layout) };
+            assert!(list.is_empty());
+            drop(unsafe { TArrayOwned::from_raw(*list) });
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Statics holding values resolved at runtime, e.g. engine functions and globals found during
+//! startup of an injected library
+
+use std::sync::OnceLock;
+
+/// A value set once after it has been resolved and read freely afterwards. Reads after
+/// initialization don't lock.
+///
+/// ```
+/// # use patternsleuth::ue::ResolvedGlobal;
+/// static FFRAME_STEP: ResolvedGlobal<usize> = ResolvedGlobal::new("FFrame::Step");
+///
+/// FFRAME_STEP.set(0x140123450).unwrap();
+/// assert_eq!(*FFRAME_STEP.get(), 0x140123450);
+/// ```
+#[derive(Debug)]
+pub struct ResolvedGlobal<T> {
+    name: &'static str,
+    value: OnceLock<T>,
+}
+impl<T> ResolvedGlobal<T> {
+    /// `name` is used in the panic message when the global is read before it's set
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            value: OnceLock::new(),
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Set the value, returning it back if the global has already been set
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.value.set(value)
+    }
+    /// The value, panics if it has not been set yet
+    pub fn get(&self) -> &T {
+        match self.value.get() {
+            Some(value) => value,
+            None => panic!("{} used before it was resolved", self.name),
+        }
+    }
+    pub fn try_get(&self) -> Option<&T> {
+        self.value.get()
+    }
+    pub fn is_set(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolved_global() {
+        static GLOBAL: ResolvedGlobal<usize> = ResolvedGlobal::new("Test");
+        assert!(GLOBAL.try_get().is_none());
+        assert_eq!(
+            std::panic::catch_unwind(|| *GLOBAL.get())
+                .unwrap_err()
+                .downcast_ref::<String>()
+                .map(String::as_str),
+            Some("Test used before it was resolved")
+        );
+
+        GLOBAL.set(0x1000).unwrap();
+        assert_eq!(GLOBAL.set(0x2000), Err(0x2000));
+        assert_eq!(*GLOBAL.get(), 0x1000);
+    }
+}
@@ -0,0 +1,206 @@
+//! Registering `FUObjectCreateListener`s and `FUObjectDeleteListener`s with `GUObjectArray` to
+//! be notified of every object allocated and freed, without detouring
+//! `FUObjectArray::AllocateUObjectIndex`/`FreeUObjectIndex`.
+//!
+//! Listeners are native objects whose pointers the engine keeps in two arrays of
+//! `FUObjectArray`, so registering one means building a C++ compatible object with a vtable
+//! and adding it to the array in place. Only valid in the game process.
+
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::containers::{TArray, TArrayOwned};
+
+/// Offsets of the listener arrays within `FUObjectArray`. They follow `ObjAvailableList` whose
+/// size depends on the engine version and build configuration, so there is no default: take
+/// them from the `this` relative accesses in `FUObjectArray::AddUObjectCreateListener` and
+/// `AddUObjectDeleteListener` of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenerOffsets {
+    /// FUObjectArray::UObjectCreateListeners
+    pub create_listeners: usize,
+    /// FUObjectArray::UObjectDeleteListeners
+    pub delete_listeners: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerKind {
+    /// `FUObjectCreateListener::NotifyUObjectCreated`, called after an object is given an index
+    Create,
+    /// `FUObjectDeleteListener::NotifyUObjectDeleted`, called before an object's index is freed
+    Delete,
+}
+
+/// Called with the address of the `UObjectBase` and its index. Delete notifications may come
+/// from the garbage collector's worker threads. Must not panic.
+pub type ListenerCallback = dyn Fn(usize, i32) + Send + Sync;
+
+/// Both listener interfaces share a layout:
+/// ```cpp
+/// virtual ~FUObjectCreateListener();
+/// virtual void NotifyUObjectCreated(const UObjectBase* Object, int32 Index) = 0;
+/// virtual void OnUObjectArrayShutdown() = 0;
+/// virtual SIZE_T GetAllocatedSize() const; // UE 4.25+
+/// ```
+/// Engines lacking trailing methods never call them so a single vtable fits all versions.
+#[repr(C)]
+struct ShimVTable {
+    vec_del_dtor: unsafe extern "system" fn(this: *mut Shim, flags: u32) -> *mut Shim,
+    notify: unsafe extern "system" fn(this: &Shim, object: *const c_void, index: i32),
+    on_uobject_array_shutdown: unsafe extern "system" fn(this: &Shim),
+    get_allocated_size: unsafe extern "system" fn(this: &Shim) -> usize,
+}
+
+static SHIM_VTABLE: ShimVTable = ShimVTable {
+    vec_del_dtor: shim_vec_del_dtor,
+    notify: shim_notify,
+    on_uobject_array_shutdown: shim_on_uobject_array_shutdown,
+    get_allocated_size: shim_get_allocated_size,
+};
+
+/// The native listener object handed to the engine
+#[repr(C)]
+struct Shim {
+    vtable: *const ShimVTable,
+    /// The engine array this listener is in
+    listeners: *mut TArray<*const Shim>,
+    registered: AtomicBool,
+    callback: Box<ListenerCallback>,
+}
+impl Shim {
+    /// Remove from the engine array if still registered
+    unsafe fn unregister(&self) {
+        if !self.registered.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        // the array is the engine's, only borrow it as owned to modify it in place
+        let listeners = &mut *(self.listeners as *mut TArrayOwned<*const Shim>);
+        if let Some(index) = listeners.iter().position(|l| std::ptr::eq(*l, self)) {
+            listeners.swap_remove(index);
+        }
+    }
+}
+
+// the engine never deletes listeners, they are owned by whoever registered them
+unsafe extern "system" fn shim_vec_del_dtor(this: *mut Shim, _flags: u32) -> *mut Shim {
+    this
+}
+unsafe extern "system" fn shim_notify(this: &Shim, object: *const c_void, index: i32) {
+    (this.callback)(object as usize, index);
+}
+// the engine expects listeners to remove themselves when the object array shuts down
+unsafe extern "system" fn shim_on_uobject_array_shutdown(this: &Shim) {
+    this.unregister();
+}
+unsafe extern "system" fn shim_get_allocated_size(_this: &Shim) -> usize {
+    0
+}
+
+/// A listener registered with `GUObjectArray`. Dropping it unregisters it.
+pub struct ObjectListener {
+    shim: Box<Shim>,
+}
+impl ObjectListener {
+    /// Register `callback` to be notified of objects being created or deleted.
+    ///
+    /// # Safety
+    /// `guobject_array` must be the address of `GUObjectArray` in the current process (see
+    /// [`GUObjectArray`](crate::resolvers::unreal::guobject_array::GUObjectArray)) with
+    /// `offsets` matching its layout. Must be called on the game thread, as must dropping the
+    /// listener. The delete listener array is also guarded by `UObjectDeleteListenersCritical`
+    /// which is not taken, so avoid registering while the garbage collector is running. The
+    /// array may grow which allocates through [`gmalloc`](super::gmalloc), so
+    /// [`set_gmalloc`](super::set_gmalloc) must have been called.
+    pub unsafe fn register(
+        guobject_array: usize,
+        offsets: ListenerOffsets,
+        kind: ListenerKind,
+        callback: impl Fn(usize, i32) + Send + Sync + 'static,
+    ) -> Self {
+        let offset = match kind {
+            ListenerKind::Create => offsets.create_listeners,
+            ListenerKind::Delete => offsets.delete_listeners,
+        };
+        let listeners = (guobject_array + offset) as *mut TArray<*const Shim>;
+        let shim = Box::new(Shim {
+            vtable: &SHIM_VTABLE,
+            listeners,
+            registered: AtomicBool::new(true),
+            callback: Box::new(callback),
+        });
+        (*(listeners as *mut TArrayOwned<*const Shim>)).push(&*shim as *const Shim);
+        Self { shim }
+    }
+    /// Whether the listener is still registered. The engine unregisters all listeners when it
+    /// shuts down the object array.
+    pub fn is_registered(&self) -> bool {
+        self.shim.registered.load(Ordering::Acquire)
+    }
+}
+impl Drop for ObjectListener {
+    fn drop(&mut self) {
+        unsafe { self.shim.unregister() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn test_listener_shims() {
+        // stand in for FUObjectArray with room for the listeners so nothing is allocated
+        let mut object_array = vec![0usize; 0x100 / 8];
+        let mut create = Vec::<*const Shim>::with_capacity(4);
+        let mut delete = Vec::<*const Shim>::with_capacity(4);
+        let offsets = ListenerOffsets {
+            create_listeners: 0xe0,
+            delete_listeners: 0xf0,
+        };
+        let base = object_array.as_mut_ptr() as usize;
+        unsafe {
+            *((base + offsets.create_listeners) as *mut TArray<*const Shim>) =
+                TArray::from_raw_parts(create.as_mut_ptr(), 0, 4);
+            *((base + offsets.delete_listeners) as *mut TArray<*const Shim>) =
+                TArray::from_raw_parts(delete.as_mut_ptr(), 0, 4);
+        }
+        let array = |offset: usize| unsafe { &*((base + offset) as *const TArray<*const Shim>) };
+
+        let created = Arc::new(Mutex::new(vec![]));
+        let listener = unsafe {
+            let created = created.clone();
+            ObjectListener::register(base, offsets, ListenerKind::Create, move |object, index| {
+                created.lock().unwrap().push((object, index))
+            })
+        };
+        let other =
+            unsafe { ObjectListener::register(base, offsets, ListenerKind::Create, |_, _| {}) };
+        assert_eq!(array(offsets.create_listeners).len(), 2);
+        assert!(array(offsets.delete_listeners).is_empty());
+
+        // call through the vtable the way the engine does
+        unsafe {
+            let shim = &*array(offsets.create_listeners).as_slice()[0];
+            ((*shim.vtable).notify)(shim, 0x1234 as *const c_void, 7);
+        }
+        assert_eq!(*created.lock().unwrap(), [(0x1234, 7)]);
+
+        unsafe {
+            let shim = &*array(offsets.create_listeners).as_slice()[0];
+            ((*shim.vtable).on_uobject_array_shutdown)(shim);
+        }
+        assert!(!listener.is_registered());
+        assert_eq!(
+            array(offsets.create_listeners).as_slice(),
+            [&*other.shim as *const Shim]
+        );
+
+        drop(listener);
+        drop(other);
+        assert!(array(offsets.create_listeners).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! Read-only access to the live localization table of `FTextLocalizationManager`.
+//!
+//! ```text
+//! TMap<FTextId, FDisplayStringEntry> DisplayStringLookupTable
+//!   FTextId (0x20)
+//!     0x00 FTextKey Namespace (const TCHAR* StrPtr, uint32 StrHash)
+//!     0x10 FTextKey Key
+//!   FDisplayStringEntry
+//!     0x00 TSharedRef<FString> DisplayString
+//!     ...  FString LocResID (WITH_EDITORONLY_DATA), uint32 SourceStringHash
+//! ```
+//!
+//! The table holds every string loaded from localization resources (`.locres`) for the current
+//! culture. Its offset within the manager differs between engine versions so readers take the
+//! address of the map itself.
+
+use super::{
+    containers::FString,
+    read::ReadMemory,
+    set::{read_map, ScriptMapLayout},
+};
+use crate::MemoryAccessError;
+
+/// Longest `FTextKey` string read before giving up on finding its terminator
+const MAX_KEY_LEN: usize = 0x1000;
+
+/// Layout of `DisplayStringLookupTable` pairs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalizationTableLayout {
+    pub map: ScriptMapLayout,
+    /// Offset of the key within `FTextId`
+    pub key: usize,
+}
+impl Default for LocalizationTableLayout {
+    /// FTextKey as string pointer and hash (UE 4.20 to 5.0), shipping `FDisplayStringEntry`
+    fn default() -> Self {
+        Self {
+            map: ScriptMapLayout::new(0x20, 8, 0x18, 8),
+            key: 0x10,
+        }
+    }
+}
+
+/// One display string of the live table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedString {
+    pub namespace: String,
+    pub key: String,
+    pub display_string: String,
+}
+
+/// Read all entries of the `DisplayStringLookupTable` at `address`
+pub fn read_localization_table<R: ReadMemory>(
+    mem: &R,
+    address: usize,
+    layout: LocalizationTableLayout,
+) -> Result<Vec<LocalizedString>, MemoryAccessError> {
+    read_map(mem, address, layout.map)?
+        .into_iter()
+        .map(|(id, entry)| {
+            Ok(LocalizedString {
+                namespace: read_text_key(mem, id)?,
+                key: read_text_key(mem, id + layout.key)?,
+                display_string: FString::read(mem, mem.read_ptr(entry)?)?,
+            })
+        })
+        .collect()
+}
+
+fn read_text_key<R: ReadMemory>(mem: &R, address: usize) -> Result<String, MemoryAccessError> {
+    let string = mem.read_ptr(address)?;
+    let mut chars = vec![];
+    for i in 0..MAX_KEY_LEN {
+        let mut c = [0; 2];
+        mem.read_bytes(string + i * 2, &mut c)?;
+        match u16::from_le_bytes(c) {
+            0 => return Ok(String::from_utf16_lossy(&chars)),
+            c => chars.push(c),
+        }
+    }
+    Err(MemoryAccessError::MemoryOutOfBoundsError)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ue::CurrentProcess;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain([0]).collect()
+    }
+
+    #[test]
+    fn test_read_localization_table() {
+        let namespace = wide("Menu");
+        let key = wide("Start");
+        let display = wide("Play");
+        let fstring: [u64; 2] = [display.as_ptr() as u64, (5 << 32) | 5];
+
+        let layout = LocalizationTableLayout::default();
+        let mut pair = vec![0u64; layout.map.set.size / 8];
+        pair[0] = namespace.as_ptr() as u64;
+        pair[2] = key.as_ptr() as u64;
+        pair[layout.map.value_offset / 8] = fstring.as_ptr() as u64;
+
+        let mut map = [0u8; 0x50];
+        map[0..8].copy_from_slice(&(pair.as_ptr() as u64).to_le_bytes());
+        map[8..12].copy_from_slice(&1i32.to_le_bytes());
+        map[0x10] = 1;
+        map[0x28..0x2c].copy_from_slice(&1i32.to_le_bytes());
+
+        let mem = unsafe { CurrentProcess::new() };
+        let entries = read_localization_table(&mem, map.as_ptr() as usize, layout).unwrap();
+        assert_eq!(
+            entries,
+            [LocalizedString {
+                namespace: "Menu".into(),
+                key: "Start".into(),
+                display_string: "Play".into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Access to the engine allocator so containers handed to or received from the engine can be
+//! allocated and freed with the same allocator the engine uses.
+
+use std::ffi::c_void;
+
+use super::global::ResolvedGlobal;
+use crate::resolvers::unreal::gmalloc::GMalloc;
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct FMalloc {
+    pub(crate) vtable: *const FMallocVTable,
+}
+unsafe impl Sync for FMalloc {}
+unsafe impl Send for FMalloc {}
+impl FMalloc {
+    pub fn malloc(&self, count: usize, alignment: u32) -> *mut c_void {
+        unsafe { ((*self.vtable).malloc)(self, count, alignment) }
+    }
+    /// # Safety
+    ///
+    /// `original` must be null or an allocation of this allocator which isn't used afterwards
+    pub unsafe fn realloc(
+        &self,
+        original: *mut c_void,
+        count: usize,
+        alignment: u32,
+    ) -> *mut c_void {
+        ((*self.vtable).realloc)(self, original, count, alignment)
+    }
+    /// # Safety
+    ///
+    /// `original` must be null or an allocation of this allocator which isn't used afterwards
+    pub unsafe fn free(&self, original: *mut c_void) {
+        ((*self.vtable).free)(self, original)
+    }
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub struct FMallocVTable {
+    pub __vec_del_dtor: *const (),
+    pub exec: *const (),
+    pub malloc:
+        unsafe extern "system" fn(this: &FMalloc, count: usize, alignment: u32) -> *mut c_void,
+    pub try_malloc:
+        unsafe extern "system" fn(this: &FMalloc, count: usize, alignment: u32) -> *mut c_void,
+    pub realloc: unsafe extern "system" fn(
+        this: &FMalloc,
+        original: *mut c_void,
+        count: usize,
+        alignment: u32,
+    ) -> *mut c_void,
+    pub try_realloc: unsafe extern "system" fn(
+        this: &FMalloc,
+        original: *mut c_void,
+        count: usize,
+        alignment: u32,
+    ) -> *mut c_void,
+    pub free: unsafe extern "system" fn(this: &FMalloc, original: *mut c_void),
+    pub quantize_size: *const (),
+    pub get_allocation_size: *const (),
+    pub trim: *const (),
+    pub setup_tls_caches_on_current_thread: *const (),
+    pub clear_and_disable_tlscaches_on_current_thread: *const (),
+    pub initialize_stats_metadata: *const (),
+    pub update_stats: *const (),
+    pub get_allocator_stats: *const (),
+    pub dump_allocator_stats: *const (),
+    pub is_internally_thread_safe: *const (),
+    pub validate_heap: *const (),
+    pub get_descriptive_name: *const (),
+}
+
+/// Address of the `FMalloc* GMalloc` global
+static GMALLOC: ResolvedGlobal<usize> = ResolvedGlobal::new("GMalloc");
+
+/// Use the resolved `GMalloc` global for all engine allocations made by this crate.
+///
+/// Panics if a different address has already been set.
+///
+/// # Safety
+/// `gmalloc` must have been resolved from the image of the current process.
+pub unsafe fn set_gmalloc(gmalloc: &GMalloc) {
+    if let Err(address) = GMALLOC.set(gmalloc.0) {
+        assert_eq!(address, *GMALLOC.get(), "GMalloc has already been set");
+    }
+}
+
+/// The engine allocator. The global is read on every call as the engine only creates it during
+/// startup.
+///
+/// Panics if [`set_gmalloc`] has not been called or the engine has not created its allocator yet.
+pub fn gmalloc() -> &'static FMalloc {
+    let global = *GMALLOC.get() as *const *const FMalloc;
+    unsafe {
+        (*global)
+            .as_ref()
+            .expect("GMalloc has not been initialized by the engine")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{alloc::Layout, sync::Once};
+
+    use super::*;
+
+    // allocations are prefixed with their size so they can be freed knowing only the pointer
+    const HEADER: usize = 16;
+
+    unsafe extern "system" fn test_malloc(_: &FMalloc, count: usize, _: u32) -> *mut c_void {
+        let ptr = std::alloc::alloc(Layout::from_size_align(count + HEADER, HEADER).unwrap());
+        *(ptr as *mut usize) = count;
+        ptr.add(HEADER) as *mut c_void
+    }
+    unsafe extern "system" fn test_realloc(
+        this: &FMalloc,
+        original: *mut c_void,
+        count: usize,
+        alignment: u32,
+    ) -> *mut c_void {
+        let new = test_malloc(this, count, alignment);
+        if !original.is_null() {
+            let size = *((original as *mut u8).sub(HEADER) as *const usize);
+            std::ptr::copy_nonoverlapping(original as *const u8, new as *mut u8, size.min(count));
+            test_free(this, original);
+        }
+        new
+    }
+    unsafe extern "system" fn test_free(_: &FMalloc, original: *mut c_void) {
+        let ptr = (original as *mut u8).sub(HEADER);
+        let size = *(ptr as *const usize);
+        std::alloc::dealloc(ptr, Layout::from_size_align(size + HEADER, HEADER).unwrap());
+    }
+
+    /// Install an allocator backed by the global Rust allocator for tests of engine owned
+    /// allocations. Tests share the process so it is only set once.
+    pub(crate) fn set_test_gmalloc() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            let null = std::ptr::null();
+            let vtable: &'static FMallocVTable = Box::leak(Box::new(FMallocVTable {
+                __vec_del_dtor: null,
+                exec: null,
+                malloc: test_malloc,
+                try_malloc: test_malloc,
+                realloc: test_realloc,
+                try_realloc: test_realloc,
+                free: test_free,
+                quantize_size: null,
+                get_allocation_size: null,
+                trim: null,
+                setup_tls_caches_on_current_thread: null,
+                clear_and_disable_tlscaches_on_current_thread: null,
+                initialize_stats_metadata: null,
+                update_stats: null,
+                get_allocator_stats: null,
+                dump_allocator_stats: null,
+                is_internally_thread_safe: null,
+                validate_heap: null,
+                get_descriptive_name: null,
+            }));
+            let malloc: &'static FMalloc = Box::leak(Box::new(FMalloc { vtable }));
+            let global: &'static *const FMalloc = Box::leak(Box::new(malloc as *const _));
+            unsafe { set_gmalloc(&GMalloc(global as *const _ as usize)) };
+        });
+    }
+}
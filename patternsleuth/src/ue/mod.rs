@@ -0,0 +1,44 @@
+//! In-memory representations of engine types
+
+pub mod asset_registry;
+pub mod build;
+pub mod containers;
+pub mod delegates;
+pub mod global;
+pub mod listeners;
+pub mod localization;
+pub mod malloc;
+pub mod modules;
+pub mod object;
+pub mod object_array;
+pub mod object_ptr;
+pub mod read;
+pub mod set;
+pub mod snapshot;
+pub mod text;
+pub mod widget;
+pub mod world;
+
+pub use asset_registry::{AssetData, AssetRegistry};
+pub use build::{BuildConfig, Configuration};
+pub use containers::{
+    decode_utf16, decode_utf16_lossy, FString, FStringOwned, TArray, TArrayOwned,
+};
+pub use delegates::{DelegateLayout, MulticastDelegateBinding};
+pub use global::ResolvedGlobal;
+pub use listeners::{ListenerKind, ListenerOffsets, ObjectListener};
+pub use localization::{read_localization_table, LocalizationTableLayout, LocalizedString};
+pub use malloc::{gmalloc, set_gmalloc, FMalloc};
+pub use modules::{read_modules, ModuleInfo, ModuleManagerLayout};
+pub use object::{
+    ObjectLayout, ObjectRef, Objects, Property, UClassRef, UEnumRef, UFieldRef, UFunctionRef,
+    UObjectRef, UPackageRef, UScriptStructRef, UStructRef,
+};
+pub use object_array::{ObjectArray, ObjectItemLayout};
+pub use object_ptr::{FName, FSoftObjectPath, FWeakObjectPtr, TSoftObjectPtr};
+pub use read::{BatchedMemory, CurrentProcess, ReadMemory};
+pub use set::{TMap, TSet};
+pub use snapshot::{ObjectDiff, ObjectSnapshot};
+pub use text::FText;
+pub use widget::{WidgetNode, Widgets};
+pub use world::World;
@@ -0,0 +1,149 @@
+//! Read-only access to the module registry of `FModuleManager`.
+//!
+//! ```text
+//! FModuleManager
+//!   0x00 vtable (FSelfRegisteringExec)
+//!   0x08 TMap<FName, TSharedRef<FModuleInfo>> Modules
+//! FModuleInfo
+//!   0x00 FString OriginalFilename
+//!   0x10 FString Filename
+//!   0x20 void* Handle
+//!   0x28 TUniquePtr<IModuleInterface> Module
+//! ```
+//!
+//! Modules of monolithic builds are statically linked so have no file or handle, only modular
+//! builds map a module to the address range of its DLL.
+
+use std::ops::Range;
+
+use super::{
+    build::BuildConfig,
+    containers::FString,
+    object_ptr::FName,
+    read::ReadMemory,
+    set::{read_map, ScriptMapLayout},
+};
+use crate::MemoryAccessError;
+
+/// Field offsets of `FModuleManager` and `FModuleInfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleManagerLayout {
+    /// FModuleManager::Modules
+    pub modules: usize,
+    /// FModuleInfo::Filename
+    pub filename: usize,
+    /// FModuleInfo::Handle
+    pub handle: usize,
+    /// FModuleInfo::Module
+    pub module: usize,
+}
+impl Default for ModuleManagerLayout {
+    /// UE 4.20 onwards
+    fn default() -> Self {
+        Self {
+            modules: 0x8,
+            filename: 0x10,
+            handle: 0x20,
+            module: 0x28,
+        }
+    }
+}
+
+/// A module known to the module manager, loaded or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInfo {
+    pub name: Option<String>,
+    /// Path of the module's DLL, empty for statically linked modules
+    pub filename: String,
+    /// Address range the DLL is mapped at
+    pub range: Option<Range<usize>>,
+    /// Whether the module has been started (`IModuleInterface` created)
+    pub loaded: bool,
+}
+
+/// Read all modules registered with the `FModuleManager` at `module_manager`. `name` converts
+/// FNames to strings.
+pub fn read_modules<R: ReadMemory>(
+    mem: &R,
+    module_manager: usize,
+    build: BuildConfig,
+    layout: ModuleManagerLayout,
+    name: impl Fn(FName) -> Option<String>,
+) -> Result<Vec<ModuleInfo>, MemoryAccessError> {
+    let map = ScriptMapLayout::new(build.fname_size(), 4, 0x10, 8);
+    read_map(mem, module_manager + layout.modules, map)?
+        .into_iter()
+        .map(|(key, value)| {
+            let info = mem.read_ptr(value)?;
+            let handle = mem.read_ptr(info + layout.handle)?;
+            Ok(ModuleInfo {
+                name: name(FName::read_for(mem, key, build)?),
+                filename: FString::read(mem, info + layout.filename)?,
+                range: if handle == 0 {
+                    None
+                } else {
+                    Some(handle..handle + image_size(mem, handle)?)
+                },
+                loaded: mem.read_ptr(info + layout.module)? != 0,
+            })
+        })
+        .collect()
+}
+
+/// `IMAGE_OPTIONAL_HEADER64::SizeOfImage` of the PE image mapped at `base`
+fn image_size<R: ReadMemory>(mem: &R, base: usize) -> Result<usize, MemoryAccessError> {
+    // IMAGE_DOS_HEADER::e_lfanew
+    let nt_headers = base + mem.read_u32(base + 0x3c)? as usize;
+    // Signature (4) + IMAGE_FILE_HEADER (0x14) + offset of SizeOfImage (0x38)
+    Ok(mem.read_u32(nt_headers + 0x50)? as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ue::CurrentProcess;
+
+    #[test]
+    fn test_read_modules() {
+        let mut image = vec![0u8; 0x200];
+        image[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        image[0xd0..0xd4].copy_from_slice(&0x5000u32.to_le_bytes());
+        let base = image.as_ptr() as usize;
+
+        let filename = "Game.dll\0".encode_utf16().collect::<Vec<_>>();
+        let mut info = [0u64; 6];
+        info[2] = filename.as_ptr() as u64;
+        info[3] = (9 << 32) | 9;
+        info[4] = base as u64;
+        info[5] = 1;
+
+        // Modules: one pair of FName Engine (index 7) and a shared reference to the info
+        let pair: [u64; 4] = [7, info.as_ptr() as u64, 0, 0];
+        let layout = ModuleManagerLayout::default();
+        let mut manager = [0u8; 0x58];
+        let map = layout.modules;
+        manager[map..map + 8].copy_from_slice(&(pair.as_ptr() as u64).to_le_bytes());
+        manager[map + 8..map + 12].copy_from_slice(&1i32.to_le_bytes());
+        manager[map + 0x10] = 1;
+        manager[map + 0x28..map + 0x2c].copy_from_slice(&1i32.to_le_bytes());
+
+        let mem = unsafe { CurrentProcess::new() };
+        let modules = read_modules(
+            &mem,
+            manager.as_ptr() as usize,
+            BuildConfig::default(),
+            layout,
+            |name| (name.comparison_index == 7).then(|| "Engine".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            modules,
+            [ModuleInfo {
+                name: Some("Engine".into()),
+                filename: "Game.dll".into(),
+                range: Some(base..base + 0x5000),
+                loaded: true,
+            }]
+        );
+    }
+}
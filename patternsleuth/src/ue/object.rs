@@ -0,0 +1,447 @@
+//! Reading reflection data common to all objects (class, name, outer and super struct chains)
+
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{build::BuildConfig, object_array::ObjectArray, object_ptr::FName, read::ReadMemory};
+use crate::MemoryAccessError;
+
+/// EObjectFlags::RF_ClassDefaultObject | EObjectFlags::RF_ArchetypeObject
+const TEMPLATE_FLAGS: u32 = 0x10 | 0x20;
+
+/// Field offsets of UObjectBase, UStruct, FField and FProperty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectLayout {
+    /// Build the offsets were derived for, also deciding how FNames are read
+    pub build: BuildConfig,
+    pub object_flags: usize,
+    pub class: usize,
+    pub name: usize,
+    pub outer: usize,
+    pub super_struct: usize,
+    pub child_properties: usize,
+    pub properties_size: usize,
+    pub field_class: usize,
+    pub field_next: usize,
+    pub field_name: usize,
+    pub property_array_dim: usize,
+    pub property_element_size: usize,
+    pub property_offset: usize,
+}
+impl ObjectLayout {
+    /// Layout for the given sizeof(FName) (see
+    /// [`FNameSize`](crate::resolvers::unreal::layout::FNameSize)) of a game build
+    pub fn new(fname_size: usize) -> Self {
+        Self::for_build(BuildConfig::new().case_preserving_names(fname_size == 12))
+    }
+    /// Layout for `build` (see
+    /// [`EngineBuildConfig`](crate::resolvers::unreal::layout::EngineBuildConfig))
+    pub fn for_build(build: BuildConfig) -> Self {
+        let align8 = |n: usize| (n + 7) & !7;
+        let fname_size = build.fname_size();
+
+        let outer = align8(0x18 + fname_size);
+        // UField::Next followed by FStructBaseChain, which editor builds omit in favour of
+        // walking the super struct chain
+        let struct_base_chain = if build.with_editor { 0 } else { 0x10 };
+        let super_struct = outer + 8 + 8 + struct_base_chain;
+
+        // FField: vtable, ClassPrivate, FFieldVariant Owner, Next, NamePrivate, FlagsPrivate,
+        // and MetaDataMap in editor builds
+        let field_name = 0x28;
+        let property_array_dim =
+            align8(field_name + fname_size + 4) + if build.with_editor { 8 } else { 0 };
+        Self {
+            build,
+            object_flags: 8,
+            class: 0x10,
+            name: 0x18,
+            outer,
+            super_struct,
+            child_properties: super_struct + 0x10,
+            properties_size: super_struct + 0x18,
+            field_class: 8,
+            field_next: 0x20,
+            field_name,
+            property_array_dim,
+            property_element_size: property_array_dim + 4,
+            // PropertyFlags, RepIndex, BlueprintReplicationCondition
+            property_offset: property_array_dim + 0x14,
+        }
+    }
+    /// Use the probed offsets of UObjectBase::ObjectFlags and UObjectBase::InternalIndex (see
+    /// [`UObjectBaseLayout`](crate::resolvers::unreal::layout::UObjectBaseLayout)), moving the
+    /// fields following them along
+    pub fn object_base(self, object_flags: usize, internal_index: usize) -> Self {
+        let class = (internal_index + 4 + 7) & !7;
+        let moved = |offset: usize| offset + class - self.class;
+        Self {
+            object_flags,
+            class,
+            name: moved(self.name),
+            outer: moved(self.outer),
+            super_struct: moved(self.super_struct),
+            child_properties: moved(self.child_properties),
+            properties_size: moved(self.properties_size),
+            ..self
+        }
+    }
+}
+impl Default for ObjectLayout {
+    fn default() -> Self {
+        Self::for_build(BuildConfig::default())
+    }
+}
+
+/// Address of an object known to be an instance of a reflection class, obtained through
+/// [`Objects::cast`]
+pub trait ObjectRef: Copy {
+    /// Class name without prefix, e.g. `Struct` for UStruct
+    const CLASS: &'static str;
+    fn from_address(address: usize) -> Self;
+    fn address(&self) -> usize;
+}
+macro_rules! object_refs {
+    ($($name:ident => $class:literal,)*) => {$(
+        #[doc = concat!("Address of a U", $class)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(pub usize);
+        impl ObjectRef for $name {
+            const CLASS: &'static str = $class;
+            fn from_address(address: usize) -> Self {
+                Self(address)
+            }
+            fn address(&self) -> usize {
+                self.0
+            }
+        }
+    )*};
+}
+object_refs! {
+    UObjectRef => "Object",
+    UFieldRef => "Field",
+    UStructRef => "Struct",
+    UClassRef => "Class",
+    UScriptStructRef => "ScriptStruct",
+    UFunctionRef => "Function",
+    UEnumRef => "Enum",
+    UPackageRef => "Package",
+}
+
+/// An FProperty of a struct
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    /// Address of the FProperty
+    pub address: usize,
+    pub name: Option<String>,
+    /// Name of the FFieldClass, e.g. `IntProperty`
+    pub class: Option<String>,
+    pub offset: i32,
+    pub element_size: i32,
+    pub array_dim: i32,
+}
+
+/// Object reflection reader over an [`ObjectArray`]
+pub struct Objects<'objects, 'mem, R, N> {
+    array: &'objects ObjectArray<'mem, R>,
+    layout: ObjectLayout,
+    name: N,
+    /// (class, class name to match) -> whether the class derives from it
+    is_a_cache: RefCell<HashMap<(usize, String), bool>>,
+    /// (struct, property name) -> offset of the property declared by it or a super struct
+    property_cache: RefCell<HashMap<(usize, String), Option<i32>>>,
+}
+impl<'objects, 'mem, R, N> Objects<'objects, 'mem, R, N>
+where
+    R: ReadMemory,
+    N: Fn(FName) -> Option<String>,
+{
+    /// `name` converts FNames to strings
+    pub fn new(array: &'objects ObjectArray<'mem, R>, layout: ObjectLayout, name: N) -> Self {
+        Self {
+            array,
+            layout,
+            name,
+            is_a_cache: Default::default(),
+            property_cache: Default::default(),
+        }
+    }
+    pub fn array(&self) -> &'objects ObjectArray<'mem, R> {
+        self.array
+    }
+    pub fn layout(&self) -> &ObjectLayout {
+        &self.layout
+    }
+    pub fn mem(&self) -> &'mem R {
+        self.array.mem()
+    }
+    pub fn fname(&self, address: usize) -> Result<Option<String>, MemoryAccessError> {
+        Ok((self.name)(FName::read_for(
+            self.mem(),
+            address,
+            self.layout.build,
+        )?))
+    }
+
+    pub fn outer(&self, object: usize) -> Result<usize, MemoryAccessError> {
+        self.mem().read_ptr(object + self.layout.outer)
+    }
+    pub fn class(&self, object: usize) -> Result<usize, MemoryAccessError> {
+        self.mem().read_ptr(object + self.layout.class)
+    }
+    pub fn super_struct(&self, object: usize) -> Result<usize, MemoryAccessError> {
+        self.mem().read_ptr(object + self.layout.super_struct)
+    }
+    pub fn name(&self, object: usize) -> Result<Option<String>, MemoryAccessError> {
+        self.fname(object + self.layout.name)
+    }
+    /// Path name made of the names of the outer chain the way
+    /// `UObjectBaseUtility::GetPathName` builds it: names are joined with `.`, except for `:`
+    /// before subobjects of an object directly in a package, e.g.
+    /// `/Game/Maps/X.X:PersistentLevel.Actor_1` or `/Script/Engine.Actor`
+    pub fn path_name(&self, object: usize) -> Result<Option<String>, MemoryAccessError> {
+        let mut chain = vec![];
+        let mut next = object;
+        while next != 0 {
+            chain.push(next);
+            next = self.outer(next)?;
+        }
+        chain.reverse();
+
+        let mut path = String::new();
+        for (i, object) in chain.iter().enumerate() {
+            let Some(name) = self.name(*object)? else {
+                return Ok(None);
+            };
+            if i > 0 {
+                let subobject =
+                    i > 1 && !self.is_package(chain[i - 1])? && self.is_package(chain[i - 2])?;
+                path.push(if subobject { ':' } else { '.' });
+            }
+            path.push_str(&name);
+        }
+        Ok(Some(path))
+    }
+    /// Class name followed by the path name as `UObjectBaseUtility::GetFullName` builds it,
+    /// e.g. `StaticMeshActor /Game/Maps/X.X:PersistentLevel.StaticMeshActor_1`
+    pub fn full_name(&self, object: usize) -> Result<Option<String>, MemoryAccessError> {
+        let (Some(class), Some(path)) = (self.name(self.class(object)?)?, self.path_name(object)?)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(format!("{class} {path}")))
+    }
+    fn is_package(&self, object: usize) -> Result<bool, MemoryAccessError> {
+        Ok(self.name(self.class(object)?)?.as_deref() == Some("Package"))
+    }
+    pub fn is_template(&self, object: usize) -> Result<bool, MemoryAccessError> {
+        Ok(self.mem().read_u32(object + self.layout.object_flags)? & TEMPLATE_FLAGS != 0)
+    }
+
+    /// Whether `object` is an instance of the class named `class_name` or one of its subclasses
+    pub fn is_a(&self, object: usize, class_name: &str) -> Result<bool, MemoryAccessError> {
+        let class = self.class(object)?;
+        let key = (class, class_name.to_string());
+        if let Some(is_a) = self.is_a_cache.borrow().get(&key) {
+            return Ok(*is_a);
+        }
+
+        let mut is_a = false;
+        let mut next = class;
+        while next != 0 {
+            if self.name(next)?.as_deref() == Some(class_name) {
+                is_a = true;
+                break;
+            }
+            next = self.super_struct(next)?;
+        }
+        self.is_a_cache.borrow_mut().insert(key, is_a);
+        Ok(is_a)
+    }
+
+    /// `object` as a `T` if its class is `T`'s class or derives from it, e.g.
+    /// `objects.cast::<UStructRef>(function)` for a UFunction
+    pub fn cast<T: ObjectRef>(&self, object: usize) -> Result<Option<T>, MemoryAccessError> {
+        Ok(self
+            .is_a(object, T::CLASS)?
+            .then(|| T::from_address(object)))
+    }
+
+    /// UStruct::PropertiesSize
+    pub fn properties_size(&self, object: usize) -> Result<i32, MemoryAccessError> {
+        self.mem().read_i32(object + self.layout.properties_size)
+    }
+    /// Properties declared by the struct `object` itself (not inherited from its super struct)
+    /// in declaration order. Assumes FProperty based reflection (UE 4.25+).
+    pub fn properties(&self, object: usize) -> Result<Vec<Property>, MemoryAccessError> {
+        let mem = self.mem();
+        let mut properties = vec![];
+        let mut next = mem.read_ptr(object + self.layout.child_properties)?;
+        while next != 0 {
+            // FFieldClass starts with its FName
+            let class = mem.read_ptr(next + self.layout.field_class)?;
+            properties.push(Property {
+                address: next,
+                name: self.fname(next + self.layout.field_name)?,
+                class: self.fname(class)?,
+                offset: mem.read_i32(next + self.layout.property_offset)?,
+                element_size: mem.read_i32(next + self.layout.property_element_size)?,
+                array_dim: mem.read_i32(next + self.layout.property_array_dim)?,
+            });
+            next = mem.read_ptr(next + self.layout.field_next)?;
+        }
+        Ok(properties)
+    }
+
+    /// Offset of the property `name` declared by the struct `object` or one of its super
+    /// structs
+    pub fn property_offset(
+        &self,
+        object: usize,
+        name: &str,
+    ) -> Result<Option<i32>, MemoryAccessError> {
+        let key = (object, name.to_string());
+        if let Some(offset) = self.property_cache.borrow().get(&key) {
+            return Ok(*offset);
+        }
+
+        let mut offset = None;
+        let mut next = object;
+        while next != 0 && offset.is_none() {
+            offset = self
+                .properties(next)?
+                .into_iter()
+                .find(|p| p.name.as_deref() == Some(name))
+                .map(|p| p.offset);
+            next = self.super_struct(next)?;
+        }
+        self.property_cache.borrow_mut().insert(key, offset);
+        Ok(offset)
+    }
+    /// Value of the object pointer property `name` of `object`, `None` if its class has no such
+    /// property or it is null
+    pub fn object_property(
+        &self,
+        object: usize,
+        name: &str,
+    ) -> Result<Option<usize>, MemoryAccessError> {
+        let Some(offset) = self.property_offset(self.class(object)?, name)? else {
+            return Ok(None);
+        };
+        let value = self.mem().read_ptr(object + offset as usize)?;
+        Ok((value != 0).then_some(value))
+    }
+
+    /// Non null elements of the `TArray` of object pointers property `name` of `object`
+    pub fn object_array_property(
+        &self,
+        object: usize,
+        name: &str,
+    ) -> Result<Vec<usize>, MemoryAccessError> {
+        let Some(offset) = self.property_offset(self.class(object)?, name)? else {
+            return Ok(vec![]);
+        };
+        let array = object + offset as usize;
+        let data = self.mem().read_ptr(array)?;
+        let num = self.mem().read_i32(array + 8)?;
+        let mut elements = vec![];
+        for i in 0..num.max(0) as usize {
+            match self.mem().read_ptr(data + i * 8)? {
+                0 => {}
+                element => elements.push(element),
+            }
+        }
+        Ok(elements)
+    }
+
+    /// Live (non template) objects matching `filter`. Objects which can't be read are skipped.
+    pub fn find(
+        &self,
+        mut filter: impl FnMut(usize) -> Result<bool, MemoryAccessError>,
+    ) -> Vec<usize> {
+        self.array
+            .iter()
+            .filter(|(_, item)| !item.is_unreachable())
+            .map(|(_, item)| item.object)
+            .filter(|object| {
+                matches!(self.is_template(*object), Ok(false))
+                    && filter(*object).unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ue::read::CurrentProcess;
+
+    #[test]
+    fn test_object_base() {
+        let layout = ObjectLayout::new(8);
+        assert_eq!(layout.object_base(8, 0xc), layout);
+
+        let moved = layout.object_base(0xc, 0x10);
+        assert_eq!((moved.object_flags, moved.class), (0xc, 0x18));
+        assert_eq!(moved.name, layout.name + 8);
+        assert_eq!(moved.super_struct, layout.super_struct + 8);
+        assert_eq!(moved.field_name, layout.field_name);
+    }
+
+    #[test]
+    fn test_path_name() {
+        // UObjectBase with the default layout: vtable, flags, class, name, outer, followed by
+        // the UStruct fields up to a null SuperStruct
+        let object = |class: usize, name: u64, outer: usize| {
+            let mut object = [0u64; 9];
+            object[2..5].copy_from_slice(&[class as u64, name, outer as u64]);
+            object
+        };
+        let addr = |object: &[u64; 9]| object.as_ptr() as usize;
+        let (package_class, world_class, level_class) =
+            (object(0, 1, 0), object(0, 2, 0), object(0, 3, 0));
+        let package = object(addr(&package_class), 4, 0);
+        let world = object(addr(&world_class), 5, addr(&package));
+        let level = object(addr(&level_class), 6, addr(&world));
+        let actor = object(addr(&level_class), 7, addr(&level));
+
+        let names = [
+            "",
+            "Package",
+            "World",
+            "Level",
+            "/Game/Maps/X",
+            "X",
+            "PersistentLevel",
+            "Actor_1",
+        ];
+        let mem = unsafe { CurrentProcess::new() };
+        let array = ObjectArray::new(&mem, 0, 0x18);
+        let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+            names
+                .get(name.comparison_index as usize)
+                .map(|n| n.to_string())
+        });
+
+        let path = |o: &[u64; 9]| objects.path_name(addr(o)).unwrap();
+        assert_eq!(path(&package).as_deref(), Some("/Game/Maps/X"));
+        assert_eq!(path(&world).as_deref(), Some("/Game/Maps/X.X"));
+        assert_eq!(
+            path(&level).as_deref(),
+            Some("/Game/Maps/X.X:PersistentLevel")
+        );
+        assert_eq!(
+            path(&actor).as_deref(),
+            Some("/Game/Maps/X.X:PersistentLevel.Actor_1")
+        );
+        assert_eq!(
+            objects.full_name(addr(&actor)).unwrap().as_deref(),
+            Some("Level /Game/Maps/X.X:PersistentLevel.Actor_1")
+        );
+
+        assert_eq!(
+            objects.cast::<UPackageRef>(addr(&package)),
+            Ok(Some(UPackageRef(addr(&package))))
+        );
+        assert_eq!(objects.cast::<UPackageRef>(addr(&world)), Ok(None));
+    }
+}
@@ -0,0 +1,200 @@
+//! Reading the chunked `GUObjectArray` in-process or from another process
+
+use super::read::ReadMemory;
+use crate::MemoryAccessError;
+
+/// FUObjectArray::ObjObjects
+const OBJ_OBJECTS: usize = 0x10;
+const OBJECTS: usize = OBJ_OBJECTS;
+const MAX_ELEMENTS: usize = OBJ_OBJECTS + 0x10;
+const NUM_ELEMENTS: usize = OBJ_OBJECTS + 0x14;
+const MAX_CHUNKS: usize = OBJ_OBJECTS + 0x18;
+
+/// EInternalObjectFlags shared by every engine version
+mod flags {
+    pub const CLUSTER_ROOT: i32 = 1 << 24;
+    pub const NATIVE: i32 = 1 << 25;
+    pub const UNREACHABLE: i32 = 1 << 28;
+    pub const ROOT_SET: i32 = 1 << 30;
+    pub const PENDING_CONSTRUCTION: i32 = 1 << 31;
+}
+
+/// FUObjectItem flag storage and semantics, which changed in UE 5.0 and again with incremental
+/// garbage collection in UE 5.4
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObjectItemLayout {
+    /// `int32 Flags`, destroyed objects are marked `PendingKill`
+    #[default]
+    PendingKill,
+    /// `int32 Flags`, `PendingKill` replaced by `Garbage`
+    Garbage,
+    /// `int64 FlagsAndRefCount` with the flags in the upper half, followed by ClusterRootIndex
+    /// and SerialNumber
+    FlagsAndRefCount,
+}
+impl ObjectItemLayout {
+    pub fn for_version(major: u16, minor: u16) -> Self {
+        match (major, minor) {
+            (..=4, _) => Self::PendingKill,
+            (5, ..=3) => Self::Garbage,
+            _ => Self::FlagsAndRefCount,
+        }
+    }
+    /// Offset of the flags within FUObjectItem
+    fn flags(self) -> usize {
+        match self {
+            Self::PendingKill | Self::Garbage => 8,
+            Self::FlagsAndRefCount => 0xc,
+        }
+    }
+    /// Offset of the serial number within FUObjectItem
+    fn serial_number(self) -> usize {
+        match self {
+            Self::PendingKill | Self::Garbage => 0x10,
+            Self::FlagsAndRefCount => 0x14,
+        }
+    }
+    /// Flag of objects marked for destruction
+    fn garbage_flag(self) -> i32 {
+        match self {
+            Self::PendingKill => 1 << 29,
+            Self::Garbage | Self::FlagsAndRefCount => 1 << 21,
+        }
+    }
+}
+
+/// An entry of the object array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectItem {
+    /// Address of the UObject, 0 for free slots
+    pub object: usize,
+    /// EInternalObjectFlags, interpreted according to `layout`
+    pub flags: i32,
+    pub serial_number: i32,
+    pub layout: ObjectItemLayout,
+}
+impl ObjectItem {
+    pub fn is_unreachable(&self) -> bool {
+        self.flags & flags::UNREACHABLE != 0
+    }
+    pub fn is_root_set(&self) -> bool {
+        self.flags & flags::ROOT_SET != 0
+    }
+    /// Marked for destruction (`PendingKill` before UE 5.0, `Garbage` after)
+    pub fn is_garbage(&self) -> bool {
+        self.flags & self.layout.garbage_flag() != 0
+    }
+    pub fn is_native(&self) -> bool {
+        self.flags & flags::NATIVE != 0
+    }
+    pub fn is_cluster_root(&self) -> bool {
+        self.flags & flags::CLUSTER_ROOT != 0
+    }
+    pub fn is_pending_construction(&self) -> bool {
+        self.flags & flags::PENDING_CONSTRUCTION != 0
+    }
+}
+
+pub struct ObjectArray<'mem, R> {
+    mem: &'mem R,
+    address: usize,
+    item_size: usize,
+    item_layout: ObjectItemLayout,
+}
+impl<'mem, R: ReadMemory> ObjectArray<'mem, R> {
+    /// `address` is the address of `GUObjectArray`, `item_size` is sizeof(FUObjectItem) (see
+    /// [`FUObjectItemSize`](crate::resolvers::unreal::layout::FUObjectItemSize))
+    pub fn new(mem: &'mem R, address: usize, item_size: usize) -> Self {
+        Self {
+            mem,
+            address,
+            item_size,
+            item_layout: Default::default(),
+        }
+    }
+    /// Interpret items according to `layout`, [`ObjectItemLayout::PendingKill`] by default
+    pub fn item_layout(mut self, layout: ObjectItemLayout) -> Self {
+        self.item_layout = layout;
+        self
+    }
+    pub fn mem(&self) -> &'mem R {
+        self.mem
+    }
+    pub fn len(&self) -> Result<usize, MemoryAccessError> {
+        Ok(self.mem.read_i32(self.address + NUM_ELEMENTS)?.max(0) as usize)
+    }
+    pub fn is_empty(&self) -> Result<bool, MemoryAccessError> {
+        Ok(self.len()? == 0)
+    }
+    /// Item at `index`, `None` if the index is out of range
+    pub fn item(&self, index: i32) -> Result<Option<ObjectItem>, MemoryAccessError> {
+        if index < 0 || index as usize >= self.len()? {
+            return Ok(None);
+        }
+        let max_elements = self.mem.read_i32(self.address + MAX_ELEMENTS)?;
+        let max_chunks = self.mem.read_i32(self.address + MAX_CHUNKS)?;
+        if max_chunks <= 0 {
+            return Ok(None);
+        }
+        let per_chunk = (max_elements / max_chunks) as usize;
+
+        let chunks = self.mem.read_ptr(self.address + OBJECTS)?;
+        let chunk = self.mem.read_ptr(chunks + index as usize / per_chunk * 8)?;
+        let item = chunk + index as usize % per_chunk * self.item_size;
+        let layout = self.item_layout;
+        Ok(Some(ObjectItem {
+            object: self.mem.read_ptr(item)?,
+            flags: self.mem.read_i32(item + layout.flags())?,
+            serial_number: self.mem.read_i32(item + layout.serial_number())?,
+            layout,
+        }))
+    }
+    /// All allocated objects and their indexes. Unreadable items are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, ObjectItem)> + '_ {
+        (0..self.len().unwrap_or_default() as i32).filter_map(|i| {
+            self.item(i)
+                .ok()
+                .flatten()
+                .filter(|item| item.object != 0)
+                .map(|item| (i, item))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ue::read::CurrentProcess;
+
+    #[test]
+    fn test_item_layouts() {
+        let object = 0x1234u64;
+        // one chunk with a single UE 5.4 item: object, ref count, flags, cluster root index,
+        // serial number
+        let item_flags = (flags::ROOT_SET | ObjectItemLayout::Garbage.garbage_flag()) as u32;
+        let item = [object, 2 | ((item_flags as u64) << 32), 5 << 32];
+        let chunks = [item.as_ptr() as u64];
+        let mut array = [0u8; 0x30];
+        array[0x10..0x18].copy_from_slice(&(chunks.as_ptr() as u64).to_le_bytes());
+        array[0x20..0x24].copy_from_slice(&(64 * 1024i32).to_le_bytes());
+        array[0x24..0x28].copy_from_slice(&1i32.to_le_bytes());
+        array[0x28..0x2c].copy_from_slice(&1i32.to_le_bytes());
+
+        let mem = unsafe { CurrentProcess::new() };
+        let objects = ObjectArray::new(&mem, array.as_ptr() as usize, 0x18)
+            .item_layout(ObjectItemLayout::for_version(5, 4));
+        let item = objects.item(0).unwrap().unwrap();
+        assert_eq!(item.object, object as usize);
+        assert_eq!(item.serial_number, 5);
+        assert!(item.is_root_set());
+        assert!(item.is_garbage());
+        assert!(!item.is_unreachable());
+
+        // the same bits mean something else in UE4
+        let item = ObjectItem {
+            layout: ObjectItemLayout::for_version(4, 27),
+            ..item
+        };
+        assert!(!item.is_garbage());
+    }
+}
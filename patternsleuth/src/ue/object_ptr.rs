@@ -0,0 +1,245 @@
+//! Weak and soft object references which can be followed to live objects through the
+//! [`ObjectArray`]
+
+use super::{build::BuildConfig, containers::FString, object_array::ObjectArray, read::ReadMemory};
+use crate::MemoryAccessError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct FName {
+    pub comparison_index: u32,
+    pub number: u32,
+}
+impl FName {
+    pub fn read<R: ReadMemory>(mem: &R, address: usize) -> Result<Self, MemoryAccessError> {
+        Self::read_for(mem, address, BuildConfig::default())
+    }
+    /// Read an FName of a game built with `build`, skipping the DisplayIndex of case preserving
+    /// names
+    pub fn read_for<R: ReadMemory>(
+        mem: &R,
+        address: usize,
+        build: BuildConfig,
+    ) -> Result<Self, MemoryAccessError> {
+        Ok(Self {
+            comparison_index: mem.read_u32(address)?,
+            number: mem.read_u32(address + build.fname_number())?,
+        })
+    }
+    pub fn is_none(&self) -> bool {
+        self.comparison_index == 0 && self.number == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct FWeakObjectPtr {
+    pub object_index: i32,
+    pub object_serial_number: i32,
+}
+impl FWeakObjectPtr {
+    pub fn read<R: ReadMemory>(mem: &R, address: usize) -> Result<Self, MemoryAccessError> {
+        Ok(Self {
+            object_index: mem.read_i32(address)?,
+            object_serial_number: mem.read_i32(address + 4)?,
+        })
+    }
+    /// Address of the referenced object if it is still alive. Slots reused by another object are
+    /// detected by their serial number.
+    pub fn get<R: ReadMemory>(
+        &self,
+        objects: &ObjectArray<'_, R>,
+    ) -> Result<Option<usize>, MemoryAccessError> {
+        if self.object_serial_number == 0 {
+            return Ok(None);
+        }
+        Ok(objects.item(self.object_index)?.and_then(|item| {
+            (item.serial_number == self.object_serial_number
+                && item.object != 0
+                && !item.is_unreachable())
+            .then_some(item.object)
+        }))
+    }
+}
+
+/// FSoftObjectPath layout which changed in UE 5.1 from a single FName to a package and asset
+/// name pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftObjectPathLayout {
+    /// FName AssetPathName, FString SubPathString
+    AssetPathName,
+    /// FTopLevelAssetPath AssetPath, FString SubPathString
+    TopLevelAssetPath,
+}
+impl SoftObjectPathLayout {
+    pub fn for_version(major: u16, minor: u16) -> Self {
+        if (major, minor) >= (5, 1) {
+            Self::TopLevelAssetPath
+        } else {
+            Self::AssetPathName
+        }
+    }
+    pub fn size(self) -> usize {
+        match self {
+            Self::AssetPathName => 0x18,
+            Self::TopLevelAssetPath => 0x20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FSoftObjectPath {
+    /// Only present for [`SoftObjectPathLayout::TopLevelAssetPath`]
+    pub package_name: Option<FName>,
+    /// Full asset path for [`SoftObjectPathLayout::AssetPathName`], otherwise the asset name
+    /// within the package
+    pub asset_name: FName,
+    pub sub_path: String,
+}
+impl FSoftObjectPath {
+    pub fn read<R: ReadMemory>(
+        mem: &R,
+        address: usize,
+        layout: SoftObjectPathLayout,
+    ) -> Result<Self, MemoryAccessError> {
+        Ok(match layout {
+            SoftObjectPathLayout::AssetPathName => Self {
+                package_name: None,
+                asset_name: FName::read(mem, address)?,
+                sub_path: FString::read(mem, address + 8)?,
+            },
+            SoftObjectPathLayout::TopLevelAssetPath => Self {
+                package_name: Some(FName::read(mem, address)?),
+                asset_name: FName::read(mem, address + 8)?,
+                sub_path: FString::read(mem, address + 0x10)?,
+            },
+        })
+    }
+    pub fn is_null(&self) -> bool {
+        self.package_name.is_none_or(|n| n.is_none()) && self.asset_name.is_none()
+    }
+    /// Format as `/Game/Package.Asset:SubPath` using `name` to convert FNames to strings
+    pub fn to_string_with(&self, mut name: impl FnMut(FName) -> String) -> String {
+        let mut path = match self.package_name {
+            Some(package) => format!("{}.{}", name(package), name(self.asset_name)),
+            None => name(self.asset_name),
+        };
+        if !self.sub_path.is_empty() {
+            path.push(':');
+            path.push_str(&self.sub_path);
+        }
+        path
+    }
+    /// Find a loaded object by path by comparing against the path name of every object in the
+    /// array (slow)
+    pub fn find<R: ReadMemory>(
+        &self,
+        objects: &ObjectArray<'_, R>,
+        name: impl FnMut(FName) -> String,
+        mut path_name: impl FnMut(usize) -> Option<String>,
+    ) -> Option<usize> {
+        if self.is_null() {
+            return None;
+        }
+        let path = self.to_string_with(name);
+        objects
+            .iter()
+            .filter(|(_, item)| !item.is_unreachable())
+            .find(|(_, item)| path_name(item.object).as_deref() == Some(&path))
+            .map(|(_, item)| item.object)
+    }
+}
+
+/// `TSoftObjectPtr`/`FSoftObjectPtr`: a cached weak pointer plus the path used to find the
+/// object again once the weak pointer becomes stale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TSoftObjectPtr {
+    pub weak: FWeakObjectPtr,
+    pub tag_at_last_test: i32,
+    pub path: FSoftObjectPath,
+}
+impl TSoftObjectPtr {
+    pub fn size(layout: SoftObjectPathLayout) -> usize {
+        0x10 + layout.size()
+    }
+    pub fn read<R: ReadMemory>(
+        mem: &R,
+        address: usize,
+        layout: SoftObjectPathLayout,
+    ) -> Result<Self, MemoryAccessError> {
+        Ok(Self {
+            weak: FWeakObjectPtr::read(mem, address)?,
+            tag_at_last_test: mem.read_i32(address + 8)?,
+            path: FSoftObjectPath::read(mem, address + 0x10, layout)?,
+        })
+    }
+    /// Object referenced by the cached weak pointer, falling back to searching by path when the
+    /// weak pointer is stale. `None` if the object is not loaded.
+    pub fn get<R: ReadMemory>(
+        &self,
+        objects: &ObjectArray<'_, R>,
+        name: impl FnMut(FName) -> String,
+        path_name: impl FnMut(usize) -> Option<String>,
+    ) -> Option<usize> {
+        match self.weak.get(objects) {
+            Ok(Some(object)) => Some(object),
+            _ => self.path.find(objects, name, path_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ue::read::CurrentProcess;
+
+    #[test]
+    fn test_fname_case_preserving() {
+        // ComparisonIndex, DisplayIndex, Number
+        let name = [0x10u32, 0x11, 3];
+        let mem = unsafe { CurrentProcess::new() };
+        let address = name.as_ptr() as usize;
+        let build = BuildConfig::new().case_preserving_names(true);
+        assert_eq!(
+            FName::read_for(&mem, address, build),
+            Ok(FName {
+                comparison_index: 0x10,
+                number: 3
+            })
+        );
+        assert_eq!(FName::read(&mem, address).unwrap().number, 0x11);
+    }
+
+    #[test]
+    fn test_weak_object_ptr() {
+        let object = 0x1234usize;
+        // one chunk with two items (object, flags, cluster root index, serial number)
+        let mut items = [0u64; 6];
+        items[0] = object as u64;
+        items[2] = 7;
+        items[3] = object as u64;
+        // EInternalObjectFlags::Unreachable
+        items[4] = 1 << 28;
+        items[5] = 8;
+        let chunks = [items.as_ptr() as u64];
+        let mut array = [0u8; 0x30];
+        array[0x10..0x18].copy_from_slice(&(chunks.as_ptr() as u64).to_le_bytes());
+        array[0x20..0x24].copy_from_slice(&(64 * 1024i32).to_le_bytes());
+        array[0x24..0x28].copy_from_slice(&2i32.to_le_bytes());
+        array[0x28..0x2c].copy_from_slice(&1i32.to_le_bytes());
+
+        let mem = unsafe { CurrentProcess::new() };
+        let objects = ObjectArray::new(&mem, array.as_ptr() as usize, 0x18);
+        let weak = |object_index, object_serial_number| FWeakObjectPtr {
+            object_index,
+            object_serial_number,
+        };
+        assert_eq!(weak(0, 7).get(&objects), Ok(Some(object)));
+        // slot reused by another object
+        assert_eq!(weak(0, 6).get(&objects), Ok(None));
+        // unreachable
+        assert_eq!(weak(1, 8).get(&objects), Ok(None));
+        // out of range
+        assert_eq!(weak(2, 7).get(&objects), Ok(None));
+    }
+}
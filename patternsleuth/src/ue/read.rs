@@ -0,0 +1,268 @@
+//! Reading engine memory either from the current process or from another process so the same
+//! container readers work in-process and externally.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{Memory, MemoryAccessError, MemoryTrait};
+
+/// Source of engine memory
+pub trait ReadMemory {
+    /// Fill `buffer` with the memory at `address`
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError>;
+
+    fn read_vec(&self, address: usize, len: usize) -> Result<Vec<u8>, MemoryAccessError> {
+        let mut buffer = vec![0; len];
+        self.read_bytes(address, &mut buffer)?;
+        Ok(buffer)
+    }
+    fn read_u32(&self, address: usize) -> Result<u32, MemoryAccessError> {
+        let mut buffer = [0; 4];
+        self.read_bytes(address, &mut buffer)?;
+        Ok(u32::from_le_bytes(buffer))
+    }
+    fn read_i32(&self, address: usize) -> Result<i32, MemoryAccessError> {
+        Ok(self.read_u32(address)? as i32)
+    }
+    fn read_ptr(&self, address: usize) -> Result<usize, MemoryAccessError> {
+        let mut buffer = [0; 8];
+        self.read_bytes(address, &mut buffer)?;
+        Ok(u64::from_le_bytes(buffer) as usize)
+    }
+    /// Read `len` UTF-16 code units
+    fn read_utf16(&self, address: usize, len: usize) -> Result<Vec<u16>, MemoryAccessError> {
+        Ok(self
+            .read_vec(address, len * 2)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect())
+    }
+}
+
+impl ReadMemory for Memory<'_> {
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+        buffer.copy_from_slice(self.range(address..address + buffer.len())?);
+        Ok(())
+    }
+}
+
+/// Memory of the current process
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentProcess(());
+impl CurrentProcess {
+    /// # Safety
+    /// Every address read through the returned reader must be valid for reads.
+    pub unsafe fn new() -> Self {
+        Self(())
+    }
+}
+impl ReadMemory for CurrentProcess {
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+        if address == 0 {
+            return Err(MemoryAccessError::MemoryOutOfBoundsError);
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(address as *const u8, buffer.as_mut_ptr(), buffer.len())
+        };
+        Ok(())
+    }
+}
+
+/// [`ReadMemory`] wrapper for sources where each read is expensive, such as another process
+/// (see `ProcessMemory`). Small reads are served from aligned chunks which are fetched from the
+/// inner reader whole on first use, so walking structures that live close together (e.g.
+/// consecutive object items or the fields of one object) costs one inner read per chunk instead
+/// of one per field.
+///
+/// Cached chunks are a snapshot, [`BatchedMemory::clear`] drops them to observe later changes.
+pub struct BatchedMemory<R> {
+    inner: R,
+    chunk_size: usize,
+    max_chunks: usize,
+    /// chunk address -> contents, `None` if the chunk can't be read as a whole
+    chunks: RefCell<HashMap<usize, Option<Rc<[u8]>>>>,
+}
+impl<R: ReadMemory> BatchedMemory<R> {
+    pub const DEFAULT_CHUNK_SIZE: usize = 0x4000;
+    /// Upper bound of cached memory before the cache is reset
+    pub const MAX_CACHED_BYTES: usize = 0x1000_0000;
+
+    pub fn new(inner: R) -> Self {
+        Self::with_chunk_size(inner, Self::DEFAULT_CHUNK_SIZE)
+    }
+    /// `chunk_size` must be a power of two
+    pub fn with_chunk_size(inner: R, chunk_size: usize) -> Self {
+        assert!(
+            chunk_size.is_power_of_two(),
+            "chunk size must be a power of two"
+        );
+        Self {
+            inner,
+            chunk_size,
+            max_chunks: (Self::MAX_CACHED_BYTES / chunk_size).max(1),
+            chunks: Default::default(),
+        }
+    }
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+    /// Drop all cached chunks
+    pub fn clear(&self) {
+        self.chunks.borrow_mut().clear();
+    }
+
+    fn chunk(&self, address: usize) -> Option<Rc<[u8]>> {
+        if let Some(chunk) = self.chunks.borrow().get(&address) {
+            return chunk.clone();
+        }
+        let chunk = self
+            .inner
+            .read_vec(address, self.chunk_size)
+            .ok()
+            .map(Rc::from);
+        let mut chunks = self.chunks.borrow_mut();
+        if chunks.len() >= self.max_chunks {
+            chunks.clear();
+        }
+        chunks.insert(address, chunk.clone());
+        chunk
+    }
+
+    /// Read many ranges at once. Requests are sorted and ranges less than a chunk apart are
+    /// merged into a single inner read, falling back to reading them one by one if a merged read
+    /// fails. Bypasses the chunk cache. Results are in request order.
+    pub fn read_many(
+        &self,
+        requests: &[(usize, usize)],
+    ) -> Vec<Result<Vec<u8>, MemoryAccessError>> {
+        let mut order = (0..requests.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| requests[*i].0);
+
+        let mut results = (0..requests.len()).map(|_| None).collect::<Vec<_>>();
+        let mut group: Vec<usize> = vec![];
+        let mut flush = |group: &mut Vec<usize>| {
+            let Some(first) = group.first() else {
+                return;
+            };
+            let start = requests[*first].0;
+            let end = group
+                .iter()
+                .map(|i| requests[*i].0 + requests[*i].1)
+                .max()
+                .unwrap();
+            match self.inner.read_vec(start, end - start) {
+                Ok(data) => {
+                    for i in group.iter() {
+                        let (address, len) = requests[*i];
+                        let offset = address - start;
+                        results[*i] = Some(Ok(data[offset..offset + len].to_vec()));
+                    }
+                }
+                // nothing to retry
+                Err(err) if group.len() == 1 => results[*first] = Some(Err(err)),
+                Err(_) => {
+                    for i in group.iter() {
+                        let (address, len) = requests[*i];
+                        results[*i] = Some(self.inner.read_vec(address, len));
+                    }
+                }
+            }
+            group.clear();
+        };
+
+        let mut end = 0;
+        for i in order {
+            let (address, len) = requests[i];
+            if !group.is_empty() && address > end + self.chunk_size {
+                flush(&mut group);
+            }
+            end = if group.is_empty() {
+                address + len
+            } else {
+                end.max(address + len)
+            };
+            group.push(i);
+        }
+        flush(&mut group);
+
+        results.into_iter().map(Option::unwrap).collect()
+    }
+}
+impl<R: ReadMemory> ReadMemory for BatchedMemory<R> {
+    fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+        // large reads gain nothing from chunking
+        if buffer.len() > self.chunk_size * 4 {
+            return self.inner.read_bytes(address, buffer);
+        }
+
+        let mask = !(self.chunk_size - 1);
+        let mut done = 0;
+        while done < buffer.len() {
+            let current = address + done;
+            let chunk_address = current & mask;
+            let Some(chunk) = self.chunk(chunk_address) else {
+                // partially readable chunk, e.g. at the end of a mapping
+                return self.inner.read_bytes(address, buffer);
+            };
+            let offset = current - chunk_address;
+            let len = (buffer.len() - done).min(self.chunk_size - offset);
+            buffer[done..done + len].copy_from_slice(&chunk[offset..offset + len]);
+            done += len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// 0x10000 readable bytes at 0x10000 where each byte is its address truncated, counting reads
+    #[derive(Default)]
+    struct Counting {
+        reads: Cell<usize>,
+    }
+    impl ReadMemory for Counting {
+        fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> Result<(), MemoryAccessError> {
+            self.reads.set(self.reads.get() + 1);
+            if address < 0x10000 || address + buffer.len() > 0x20000 {
+                return Err(MemoryAccessError::MemoryOutOfBoundsError);
+            }
+            for (i, b) in buffer.iter_mut().enumerate() {
+                *b = (address + i) as u8;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_batched_memory() {
+        let mem = BatchedMemory::with_chunk_size(Counting::default(), 0x100);
+
+        assert_eq!(mem.read_u32(0x10004).unwrap(), 0x07060504);
+        assert_eq!(mem.read_u32(0x10010).unwrap(), 0x13121110);
+        assert_eq!(mem.inner().reads.get(), 1, "same chunk read once");
+
+        // spans two chunks
+        assert_eq!(mem.read_vec(0x100fe, 4).unwrap(), [0xfe, 0xff, 0x00, 0x01]);
+        assert_eq!(mem.inner().reads.get(), 2);
+
+        // reads past the end of readable memory fall back to direct reads
+        assert!(mem.read_u32(0x1fffc).is_ok());
+        assert!(mem.read_u32(0x1fffe).is_err());
+        assert!(mem.read_u32(0x8).is_err());
+    }
+
+    #[test]
+    fn test_read_many() {
+        let mem = BatchedMemory::with_chunk_size(Counting::default(), 0x100);
+        let results = mem.read_many(&[(0x10020, 2), (0x10000, 2), (0x18000, 1), (0x8, 1)]);
+        assert_eq!(results[0].as_ref().unwrap(), &[0x20, 0x21]);
+        assert_eq!(results[1].as_ref().unwrap(), &[0x00, 0x01]);
+        assert_eq!(results[2].as_ref().unwrap(), &[0x00]);
+        assert!(results[3].is_err());
+        // 0x8 alone, 0x10000 and 0x10020 merged, 0x18000 alone
+        assert_eq!(mem.inner().reads.get(), 3);
+    }
+}
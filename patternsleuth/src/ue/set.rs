@@ -0,0 +1,244 @@
+//! Read-only access to `TSet` and `TMap`.
+//!
+//! Both are a `TSparseArray` of elements followed by the hash buckets:
+//!
+//! ```text
+//! TSet (0x50)
+//!   0x00 TSparseArray Elements
+//!          0x00 TArray Data
+//!          0x10 TBitArray AllocationFlags (4 inline u32 words, secondary pointer, NumBits, MaxBits)
+//!          0x30 int32 FirstFreeIndex
+//!          0x34 int32 NumFreeIndices
+//!   0x38 Hash (inline FSetElementId, secondary pointer)
+//!   0x48 int32 HashSize
+//! ```
+//!
+//! `TMap<K, V>` is a `TSet<TPair<K, V>>`. Elements are followed by `HashNextId` and `HashIndex`
+//! so the element stride depends on the value type, see [`ScriptSetLayout`].
+
+use std::marker::PhantomData;
+
+use super::read::{CurrentProcess, ReadMemory};
+use crate::MemoryAccessError;
+
+const ALLOCATION_FLAGS: usize = 0x10;
+const ALLOCATION_FLAGS_SECONDARY: usize = ALLOCATION_FLAGS + 0x10;
+const ALLOCATION_FLAGS_NUM_BITS: usize = ALLOCATION_FLAGS + 0x18;
+const NUM_FREE_INDICES: usize = 0x34;
+
+fn align(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// Layout of set elements, mirroring `FScriptSet::GetScriptLayout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptSetLayout {
+    /// Stride of elements in the sparse array
+    pub size: usize,
+}
+impl ScriptSetLayout {
+    pub fn new(element_size: usize, element_alignment: usize) -> Self {
+        let hash_index_offset = align(element_size, 4) + 4;
+        // free elements store a FFreeListLink (two int32) in place of the element
+        let size = (hash_index_offset + 4).max(8);
+        Self {
+            size: align(size, element_alignment.max(4)),
+        }
+    }
+    pub fn of<T>() -> Self {
+        Self::new(std::mem::size_of::<T>(), std::mem::align_of::<T>())
+    }
+}
+
+/// Layout of map pairs, mirroring `FScriptMap::GetScriptLayout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptMapLayout {
+    pub value_offset: usize,
+    pub set: ScriptSetLayout,
+}
+impl ScriptMapLayout {
+    pub fn new(
+        key_size: usize,
+        key_alignment: usize,
+        value_size: usize,
+        value_alignment: usize,
+    ) -> Self {
+        let value_offset = align(key_size, value_alignment);
+        let pair_alignment = key_alignment.max(value_alignment);
+        let pair_size = align(value_offset + value_size, pair_alignment);
+        Self {
+            value_offset,
+            set: ScriptSetLayout::new(pair_size, pair_alignment),
+        }
+    }
+    pub fn of<K, V>() -> Self {
+        Self::new(
+            std::mem::size_of::<K>(),
+            std::mem::align_of::<K>(),
+            std::mem::size_of::<V>(),
+            std::mem::align_of::<V>(),
+        )
+    }
+}
+
+/// Number of allocated elements of the set at `address`
+pub fn read_set_len<R: ReadMemory>(mem: &R, address: usize) -> Result<usize, MemoryAccessError> {
+    let num = mem.read_i32(address + 8)?;
+    let num_free = mem.read_i32(address + NUM_FREE_INDICES)?;
+    Ok((num - num_free).max(0) as usize)
+}
+
+/// Addresses of all allocated elements of the set at `address`
+pub fn read_set<R: ReadMemory>(
+    mem: &R,
+    address: usize,
+    layout: ScriptSetLayout,
+) -> Result<Vec<usize>, MemoryAccessError> {
+    let data = mem.read_ptr(address)?;
+    let num = mem.read_i32(address + 8)?.max(0) as usize;
+    if num == 0 {
+        return Ok(vec![]);
+    }
+
+    let num_bits = mem.read_i32(address + ALLOCATION_FLAGS_NUM_BITS)?.max(0) as usize;
+    let secondary = mem.read_ptr(address + ALLOCATION_FLAGS_SECONDARY)?;
+    let flags = if secondary != 0 {
+        secondary
+    } else {
+        address + ALLOCATION_FLAGS
+    };
+    let flags = mem.read_vec(flags, align(num_bits.min(num), 32) / 8)?;
+
+    Ok((0..num.min(num_bits))
+        .filter(|i| flags[i / 8] & (1 << (i % 8)) != 0)
+        .map(|i| data + i * layout.size)
+        .collect())
+}
+
+/// Addresses of the key and value of all pairs of the map at `address`
+pub fn read_map<R: ReadMemory>(
+    mem: &R,
+    address: usize,
+    layout: ScriptMapLayout,
+) -> Result<Vec<(usize, usize)>, MemoryAccessError> {
+    Ok(read_set(mem, address, layout.set)?
+        .into_iter()
+        .map(|pair| (pair, pair + layout.value_offset))
+        .collect())
+}
+
+/// In-process view of a `TSet<T>` with the default allocators
+#[repr(C, align(8))]
+pub struct TSet<T> {
+    data: [u8; 0x50],
+    _phantom: PhantomData<T>,
+}
+impl<T> TSet<T> {
+    pub fn len(&self) -> usize {
+        read_set_len(&self.mem(), self as *const _ as usize).unwrap_or_default()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        read_set(
+            &self.mem(),
+            self as *const _ as usize,
+            ScriptSetLayout::of::<T>(),
+        )
+        .unwrap_or_default()
+        .into_iter()
+        .map(|element| unsafe { &*(element as *const T) })
+    }
+    fn mem(&self) -> CurrentProcess {
+        // a live set only points at its own allocations
+        unsafe { CurrentProcess::new() }
+    }
+}
+impl<T: std::fmt::Debug> std::fmt::Debug for TSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// In-process view of a `TMap<K, V>` with the default allocators
+#[repr(C, align(8))]
+pub struct TMap<K, V> {
+    data: [u8; 0x50],
+    _phantom: PhantomData<(K, V)>,
+}
+impl<K, V> TMap<K, V> {
+    pub fn len(&self) -> usize {
+        read_set_len(&self.mem(), self as *const _ as usize).unwrap_or_default()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        read_map(
+            &self.mem(),
+            self as *const _ as usize,
+            ScriptMapLayout::of::<K, V>(),
+        )
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| unsafe { (&*(key as *const K), &*(value as *const V)) })
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+    fn mem(&self) -> CurrentProcess {
+        unsafe { CurrentProcess::new() }
+    }
+}
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for TMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layouts() {
+        // TSet<int32>: element + HashNextId + HashIndex
+        assert_eq!(ScriptSetLayout::of::<i32>().size, 0xc);
+        // TMap<FName, UObject*>: 8 byte key, pointer value
+        let layout = ScriptMapLayout::new(8, 4, 8, 8);
+        assert_eq!(layout.value_offset, 8);
+        assert_eq!(layout.set.size, 0x18);
+        // TMap<int32, uint8>
+        let layout = ScriptMapLayout::of::<i32, u8>();
+        assert_eq!(layout.value_offset, 4);
+        assert_eq!(layout.set.size, 0x10);
+        // in-process views match the engine containers
+        assert_eq!(std::mem::size_of::<TSet<u8>>(), 0x50);
+        assert_eq!(std::mem::align_of::<TSet<u8>>(), 8);
+        assert_eq!(std::mem::align_of::<TMap<u8, u8>>(), 8);
+    }
+
+    #[test]
+    fn test_read_set() {
+        // three slots with the middle one free
+        let elements: [u32; 9] = [10, 0, 0, 0xffff, 0xffff, 0, 30, 0, 0];
+        #[repr(C, align(8))]
+        struct Storage([u8; 0x50]);
+        let mut storage = Storage([0; 0x50]);
+        let set = &mut storage.0;
+        set[0..8].copy_from_slice(&(elements.as_ptr() as u64).to_le_bytes());
+        set[8..12].copy_from_slice(&3i32.to_le_bytes());
+        set[12..16].copy_from_slice(&4i32.to_le_bytes());
+        set[0x10] = 0b101;
+        set[0x28..0x2c].copy_from_slice(&3i32.to_le_bytes());
+        set[0x34..0x38].copy_from_slice(&1i32.to_le_bytes());
+
+        let set: &TSet<u32> = unsafe { &*(&storage as *const Storage as *const TSet<u32>) };
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), [10, 30]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Snapshots of the live objects of an [`ObjectArray`] and the difference between two of them,
+//! e.g. to find out which classes a game action instantiates.
+//!
+//! [`ObjectArray`]: super::ObjectArray
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{object::Objects, object_ptr::FName, read::ReadMemory};
+
+/// Live objects by `(index, address)` with the name of their class. Class names are captured
+/// when the snapshot is taken since destroyed objects can't be inspected afterwards.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ObjectSnapshot {
+    objects: HashMap<(i32, usize), String>,
+}
+impl ObjectSnapshot {
+    /// Snapshot all live objects. Readers caching memory (e.g.
+    /// [`BatchedMemory`](super::BatchedMemory)) must be cleared between snapshots.
+    pub fn take<R, N>(objects: &Objects<'_, '_, R, N>) -> Self
+    where
+        R: ReadMemory,
+        N: Fn(FName) -> Option<String>,
+    {
+        let class_name = |object| -> Option<String> {
+            objects
+                .class(object)
+                .ok()
+                .and_then(|class| objects.name(class).ok().flatten())
+        };
+        Self {
+            objects: objects
+                .array()
+                .iter()
+                .filter(|(_, item)| !item.is_unreachable())
+                .map(|(index, item)| {
+                    let class = class_name(item.object).unwrap_or_else(|| "<unknown>".into());
+                    ((index, item.object), class)
+                })
+                .collect(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Objects created and destroyed between `self` and `later`. A slot reused for a new object
+    /// at a different address counts as both.
+    pub fn diff(&self, later: &Self) -> ObjectDiff {
+        let mut diff = ObjectDiff::default();
+        for (key, class) in &later.objects {
+            if !self.objects.contains_key(key) {
+                diff.created.entry(class.clone()).or_default().push(key.1);
+            }
+        }
+        for (key, class) in &self.objects {
+            if !later.objects.contains_key(key) {
+                diff.destroyed.entry(class.clone()).or_default().push(key.1);
+            }
+        }
+        for addresses in diff.created.values_mut().chain(diff.destroyed.values_mut()) {
+            addresses.sort();
+        }
+        diff
+    }
+}
+
+/// Created and destroyed object addresses grouped by class name
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ObjectDiff {
+    pub created: BTreeMap<String, Vec<usize>>,
+    pub destroyed: BTreeMap<String, Vec<usize>>,
+}
+impl ObjectDiff {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.destroyed.is_empty()
+    }
+    /// `(class, created, destroyed)` counts ordered by the most created, then destroyed
+    pub fn counts(&self) -> Vec<(&str, usize, usize)> {
+        let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for (class, addresses) in &self.created {
+            counts.entry(class).or_default().0 = addresses.len();
+        }
+        for (class, addresses) in &self.destroyed {
+            counts.entry(class).or_default().1 = addresses.len();
+        }
+        let mut counts = counts
+            .into_iter()
+            .map(|(class, (created, destroyed))| (class, created, destroyed))
+            .collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snapshot(objects: &[(i32, usize, &str)]) -> ObjectSnapshot {
+        ObjectSnapshot {
+            objects: objects
+                .iter()
+                .map(|(index, address, class)| ((*index, *address), class.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = snapshot(&[
+            (0, 0x100, "Package"),
+            (1, 0x200, "Actor"),
+            (2, 0x300, "Pawn"),
+        ]);
+        let b = snapshot(&[
+            (0, 0x100, "Package"),
+            (1, 0x400, "Actor"),
+            (3, 0x500, "Actor"),
+            (4, 0x600, "Emitter"),
+        ]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.created["Actor"], [0x400, 0x500]);
+        assert_eq!(diff.destroyed["Actor"], [0x200]);
+        assert_eq!(diff.destroyed["Pawn"], [0x300]);
+        assert_eq!(
+            diff.counts(),
+            [("Actor", 2, 1), ("Emitter", 1, 0), ("Pawn", 0, 1)]
+        );
+        assert!(a.diff(&a).is_empty());
+    }
+}
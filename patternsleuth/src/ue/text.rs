@@ -0,0 +1,139 @@
+//! Read-only access to `FText`
+
+use super::{containers::FString, read::ReadMemory};
+use crate::MemoryAccessError;
+
+/// Layout of `FText` and the `ITextData` vtable, which changed when text data became
+/// intrusively reference counted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextLayout {
+    /// `TSharedRef<ITextData>` followed by `Flags`. The vtable starts with the destructor,
+    /// `GetSourceString` (UE4) or `OwnsLocalizedString` (UE5) and `GetDisplayString`
+    #[default]
+    SharedRef,
+    /// `TRefCountPtr<ITextData>` followed by `Flags`. `ITextData` implements
+    /// `IRefCountedObject` so `AddRef`, `Release` and `GetRefCount` follow the destructor
+    RefCounted,
+}
+impl TextLayout {
+    pub fn for_version(major: u16, minor: u16) -> Self {
+        match (major, minor) {
+            (..=4, _) | (5, ..=1) => Self::SharedRef,
+            _ => Self::RefCounted,
+        }
+    }
+    /// Offset of `Flags` within `FText`
+    pub fn flags(self) -> usize {
+        match self {
+            Self::SharedRef => 0x10,
+            Self::RefCounted => 8,
+        }
+    }
+    /// Vtable slot of `ITextData::GetDisplayString`
+    fn get_display_string(self) -> usize {
+        match self {
+            Self::SharedRef => 2,
+            Self::RefCounted => 5,
+        }
+    }
+}
+
+/// `FText`: a reference to polymorphic `ITextData` followed by flags at
+/// [`TextLayout::flags`]. Only the leading pointer is common to every layout so this is only
+/// usable behind a reference into engine memory.
+#[derive(Debug)]
+#[repr(C)]
+pub struct FText {
+    pub text_data: *const ITextData,
+}
+
+#[repr(C)]
+pub struct ITextData {
+    vtable: *const *const (),
+}
+
+type GetDisplayString = unsafe extern "system" fn(this: &ITextData) -> &FString;
+
+impl FText {
+    /// Display string of in-process text, as returned by `FText::ToString`
+    ///
+    /// # Safety
+    /// The text must be live engine memory with the given layout.
+    pub unsafe fn display_string(&self, layout: TextLayout) -> Option<String> {
+        let data = self.text_data.as_ref()?;
+        let get_display_string: GetDisplayString =
+            std::mem::transmute(*data.vtable.add(layout.get_display_string()));
+        Some(get_display_string(data).to_string())
+    }
+
+    /// Flags of in-process text
+    ///
+    /// # Safety
+    /// The text must be live engine memory with the given layout.
+    pub unsafe fn flags(&self, layout: TextLayout) -> u32 {
+        (self as *const Self)
+            .byte_add(layout.flags())
+            .cast::<u32>()
+            .read()
+    }
+
+    /// Read the display string of the text at `address` from another process.
+    ///
+    /// Text data is polymorphic and cannot be dispatched externally so `display_string_offset`
+    /// locates the `TSharedPtr<FString>` holding the display string within the text data object.
+    pub fn read_display_string<R: ReadMemory>(
+        mem: &R,
+        address: usize,
+        display_string_offset: usize,
+    ) -> Result<String, MemoryAccessError> {
+        let text_data = mem.read_ptr(address)?;
+        let string = mem.read_ptr(text_data + display_string_offset)?;
+        FString::read(mem, string)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[repr(C)]
+    struct TestTextData {
+        vtable: *const *const (),
+        display_string: FString,
+    }
+
+    unsafe extern "system" fn get_display_string(this: &ITextData) -> &FString {
+        let this = &*(this as *const ITextData as *const TestTextData);
+        &this.display_string
+    }
+
+    #[test]
+    fn test_display_string() {
+        for (layout, version) in [
+            (TextLayout::SharedRef, (4, 27)),
+            (TextLayout::RefCounted, (5, 3)),
+        ] {
+            assert_eq!(TextLayout::for_version(version.0, version.1), layout);
+
+            let mut vtable = [std::ptr::null::<()>(); 6];
+            vtable[layout.get_display_string()] = get_display_string as *const ();
+            let mut chars = "hello".encode_utf16().chain([0]).collect::<Vec<_>>();
+            let data = TestTextData {
+                vtable: vtable.as_ptr(),
+                display_string: unsafe {
+                    FString::from_raw_parts(chars.as_mut_ptr(), chars.len(), chars.len())
+                },
+            };
+            let mut text = [0u64; 3];
+            text[0] = &data as *const TestTextData as u64;
+            text[layout.flags() / 8] = 2;
+
+            let text = unsafe { &*(text.as_ptr() as *const FText) };
+            assert_eq!(
+                unsafe { text.display_string(layout) }.as_deref(),
+                Some("hello")
+            );
+            assert_eq!(unsafe { text.flags(layout) }, 2);
+        }
+    }
+}
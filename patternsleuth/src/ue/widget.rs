@@ -0,0 +1,280 @@
+//! Walking UMG widget trees through reflection, e.g. to dump the widgets of the current UI.
+//!
+//! - a `UUserWidget` holds its widgets in `WidgetTree->RootWidget`
+//! - a `UPanelWidget` holds its children in `Slots[]->Content`
+//! - widgets placed in a `UCanvasPanel` have their position and size in their slot's
+//!   `LayoutData`
+
+use super::{object::Objects, object_ptr::FName, read::ReadMemory};
+use crate::MemoryAccessError;
+
+/// Guards against cycles when reading a tree that is being modified
+const MAX_DEPTH: usize = 64;
+
+/// ESlateVisibility
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Visible,
+    Collapsed,
+    Hidden,
+    HitTestInvisible,
+    SelfHitTestInvisible,
+    Unknown(u8),
+}
+impl From<u8> for Visibility {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Visible,
+            1 => Self::Collapsed,
+            2 => Self::Hidden,
+            3 => Self::HitTestInvisible,
+            4 => Self::SelfHitTestInvisible,
+            other => Self::Unknown(other),
+        }
+    }
+}
+impl Visibility {
+    /// Whether the widget is drawn
+    pub fn is_visible(self) -> bool {
+        matches!(
+            self,
+            Self::Visible | Self::HitTestInvisible | Self::SelfHitTestInvisible
+        )
+    }
+}
+
+/// `UCanvasPanelSlot::LayoutData.Offsets`, the position and size of a widget in a canvas unless
+/// its anchors stretch it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasLayout {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetNode {
+    pub widget: usize,
+    /// Name of the widget class, e.g. `TextBlock` or `WBP_MainMenu_C`
+    pub class: Option<String>,
+    pub name: Option<String>,
+    /// Full path name, e.g. `/Engine/Transient.GameEngine_0:BP_GameInstance_C_0.WBP_Hud_C_0`
+    pub path: Option<String>,
+    pub visibility: Option<Visibility>,
+    /// Only present for widgets in a canvas panel
+    pub layout: Option<CanvasLayout>,
+    pub children: Vec<WidgetNode>,
+}
+impl WidgetNode {
+    /// Visit the node and its descendants depth first along with their depth
+    pub fn walk(&self, visitor: &mut impl FnMut(&WidgetNode, usize)) {
+        fn walk(node: &WidgetNode, depth: usize, visitor: &mut impl FnMut(&WidgetNode, usize)) {
+            visitor(node, depth);
+            for child in &node.children {
+                walk(child, depth + 1, visitor);
+            }
+        }
+        walk(self, 0, visitor)
+    }
+}
+
+/// UMG reader over [`Objects`]. Requires FProperty based reflection (UE 4.25+).
+pub struct Widgets<'a, 'objects, 'mem, R, N> {
+    objects: &'a Objects<'objects, 'mem, R, N>,
+}
+impl<'a, 'objects, 'mem, R, N> Widgets<'a, 'objects, 'mem, R, N>
+where
+    R: ReadMemory,
+    N: Fn(FName) -> Option<String>,
+{
+    pub fn new(objects: &'a Objects<'objects, 'mem, R, N>) -> Self {
+        Self { objects }
+    }
+
+    /// Live user widgets which are not part of another widget's tree, i.e. those created with
+    /// `CreateWidget` such as screens and HUDs
+    pub fn root_user_widgets(&self) -> Vec<usize> {
+        let objects = self.objects;
+        objects.find(|object| {
+            if !objects.is_a(object, "UserWidget")? {
+                return Ok(false);
+            }
+            let outer = objects.outer(object)?;
+            Ok(outer == 0 || !objects.is_a(outer, "WidgetTree")?)
+        })
+    }
+
+    /// Read the tree of widgets below `widget`, including the trees of nested user widgets
+    pub fn tree(&self, widget: usize) -> Result<WidgetNode, MemoryAccessError> {
+        self.node(widget, 0)
+    }
+
+    fn node(&self, widget: usize, depth: usize) -> Result<WidgetNode, MemoryAccessError> {
+        let objects = self.objects;
+        let class = objects.class(widget)?;
+
+        let mut children = vec![];
+        if depth < MAX_DEPTH {
+            for child in self.children(widget)? {
+                children.push(self.node(child, depth + 1)?);
+            }
+        }
+
+        Ok(WidgetNode {
+            widget,
+            class: objects.name(class)?,
+            name: objects.name(widget)?,
+            path: objects.path_name(widget)?,
+            visibility: match objects.property_offset(class, "Visibility")? {
+                Some(offset) => {
+                    let value = objects.mem().read_vec(widget + offset as usize, 1)?[0];
+                    Some(value.into())
+                }
+                None => None,
+            },
+            layout: self.layout(widget)?,
+            children,
+        })
+    }
+
+    fn children(&self, widget: usize) -> Result<Vec<usize>, MemoryAccessError> {
+        let objects = self.objects;
+        if objects.is_a(widget, "UserWidget")? {
+            let root = match objects.object_property(widget, "WidgetTree")? {
+                Some(tree) => objects.object_property(tree, "RootWidget")?,
+                None => None,
+            };
+            Ok(root.into_iter().collect())
+        } else if objects.is_a(widget, "PanelWidget")? {
+            let mut children = vec![];
+            for slot in objects.object_array_property(widget, "Slots")? {
+                children.extend(objects.object_property(slot, "Content")?);
+            }
+            Ok(children)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn layout(&self, widget: usize) -> Result<Option<CanvasLayout>, MemoryAccessError> {
+        let objects = self.objects;
+        let Some(slot) = objects.object_property(widget, "Slot")? else {
+            return Ok(None);
+        };
+        if !objects.is_a(slot, "CanvasPanelSlot")? {
+            return Ok(None);
+        }
+        let Some(offset) = objects.property_offset(objects.class(slot)?, "LayoutData")? else {
+            return Ok(None);
+        };
+        // FMargin Offsets: Left, Top, Right, Bottom
+        let offsets = slot + offset as usize;
+        let read = |i: usize| -> Result<f32, MemoryAccessError> {
+            Ok(f32::from_bits(objects.mem().read_u32(offsets + i * 4)?))
+        };
+        Ok(Some(CanvasLayout {
+            position: [read(0)?, read(1)?],
+            size: [read(2)?, read(3)?],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{TestImageBuilder, TestObjects},
+        ue::{ObjectArray, ObjectLayout},
+    };
+
+    #[test]
+    fn test_widget_tree() {
+        let base = 0x140000000;
+        let mut objects = TestObjects::new(base + 0x10000);
+        let object = objects.class("Object", 0);
+        let widget = objects.class("Widget", object);
+        let user_widget = objects.class("UserWidget", widget);
+        let panel = objects.class("PanelWidget", widget);
+        let canvas = objects.class("CanvasPanel", panel);
+        let text_block = objects.class("TextBlock", widget);
+        let widget_tree = objects.class("WidgetTree", object);
+        let panel_slot = objects.class("PanelSlot", object);
+        let canvas_slot = objects.class("CanvasPanelSlot", panel_slot);
+        let hud_class = objects.class("WBP_Hud_C", user_widget);
+        let button_class = objects.class("WBP_Button_C", user_widget);
+        for (class, property, kind, offset) in [
+            (widget, "Visibility", "EnumProperty", 0x100),
+            (widget, "Slot", "ObjectProperty", 0x108),
+            (user_widget, "WidgetTree", "ObjectProperty", 0x110),
+            (widget_tree, "RootWidget", "ObjectProperty", 0x100),
+            (panel, "Slots", "ArrayProperty", 0x110),
+            (panel_slot, "Content", "ObjectProperty", 0x100),
+            (canvas_slot, "LayoutData", "StructProperty", 0x110),
+        ] {
+            objects.property(class, property, kind, offset);
+        }
+
+        let hud = objects.object(hud_class, "WBP_Hud_C_0", 0);
+        let tree = objects.object(widget_tree, "WidgetTree", hud);
+        let root = objects.object(canvas, "Root", tree);
+        let title = objects.object(text_block, "Title", tree);
+        let button = objects.object(button_class, "Button", tree);
+        let title_slot = objects.object(canvas_slot, "CanvasPanelSlot_0", root);
+        let button_slot = objects.object(panel_slot, "PanelSlot_0", root);
+
+        objects.write_ptr(hud + 0x110, tree);
+        objects.write_ptr(tree + 0x100, root);
+        objects.write_ptr_array(root + 0x110, &[title_slot, button_slot]);
+        objects.write_ptr(title_slot + 0x100, title);
+        objects.write_ptr(button_slot + 0x100, button);
+        objects.write_ptr(title + 0x108, title_slot);
+        objects.write_ptr(button + 0x108, button_slot);
+        objects.write(title + 0x100, &[2]);
+        let offsets = [10.0f32, 20.0, 300.0, 40.0];
+        objects.write(title_slot + 0x110, &offsets.map(f32::to_le_bytes).concat());
+
+        let names = objects.names();
+        let (builder, array) = objects.add_to(TestImageBuilder::new(base));
+        let image = builder.build().unwrap();
+        let array = ObjectArray::new(&image.memory, array, TestObjects::ITEM_SIZE);
+        let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+            names.get(name.comparison_index as usize).cloned()
+        });
+        let widgets = Widgets::new(&objects);
+
+        // the nested user widget is part of the tree of the HUD
+        assert_eq!(widgets.root_user_widgets(), vec![hud]);
+
+        let tree = widgets.tree(hud).unwrap();
+        let mut nodes = vec![];
+        tree.walk(&mut |node, depth| {
+            nodes.push((
+                depth,
+                node.class.clone().unwrap(),
+                node.name.clone().unwrap(),
+            ))
+        });
+        let expected = [
+            (0, "WBP_Hud_C", "WBP_Hud_C_0"),
+            (1, "CanvasPanel", "Root"),
+            (2, "TextBlock", "Title"),
+            (2, "WBP_Button_C", "Button"),
+        ]
+        .map(|(depth, class, name)| (depth, class.to_string(), name.to_string()));
+        assert_eq!(nodes, expected);
+
+        let title = &tree.children[0].children[0];
+        assert_eq!(title.path.as_deref(), Some("WBP_Hud_C_0.WidgetTree.Title"));
+        assert_eq!(title.visibility, Some(Visibility::Hidden));
+        assert!(!title.visibility.unwrap().is_visible());
+        assert_eq!(
+            title.layout,
+            Some(CanvasLayout {
+                position: [10.0, 20.0],
+                size: [300.0, 40.0],
+            })
+        );
+        let button = &tree.children[0].children[1];
+        assert_eq!(button.visibility, Some(Visibility::Visible));
+        assert_eq!(button.layout, None);
+    }
+}
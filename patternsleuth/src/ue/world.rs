@@ -0,0 +1,200 @@
+//! Traversal of the objects making up a world, built on [`GWorld`] and [`GUObjectArray`].
+//!
+//! Relationships are derived from reflection data available on every object (class, super
+//! struct and outer chains) so the same code works in-process and on other processes:
+//!
+//! - actors are outered to a `ULevel` which is outered to the `UWorld`
+//! - components are outered to their owning actor and derive from `ActorComponent`
+//! - members such as `UWorld::GameState` are read at the offsets of their reflected properties
+//!
+//! [`GWorld`]: crate::resolvers::unreal::gworld::GWorld
+//! [`GUObjectArray`]: crate::resolvers::unreal::guobject_array::GUObjectArray
+
+use super::{object::Objects, object_ptr::FName, read::ReadMemory};
+use crate::MemoryAccessError;
+
+pub struct World<'a, 'objects, 'mem, R, N> {
+    objects: &'a Objects<'objects, 'mem, R, N>,
+    world: usize,
+}
+impl<'a, 'objects, 'mem, R, N> World<'a, 'objects, 'mem, R, N>
+where
+    R: ReadMemory,
+    N: Fn(FName) -> Option<String>,
+{
+    /// `gworld` is the address of the `GWorld` global
+    pub fn new(
+        objects: &'a Objects<'objects, 'mem, R, N>,
+        gworld: usize,
+    ) -> Result<Self, MemoryAccessError> {
+        let world = objects.mem().read_ptr(gworld)?;
+        if world == 0 {
+            return Err(MemoryAccessError::MemoryOutOfBoundsError);
+        }
+        Ok(Self { objects, world })
+    }
+    /// Address of the current UWorld
+    pub fn world(&self) -> usize {
+        self.world
+    }
+
+    /// UWorld::PersistentLevel
+    pub fn persistent_level(&self) -> Result<Option<usize>, MemoryAccessError> {
+        self.objects.object_property(self.world, "PersistentLevel")
+    }
+    /// UWorld::AuthorityGameMode, only present on the server
+    pub fn game_mode(&self) -> Result<Option<usize>, MemoryAccessError> {
+        self.objects
+            .object_property(self.world, "AuthorityGameMode")
+    }
+    /// UWorld::GameState
+    pub fn game_state(&self) -> Result<Option<usize>, MemoryAccessError> {
+        self.objects.object_property(self.world, "GameState")
+    }
+    /// UWorld::OwningGameInstance
+    pub fn game_instance(&self) -> Result<Option<usize>, MemoryAccessError> {
+        self.objects
+            .object_property(self.world, "OwningGameInstance")
+    }
+    /// Player controller of the first local player, found through
+    /// `UGameInstance::LocalPlayers[0]->PlayerController`
+    pub fn local_player_controller(&self) -> Result<Option<usize>, MemoryAccessError> {
+        let objects = self.objects;
+        let Some(game_instance) = self.game_instance()? else {
+            return Ok(None);
+        };
+        let Some(offset) =
+            objects.property_offset(objects.class(game_instance)?, "LocalPlayers")?
+        else {
+            return Ok(None);
+        };
+        // TArray<ULocalPlayer*>
+        let local_players = game_instance + offset as usize;
+        if objects.mem().read_i32(local_players + 8)? <= 0 {
+            return Ok(None);
+        }
+        let player = objects
+            .mem()
+            .read_ptr(objects.mem().read_ptr(local_players)?)?;
+        if player == 0 {
+            return Ok(None);
+        }
+        objects.object_property(player, "PlayerController")
+    }
+    /// APlayerController::PlayerInput of the local player controller, an `EnhancedPlayerInput`
+    /// when the game uses EnhancedInput
+    pub fn local_player_input(&self) -> Result<Option<usize>, MemoryAccessError> {
+        match self.local_player_controller()? {
+            Some(controller) => self.objects.object_property(controller, "PlayerInput"),
+            None => Ok(None),
+        }
+    }
+
+    /// Actors in any level of the world which are instances of `class_name` (e.g. "BP_Enemy_C")
+    /// or one of its subclasses
+    pub fn actors_of_class(&self, class_name: &str) -> Vec<usize> {
+        let objects = self.objects;
+        objects.find(|object| {
+            let level = objects.outer(object)?;
+            Ok(level != 0
+                && objects.outer(level)? == self.world
+                && objects.is_a(level, "Level")?
+                && objects.is_a(object, "Actor")?
+                && objects.is_a(object, class_name)?)
+        })
+    }
+
+    /// Components owned by `actor`
+    pub fn components_of(&self, actor: usize) -> Vec<usize> {
+        let objects = self.objects;
+        objects.find(|object| {
+            Ok(objects.outer(object)? == actor && objects.is_a(object, "ActorComponent")?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{TestImageBuilder, TestObjects},
+        ue::{ObjectArray, ObjectLayout},
+    };
+
+    #[test]
+    fn test_world() {
+        let base = 0x140000000;
+        let mut objects = TestObjects::new(base + 0x10000);
+        let object = objects.class("Object", 0);
+        let [level, world, actor, component, game_instance, local_player] = [
+            "Level",
+            "World",
+            "Actor",
+            "ActorComponent",
+            "GameInstance",
+            "LocalPlayer",
+        ]
+        .map(|name| objects.class(name, object));
+        let enemy = objects.class("BP_Enemy_C", actor);
+        let controller = objects.class("PlayerController", actor);
+        for (class, property, offset) in [
+            (world, "PersistentLevel", 0x100),
+            (world, "GameState", 0x108),
+            (world, "OwningGameInstance", 0x110),
+            (game_instance, "LocalPlayers", 0x100),
+            (local_player, "PlayerController", 0x100),
+            (controller, "PlayerInput", 0x100),
+        ] {
+            objects.property(class, property, "ObjectProperty", offset);
+        }
+
+        let the_world = objects.object(world, "Map", 0);
+        let persistent_level = objects.object(level, "PersistentLevel", the_world);
+        let enemy_0 = objects.object(enemy, "BP_Enemy_C_0", persistent_level);
+        let actor_0 = objects.object(actor, "Actor_0", persistent_level);
+        let mesh = objects.object(component, "Mesh", enemy_0);
+        let game_state = objects.object(actor, "GameState_0", persistent_level);
+        let instance = objects.object(game_instance, "GameInstance_0", 0);
+        let player = objects.object(local_player, "LocalPlayer_0", instance);
+        let player_controller = objects.object(controller, "PlayerController_0", persistent_level);
+        let input = objects.object(object, "PlayerInput_0", player_controller);
+        // the same class in another world
+        let other_world = objects.object(world, "Other", 0);
+        let other_level = objects.object(level, "PersistentLevel", other_world);
+        objects.object(enemy, "BP_Enemy_C_0", other_level);
+
+        objects.write_ptr(the_world + 0x100, persistent_level);
+        objects.write_ptr(the_world + 0x108, game_state);
+        objects.write_ptr(the_world + 0x110, instance);
+        objects.write_ptr_array(instance + 0x100, &[player]);
+        objects.write_ptr(player + 0x100, player_controller);
+        objects.write_ptr(player_controller + 0x100, input);
+        let gworld = objects.alloc(8);
+        objects.write_ptr(gworld, the_world);
+
+        let names = objects.names();
+        let (builder, array) = objects.add_to(TestImageBuilder::new(base));
+        let image = builder.build().unwrap();
+        let array = ObjectArray::new(&image.memory, array, TestObjects::ITEM_SIZE);
+        let objects = Objects::new(&array, ObjectLayout::default(), |name| {
+            names.get(name.comparison_index as usize).cloned()
+        });
+
+        let world = World::new(&objects, gworld).unwrap();
+        assert_eq!(world.world(), the_world);
+        assert_eq!(world.persistent_level(), Ok(Some(persistent_level)));
+        assert_eq!(world.game_state(), Ok(Some(game_state)));
+        assert_eq!(world.game_mode(), Ok(None));
+        assert_eq!(world.game_instance(), Ok(Some(instance)));
+        assert_eq!(world.local_player_controller(), Ok(Some(player_controller)));
+        assert_eq!(world.local_player_input(), Ok(Some(input)));
+
+        assert_eq!(world.actors_of_class("BP_Enemy_C"), vec![enemy_0]);
+        assert_eq!(
+            world.actors_of_class("Actor"),
+            vec![enemy_0, actor_0, game_state, player_controller]
+        );
+        assert_eq!(world.components_of(enemy_0), vec![mesh]);
+        assert!(world.components_of(actor_0).is_empty());
+    }
+}
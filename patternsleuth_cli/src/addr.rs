@@ -0,0 +1,88 @@
+//! Converting between virtual addresses, RVAs and file offsets, for cross-referencing results
+//! with hex editors, debuggers and other tools
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use patternsleuth::image::Image;
+
+use crate::parse_maybe_hex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AddressKind {
+    /// Virtual address at the image base of the executable (or of the running process)
+    Va,
+    /// Relative virtual address
+    Rva,
+    /// Offset in the executable file
+    Offset,
+}
+
+#[derive(Parser)]
+pub struct CommandAddr {
+    /// Path to a game executable or the ID of a running game process
+    target: String,
+
+    /// Values to convert, hex with 0x prefix or decimal
+    #[arg(required = true, value_parser(parse_maybe_hex))]
+    values: Vec<usize>,
+
+    /// What the values are
+    #[arg(short, long, value_enum, default_value_t = AddressKind::Va)]
+    from: AddressKind,
+
+    /// Rebase the executable to this address, e.g. the base of a running instance or debugger
+    /// session with ASLR. Running processes are already at their actual base.
+    #[arg(short, long, value_parser(parse_maybe_hex))]
+    base: Option<usize>,
+}
+
+pub fn addr(command: CommandAddr) -> Result<()> {
+    let bin_data;
+    let exe = if let Ok(pid) = command.target.parse::<i32>() {
+        if command.base.is_some() {
+            bail!("--base can't be used with a running process");
+        }
+        patternsleuth::process::external::read_image_from_pid(pid)?
+    } else {
+        bin_data = fs::read(PathBuf::from(&command.target))?;
+        let builder = Image::builder().functions(true);
+        match command.base {
+            Some(base) => builder.base_address(base),
+            None => builder,
+        }
+        .build(&bin_data)?
+    };
+
+    let hex = |value: Option<usize>| {
+        value
+            .map(|v| format!("{v:#x}"))
+            .unwrap_or_else(|| "-".into())
+    };
+
+    println!("{:>18} {:>18} {:>18}  location", "va", "rva", "offset");
+    for value in command.values {
+        let va = match command.from {
+            AddressKind::Va => Some(value),
+            AddressKind::Rva => Some(exe.rva_to_va(value)),
+            AddressKind::Offset => exe.file_offset_to_va(value),
+        };
+        let Some(va) = va else {
+            bail!("file offset {value:#x} is not part of any section");
+        };
+        let offset = match command.from {
+            AddressKind::Offset => Some(value),
+            _ => exe.va_to_file_offset(va),
+        };
+        println!(
+            "{:>18} {:>18} {:>18}  {}",
+            hex(Some(va)),
+            hex(exe.va_to_rva(va)),
+            hex(offset),
+            exe.annotate(va)
+        );
+    }
+
+    Ok(())
+}
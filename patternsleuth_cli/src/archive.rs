@@ -0,0 +1,170 @@
+//! Games stored in zip archives. The executable, the DLLs which may contain the engine and its
+//! PDB are extracted once to a cache directory and scanned from there like any other game, so
+//! every command reading games supports archives without changes.
+//!
+//! 7z archives are not supported, they can't be read without decompressing the whole archive.
+
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+/// Whether `path` is an archive which may contain a game
+pub(crate) fn is_archive(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+/// Directory archives are extracted to, `$PATTERNSLEUTH_ARCHIVE_CACHE` or a directory in the
+/// system temp directory
+fn cache_root() -> PathBuf {
+    std::env::var_os("PATTERNSLEUTH_ARCHIVE_CACHE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("patternsleuth-archives"))
+}
+
+/// Extract the game executable of `archive` and the files next to it which are needed to scan
+/// it. Returns the path of the extracted executable, `None` if the archive contains none.
+/// Extracted files are reused as long as they are newer than the archive.
+pub(crate) fn extract_game(archive: &Path) -> Result<Option<PathBuf>> {
+    let file = fs::File::open(archive)
+        .with_context(|| format!("failed to open archive {}", archive.display()))?;
+    let modified = file.metadata()?.modified()?;
+    let mut zip = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("failed to read archive {}", archive.display()))?;
+
+    let names = (0..zip.len())
+        .map(|i| -> Result<_> {
+            let entry = zip.by_index(i)?;
+            Ok((!entry.is_dir())
+                .then(|| entry.enclosed_name().map(|p| p.to_path_buf()))
+                .flatten())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some((exe, companions)) = select_entries(&names) else {
+        return Ok(None);
+    };
+
+    let dir = cache_root().join(cache_dir_name(archive)?);
+    let path_of = |index: usize| dir.join(names[index].as_ref().unwrap().file_name().unwrap());
+
+    let exe_path = path_of(exe);
+    let up_to_date = fs::metadata(&exe_path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|extracted| extracted >= modified);
+    if up_to_date {
+        return Ok(Some(exe_path));
+    }
+
+    fs::create_dir_all(&dir)?;
+    // the executable is extracted last so its presence means extraction finished
+    for index in companions.into_iter().chain([exe]) {
+        let path = path_of(index);
+        tracing::info!("extracting {}", path.display());
+        let tmp = path.with_extension("tmp");
+        std::io::copy(&mut zip.by_index(index)?, &mut fs::File::create(&tmp)?)?;
+        fs::rename(&tmp, &path)?;
+    }
+    Ok(Some(exe_path))
+}
+
+/// Name of the cache directory of `archive`: its stem for readability and a hash of its canonical
+/// path, so archives of the same name in different directories don't share extracted files
+fn cache_dir_name(archive: &Path) -> Result<String> {
+    let stem = archive
+        .file_stem()
+        .context("archive path has no file name")?
+        .to_string_lossy();
+    let path = fs::canonicalize(archive)
+        .with_context(|| format!("failed to resolve archive path {}", archive.display()))?;
+    // FNV-1a, stable across runs and toolchains unlike std's hasher
+    let hash = path
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+    Ok(format!("{stem}-{hash:016x}"))
+}
+
+/// Pick the game executable among the files of an archive, preferring shipping builds, and the
+/// DLLs and PDBs in the same directory. Returns indexes into `names`.
+fn select_entries(names: &[Option<PathBuf>]) -> Option<(usize, Vec<usize>)> {
+    let has_ext = |path: &Path, exts: &[&str]| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| exts.iter().any(|x| x.eq_ignore_ascii_case(e)))
+    };
+    let stem = |path: &Path| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default()
+    };
+
+    let exes = names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| Some((i, name.as_deref()?)))
+        .filter(|(_, name)| has_ext(name, &["exe"]));
+    let (exe, exe_name) = exes.min_by_key(|(_, name)| {
+        (
+            !stem(name).ends_with("-shipping"),
+            name.components().count(),
+        )
+    })?;
+
+    let companions = names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| Some((i, name.as_deref()?)))
+        .filter(|(i, name)| *i != exe && name.parent() == exe_name.parent())
+        .filter(|(_, name)| {
+            has_ext(name, &["dll"]) || (has_ext(name, &["pdb"]) && stem(name) == stem(exe_name))
+        })
+        .map(|(i, _)| i)
+        .collect();
+    Some((exe, companions))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_select_entries() {
+        let names = [
+            Some("Game/Binaries/Win64/Game-Win64-Shipping.pdb"),
+            Some("Engine/Binaries/Win64/CrashReportClient.exe"),
+            None,
+            Some("Game/Binaries/Win64/Game-Win64-Shipping.exe"),
+            Some("Game/Binaries/Win64/steam_api64.dll"),
+            Some("Game/Content/Paks/Game.pak"),
+            Some("Game.exe"),
+        ]
+        .map(|name| name.map(PathBuf::from));
+        assert_eq!(select_entries(&names), Some((3, vec![0, 4])));
+        assert_eq!(select_entries(&names[4..6]), None);
+    }
+
+    #[test]
+    fn test_cache_dir_name() {
+        let dir = std::env::temp_dir().join("patternsleuth-archive-test");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        for dir in [&a, &b] {
+            fs::create_dir_all(dir).unwrap();
+            fs::write(dir.join("Game.zip"), []).unwrap();
+        }
+
+        let name = cache_dir_name(&a.join("Game.zip")).unwrap();
+        assert!(name.starts_with("Game-"));
+        assert_eq!(name, cache_dir_name(&a.join("../a/Game.zip")).unwrap());
+        assert_ne!(name, cache_dir_name(&b.join("Game.zip")).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
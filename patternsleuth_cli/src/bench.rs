@@ -0,0 +1,161 @@
+//! Scanner throughput benchmark using the patterns queued by the built-in resolvers as workload
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use patternsleuth::{
+    image::{Image, MappedFile},
+    resolvers::{resolvers, EvalEvent},
+    scanner::{scan_pattern, Pattern},
+};
+use prettytable::{row, Table};
+
+#[derive(Parser)]
+pub struct CommandBench {
+    /// Path to the binary to scan
+    exe: PathBuf,
+
+    /// Number of times each measurement is repeated (the fastest run is reported)
+    #[arg(short, long, default_value = "3")]
+    iterations: usize,
+
+    /// Additional patterns to include in the workload (can be specified multiple times)
+    #[arg(short, long, value_parser(|s: &_| Pattern::new(s)))]
+    patterns: Vec<Pattern>,
+
+    /// Number of most expensive patterns to list
+    #[arg(long, default_value = "20")]
+    top: usize,
+
+    /// Also measure the naive scanner (checks every pattern at every offset, very slow)
+    #[arg(long)]
+    naive: bool,
+}
+
+pub fn bench(command: CommandBench) -> Result<()> {
+    let data = MappedFile::open(&command.exe)?;
+    let exe = Image::builder().functions(false).build_mapped(&data)?;
+
+    let patterns = workload(&exe, &command.patterns);
+    if patterns.is_empty() {
+        bail!("no patterns in workload");
+    }
+    let pattern_refs = patterns.iter().collect::<Vec<_>>();
+
+    let sections = exe.memory.sections();
+    let bytes: usize = sections.iter().map(|s| s.len()).sum();
+    println!(
+        "workload: {} patterns over {} sections ({:.1} MiB)",
+        patterns.len(),
+        sections.len(),
+        bytes as f64 / (1024. * 1024.)
+    );
+
+    let measure = |f: &dyn Fn()| -> Duration {
+        (0..command.iterations.max(1))
+            .map(|_| {
+                let start = Instant::now();
+                f();
+                start.elapsed()
+            })
+            .min()
+            .unwrap()
+    };
+    let throughput = |d: Duration| bytes as f64 / d.as_secs_f64() / 1e9;
+
+    let mut table = Table::new();
+    table.set_titles(row!["backend", "time", "GB/s"]);
+
+    let batched = measure(&|| {
+        for s in sections {
+            scan_pattern(&pattern_refs, s.address(), s.data());
+        }
+    });
+    table.add_row(row![
+        "batched",
+        format!("{batched:.2?}"),
+        format!("{:.2}", throughput(batched))
+    ]);
+
+    let mut per_pattern = vec![];
+    for pattern in &patterns {
+        let time = measure(&|| {
+            for s in sections {
+                scan_pattern(&[pattern], s.address(), s.data());
+            }
+        });
+        per_pattern.push((pattern, time));
+    }
+    let sequential: Duration = per_pattern.iter().map(|(_, t)| *t).sum();
+    table.add_row(row![
+        "sequential",
+        format!("{sequential:.2?}"),
+        format!("{:.2}", throughput(sequential))
+    ]);
+
+    if command.naive {
+        let naive = measure(&|| {
+            for s in sections {
+                let data = s.data();
+                for pattern in &patterns {
+                    std::hint::black_box(
+                        (0..data.len().saturating_sub(pattern.simple.len() - 1))
+                            .filter(|i| pattern.is_match(data, s.address(), *i))
+                            .count(),
+                    );
+                }
+            }
+        });
+        table.add_row(row![
+            "naive",
+            format!("{naive:.2?}"),
+            format!("{:.2}", throughput(naive))
+        ]);
+    }
+    table.printstd();
+
+    per_pattern.sort_by_key(|(_, t)| std::cmp::Reverse(*t));
+    let mut table = Table::new();
+    table.set_titles(row!["time", "GB/s", "pattern"]);
+    for (pattern, time) in per_pattern.iter().take(command.top) {
+        table.add_row(row![
+            format!("{time:.2?}"),
+            format!("{:.2}", throughput(*time)),
+            pattern.to_string()
+        ]);
+    }
+    table.printstd();
+
+    Ok(())
+}
+
+/// Collect every pattern the built-in resolvers scan for on this image, deduplicated
+fn workload(exe: &Image<'_>, extra: &[Pattern]) -> Vec<Pattern> {
+    let queued = Arc::new(Mutex::new(BTreeMap::new()));
+    let events = {
+        let queued = queued.clone();
+        Arc::new(move |event: &EvalEvent| {
+            if let EvalEvent::PatternQueued(pattern) = event {
+                queued
+                    .lock()
+                    .unwrap()
+                    .entry(pattern.to_string())
+                    .or_insert_with(|| (*pattern).clone());
+            }
+        })
+    };
+    let all = resolvers().map(|r| r.getter).collect::<Vec<_>>();
+    exe.resolve_many_with_events(&all, events);
+
+    let mut patterns = std::mem::take(&mut *queued.lock().unwrap())
+        .into_values()
+        .collect::<Vec<_>>();
+    patterns.extend(extra.iter().cloned());
+    patterns
+}
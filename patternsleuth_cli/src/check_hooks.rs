@@ -0,0 +1,104 @@
+//! Checking whether functions found by resolvers are already hooked in a running game, e.g. by
+//! another mod, before hooking them yourself
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use patternsleuth::{
+    hooks::detect_patch,
+    image::{Image, MappedFile},
+    process::external::{exe_path, memory_regions, read_image_from_pid, ProcessMemory},
+    resolvers::resolvers,
+};
+
+use crate::{parse_resolver_match, selected_resolvers, ResolverMatch};
+
+#[derive(Parser)]
+pub struct CommandCheckHooks {
+    /// A game process ID to check
+    #[arg(long)]
+    pid: i32,
+
+    /// A resolver to check (can be specified multiple times). Checks all resolvers if omitted.
+    /// Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
+}
+
+pub fn check_hooks(command: CommandCheckHooks) -> Result<()> {
+    // patched prologues are exactly what make resolvers fail on the live image, so resolve
+    // against the executable on disk loaded where the live one is
+    let base_address = read_image_from_pid(command.pid)?.base_address;
+    let exe_path = exe_path(command.pid)?;
+    let file = MappedFile::open(&exe_path)
+        .with_context(|| format!("failed to open {}", exe_path.display()))?;
+    let exe = Image::builder()
+        .base_address(base_address)
+        .build_mapped(&file)?;
+    let mem = ProcessMemory::new(command.pid)?;
+    let regions = memory_regions(command.pid)?;
+    let owner = |address: usize| {
+        regions
+            .iter()
+            .find(|region| region.range.contains(&address))
+            .and_then(|region| region.path.as_deref())
+            .unwrap_or("<anonymous>")
+    };
+
+    let selected = if command.resolver.is_empty() {
+        resolvers().collect()
+    } else {
+        selected_resolvers(&command.resolver)
+    };
+    let getters = selected.iter().map(|r| r.getter).collect::<Vec<_>>();
+
+    let mut checked = 0;
+    let mut patched = 0;
+    let mut failed = 0;
+    for (resolver, res) in selected.iter().zip(exe.resolve_many(&getters)) {
+        let res = match res {
+            Ok(res) => res,
+            Err(err) => {
+                failed += 1;
+                println!(
+                    "{}",
+                    format!("{}: failed to resolve: {err}", resolver.name).yellow()
+                );
+                continue;
+            }
+        };
+        for (name, address) in res.addresses().prefixed(resolver.name) {
+            // only functions can be hooked
+            let code = exe
+                .memory
+                .get_section_containing(address)
+                .map(|section| section.permissions().execute)
+                .unwrap_or_default();
+            if !code {
+                continue;
+            }
+            checked += 1;
+            match detect_patch(&mem, address) {
+                Ok(Some(patch)) => {
+                    patched += 1;
+                    let destination = match patch.destination {
+                        Some(destination) => format!("{destination:#x} in {}", owner(destination)),
+                        None => "unknown destination".to_string(),
+                    };
+                    println!(
+                        "{}",
+                        format!("{name} {address:#x}: {} -> {destination}", patch.kind).red()
+                    );
+                }
+                Ok(None) => println!("{name} {address:#x}: ok"),
+                Err(err) => println!("{}", format!("{name} {address:#x}: {err}").yellow()),
+            }
+        }
+    }
+    println!("{patched} of {checked} functions are patched");
+    if failed != 0 {
+        println!("{failed} resolvers failed");
+    }
+
+    Ok(())
+}
@@ -12,7 +12,8 @@ use rayon::prelude::*;
 use rusqlite::{Connection, OptionalExtension};
 
 use crate::{
-    disassemble, get_games, CommandAutoGen, CommandBuildIndex, CommandViewSymbol, GameFileEntry,
+    disassemble, get_games, sig_diff, CommandAutoGen, CommandBuildIndex, CommandIdentify,
+    CommandViewSymbol, GameFileEntry,
 };
 
 fn generate_patterns_for_symbol(symbol: &str) -> Result<Vec<Pattern>> {
@@ -159,8 +160,8 @@ pub(crate) fn auto_gen(_command: CommandAutoGen) -> Result<()> {
     let mut matches: HashMap<&str, usize> = Default::default();
     let mut bad = HashSet::new();
 
-    let games_vec = get_games([])?;
-    for GameFileEntry { name, exe_path } in games_vec {
+    let games_vec = get_games(&Default::default())?;
+    for GameFileEntry { name, exe_path, .. } in games_vec {
         println!("{:?} {:?}", name, exe_path.display());
 
         let bin_data = fs::read(&exe_path)?;
@@ -267,8 +268,7 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
         });
     }
 
-    let resolvers = command
-        .resolver
+    let resolvers = crate::selected_resolvers(&command.resolver)
         .into_iter()
         .map(|res| res.getter)
         .collect::<Vec<_>>();
@@ -276,11 +276,11 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
     if !resolvers.is_empty() {
         let mut games: HashSet<String> = Default::default();
 
-        for game in crate::get_games([])? {
+        for game in crate::get_games(&Default::default())? {
             #[allow(unused_assignments)]
             let mut bin_data = None;
 
-            let GameFileEntry { name, exe_path } = game;
+            let GameFileEntry { name, exe_path, .. } = game;
 
             bin_data = Some(fs::read(&exe_path)?);
 
@@ -464,6 +464,22 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
             table.printstd();
         }
 
+        if command.generate {
+            let functions = groups
+                .iter()
+                .flatten()
+                .sorted_by_key(|f| f.index)
+                .map(|f| {
+                    (
+                        f.function.game.as_str(),
+                        f.function.address,
+                        f.function.data.as_slice(),
+                    )
+                })
+                .collect_vec();
+            print_shared_patterns(&functions)?;
+        }
+
         /*
         let mut table = Table::new();
         table.set_titles(cells.iter().map(|c| c.0.clone()).collect());
@@ -479,6 +495,124 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
     Ok(())
 }
 
+/// Print the longest instruction sequence shared by all `functions` (game executable, address,
+/// bytes) and the best patterns generated from the shared instructions, with their number of
+/// matches in each game. A pattern is unique in a game if it only matches the functions of that
+/// game.
+fn print_shared_patterns(functions: &[(&str, usize, &[u8])]) -> Result<()> {
+    const COUNT: usize = 10;
+
+    let decoded = functions
+        .iter()
+        .map(|(_, address, data)| sig_diff::decode_bytes(data, *address))
+        .collect_vec();
+    let decoded = decoded.iter().map(Vec::as_slice).collect_vec();
+
+    let Some((addresses, longest)) = sig_diff::longest_shared(&decoded) else {
+        println!(
+            "no instructions shared by all {} functions",
+            functions.len()
+        );
+        return Ok(());
+    };
+    println!(
+        "longest sequence shared by all {} functions (at +{:#x} in {}):",
+        functions.len(),
+        addresses[0] - functions[0].1,
+        functions[0].0
+    );
+    println!("  {longest}");
+
+    let candidates = sig_diff::candidate_patterns(&decoded);
+    let patterns = candidates.iter().map(|c| &c.pattern).collect_vec();
+
+    // indexes of the functions of each game
+    let mut games: Vec<(&str, Vec<usize>)> = vec![];
+    for (i, (game, _, _)) in functions.iter().enumerate() {
+        match games.iter_mut().find(|(g, _)| *g == *game) {
+            Some((_, indexes)) => indexes.push(i),
+            None => games.push((*game, vec![i])),
+        }
+    }
+    let mut matches = vec![];
+    for (game, _) in &games {
+        let data = fs::read(game)?;
+        let exe = Image::builder().functions(false).build(&data)?;
+        matches.push(sig_diff::scan_code(&exe, &patterns));
+    }
+
+    struct Stats<'a> {
+        candidate: &'a sig_diff::Candidate,
+        /// Number of matches and whether they are exactly the expected ones, per game
+        games: Vec<(usize, bool)>,
+    }
+    let mut stats = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, candidate)| Stats {
+            candidate,
+            games: games
+                .iter()
+                .zip(&matches)
+                .map(|((_, indexes), matches)| {
+                    let matches = matches[i].iter().copied().sorted().collect_vec();
+                    let expected = indexes
+                        .iter()
+                        .map(|f| candidate.addresses[*f])
+                        .sorted()
+                        .dedup()
+                        .collect_vec();
+                    (matches.len(), matches == expected)
+                })
+                .collect(),
+        })
+        .collect_vec();
+    // unique in the most games first, then the fewest total matches, then the shortest
+    stats.sort_by_key(|s| {
+        (
+            s.games.iter().filter(|(_, unique)| !unique).count(),
+            s.games.iter().map(|(count, _)| count).sum::<usize>(),
+            s.candidate.pattern.simple.sig.len(),
+        )
+    });
+    // one pattern per run of shared instructions
+    let mut runs = HashSet::new();
+    stats.retain(|s| runs.insert(s.candidate.run));
+
+    let mut table = Table::new();
+    table.set_titles(
+        ["pattern", "offset"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(games.iter().map(|(game, _)| {
+                let path = std::path::Path::new(game);
+                path.file_stem()
+                    .map_or(game.to_string(), |s| s.to_string_lossy().to_string())
+            }))
+            .map(|title| Cell::new(&title))
+            .collect(),
+    );
+    for s in stats.iter().take(COUNT) {
+        table.add_row(Row::new(
+            [
+                s.candidate.pattern.to_string(),
+                format!("+{:#x}", s.candidate.addresses[0] - functions[0].1),
+            ]
+            .into_iter()
+            .chain(s.games.iter().map(|(count, unique)| match unique {
+                true => count.to_string(),
+                false => format!("{count}!"),
+            }))
+            .map(|cell| Cell::new(&cell))
+            .collect(),
+        ));
+    }
+    println!("candidate patterns (matches per game, ! = not unique to the function):");
+    table.printstd();
+
+    Ok(())
+}
+
 pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
     use crossbeam::channel::bounded;
 
@@ -492,6 +626,7 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
             demangled: String,
         },
         Xref((String, usize, usize, usize)),
+        String((String, usize, String)),
     }
 
     let mut conn = Connection::open("data.db")?;
@@ -529,18 +664,30 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
         (),
     )?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS strings (
+            game      TEXT NOT NULL,
+            address_function INTEGER NOT NULL,
+            string    TEXT NOT NULL
+        )",
+        (),
+    )?;
+
     let (tx, rx) = bounded::<Insert>(0);
 
-    let existing_games = {
-        let mut stmt = conn.prepare("SELECT DISTINCT game FROM functions")?;
+    let indexed_games = |table: &str| -> Result<HashSet<std::path::PathBuf>> {
+        let mut stmt = conn.prepare(&format!("SELECT DISTINCT game FROM {table}"))?;
         #[warn(clippy::let_and_return)]
         let result = stmt
             .query_map((), |row| {
                 Ok(std::path::PathBuf::from(row.get::<_, String>(0)?))
             })?
             .collect::<rusqlite::Result<HashSet<_>>>()?;
-        result
+        Ok(result)
     };
+    let existing_games = indexed_games("functions")?;
+    // games indexed before strings were, only their strings are indexed
+    let games_with_strings = indexed_games("strings")?;
 
     crossbeam::scope(|scope| -> Result<()> {
         scope.spawn(|_| -> Result<()> {
@@ -574,15 +721,27 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
                             panic!("{:?} {:?}", e, i);
                         }
                     }
+                    Insert::String(i) => {
+                        let r = transction.execute(
+                            "INSERT INTO strings (game, address_function, string) VALUES (?1, ?2, ?3)",
+                            i.clone(),
+                        );
+                        if let Err(e) = r {
+                            panic!("{:?} {:?}", e, i);
+                        }
+                    }
                 }
             }
             transction.commit()?;
             Ok(())
         });
 
-        let games_with_symbols = get_games(command.game)?
+        let games_with_symbols = get_games(&command.games)?
             .into_iter()
-            .filter(|g| !existing_games.contains(&g.exe_path) && g.exe_path.with_extension("pdb").exists())
+            .filter(|g| {
+                !(existing_games.contains(&g.exe_path) && games_with_strings.contains(&g.exe_path))
+                    && g.exe_path.with_extension("pdb").exists()
+            })
             .collect::<Vec<_>>();
 
         use indicatif::ParallelProgressIterator;
@@ -601,8 +760,9 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
         games_with_symbols
             .par_iter()
             .progress_with(pb.clone())
-            .try_for_each(|GameFileEntry { name, exe_path }| -> Result<()> {
+            .try_for_each(|GameFileEntry { name, exe_path, .. }| -> Result<()> {
                 pb.set_message("total");
+                let strings_only = existing_games.contains(exe_path);
 
                 let bin_data = fs::read(exe_path)?;
                 let exe = match Image::builder()
@@ -617,6 +777,27 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
                     }
                 };
 
+                // collect root exceptions / functions
+                let functions = exe.get_root_functions()?;
+
+                if strings_only {
+                    let pb = m.add(indicatif::ProgressBar::new(functions.len() as u64));
+                    pb.set_style(sty.clone());
+                    pb.set_message(format!("inserting strings for {}", name));
+
+                    for function in functions.iter().progress_with(pb) {
+                        for string in referenced_strings(&exe, function.start)? {
+                            tx.send(Insert::String((
+                                exe_path.to_string_lossy().to_string(),
+                                function.start,
+                                string,
+                            )))
+                            .unwrap();
+                        }
+                    }
+                    return Ok(());
+                }
+
                 let symbols = exe.symbols.as_ref().unwrap();
 
                 let pb = m.add(indicatif::ProgressBar::new(symbols.len() as u64));
@@ -637,9 +818,6 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
                     },
                 )?;
 
-                // collect root exceptions / functions
-                let functions = exe.get_root_functions()?;
-
                 let pb = m.add(indicatif::ProgressBar::new(functions.len() as u64));
                 pb.set_style(sty.clone());
                 pb.set_message(format!("inserting functions for {}", name));
@@ -667,6 +845,15 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
                             .unwrap();
                         }
 
+                        for string in referenced_strings(&exe, range.start)? {
+                            tx.send(Insert::String((
+                                exe_path.to_string_lossy().to_string(),
+                                range.start,
+                                string,
+                            )))
+                            .unwrap();
+                        }
+
                         Ok(())
                     },
                 )?;
@@ -698,10 +885,111 @@ pub(crate) fn build(command: CommandBuildIndex) -> Result<()> {
         "CREATE INDEX IF NOT EXISTS xrefs_game_address_reference_idx ON xrefs (game, address_reference)",
         (),
     )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS strings_string_idx ON strings (string)",
+        (),
+    )?;
+
+    Ok(())
+}
+
+/// Identify functions of an executable without symbols by the strings they reference. Each
+/// indexed symbol referencing one of the strings scores the inverse of the number of symbols
+/// referencing that string, so strings unique to one function (log messages, cvar names)
+/// dominate and common ones barely count.
+pub(crate) fn identify(command: CommandIdentify) -> Result<()> {
+    let conn = Connection::open("data.db")?;
+    let bin_data = fs::read(&command.path)?;
+    let exe = Image::builder().functions(true).build(&bin_data)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT symbol, demangled FROM strings
+        JOIN symbols ON strings.game = symbols.game AND strings.address_function = symbols.address
+        WHERE string = ?1",
+    )?;
+
+    for address in command.function {
+        let range = patternsleuth::disassemble::function_range(&exe, address)?;
+        let strings = referenced_strings(&exe, range.start)?;
+        println!("{address:#x}: {} notable strings", strings.len());
+
+        // symbol => (demangled, score, matched strings)
+        let mut scores: HashMap<String, (String, f64, usize)> = HashMap::new();
+        for string in &strings {
+            let symbols = stmt
+                .query_map((string,), |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+            let weight = 1. / symbols.len() as f64;
+            for (symbol, demangled) in symbols {
+                let entry = scores.entry(symbol).or_insert((demangled, 0., 0));
+                entry.1 += weight;
+                entry.2 += 1;
+            }
+        }
+
+        if scores.is_empty() {
+            println!("  no indexed function references these strings");
+        }
+        for (demangled, score, matched) in scores
+            .into_values()
+            .sorted_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+            .take(command.count)
+        {
+            println!("  {score:6.2} {matched:3}/{} {demangled}", strings.len());
+        }
+    }
 
     Ok(())
 }
 
+/// Longest string read when indexing, longer ones are unlikely to be strings at all
+const MAX_STRING_LEN: usize = 512;
+
+/// Distinct strings referenced by a function which are worth indexing, either wide (`TEXT()`)
+/// or narrow
+fn referenced_strings(exe: &Image, function: usize) -> Result<Vec<String>> {
+    Ok(patternsleuth::disassemble::data_references(exe, function)?
+        .into_iter()
+        .filter_map(|reference| {
+            let section = exe.memory.get_section_containing(reference.address).ok()?;
+            if section.permissions().execute {
+                return None;
+            }
+            let data = &section.data()[reference.address - section.address()..];
+            read_utf16(data)
+                .filter(|s| is_notable(s))
+                .or_else(|| read_utf8(data).filter(|s| is_notable(s)))
+        })
+        .sorted()
+        .dedup()
+        .collect())
+}
+
+fn read_utf16(data: &[u8]) -> Option<String> {
+    let units = data
+        .chunks_exact(2)
+        .take(MAX_STRING_LEN)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect_vec();
+    let len = units.iter().position(|c| *c == 0)?;
+    String::from_utf16(&units[..len]).ok()
+}
+
+fn read_utf8(data: &[u8]) -> Option<String> {
+    let data = &data[..data.len().min(MAX_STRING_LEN)];
+    let len = data.iter().position(|b| *b == 0)?;
+    std::str::from_utf8(&data[..len]).ok().map(str::to_string)
+}
+
+/// Printable ASCII text with a few letters in it, e.g. log format strings, cvar names or
+/// asset paths. Also rejects narrow strings misread as wide as they decode to non-ASCII.
+fn is_notable(s: &str) -> bool {
+    s.len() >= 6
+        && s.chars()
+            .all(|c| c.is_ascii_graphic() || c == ' ' || c == '\n' || c == '\t')
+        && s.chars().filter(char::is_ascii_alphabetic).count() >= 4
+}
+
 fn build_common_pattern<B: AsRef<[u8]>>(function_bodies: impl AsRef<[B]>) -> Option<String> {
     let function_bodies = function_bodies.as_ref();
     if let Some(len) = function_bodies.iter().map(|b| b.as_ref().len()).min() {
@@ -740,3 +1028,28 @@ fn build_common_pattern<B: AsRef<[u8]>>(function_bodies: impl AsRef<[B]>) -> Opt
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_strings() {
+        let wide = "LogNet: %s\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect_vec();
+        assert_eq!(read_utf16(&wide).as_deref(), Some("LogNet: %s"));
+        assert_eq!(
+            read_utf8(b"r.ScreenPercentage\0").as_deref(),
+            Some("r.ScreenPercentage")
+        );
+        assert_eq!(read_utf8(b"unterminated"), None);
+
+        // narrow strings read as wide decode to CJK and are rejected
+        let misread = read_utf16(b"r.ScreenPercentage\0\0").unwrap();
+        assert!(!is_notable(&misread));
+        assert!(is_notable("r.ScreenPercentage"));
+        assert!(!is_notable("%d %d"));
+    }
+}
@@ -2,10 +2,13 @@ use std::ops::Range;
 
 use colored::{ColoredString, Colorize};
 use iced_x86::{
-    Decoder, DecoderOptions, Formatter, FormatterOutput, FormatterTextKind, IntelFormatter, OpKind,
+    Decoder, DecoderOptions, Formatter, FormatterOutput, FormatterTextKind, Instruction,
+    IntelFormatter, OpKind,
 };
 use patternsleuth::{image::Image, scanner::Pattern, MemoryTrait};
 
+use crate::names::Names;
+
 #[derive(Default)]
 struct Output {
     pub buffer: String,
@@ -18,7 +21,45 @@ impl FormatterOutput for Output {
     }
 }
 
-pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>) -> String {
+/// Push the user label of `address` on its own line
+fn push_label(output: &mut Output, exe: &Image, names: Option<&Names>, address: u64) {
+    if let Some(name) = names.and_then(|names| names.get(exe, address as usize)) {
+        #[allow(clippy::unnecessary_to_owned)]
+        output
+            .buffer
+            .push_str(&format!("{name}:").bright_green().to_string());
+        output.buffer.push('\n');
+    }
+}
+
+/// Push the user label of the branch target or RIP relative operand of `instruction`
+fn push_target_label(
+    output: &mut Output,
+    exe: &Image,
+    names: Option<&Names>,
+    instruction: &Instruction,
+) {
+    let target = if instruction.op_kinds().any(|op| op == OpKind::NearBranch64) {
+        instruction.near_branch64()
+    } else if instruction.is_ip_rel_memory_operand() {
+        instruction.ip_rel_memory_address()
+    } else {
+        return;
+    };
+    if let Some(name) = names.and_then(|names| names.get(exe, target as usize)) {
+        #[allow(clippy::unnecessary_to_owned)]
+        output
+            .buffer
+            .push_str(&format!(" ; {name}").bright_green().to_string());
+    }
+}
+
+pub(crate) fn disassemble(
+    exe: &Image,
+    address: usize,
+    pattern: Option<&Pattern>,
+    names: Option<&Names>,
+) -> String {
     let context = 20; // number of instructions before and after
     let max_inst = 16; // max size of x86 instruction in bytes
 
@@ -33,18 +74,27 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
             section.name(),
         ));
 
-        let (is_fn, data, start_address) = if let Ok(Some(f)) = exe.get_root_function(address) {
-            let fns = exe.get_child_functions(f.range.start).unwrap();
-            let min = fns.iter().map(|f| f.range.start).min().unwrap();
-            let max = fns.iter().map(|f| f.range.end).max().unwrap();
-            let range = min..max;
-
-            output.buffer.push_str(&format!(
-                "{:016x} - {:016x} = function\n",
-                range.start, range.end
-            ));
+        let function = exe.functions().function_at(address);
+        let (is_fn, data, start_address) = if let Ok(Some(f)) = function {
+            let ranges = f.ranges();
+            for range in &ranges {
+                let kind = match range.contains(&f.start()) {
+                    true => "function",
+                    false => "function chunk",
+                };
+                output.buffer.push_str(&format!(
+                    "{:016x} - {:016x} = {kind}\n",
+                    range.start, range.end
+                ));
+            }
+            // only disassemble the chunk containing the address, the gaps between chunks of a
+            // split function belong to other functions
+            let range = ranges
+                .into_iter()
+                .find(|r| r.contains(&address))
+                .unwrap_or_else(|| f.span());
             if let Some(symbols) = &exe.symbols {
-                if let Some(symbol) = symbols.get(&range.start) {
+                if let Some(symbol) = symbols.get(&f.start()) {
                     #[allow(clippy::unnecessary_to_owned)]
                     output
                         .buffer
@@ -92,6 +142,7 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
         let mut formatter = IntelFormatter::new();
         formatter.options_mut().set_first_operand_char_index(8);
         for instruction in instructions {
+            push_label(&mut output, exe, names, instruction.ip());
             let ip = format!("{:016x}", instruction.ip());
             if (instruction.ip()..instruction.ip() + instruction.len() as u64)
                 .contains(&(address as u64))
@@ -135,6 +186,7 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
             }
 
             formatter.format(&instruction, &mut output);
+            push_target_label(&mut output, exe, names, &instruction);
             output.buffer.push('\n');
         }
     } else {
@@ -145,7 +197,7 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
     output.buffer
 }
 
-pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>) -> String {
+pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>, names: Option<&Names>) -> String {
     let address = range.start;
     let mut output = Output::default();
 
@@ -186,6 +238,7 @@ pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>) -> String {
         let mut formatter = IntelFormatter::new();
         formatter.options_mut().set_first_operand_char_index(8);
         for instruction in instructions {
+            push_label(&mut output, exe, names, instruction.ip());
             let ip = format!("{:016x}", instruction.ip());
             output.buffer.push_str(&ip);
             output.buffer.push_str(":  ");
@@ -203,6 +256,7 @@ pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>) -> String {
             }
 
             formatter.format(&instruction, &mut output);
+            push_target_label(&mut output, exe, names, &instruction);
             output.buffer.push('\n');
         }
     } else {
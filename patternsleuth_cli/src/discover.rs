@@ -0,0 +1,182 @@
+//! Discovery of Unreal Engine games installed through Steam or the Epic Games Launcher
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+use crate::{find_game_modules, GameFileEntry};
+
+/// Find all installed Steam and Epic games that look like UE titles
+pub fn discover_installed() -> Result<Vec<GameFileEntry>> {
+    let mut install_dirs = steam_install_dirs();
+    install_dirs.extend(epic_install_dirs());
+    install_dirs.sort();
+    install_dirs.dedup();
+
+    Ok(install_dirs
+        .iter()
+        .filter_map(|dir| {
+            let exe_path = find_ue_exe(dir)?;
+            let name = dir.file_name()?.to_string_lossy().to_string();
+            Some(GameFileEntry {
+                name,
+                modules: find_game_modules(&exe_path),
+                exe_path,
+                // loaded by get_games along with the profiles of the other games
+                profile: None,
+            })
+        })
+        .collect())
+}
+
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = vec![];
+    if let Some(steam) = std::env::var_os("STEAM_DIR") {
+        roots.push(steam.into());
+    }
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = std::env::var_os(var) {
+                roots.push(Path::new(&dir).join("Steam"));
+            }
+        }
+    } else if let Some(home) = std::env::var_os("HOME") {
+        let home = Path::new(&home);
+        roots.push(home.join(".steam/steam"));
+        roots.push(home.join(".local/share/Steam"));
+    }
+    roots.into_iter().filter(|r| r.is_dir()).collect()
+}
+
+/// Library folders listed in steamapps/libraryfolders.vdf (plus the Steam root itself)
+fn steam_libraries(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    if let Ok(vdf) = fs::read_to_string(root.join("steamapps/libraryfolders.vdf")) {
+        libraries.extend(parse_vdf_paths(&vdf));
+    }
+    libraries
+}
+
+/// Extract values of "path" keys from a VDF file without fully parsing it
+fn parse_vdf_paths(vdf: &str) -> Vec<PathBuf> {
+    vdf.lines()
+        .filter_map(|line| {
+            let mut parts = line.split('"').filter(|p| !p.trim().is_empty());
+            if parts.next()? != "path" {
+                return None;
+            }
+            parts.next().map(|p| p.replace("\\\\", "\\").into())
+        })
+        .collect()
+}
+
+fn steam_install_dirs() -> Vec<PathBuf> {
+    steam_roots()
+        .iter()
+        .flat_map(|root| steam_libraries(root))
+        .flat_map(|library| read_dirs(&library.join("steamapps/common")))
+        .collect()
+}
+
+fn epic_install_dirs() -> Vec<PathBuf> {
+    #[derive(serde::Deserialize)]
+    struct Manifest {
+        #[serde(rename = "InstallLocation")]
+        install_location: PathBuf,
+    }
+
+    let manifests = std::env::var_os("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("C:\\ProgramData"))
+        .join("Epic/EpicGamesLauncher/Data/Manifests");
+    let Ok(entries) = fs::read_dir(manifests) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|e| e == "item").unwrap_or_default())
+        .filter_map(|p| serde_json::from_slice::<Manifest>(&fs::read(p).ok()?).ok())
+        .map(|m| m.install_location)
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+fn read_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+/// Locate the game module of a UE title: <install>/<Project>/Binaries/Win64/<Project>*.exe.
+/// Shipping executables and installs with an Engine directory are accepted based on layout alone,
+/// otherwise the executable must contain a UE build string.
+fn find_ue_exe(install_dir: &Path) -> Option<PathBuf> {
+    let has_engine_dir = install_dir.join("Engine").is_dir();
+    let mut candidates = read_dirs(install_dir)
+        .into_iter()
+        .filter(|p| p.file_name().map(|n| n != "Engine").unwrap_or_default())
+        .filter_map(|project| crate::find_ext(project.join("Binaries/Win64"), &["exe"]).ok()?)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|exe| !is_shipping(exe));
+
+    candidates.into_iter().find(|exe| {
+        has_engine_dir || is_shipping(exe) || contains_ue_build_string(exe).unwrap_or_default()
+    })
+}
+
+fn is_shipping(exe: &Path) -> bool {
+    exe.file_stem()
+        .map(|s| s.to_string_lossy().ends_with("-Shipping"))
+        .unwrap_or_default()
+}
+
+fn contains_ue_build_string(exe: &Path) -> Result<bool> {
+    let data = fs::read(exe)?;
+    let needles = ["++UE4+Release", "++UE5+Release"].map(|s| {
+        s.encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<u8>>()
+    });
+    Ok(needles
+        .iter()
+        .any(|n| memchr::memmem::find(&data, n).is_some()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_vdf_paths() {
+        let vdf = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"label"		""
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+	}
+}
+"#;
+        assert_eq!(
+            parse_vdf_paths(vdf),
+            vec![
+                PathBuf::from("C:\\Program Files (x86)\\Steam"),
+                PathBuf::from("D:\\SteamLibrary")
+            ]
+        );
+    }
+}
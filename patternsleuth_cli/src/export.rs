@@ -0,0 +1,311 @@
+//! Writing resolved addresses in formats understood by other reversing tools so they can be
+//! used without copying addresses by hand
+
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use patternsleuth::{image::Image, resolvers::resolvers};
+
+use crate::{parse_resolver_match, selected_resolvers, ResolverMatch};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// Frida script with an Interceptor.attach skeleton for every function
+    Frida,
+    /// Cheat Engine table (.CT) with a user defined symbol and entry per address
+    CheatEngine,
+    /// x64dbg script labeling every address
+    X64dbg,
+}
+
+#[derive(Parser)]
+pub struct CommandExport {
+    /// Path to a game executable or the ID of a running game process
+    target: String,
+
+    /// Output format
+    #[arg(short, long, value_enum)]
+    format: ExportFormat,
+
+    /// A resolver to export (can be specified multiple times). Exports all resolvers if omitted.
+    /// Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
+
+    /// Module name addresses are written relative to. Defaults to the executable file name and
+    /// is required for processes
+    #[arg(short, long)]
+    module: Option<String>,
+
+    /// File to write to instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// An address to export, relative to the module
+struct Entry {
+    name: String,
+    rva: usize,
+    /// Whether the address is in an executable section and likely a function
+    code: bool,
+}
+
+pub fn export(command: CommandExport) -> Result<()> {
+    let bin_data;
+    let (exe, module) = if let Ok(pid) = command.target.parse::<i32>() {
+        let Some(module) = command.module else {
+            bail!("--module is required when exporting from a running process");
+        };
+        (
+            patternsleuth::process::external::read_image_from_pid(pid)?,
+            module,
+        )
+    } else {
+        let path = PathBuf::from(&command.target);
+        bin_data = fs::read(&path)?;
+        let module = match command.module {
+            Some(module) => module,
+            None => path
+                .file_name()
+                .context("executable path has no file name")?
+                .to_string_lossy()
+                .to_string(),
+        };
+        (Image::builder().functions(true).build(&bin_data)?, module)
+    };
+
+    let selected = if command.resolver.is_empty() {
+        resolvers().collect()
+    } else {
+        selected_resolvers(&command.resolver)
+    };
+    let getters = selected.iter().map(|r| r.getter).collect::<Vec<_>>();
+
+    let mut entries = vec![];
+    for (resolver, res) in selected.iter().zip(exe.resolve_many(&getters)) {
+        match res {
+            Ok(res) => {
+                for (name, address) in res.addresses().prefixed(resolver.name) {
+                    let Some(rva) = exe.va_to_rva(address) else {
+                        eprintln!("skipping {name}: {address:#x} is outside of the image");
+                        continue;
+                    };
+                    let code = exe
+                        .memory
+                        .get_section_containing(address)
+                        .map(|section| section.permissions().execute)
+                        .unwrap_or_default();
+                    entries.push(Entry { name, rva, code });
+                }
+            }
+            Err(err) => eprintln!("skipping {}: {err}", resolver.name),
+        }
+    }
+
+    let output = match command.format {
+        ExportFormat::Frida => frida(&module, &entries),
+        ExportFormat::CheatEngine => cheat_engine(&module, &entries),
+        ExportFormat::X64dbg => x64dbg(&module, &entries),
+    };
+    match &command.output {
+        Some(path) => {
+            fs::write(path, output)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            eprintln!("exported {} addresses to {}", entries.len(), path.display());
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+/// Quote `s` as a JavaScript string literal
+fn js_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // control characters, and the line separators older engines reject in literals
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                write!(out, "\\u{:04x}", c as u32).unwrap()
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn frida(module: &str, entries: &[Entry]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "const base = Process.getModuleByName({}).base;",
+        js_string(module)
+    )
+    .unwrap();
+    writeln!(out, "const addresses = {{").unwrap();
+    for entry in entries {
+        writeln!(
+            out,
+            "  {}: base.add({:#x}),",
+            js_string(&entry.name),
+            entry.rva
+        )
+        .unwrap();
+    }
+    writeln!(out, "}};").unwrap();
+    for entry in entries.iter().filter(|e| e.code) {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "Interceptor.attach(addresses[{}], {{",
+            js_string(&entry.name)
+        )
+        .unwrap();
+        writeln!(out, "  onEnter(args) {{").unwrap();
+        writeln!(out, "  }},").unwrap();
+        writeln!(out, "  onLeave(retval) {{").unwrap();
+        writeln!(out, "  }},").unwrap();
+        writeln!(out, "}});").unwrap();
+    }
+    out
+}
+
+fn cheat_engine(module: &str, entries: &[Entry]) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let address = |entry: &Entry| format!("\"{}\"+{:X}", escape(module), entry.rva);
+
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#).unwrap();
+    writeln!(out, r#"<CheatTable CheatEngineTableVersion="45">"#).unwrap();
+    writeln!(out, "  <CheatEntries>").unwrap();
+    for (id, entry) in entries.iter().enumerate() {
+        writeln!(out, "    <CheatEntry>").unwrap();
+        writeln!(out, "      <ID>{id}</ID>").unwrap();
+        writeln!(
+            out,
+            "      <Description>\"{}\"</Description>",
+            escape(&entry.name)
+        )
+        .unwrap();
+        writeln!(out, "      <ShowAsHex>1</ShowAsHex>").unwrap();
+        writeln!(out, "      <VariableType>8 Bytes</VariableType>").unwrap();
+        writeln!(out, "      <Address>{}</Address>", address(entry)).unwrap();
+        writeln!(out, "    </CheatEntry>").unwrap();
+    }
+    writeln!(out, "  </CheatEntries>").unwrap();
+    writeln!(out, "  <UserdefinedSymbols>").unwrap();
+    for entry in entries {
+        writeln!(out, "    <SymbolEntry>").unwrap();
+        writeln!(out, "      <Name>{}</Name>", escape(&entry.name)).unwrap();
+        writeln!(out, "      <Address>{}</Address>", address(entry)).unwrap();
+        writeln!(out, "    </SymbolEntry>").unwrap();
+    }
+    writeln!(out, "  </UserdefinedSymbols>").unwrap();
+    writeln!(out, "</CheatTable>").unwrap();
+    out
+}
+
+fn x64dbg(module: &str, entries: &[Entry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        // `module:$rva` is relative to the module base, numbers are hex
+        writeln!(out, "lbl {module}:${:X}, {:?}", entry.rva, entry.name).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                name: "GMalloc".to_string(),
+                rva: 0x4f00000,
+                code: false,
+            },
+            Entry {
+                name: "UObject::ProcessEvent".to_string(),
+                rva: 0x1234,
+                code: true,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_frida() {
+        assert_eq!(
+            frida("FSD-Win64-Shipping.exe", &entries()),
+            r#"const base = Process.getModuleByName("FSD-Win64-Shipping.exe").base;
+const addresses = {
+  "GMalloc": base.add(0x4f00000),
+  "UObject::ProcessEvent": base.add(0x1234),
+};
+
+Interceptor.attach(addresses["UObject::ProcessEvent"], {
+  onEnter(args) {
+  },
+  onLeave(retval) {
+  },
+});
+"#
+        );
+    }
+
+    #[test]
+    fn test_js_string() {
+        assert_eq!(js_string("Game.exe"), r#""Game.exe""#);
+        assert_eq!(
+            js_string("a\"b\\c\nd\u{2028}e\u{7f}"),
+            r#""a\"b\\c\nd\u2028e\u007f""#
+        );
+        // other non-ASCII is valid in a UTF-8 script
+        assert_eq!(js_string("ゲーム"), r#""ゲーム""#);
+    }
+
+    #[test]
+    fn test_cheat_engine() {
+        assert_eq!(
+            cheat_engine("Game<1>.exe", &entries()[..1]),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<CheatTable CheatEngineTableVersion="45">
+  <CheatEntries>
+    <CheatEntry>
+      <ID>0</ID>
+      <Description>"GMalloc"</Description>
+      <ShowAsHex>1</ShowAsHex>
+      <VariableType>8 Bytes</VariableType>
+      <Address>"Game&lt;1&gt;.exe"+4F00000</Address>
+    </CheatEntry>
+  </CheatEntries>
+  <UserdefinedSymbols>
+    <SymbolEntry>
+      <Name>GMalloc</Name>
+      <Address>"Game&lt;1&gt;.exe"+4F00000</Address>
+    </SymbolEntry>
+  </UserdefinedSymbols>
+</CheatTable>
+"#
+        );
+    }
+
+    #[test]
+    fn test_x64dbg() {
+        assert_eq!(
+            x64dbg("Game.exe", &entries()),
+            "lbl Game.exe:$4F00000, \"GMalloc\"\nlbl Game.exe:$1234, \"UObject::ProcessEvent\"\n"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Function size and padding statistics per game, derived from the exception table. Useful to
+//! calibrate pattern lengths and to spot compiler setting changes between versions of a game
+//! (inlining, alignment, padding fill) which break signatures en masse.
+
+use std::ops::Range;
+
+use anyhow::Result;
+use clap::Parser;
+use patternsleuth::{
+    image::{Image, MappedFile},
+    MemoryTrait,
+};
+use prettytable::{row, Table};
+
+use crate::{get_games, GameSelection};
+
+#[derive(Parser)]
+pub struct CommandFunctionStats {
+    #[command(flatten)]
+    games: GameSelection,
+
+    /// Show the change of each statistic relative to the previous game, e.g. to compare
+    /// versions of a game selected in release order
+    #[arg(long)]
+    diff: bool,
+
+    /// Print a histogram of function sizes per game
+    #[arg(long)]
+    histogram: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct FunctionStats {
+    count: usize,
+    total_size: usize,
+    /// Sizes at the 10th, 50th and 90th percentile
+    percentiles: [usize; 3],
+    /// Functions starting at a 16 byte boundary
+    aligned: usize,
+    /// Bytes between consecutive functions of the same section
+    padding: usize,
+    /// Padding bytes which are `int3`
+    padding_int3: usize,
+    /// Padding bytes which are zero
+    padding_zero: usize,
+    /// Number of functions per size bucket, bucket `i` holding sizes in `[2^i, 2^(i+1))`
+    histogram: Vec<usize>,
+}
+impl FunctionStats {
+    /// `byte` returns the byte at an address, `None` if unmapped
+    fn new(mut functions: Vec<Range<usize>>, byte: impl Fn(usize) -> Option<u8>) -> Self {
+        functions.sort_by_key(|f| f.start);
+        let mut sizes = functions.iter().map(|f| f.len()).collect::<Vec<_>>();
+        sizes.sort();
+
+        let mut stats = Self {
+            count: functions.len(),
+            total_size: sizes.iter().sum(),
+            ..Default::default()
+        };
+        if !sizes.is_empty() {
+            stats.percentiles = [10, 50, 90].map(|p| sizes[(sizes.len() - 1) * p / 100]);
+        }
+        for size in &sizes {
+            let bucket = (*size).max(1).ilog2() as usize;
+            if stats.histogram.len() <= bucket {
+                stats.histogram.resize(bucket + 1, 0);
+            }
+            stats.histogram[bucket] += 1;
+        }
+        stats.aligned = functions.iter().filter(|f| f.start % 16 == 0).count();
+
+        for pair in functions.windows(2) {
+            let gap = pair[0].end..pair[1].start;
+            // larger gaps are data or functions without unwind info rather than padding
+            if gap.is_empty() || gap.len() >= 64 {
+                continue;
+            }
+            let Some(bytes) = gap.map(&byte).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            stats.padding += bytes.len();
+            stats.padding_int3 += bytes.iter().filter(|b| **b == 0xcc).count();
+            stats.padding_zero += bytes.iter().filter(|b| **b == 0).count();
+        }
+        stats
+    }
+
+    fn mean(&self) -> f64 {
+        self.total_size as f64 / self.count.max(1) as f64
+    }
+    fn aligned_percent(&self) -> f64 {
+        100. * self.aligned as f64 / self.count.max(1) as f64
+    }
+    fn int3_percent(&self) -> f64 {
+        100. * self.padding_int3 as f64 / self.padding.max(1) as f64
+    }
+}
+
+pub fn function_stats(command: CommandFunctionStats) -> Result<()> {
+    let mut all = vec![];
+    for game in get_games(&command.games)? {
+        let data = MappedFile::open(&game.exe_path)?;
+        let exe = Image::builder().functions(true).build_mapped(&data)?;
+        let functions = exe.get_root_functions()?;
+        let stats = FunctionStats::new(functions, |address| exe.memory.index(address).ok());
+        all.push((game.name, stats));
+    }
+
+    let mut table = Table::new();
+    table.set_titles(row![
+        "game",
+        "functions",
+        "total size",
+        "mean",
+        "p10",
+        "median",
+        "p90",
+        "16 aligned",
+        "padding",
+        "int3",
+        "zero"
+    ]);
+    let mut previous: Option<&FunctionStats> = None;
+    for (name, stats) in &all {
+        let diff = |value: f64, old: Option<f64>| match old {
+            Some(old) if command.diff && old != value => format!(" ({:+})", value - old),
+            _ => String::new(),
+        };
+        let int = |value: usize, old: fn(&FunctionStats) -> usize| {
+            format!(
+                "{value}{}",
+                diff(value as f64, previous.map(|p| old(p) as f64))
+            )
+        };
+        let float = |value: f64, old: fn(&FunctionStats) -> f64, unit: &str| {
+            let delta = match previous.map(old) {
+                Some(old) if command.diff && (old - value).abs() >= 0.05 => {
+                    format!(" ({:+.1}{unit})", value - old)
+                }
+                _ => String::new(),
+            };
+            format!("{value:.1}{unit}{delta}")
+        };
+        table.add_row(row![
+            name,
+            int(stats.count, |s| s.count),
+            int(stats.total_size, |s| s.total_size),
+            float(stats.mean(), FunctionStats::mean, ""),
+            int(stats.percentiles[0], |s| s.percentiles[0]),
+            int(stats.percentiles[1], |s| s.percentiles[1]),
+            int(stats.percentiles[2], |s| s.percentiles[2]),
+            float(stats.aligned_percent(), FunctionStats::aligned_percent, "%"),
+            int(stats.padding, |s| s.padding),
+            float(stats.int3_percent(), FunctionStats::int3_percent, "%"),
+            int(stats.padding_zero, |s| s.padding_zero)
+        ]);
+        previous = Some(stats);
+    }
+    table.printstd();
+
+    if command.histogram {
+        for (name, stats) in &all {
+            println!("{name}:");
+            let max = stats.histogram.iter().copied().max().unwrap_or(1).max(1);
+            for (bucket, count) in stats.histogram.iter().enumerate() {
+                let range = format!("{}..{}", 1usize << bucket, 1usize << (bucket + 1));
+                let bar = "#".repeat(count * 50 / max);
+                println!("  {range:>14} {count:>8} {bar}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_function_stats() {
+        let memory = [0xccu8; 0x100];
+        let functions = vec![0x40..0x48, 0x00..0x10, 0x10..0x3c];
+        let stats = FunctionStats::new(functions, |address| memory.get(address).copied());
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.total_size, 0x10 + 0x2c + 0x8);
+        assert_eq!(stats.percentiles, [0x8, 0x10, 0x10]);
+        assert_eq!(stats.aligned, 3);
+        assert_eq!(stats.padding, 4);
+        assert_eq!(stats.padding_int3, 4);
+        assert_eq!(stats.histogram, [0, 0, 0, 1, 1, 1]);
+    }
+}
@@ -0,0 +1,181 @@
+//! Recording resolutions of the game corpus to a golden file and checking later runs against it
+//! to catch regressions from changes to shared resolver helpers
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use patternsleuth::{
+    image::{Image, MappedFile},
+    resolvers::{
+        golden::{check, Regression, Resolutions},
+        resolve_many_modules, resolvers, NamedResolver, Resolution,
+    },
+};
+
+use crate::{
+    get_games, parse_resolver_match, selected_resolvers, GameFileEntry, GameSelection,
+    ResolverMatch,
+};
+
+type Golden = Resolutions<Box<dyn Resolution>>;
+
+#[derive(Parser)]
+pub struct CommandRecordGolden {
+    #[command(flatten)]
+    games: GameSelection,
+
+    /// A resolver to record (can be specified multiple times). Records all resolvers if
+    /// omitted. Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
+
+    /// Golden file to write
+    #[arg(long, default_value = "golden.json")]
+    path: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct CommandCheckGolden {
+    #[command(flatten)]
+    games: GameSelection,
+
+    /// Golden file to check against
+    #[arg(long, default_value = "golden.json")]
+    path: PathBuf,
+}
+
+pub fn record_golden(command: CommandRecordGolden) -> Result<()> {
+    let selected = if command.resolver.is_empty() {
+        resolvers().collect()
+    } else {
+        selected_resolvers(&command.resolver)
+    };
+    let golden = resolve_games(get_games(&command.games)?, &selected)?;
+
+    let passing = golden
+        .values()
+        .flat_map(|game| game.values())
+        .filter(|res| res.is_ok())
+        .count();
+    fs::write(&command.path, serde_json::to_vec_pretty(&golden)?)
+        .with_context(|| format!("failed to write {}", command.path.display()))?;
+    println!(
+        "recorded {passing} passing resolutions of {} games to {}",
+        golden.len(),
+        command.path.display()
+    );
+    Ok(())
+}
+
+pub fn check_golden(command: CommandCheckGolden) -> Result<()> {
+    let mut golden: Golden = serde_json::from_slice(
+        &fs::read(&command.path)
+            .with_context(|| format!("failed to read {}", command.path.display()))?,
+    )?;
+
+    let games = get_games(&command.games)?
+        .into_iter()
+        .filter(|game| golden.contains_key(&game.name))
+        .collect_vec();
+    let found = games
+        .iter()
+        .map(|game| game.name.clone())
+        .collect::<HashSet<_>>();
+    for game in golden.keys().filter(|game| !found.contains(*game)) {
+        println!("{}", format!("skipping {game}: not found").yellow());
+    }
+    golden.retain(|game, _| found.contains(game));
+
+    let names = golden
+        .values()
+        .flat_map(|game| game.keys())
+        .collect::<HashSet<_>>();
+    let selected = resolvers()
+        .filter(|r| names.contains(&r.name.to_string()))
+        .collect_vec();
+    let current = resolve_games(games, &selected)?;
+
+    let result = check(&golden, &current);
+    for (game, resolver, expected, regression) in &result.regressions {
+        let what = match regression {
+            Regression::Missing => "resolver no longer exists".to_string(),
+            Regression::Failed(err) => format!("failed: {err}"),
+            Regression::Changed(actual) => format!("changed to {actual:x?}"),
+        };
+        println!(
+            "{}",
+            format!("{game} {resolver}: expected {expected:x?}, {what}").red()
+        );
+    }
+    for (game, resolver, actual) in &result.improvements {
+        println!(
+            "{}",
+            format!("{game} {resolver}: now resolves to {actual:x?}").green()
+        );
+    }
+
+    if !result.passed() {
+        bail!("{} resolutions regressed", result.regressions.len());
+    }
+    println!("all golden resolutions passed");
+    Ok(())
+}
+
+/// Resolve `resolvers` for every game, keeping the results in their serialized form so they
+/// compare the same way as ones loaded from a golden file
+fn resolve_games(
+    games: Vec<GameFileEntry>,
+    resolvers: &[&'static NamedResolver],
+) -> Result<Golden> {
+    use rayon::prelude::*;
+
+    let getters = resolvers.iter().map(|r| r.getter).collect_vec();
+    let results: Mutex<Golden> = Mutex::new(BTreeMap::new());
+
+    let progress = ProgressBar::new(games.len() as u64);
+    games.into_par_iter().try_for_each(|game| -> Result<()> {
+        progress.println(format!("{:?} {:?}", game.name, game.exe_path.display()));
+
+        let paths = std::iter::once(&game.exe_path).chain(&game.modules);
+        let data = paths
+            .map(|path| -> Result<_> { Ok((path, MappedFile::open(path)?)) })
+            .collect::<Result<Vec<_>>>()?;
+        let mut images = vec![];
+        for (path, data) in &data {
+            match Image::builder().functions(true).build_mapped(data) {
+                Ok(image) => images.push(image),
+                Err(err) => progress.println(format!("err reading {}: {}", path.display(), err)),
+            }
+        }
+        if images.is_empty() {
+            progress.inc(1);
+            return Ok(());
+        }
+
+        let resolution = resolve_many_modules(&images.iter().collect_vec(), &getters);
+        let map = resolvers
+            .iter()
+            .zip(resolution)
+            .map(|(resolver, res)| -> Result<_> {
+                let res = res.map(|(_, res)| res);
+                let res = serde_json::from_value(serde_json::to_value(&res)?)?;
+                Ok((resolver.name.to_string(), res))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        results.lock().unwrap().insert(game.name, map);
+
+        progress.inc(1);
+        Ok(())
+    })?;
+
+    Ok(results.into_inner().unwrap())
+}
@@ -0,0 +1,252 @@
+//! Quick triage of a shipped game: engine version, build configuration and which of the commonly
+//! used resolvers are available
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use patternsleuth::{
+    image::Image,
+    resolvers::{
+        unreal::{
+            blueprint_library::UFunctionBind,
+            engine_version::{EngineVersion, EngineVersionStrings},
+            fname::{FNamePool, FNameToString},
+            game_loop::{FEngineLoopInit, UGameEngineTick},
+            gengine::GEngine,
+            gmalloc::GMalloc,
+            guobject_array::GUObjectArray,
+            kismet::GNatives,
+            layout::{BuildConfiguration, DoCheck},
+            pak::FPakPlatformFileInitialize,
+            static_construct_object::StaticConstructObjectInternal,
+            static_find_object::StaticFindObjectFast,
+        },
+        DynEq, DynResolverFactory,
+    },
+    MemoryTrait,
+};
+
+#[derive(Parser)]
+pub struct CommandInfo {
+    /// Path to a game executable or the ID of a running game process
+    target: String,
+}
+
+type ResolverGetter = fn() -> &'static DynResolverFactory;
+
+/// Resolvers reported in the availability table
+const KEY_RESOLVERS: &[(&str, ResolverGetter)] = &[
+    ("GUObjectArray", GUObjectArray::dyn_resolver),
+    ("FNameToString", FNameToString::dyn_resolver),
+    ("FNamePool", FNamePool::dyn_resolver),
+    ("GMalloc", GMalloc::dyn_resolver),
+    ("GEngine", GEngine::dyn_resolver),
+    ("GNatives", GNatives::dyn_resolver),
+    (
+        "StaticConstructObjectInternal",
+        StaticConstructObjectInternal::dyn_resolver,
+    ),
+    ("StaticFindObjectFast", StaticFindObjectFast::dyn_resolver),
+    ("FEngineLoopInit", FEngineLoopInit::dyn_resolver),
+    ("UGameEngineTick", UGameEngineTick::dyn_resolver),
+    ("UFunctionBind", UFunctionBind::dyn_resolver),
+    (
+        "FPakPlatformFileInitialize",
+        FPakPlatformFileInitialize::dyn_resolver,
+    ),
+];
+
+pub fn info(command: CommandInfo) -> Result<()> {
+    let mut bin_data = None;
+    let (exe_path, exe) = if let Ok(pid) = command.target.parse::<i32>() {
+        let exe_path = fs::read_link(format!("/proc/{pid}/exe")).ok();
+        (
+            exe_path,
+            patternsleuth::process::external::read_image_from_pid(pid)?,
+        )
+    } else {
+        let exe_path = PathBuf::from(&command.target);
+        bin_data = Some(fs::read(&exe_path)?);
+        let exe = Image::builder()
+            .functions(true)
+            .build(bin_data.as_ref().unwrap())?;
+        (Some(exe_path), exe)
+    };
+
+    println!("{:>22}: {:#x}", "base address", exe.base_address);
+    if let Some(path) = &exe_path {
+        println!("{:>22}: {}", "path", path.display());
+    }
+    if let Some(entry_point) = exe.entry_point() {
+        println!("{:>22}: {:#x}", "entry point", entry_point);
+    }
+    for callback in exe.tls_callbacks() {
+        println!("{:>22}: {:#x}", "tls callback", callback);
+    }
+    if let Some(overlay) = exe.overlay() {
+        println!(
+            "{:>22}: {:?} at {:#x}, {} bytes",
+            "overlay", overlay.kind, overlay.offset, overlay.size
+        );
+    }
+
+    let mut resolvers: Vec<ResolverGetter> = vec![
+        EngineVersion::dyn_resolver,
+        EngineVersionStrings::dyn_resolver,
+        BuildConfiguration::dyn_resolver,
+        DoCheck::dyn_resolver,
+    ];
+    resolvers.extend(KEY_RESOLVERS.iter().map(|(_, getter)| *getter));
+    let results = exe.resolve_many(&resolvers);
+
+    let version = results[0]
+        .as_ref()
+        .ok()
+        .and_then(|r| r.as_any().downcast_ref::<EngineVersion>())
+        .map(|v| v.to_string());
+    let strings = results[1]
+        .as_ref()
+        .ok()
+        .and_then(|r| r.as_any().downcast_ref::<EngineVersionStrings>());
+    let config = results[2]
+        .as_ref()
+        .ok()
+        .and_then(|r| r.as_any().downcast_ref::<BuildConfiguration>());
+    let do_check = results[3]
+        .as_ref()
+        .ok()
+        .and_then(|r| r.as_any().downcast_ref::<DoCheck>())
+        .is_some_and(|c| c.0);
+
+    println!(
+        "{:>22}: {}",
+        "engine version",
+        version.as_deref().unwrap_or("unknown")
+    );
+    if let Some(strings) = strings {
+        println!("{:>22}: {}", "branch", strings.branch_name);
+        println!("{:>22}: {}", "build date", strings.build_date);
+        println!(
+            "{:>22}: {}",
+            "changelist",
+            changelist(&strings.build_version).unwrap_or("unknown")
+        );
+    }
+    // images without debug record fall back to the executable name, which may have been renamed
+    let config = match config {
+        Some(config) => format!("{:?}", config.0),
+        None => exe_path
+            .as_deref()
+            .map(build_configuration)
+            .unwrap_or("unknown")
+            .to_string(),
+    };
+    println!(
+        "{:>22}: {config}{}",
+        "build configuration",
+        if do_check { " with checks" } else { "" }
+    );
+    println!(
+        "{:>22}: {}",
+        "linker timestamp",
+        linker_timestamp(&exe, bin_data.as_deref())
+            .map(format_timestamp)
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+    if let Some((paks, utocs)) = exe_path.as_deref().and_then(count_paks) {
+        println!("{:>22}: {paks} .pak, {utocs} .utoc (IOStore)", "content");
+    }
+
+    println!();
+    for ((name, _), result) in KEY_RESOLVERS.iter().zip(&results[4..]) {
+        match result {
+            Ok(res) => println!("{name:>30}: {}", format!("{res:x?}").green()),
+            Err(err) => println!("{name:>30}: {}", err.to_string().red()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the changelist from a build version such as "++UE4+Release-4.27-CL-18319896"
+fn changelist(build_version: &str) -> Option<&str> {
+    let (_, cl) = build_version.rsplit_once("-CL-")?;
+    let end = cl.find(|c: char| !c.is_ascii_digit()).unwrap_or(cl.len());
+    (end > 0).then_some(&cl[..end])
+}
+
+/// Guess the build configuration from the executable name suffix
+/// (e.g. FSD-Win64-Shipping.exe). Development builds have no suffix.
+fn build_configuration(exe_path: &Path) -> &'static str {
+    let stem = exe_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    [
+        ("-Shipping", "Shipping"),
+        ("-DebugGame", "DebugGame"),
+        ("-Test", "Test"),
+        ("-Debug", "Debug"),
+    ]
+    .into_iter()
+    .find(|(suffix, _)| stem.ends_with(suffix))
+    .map(|(_, config)| config)
+    .unwrap_or("Development")
+}
+
+/// Read TimeDateStamp from the PE file header, preferring the file on disk when available
+fn linker_timestamp(exe: &Image<'_>, data: Option<&[u8]>) -> Option<u32> {
+    if let Some(data) = data {
+        use object::read::pe::PeFile64;
+        return PeFile64::parse(data).ok().map(|pe| {
+            pe.nt_headers()
+                .file_header
+                .time_date_stamp
+                .get(object::LittleEndian)
+        });
+    }
+    let e_lfanew = exe.memory.u32_le(exe.base_address + 0x3c).ok()? as usize;
+    exe.memory.u32_le(exe.base_address + e_lfanew + 8).ok()
+}
+
+fn format_timestamp(timestamp: u32) -> String {
+    time::OffsetDateTime::from_unix_timestamp(timestamp as i64)
+        .ok()
+        .and_then(|t| {
+            t.format(time::macros::format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second] UTC"
+            ))
+            .ok()
+        })
+        .unwrap_or_else(|| format!("{timestamp:#x}"))
+}
+
+/// Count .pak and .utoc containers in <Project>/Content/Paks relative to
+/// <Project>/Binaries/<Platform>/<exe>
+fn count_paks(exe_path: &Path) -> Option<(usize, usize)> {
+    let paks_dir = exe_path.parent()?.parent()?.parent()?.join("Content/Paks");
+    let mut counts = (0, 0);
+    let mut visit = |dir: &Path| -> Option<()> {
+        for entry in fs::read_dir(dir).ok()?.flatten() {
+            match entry.path().extension().and_then(|e| e.to_str()) {
+                Some("pak") => counts.0 += 1,
+                Some("utoc") => counts.1 += 1,
+                _ => {}
+            }
+        }
+        Some(())
+    };
+    visit(&paks_dir)?;
+    // mod/DLC paks are commonly mounted from sub-directories
+    for entry in fs::read_dir(&paks_dir).ok()?.flatten() {
+        if entry.path().is_dir() {
+            visit(&entry.path());
+        }
+    }
+    Some(counts)
+}
@@ -0,0 +1,374 @@
+//! Dumping class and struct layouts from the reflection data of a running game and comparing
+//! dumps between game versions to find which hardcoded offsets need updating
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::Colorize;
+use itertools::Itertools;
+use patternsleuth::{
+    process::external::{find_processes, read_image_from_pid, ProcessMemory},
+    resolvers::unreal::{
+        engine_version::EngineVersion,
+        fname::{CachedNamePool, FNamePool},
+        guobject_array::GUObjectArray,
+        layout::{EngineBuildConfig, FUObjectItemSize, UObjectBaseLayout},
+    },
+    ue::{BatchedMemory, ObjectArray, ObjectItemLayout, ObjectLayout, Objects},
+    MemoryAccessError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{get_games, GameSelection};
+
+#[derive(Parser)]
+pub struct CommandDumpLayouts {
+    /// A game process ID to read layouts from
+    #[arg(long, required_unless_present = "game", conflicts_with = "game")]
+    pid: Option<i32>,
+
+    /// A game (as selected by `scan --game`) whose running process to read layouts from
+    #[arg(short, long)]
+    game: Option<String>,
+
+    /// A directory containing one sub-directory per game, used to look up --game
+    #[arg(long, requires = "game")]
+    games_root: Vec<PathBuf>,
+
+    /// Path to write the JSON dump to
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct CommandDiffLayouts {
+    /// Path to the dump of the old game version
+    a: PathBuf,
+
+    /// Path to the dump of the new game version
+    b: PathBuf,
+
+    /// Only report structs whose path matches this glob (e.g. "/Script/Engine.*")
+    #[arg(short, long)]
+    filter: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LayoutDump {
+    /// Game or process the layouts were read from
+    source: String,
+    /// Struct path name -> layout
+    structs: BTreeMap<String, StructLayout>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct StructLayout {
+    super_struct: Option<String>,
+    size: i32,
+    properties: Vec<PropertyLayout>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PropertyLayout {
+    name: String,
+    kind: String,
+    offset: i32,
+    element_size: i32,
+    array_dim: i32,
+}
+
+pub fn dump_layouts(command: CommandDumpLayouts) -> Result<()> {
+    let (source, pid) = match (command.pid, &command.game) {
+        (Some(pid), _) => (format!("pid={pid}"), pid),
+        (None, Some(game)) => {
+            let games = get_games(&GameSelection {
+                game: vec![game.clone()],
+                games_root: command.games_root.clone(),
+                ..Default::default()
+            })?;
+            let [game] = games.as_slice() else {
+                bail!(
+                    "--game must select exactly one game, matched: [{}]",
+                    games.iter().map(|g| &g.name).join(", ")
+                );
+            };
+            (game.name.clone(), find_pid(&game.exe_path)?)
+        }
+        (None, None) => unreachable!(),
+    };
+
+    let exe = read_image_from_pid(pid)?;
+    let guobject_array = exe.resolve(GUObjectArray::resolver())?;
+    let item_size = exe.resolve(FUObjectItemSize::resolver())?;
+    let build = exe.resolve(EngineBuildConfig::resolver())?;
+    let pool = exe.resolve(FNamePool::resolver())?;
+    // without a version the UE4 layout is assumed, misinterpreting the item flags of UE 5
+    let item_layout = exe
+        .resolve(EngineVersion::resolver())
+        .map(|v| ObjectItemLayout::for_version(v.major, v.minor))
+        .unwrap_or_default();
+
+    let mem = BatchedMemory::new(ProcessMemory::new(pid)?);
+    let names = CachedNamePool::new(&mem, pool.0);
+    let array = ObjectArray::new(&mem, guobject_array.0, item_size.0).item_layout(item_layout);
+    let layout = ObjectLayout::for_build(build.0);
+    // the UObjectBase of the engine is assumed if the probe fails
+    let layout = match exe.resolve(UObjectBaseLayout::resolver()) {
+        Ok(base) => layout.object_base(base.object_flags, base.internal_index),
+        Err(_) => layout,
+    };
+    let objects = Objects::new(&array, layout, |name| names.fname(name).ok());
+
+    let is_struct = |object| -> Result<bool, MemoryAccessError> {
+        Ok(objects.is_a(object, "Class")? || objects.is_a(object, "ScriptStruct")?)
+    };
+
+    let mut structs = BTreeMap::new();
+    for object in objects.find(is_struct) {
+        let Ok(Some(path)) = objects.path_name(object) else {
+            continue;
+        };
+        let read = || -> Result<StructLayout> {
+            let super_struct = match objects.super_struct(object)? {
+                0 => None,
+                s => objects.path_name(s)?,
+            };
+            let properties = objects
+                .properties(object)?
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| PropertyLayout {
+                    // by position rather than address so unnamed properties line up between dumps
+                    name: p.name.unwrap_or_else(|| format!("<unnamed {i}>")),
+                    kind: p.class.unwrap_or_default(),
+                    offset: p.offset,
+                    element_size: p.element_size,
+                    array_dim: p.array_dim,
+                })
+                .collect();
+            Ok(StructLayout {
+                super_struct,
+                size: objects.properties_size(object)?,
+                properties,
+            })
+        };
+        match read() {
+            Ok(layout) => {
+                structs.insert(path, layout);
+            }
+            Err(err) => println!("{}", format!("failed to read {path}: {err}").yellow()),
+        }
+    }
+
+    println!(
+        "dumped {} structs from {source} to {}",
+        structs.len(),
+        command.output.display()
+    );
+    fs::write(
+        &command.output,
+        serde_json::to_vec_pretty(&LayoutDump { source, structs })?,
+    )
+    .with_context(|| format!("failed to write {}", command.output.display()))?;
+
+    Ok(())
+}
+
+/// PID of the single running process of `exe_path`
+fn find_pid(exe_path: &Path) -> Result<i32> {
+    let exe_name = exe_path
+        .file_name()
+        .context("game has no executable")?
+        .to_string_lossy();
+    let pids = find_processes(&exe_name)?;
+    match pids.as_slice() {
+        [pid] => Ok(*pid),
+        [] => bail!("no running process found for {}", exe_path.display()),
+        _ => bail!(
+            "multiple running processes found for {}, use --pid to select one of: {}",
+            exe_path.display(),
+            pids.iter().join(", ")
+        ),
+    }
+}
+
+pub fn diff_layouts(command: CommandDiffLayouts) -> Result<()> {
+    let read = |path: &Path| -> Result<LayoutDump> {
+        serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("failed to read layout dump {}", path.display()))
+    };
+    let a = read(&command.a)?;
+    let b = read(&command.b)?;
+
+    let filter = command
+        .filter
+        .as_deref()
+        .map(|f| -> Result<_> {
+            Ok(globset::GlobBuilder::new(f)
+                .case_insensitive(true)
+                .build()?
+                .compile_matcher())
+        })
+        .transpose()?;
+
+    println!("{} {}", "---".red(), a.source);
+    println!("{} {}", "+++".green(), b.source);
+
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for path in a.structs.keys().chain(b.structs.keys()).sorted().dedup() {
+        if let Some(filter) = &filter {
+            if !filter.is_match(path) {
+                continue;
+            }
+        }
+        match (a.structs.get(path), b.structs.get(path)) {
+            (Some(_), None) => {
+                removed += 1;
+                println!("{}", format!("- {path}").red());
+            }
+            (None, Some(_)) => {
+                added += 1;
+                println!("{}", format!("+ {path}").green());
+            }
+            (Some(sa), Some(sb)) => {
+                let lines = diff_struct(sa, sb);
+                if !lines.is_empty() {
+                    changed += 1;
+                    println!("{}", path.bold());
+                    for line in lines {
+                        println!("  {line}");
+                    }
+                }
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    println!("{changed} changed, {added} added, {removed} removed");
+
+    Ok(())
+}
+
+/// Human readable differences between two layouts of the same struct
+fn diff_struct(a: &StructLayout, b: &StructLayout) -> Vec<String> {
+    let mut lines = vec![];
+    if a.super_struct != b.super_struct {
+        lines.push(format!(
+            "super {} -> {}",
+            a.super_struct.as_deref().unwrap_or("<none>"),
+            b.super_struct.as_deref().unwrap_or("<none>")
+        ));
+    }
+    if a.size != b.size {
+        lines.push(format!("size {:#x} -> {:#x}", a.size, b.size));
+    }
+
+    let props_a = a
+        .properties
+        .iter()
+        .map(|p| (&p.name, p))
+        .collect::<BTreeMap<_, _>>();
+    let props_b = b
+        .properties
+        .iter()
+        .map(|p| (&p.name, p))
+        .collect::<BTreeMap<_, _>>();
+    for name in props_a.keys().chain(props_b.keys()).sorted().dedup() {
+        match (props_a.get(name), props_b.get(name)) {
+            (Some(p), None) => lines.push(
+                format!("- {name}: {} at {:#x}", p.kind, p.offset)
+                    .red()
+                    .to_string(),
+            ),
+            (None, Some(p)) => lines.push(
+                format!("+ {name}: {} at {:#x}", p.kind, p.offset)
+                    .green()
+                    .to_string(),
+            ),
+            (Some(pa), Some(pb)) => {
+                let mut changes = vec![];
+                if pa.kind != pb.kind {
+                    changes.push(format!("kind {} -> {}", pa.kind, pb.kind));
+                }
+                if pa.offset != pb.offset {
+                    changes.push(format!("offset {:#x} -> {:#x}", pa.offset, pb.offset));
+                }
+                if pa.element_size != pb.element_size {
+                    changes.push(format!(
+                        "size {:#x} -> {:#x}",
+                        pa.element_size, pb.element_size
+                    ));
+                }
+                if pa.array_dim != pb.array_dim {
+                    changes.push(format!("array dim {} -> {}", pa.array_dim, pb.array_dim));
+                }
+                if !changes.is_empty() {
+                    lines.push(
+                        format!("~ {name}: {}", changes.join(", "))
+                            .yellow()
+                            .to_string(),
+                    );
+                }
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn property(name: &str, kind: &str, offset: i32) -> PropertyLayout {
+        PropertyLayout {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            offset,
+            element_size: 4,
+            array_dim: 1,
+        }
+    }
+
+    #[test]
+    fn test_diff_struct() {
+        let a = StructLayout {
+            super_struct: Some("/Script/Engine.Actor".to_string()),
+            size: 0x20,
+            properties: vec![
+                property("Health", "FloatProperty", 0x10),
+                property("Armor", "FloatProperty", 0x14),
+            ],
+        };
+        assert!(diff_struct(&a, &a).is_empty());
+
+        let b = StructLayout {
+            super_struct: Some("/Script/Engine.Pawn".to_string()),
+            size: 0x28,
+            properties: vec![
+                property("Health", "DoubleProperty", 0x18),
+                property("Shield", "FloatProperty", 0x20),
+            ],
+        };
+        let lines = diff_struct(&a, &b);
+        let expected = [
+            "super /Script/Engine.Actor -> /Script/Engine.Pawn",
+            "size 0x20 -> 0x28",
+            "- Armor: FloatProperty at 0x14",
+            "~ Health: kind FloatProperty -> DoubleProperty, offset 0x10 -> 0x18",
+            "+ Shield: FloatProperty at 0x20",
+        ];
+        assert_eq!(lines.len(), expected.len());
+        for (line, expected) in lines.iter().zip(expected) {
+            assert!(
+                line.contains(expected),
+                "{line:?} should contain {expected:?}"
+            );
+        }
+    }
+}
@@ -1,36 +1,76 @@
+mod addr;
+mod archive;
+mod bench;
+mod check_hooks;
 mod db;
 mod disassemble;
+mod discover;
+mod export;
+mod function_stats;
+mod golden;
+mod info;
+mod layouts;
+mod maps;
+mod names;
+mod objects_diff;
+mod pointer_scan;
+mod repl;
+mod session;
+mod sig_diff;
+mod writers;
 
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use anyhow::{bail, Context, Result};
-use clap::builder::{
-    IntoResettable, PossibleValue, PossibleValuesParser, TypedValueParser, ValueParser,
-};
-use clap::Parser;
+use clap::{Args, Parser};
 use indicatif::ProgressBar;
 use itertools::Itertools;
 use patricia_tree::StringPatriciaMap;
-use patternsleuth::image::Image;
-use patternsleuth::resolvers::{resolvers, NamedResolver};
+use patternsleuth::image::{Image, MappedFile, SectionFilter};
+use patternsleuth::resolvers::{
+    resolution_plan, resolve_many_modules, resolvers, NamedResolver, ResolveError, Value, Values,
+};
 
 use patternsleuth::scanner::Xref;
-use patternsleuth::symbols::Symbol;
-use patternsleuth::{scanner::Pattern, PatternConfig, Resolution};
+use patternsleuth::{
+    pattern_set::PatternSet, profile::GameProfile, scanner::Pattern, ExpectedMatches,
+    PatternConfig, Resolution, SectionPermissions,
+};
 
 #[derive(Parser)]
 enum Commands {
-    Scan(CommandScan),
+    Scan(Box<CommandScan>),
     Report(CommandReport),
     DiffReport(CommandDiffReport),
     Symbols(CommandSymbols),
     BuildIndex(CommandBuildIndex),
     ViewSymbol(CommandViewSymbol),
+    Identify(CommandIdentify),
     AutoGen(CommandAutoGen),
+    Info(info::CommandInfo),
+    Bench(bench::CommandBench),
+    DumpLayouts(layouts::CommandDumpLayouts),
+    DiffLayouts(layouts::CommandDiffLayouts),
+    ObjectsDiff(objects_diff::CommandObjectsDiff),
+    Addr(addr::CommandAddr),
+    PointerScan(pointer_scan::CommandPointerScan),
+    Maps(maps::CommandMaps),
+    Export(export::CommandExport),
+    CheckHooks(check_hooks::CommandCheckHooks),
+    RecordGolden(golden::CommandRecordGolden),
+    CheckGolden(golden::CommandCheckGolden),
+    SigDiff(sig_diff::CommandSigDiff),
+    Repl(repl::CommandRepl),
+    Session(session::CommandSession),
+    FunctionStats(function_stats::CommandFunctionStats),
+    Name(names::CommandName),
+    GlobalWriters(writers::CommandGlobalWriters),
 }
 
 fn parse_maybe_hex(s: &str) -> Result<usize> {
@@ -39,32 +79,111 @@ fn parse_maybe_hex(s: &str) -> Result<usize> {
         .unwrap_or_else(|| s.parse())?)
 }
 
-fn resolver_parser() -> impl IntoResettable<ValueParser> {
-    fn parse_resolver(s: &str) -> Result<&'static NamedResolver> {
-        resolvers()
-            .find(|res| s == res.name)
-            .context("Resolver not found")
+/// Parse an address range such as "0x1000-0x2000"
+fn parse_range(s: &str) -> Result<Range<usize>> {
+    let (start, end) = s
+        .split_once('-')
+        .with_context(|| format!("expected range as start-end, got {s:?}"))?;
+    let range = parse_maybe_hex(start)?..parse_maybe_hex(end)?;
+    if range.is_empty() {
+        bail!("range {s:?} is empty");
     }
-    fn possible_resolvers() -> Vec<PossibleValue> {
-        resolvers().map(|r| r.name.into()).collect()
+    Ok(range)
+}
+
+/// Resolvers matched by a single `--resolver` argument, either an exact name or a glob such as
+/// `FName*`
+#[derive(Clone)]
+struct ResolverMatch(Vec<&'static NamedResolver>);
+
+fn parse_resolver_match(s: &str) -> Result<ResolverMatch> {
+    let glob = globset::GlobBuilder::new(s)
+        .case_insensitive(true)
+        .build()?
+        .compile_matcher();
+    let matched = resolvers()
+        .filter(|res| glob.is_match(res.name))
+        .collect::<Vec<_>>();
+    if matched.is_empty() {
+        bail!(
+            "no resolver matches {s:?}, possible values: {}",
+            resolvers().map(|res| res.name).join(", ")
+        );
     }
-    PossibleValuesParser::new(possible_resolvers()).map(|v| parse_resolver(&v).unwrap())
+    Ok(ResolverMatch(matched))
 }
 
-#[derive(Parser)]
-struct CommandScan {
+/// Flatten `--resolver` arguments into a list of resolvers without duplicates, in the order they
+/// were first matched
+fn selected_resolvers(matches: &[ResolverMatch]) -> Vec<&'static NamedResolver> {
+    let mut seen = HashSet::new();
+    matches
+        .iter()
+        .flat_map(|m| m.0.iter().copied())
+        .filter(|res| seen.insert(res.name))
+        .collect()
+}
+
+#[derive(Args, Clone, Default)]
+struct GameSelection {
     /// A game to scan (can be specified multiple times). Scans everything if omitted. Supports
     /// globs
     #[arg(short, long)]
     game: Vec<String>,
 
+    /// A directory containing one sub-directory per game (can be specified multiple times).
+    /// Falls back to $PATTERNSLEUTH_GAMES_ROOT, then `games_roots` in ./patternsleuth.json, then
+    /// ./games
+    #[arg(long)]
+    games_root: Vec<PathBuf>,
+
+    /// Paths to game executables to scan directly instead of searching games roots
+    #[arg()]
+    exe: Vec<PathBuf>,
+
+    /// Also scan Unreal Engine games installed through Steam or the Epic Games Launcher
+    #[arg(long)]
+    discover_installed: bool,
+
+    /// A directory containing game profiles named `<game>.toml` (can be specified multiple
+    /// times). Falls back to $PATTERNSLEUTH_PROFILES_DIR, then ./profiles. A `profile.toml` next
+    /// to the game executable takes precedence
+    #[arg(long)]
+    profiles_dir: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+#[command(group(clap::ArgGroup::new("process").args(["pid", "launch"])))]
+struct CommandScan {
+    #[command(flatten)]
+    games: GameSelection,
+
     /// A game process ID to attach to and scan
     #[arg(long)]
     pid: Option<i32>,
 
-    /// A resolver to scan for (can be specified multiple times)
-    #[arg(short, long, value_parser(resolver_parser()))]
-    resolver: Vec<&'static NamedResolver>,
+    /// Launch a game executable suspended and scan it before any of its code runs. The game is
+    /// resumed once the scan is done (Windows only)
+    #[arg(long)]
+    launch: Option<PathBuf>,
+
+    /// Argument to pass to the launched game (can be specified multiple times)
+    #[arg(long, requires = "launch", allow_hyphen_values = true)]
+    launch_arg: Vec<String>,
+
+    /// Suspend the process while reading its memory (only with --pid). With --launch, keep the
+    /// game suspended after the scan until enter is pressed, e.g. to install hooks
+    #[arg(long, requires = "process")]
+    suspend: bool,
+
+    /// Only read from the process, requesting no access beyond reading its memory and querying
+    /// its modules (only with --pid)
+    #[arg(long, requires = "pid", conflicts_with_all = ["suspend", "launch"])]
+    read_only: bool,
+
+    /// A resolver to scan for (can be specified multiple times). Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
 
     /// Show disassembly context for each stage of every match (I recommend only using with
     /// aggressive filters)
@@ -79,41 +198,128 @@ struct CommandScan {
     #[arg(short, long, value_parser(|s: &_| Pattern::new(s)))]
     patterns: Vec<Pattern>,
 
-    /// A path to a JSON pattern config file
+    /// A path to a pattern config file (can be specified multiple times). `.toml` files are
+    /// loaded as pattern sets and merged in order, anything else as the legacy JSON format
     #[arg(long)]
-    pattern_config: Option<PathBuf>,
+    pattern_config: Vec<PathBuf>,
+
+    /// Record the addresses and match counts of the patterns of `.toml` pattern configs in the
+    /// configs, so later scans warn when they change. Rewrites the files, dropping comments
+    #[arg(long, requires = "pattern_config")]
+    update_expectations: bool,
 
     /// An xref to scan for (can be specified multiple times)
     #[arg(short, long, value_parser(|s: &str| parse_maybe_hex(s).map(Xref)))]
     xref: Vec<Xref>,
 
+    /// Number of matches expected of each --patterns and --xref: N for exactly N or N+ for at
+    /// least N. Deviations are flagged in the output and the summary
+    #[arg(long)]
+    expect: Option<ExpectedMatches>,
+
+    /// Only scan patterns and xrefs in sections with these permissions (e.g. "rx" or "rw")
+    #[arg(long)]
+    section_permissions: Option<SectionPermissions>,
+
+    /// Only scan patterns and xrefs in this virtual address range, e.g. "0x140001000-0x140002000"
+    /// (can be specified multiple times)
+    #[arg(long, value_parser(parse_range))]
+    range: Vec<Range<usize>>,
+
+    /// Drop matches in executable sections which don't look like code, e.g. in `int3` padding
+    /// between functions
+    #[arg(long)]
+    filter_code: bool,
+
     /// Load and display symbols from PDBs when available (can be slow)
     #[arg(long)]
     symbols: bool,
 
+    /// Where to look for PDBs besides next to the executable, in the `_NT_SYMBOL_PATH` format
+    /// e.g. `srv*C:\symbols*https://msdl.microsoft.com/download/symbols`. Defaults to
+    /// `_NT_SYMBOL_PATH`
+    #[arg(long, requires = "symbols")]
+    symbol_path: Option<String>,
+
     /// Skip parsing of exception table
     #[arg(long)]
     skip_exceptions: bool,
 
+    /// Skip sections and exception entries which can't be read (e.g. deliberately corrupted by
+    /// copy protection) instead of failing to load the executable
+    #[arg(long)]
+    tolerant: bool,
+
+    /// Only load sections with this name, e.g. ".text" (can be specified multiple times)
+    #[arg(long)]
+    section: Vec<String>,
+
+    /// Skip loading sections larger than this many bytes, e.g. embedded assets
+    #[arg(long)]
+    max_section_size: Option<usize>,
+
+    /// Also scan data appended to the executable after its sections, e.g. an embedded pak,
+    /// loaded as a section named "overlay"
+    #[arg(long)]
+    include_overlay: bool,
+
     /// Show scan summary
     #[arg(long)]
     summary: bool,
 
+    /// Write resolutions to a JSON file keyed by game and resolver name, along with the values of
+    /// resolvers which don't resolve to addresses (e.g. struct sizes) under `$values`. Otherwise
+    /// uses the same format as `report` so the output can be compared with `diff-report`
+    #[arg(long)]
+    json: Option<PathBuf>,
+
     /// Show scan progress
     #[arg(long)]
     progress: bool,
+
+    /// Number of games to scan concurrently. Output of each game is printed once all games
+    /// have been scanned, in the same order as when scanning serially
+    #[arg(short, long, default_value = "1")]
+    jobs: usize,
+
+    /// Keep running and rescan whenever game executables or the pattern config change, printing
+    /// differences against the previous scan
+    #[arg(long)]
+    watch: bool,
+
+    /// Polling interval in seconds used by --watch
+    #[arg(long, default_value = "2")]
+    watch_interval: u64,
+
+    /// Print the resolvers which would run, including dependencies, without scanning anything
+    #[arg(long)]
+    plan: bool,
+}
+
+impl CommandScan {
+    fn section_filter(&self) -> Option<SectionFilter> {
+        if self.section.is_empty() && self.max_section_size.is_none() {
+            return None;
+        }
+        let filter = self
+            .section
+            .iter()
+            .fold(SectionFilter::default(), |filter, name| filter.name(name));
+        Some(match self.max_section_size {
+            Some(max) => filter.max_size(max),
+            None => filter,
+        })
+    }
 }
 
 #[derive(Parser)]
 struct CommandReport {
-    /// A game to scan (can be specified multiple times). Scans everything if omitted. Supports
-    /// globs
-    #[arg(short, long)]
-    game: Vec<String>,
+    #[command(flatten)]
+    games: GameSelection,
 
-    /// A resolver to scan for (can be specified multiple times)
-    #[arg(short, long, value_parser(resolver_parser()))]
-    resolver: Vec<&'static NamedResolver>,
+    /// A resolver to scan for (can be specified multiple times). Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
 }
 
 #[derive(Parser)]
@@ -127,10 +333,8 @@ struct CommandDiffReport {
 
 #[derive(Parser)]
 struct CommandSymbols {
-    /// A game to scan (can be specified multiple times). Scans everything if omitted. Supports
-    /// globs
-    #[arg(short, long)]
-    game: Vec<String>,
+    #[command(flatten)]
+    games: GameSelection,
 
     #[arg(short, long)]
     symbol: Vec<regex::Regex>,
@@ -138,10 +342,8 @@ struct CommandSymbols {
 
 #[derive(Parser)]
 struct CommandBuildIndex {
-    /// A game to scan (can be specified multiple times). Scans everything if omitted. Supports
-    /// globs
-    #[arg(short, long)]
-    game: Vec<String>,
+    #[command(flatten)]
+    games: GameSelection,
 }
 
 #[derive(Parser)]
@@ -186,12 +388,32 @@ struct CommandViewSymbol {
     #[arg(short, long)]
     function: Vec<FunctionSpec>,
 
-    #[arg(short, long, value_parser(resolver_parser()))]
-    resolver: Vec<&'static NamedResolver>,
+    /// A resolver to run (can be specified multiple times). Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
 
     /// Whether to show symbols in function disassembly
     #[arg(long)]
     show_symbols: bool,
+
+    /// Generate patterns from the instructions shared by all found functions and count their
+    /// matches in the executable of each function
+    #[arg(long)]
+    generate: bool,
+}
+
+#[derive(Parser)]
+struct CommandIdentify {
+    /// Executable containing the functions
+    path: PathBuf,
+
+    /// Address of a function to identify (can be specified multiple times)
+    #[arg(required = true, value_parser(parse_maybe_hex))]
+    function: Vec<usize>,
+
+    /// Number of candidate symbols to show per function
+    #[arg(short = 'n', long, default_value = "10")]
+    count: usize,
 }
 
 #[derive(Parser)]
@@ -224,13 +446,32 @@ fn main() -> Result<()> {
         .init();
 
     match Commands::parse() {
-        Commands::Scan(command) => scan(command),
+        Commands::Scan(command) => scan(*command),
         Commands::Report(command) => report(command),
         Commands::DiffReport(command) => diff_report(command),
         Commands::Symbols(command) => symbols(command),
         Commands::BuildIndex(command) => db::build(command),
         Commands::ViewSymbol(command) => db::view(command),
+        Commands::Identify(command) => db::identify(command),
         Commands::AutoGen(command) => db::auto_gen(command),
+        Commands::Info(command) => info::info(command),
+        Commands::Bench(command) => bench::bench(command),
+        Commands::DumpLayouts(command) => layouts::dump_layouts(command),
+        Commands::DiffLayouts(command) => layouts::diff_layouts(command),
+        Commands::ObjectsDiff(command) => objects_diff::objects_diff(command),
+        Commands::Addr(command) => addr::addr(command),
+        Commands::PointerScan(command) => pointer_scan::pointer_scan(command),
+        Commands::Maps(command) => maps::maps(command),
+        Commands::Export(command) => export::export(command),
+        Commands::CheckHooks(command) => check_hooks::check_hooks(command),
+        Commands::RecordGolden(command) => golden::record_golden(command),
+        Commands::CheckGolden(command) => golden::check_golden(command),
+        Commands::SigDiff(command) => sig_diff::sig_diff(command),
+        Commands::Repl(command) => repl::repl(command),
+        Commands::Session(command) => session::session(command),
+        Commands::FunctionStats(command) => function_stats::function_stats(command),
+        Commands::Name(command) => names::name(command),
+        Commands::GlobalWriters(command) => writers::global_writers(command),
     }
 }
 
@@ -243,41 +484,194 @@ impl std::fmt::Display for Sig {
     }
 }
 
+/// Per game textual results of a scan keyed by sig or resolver name, used by --watch to print
+/// what changed between consecutive scans
+type ScanSnapshot = BTreeMap<String, BTreeMap<String, String>>;
+
 fn scan(command: CommandScan) -> Result<()> {
+    if command.plan {
+        let selected = if command.resolver.is_empty() {
+            resolvers().collect::<Vec<_>>()
+        } else {
+            selected_resolvers(&command.resolver)
+        };
+        for resolver in resolution_plan(&selected) {
+            let selected = selected.iter().any(|r| r.name == resolver.name);
+            println!(
+                "{}{}",
+                resolver.name,
+                if selected { "" } else { " (dependency)" }
+            );
+        }
+        return Ok(());
+    }
+    if !command.watch {
+        scan_once(&command)?;
+        return Ok(());
+    }
+    if command.pid.is_some() || command.launch.is_some() {
+        bail!("--watch is not supported when scanning a process");
+    }
+
+    use colored::Colorize;
+
+    let interval = std::time::Duration::from_secs(command.watch_interval.max(1));
+    let mut previous = scan_once(&command)?;
+    let mut state = watch_state(&command)?;
+    loop {
+        println!("watching for changes...");
+        loop {
+            std::thread::sleep(interval);
+            let current = watch_state(&command)?;
+            if current != state {
+                // wait for writes to settle so partially copied files aren't scanned
+                state = current;
+                loop {
+                    std::thread::sleep(interval);
+                    let current = watch_state(&command)?;
+                    if current == state {
+                        break;
+                    }
+                    state = current;
+                }
+                break;
+            }
+        }
+
+        let snapshot = scan_once(&command)?;
+
+        let mut changes = vec![];
+        for game in previous.keys().chain(snapshot.keys()).sorted().dedup() {
+            match (previous.get(game), snapshot.get(game)) {
+                (Some(_), None) => changes.push(format!("{game}: {}", "removed".red())),
+                (None, Some(_)) => changes.push(format!("{game}: {}", "added".green())),
+                (Some(prev), Some(cur)) => {
+                    for key in prev.keys().chain(cur.keys()).sorted().dedup() {
+                        let (p, c) = (prev.get(key), cur.get(key));
+                        if p != c {
+                            changes.push(format!(
+                                "{game} {key}:\n  {}\n  {}",
+                                format!("- {}", p.map(String::as_str).unwrap_or("<none>")).red(),
+                                format!("+ {}", c.map(String::as_str).unwrap_or("<none>")).green(),
+                            ));
+                        }
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        if changes.is_empty() {
+            println!("no changes since previous scan");
+        } else {
+            println!("changes since previous scan:");
+            for change in changes {
+                println!("{change}");
+            }
+        }
+
+        previous = snapshot;
+    }
+}
+
+/// Modification time and size of every watched file. Compared between polls to detect changes.
+fn watch_state(
+    command: &CommandScan,
+) -> Result<Vec<(PathBuf, Option<std::time::SystemTime>, u64)>> {
+    let mut paths = get_games(&command.games)?
+        .into_iter()
+        .flat_map(|g| std::iter::once(g.exe_path).chain(g.modules))
+        .collect_vec();
+    paths.extend(command.pattern_config.iter().cloned());
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let meta = fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map(|m| m.len()).unwrap_or_default();
+            (path, modified, len)
+        })
+        .collect())
+}
+
+/// Patterns of a legacy JSON pattern config, mapping symbols to lists of patterns
+fn read_json_pattern_config(path: &Path) -> Result<Vec<PatternConfig<Sig>>> {
+    let file = fs::read_to_string(path)
+        .with_context(|| format!("failed to read pattern config {}", path.display()))?;
+    let config: HashMap<String, Vec<String>> = serde_json::from_str(&file)
+        .with_context(|| format!("failed to parse pattern config {}", path.display()))?;
+
+    let mut patterns = vec![];
+    for (symbol, symbol_patterns) in config {
+        for (i, p) in symbol_patterns.into_iter().enumerate() {
+            let pattern = Pattern::new(&p).with_context(|| {
+                format!("invalid pattern {p:?} for {symbol} in {}", path.display())
+            })?;
+            patterns.push(PatternConfig::new(
+                Sig(format!("file {symbol}")),
+                format!("#{i} {symbol}"),
+                None,
+                pattern,
+            ));
+        }
+    }
+    Ok(patterns)
+}
+
+fn scan_once(command: &CommandScan) -> Result<ScanSnapshot> {
+    let mut pattern_set = PatternSet::default();
+    for path in &command.pattern_config {
+        if path.extension().map(|e| e == "toml").unwrap_or_default() {
+            pattern_set.merge(PatternSet::load(path)?);
+        }
+    }
+
+    let json_patterns = command
+        .pattern_config
+        .iter()
+        .filter(|path| path.extension().map(|e| e != "toml").unwrap_or(true))
+        .map(|path| read_json_pattern_config(path))
+        .flatten_ok()
+        .collect::<Result<Vec<_>>>()?;
+
     let include_default = command.patterns.is_empty() && command.xref.is_empty();
     // TODO warn if empty?
     let patterns = command
         .patterns
-        .into_iter()
+        .iter()
+        .cloned()
         .enumerate()
         .map(|(i, p)| PatternConfig::new(Sig("arg".to_string()), format!("pattern {i}"), None, p))
-        .chain(command.xref.into_iter().enumerate().map(|(i, p)| {
+        .chain(command.xref.iter().cloned().enumerate().map(|(i, p)| {
             PatternConfig::xref(Sig("arg".to_string()), format!("xref {i}"), None, p)
         }))
-        .chain(command.pattern_config.into_iter().flat_map(|path| {
-            let file = std::fs::read_to_string(path).unwrap();
-            let config: HashMap<String, Vec<String>> = serde_json::from_str(&file).unwrap();
-
-            config.into_iter().flat_map(|(symbol, patterns)| {
-                patterns.into_iter().enumerate().map(move |(i, p)| {
-                    PatternConfig::new(
-                        Sig(format!("file {symbol}")),
-                        format!("#{i} {symbol}"),
-                        None,
-                        Pattern::new(p).unwrap(),
-                    )
-                })
-            })
-        }))
+        .map(|config| match command.expect {
+            Some(expected) => config.expect(expected),
+            None => config,
+        })
+        .chain(pattern_set.pattern_configs(|entry| Sig(format!("file {}", entry.name)))?)
+        .chain(json_patterns)
+        .map(|config| match command.section_permissions {
+            Some(permissions) => config.permissions(permissions),
+            None => config,
+        })
+        .map(|config| {
+            command
+                .range
+                .iter()
+                .fold(config, |config, range| config.range(range.clone()))
+        })
+        .map(|config| match command.filter_code {
+            true => config.filter_code(),
+            false => config,
+        })
         .collect_vec();
 
     let resolvers = if command.resolver.is_empty() && include_default {
         resolvers().collect::<Vec<_>>()
     } else {
-        command.resolver
+        selected_resolvers(&command.resolver)
     };
-    let dyn_resolvers = resolvers.iter().map(|res| res.getter).collect::<Vec<_>>();
-
     let sigs = patterns
         .iter()
         .map(|p| p.sig.clone())
@@ -287,11 +681,11 @@ fn scan(command: CommandScan) -> Result<()> {
 
     let mut all: HashMap<(String, (&Sig, &String)), Vec<Resolution>> = HashMap::new();
     let mut all_resolutions: HashMap<String, _> = Default::default();
+    let mut snapshot = ScanSnapshot::default();
 
     use colored::Colorize;
     use indicatif::ProgressIterator;
-    use itertools::join;
-    use prettytable::{format, row, Cell, Row, Table};
+    use prettytable::{Cell, Row, Table};
 
     enum Output {
         Stdout,
@@ -309,246 +703,137 @@ fn scan(command: CommandScan) -> Result<()> {
 
     let mut games_vec = vec![];
 
+    #[cfg(windows)]
+    let launched = command
+        .launch
+        .as_ref()
+        .map(|exe| patternsleuth::process::external::launch_suspended(exe, &command.launch_arg))
+        .transpose()?;
+    #[cfg(windows)]
+    if let Some(launched) = &launched {
+        games_vec.push(GameEntry::Process(GameProcessEntry {
+            pid: launched.pid(),
+        }));
+    }
+    #[cfg(not(windows))]
+    if command.launch.is_some() {
+        bail!("--launch is only supported on Windows");
+    }
+
     if let Some(pid) = command.pid {
         games_vec.push(GameEntry::Process(GameProcessEntry { pid }));
-    } else {
-        games_vec.extend(get_games(command.game)?.into_iter().map(GameEntry::File));
+    } else if command.launch.is_none() {
+        games_vec.extend(get_games(&command.games)?.into_iter().map(GameEntry::File));
     }
 
-    let (output, iter): (_, Box<dyn Iterator<Item = _>>) = if command.progress {
-        let progress = ProgressBar::new(games_vec.len() as u64);
-        (
-            Output::Progress(progress.clone()),
-            Box::new(games_vec.iter().progress_with(progress)),
-        )
-    } else {
-        (Output::Stdout, Box::new(games_vec.iter()))
+    let setup = ScanSetup {
+        command,
+        patterns: &patterns,
+        pattern_set: &pattern_set,
+        sigs: &sigs,
+        resolvers: &resolvers,
     };
 
-    for game in iter {
-        #[allow(unused_assignments)]
-        let mut bin_data = None;
-
-        let (name, exe) = match game {
-            GameEntry::File(GameFileEntry { name, exe_path }) => {
-                output.println(format!("{:?} {:?}", name, exe_path.display()));
-
-                bin_data = Some(fs::read(exe_path)?);
-
-                (Cow::Borrowed(name), {
-                    let bin_data = bin_data.as_ref().unwrap();
-                    let builder = Image::builder().functions(!command.skip_exceptions);
-                    let exe = if command.symbols {
-                        builder.symbols(exe_path).build(bin_data)
-                    } else {
-                        builder.build(bin_data)
-                    };
-                    match exe {
-                        Ok(exe) => exe,
-                        Err(err) => {
-                            output.println(format!("err reading {}: {}", exe_path.display(), err));
-                            continue;
-                        }
-                    }
-                })
-            }
-            GameEntry::Process(GameProcessEntry { pid }) => {
-                output.println(format!("PID={pid}"));
-
-                (
-                    Cow::Owned(format!("PID={pid}")),
-                    patternsleuth::process::external::read_image_from_pid(*pid)?,
-                )
-            }
+    let scans = if command.jobs > 1 {
+        scan_games_parallel(&setup, &games_vec)?
+    } else {
+        let (output, iter): (_, Box<dyn Iterator<Item = _>>) = if command.progress {
+            let progress = ProgressBar::new(games_vec.len() as u64);
+            (
+                Output::Progress(progress.clone()),
+                Box::new(games_vec.iter().progress_with(progress)),
+            )
+        } else {
+            (Output::Stdout, Box::new(games_vec.iter()))
         };
 
-        games.insert(name.to_string());
-
-        let scan = exe.scan(&patterns)?;
-
-        // group results by Sig
-        let folded_scans = scan
-            .results
-            .iter()
-            .map(|(config, m)| (&config.sig, (config, m)))
-            .fold(HashMap::new(), |mut map: HashMap<_, Vec<_>>, (k, v)| {
-                map.entry(k).or_default().push(v);
-                map
-            });
-
-        let mut table = Table::new();
-        table.set_titles(row!["sig", "offline scan"]);
-
-        for sig in &sigs {
-            let mut cells = vec![];
-            cells.push(Cell::new(&sig.to_string()));
-
-            if let Some(sig_scans) = folded_scans.get(&sig) {
-                if command.disassemble {
-                    let mut table = Table::new();
-                    table.set_format(*format::consts::FORMAT_NO_BORDER);
-                    for m in sig_scans.iter() {
-                        let mut cells = vec![];
-                        cells.push(Cell::new(&format!(
-                            "{}\n{}",
-                            m.0.name,
-                            disassemble::disassemble(
-                                &exe,
-                                m.1.address,
-                                m.0.scan.scan_type.get_pattern()
-                            )
-                        )));
-                        table.add_row(Row::new(cells));
-                    }
-                    cells.push(Cell::new(&table.to_string()));
-                } else if command.disassemble_merged {
-                    cells.push(Cell::new({
-                        let cells = sig_scans
-                            .iter()
-                            .fold(
-                                HashMap::<&Resolution, HashMap<&str, usize>>::new(),
-                                |mut map, m| {
-                                    *map.entry(m.1).or_default().entry(&m.0.name).or_default() += 1;
-                                    map
-                                },
-                            )
-                            .iter()
-                            // sort by pattern name, then match address
-                            .sorted_by_key(|&data| data.0)
-                            .map(|(m, counts)| {
-                                let dis = disassemble::disassemble(&exe, m.address, None);
-
-                                let mut lines = vec![];
-                                for (name, count) in counts.iter().sorted_by_key(|e| e.0) {
-                                    let count = if *count > 1 {
-                                        format!(" (x{count})")
-                                    } else {
-                                        "".to_string()
-                                    };
-
-                                    lines.push(format!("{:?}{}", name, count).normal().to_string());
-                                }
-                                lines.push(dis);
-
-                                Cell::new(&join(lines, "\n"))
-                            })
-                            .collect::<Vec<_>>();
-
-                        let mut table = Table::new();
-                        table.set_format(*format::consts::FORMAT_NO_BORDER);
-
-                        table.add_row(Row::new(cells));
-
-                        &table.to_string()
-                    }));
-                } else {
-                    cells.push(Cell::new({
-                        let mut lines = sig_scans
-                            .iter()
-                            // group and count matches by (pattern name, address)
-                            .fold(
-                                HashMap::<(&String, &Resolution), usize>::new(),
-                                |mut map, m| {
-                                    *map.entry((&m.0.name, m.1)).or_default() += 1;
-                                    map
-                                },
-                            )
-                            .iter()
-                            // sort by pattern name, then match address
-                            .sorted_by_key(|&data| data.0)
-                            .map(|(m, count)| {
-                                // add count indicator if more than 1
-                                let count = if *count > 1 {
-                                    format!(" (x{count})")
-                                } else {
-                                    "".to_string()
-                                };
-
-                                (
-                                    format!("{:016x} {:?}{}", m.1.address, m.0, count)
-                                        .normal()
-                                        .to_string(),
-                                    exe.symbols
-                                        .as_ref()
-                                        .and_then(|symbols| symbols.get(&m.1.address)),
-                                )
-                            })
-                            .collect::<Vec<_>>();
-                        let max_len = lines.iter().map(|(line, _)| line.len()).max();
-                        for (line, symbol) in &mut lines {
-                            if let Some(symbol) = symbol {
-                                line.push_str(&format!(
-                                    "{}{}",
-                                    " ".repeat(1 + max_len.unwrap() - line.len()),
-                                    symbol.name.bright_yellow()
-                                ));
-                            }
-                        }
-                        &join(lines.iter().map(|(line, _)| line), "\n").to_string()
-                    }));
-                }
-            } else {
-                #[allow(clippy::unnecessary_to_owned)]
-                cells.push(Cell::new(&"not found".red().to_string()));
+        let mut scans = vec![];
+        for game in iter {
+            if let Some(scan) = scan_game(&setup, game, &|line| output.println(line))? {
+                scans.push(scan);
             }
-
-            table.add_row(Row::new(cells));
-        }
-
-        let game_name = match game {
-            GameEntry::File(GameFileEntry { name, .. }) => name.clone(),
-            GameEntry::Process(GameProcessEntry { pid }) => format!("pid={pid}"),
-        };
-
-        let resolution = tracing::info_span!("scan", game = game_name)
-            .in_scope(|| exe.resolve_many(&dyn_resolvers));
-
-        for (resolver, resolution) in resolvers.iter().zip(&resolution) {
-            table.add_row(Row::new(
-                [
-                    Cell::new(resolver.name),
-                    match resolution {
-                        Ok(res) => Cell::new(&format!("{:#x?}", res)),
-                        Err(err) =>
-                        {
-                            #[allow(clippy::unnecessary_to_owned)]
-                            Cell::new(&format!("{:x?}", err).red().to_string())
-                        }
-                    },
-                ]
-                .to_vec(),
-            ));
         }
+        scans
+    };
 
-        if !resolution.is_empty() {
-            all_resolutions.insert(name.to_string(), resolution);
+    #[cfg(windows)]
+    if let Some(launched) = launched {
+        if command.suspend {
+            println!("PID={} is suspended, press enter to resume", launched.pid());
+            std::io::stdin().read_line(&mut String::new())?;
         }
+        launched.resume()?;
+    }
 
-        output.println(table.to_string());
+    if command.update_expectations {
+        update_expectations(command, &scans)?;
+    }
 
+    for scan in scans {
+        games.insert(scan.name.clone());
+        snapshot.insert(scan.name.clone(), scan.snapshot);
+        if !scan.resolution.is_empty() {
+            all_resolutions.insert(scan.name.clone(), scan.resolution);
+        }
         // fold current game scans into summary scans
         scan.results.into_iter().fold(&mut all, |map, m| {
-            map.entry((name.to_string(), (&m.0.sig, &m.0.name)))
+            map.entry((scan.name.clone(), (&m.0.sig, &m.0.name)))
                 .or_default()
                 .push(m.1);
             map
         });
     }
 
-    // force any progress output to be dropped
     let output = Output::Stdout;
 
+    if let Some(path) = &command.json {
+        let json = all_resolutions
+            .iter()
+            .map(|(game, resolution)| {
+                let values = resolvers
+                    .iter()
+                    .zip(resolution)
+                    .filter_map(|(r, res)| Some(res.as_ref().ok()?.values().prefixed(r.name)))
+                    .flatten()
+                    .collect();
+                let resolutions = resolvers
+                    .iter()
+                    .map(|r| r.name.to_string())
+                    .zip(resolution)
+                    .collect();
+                (
+                    game.as_str(),
+                    ReportGame {
+                        values,
+                        resolutions,
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        fs::write(path, serde_json::to_vec_pretty(&json)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
     if command.summary {
         #[derive(Debug, Default)]
         struct Summary {
             matches: usize,
+            /// Games matching a different number of times than the pattern declares
+            mismatches: usize,
         }
         impl Summary {
             fn format(&self) -> String {
-                if self.matches == 0 {
+                let matches = if self.matches == 0 {
                     "none".to_owned()
                 } else {
                     format!("M={}", self.matches)
+                };
+                if self.mismatches == 0 {
+                    matches
+                } else {
+                    format!("{matches} mismatch={}", self.mismatches)
+                        .red()
+                        .to_string()
                 }
             }
         }
@@ -579,13 +864,14 @@ fn scan(command: CommandScan) -> Result<()> {
                 .iter()
                 .map(|conf| {
                     let res = all.get(&(game.to_string(), (&conf.sig, &conf.name)));
-                    if let Some(res) = res {
-                        for res in res {
-                            matched_addresses.insert(res.address);
-                        }
-                        Summary { matches: res.len() }
-                    } else {
-                        Summary { matches: 0 }
+                    let matches = res.map_or(0, |res| res.len());
+                    for res in res.into_iter().flatten() {
+                        matched_addresses.insert(res.address);
+                    }
+                    let mismatch = conf.expected.is_some_and(|e| !e.matches(matches));
+                    Summary {
+                        matches,
+                        mismatches: mismatch as usize,
                     }
                 })
                 .collect();
@@ -594,6 +880,7 @@ fn scan(command: CommandScan) -> Result<()> {
                 if s.matches > 0 {
                     totals[i].matches += 1;
                 }
+                totals[i].mismatches += s.mismatches;
             }
 
             match matched_addresses.len() {
@@ -619,7 +906,9 @@ fn scan(command: CommandScan) -> Result<()> {
                         Ok(res) => row.push(Cell::new(&format!("{:x?}", res))),
                         Err(err) => {
                             #[allow(clippy::unnecessary_to_owned)]
-                            row.push(Cell::new(&format!("{:x?}", err).red().to_string()));
+                            row.push(Cell::new(
+                                &format!("{:x?}", err.root_cause()).red().to_string(),
+                            ));
                         }
                     }
                 }
@@ -653,21 +942,638 @@ fn scan(command: CommandScan) -> Result<()> {
         output.println(summary.to_string());
     }
 
+    Ok(snapshot)
+}
+
+/// Record the matches of the patterns of every `.toml` pattern config as their expected matches
+fn update_expectations(command: &CommandScan, scans: &[GameScan]) -> Result<()> {
+    let toml_paths = command
+        .pattern_config
+        .iter()
+        .filter(|path| path.extension().is_some_and(|e| e == "toml"));
+    for path in toml_paths {
+        let mut set = PatternSet::load(path)?;
+        for entry in &mut set.patterns {
+            for scan in scans {
+                let addresses = scan
+                    .results
+                    .iter()
+                    .filter(|(c, _)| c.name == entry.name)
+                    .map(|(_, m)| m.address)
+                    .collect_vec();
+                entry.record_expected(&scan.name, &addresses);
+            }
+        }
+        set.save(path)?;
+        println!("updated expectations in {}", path.display());
+    }
     Ok(())
 }
 
+/// Patterns and resolvers shared by the scans of every game
+struct ScanSetup<'a> {
+    command: &'a CommandScan,
+    patterns: &'a [PatternConfig<Sig>],
+    pattern_set: &'a PatternSet,
+    sigs: &'a HashSet<Sig>,
+    resolvers: &'a [&'static NamedResolver],
+}
+
+/// Results of scanning a single game, merged into the overall results in game order
+struct GameScan<'a> {
+    name: String,
+    snapshot: BTreeMap<String, String>,
+    resolution:
+        Vec<patternsleuth::resolvers::Result<Arc<dyn patternsleuth::resolvers::Resolution>>>,
+    results: Vec<(&'a PatternConfig<Sig>, Resolution)>,
+}
+
+/// Read the image of a scanned process. A launched game is still suspended so its module list
+/// isn't set up yet and the executable is found from its mapping instead.
+fn read_process_image<'data>(command: &CommandScan, pid: i32) -> Result<Image<'data>> {
+    use patternsleuth::process::external;
+
+    #[cfg(windows)]
+    if let Some(exe) = &command.launch {
+        let exe_name = exe.file_name().context("exe path has no file name")?;
+        return external::read_image_from_suspended(pid, &exe_name.to_string_lossy());
+    }
+    external::read_image_from_pid_with(
+        pid,
+        external::ReadOptions {
+            suspend: command.suspend,
+            read_only: command.read_only,
+        },
+    )
+}
+
+/// Scan a single game, writing its output through `emit`. Returns `None` if the executable
+/// could not be read.
+fn scan_game<'a>(
+    setup: &ScanSetup<'a>,
+    game: &GameEntry,
+    emit: &dyn Fn(String),
+) -> Result<Option<GameScan<'a>>> {
+    use colored::Colorize;
+    use itertools::join;
+    use prettytable::{format, row, Cell, Row, Table};
+
+    let command = setup.command;
+
+    #[allow(unused_assignments)]
+    let mut bin_data = None;
+
+    let (name, exe) = match game {
+        GameEntry::File(GameFileEntry {
+            name,
+            exe_path,
+            profile,
+            ..
+        }) => {
+            emit(format!("{:?} {:?}", name, exe_path.display()));
+
+            bin_data = Some(MappedFile::open(exe_path)?);
+
+            (Cow::Borrowed(name), {
+                let bin_data = bin_data.as_ref().unwrap();
+                let builder = Image::builder()
+                    .functions(!command.skip_exceptions)
+                    .overlay(command.include_overlay)
+                    .tolerant(command.tolerant);
+                let builder = match profile.as_ref().map(|p| p.base_address()).transpose()? {
+                    Some(Some(base_address)) => builder.base_address(base_address),
+                    _ => builder,
+                };
+                let builder = match command.section_filter() {
+                    Some(filter) => builder.sections(filter),
+                    None => builder,
+                };
+                let exe = if command.symbols {
+                    let builder = builder.symbols(exe_path);
+                    let builder = match &command.symbol_path {
+                        Some(path) => builder.symbol_providers(
+                            patternsleuth::symbols::SymbolProviders::from_symbol_path(path),
+                        ),
+                        None => builder,
+                    };
+                    builder.build_mapped(bin_data)
+                } else {
+                    builder.build_mapped(bin_data)
+                };
+                match exe {
+                    Ok(exe) => exe,
+                    Err(err) => {
+                        emit(format!("err reading {}: {}", exe_path.display(), err));
+                        return Ok(None);
+                    }
+                }
+            })
+        }
+        GameEntry::Process(GameProcessEntry { pid }) => {
+            emit(format!("PID={pid}"));
+
+            (
+                Cow::Owned(format!("PID={pid}")),
+                read_process_image(command, *pid)?,
+            )
+        }
+    };
+
+    // resolvers are also run against other binaries of the game which may contain the engine
+    let module_data = match game {
+        GameEntry::File(GameFileEntry { modules, .. }) => modules
+            .iter()
+            .map(|path| -> Result<_> { Ok((path, MappedFile::open(path)?)) })
+            .collect::<Result<Vec<_>>>()?,
+        GameEntry::Process(_) => vec![],
+    };
+    let mut modules = vec![];
+    for (path, data) in &module_data {
+        let builder = Image::builder()
+            .functions(!command.skip_exceptions)
+            .tolerant(command.tolerant);
+        let builder = match command.section_filter() {
+            Some(filter) => builder.sections(filter),
+            None => builder,
+        };
+        match builder.build_mapped(data) {
+            Ok(image) => modules.push((module_name(path), image)),
+            Err(err) => emit(format!("err reading {}: {}", path.display(), err)),
+        }
+    }
+
+    for warning in &exe.warnings {
+        emit(format!("warning: {warning}").yellow().to_string());
+    }
+    if let Some(report) = exe.detect_obfuscation() {
+        emit(
+            format!("warning: image appears obfuscated: {report}")
+                .yellow()
+                .to_string(),
+        );
+    }
+
+    let profile = match game {
+        GameEntry::File(GameFileEntry { profile, .. }) => profile.as_ref(),
+        GameEntry::Process(_) => None,
+    };
+    if let Some(version) = profile.and_then(|p| p.engine_version.as_ref()) {
+        emit(format!("engine version {version} (from game profile)"));
+    }
+
+    let scan = exe.scan(setup.patterns)?;
+
+    for config in setup.patterns {
+        let Some(expected) = config.expected else {
+            continue;
+        };
+        let count = scan
+            .results
+            .iter()
+            .filter(|(c, _)| std::ptr::eq(*c, config))
+            .count();
+        if !expected.matches(count) {
+            emit(
+                format!(
+                    "mismatch: {:?} matched {count} times, expected {expected}",
+                    config.name
+                )
+                .red()
+                .to_string(),
+            );
+        }
+    }
+    for (pattern_name, entry) in setup.pattern_set.by_name() {
+        if let Some(expected) = entry.expected_addresses(&name)? {
+            let addresses = scan
+                .results
+                .iter()
+                .filter(|(c, _)| c.name == pattern_name)
+                .map(|(_, m)| m.address)
+                .sorted()
+                .dedup()
+                .collect_vec();
+            if addresses != expected {
+                let list =
+                    |addresses: &[usize]| join(addresses.iter().map(|a| format!("{a:#x}")), ", ");
+                emit(
+                    format!(
+                        "warning: {pattern_name:?} matched at [{}], expected [{}]",
+                        list(&addresses),
+                        list(&expected)
+                    )
+                    .yellow()
+                    .to_string(),
+                );
+            }
+        }
+    }
+
+    let mut game_snapshot = BTreeMap::new();
+
+    // user labels to annotate disassembly with, see `name`
+    let names = match game {
+        GameEntry::File(GameFileEntry { exe_path, .. })
+            if command.disassemble || command.disassemble_merged =>
+        {
+            Some(names::Names::open(exe_path)?)
+        }
+        _ => None,
+    };
+
+    // game specific patterns of the profile are reported on their own as they aren't known to
+    // the other games
+    if let Some(profile) = profile {
+        let profile_set = profile.pattern_set();
+        let configs = profile_set.pattern_configs(|_| ())?;
+        let profile_scan = exe.scan(&configs)?;
+        for (entry, config) in profile_set.patterns.iter().zip(&configs) {
+            let addresses = profile_scan
+                .results
+                .iter()
+                .filter(|(c, _)| c.name == entry.name)
+                .map(|(_, m)| m.address)
+                .sorted()
+                .dedup()
+                .collect_vec();
+            let line = format!(
+                "profile {:?}: {}",
+                entry.name,
+                join(addresses.iter().map(|a| format!("{a:016x}")), ", ")
+            );
+            match config.expected {
+                Some(expected) if !expected.matches(addresses.len()) => emit(
+                    format!("{line} (expected {expected} matches)")
+                        .red()
+                        .to_string(),
+                ),
+                _ => emit(line),
+            }
+            game_snapshot.insert(
+                format!("profile {}", entry.name),
+                join(addresses.iter().map(|a| format!("{a:016x}")), ", "),
+            );
+        }
+    }
+
+    // group results by Sig
+    let folded_scans = scan
+        .results
+        .iter()
+        .map(|(config, m)| (&config.sig, (config, m)))
+        .fold(HashMap::new(), |mut map: HashMap<_, Vec<_>>, (k, v)| {
+            map.entry(k).or_default().push(v);
+            map
+        });
+
+    let mut table = Table::new();
+    table.set_titles(row!["sig", "offline scan"]);
+
+    for sig in setup.sigs {
+        let mut cells = vec![];
+        cells.push(Cell::new(&sig.to_string()));
+
+        if let Some(sig_scans) = folded_scans.get(&sig) {
+            game_snapshot.insert(
+                sig.to_string(),
+                join(
+                    sig_scans
+                        .iter()
+                        .map(|m| (&m.0.name, m.1.address))
+                        .sorted()
+                        .dedup()
+                        .map(|(name, address)| format!("{address:016x} {name:?}")),
+                    ", ",
+                ),
+            );
+            if command.disassemble {
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER);
+                for m in sig_scans.iter() {
+                    let mut cells = vec![];
+                    cells.push(Cell::new(&format!(
+                        "{}\n{}",
+                        m.0.name,
+                        disassemble::disassemble(
+                            &exe,
+                            m.1.address,
+                            m.0.scan.scan_type.get_pattern(),
+                            names.as_ref(),
+                        )
+                    )));
+                    table.add_row(Row::new(cells));
+                }
+                cells.push(Cell::new(&table.to_string()));
+            } else if command.disassemble_merged {
+                cells.push(Cell::new({
+                    let cells = sig_scans
+                        .iter()
+                        .fold(
+                            HashMap::<&Resolution, HashMap<&str, usize>>::new(),
+                            |mut map, m| {
+                                *map.entry(m.1).or_default().entry(&m.0.name).or_default() += 1;
+                                map
+                            },
+                        )
+                        .iter()
+                        // sort by pattern name, then match address
+                        .sorted_by_key(|&data| data.0)
+                        .map(|(m, counts)| {
+                            let dis =
+                                disassemble::disassemble(&exe, m.address, None, names.as_ref());
+
+                            let mut lines = vec![];
+                            for (name, count) in counts.iter().sorted_by_key(|e| e.0) {
+                                let count = if *count > 1 {
+                                    format!(" (x{count})")
+                                } else {
+                                    "".to_string()
+                                };
+
+                                lines.push(format!("{:?}{}", name, count).normal().to_string());
+                            }
+                            lines.push(dis);
+
+                            Cell::new(&join(lines, "\n"))
+                        })
+                        .collect::<Vec<_>>();
+
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER);
+
+                    table.add_row(Row::new(cells));
+
+                    &table.to_string()
+                }));
+            } else {
+                cells.push(Cell::new({
+                    let mut lines = sig_scans
+                        .iter()
+                        // group and count matches by (pattern name, address)
+                        .fold(
+                            HashMap::<(&String, &Resolution), usize>::new(),
+                            |mut map, m| {
+                                *map.entry((&m.0.name, m.1)).or_default() += 1;
+                                map
+                            },
+                        )
+                        .iter()
+                        // sort by pattern name, then match address
+                        .sorted_by_key(|&data| data.0)
+                        .map(|(m, count)| {
+                            // add count indicator if more than 1
+                            let count = if *count > 1 {
+                                format!(" (x{count})")
+                            } else {
+                                "".to_string()
+                            };
+
+                            (
+                                format!("{:016x} {:?}{}", m.1.address, m.0, count)
+                                    .normal()
+                                    .to_string(),
+                                exe.symbols
+                                    .as_ref()
+                                    .and_then(|symbols| symbols.get(&m.1.address)),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let max_len = lines.iter().map(|(line, _)| line.len()).max();
+                    for (line, symbol) in &mut lines {
+                        if let Some(symbol) = symbol {
+                            line.push_str(&format!(
+                                "{}{}",
+                                " ".repeat(1 + max_len.unwrap() - line.len()),
+                                symbol.name.bright_yellow()
+                            ));
+                        }
+                    }
+                    &join(lines.iter().map(|(line, _)| line), "\n").to_string()
+                }));
+            }
+        } else {
+            game_snapshot.insert(sig.to_string(), "not found".to_string());
+            #[allow(clippy::unnecessary_to_owned)]
+            cells.push(Cell::new(&"not found".red().to_string()));
+        }
+
+        table.add_row(Row::new(cells));
+    }
+
+    let game_name = match game {
+        GameEntry::File(GameFileEntry { name, .. }) => name.clone(),
+        GameEntry::Process(GameProcessEntry { pid }) => format!("pid={pid}"),
+    };
+
+    // resolvers the profile marks as broken for this game are not run at all
+    let skipped = |resolver: &NamedResolver| profile.is_some_and(|p| p.skips(resolver.name));
+    let dyn_resolvers = setup
+        .resolvers
+        .iter()
+        .filter(|res| !skipped(res))
+        .map(|res| res.getter)
+        .collect_vec();
+    let (resolution, resolved_in): (Vec<_>, Vec<_>) = tracing::info_span!("scan", game = game_name)
+        .in_scope(|| {
+            if modules.is_empty() {
+                let resolution = exe.resolve_many(&dyn_resolvers);
+                let resolved_in = vec![None; resolution.len()];
+                return (resolution, resolved_in);
+            }
+            let images = std::iter::once(&exe)
+                .chain(modules.iter().map(|(_, image)| image))
+                .collect_vec();
+            resolve_many_modules(&images, &dyn_resolvers)
+                .into_iter()
+                .map(|res| match res {
+                    Ok((module, res)) => (Ok(res), Some(module)),
+                    Err(err) => (Err(err), None),
+                })
+                .unzip()
+        });
+    let (resolution, resolved_in): (Vec<_>, Vec<_>) = {
+        let mut results = resolution.into_iter().zip(resolved_in);
+        setup
+            .resolvers
+            .iter()
+            .map(|res| {
+                if skipped(res) {
+                    (
+                        Err(ResolveError::Msg("skipped by game profile".into())),
+                        None,
+                    )
+                } else {
+                    results.next().unwrap()
+                }
+            })
+            .unzip()
+    };
+    // label results with the module they were found in when scanning several
+    let module_label = |module: &Option<usize>| match module {
+        Some(0) => match game {
+            GameEntry::File(GameFileEntry { exe_path, .. }) => {
+                format!("[{}] ", module_name(exe_path))
+            }
+            GameEntry::Process(_) => "[exe] ".to_string(),
+        },
+        Some(i) => format!("[{}] ", modules[i - 1].0),
+        None => "".to_string(),
+    };
+
+    for ((resolver, resolution), module) in
+        setup.resolvers.iter().zip(&resolution).zip(&resolved_in)
+    {
+        let label = module_label(module);
+        // resolutions which don't resolve to addresses are shown as their values
+        let values = resolution
+            .as_ref()
+            .ok()
+            .filter(|res| res.addresses().is_empty())
+            .map(|res| res.values())
+            .filter(|values| !values.is_empty());
+        game_snapshot.insert(
+            resolver.name.to_string(),
+            match (resolution, &values) {
+                (Ok(_), Some(values)) => format!("{label}{}", format_values(values)),
+                (Ok(res), None) => format!("{label}{:x?}", res),
+                (Err(err), _) => err.to_string(),
+            },
+        );
+        // track individual addresses and values of multi-address resolutions so watch diffs
+        // show exactly which entries moved
+        if let Ok(res) = resolution {
+            let addresses = res.addresses();
+            if addresses.len() > 1 {
+                for (key, address) in addresses.prefixed(resolver.name) {
+                    game_snapshot.insert(key, format!("{address:#x}"));
+                }
+            }
+            let values = res.values();
+            if values.len() > 1 {
+                for (key, value) in values.prefixed(resolver.name) {
+                    game_snapshot.insert(key, value.to_string());
+                }
+            }
+        }
+        table.add_row(Row::new(
+            [
+                Cell::new(resolver.name),
+                match (resolution, &values) {
+                    (Ok(_), Some(values)) => {
+                        Cell::new(&format!("{label}{}", format_values(values)))
+                    }
+                    (Ok(res), None) => Cell::new(&format!("{label}{:#x?}", res)),
+                    (Err(err), _) =>
+                    {
+                        #[allow(clippy::unnecessary_to_owned)]
+                        Cell::new(&err.to_string().red().to_string())
+                    }
+                },
+            ]
+            .to_vec(),
+        ));
+    }
+
+    emit(table.to_string());
+
+    Ok(Some(GameScan {
+        name: name.to_string(),
+        snapshot: game_snapshot,
+        resolution,
+        results: scan.results,
+    }))
+}
+
+/// A single value as is, several as `name = value` pairs
+fn format_values(values: &Values) -> String {
+    match values.get("") {
+        Some(value) if values.len() == 1 => value.to_string(),
+        _ => values
+            .0
+            .iter()
+            .map(|(name, value)| format!("{name} = {value}"))
+            .join(", "),
+    }
+}
+
+/// Scan `games` on `setup.command.jobs` threads, each game with its own resolver eval. Output
+/// is buffered per game and printed in game order once all scans finish.
+fn scan_games_parallel<'a>(
+    setup: &ScanSetup<'a>,
+    games: &[GameEntry],
+) -> Result<Vec<GameScan<'a>>> {
+    use indicatif::{MultiProgress, ProgressStyle};
+    use rayon::prelude::*;
+
+    let multi = MultiProgress::new();
+    let total = setup
+        .command
+        .progress
+        .then(|| multi.add(ProgressBar::new(games.len() as u64)));
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(setup.command.jobs)
+        .build()?;
+    let results = pool.install(|| {
+        games
+            .par_iter()
+            .map(|game| -> Result<_> {
+                let bar = setup.command.progress.then(|| {
+                    let bar = multi.add(
+                        ProgressBar::new_spinner()
+                            .with_style(
+                                ProgressStyle::with_template("{spinner} {elapsed} {msg}").unwrap(),
+                            )
+                            .with_message(match game {
+                                GameEntry::File(GameFileEntry { name, .. }) => name.clone(),
+                                GameEntry::Process(GameProcessEntry { pid }) => {
+                                    format!("PID={pid}")
+                                }
+                            }),
+                    );
+                    bar.enable_steady_tick(std::time::Duration::from_millis(100));
+                    bar
+                });
+
+                let lines = std::cell::RefCell::new(vec![]);
+                let scan = scan_game(setup, game, &|line| lines.borrow_mut().push(line));
+
+                if let Some(bar) = bar {
+                    bar.finish_and_clear();
+                    multi.remove(&bar);
+                }
+                if let Some(total) = &total {
+                    total.inc(1);
+                }
+                Ok((lines.into_inner(), scan?))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    if let Some(total) = total {
+        total.finish_and_clear();
+    }
+
+    let mut scans = vec![];
+    for (lines, scan) in results {
+        for line in lines {
+            println!("{line}");
+        }
+        scans.extend(scan);
+    }
+    Ok(scans)
+}
+
 fn report(command: CommandReport) -> Result<()> {
     use rayon::prelude::*;
 
-    fn load_game(path: impl AsRef<Path>, data: &mut Vec<u8>) -> Result<Image<'_>> {
-        use std::io::Read;
-        data.clear();
-        fs::File::open(path)?.read_to_end(data)?;
-        Image::builder().build(data)
+    fn load_game(path: impl AsRef<Path>, data: &mut Option<MappedFile>) -> Result<Image<'_>> {
+        Image::builder()
+            .functions(true)
+            .build_mapped(data.insert(MappedFile::open(path)?))
     }
 
-    let resolvers = command
-        .resolver
+    let named_resolvers = selected_resolvers(&command.resolver);
+    let resolvers = named_resolvers
         .iter()
         .map(|res| res.getter)
         .collect::<Vec<_>>();
@@ -676,7 +1582,7 @@ fn report(command: CommandReport) -> Result<()> {
         "[year]-[month]-[day]_[hour]-[minute]-[second]"
     ))?;
 
-    let games = get_games(command.game)?;
+    let games = get_games(&command.games)?;
 
     let results = std::sync::Arc::new(std::sync::Mutex::new(BTreeMap::new()));
 
@@ -684,7 +1590,7 @@ fn report(command: CommandReport) -> Result<()> {
     games.into_par_iter().try_for_each(|game| -> Result<()> {
         progress.println(format!("{:?} {:?}", game.name, game.exe_path.display()));
 
-        let mut data = vec![];
+        let mut data = None;
         let exe = match load_game(&game.exe_path, &mut data) {
             Ok(exe) => exe,
             Err(err) => {
@@ -694,10 +1600,27 @@ fn report(command: CommandReport) -> Result<()> {
             }
         };
 
-        let resolution = exe.resolve_many(&resolvers);
+        let mut module_data = game.modules.iter().map(|_| None).collect_vec();
+        let mut images = vec![&exe];
+        let modules = game
+            .modules
+            .iter()
+            .zip(&mut module_data)
+            .filter_map(|(path, data)| match load_game(path, data) {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    progress.println(format!("err reading {}: {}", path.display(), err));
+                    None
+                }
+            })
+            .collect_vec();
+        images.extend(&modules);
+
+        let resolution = resolve_many_modules(&images, &resolvers)
+            .into_iter()
+            .map(|res| res.map(|(_, res)| res));
 
-        let map = command
-            .resolver
+        let map = named_resolvers
             .iter()
             .zip(resolution)
             .map(|(resolver, resolution)| (resolver.name, resolution))
@@ -732,11 +1655,27 @@ fn report(command: CommandReport) -> Result<()> {
 
     Ok(())
 }
+/// Resolutions of a game as written by `scan --json`. Reports written by `report` have no
+/// values but otherwise use the same format.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ReportGame<R> {
+    /// Results of resolvers which aren't addresses keyed like [`Values::prefixed`]. The key
+    /// can't collide with resolver names.
+    #[serde(
+        rename = "$values",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    values: BTreeMap<String, Value>,
+    #[serde(flatten)]
+    resolutions: BTreeMap<String, R>,
+}
+
 fn diff_report(command: CommandDiffReport) -> Result<()> {
     use colored::Colorize;
     use patternsleuth::resolvers::{Resolution, ResolveError};
     use prettytable::{Cell, Row, Table};
-    type Report = BTreeMap<String, BTreeMap<String, Result<Box<dyn Resolution>, ResolveError>>>;
+    type Report = BTreeMap<String, ReportGame<Result<Box<dyn Resolution>, ResolveError>>>;
 
     let a: Report = serde_json::from_slice(&fs::read(command.a)?)?;
     let b: Report = serde_json::from_slice(&fs::read(command.b)?)?;
@@ -748,8 +1687,8 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
     let mut diffs: BTreeMap<&str, BTreeMap<&str, (Res, Res)>> = Default::default();
 
     for game in a.keys().chain(b.keys()).unique() {
-        let game_a = a.get(game);
-        let game_b = b.get(game);
+        let game_a = a.get(game).map(|game| &game.resolutions);
+        let game_b = b.get(game).map(|game| &game.resolutions);
         if game_a.is_none() {
             games_only_in_b.push(game);
         }
@@ -890,20 +1829,18 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
 }
 
 fn symbols(command: CommandSymbols) -> Result<()> {
-    let re = &command.symbol;
-    let filter = |sym: &Symbol| re.iter().any(|re| re.is_match(&sym.name));
-
     use prettytable::{Cell, Row, Table};
 
     let mut cells = vec![];
 
-    for GameFileEntry { name, exe_path } in get_games(command.game)? {
+    for GameFileEntry { name, exe_path, .. } in get_games(&command.games)? {
         if !exe_path.with_extension("pdb").exists() && !exe_path.with_extension("sym").exists() {
             continue;
         }
 
         println!("{:?} {:?}", name, exe_path.display());
         let bin_data = fs::read(&exe_path)?;
+        let names = names::Names::open(&exe_path)?;
         let exe = match Image::builder()
             .functions(true)
             .symbols(&exe_path)
@@ -916,16 +1853,23 @@ fn symbols(command: CommandSymbols) -> Result<()> {
             }
         };
 
-        for (address, sym) in exe.symbols.as_ref().unwrap() {
-            if filter(sym) {
-                if let Ok(Some(full_range)) = exe.get_root_function_range(*address) {
-                    cells.push((
-                        sym.clone(),
-                        disassemble::disassemble_range(&exe, full_range),
-                    ));
-                } else {
-                    println!("{:016x} [NO EXCEPT] {}", address, sym.name);
-                }
+        for function in exe
+            .find_functions_by_symbols(&command.symbol)
+            .into_iter()
+            .flatten()
+            .sorted_by_key(|f| f.address)
+            .dedup_by(|a, b| a.address == b.address)
+        {
+            if let Some(full_range) = function.range {
+                cells.push((
+                    function.symbol,
+                    disassemble::disassemble_range(&exe, full_range, Some(&names)),
+                ));
+            } else {
+                println!(
+                    "{:016x} [NO EXCEPT] {}",
+                    function.address, function.symbol.name
+                );
             }
         }
     }
@@ -948,15 +1892,99 @@ enum GameEntry {
 struct GameFileEntry {
     name: String,
     exe_path: PathBuf,
+    /// Other binaries of the game which may contain the engine, see [`find_game_modules`]
+    modules: Vec<PathBuf>,
+    profile: Option<GameProfile>,
+}
+
+impl GameFileEntry {
+    /// Load the profile of the game if it has one, which may list the modules to scan
+    fn new(selection: &GameSelection, name: String, exe_path: PathBuf) -> Result<Self> {
+        let game_dir = exe_path.parent().unwrap_or(Path::new("."));
+        let profile = GameProfile::find(&name, game_dir, &profiles_dirs(selection))?;
+        let modules = match &profile {
+            Some(profile) if !profile.modules.is_empty() => profile
+                .modules
+                .iter()
+                .map(|module| game_dir.join(module))
+                .collect(),
+            _ => find_game_modules(&exe_path),
+        };
+        Ok(Self {
+            name,
+            exe_path,
+            modules,
+            profile,
+        })
+    }
 }
 
 struct GameProcessEntry {
     pid: i32,
 }
 
-fn get_games(filter: impl AsRef<[String]>) -> Result<Vec<GameFileEntry>> {
-    let games_filter = filter
-        .as_ref()
+/// Directories to search for games when none are given on the command line
+fn games_roots(selection: &GameSelection) -> Result<Vec<PathBuf>> {
+    if !selection.games_root.is_empty() {
+        return Ok(selection.games_root.clone());
+    }
+    if let Some(roots) = std::env::var_os("PATTERNSLEUTH_GAMES_ROOT") {
+        return Ok(std::env::split_paths(&roots).collect());
+    }
+    let config = Path::new("patternsleuth.json");
+    if config.exists() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            #[serde(default)]
+            games_roots: Vec<PathBuf>,
+        }
+        let config: Config = serde_json::from_slice(&fs::read(config)?)
+            .with_context(|| format!("failed to parse {}", config.display()))?;
+        if !config.games_roots.is_empty() {
+            return Ok(config.games_roots);
+        }
+    }
+    Ok(vec!["games".into()])
+}
+
+/// Directories to search for game profiles, see [`GameProfile::find`]
+fn profiles_dirs(selection: &GameSelection) -> Vec<PathBuf> {
+    if !selection.profiles_dir.is_empty() {
+        return selection.profiles_dir.clone();
+    }
+    if let Some(dirs) = std::env::var_os("PATTERNSLEUTH_PROFILES_DIR") {
+        return std::env::split_paths(&dirs).collect();
+    }
+    vec!["profiles".into()]
+}
+
+fn get_games(selection: &GameSelection) -> Result<Vec<GameFileEntry>> {
+    if !selection.exe.is_empty() {
+        return selection
+            .exe
+            .iter()
+            .map(|exe_path| {
+                if !exe_path.is_file() {
+                    bail!("{} is not a file", exe_path.display());
+                }
+                let name = exe_path
+                    .file_stem()
+                    .context("exe path has no file name")?
+                    .to_string_lossy()
+                    .to_string();
+                let exe_path = if archive::is_archive(exe_path) {
+                    archive::extract_game(exe_path)?
+                        .with_context(|| format!("no executable found in {}", exe_path.display()))?
+                } else {
+                    exe_path.clone()
+                };
+                GameFileEntry::new(selection, name, exe_path)
+            })
+            .collect();
+    }
+
+    let games_filter = selection
+        .game
         .iter()
         .map(|g| {
             Ok(globset::GlobBuilder::new(g)
@@ -965,38 +1993,88 @@ fn get_games(filter: impl AsRef<[String]>) -> Result<Vec<GameFileEntry>> {
                 .compile_matcher())
         })
         .collect::<Result<Vec<_>>>()?;
-    //eprintln!("{}", std::env::current_dir().unwrap().to_str().unwrap());
-    fs::read_dir("games")?
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|entry| -> Result<Option<(String, PathBuf)>> {
-            //eprintln!("FSOK!");
-            let dir_name = entry.file_name();
-            let name = dir_name.to_string_lossy().to_string();
-            if !games_filter
-                .is_empty()
-                .then_some(true)
-                .unwrap_or_else(|| games_filter.iter().any(|g| g.is_match(&name)))
-            {
-                return Ok(None);
+
+    let is_selected = |name: &str| -> bool {
+        games_filter.is_empty() || games_filter.iter().any(|g| g.is_match(name))
+    };
+
+    let mut entries = vec![];
+    if selection.discover_installed {
+        for game in discover::discover_installed()? {
+            if is_selected(&game.name) {
+                entries.push((game.name, game.exe_path));
+            }
+        }
+    }
+    for root in games_roots(selection)? {
+        if selection.discover_installed && !root.exists() {
+            continue;
+        }
+        let dir = fs::read_dir(&root)
+            .with_context(|| format!("failed to read games root {}", root.display()))?;
+        for entry in dir {
+            let entry = entry?;
+            // games may also be stored as archives named after the game
+            if archive::is_archive(&entry.path()) {
+                let path = entry.path();
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                if is_selected(&name) {
+                    if let Some(exe_path) = archive::extract_game(&path)? {
+                        entries.push((name, exe_path));
+                    }
+                }
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_selected(&name) {
+                continue;
+            }
+            if !entry.path().is_dir() {
+                continue;
+            }
+            if let Some(exe_path) = find_ext(entry.path(), &["exe", "elf"])? {
+                entries.push((name, exe_path));
             }
+        }
+    }
 
-            let Some(exe_path) = find_ext(entry.path(), &["exe", "elf"])
-                .transpose()
-                .transpose()?
-            else {
-                return Ok(None);
-            };
-            Ok(Some((name, exe_path)))
-        })
-        .filter_map(|r| r.transpose())
-        .collect::<Result<Vec<(String, _)>>>()
-        .map(|entries| {
-            sample_order(entries, 3)
-                .into_iter()
-                .map(|(name, exe_path)| GameFileEntry { name, exe_path })
-                .collect::<Vec<GameFileEntry>>()
+    sample_order(entries, 3)
+        .into_iter()
+        .map(|(name, exe_path)| GameFileEntry::new(selection, name, exe_path))
+        .collect()
+}
+
+fn module_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// DLLs next to `exe_path` which may contain the engine rather than the executable itself, e.g.
+/// `Game-Win64-Shipping.dll` loaded by a launcher exe or the per module DLLs of modular builds
+fn find_game_modules(exe_path: &Path) -> Vec<PathBuf> {
+    let Some(Ok(dir)) = exe_path.parent().map(fs::read_dir) else {
+        return vec![];
+    };
+    let mut modules = dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let is_dll = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("dll"));
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            is_dll
+                && path.is_file()
+                && (stem.ends_with("-shipping")
+                    || ["-win64-", "-wingdk-"].iter().any(|p| stem.contains(p)))
         })
+        .collect_vec();
+    modules.sort();
+    modules
 }
 
 /// Distribute pairs such that unique prefixes are encountered early
@@ -1042,4 +2120,75 @@ mod test {
         let ordered = sample_order(entries.clone(), 1);
         assert_eq!(entries, ordered);
     }
+
+    #[test]
+    fn test_find_game_modules() {
+        let dir = std::env::temp_dir().join(format!("game-modules-{}", std::process::id()));
+        fs::create_dir_all(dir.join("Plugin-Win64-Shipping.dll")).unwrap();
+        for file in [
+            "Game.exe",
+            "Game-Win64-Shipping.dll",
+            "Engine-WinGDK-Core.DLL",
+            "Launcher-Shipping.dll",
+            "steam_api64.dll",
+            "Game-Win64-Shipping.pdb",
+        ] {
+            fs::write(dir.join(file), []).unwrap();
+        }
+
+        let names = find_game_modules(&dir.join("Game.exe"))
+            .iter()
+            .map(|path| module_name(path))
+            .collect_vec();
+        // directories are skipped
+        assert_eq!(
+            names,
+            [
+                "Engine-WinGDK-Core.DLL",
+                "Game-Win64-Shipping.dll",
+                "Launcher-Shipping.dll"
+            ]
+        );
+        assert!(find_game_modules(&dir.join("missing").join("Game.exe")).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_report_game_values() {
+        use patternsleuth::resolvers::{unreal::layout::FNameSize, Resolution};
+        type Game = ReportGame<Result<Box<dyn Resolution>, ResolveError>>;
+
+        let resolution: Result<Arc<dyn Resolution>, ResolveError> = Ok(Arc::new(FNameSize(12)));
+        let write = |values: BTreeMap<String, Value>| {
+            serde_json::to_string(&ReportGame {
+                values,
+                resolutions: [("FNameSize".to_string(), &resolution)].into(),
+            })
+            .unwrap()
+        };
+
+        let values = BTreeMap::from([("FNameSize".to_string(), Value::Int(12))]);
+        let game: Game = serde_json::from_str(&write(values.clone())).unwrap();
+        assert_eq!(game.values, values);
+        assert_eq!(game.resolutions.len(), 1);
+        assert!(game.resolutions["FNameSize"].is_ok());
+
+        // same as the output of `report`
+        let json = write(Default::default());
+        assert!(!json.contains("$values"));
+        let game: Game = serde_json::from_str(&json).unwrap();
+        assert!(game.values.is_empty());
+        assert_eq!(game.resolutions.len(), 1);
+    }
+
+    #[test]
+    fn test_scan_process_args() {
+        let parse = |args: &[&str]| CommandScan::try_parse_from(["scan"].iter().chain(args));
+        assert!(parse(&["--pid", "1", "--read-only"]).is_ok());
+        assert!(parse(&["--launch", "Game.exe", "--suspend"]).is_ok());
+        // a launched game is always written to, it is created and resumed by the scan
+        assert!(parse(&["--launch", "Game.exe", "--read-only"]).is_err());
+        assert!(parse(&["--pid", "1", "--launch", "Game.exe"]).is_err());
+        assert!(parse(&["--launch-arg", "-log"]).is_err());
+    }
 }
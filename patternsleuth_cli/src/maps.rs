@@ -0,0 +1,54 @@
+//! Listing the address space of a running game, to see which regions a live scan covers
+
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use patternsleuth::process::external::{memory_regions, read_image_from_pid};
+
+#[derive(Parser)]
+pub struct CommandMaps {
+    /// A game process ID to list
+    #[arg(long)]
+    pid: i32,
+}
+
+pub fn maps(command: CommandMaps) -> Result<()> {
+    let exe = read_image_from_pid(command.pid)?;
+    let regions = memory_regions(command.pid)?;
+
+    println!("image base {:#x}", exe.base_address);
+    for section in exe.memory.sections() {
+        println!(
+            "  {:<10} {:016x}-{:016x} {}",
+            section.name(),
+            section.address(),
+            section.address() + section.len(),
+            section.permissions(),
+        );
+    }
+
+    let mut scanned = 0;
+    for region in &regions {
+        let included = region.included_in(&exe);
+        let line = format!(
+            "{} {:016x}-{:016x} {} {}",
+            if included { "*" } else { " " },
+            region.range.start,
+            region.range.end,
+            region.permissions,
+            region.path.as_deref().unwrap_or_default(),
+        );
+        if included {
+            scanned += 1;
+            println!("{}", line.green());
+        } else {
+            println!("{line}");
+        }
+    }
+    println!(
+        "{} regions, {scanned} included in the scanned image (marked *)",
+        regions.len()
+    );
+
+    Ok(())
+}
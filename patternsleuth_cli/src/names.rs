@@ -0,0 +1,212 @@
+//! Per-game database of user labels for addresses, kept next to the executable as
+//! `<exe>.names.json` so findings accumulate across runs without an external disassembler.
+//! Disassembly annotates labelled addresses and references to them. This is also where
+//! [`repl`](crate::repl) bookmarks go, so a label is the same whether it was added from the repl
+//! or with the `name` command.
+//!
+//! Labels are stored by RVA so they stay valid when the image is loaded at another base.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use patternsleuth::image::{Image, MappedFile};
+
+use crate::{get_games, parse_maybe_hex, GameSelection};
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Names {
+    /// Label by RVA, written with hex keys
+    #[serde(default, with = "hex_keys")]
+    names: BTreeMap<usize, String>,
+    /// Free form note by RVA of a labelled address
+    #[serde(default, with = "hex_keys", skip_serializing_if = "BTreeMap::is_empty")]
+    notes: BTreeMap<usize, String>,
+}
+
+mod hex_keys {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<usize, String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(rva, name)| (format!("{rva:#x}"), name)))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<usize, String>, D::Error> {
+        BTreeMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(rva, name)| {
+                let rva = crate::parse_maybe_hex(&rva).map_err(serde::de::Error::custom)?;
+                Ok((rva, name))
+            })
+            .collect()
+    }
+}
+
+impl Names {
+    pub fn path(exe_path: &Path) -> PathBuf {
+        exe_path.with_extension("names.json")
+    }
+    /// Load the names of the game at `exe_path`, empty if it has none yet
+    pub fn open(exe_path: &Path) -> Result<Self> {
+        let path = Self::path(exe_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("failed to parse names {}", path.display()))
+    }
+    pub fn save(&self, exe_path: &Path) -> Result<()> {
+        let path = Self::path(exe_path);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write names {}", path.display()))
+    }
+    pub fn get(&self, exe: &Image, address: usize) -> Option<&str> {
+        self.names.get(&exe.va_to_rva(address)?).map(String::as_str)
+    }
+    pub fn note(&self, exe: &Image, address: usize) -> Option<&str> {
+        self.notes.get(&exe.va_to_rva(address)?).map(String::as_str)
+    }
+    /// Address of the first entry labelled `name`
+    pub fn address_of(&self, exe: &Image, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(rva, _)| exe.rva_to_va(*rva))
+    }
+    /// Label `address`, returning the label it replaced. A `note` replaces the previous note of
+    /// the address, which is otherwise kept.
+    pub fn insert(
+        &mut self,
+        exe: &Image,
+        address: usize,
+        name: String,
+        note: Option<String>,
+    ) -> Result<Option<String>> {
+        let Some(rva) = exe.va_to_rva(address) else {
+            bail!(
+                "{address:#x} is below the image base {:#x}",
+                exe.base_address
+            );
+        };
+        if let Some(note) = note {
+            self.notes.insert(rva, note);
+        }
+        Ok(self.names.insert(rva, name))
+    }
+    /// Remove the label and note of `address`
+    pub fn remove(&mut self, exe: &Image, address: usize) -> Option<String> {
+        let rva = exe.va_to_rva(address)?;
+        self.notes.remove(&rva);
+        self.names.remove(&rva)
+    }
+    /// Labels by address, in address order
+    pub fn iter<'a>(&'a self, exe: &'a Image) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        self.names
+            .iter()
+            .map(|(rva, name)| (exe.rva_to_va(*rva), name.as_str()))
+    }
+}
+
+#[derive(Parser)]
+pub struct CommandName {
+    #[command(flatten)]
+    games: GameSelection,
+
+    /// Address to name or look up. Lists every name of the game if omitted
+    #[arg(short, long, value_parser(parse_maybe_hex))]
+    address: Option<usize>,
+
+    /// Label to give the address
+    #[arg(short, long, requires = "address")]
+    label: Option<String>,
+
+    /// Note to keep with the label
+    #[arg(short, long, requires = "label")]
+    note: Option<String>,
+
+    /// Remove the name of the address
+    #[arg(short, long, requires = "address", conflicts_with = "label")]
+    remove: bool,
+}
+
+pub fn name(command: CommandName) -> Result<()> {
+    let games = get_games(&command.games)?;
+    let [game] = games.as_slice() else {
+        bail!("{} games selected, select exactly one", games.len());
+    };
+    let data = MappedFile::open(&game.exe_path)?;
+    let exe = Image::builder().build_mapped(&data)?;
+    let mut names = Names::open(&game.exe_path)?;
+
+    let Some(address) = command.address else {
+        for (address, name) in names.iter(&exe) {
+            print_name(address, name, names.note(&exe, address));
+        }
+        return Ok(());
+    };
+    if command.remove {
+        match names.remove(&exe, address) {
+            Some(old) => println!("removed {address:#x} {old}"),
+            None => println!("{address:#x} has no name"),
+        }
+    } else if let Some(label) = command.label {
+        match names.insert(&exe, address, label.clone(), command.note)? {
+            Some(old) => println!("{address:#x} {old} -> {label}"),
+            None => println!("{address:#x} {label}"),
+        }
+    } else {
+        match names.get(&exe, address) {
+            Some(name) => print_name(address, name, names.note(&exe, address)),
+            None => println!("{address:#x} has no name"),
+        }
+        return Ok(());
+    }
+    names.save(&game.exe_path)
+}
+
+pub fn print_name(address: usize, name: &str, note: Option<&str>) {
+    match note {
+        Some(note) => println!("{address:#x} {name} {note}"),
+        None => println!("{address:#x} {name}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_names_hex_keys() {
+        let mut names = Names {
+            names: BTreeMap::from([(0x1234, "GMalloc".to_string())]),
+            notes: BTreeMap::new(),
+        };
+        let json = serde_json::to_string(&names).unwrap();
+        assert_eq!(json, r#"{"names":{"0x1234":"GMalloc"}}"#);
+        assert_eq!(serde_json::from_str::<Names>(&json).unwrap(), names);
+
+        names
+            .notes
+            .insert(0x1234, "from FMemory::Malloc".to_string());
+        let json = serde_json::to_string(&names).unwrap();
+        assert_eq!(
+            json,
+            r#"{"names":{"0x1234":"GMalloc"},"notes":{"0x1234":"from FMemory::Malloc"}}"#
+        );
+        assert_eq!(serde_json::from_str::<Names>(&json).unwrap(), names);
+        assert_eq!(
+            serde_json::from_str::<Names>("{}").unwrap(),
+            Names::default()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Snapshotting the objects of a running game before and after an action to see which classes
+//! it instantiates and destroys
+
+use std::{io::BufRead, time::Duration};
+
+use anyhow::Result;
+use clap::Parser;
+use colored::Colorize;
+use patternsleuth::{
+    process::external::{read_image_from_pid, ProcessMemory},
+    resolvers::unreal::{
+        engine_version::EngineVersion,
+        fname::{CachedNamePool, FNamePool},
+        guobject_array::GUObjectArray,
+        layout::{EngineBuildConfig, FUObjectItemSize, UObjectBaseLayout},
+    },
+    ue::{BatchedMemory, ObjectArray, ObjectItemLayout, ObjectLayout, ObjectSnapshot, Objects},
+};
+
+#[derive(Parser)]
+pub struct CommandObjectsDiff {
+    /// A game process ID to snapshot
+    #[arg(long)]
+    pid: i32,
+
+    /// Take the second snapshot after this many seconds instead of waiting for Enter
+    #[arg(short, long)]
+    wait: Option<f64>,
+
+    /// Also print the path names of created objects which are still alive
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+pub fn objects_diff(command: CommandObjectsDiff) -> Result<()> {
+    let exe = read_image_from_pid(command.pid)?;
+    let guobject_array = exe.resolve(GUObjectArray::resolver())?;
+    let item_size = exe.resolve(FUObjectItemSize::resolver())?;
+    let build = exe.resolve(EngineBuildConfig::resolver())?;
+    let pool = exe.resolve(FNamePool::resolver())?;
+    // without a version the UE4 layout is assumed, misinterpreting the item flags of UE 5
+    let item_layout = exe
+        .resolve(EngineVersion::resolver())
+        .map(|v| ObjectItemLayout::for_version(v.major, v.minor))
+        .unwrap_or_default();
+
+    let mem = BatchedMemory::new(ProcessMemory::new(command.pid)?);
+    let names = CachedNamePool::new(&mem, pool.0);
+    let array = ObjectArray::new(&mem, guobject_array.0, item_size.0).item_layout(item_layout);
+    let layout = ObjectLayout::for_build(build.0);
+    // the UObjectBase of the engine is assumed if the probe fails
+    let layout = match exe.resolve(UObjectBaseLayout::resolver()) {
+        Ok(base) => layout.object_base(base.object_flags, base.internal_index),
+        Err(_) => layout,
+    };
+    let objects = Objects::new(&array, layout, |name| names.fname(name).ok());
+
+    let before = ObjectSnapshot::take(&objects);
+    println!("took snapshot of {} objects", before.len());
+
+    match command.wait {
+        Some(seconds) => {
+            println!("waiting {seconds}s");
+            std::thread::sleep(Duration::from_secs_f64(seconds));
+        }
+        None => {
+            println!("perform the action in game, then press Enter");
+            std::io::stdin().lock().lines().next().transpose()?;
+        }
+    }
+
+    mem.clear();
+    let after = ObjectSnapshot::take(&objects);
+    println!("took snapshot of {} objects", after.len());
+
+    let diff = before.diff(&after);
+    if diff.is_empty() {
+        println!("no objects were created or destroyed");
+        return Ok(());
+    }
+
+    println!("{:>8} {:>10}  class", "created", "destroyed");
+    for (class, created, destroyed) in diff.counts() {
+        println!(
+            "{:>8} {:>10}  {class}",
+            if created > 0 {
+                format!("+{created}").green()
+            } else {
+                "".normal()
+            },
+            if destroyed > 0 {
+                format!("-{destroyed}").red()
+            } else {
+                "".normal()
+            },
+        );
+        if command.verbose {
+            for object in diff.created.get(class).into_iter().flatten() {
+                if let Ok(Some(path)) = objects.path_name(*object) {
+                    println!("{:>20}{path}", "");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
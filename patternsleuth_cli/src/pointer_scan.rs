@@ -0,0 +1,71 @@
+//! Finding static pointer paths to an object in a running game
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use patternsleuth::{
+    pointer_scan::{PointerMap, PointerScanOptions},
+    process::external::{read_image_from_pid, writable_regions, ProcessMemory},
+};
+
+use crate::parse_maybe_hex;
+
+#[derive(Parser)]
+pub struct CommandPointerScan {
+    /// A game process ID to scan
+    #[arg(long)]
+    pid: i32,
+
+    /// Address to find paths to
+    #[arg(value_parser(parse_maybe_hex))]
+    target: usize,
+
+    /// Maximum number of pointers to follow
+    #[arg(short, long, default_value_t = 4)]
+    depth: usize,
+
+    /// Maximum offset added to each pointer
+    #[arg(short, long, default_value = "0x1000", value_parser(parse_maybe_hex))]
+    max_offset: usize,
+
+    /// Maximum number of paths to report
+    #[arg(short, long, default_value_t = 100)]
+    limit: usize,
+}
+
+pub fn pointer_scan(command: CommandPointerScan) -> Result<()> {
+    let exe = read_image_from_pid(command.pid)?;
+    // module globals live in the writable sections of the executable
+    let statics = exe
+        .memory
+        .sections()
+        .iter()
+        .filter(|s| s.permissions().write)
+        .map(|s| s.address()..s.address() + s.len())
+        .collect::<Vec<_>>();
+    if statics.is_empty() {
+        bail!("executable has no writable sections");
+    }
+
+    let mem = ProcessMemory::new(command.pid)?;
+    let regions = writable_regions(command.pid)?;
+    println!("building pointer map of {} regions", regions.len());
+    let map = PointerMap::build(&mem, &regions);
+    println!("found {} pointers", map.len());
+
+    let paths = patternsleuth::pointer_scan::pointer_scan(
+        &map,
+        &statics,
+        command.target,
+        &PointerScanOptions {
+            max_depth: command.depth,
+            max_offset: command.max_offset,
+            max_results: command.limit,
+        },
+    );
+    for path in &paths {
+        println!("{}", path.relative_to("exe", exe.base_address));
+    }
+    println!("{} paths to {:#x}", paths.len(), command.target);
+
+    Ok(())
+}
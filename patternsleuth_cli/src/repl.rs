@@ -0,0 +1,467 @@
+//! Interactive prompt for authoring patterns against a single game: queries are scanned as they
+//! are typed so a pattern can be refined until it is unique, then exported as a pattern set
+//! entry. With `--session` queries are kept in a [`Session`] file. Bookmarks are labels in the
+//! [`Names`] of the game, so they are shown in disassembly and shared with the `name` command.
+
+use std::{
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use patternsleuth::{
+    image::{Image, MappedFile},
+    resolvers::unreal::util,
+    scanner::{Pattern, Xref},
+    PatternConfig,
+};
+
+use crate::{
+    disassemble::disassemble,
+    get_games,
+    names::{print_name, Names},
+    parse_maybe_hex,
+    session::{Session, SessionQuery},
+    GameSelection,
+};
+
+/// Number of match addresses printed per query
+const MAX_LISTED: usize = 10;
+
+const HELP: &str = "\
+commands:
+  pattern <bytes>      scan for a byte pattern, e.g. 48 8b 05 ?? ?? ?? ?? 48 85 c0
+  xref <address>       scan for references to an address
+  str <string>         scan for a UTF-16 and UTF-8 string and references to it
+  dis <address>        disassemble around an address or named address
+  show [n]             disassemble the nth match of the last pattern or xref (default 0)
+  export [name]        print the last pattern or xref as a pattern set entry
+  bookmark <name> [address] [note]
+                       name an address (default the first match of the last query) in the
+                       names of the game, shown in disassembly
+  bookmarks            list the names of the game
+  history              list queries of the session
+  save <path>          save the session to a file, kept up to date from then on
+  help                 print this help
+  quit                 exit";
+
+#[derive(clap::Parser)]
+pub struct CommandRepl {
+    #[command(flatten)]
+    games: GameSelection,
+
+    /// Session file to resume and keep queries in. The game of the session is
+    /// reopened if none is selected
+    #[arg(long)]
+    session: Option<PathBuf>,
+}
+
+/// Last pattern or xref scanned, kept to be shown in detail and exported
+enum Query {
+    Pattern(Pattern),
+    Xref(usize),
+}
+
+struct Repl<'data> {
+    exe: Image<'data>,
+    exe_path: PathBuf,
+    names: Names,
+    last: Option<(Query, Vec<usize>)>,
+    session: Session,
+    session_path: Option<PathBuf>,
+}
+
+pub fn repl(command: CommandRepl) -> Result<()> {
+    let mut session = match &command.session {
+        Some(path) => Session::open(path)?,
+        None => Session::default(),
+    };
+    let mut selection = command.games.clone();
+    if selection.game.is_empty() && selection.exe.is_empty() {
+        if let Some(exe_path) = &session.exe_path {
+            selection.exe = vec![exe_path.clone()];
+        }
+    }
+
+    let mut games = get_games(&selection)?;
+    match games.len() {
+        1 => {}
+        0 => bail!("no game selected"),
+        n => bail!(
+            "{n} games selected, select one: {}",
+            games.iter().map(|g| &g.name).join(", ")
+        ),
+    }
+    let game = games.remove(0);
+    let data = MappedFile::open(&game.exe_path)
+        .with_context(|| format!("failed to open {}", game.exe_path.display()))?;
+    session.exe_path = Some(game.exe_path.clone());
+    let mut repl = Repl {
+        exe: Image::builder().build_mapped(&data)?,
+        names: Names::open(&game.exe_path)?,
+        exe_path: game.exe_path.clone(),
+        last: None,
+        session,
+        session_path: command.session,
+    };
+    repl.resume();
+
+    println!("{} loaded, type help for commands", game.name);
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let (cmd, arg) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(cmd, arg)| (cmd, arg.trim()))
+            .unwrap_or((line.trim(), ""));
+        let result = match cmd {
+            "" => Ok(()),
+            "pattern" | "p" => repl.pattern(arg),
+            "xref" | "x" => repl.xref(arg),
+            "str" | "s" => repl.string(arg),
+            "dis" | "d" => repl.address(arg).map(|address| {
+                println!(
+                    "{}",
+                    disassemble(&repl.exe, address, None, Some(&repl.names))
+                )
+            }),
+            "show" => repl.show(arg),
+            "export" => repl.export(arg),
+            "bookmark" | "b" => repl.bookmark(arg),
+            "bookmarks" => {
+                repl.bookmarks();
+                Ok(())
+            }
+            "history" => {
+                repl.history();
+                Ok(())
+            }
+            "save" => repl.save_as(arg),
+            "help" | "?" => {
+                println!("{HELP}");
+                Ok(())
+            }
+            "quit" | "exit" | "q" => break,
+            _ => Err(anyhow::anyhow!(
+                "unknown command {cmd:?}, type help for commands"
+            )),
+        };
+        if let Err(err) = result {
+            println!("error: {err:#}");
+        }
+    }
+    Ok(())
+}
+
+impl Repl<'_> {
+    fn scan(&self, config: PatternConfig<()>) -> Result<Vec<usize>> {
+        let configs = [config];
+        let mut matches = self
+            .exe
+            .scan(&configs)?
+            .results
+            .into_iter()
+            .map(|(_, res)| res.address)
+            .collect_vec();
+        matches.sort();
+        matches.dedup();
+        Ok(matches)
+    }
+
+    /// Restore the last pattern or xref of a resumed session without scanning it again
+    fn resume(&mut self) {
+        self.last = self.session.queries.iter().rev().find_map(|saved| {
+            let query = match saved.query.split_once(' ')? {
+                ("pattern", pattern) => Query::Pattern(Pattern::new(pattern).ok()?),
+                ("xref", address) => Query::Xref(parse_maybe_hex(address).ok()?),
+                _ => return None,
+            };
+            Some((query, saved.matches.clone()))
+        });
+        if !self.session.queries.is_empty() {
+            println!(
+                "resumed session with {} queries",
+                self.session.queries.len()
+            );
+        }
+    }
+
+    /// Record a query in the session and save it
+    fn record(&mut self, query: String, matches: &[usize]) -> Result<()> {
+        self.session.queries.push(SessionQuery {
+            query,
+            matches: matches.to_vec(),
+        });
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        match &self.session_path {
+            Some(path) => self.session.save(path),
+            None => Ok(()),
+        }
+    }
+
+    fn save_as(&mut self, arg: &str) -> Result<()> {
+        if arg.is_empty() {
+            bail!("expected a path");
+        }
+        self.session_path = Some(arg.into());
+        self.save()
+    }
+
+    fn pattern(&mut self, arg: &str) -> Result<()> {
+        let pattern = Pattern::new(arg)?;
+        let matches = self.scan(PatternConfig::new((), "repl".into(), None, pattern.clone()))?;
+        print_matches(&matches);
+        if let Some(first) = matches.first() {
+            println!(
+                "{}",
+                disassemble(&self.exe, *first, Some(&pattern), Some(&self.names))
+            );
+        }
+        self.record(format!("pattern {arg}"), &matches)?;
+        self.last = Some((Query::Pattern(pattern), matches));
+        Ok(())
+    }
+
+    fn xref(&mut self, arg: &str) -> Result<()> {
+        let address = parse_maybe_hex(arg)?;
+        let matches = self.scan(PatternConfig::xref((), "repl".into(), None, Xref(address)))?;
+        print_matches(&matches);
+        self.record(format!("xref {address:#x}"), &matches)?;
+        self.last = Some((Query::Xref(address), matches));
+        Ok(())
+    }
+
+    fn bookmark(&mut self, arg: &str) -> Result<()> {
+        let mut args = arg.splitn(3, char::is_whitespace);
+        let Some(name) = args.next().filter(|name| !name.is_empty()) else {
+            bail!("expected a name");
+        };
+        let address = match args.next() {
+            Some(address) => parse_maybe_hex(address)?,
+            None => match &self.last {
+                Some((_, matches)) if !matches.is_empty() => matches[0],
+                _ => bail!("expected an address, the last query has no matches"),
+            },
+        };
+        let note = args.next().map(|note| note.trim().to_string());
+        println!("{name} = {address:#x}");
+        self.names
+            .insert(&self.exe, address, name.to_string(), note)?;
+        self.names.save(&self.exe_path)
+    }
+
+    /// Parse an address, or look it up by name
+    fn address(&self, arg: &str) -> Result<usize> {
+        parse_maybe_hex(arg).or_else(|err| {
+            self.names
+                .address_of(&self.exe, arg)
+                .ok_or(err.context(format!("{arg:?} is neither an address nor a name")))
+        })
+    }
+
+    fn bookmarks(&self) {
+        for (address, name) in self.names.iter(&self.exe) {
+            print_name(address, name, self.names.note(&self.exe, address));
+        }
+    }
+
+    fn history(&self) {
+        for query in &self.session.queries {
+            println!("{}: {} matches", query.query, query.matches.len());
+        }
+    }
+
+    fn string(&mut self, arg: &str) -> Result<()> {
+        if arg.is_empty() {
+            bail!("expected a string");
+        }
+        for (encoding, pattern) in [
+            ("utf-16", util::utf16_pattern(arg)),
+            ("utf-8", util::utf8_pattern(arg)),
+        ] {
+            let strings = self.scan(PatternConfig::new((), "repl".into(), None, pattern))?;
+            println!("{encoding}: {} matches", strings.len());
+            for string in strings.iter().take(MAX_LISTED) {
+                let refs =
+                    self.scan(PatternConfig::xref((), "repl".into(), None, Xref(*string)))?;
+                println!(
+                    "  {string:#x}: {} xrefs {}",
+                    refs.len(),
+                    refs.iter()
+                        .take(MAX_LISTED)
+                        .map(|r| format!("{r:#x}"))
+                        .join(" ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&self, arg: &str) -> Result<()> {
+        let Some((query, matches)) = &self.last else {
+            bail!("no pattern or xref scanned yet");
+        };
+        let index = if arg.is_empty() { 0 } else { arg.parse()? };
+        let Some(address) = matches.get(index) else {
+            bail!("only {} matches", matches.len());
+        };
+        let pattern = match query {
+            Query::Pattern(pattern) => Some(pattern),
+            Query::Xref(_) => None,
+        };
+        println!(
+            "{}",
+            disassemble(&self.exe, *address, pattern, Some(&self.names))
+        );
+        Ok(())
+    }
+
+    fn export(&self, arg: &str) -> Result<()> {
+        let Some((query, matches)) = &self.last else {
+            bail!("no pattern or xref scanned yet");
+        };
+        let name = if arg.is_empty() { "unnamed" } else { arg };
+        println!("[[patterns]]");
+        println!("name = {name:?}");
+        match query {
+            Query::Pattern(pattern) => println!("pattern = {:?}", pattern.to_string()),
+            Query::Xref(address) => println!("xref = \"{address:#x}\""),
+        }
+        println!("expected_count = {}", matches.len());
+        Ok(())
+    }
+}
+
+fn print_matches(matches: &[usize]) {
+    println!(
+        "{} matches {}{}",
+        matches.len(),
+        matches
+            .iter()
+            .take(MAX_LISTED)
+            .map(|m| format!("{m:#x}"))
+            .join(" "),
+        if matches.len() > MAX_LISTED {
+            " ..."
+        } else {
+            ""
+        }
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use object::SectionKind;
+    use patternsleuth::testing::TestImageBuilder;
+
+    use super::*;
+
+    const BASE: usize = 0x140000000;
+    const FUNCTION: usize = BASE + 0x1000;
+    const GLOBAL: usize = BASE + 0x2000;
+
+    /// Repl over an image with a function reading a global: mov rax, [GLOBAL]; ret
+    fn test_repl() -> Repl<'static> {
+        let mut code = vec![0x48, 0x8b, 0x05];
+        code.extend(((GLOBAL - (FUNCTION + 7)) as i32).to_le_bytes());
+        code.push(0xc3);
+        let exe = TestImageBuilder::new(BASE)
+            .section(".text", SectionKind::Text, FUNCTION, 0x1000)
+            .section(".data", SectionKind::Data, GLOBAL, 0x1000)
+            .write(FUNCTION, &code)
+            .function(FUNCTION..FUNCTION + code.len())
+            .build()
+            .unwrap();
+        Repl {
+            exe,
+            exe_path: PathBuf::new(),
+            names: Names::default(),
+            last: None,
+            session: Session::default(),
+            session_path: None,
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut repl = test_repl();
+        assert!(repl.show("").is_err());
+        assert!(repl.export("").is_err());
+
+        repl.pattern("48 8b 05 ?? ?? ?? ?? c3").unwrap();
+        assert!(matches!(&repl.last, Some((Query::Pattern(_), m)) if *m == [FUNCTION]));
+        repl.show("0").unwrap();
+        assert!(repl.show("1").is_err());
+        repl.export("ReadGlobal").unwrap();
+
+        repl.xref(&format!("{GLOBAL:#x}")).unwrap();
+        assert!(matches!(&repl.last, Some((Query::Xref(GLOBAL), m)) if m.len() == 1));
+
+        assert!(repl.pattern("zz").is_err());
+        assert!(repl.string("").is_err());
+        repl.string("missing").unwrap();
+    }
+
+    #[test]
+    fn test_session() {
+        let mut repl = test_repl();
+        repl.pattern("48 8b 05 ?? ?? ?? ?? c3").unwrap();
+        repl.xref(&format!("{GLOBAL:#x}")).unwrap();
+        let queries = repl
+            .session
+            .queries
+            .iter()
+            .map(|q| q.query.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            queries,
+            ["pattern 48 8b 05 ?? ?? ?? ?? c3", "xref 0x140002000"]
+        );
+
+        let mut resumed = test_repl();
+        resumed.session = repl.session.clone();
+        resumed.resume();
+        assert!(matches!(&resumed.last, Some((Query::Xref(GLOBAL), m)) if *m == [FUNCTION + 3]));
+
+        // queries that can't be restored are skipped
+        resumed.session.queries.push(SessionQuery {
+            query: "string missing".into(),
+            matches: vec![],
+        });
+        resumed.resume();
+        assert!(matches!(&resumed.last, Some((Query::Xref(GLOBAL), m)) if *m == [FUNCTION + 3]));
+    }
+
+    #[test]
+    fn test_bookmark() {
+        let dir = std::env::temp_dir().join(format!("patternsleuth-repl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut repl = test_repl();
+        repl.exe_path = dir.join("Game.exe");
+
+        assert!(repl.bookmark("").is_err());
+        assert!(repl.bookmark("Main").is_err());
+        repl.pattern("48 8b 05 ?? ?? ?? ?? c3").unwrap();
+        repl.bookmark("Main").unwrap();
+        repl.bookmark(&format!("Global {GLOBAL:#x} read by Main"))
+            .unwrap();
+
+        assert_eq!(repl.address("Main").unwrap(), FUNCTION);
+        assert_eq!(repl.address("0x10").unwrap(), 0x10);
+        assert!(repl.address("nope").is_err());
+        assert_eq!(repl.names.note(&repl.exe, GLOBAL), Some("read by Main"));
+        assert_eq!(Names::open(&repl.exe_path).unwrap(), repl.names);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
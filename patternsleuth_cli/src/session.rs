@@ -0,0 +1,106 @@
+//! Session files keeping the queries of [`repl`](crate::repl) sessions between runs, so pattern
+//! hunting spanning several days can be resumed where it was left. Bookmarks are kept in the
+//! [`Names`](crate::names::Names) of the game rather than the session.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use itertools::Itertools;
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    /// Executable the session was started with, reopened when resuming without selecting a game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe_path: Option<PathBuf>,
+    /// Queries in the order they were run
+    #[serde(default)]
+    pub queries: Vec<SessionQuery>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SessionQuery {
+    /// The command as typed, e.g. `pattern 48 8b 05`
+    pub query: String,
+    pub matches: Vec<usize>,
+}
+
+impl Session {
+    /// Load the session at `path`, or start a new one if it doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("failed to parse session {}", path.display()))
+    }
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write session {}", path.display()))
+    }
+}
+
+#[derive(Parser)]
+pub struct CommandSession {
+    /// Session file written by `repl --session`
+    path: PathBuf,
+
+    /// Only print queries containing this string
+    #[arg(short, long)]
+    filter: Option<String>,
+}
+
+pub fn session(command: CommandSession) -> Result<()> {
+    let session = Session::open(&command.path)?;
+    if let Some(exe_path) = &session.exe_path {
+        println!("exe: {}", exe_path.display());
+    }
+    println!("queries:");
+    for query in &session.queries {
+        if command
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !query.query.contains(filter.as_str()))
+        {
+            continue;
+        }
+        println!(
+            "  {}: {} matches {}",
+            query.query,
+            query.matches.len(),
+            query
+                .matches
+                .iter()
+                .take(10)
+                .map(|m| format!("{m:#x}"))
+                .join(" ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let path = std::env::temp_dir().join(format!("session-{}.json", std::process::id()));
+        assert_eq!(Session::open(&path).unwrap(), Session::default());
+
+        let session = Session {
+            exe_path: Some("Game.exe".into()),
+            queries: vec![SessionQuery {
+                query: "pattern 48 8b 05".into(),
+                matches: vec![0x140001000, 0x140002000],
+            }],
+        };
+        session.save(&path).unwrap();
+        let loaded = Session::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), session);
+    }
+}
@@ -0,0 +1,522 @@
+//! Porting a game specific signature to a new build of the game: the function known in the old
+//! build is located in the new build by instruction similarity, then patterns matching it
+//! uniquely in both builds are generated from the instructions the two versions share.
+//!
+//! Pattern generation works for any number of versions of a function, `view-symbol --generate`
+//! uses it for every game of the index having the symbol.
+
+use std::{collections::HashMap, ops::Range, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use iced_x86::{Code, Decoder, DecoderOptions};
+use itertools::Itertools;
+use patternsleuth::{image::Image, scanner::Pattern, MemoryTrait};
+use rayon::prelude::*;
+
+use crate::parse_maybe_hex;
+
+/// Functions are compared by at most this many leading instructions
+const MAX_INSTRUCTIONS: usize = 2000;
+/// Number of new functions most similar by instruction histogram which are aligned in full
+const ALIGNED_CANDIDATES: usize = 16;
+const MIN_PATTERN_LEN: usize = 6;
+const MAX_PATTERN_LEN: usize = 64;
+
+#[derive(Parser)]
+pub struct CommandSigDiff {
+    /// Executable of the build the function is known in
+    old: PathBuf,
+
+    /// Executable of the build to find the function in
+    new: PathBuf,
+
+    /// Address of the function in the old build (hex with 0x prefix or decimal), or its symbol
+    /// name if a PDB is available for the old build
+    function: String,
+
+    /// Address of the function in the new build, skips the similarity search
+    #[arg(long, value_parser(parse_maybe_hex))]
+    new_function: Option<usize>,
+
+    /// Number of patterns to print
+    #[arg(short = 'n', long, default_value = "5")]
+    count: usize,
+}
+
+pub fn sig_diff(command: CommandSigDiff) -> Result<()> {
+    let old_data = std::fs::read(&command.old)
+        .with_context(|| format!("failed to read {}", command.old.display()))?;
+    let new_data = std::fs::read(&command.new)
+        .with_context(|| format!("failed to read {}", command.new.display()))?;
+
+    let old_address = parse_maybe_hex(&command.function);
+    let old = if old_address.is_ok() {
+        Image::builder().functions(true).build(&old_data)?
+    } else {
+        Image::builder()
+            .functions(true)
+            .symbols(&command.old)
+            .build(&old_data)?
+    };
+    let new = Image::builder().functions(true).build(&new_data)?;
+
+    let old_address = match old_address {
+        Ok(address) => address,
+        Err(_) => find_symbol(&old, &command.function)?,
+    };
+    let old_fn = function_at(&old, old_address)?;
+    let old_insts = decode(&old, old_fn.clone())?;
+    println!(
+        "old function {:#x}-{:#x} ({} instructions)",
+        old_fn.start,
+        old_fn.end,
+        old_insts.len()
+    );
+
+    let (new_fn, new_insts) = match command.new_function {
+        Some(address) => {
+            let range = function_at(&new, address)?;
+            let insts = decode(&new, range.clone())?;
+            (range, insts)
+        }
+        None => {
+            let mut candidates = find_similar(&old_insts, &new)?;
+            println!("most similar functions in new build:");
+            for (range, _, similarity) in candidates.iter().take(5) {
+                println!("  {:#x}-{:#x} {similarity:.3}", range.start, range.end);
+            }
+            if candidates.is_empty() {
+                bail!("no function of similar size found in new build");
+            }
+            let (range, insts, _) = candidates.remove(0);
+            (range, insts)
+        }
+    };
+    println!(
+        "new function {:#x}-{:#x} ({} instructions)",
+        new_fn.start,
+        new_fn.end,
+        new_insts.len()
+    );
+
+    let candidates = candidate_patterns(&[&old_insts, &new_insts]);
+    let patterns = candidates.iter().map(|c| &c.pattern).collect_vec();
+    let old_matches = scan_code(&old, &patterns);
+    let new_matches = scan_code(&new, &patterns);
+
+    // keep one unique pattern per run of shared instructions, the shortest of those starting
+    // closest to the start of the run
+    let mut found: Vec<&Candidate> = vec![];
+    for (i, candidate) in candidates.iter().enumerate() {
+        let unique = old_matches[i] == candidate.addresses[..1]
+            && new_matches[i] == candidate.addresses[1..];
+        if unique && !found.iter().any(|f| f.run == candidate.run) {
+            found.push(candidate);
+        }
+    }
+    found.sort_by_key(|c| (c.pattern.simple.sig.len(), c.addresses[0] - old_fn.start));
+
+    if found.is_empty() {
+        bail!("no pattern of the shared instructions is unique in both builds");
+    }
+    println!("patterns matching once in both builds:");
+    for candidate in found.iter().take(command.count) {
+        let old_offset = candidate.addresses[0] - old_fn.start;
+        let new_offset = candidate.addresses[1] - new_fn.start;
+        let location = if old_offset == new_offset {
+            format!("+{old_offset:#x}")
+        } else {
+            format!("+{old_offset:#x} in old, +{new_offset:#x} in new")
+        };
+        println!("  {}  ({location})", candidate.pattern);
+    }
+    Ok(())
+}
+
+fn find_symbol(image: &Image, name: &str) -> Result<usize> {
+    let Some(symbols) = &image.symbols else {
+        bail!("{name:?} is not an address and no symbols were found for the old build");
+    };
+    let mut matches = symbols
+        .iter()
+        .filter(|(_, s)| s.name == name || s.demangle() == name)
+        .map(|(address, _)| *address)
+        .collect_vec();
+    matches.sort();
+    match matches.as_slice() {
+        [address] => Ok(*address),
+        [] => bail!("no symbol named {name:?}"),
+        _ => bail!("{name:?} matches several symbols: {matches:#x?}"),
+    }
+}
+
+fn function_at(image: &Image, address: usize) -> Result<Range<usize>> {
+    Ok(image
+        .functions()
+        .function_at(address)?
+        .with_context(|| format!("{address:#x} is not in a function"))?
+        .span())
+}
+
+/// An instruction with the bytes that change when the image is relinked (RIP relative
+/// displacements and branch targets) masked out
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Instruction {
+    address: usize,
+    code: Code,
+    bytes: Vec<Option<u8>>,
+}
+
+fn decode(image: &Image, range: Range<usize>) -> Result<Vec<Instruction>> {
+    let data = image.memory.range(range.clone())?;
+    Ok(decode_bytes(data, range.start))
+}
+
+pub(crate) fn decode_bytes(data: &[u8], address: usize) -> Vec<Instruction> {
+    let mut decoder = Decoder::with_ip(64, data, address as u64, DecoderOptions::NONE);
+    let mut insts = vec![];
+    while decoder.can_decode() && insts.len() < MAX_INSTRUCTIONS {
+        let start = decoder.position();
+        let inst = decoder.decode();
+        if inst.is_invalid() {
+            break;
+        }
+        let offsets = decoder.get_constant_offsets(&inst);
+        let mut bytes = data[start..start + inst.len()]
+            .iter()
+            .map(|b| Some(*b))
+            .collect_vec();
+        let mut mask = |offset: usize, size: usize| {
+            for b in &mut bytes[offset..offset + size] {
+                *b = None;
+            }
+        };
+        if offsets.has_displacement() && inst.is_ip_rel_memory_operand() {
+            mask(offsets.displacement_offset(), offsets.displacement_size());
+        }
+        if offsets.has_immediate()
+            && (inst.is_call_near() || inst.is_jmp_near() || inst.is_jcc_near())
+        {
+            mask(offsets.immediate_offset(), offsets.immediate_size());
+        }
+        insts.push(Instruction {
+            address: inst.ip() as usize,
+            code: inst.code(),
+            bytes,
+        });
+    }
+    insts
+}
+
+fn histogram(insts: &[Instruction]) -> HashMap<Code, usize> {
+    let mut histogram = HashMap::new();
+    for inst in insts {
+        *histogram.entry(inst.code).or_default() += 1;
+    }
+    histogram
+}
+
+/// Weighted Jaccard similarity of two instruction histograms
+fn histogram_similarity(a: &HashMap<Code, usize>, b: &HashMap<Code, usize>) -> f64 {
+    let (mut min, mut max) = (0, 0);
+    for code in a.keys().chain(b.keys()).unique() {
+        let (a, b) = (
+            a.get(code).copied().unwrap_or_default(),
+            b.get(code).copied().unwrap_or_default(),
+        );
+        min += a.min(b);
+        max += a.max(b);
+    }
+    if max == 0 {
+        0.
+    } else {
+        min as f64 / max as f64
+    }
+}
+
+/// Indexes of instructions with equal opcodes in the longest common subsequence of `a` and `b`
+fn align(a: &[Instruction], b: &[Instruction]) -> Vec<(usize, usize)> {
+    let width = b.len() + 1;
+    let mut table = vec![0u16; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i * width + j] = if a[i].code == b[j].code {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = vec![];
+    while i < a.len() && j < b.len() {
+        if a[i].code == b[j].code {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// A function's range, decoded instructions and similarity score in [0, 1]
+type ScoredFunction = (Range<usize>, Vec<Instruction>, f64);
+
+/// Functions of `image` ordered by similarity to `old`. Functions of very different size are
+/// skipped, the rest are ranked by instruction histogram and the best few by alignment.
+fn find_similar(old: &[Instruction], image: &Image) -> Result<Vec<ScoredFunction>> {
+    let old_len = old.len();
+    let old_histogram = histogram(old);
+
+    let mut candidates = image
+        .get_root_functions()?
+        .into_par_iter()
+        .filter_map(|range| {
+            let insts = decode(image, range.clone()).ok()?;
+            (insts.len() * 2 >= old_len && insts.len() <= old_len * 2).then(|| {
+                let similarity = histogram_similarity(&old_histogram, &histogram(&insts));
+                (range, insts, similarity)
+            })
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.start.cmp(&b.0.start)));
+    candidates.truncate(ALIGNED_CANDIDATES);
+
+    let mut candidates = candidates
+        .into_par_iter()
+        .map(|(range, insts, _)| {
+            let aligned = align(old, &insts).len();
+            let similarity = 2. * aligned as f64 / (old_len + insts.len()) as f64;
+            (range, insts, similarity)
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.start.cmp(&b.0.start)));
+    Ok(candidates)
+}
+
+pub(crate) struct Candidate {
+    /// Index of the run of shared instructions the pattern was taken from
+    pub run: usize,
+    /// Address of the first instruction in each function
+    pub addresses: Vec<usize>,
+    pub pattern: Pattern,
+}
+
+/// Runs of instructions shared by all `functions`, as the index of the instruction in each
+/// function. The first function is aligned with each of the others, instructions aligned with
+/// ones of equal length in all of them are shared.
+fn shared_runs(functions: &[&[Instruction]]) -> Vec<Vec<Vec<usize>>> {
+    let Some((first, others)) = functions.split_first() else {
+        return vec![];
+    };
+    let alignments = others
+        .iter()
+        .map(|other| {
+            align(first, other)
+                .into_iter()
+                .filter(|(i, j)| first[*i].bytes.len() == other[*j].bytes.len())
+                .collect::<HashMap<_, _>>()
+        })
+        .collect_vec();
+    let shared = (0..first.len()).filter_map(|i| {
+        let mut indexes = vec![i];
+        for alignment in &alignments {
+            indexes.push(*alignment.get(&i)?);
+        }
+        Some(indexes)
+    });
+
+    let mut runs: Vec<Vec<Vec<usize>>> = vec![];
+    for indexes in shared {
+        match runs.last_mut() {
+            Some(run)
+                if run
+                    .last()
+                    .unwrap()
+                    .iter()
+                    .zip(&indexes)
+                    .all(|(prev, i)| prev + 1 == *i) =>
+            {
+                run.push(indexes)
+            }
+            _ => runs.push(vec![indexes]),
+        }
+    }
+    runs
+}
+
+/// Bytes of the instructions of a run, wildcards where any function differs
+fn merge_run(functions: &[&[Instruction]], run: &[Vec<usize>]) -> Vec<Vec<Option<u8>>> {
+    run.iter()
+        .map(|indexes| {
+            let mut merged = functions[0][indexes[0]].bytes.clone();
+            for (function, i) in functions.iter().zip(indexes).skip(1) {
+                for (a, b) in merged.iter_mut().zip(&function[*i].bytes) {
+                    if a != b {
+                        *a = None;
+                    }
+                }
+            }
+            merged
+        })
+        .collect()
+}
+
+fn format_bytes<'a>(bytes: impl IntoIterator<Item = &'a Option<u8>>) -> String {
+    bytes
+        .into_iter()
+        .map(|b| match b {
+            Some(b) => format!("{b:02X}"),
+            None => "??".to_string(),
+        })
+        .join(" ")
+}
+
+/// The longest run of instructions shared by all `functions` as a pattern (without trailing
+/// wildcards), along with the address it starts at in each function
+pub(crate) fn longest_shared(functions: &[&[Instruction]]) -> Option<(Vec<usize>, String)> {
+    shared_runs(functions)
+        .into_iter()
+        .map(|run| {
+            let mut bytes = merge_run(functions, &run).concat();
+            while bytes.last() == Some(&None) {
+                bytes.pop();
+            }
+            let addresses = functions
+                .iter()
+                .zip(&run[0])
+                .map(|(function, i)| function[*i].address)
+                .collect_vec();
+            (addresses, bytes)
+        })
+        .max_by_key(|(_, bytes)| bytes.len())
+        .map(|(addresses, bytes)| (addresses, format_bytes(&bytes)))
+}
+
+/// Patterns made from runs of instructions shared by all `functions`, starting at every
+/// instruction of the run and growing one instruction at a time. Bytes differing between the
+/// functions are wildcards.
+pub(crate) fn candidate_patterns(functions: &[&[Instruction]]) -> Vec<Candidate> {
+    let mut candidates = vec![];
+    for (run_index, run) in shared_runs(functions).iter().enumerate() {
+        let merged = merge_run(functions, run);
+        for start in 0..run.len() {
+            if merged[start].first().copied().flatten().is_none() {
+                continue;
+            }
+            let mut bytes = vec![];
+            for inst in &merged[start..] {
+                bytes.extend(inst);
+                if bytes.len() > MAX_PATTERN_LEN {
+                    break;
+                }
+                if bytes.len() < MIN_PATTERN_LEN || inst.last().copied().flatten().is_none() {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    run: run_index,
+                    addresses: functions
+                        .iter()
+                        .zip(&run[start])
+                        .map(|(function, i)| function[*i].address)
+                        .collect(),
+                    pattern: Pattern::new(format_bytes(&bytes)).unwrap(),
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// Addresses matched by each pattern in the executable sections of `image`
+pub(crate) fn scan_code(image: &Image, patterns: &[&Pattern]) -> Vec<Vec<usize>> {
+    let mut matches = patterns.iter().map(|_| vec![]).collect_vec();
+    for section in image.memory.sections() {
+        if !section.permissions().execute {
+            continue;
+        }
+        let results =
+            patternsleuth::scanner::scan_pattern(patterns, section.address(), section.data());
+        for (matches, results) in matches.iter_mut().zip(results) {
+            matches.extend(results);
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_candidate_patterns() {
+        // same function in two builds with a different global and a stack frame grown by 8
+        let old = [
+            0x48, 0x89, 0x5c, 0x24, 0x08, // mov [rsp+8], rbx
+            0x48, 0x83, 0xec, 0x20, // sub rsp, 0x20
+            0x48, 0x8b, 0x05, 0x10, 0x20, 0x30, 0x00, // mov rax, [rip+0x302010]
+            0x48, 0x85, 0xc0, // test rax, rax
+        ];
+        let new = [
+            0x48, 0x89, 0x5c, 0x24, 0x08, // mov [rsp+8], rbx
+            0x48, 0x83, 0xec, 0x28, // sub rsp, 0x28
+            0x48, 0x8b, 0x05, 0x40, 0x50, 0x60, 0x00, // mov rax, [rip+0x605040]
+            0x48, 0x85, 0xc0, // test rax, rax
+        ];
+        let old = decode_bytes(&old, 0x1000);
+        let new = decode_bytes(&new, 0x2000);
+        assert_eq!(old[2].bytes[3..], [None; 4]);
+
+        let candidates = candidate_patterns(&[&old, &new]);
+        // patterns never end in a wildcard so the first one spans the whole function
+        let first = candidates
+            .iter()
+            .find(|c| c.addresses[0] == 0x1000)
+            .unwrap();
+        assert_eq!(first.addresses[1], 0x2000);
+        assert_eq!(
+            first.pattern.to_string(),
+            "48 89 5C 24 08 48 83 EC ?? 48 8B 05 ?? ?? ?? ?? 48 85 C0"
+        );
+        assert!(candidates
+            .iter()
+            .any(|c| c.pattern.to_string() == "48 8B 05 ?? ?? ?? ?? 48 85 C0"));
+    }
+
+    #[test]
+    fn test_longest_shared() {
+        let a = [
+            0x48, 0x83, 0xec, 0x28, // sub rsp, 0x28
+            0x33, 0xc9, // xor ecx, ecx
+            0xe8, 0x10, 0x00, 0x00, 0x00, // call +0x10
+            0x48, 0x85, 0xc0, // test rax, rax
+        ];
+        // inlined an extra instruction before the call
+        let b = [
+            0x48, 0x83, 0xec, 0x28, // sub rsp, 0x28
+            0x90, // nop
+            0x33, 0xc9, // xor ecx, ecx
+            0xe8, 0x20, 0x00, 0x00, 0x00, // call +0x20
+            0x48, 0x85, 0xc0, // test rax, rax
+        ];
+        let c = [
+            0x48, 0x83, 0xec, 0x38, // sub rsp, 0x38
+            0x33, 0xc9, // xor ecx, ecx
+            0xe8, 0x30, 0x00, 0x00, 0x00, // call +0x30
+            0x48, 0x85, 0xc0, // test rax, rax
+        ];
+        let (a, b, c) = (
+            decode_bytes(&a, 0x1000),
+            decode_bytes(&b, 0x2000),
+            decode_bytes(&c, 0x3000),
+        );
+        let (addresses, pattern) = longest_shared(&[&a, &b, &c]).unwrap();
+        assert_eq!(addresses, [0x1004, 0x2005, 0x3004]);
+        assert_eq!(pattern, "33 C9 E8 ?? ?? ?? ?? 48 85 C0");
+    }
+}
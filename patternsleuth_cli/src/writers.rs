@@ -0,0 +1,88 @@
+//! Finding the code writing globals found by resolvers, e.g. which function initializes `GMalloc`,
+//! to hook the initialization itself or to tell when the engine replaces the object a global
+//! points to
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::Colorize;
+use patternsleuth::{
+    disassemble::{global_writes, WriteKind},
+    image::Image,
+};
+
+use crate::{parse_maybe_hex, parse_resolver_match, selected_resolvers, ResolverMatch};
+
+#[derive(Parser)]
+pub struct CommandGlobalWriters {
+    /// Path to a game executable or the ID of a running game process
+    target: String,
+
+    /// A resolver whose globals to look up (can be specified multiple times). Supports globs
+    #[arg(short, long, value_parser(parse_resolver_match))]
+    resolver: Vec<ResolverMatch>,
+
+    /// Global addresses to look up in addition to the resolved ones, hex with 0x prefix or
+    /// decimal
+    #[arg(value_parser(parse_maybe_hex))]
+    addresses: Vec<usize>,
+}
+
+pub fn global_writers(command: CommandGlobalWriters) -> Result<()> {
+    if command.resolver.is_empty() && command.addresses.is_empty() {
+        bail!("no --resolver or address given");
+    }
+
+    let bin_data;
+    let exe = if let Ok(pid) = command.target.parse::<i32>() {
+        patternsleuth::process::external::read_image_from_pid(pid)?
+    } else {
+        bin_data = fs::read(PathBuf::from(&command.target))?;
+        Image::builder().functions(true).build(&bin_data)?
+    };
+
+    let mut globals = command
+        .addresses
+        .iter()
+        .map(|address| (format!("{address:#x}"), *address))
+        .collect::<Vec<_>>();
+    let selected = selected_resolvers(&command.resolver);
+    let getters = selected.iter().map(|r| r.getter).collect::<Vec<_>>();
+    for (resolver, res) in selected.iter().zip(exe.resolve_many(&getters)) {
+        match res {
+            Ok(res) => globals.extend(
+                res.addresses()
+                    .prefixed(resolver.name)
+                    .into_iter()
+                    // functions are never written
+                    .filter(|(_, address)| !exe.is_code(*address)),
+            ),
+            Err(err) => eprintln!("skipping {}: {err}", resolver.name),
+        }
+    }
+
+    for (name, global) in globals {
+        println!("{} {global:#x}", name.bold());
+        let writes = global_writes(&exe, global)?;
+        if writes.is_empty() {
+            println!("  {}", "no writes found".yellow());
+        }
+        for write in writes {
+            let kind = match write.kind {
+                WriteKind::Store => format!("{:<6}", "store").green(),
+                WriteKind::Clear => format!("{:<6}", "clear").red(),
+                WriteKind::Update => "update".normal(),
+            };
+            println!(
+                "  {} {:>2} bytes at {:#x}  {}",
+                kind,
+                write.size,
+                write.ip,
+                exe.annotate(write.ip)
+            );
+        }
+    }
+
+    Ok(())
+}